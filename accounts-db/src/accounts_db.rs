@@ -358,7 +358,7 @@ pub const DEFAULT_MAX_ANCIENT_STORAGES: usize = 100_000;
 #[cfg(not(test))]
 const ABSURD_CONSECUTIVE_FAILED_ITERATIONS: usize = 100;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AccountShrinkThreshold {
     /// Measure the total space sparseness across all candidates
     /// And select the candidates by using the top sparse account storage entries to shrink.
@@ -913,6 +913,16 @@ pub struct AccountsDb {
     pub thread_pool_foreground: ThreadPool,
     /// Thread pool for background tasks, e.g. AccountsBackgroundService and flush/clean/shrink
     pub thread_pool_background: ThreadPool,
+    /// Thread pool for calculating the accounts hash
+    ///
+    /// Built on first use, sized for startup until `use_steady_state_hash_threads()` is called.
+    thread_pool_hash: Mutex<Option<Arc<ThreadPool>>>,
+    /// Number of threads for calculating the accounts hash while starting up
+    num_startup_hash_threads: usize,
+    /// Number of threads for calculating the accounts hash once started up
+    num_steady_state_hash_threads: usize,
+    /// true once `use_steady_state_hash_threads()` has been called
+    is_steady_state_hash: AtomicBool,
 
     pub stats: AccountsStats,
 
@@ -978,6 +988,9 @@ pub struct AccountsDb {
     /// debug feature to scan every append vec and verify refcounts are equal
     exhaustively_verify_refcounts: bool,
 
+    /// Counts the accounts added to the index by `generate_index`
+    num_accounts_indexed: Option<Arc<AtomicU64>>,
+
     /// storage format to use for new storages
     accounts_file_provider: AccountsFileProvider,
 
@@ -1104,6 +1117,15 @@ impl AccountsDb {
             .build()
             .expect("new rayon threadpool");
 
+        let num_startup_hash_threads = accounts_db_config
+            .num_startup_hash_threads
+            .map(Into::into)
+            .unwrap_or_else(default_num_foreground_threads);
+        let num_steady_state_hash_threads = accounts_db_config
+            .num_steady_state_hash_threads
+            .map(Into::into)
+            .unwrap_or_else(quarter_thread_count);
+
         let new = Self {
             accounts_index,
             paths,
@@ -1132,9 +1154,14 @@ impl AccountsDb {
             write_cache_limit_bytes: accounts_db_config.write_cache_limit_bytes,
             partitioned_epoch_rewards_config: accounts_db_config.partitioned_epoch_rewards_config,
             exhaustively_verify_refcounts: accounts_db_config.exhaustively_verify_refcounts,
+            num_accounts_indexed: accounts_db_config.num_accounts_indexed,
             scan_filter_for_shrinking: accounts_db_config.scan_filter_for_shrinking,
             thread_pool_foreground,
             thread_pool_background,
+            thread_pool_hash: Mutex::default(),
+            num_startup_hash_threads,
+            num_steady_state_hash_threads,
+            is_steady_state_hash: AtomicBool::new(false),
             active_stats: ActiveStats::default(),
             storage: AccountStorage::default(),
             accounts_cache: AccountsCache::default(),
@@ -1177,6 +1204,24 @@ impl AccountsDb {
         &self.bank_hash_details_dir
     }
 
+    pub fn shrink_ratio(&self) -> AccountShrinkThreshold {
+        self.shrink_ratio
+    }
+
+    /// Returns the format new account storage files are created in
+    pub fn accounts_file_provider(&self) -> AccountsFileProvider {
+        self.accounts_file_provider
+    }
+
+    /// Returns the low and high watermark sizes for the read cache, in bytes
+    pub fn read_cache_limit_bytes(&self) -> (usize, usize) {
+        self.read_only_accounts_cache.max_data_size()
+    }
+
+    pub fn write_cache_limit_bytes(&self) -> Option<u64> {
+        self.write_cache_limit_bytes
+    }
+
     /// Returns true if there is an accounts update notifier.
     pub fn has_accounts_update_notifier(&self) -> bool {
         self.accounts_update_notifier.is_some()
@@ -4931,6 +4976,49 @@ impl AccountsDb {
         );
     }
 
+    fn new_hash_thread_pool(num_threads: usize) -> ThreadPool {
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|i| format!("solAcctsHash{i:02}"))
+            .num_threads(num_threads)
+            .build()
+            .expect("new rayon threadpool")
+    }
+
+    /// Returns the thread pool for calculating the accounts hash, building it if needed
+    fn hash_thread_pool(&self) -> Arc<ThreadPool> {
+        let mut thread_pool_hash = self.thread_pool_hash.lock().unwrap();
+        Arc::clone(
+            thread_pool_hash.get_or_insert_with(|| {
+                Arc::new(Self::new_hash_thread_pool(self.num_hash_threads()))
+            }),
+        )
+    }
+
+    /// Switches calculating the accounts hash over to the steady state number of threads
+    ///
+    /// Called once the validator has started up, so that later calculations do not compete
+    /// with replay for all the cores. The startup thread pool is dropped; the steady state one
+    /// is built on first use.
+    pub fn use_steady_state_hash_threads(&self) {
+        self.is_steady_state_hash.store(true, Ordering::Relaxed);
+        self.thread_pool_hash.lock().unwrap().take();
+    }
+
+    /// Returns the number of threads currently used for calculating the accounts hash
+    pub fn num_hash_threads(&self) -> usize {
+        if self.is_steady_state_hash.load(Ordering::Relaxed) {
+            self.num_steady_state_hash_threads
+        } else {
+            self.num_startup_hash_threads
+        }
+    }
+
+    /// Returns true if the thread pool for calculating the accounts hash has been built
+    #[cfg(test)]
+    fn has_hash_thread_pool(&self) -> bool {
+        self.thread_pool_hash.lock().unwrap().is_some()
+    }
+
     /// Calculates the accounts lt hash
     ///
     /// Only intended to be called at startup (or by tests).
@@ -4947,43 +5035,50 @@ impl AccountsDb {
         // A different parallel implementation that iterated over the bins *sequentially* and then
         // hashed the accounts *within* a bin in parallel took about 600 seconds.  That impl uses
         // less memory, as only a single index bin is loaded into mem at a time.
-        let mut lt_hash = self
-            .accounts_index
-            .account_maps
-            .par_iter()
-            .fold(
-                LtHash::identity,
-                |mut accumulator_lt_hash, accounts_index_bin| {
-                    for pubkey in accounts_index_bin.keys() {
-                        let account_lt_hash = self
-                            .accounts_index
-                            .get_with_and_then(&pubkey, ancestors, false, |(slot, account_info)| {
-                                (!account_info.is_zero_lamport()).then(|| {
-                                    self.get_account_accessor(
-                                        slot,
-                                        &pubkey,
-                                        &account_info.storage_location(),
-                                    )
-                                    .get_loaded_account(|loaded_account| {
-                                        Self::lt_hash_account(&loaded_account, &pubkey)
-                                    })
-                                    // SAFETY: The index said this pubkey exists, so
-                                    // there must be an account to load.
-                                    .unwrap()
-                                })
-                            })
-                            .flatten();
-                        if let Some(account_lt_hash) = account_lt_hash {
-                            accumulator_lt_hash.mix_in(&account_lt_hash.0);
+        let thread_pool = self.hash_thread_pool();
+        let mut lt_hash = thread_pool.install(|| {
+            self.accounts_index
+                .account_maps
+                .par_iter()
+                .fold(
+                    LtHash::identity,
+                    |mut accumulator_lt_hash, accounts_index_bin| {
+                        for pubkey in accounts_index_bin.keys() {
+                            let account_lt_hash = self
+                                .accounts_index
+                                .get_with_and_then(
+                                    &pubkey,
+                                    ancestors,
+                                    false,
+                                    |(slot, account_info)| {
+                                        (!account_info.is_zero_lamport()).then(|| {
+                                            self.get_account_accessor(
+                                                slot,
+                                                &pubkey,
+                                                &account_info.storage_location(),
+                                            )
+                                            .get_loaded_account(|loaded_account| {
+                                                Self::lt_hash_account(&loaded_account, &pubkey)
+                                            })
+                                            // SAFETY: The index said this pubkey exists, so
+                                            // there must be an account to load.
+                                            .unwrap()
+                                        })
+                                    },
+                                )
+                                .flatten();
+                            if let Some(account_lt_hash) = account_lt_hash {
+                                accumulator_lt_hash.mix_in(&account_lt_hash.0);
+                            }
                         }
-                    }
-                    accumulator_lt_hash
-                },
-            )
-            .reduce(LtHash::identity, |mut accum, elem| {
-                accum.mix_in(&elem);
-                accum
-            });
+                        accumulator_lt_hash
+                    },
+                )
+                .reduce(LtHash::identity, |mut accum, elem| {
+                    accum.mix_in(&elem);
+                    accum
+                })
+        });
 
         let cache_lt_hash = {
             let mut cache_lt_hash = LtHash::identity();
@@ -6422,6 +6517,7 @@ impl AccountsDb {
                             let mut reader = append_vec::new_scan_accounts_reader();
                             for next_item in storages_orderer.iter() {
                                 let storage = next_item.storage;
+                                let num_accounts = thread_accum.num_accounts;
                                 self.generate_index_for_slot(
                                     &mut reader,
                                     &mut thread_accum,
@@ -6429,6 +6525,12 @@ impl AccountsDb {
                                     storage,
                                 );
                                 num_processed.fetch_add(1, Ordering::Relaxed);
+                                if let Some(num_accounts_indexed) = &self.num_accounts_indexed {
+                                    num_accounts_indexed.fetch_add(
+                                        thread_accum.num_accounts - num_accounts,
+                                        Ordering::Relaxed,
+                                    );
+                                }
                             }
                             thread_accum
                         })
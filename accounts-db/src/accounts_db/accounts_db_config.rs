@@ -9,7 +9,11 @@ use {
             DEFAULT_PARTITIONED_EPOCH_REWARDS_CONFIG, PartitionedEpochRewardsConfig,
         },
     },
-    std::{num::NonZeroUsize, path::PathBuf},
+    std::{
+        num::NonZeroUsize,
+        path::PathBuf,
+        sync::{Arc, atomic::AtomicU64},
+    },
 };
 
 #[derive(Debug, Default, Clone)]
@@ -41,6 +45,12 @@ pub struct AccountsDbConfig {
     pub num_background_threads: Option<NonZeroUsize>,
     /// Number of threads for foreground operations (`thread_pool_foreground`)
     pub num_foreground_threads: Option<NonZeroUsize>,
+    /// Number of threads for calculating the accounts hash while starting up
+    pub num_startup_hash_threads: Option<NonZeroUsize>,
+    /// Number of threads for calculating the accounts hash once started up
+    pub num_steady_state_hash_threads: Option<NonZeroUsize>,
+    /// Counts the accounts added to the index while generating it at startup
+    pub num_accounts_indexed: Option<Arc<AtomicU64>>,
 }
 
 pub const ACCOUNTS_DB_CONFIG_FOR_TESTING: AccountsDbConfig = AccountsDbConfig {
@@ -61,6 +71,9 @@ pub const ACCOUNTS_DB_CONFIG_FOR_TESTING: AccountsDbConfig = AccountsDbConfig {
     scan_filter_for_shrinking: ScanFilter::OnlyAbnormalTest,
     num_background_threads: None,
     num_foreground_threads: None,
+    num_startup_hash_threads: None,
+    num_steady_state_hash_threads: None,
+    num_accounts_indexed: None,
 };
 
 pub const ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS: AccountsDbConfig = AccountsDbConfig {
@@ -81,4 +94,7 @@ pub const ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS: AccountsDbConfig = AccountsDbConfig
     scan_filter_for_shrinking: ScanFilter::OnlyAbnormal,
     num_background_threads: None,
     num_foreground_threads: None,
+    num_startup_hash_threads: None,
+    num_steady_state_hash_threads: None,
+    num_accounts_indexed: None,
 };
@@ -32,6 +32,7 @@ mod tests {
             accounts::Accounts,
             accounts_update_notifier_interface::{
                 AccountForGeyser, AccountsUpdateNotifier, AccountsUpdateNotifierInterface,
+                SnapshotStreamSession,
             },
             ancestors::Ancestors,
             utils::create_account_shared_data,
@@ -277,4 +278,75 @@ mod tests {
         assert_eq!(notif_slot, slot_close);
         assert_eq!(notif_account, account_close);
     }
+
+    #[test]
+    fn test_snapshot_stream_session() {
+        let accounts_db = Arc::new(AccountsDb::new_single_for_tests());
+        let mut pubkeys: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(10)
+            .collect();
+        pubkeys.sort_unstable();
+        let account = AccountSharedData::new(1, 0, &Pubkey::default());
+        for pubkey in &pubkeys {
+            accounts_db.store_for_tests((0, [(pubkey, &account)].as_slice()));
+        }
+        accounts_db.add_root_and_flush_write_cache(0);
+        let session = SnapshotStreamSession::new(&accounts_db, 0);
+        assert_eq!(accounts_db.scan_tracker.min_ongoing_scan_root(), Some(0));
+
+        // Updated and closed since the snapshot, then cleaned
+        let updated_account = AccountSharedData::new(2, 0, &Pubkey::default());
+        let closed_account = AccountSharedData::new(0, 0, &Pubkey::default());
+        accounts_db.store_for_tests((
+            1,
+            [
+                (&pubkeys[2], &updated_account),
+                (&pubkeys[7], &closed_account),
+            ]
+            .as_slice(),
+        ));
+        accounts_db.add_root_and_flush_write_cache(1);
+        accounts_db.clean_accounts_for_tests();
+
+        let partitions = session.partition(None).unwrap();
+        assert_eq!(partitions.num_pubkeys(), 10);
+        assert_eq!(partitions.num_after_cursor(), 10);
+
+        // Stop after the first 4 accounts, then resume after the last one
+        let mut streamed = vec![];
+        assert!(!session.stream(partitions, |pubkey, account| {
+            streamed.push((*pubkey, account));
+            streamed.len() < 4
+        }));
+        let cursor = streamed.last().map(|(pubkey, _)| *pubkey);
+        assert_eq!(cursor, Some(pubkeys[3]));
+        let partitions = session.partition(cursor).unwrap();
+        assert_eq!(partitions.num_pubkeys(), 10);
+        assert_eq!(partitions.num_after_cursor(), 6);
+        assert!(session.stream(partitions, |pubkey, account| {
+            streamed.push((*pubkey, account));
+            true
+        }));
+
+        let streamed_pubkeys: Vec<_> = streamed.iter().map(|(pubkey, _)| *pubkey).collect();
+        assert_eq!(streamed_pubkeys, pubkeys);
+        // Accounts are streamed as of the snapshot slot
+        for (_, account_as_of_snapshot) in &streamed {
+            assert_eq!(*account_as_of_snapshot, Some((account.clone(), 0)));
+        }
+
+        // The snapshot slot is unpinned once every clone of the session is dropped
+        let clone = session.clone();
+        drop(session);
+        assert_eq!(accounts_db.scan_tracker.min_ongoing_scan_root(), Some(0));
+        drop(clone);
+        assert_eq!(accounts_db.scan_tracker.min_ongoing_scan_root(), None);
+
+        // Nothing is streamed once the accounts db is dropped
+        let session = SnapshotStreamSession::new(&accounts_db, 1);
+        let partitions = session.partition(None).unwrap();
+        drop(accounts_db);
+        assert!(!session.stream(partitions, |_, _| panic!("accounts db was dropped")));
+        assert!(session.partition(None).is_none());
+    }
 }
@@ -17,6 +17,7 @@ use {
     solana_pubkey::PUBKEY_BYTES,
     std::{
         iter::{self, FromIterator},
+        num::NonZeroUsize,
         ops::Range,
         str::FromStr,
         sync::{
@@ -5577,6 +5578,36 @@ fn test_sweep_get_oldest_non_ancient_slot_max() {
     }
 }
 
+#[test]
+fn test_hash_threads() {
+    let db = AccountsDb::new_with_config(
+        Vec::new(),
+        AccountsDbConfig {
+            num_startup_hash_threads: Some(NonZeroUsize::new(3).unwrap()),
+            num_steady_state_hash_threads: Some(NonZeroUsize::new(2).unwrap()),
+            ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+        },
+        None,
+        Arc::default(),
+    );
+    // the thread pools are only built once the accounts hash is calculated
+    assert_eq!(db.num_hash_threads(), 3);
+    assert!(!db.has_hash_thread_pool());
+    db.calculate_accounts_lt_hash_at_startup_from_index(&Ancestors::default());
+    assert_eq!(db.hash_thread_pool().current_num_threads(), 3);
+
+    // switching drops the startup thread pool
+    db.use_steady_state_hash_threads();
+    assert_eq!(db.num_hash_threads(), 2);
+    assert!(!db.has_hash_thread_pool());
+    // calculating the accounts hash still works after switching thread pools
+    assert_eq!(
+        db.calculate_accounts_lt_hash_at_startup_from_index(&Ancestors::default()),
+        AccountsLtHash(LtHash::identity()),
+    );
+    assert_eq!(db.hash_thread_pool().current_num_threads(), 2);
+}
+
 #[test]
 fn test_sweep_get_oldest_non_ancient_slot() {
     let epoch_schedule = EpochSchedule::default();
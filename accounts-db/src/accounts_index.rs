@@ -2,7 +2,7 @@ mod account_map_entry;
 mod accounts_index_storage;
 mod bucket_map_holder;
 pub(crate) mod in_mem_accounts_index;
-mod iter;
+pub(crate) mod iter;
 mod roots_tracker;
 mod secondary;
 mod stats;
@@ -21,7 +21,9 @@ use {
     in_mem_accounts_index::{
         ExistedLocation, InMemAccountsIndex, InsertNewEntryResults, StartupStats,
     },
-    iter::AccountsIndexPubkeyIterator,
+    iter::{
+        AccountsIndexOrderedPubkeyChunks, AccountsIndexPubkeyIterator, MAX_ORDERED_PUBKEY_CHUNK_LEN,
+    },
     log::*,
     rand::{Rng, rng},
     rayon::iter::{IntoParallelIterator, ParallelIterator},
@@ -299,6 +301,15 @@ impl<T: IndexValue, U: DiskIndexValue + From<T> + Into<T>> AccountsIndex<T, U> {
         AccountsIndexPubkeyIterator::new(self)
     }
 
+    /// Returns the pubkeys greater than `start_after` in ascending order, in
+    /// chunks to be read with `next_chunk()`
+    pub(crate) fn ordered_chunks(
+        &self,
+        start_after: Option<Pubkey>,
+    ) -> AccountsIndexOrderedPubkeyChunks {
+        AccountsIndexOrderedPubkeyChunks::new(self, start_after, MAX_ORDERED_PUBKEY_CHUNK_LEN)
+    }
+
     /// is the accounts index using disk as a backing store
     pub fn is_disk_index_enabled(&self) -> bool {
         self.storage.storage.is_disk_index_enabled()
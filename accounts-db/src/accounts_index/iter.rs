@@ -1,7 +1,7 @@
 use {
     super::{AccountsIndex, DiskIndexValue, IndexValue, in_mem_accounts_index::InMemAccountsIndex},
     solana_pubkey::Pubkey,
-    std::sync::Arc,
+    std::{ops::RangeInclusive, sync::Arc},
 };
 
 /// Iterator over AccountsIndex
@@ -40,6 +40,108 @@ impl<T: IndexValue, U: DiskIndexValue + From<T> + Into<T>> Iterator
     }
 }
 
+/// The maximum number of pubkeys in a chunk of `AccountsIndexOrderedPubkeyChunks`,
+/// unless more pubkeys than this share their first byte
+pub(crate) const MAX_ORDERED_PUBKEY_CHUNK_LEN: usize = 1 << 20;
+
+/// Chunks of the pubkeys of an AccountsIndex, in ascending pubkey order
+///
+/// Bins are not ordered by pubkey, so the pubkeys of the index are first
+/// counted by their first byte, and consecutive first bytes are grouped into
+/// chunks of at most `max_chunk_len` pubkeys. Each call to `next_chunk()` then
+/// reads the keys of every bin, and returns those of the next chunk, sorted.
+/// Thus only one chunk of pubkeys is held at a time, at the cost of reading the
+/// keys of the index once per chunk.
+///
+/// Pubkeys added to the index after the chunks were planned are returned if
+/// they belong to a chunk not returned yet, and chunks may be empty if pubkeys
+/// were removed from the index in the meantime.
+#[derive(Debug)]
+pub struct AccountsIndexOrderedPubkeyChunks {
+    start_after: Option<Pubkey>,
+    num_pubkeys: usize,
+    num_remaining: usize,
+    chunks: std::vec::IntoIter<RangeInclusive<u8>>,
+}
+
+impl AccountsIndexOrderedPubkeyChunks {
+    /// Plans the chunks of the pubkeys greater than `start_after`, or of all of
+    /// them if `None`
+    pub fn new<T: IndexValue, U: DiskIndexValue + From<T> + Into<T>>(
+        index: &AccountsIndex<T, U>,
+        start_after: Option<Pubkey>,
+        max_chunk_len: usize,
+    ) -> Self {
+        let mut num_pubkeys = 0;
+        let mut num_by_first_byte = [0; u8::MAX as usize + 1];
+        for pubkey in index.account_maps.iter().flat_map(|bin| bin.keys()) {
+            num_pubkeys += 1;
+            if start_after.is_none_or(|start_after| pubkey > start_after) {
+                num_by_first_byte[usize::from(pubkey.as_array()[0])] += 1;
+            }
+        }
+
+        let mut chunks = Vec::new();
+        let mut chunk: Option<(RangeInclusive<u8>, usize)> = None;
+        for (first_byte, num) in (0..=u8::MAX).zip(num_by_first_byte) {
+            if num == 0 {
+                continue;
+            }
+            match &mut chunk {
+                Some((range, len)) if *len + num <= max_chunk_len => {
+                    *range = *range.start()..=first_byte;
+                    *len += num;
+                }
+                _ => chunks.extend(
+                    chunk
+                        .replace((first_byte..=first_byte, num))
+                        .map(|(range, _)| range),
+                ),
+            }
+        }
+        chunks.extend(chunk.map(|(range, _)| range));
+
+        Self {
+            start_after,
+            num_pubkeys,
+            num_remaining: num_by_first_byte.iter().sum(),
+            chunks: chunks.into_iter(),
+        }
+    }
+
+    /// Returns the number of pubkeys in the index when the chunks were planned
+    pub fn num_pubkeys(&self) -> usize {
+        self.num_pubkeys
+    }
+
+    /// Returns the number of pubkeys not yet returned by `next_chunk()`
+    pub fn num_remaining(&self) -> usize {
+        self.num_remaining
+    }
+
+    /// Returns the pubkeys of the next chunk in `index`, sorted, or `None` once
+    /// every chunk was returned
+    pub fn next_chunk<T: IndexValue, U: DiskIndexValue + From<T> + Into<T>>(
+        &mut self,
+        index: &AccountsIndex<T, U>,
+    ) -> Option<Vec<Pubkey>> {
+        let range = self.chunks.next()?;
+        let start_after = self.start_after;
+        let mut pubkeys: Vec<_> = index
+            .account_maps
+            .iter()
+            .flat_map(|bin| bin.keys())
+            .filter(|pubkey| {
+                range.contains(&pubkey.as_array()[0])
+                    && start_after.is_none_or(|start_after| *pubkey > start_after)
+            })
+            .collect();
+        pubkeys.sort_unstable();
+        self.num_remaining = self.num_remaining.saturating_sub(pubkeys.len());
+        Some(pubkeys)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -165,4 +267,57 @@ mod tests {
         assert_eq!(num_empty_bins, 0);
         assert_eq!(pubkeys, actual_pubkeys);
     }
+
+    /// Ensure the ordered chunks hold the pubkeys after `start_after`, in order,
+    /// and no more pubkeys than the chunk length unless they share a first byte.
+    #[test]
+    fn test_ordered_chunks() {
+        let index = AccountsIndex::<bool, bool>::default_for_tests();
+        let mut pubkeys: Vec<_> = iter::repeat_with(solana_pubkey::new_rand)
+            .take(index.bins() * 3)
+            .collect();
+        // include the extremes of the first byte, and more pubkeys sharing a
+        // first byte than fit in a chunk
+        pubkeys.push(Pubkey::from([0; 32]));
+        pubkeys.push(Pubkey::from([u8::MAX; 32]));
+        pubkeys.extend((0..20).map(|i| {
+            let mut bytes = [0x42; 32];
+            bytes[31] = i;
+            Pubkey::from(bytes)
+        }));
+        for pubkey in &pubkeys {
+            let mut gc = ReclaimsSlotList::new();
+            index.upsert(0, 0, pubkey, true, &mut gc, UpsertReclaim::PopulateReclaims);
+        }
+        pubkeys.sort_unstable();
+
+        let max_chunk_len = 10;
+        let collect_chunks = |start_after| {
+            let mut ordered_chunks =
+                AccountsIndexOrderedPubkeyChunks::new(&index, start_after, max_chunk_len);
+            assert_eq!(ordered_chunks.num_pubkeys(), pubkeys.len());
+            let num_after = ordered_chunks.num_remaining();
+            let mut ordered_pubkeys = Vec::new();
+            while let Some(mut chunk) = ordered_chunks.next_chunk(&index) {
+                assert!(
+                    chunk.len() <= max_chunk_len
+                        || chunk.iter().all(|pubkey| pubkey.as_array()[0] == 0x42)
+                );
+                ordered_pubkeys.append(&mut chunk);
+                assert_eq!(
+                    ordered_chunks.num_remaining(),
+                    num_after - ordered_pubkeys.len()
+                );
+            }
+            assert_eq!(ordered_chunks.num_remaining(), 0);
+            ordered_pubkeys
+        };
+        assert_eq!(collect_chunks(None), pubkeys);
+        for start_after in [0, 1, pubkeys.len() / 2, pubkeys.len() - 1] {
+            assert_eq!(
+                collect_chunks(Some(pubkeys[start_after])),
+                pubkeys[start_after + 1..]
+            );
+        }
+    }
 }
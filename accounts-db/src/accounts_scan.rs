@@ -84,6 +84,26 @@ impl ScanTracker {
     pub fn min_ongoing_scan_root(&self) -> Option<Slot> {
         Self::min_ongoing_scan_root_from_btree(&self.ongoing_scan_roots.read().unwrap())
     }
+
+    /// Pins `root` in `ongoing_scan_roots`, so that clean does not advance
+    /// past it until it is unpinned with `unpin_root()`.
+    pub(crate) fn pin_root(&self, root: Slot) {
+        *self
+            .ongoing_scan_roots
+            .write()
+            .unwrap()
+            .entry(root)
+            .or_default() += 1;
+    }
+
+    pub(crate) fn unpin_root(&self, root: Slot) {
+        let mut ongoing_scan_roots = self.ongoing_scan_roots.write().unwrap();
+        let count = ongoing_scan_roots.get_mut(&root).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            ongoing_scan_roots.remove(&root);
+        }
+    }
 }
 
 /// Guard that protects account state during an accounts scan.
@@ -250,12 +270,7 @@ impl Drop for ScanGuard<'_> {
         self.scan_tracker
             .active_scans
             .fetch_sub(1, Ordering::Relaxed);
-        let mut ongoing_scan_roots = self.scan_tracker.ongoing_scan_roots.write().unwrap();
-        let count = ongoing_scan_roots.get_mut(&self.max_root).unwrap();
-        *count -= 1;
-        if *count == 0 {
-            ongoing_scan_roots.remove(&self.max_root);
-        }
+        self.scan_tracker.unpin_root(self.max_root);
     }
 }
 
@@ -1,9 +1,14 @@
 use {
+    crate::{
+        accounts_db::{AccountsDb, LoadHint, PopulateReadCache},
+        accounts_index::iter::AccountsIndexOrderedPubkeyChunks,
+        ancestors::Ancestors,
+    },
     solana_account::{AccountSharedData, ReadableAccount},
     solana_clock::{Epoch, Slot},
     solana_pubkey::Pubkey,
     solana_transaction::sanitized::SanitizedTransaction,
-    std::sync::Arc,
+    std::sync::{Arc, Weak},
 };
 
 pub trait AccountsUpdateNotifierInterface: std::fmt::Debug {
@@ -31,10 +36,127 @@ pub trait AccountsUpdateNotifierInterface: std::fmt::Debug {
 
     /// Notified when all accounts have been notified when restoring from a snapshot.
     fn notify_end_of_restore_from_snapshot(&self);
+
+    /// Notified once the validator has started from a snapshot, with the
+    /// session to stream its accounts from in pubkey order.
+    fn start_snapshot_stream(&self, _session: SnapshotStreamSession) {}
 }
 
 pub type AccountsUpdateNotifier = Arc<dyn AccountsUpdateNotifierInterface + Sync + Send>;
 
+/// The accounts of the snapshot the validator started from, streamed in pubkey
+/// order so that a consumer can persist the last pubkey it processed as a
+/// cursor, and resume the stream after it.
+///
+/// Accounts are read as of the snapshot slot. To keep those versions of the
+/// accounts, clean does not advance past the snapshot slot for as long as the
+/// session, or a clone of it, is alive.
+#[derive(Debug, Clone)]
+pub struct SnapshotStreamSession {
+    accounts_db: Weak<AccountsDb>,
+    slot: Slot,
+    _root_pin: Arc<SnapshotRootPin>,
+}
+
+impl SnapshotStreamSession {
+    /// `slot` must be the latest root of `accounts_db`, so that clean did not
+    /// advance past it yet
+    pub fn new(accounts_db: &Arc<AccountsDb>, slot: Slot) -> Self {
+        accounts_db.scan_tracker.pin_root(slot);
+        Self {
+            accounts_db: Arc::downgrade(accounts_db),
+            slot,
+            _root_pin: Arc::new(SnapshotRootPin {
+                accounts_db: Arc::downgrade(accounts_db),
+                slot,
+            }),
+        }
+    }
+
+    /// The slot of the snapshot
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// Partitions the pubkeys after `cursor` into chunks for `stream()`.
+    ///
+    /// Returns `None` once the accounts db is dropped.
+    pub fn partition(&self, cursor: Option<Pubkey>) -> Option<SnapshotStreamPartitions> {
+        let accounts_db = self.accounts_db.upgrade()?;
+        Some(SnapshotStreamPartitions(
+            accounts_db.accounts_index.ordered_chunks(cursor),
+        ))
+    }
+
+    /// Calls `f` with every pubkey of `partitions` in ascending order, along
+    /// with the version of its account as of the snapshot slot and the slot of
+    /// that version, or `None` if the account has zero lamports.
+    ///
+    /// Stops once `f` returns false, or once the accounts db is dropped.
+    /// Returns true if every pubkey of `partitions` was visited.
+    pub fn stream(
+        &self,
+        mut partitions: SnapshotStreamPartitions,
+        mut f: impl FnMut(&Pubkey, Option<(AccountSharedData, Slot)>) -> bool,
+    ) -> bool {
+        // Loads the latest version rooted at or before the snapshot slot
+        let ancestors = Ancestors::from(vec![self.slot]);
+        loop {
+            // The accounts db is only held while streaming a chunk of pubkeys,
+            // so that the stream does not keep it alive past validator shutdown
+            let Some(accounts_db) = self.accounts_db.upgrade() else {
+                return false;
+            };
+            let Some(pubkeys) = partitions.0.next_chunk(&accounts_db.accounts_index) else {
+                return true;
+            };
+            for pubkey in pubkeys {
+                let account = accounts_db.load(
+                    &ancestors,
+                    &pubkey,
+                    LoadHint::Unspecified,
+                    PopulateReadCache::False,
+                );
+                if !f(&pubkey, account) {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Unpins the snapshot slot of a `SnapshotStreamSession` once dropped
+#[derive(Debug)]
+struct SnapshotRootPin {
+    accounts_db: Weak<AccountsDb>,
+    slot: Slot,
+}
+
+impl Drop for SnapshotRootPin {
+    fn drop(&mut self) {
+        if let Some(accounts_db) = self.accounts_db.upgrade() {
+            accounts_db.scan_tracker.unpin_root(self.slot);
+        }
+    }
+}
+
+/// The pubkeys of a `SnapshotStreamSession` after a cursor, partitioned into
+/// chunks of consecutive pubkeys so that only one chunk is held at a time.
+#[derive(Debug)]
+pub struct SnapshotStreamPartitions(AccountsIndexOrderedPubkeyChunks);
+
+impl SnapshotStreamPartitions {
+    /// Returns the number of pubkeys in the accounts index when partitioned
+    pub fn num_pubkeys(&self) -> usize {
+        self.0.num_pubkeys()
+    }
+
+    /// Returns the number of pubkeys after the cursor
+    pub fn num_after_cursor(&self) -> usize {
+        self.0.num_remaining()
+    }
+}
+
 /// Account type with only the fields necessary for Geyser
 #[derive(Debug, Clone)]
 pub struct AccountForGeyser<'a> {
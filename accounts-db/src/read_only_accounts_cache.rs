@@ -84,8 +84,8 @@ struct EvictorControl {
 #[derive(Debug)]
 pub(crate) struct ReadOnlyAccountsCache {
     cache: Arc<DashMap<ReadOnlyCacheKey, ReadOnlyAccountCacheEntry, AHashRandomState>>,
-    _max_data_size_lo: usize,
-    _max_data_size_hi: usize,
+    max_data_size_lo: usize,
+    max_data_size_hi: usize,
     data_size: Arc<AtomicUsize>,
     cache_len: Arc<AtomicUsize>,
 
@@ -144,8 +144,8 @@ impl ReadOnlyAccountsCache {
 
         Self {
             highest_slot_stored: AtomicU64::default(),
-            _max_data_size_lo: max_data_size_lo,
-            _max_data_size_hi: max_data_size_hi,
+            max_data_size_lo,
+            max_data_size_hi,
             cache,
             data_size,
             cache_len,
@@ -265,6 +265,11 @@ impl ReadOnlyAccountsCache {
         self.data_size.load(Ordering::Relaxed)
     }
 
+    /// Returns the low and high watermark sizes, in bytes
+    pub(crate) fn max_data_size(&self) -> (usize, usize) {
+        (self.max_data_size_lo, self.max_data_size_hi)
+    }
+
     pub(crate) fn get_and_reset_stats(&self) -> ReadOnlyCacheStats {
         let hits = self.stats.hits.swap(0, Ordering::Relaxed);
         let misses = self.stats.misses.swap(0, Ordering::Relaxed);
@@ -428,8 +433,7 @@ impl ReadOnlyAccountsCache {
         R: Rng,
         C: FnMut(&Pubkey, Option<ReadOnlyAccountCacheEntry>),
     {
-        #[allow(clippy::used_underscore_binding)]
-        let target_data_size = self._max_data_size_lo;
+        let target_data_size = self.max_data_size_lo;
         Self::evict(
             target_data_size,
             &self.data_size,
@@ -528,9 +528,9 @@ mod tests {
         assert_eq!(ctx.pool_receiver.try_iter().count(), 2);
         assert_eq!(ctx.verifier.stats.vote_stats.pool_sent.0, 1);
         assert_eq!(ctx.verifier.stats.cert_stats.pool_sent.0, 1);
-        let received_verified_votes1 = ctx.repair_receiver.try_recv().unwrap();
+        let (pubkey, slots, _observed_at) = ctx.repair_receiver.try_recv().unwrap();
         assert_eq!(
-            received_verified_votes1,
+            (pubkey, slots),
             (
                 ctx.validator_keypairs[vote_rank1].vote_keypair.pubkey(),
                 vec![5]
@@ -555,9 +555,9 @@ mod tests {
         assert_eq!(ctx.pool_receiver.try_iter().count(), 1);
         assert_eq!(ctx.verifier.stats.vote_stats.pool_sent.0, 1);
         assert_eq!(ctx.verifier.stats.cert_stats.pool_sent.0, 0);
-        let received_verified_votes2 = ctx.repair_receiver.try_recv().unwrap();
+        let (pubkey, slots, _observed_at) = ctx.repair_receiver.try_recv().unwrap();
         assert_eq!(
-            received_verified_votes2,
+            (pubkey, slots),
             (
                 ctx.validator_keypairs[vote_rank2].vote_keypair.pubkey(),
                 vec![6]
@@ -581,9 +581,9 @@ mod tests {
         assert_eq!(ctx.pool_receiver.try_iter().count(), 1);
         assert_eq!(ctx.verifier.stats.vote_stats.pool_sent.0, 1);
         assert_eq!(ctx.verifier.stats.cert_stats.pool_sent.0, 0);
-        let received_verified_votes3 = ctx.repair_receiver.try_recv().unwrap();
+        let (pubkey, slots, _observed_at) = ctx.repair_receiver.try_recv().unwrap();
         assert_eq!(
-            received_verified_votes3,
+            (pubkey, slots),
             (
                 ctx.validator_keypairs[vote_rank3].vote_keypair.pubkey(),
                 vec![7]
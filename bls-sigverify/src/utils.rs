@@ -95,8 +95,9 @@ pub(super) fn send_votes_to_repair(
     channel: &VerifiedVoterSlotsSender,
     stats: &mut SigVerifyVoteStats,
 ) -> Result<(), SigVerifyVoteError> {
+    let observed_at = Instant::now();
     for (pubkey, slots) in votes {
-        match channel.try_send((pubkey, slots)) {
+        match channel.try_send((pubkey, slots, observed_at)) {
             Ok(()) => {
                 stats.repair_sent += 1;
             }
@@ -0,0 +1,68 @@
+use {
+    criterion::{Criterion, Throughput, criterion_group, criterion_main},
+    solana_builtins_default_costs::{
+        BuiltinMigrationFeatureIndex, filter_builtin_keys, get_builtin_migration_feature_index,
+    },
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::{compute_budget, system_program, vote},
+    std::hint::black_box,
+};
+
+const NUM_KEYS_PER_MESSAGE: usize = 32;
+const NUM_MESSAGES_PER_ITER: usize = 1024;
+
+/// Account keys of a message with a few builtins among mostly non-builtin keys
+fn message_keys() -> Vec<Pubkey> {
+    let mut keys: Vec<_> = (0..NUM_KEYS_PER_MESSAGE)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    keys[1] = system_program::id();
+    keys[2] = compute_budget::id();
+    keys[3] = vote::id();
+    keys
+}
+
+fn count_builtins<'a>(
+    classified: impl Iterator<Item = (&'a Pubkey, BuiltinMigrationFeatureIndex)>,
+) -> usize {
+    classified
+        .filter(|(_, feature_index)| {
+            !matches!(feature_index, BuiltinMigrationFeatureIndex::NotBuiltin)
+        })
+        .count()
+}
+
+fn bench_filter_builtin_keys(c: &mut Criterion) {
+    let keys = message_keys();
+    let mut group = c.benchmark_group("bench_filter_builtin_keys");
+    group.throughput(Throughput::Elements(
+        (NUM_KEYS_PER_MESSAGE * NUM_MESSAGES_PER_ITER) as u64,
+    ));
+    group.bench_function("filter_builtin_keys", |bencher| {
+        bencher.iter(|| {
+            (0..NUM_MESSAGES_PER_ITER).for_each(|_| {
+                assert_eq!(
+                    count_builtins(filter_builtin_keys(black_box(keys.iter()))),
+                    3
+                )
+            })
+        });
+    });
+    group.bench_function("get_builtin_migration_feature_index", |bencher| {
+        bencher.iter(|| {
+            (0..NUM_MESSAGES_PER_ITER).for_each(|_| {
+                assert_eq!(
+                    count_builtins(
+                        black_box(keys.iter())
+                            .map(|key| (key, get_builtin_migration_feature_index(key)))
+                    ),
+                    3
+                )
+            })
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_filter_builtin_keys);
+criterion_main!(benches);
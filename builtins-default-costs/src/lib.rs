@@ -4,7 +4,7 @@
 #[cfg(feature = "dev-context-only-utils")]
 use qualifier_attr::field_qualifiers;
 use {
-    agave_feature_set::bls_pubkey_management_in_vote_account,
+    agave_feature_set::{FeatureSet, bls_pubkey_management_in_vote_account},
     ahash::AHashMap,
     solana_pubkey::Pubkey,
     solana_sdk_ids::{
@@ -119,6 +119,11 @@ const NON_MIGRATING_BUILTINS_COSTS: &[(Pubkey, BuiltinCost)] = &[
     (ed25519_program::id(), BuiltinCost::NotMigrating),
 ];
 
+/// Number of compute units allotted to each instruction of a builtin that has
+/// not migrated to core BPF. Must equal `MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT`
+/// of `solana-program-runtime`.
+pub const BUILTIN_INSTRUCTION_COST: u64 = 3_000;
+
 /// A table of 256 booleans indicates whether the first `u8` of a Pubkey exists in
 /// BUILTIN_INSTRUCTION_COSTS. If the value is true, the Pubkey might be a builtin key;
 /// if false, it cannot be a builtin key. This table allows for quick filtering of
@@ -149,6 +154,32 @@ pub fn get_builtin_migration_feature_index(program_id: &Pubkey) -> BuiltinMigrat
     )
 }
 
+/// Returns the number of compute units the cost model allots to an instruction
+/// of the builtin `program_id` under `feature_set`, or `None` if `program_id`
+/// is not a builtin or its core BPF migration feature is active.
+pub fn get_builtin_instruction_cost(program_id: &Pubkey, feature_set: &FeatureSet) -> Option<u64> {
+    let builtin_cost = BUILTIN_INSTRUCTION_COSTS.get(program_id)?;
+    match builtin_cost.core_bpf_migration_feature() {
+        Some(feature_id) if feature_set.is_active(feature_id) => None,
+        _ => Some(BUILTIN_INSTRUCTION_COST),
+    }
+}
+
+/// Classifies each of `keys`, in order, the way `get_builtin_migration_feature_index`
+/// does, skipping the map lookup for keys ruled out by `MAYBE_BUILTIN_KEY`.
+pub fn filter_builtin_keys<'a>(
+    keys: impl Iterator<Item = &'a Pubkey>,
+) -> impl Iterator<Item = (&'a Pubkey, BuiltinMigrationFeatureIndex)> {
+    keys.map(|key| {
+        let feature_index = if MAYBE_BUILTIN_KEY[key.as_ref()[0] as usize] {
+            get_builtin_migration_feature_index(key)
+        } else {
+            BuiltinMigrationFeatureIndex::NotBuiltin
+        };
+        (key, feature_index)
+    })
+}
+
 /// const function validates `position` correctness at compile time.
 const fn validate_position(migrating_builtins: &[(Pubkey, BuiltinCost)]) {
     let mut index = 0;
@@ -178,6 +209,57 @@ pub fn get_migration_feature_id(index: usize) -> &'static Pubkey {
         .expect("migrating builtin")
 }
 
+/// Returns every builtin program ID along with its core BPF migration feature
+/// ID, if the builtin is migrating. Migrating builtins come first, in
+/// MIGRATING_BUILTINS_COSTS order.
+pub fn builtins_with_migration_features() -> Vec<(&'static Pubkey, Option<&'static Pubkey>)> {
+    MIGRATING_BUILTINS_COSTS
+        .iter()
+        .chain(NON_MIGRATING_BUILTINS_COSTS.iter())
+        .map(|(program_id, builtin_cost)| (program_id, builtin_cost.core_bpf_migration_feature()))
+        .collect()
+}
+
+/// Returns the builtins in MIGRATING_BUILTINS_COSTS, in order, each with its
+/// core BPF migration feature ID.
+pub fn migrating_builtins() -> impl Iterator<Item = (&'static Pubkey, &'static Pubkey)> {
+    MIGRATING_BUILTINS_COSTS
+        .iter()
+        .filter_map(|(program_id, builtin_cost)| {
+            builtin_cost
+                .core_bpf_migration_feature()
+                .map(|feature_id| (program_id, feature_id))
+        })
+}
+
+/// Summary of how far the builtins in MIGRATING_BUILTINS_COSTS have progressed
+/// through their core BPF migration under a given feature set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// Number of builtins with a migration feature.
+    pub total_migrating: usize,
+    /// Number of those builtins whose migration feature is active.
+    pub completed: usize,
+    /// Number of those builtins whose migration feature is not yet active.
+    pub pending: usize,
+}
+
+/// Counts how many migrating builtins have activated their migration feature
+/// in `feature_set`.
+pub fn migration_progress(feature_set: &FeatureSet) -> MigrationProgress {
+    let total_migrating = MIGRATING_BUILTINS_COSTS.len();
+    let completed = MIGRATING_BUILTINS_COSTS
+        .iter()
+        .filter_map(|(_, builtin_cost)| builtin_cost.core_bpf_migration_feature())
+        .filter(|feature_id| feature_set.is_active(feature_id))
+        .count();
+    MigrationProgress {
+        total_migrating,
+        completed,
+        pending: total_migrating - completed,
+    }
+}
+
 #[cfg(feature = "dev-context-only-utils")]
 pub fn get_migration_feature_position(feature_id: &Pubkey) -> usize {
     MIGRATING_BUILTINS_COSTS
@@ -186,6 +268,66 @@ pub fn get_migration_feature_position(feature_id: &Pubkey) -> usize {
         .unwrap()
 }
 
+/// Instruction costs to use in place of the ones of the static table, so that
+/// simulations can model hypothetical cost changes without touching it.
+///
+/// Only available to tests and tooling, never to release builds.
+#[cfg(feature = "dev-context-only-utils")]
+#[derive(Clone, Debug, Default)]
+pub struct BuiltinCostOverrides {
+    costs: AHashMap<Pubkey, u64>,
+}
+
+#[cfg(feature = "dev-context-only-utils")]
+impl BuiltinCostOverrides {
+    pub fn new(costs: AHashMap<Pubkey, u64>) -> Self {
+        Self { costs }
+    }
+
+    /// Overrides the cost of an instruction of `program_id`, returning the
+    /// previous override if any
+    pub fn insert(&mut self, program_id: Pubkey, cost: u64) -> Option<u64> {
+        self.costs.insert(program_id, cost)
+    }
+
+    pub fn get(&self, program_id: &Pubkey) -> Option<u64> {
+        self.costs.get(program_id).copied()
+    }
+}
+
+/// Same as `get_builtin_instruction_cost`, except that a cost in `overrides`
+/// takes precedence over the static table, whatever the status of the
+/// migration feature of `program_id`.
+#[cfg(feature = "dev-context-only-utils")]
+pub fn get_builtin_instruction_cost_with_overrides(
+    program_id: &Pubkey,
+    feature_set: &FeatureSet,
+    overrides: &BuiltinCostOverrides,
+) -> Option<u64> {
+    overrides
+        .get(program_id)
+        .or_else(|| get_builtin_instruction_cost(program_id, feature_set))
+}
+
+// Checks that the overrides are absent from every build without
+// dev-context-only-utils, such as release builds and the isolated builds of
+// scripts/check-dev-context-only-utils.sh. Both glob imports provide the names
+// only if this crate defines them too, in which case naming them below is
+// ambiguous and fails to compile.
+#[cfg(not(feature = "dev-context-only-utils"))]
+const _: () = {
+    #[allow(dead_code)]
+    mod absent {
+        pub struct BuiltinCostOverrides;
+        pub fn get_builtin_instruction_cost_with_overrides() {}
+    }
+    #[allow(unused_imports)]
+    use {crate::*, absent::*};
+
+    type _Overrides = BuiltinCostOverrides;
+    const _: fn() = get_builtin_instruction_cost_with_overrides;
+};
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -236,6 +378,175 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_filter_builtin_keys() {
+        let not_builtin = Pubkey::new_unique();
+        let keys = [vote::id(), not_builtin, compute_budget::id(), not_builtin];
+        let classified: Vec<_> = filter_builtin_keys(keys.iter()).collect();
+        assert!(classified.iter().map(|(key, _)| *key).eq(keys.iter()));
+        assert!(matches!(
+            classified[0].1,
+            BuiltinMigrationFeatureIndex::BuiltinWithMigrationFeature(0)
+        ));
+        assert!(matches!(
+            classified[1].1,
+            BuiltinMigrationFeatureIndex::NotBuiltin
+        ));
+        assert!(matches!(
+            classified[2].1,
+            BuiltinMigrationFeatureIndex::BuiltinNoMigrationFeature
+        ));
+        assert!(matches!(
+            classified[3].1,
+            BuiltinMigrationFeatureIndex::NotBuiltin
+        ));
+    }
+
+    #[test]
+    fn test_get_builtin_instruction_cost() {
+        let mut feature_set = FeatureSet::default();
+        assert_eq!(
+            get_builtin_instruction_cost(&Pubkey::new_unique(), &feature_set),
+            None
+        );
+        assert_eq!(
+            get_builtin_instruction_cost(&vote::id(), &feature_set),
+            Some(BUILTIN_INSTRUCTION_COST)
+        );
+
+        // The vote program is evicted from the builtins once its migration
+        // feature is active
+        feature_set.activate(&bls_pubkey_management_in_vote_account::id(), 0);
+        assert_eq!(
+            get_builtin_instruction_cost(&vote::id(), &feature_set),
+            None
+        );
+
+        // Non-migrating builtins keep their cost under any feature set
+        for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+            for (program_id, _) in NON_MIGRATING_BUILTINS_COSTS {
+                assert_eq!(
+                    get_builtin_instruction_cost(program_id, &feature_set),
+                    Some(BUILTIN_INSTRUCTION_COST),
+                    "{program_id}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_builtin_instruction_cost_with_overrides() {
+        let mut feature_set = FeatureSet::default();
+        let not_builtin = Pubkey::new_unique();
+        let mut overrides = BuiltinCostOverrides::default();
+
+        // Without overrides the static table is used
+        assert_eq!(
+            get_builtin_instruction_cost_with_overrides(&vote::id(), &feature_set, &overrides),
+            Some(BUILTIN_INSTRUCTION_COST)
+        );
+        assert_eq!(
+            get_builtin_instruction_cost_with_overrides(&not_builtin, &feature_set, &overrides),
+            None
+        );
+
+        assert_eq!(overrides.insert(vote::id(), 42), None);
+        assert_eq!(overrides.insert(not_builtin, 7), None);
+        assert_eq!(overrides.insert(vote::id(), 2_100), Some(42));
+        assert_eq!(
+            get_builtin_instruction_cost_with_overrides(&vote::id(), &feature_set, &overrides),
+            Some(2_100)
+        );
+        assert_eq!(
+            get_builtin_instruction_cost_with_overrides(&not_builtin, &feature_set, &overrides),
+            Some(7)
+        );
+        assert_eq!(
+            get_builtin_instruction_cost_with_overrides(
+                &system_program::id(),
+                &feature_set,
+                &overrides
+            ),
+            Some(BUILTIN_INSTRUCTION_COST)
+        );
+
+        // Overrides still apply once the builtin is migrated
+        feature_set.activate(&bls_pubkey_management_in_vote_account::id(), 0);
+        assert_eq!(
+            get_builtin_instruction_cost_with_overrides(&vote::id(), &feature_set, &overrides),
+            Some(2_100)
+        );
+
+        // The static table is left untouched
+        assert_eq!(
+            get_builtin_instruction_cost(&vote::id(), &FeatureSet::default()),
+            Some(BUILTIN_INSTRUCTION_COST)
+        );
+    }
+
+    #[test]
+    fn test_migrating_builtins() {
+        let migrating: Vec<_> = migrating_builtins().collect();
+        assert_eq!(migrating.len(), MIGRATING_BUILTINS_COSTS.len());
+        assert!(migrating.contains(&(&vote::id(), &bls_pubkey_management_in_vote_account::id())));
+        for (position, (program_id, _)) in migrating.into_iter().enumerate() {
+            assert!(matches!(
+                get_builtin_migration_feature_index(program_id),
+                BuiltinMigrationFeatureIndex::BuiltinWithMigrationFeature(index) if index == position
+            ));
+        }
+    }
+
+    #[test]
+    fn test_builtins_with_migration_features() {
+        let builtins = builtins_with_migration_features();
+        assert_eq!(builtins.len(), TOTAL_COUNT_BUILTINS);
+        assert_eq!(
+            BUILTIN_INSTRUCTION_COSTS.len(),
+            TOTAL_COUNT_BUILTINS,
+            "builtin program IDs must be unique"
+        );
+        for (program_id, migration_feature) in builtins {
+            if *program_id == vote::id() {
+                assert_eq!(
+                    migration_feature,
+                    Some(&bls_pubkey_management_in_vote_account::id())
+                );
+            } else {
+                assert_eq!(migration_feature, None, "{program_id}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_migration_progress() {
+        // The vote program is the only migrating builtin; its migration is
+        // gated on `bls_pubkey_management_in_vote_account`.
+        let mut feature_set = FeatureSet::default();
+        assert_eq!(
+            migration_progress(&feature_set),
+            MigrationProgress {
+                total_migrating: 1,
+                completed: 0,
+                pending: 1,
+            }
+        );
+
+        feature_set.activate(&bls_pubkey_management_in_vote_account::id(), 0);
+        assert_eq!(
+            migration_progress(&feature_set),
+            MigrationProgress {
+                total_migrating: 1,
+                completed: 1,
+                pending: 0,
+            }
+        );
+
+        feature_set.deactivate(&bls_pubkey_management_in_vote_account::id());
+        assert_eq!(migration_progress(&feature_set).completed, 0);
+        assert_eq!(migration_progress(&FeatureSet::all_enabled()).pending, 0);
+    }
+
     #[test]
     #[should_panic(expected = "valid index of MIGRATING_BUILTINS_COSTS")]
     fn test_get_migration_feature_id_invalid_index() {
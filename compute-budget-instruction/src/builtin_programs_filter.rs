@@ -43,7 +43,7 @@ impl BuiltinProgramsFilter {
     }
 
     #[inline]
-    fn check_program_kind(program_id: &Pubkey) -> ProgramKind {
+    pub(crate) fn check_program_kind(program_id: &Pubkey) -> ProgramKind {
         if !MAYBE_BUILTIN_KEY[program_id.as_ref()[0] as usize] {
             return ProgramKind::NotBuiltin;
         }
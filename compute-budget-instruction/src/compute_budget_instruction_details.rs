@@ -72,32 +72,51 @@ impl ComputeBudgetInstructionDetails {
             let mut filter = BuiltinProgramsFilter::new();
             // reiterate to collect builtin details
             for (program_id, instruction) in instructions {
-                match filter.get_program_kind(instruction.program_id_index as usize, program_id) {
-                    ProgramKind::Builtin => {
-                        compute_budget_instruction_details
-                            .num_non_migratable_builtin_instructions += 1;
-                    }
-                    ProgramKind::NotBuiltin => {
-                        compute_budget_instruction_details.num_non_builtin_instructions += 1;
-                    }
-                    ProgramKind::MigratingBuiltin {
-                        core_bpf_migration_feature_index,
-                    } => {
-                        *compute_budget_instruction_details
-                            .migrating_builtin_feature_counters
-                            .migrating_builtin
-                            .get_mut(core_bpf_migration_feature_index)
-                            .expect(
-                                "migrating feature index within range of MIGRATION_FEATURE_IDS",
-                            ) += 1;
-                    }
-                }
+                compute_budget_instruction_details.count_program_kind(
+                    filter.get_program_kind(instruction.program_id_index as usize, program_id),
+                );
             }
         }
 
         Ok(compute_budget_instruction_details)
     }
 
+    /// Details of a message that sets no compute unit limit and whose
+    /// instructions invoke `program_ids`, in order, with `None` counted as a
+    /// program that is not a builtin
+    pub(crate) fn from_program_ids<'a>(
+        program_ids: impl Iterator<Item = Option<&'a Pubkey>>,
+    ) -> Self {
+        let mut compute_budget_instruction_details = ComputeBudgetInstructionDetails::default();
+        for program_id in program_ids {
+            compute_budget_instruction_details.count_program_kind(program_id.map_or(
+                ProgramKind::NotBuiltin,
+                BuiltinProgramsFilter::check_program_kind,
+            ));
+        }
+        compute_budget_instruction_details
+    }
+
+    fn count_program_kind(&mut self, program_kind: ProgramKind) {
+        match program_kind {
+            ProgramKind::Builtin => {
+                self.num_non_migratable_builtin_instructions += 1;
+            }
+            ProgramKind::NotBuiltin => {
+                self.num_non_builtin_instructions += 1;
+            }
+            ProgramKind::MigratingBuiltin {
+                core_bpf_migration_feature_index,
+            } => {
+                *self
+                    .migrating_builtin_feature_counters
+                    .migrating_builtin
+                    .get_mut(core_bpf_migration_feature_index)
+                    .expect("migrating feature index within range of MIGRATION_FEATURE_IDS") += 1;
+            }
+        }
+    }
+
     pub fn sanitize_and_convert_to_compute_budget_limits(
         &self,
         feature_set: &FeatureSet,
@@ -193,7 +212,7 @@ impl ComputeBudgetInstructionDetails {
         (MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes) && bytes.is_multiple_of(1024)
     }
 
-    fn calculate_default_compute_unit_limit(&self, feature_set: &FeatureSet) -> u32 {
+    pub(crate) fn calculate_default_compute_unit_limit(&self, feature_set: &FeatureSet) -> u32 {
         // evaluate if any builtin has migrated with feature_set
         let (num_migrated, num_not_migrated) = self
             .migrating_builtin_feature_counters
@@ -224,7 +243,8 @@ mod test {
     use {
         super::*,
         solana_builtins_default_costs::{
-            BuiltinCost, MigratingBuiltinCost, get_migration_feature_position,
+            BUILTIN_INSTRUCTION_COST, BuiltinCost, MigratingBuiltinCost,
+            get_migration_feature_position,
         },
         solana_instruction::Instruction,
         solana_keypair::Keypair,
@@ -243,6 +263,14 @@ mod test {
         )))
     }
 
+    #[test]
+    fn test_builtin_instruction_cost() {
+        assert_eq!(
+            BUILTIN_INSTRUCTION_COST,
+            u64::from(MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT)
+        );
+    }
+
     #[test]
     fn test_try_from_request_heap() {
         let tx = build_sanitized_transaction(&[
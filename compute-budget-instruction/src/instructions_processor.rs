@@ -18,16 +18,46 @@ pub fn process_compute_budget_instructions<'a>(
         .sanitize_and_convert_to_compute_budget_limits(feature_set)
 }
 
+/// Returns the compute unit limit the runtime assigns to a message that does
+/// not request one, where `program_ids` holds the program invoked by each of
+/// the message's `instruction_count` instructions.
+///
+/// Builtin programs are allotted `MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT`,
+/// unless `feature_set` has activated their migration to core BPF, and every
+/// other program `DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT`, up to a total of
+/// `MAX_COMPUTE_UNIT_LIMIT`.
+///
+/// The instructions beyond the end of `program_ids` are counted as invoking a
+/// program that is not a builtin, and the programs beyond `instruction_count`
+/// are ignored.
+///
+/// DEVELOPER WARNING: the result is consensus critical, it must stay in line
+/// with `process_compute_budget_instructions()` for transactions without a
+/// `SetComputeUnitLimit` instruction.
+pub fn default_compute_budget_for_message(
+    program_ids: &[Pubkey],
+    feature_set: &FeatureSet,
+    instruction_count: usize,
+) -> u64 {
+    let program_ids = (0..instruction_count).map(|index| program_ids.get(index));
+    let compute_unit_limit = ComputeBudgetInstructionDetails::from_program_ids(program_ids)
+        .calculate_default_compute_unit_limit(feature_set)
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+    u64::from(compute_unit_limit)
+}
+
 #[cfg(test)]
 mod tests {
     use {
         super::*,
+        agave_feature_set::bls_pubkey_management_in_vote_account,
         solana_compute_budget_interface::ComputeBudgetInstruction,
         solana_hash::Hash,
         solana_instruction::{Instruction, error::InstructionError},
         solana_keypair::Keypair,
         solana_message::Message,
         solana_pubkey::Pubkey,
+        solana_sdk_ids::{compute_budget, system_program, vote},
         solana_signer::Signer,
         solana_svm_transaction::svm_message::SVMStaticMessage,
         solana_system_interface::instruction::transfer,
@@ -447,4 +477,93 @@ mod tests {
             assert_eq!(result, expected_result);
         }
     }
+
+    #[test]
+    fn test_default_compute_budget_for_message() {
+        let mut vote_migrated = FeatureSet::default();
+        vote_migrated.activate(&bls_pubkey_management_in_vote_account::id(), 0);
+        let feature_sets = [
+            FeatureSet::default(),
+            vote_migrated,
+            FeatureSet::all_enabled(),
+        ];
+        let default_compute_budget = |program_ids: &[Pubkey], feature_set| {
+            default_compute_budget_for_message(program_ids, feature_set, program_ids.len())
+        };
+        let builtin_limit = u64::from(MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT);
+        let default_limit = u64::from(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT);
+
+        for feature_set in &feature_sets {
+            assert_eq!(default_compute_budget(&[], feature_set), 0);
+            assert_eq!(
+                default_compute_budget(&[Pubkey::new_unique(), system_program::id()], feature_set),
+                default_limit + builtin_limit
+            );
+            assert_eq!(
+                default_compute_budget(&[Pubkey::new_unique(); 10], feature_set),
+                u64::from(MAX_COMPUTE_UNIT_LIMIT)
+            );
+        }
+
+        // Instructions without their program are not builtins, and programs
+        // without their instruction are ignored
+        let program_ids = [system_program::id(), Pubkey::new_unique()];
+        for (instruction_count, expected_limit) in [
+            (0, 0),
+            (1, builtin_limit),
+            (3, builtin_limit + 2 * default_limit),
+        ] {
+            assert_eq!(
+                default_compute_budget_for_message(
+                    &program_ids,
+                    &feature_sets[0],
+                    instruction_count
+                ),
+                expected_limit
+            );
+        }
+
+        // The vote program is only allotted the builtin limit until its
+        // migration feature is active
+        let program_ids = [vote::id(), compute_budget::id()];
+        assert_eq!(
+            default_compute_budget(&program_ids, &feature_sets[0]),
+            2 * builtin_limit
+        );
+        for feature_set in &feature_sets[1..] {
+            assert_eq!(
+                default_compute_budget(&program_ids, feature_set),
+                default_limit + builtin_limit
+            );
+        }
+
+        // Matches the limit the runtime derives for a transaction
+        let payer_keypair = Keypair::new();
+        let transaction =
+            SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[
+                    ComputeBudgetInstruction::request_heap_frame(40 * 1024),
+                    Instruction::new_with_bincode(Pubkey::new_unique(), &0_u8, vec![]),
+                    transfer(&payer_keypair.pubkey(), &Pubkey::new_unique(), 2),
+                    Instruction::new_with_bincode(vote::id(), &0_u8, vec![]),
+                ],
+                Some(&payer_keypair.pubkey()),
+                &[&payer_keypair],
+                Hash::default(),
+            ));
+        let program_ids: Vec<_> = SVMStaticMessage::program_instructions_iter(&transaction)
+            .map(|(program_id, _)| *program_id)
+            .collect();
+        for feature_set in &feature_sets {
+            let compute_budget_limits = process_compute_budget_instructions(
+                SVMStaticMessage::program_instructions_iter(&transaction),
+                feature_set,
+            )
+            .unwrap();
+            assert_eq!(
+                default_compute_budget(&program_ids, feature_set),
+                u64::from(compute_budget_limits.compute_unit_limit)
+            );
+        }
+    }
 }
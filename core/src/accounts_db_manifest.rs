@@ -0,0 +1,264 @@
+//! A manifest of the accounts-db options that the accounts state on disk
+//! depends on.
+//!
+//! The manifest is written under the ledger path whenever a bank snapshot is
+//! completed and when the validator shuts down, and compared against the
+//! current options at startup, before the accounts are loaded. Some changes
+//! otherwise only fail deep inside loading, e.g. moving the account paths while
+//! starting up from the local state left by the previous run. A missing or
+//! unreadable manifest is not checked.
+
+use {
+    crate::validator::ValidatorError,
+    agave_snapshots::SnapshotVersion,
+    serde::{Deserialize, Serialize},
+    solana_accounts_db::accounts_file::AccountsFileProvider,
+    solana_ledger::use_snapshot_archives_at_startup::{self, UseSnapshotArchivesAtStartup},
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Name of the file, under the ledger path, holding the manifest
+pub const ACCOUNTS_DB_MANIFEST_FILE: &str = "accounts_db_manifest.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountsDbManifest {
+    /// Version of the validator that wrote the manifest
+    pub version: String,
+    pub account_paths: Vec<PathBuf>,
+    /// Format of the account storage files
+    pub accounts_file_format: String,
+    /// Version of the bank snapshots the local state is loaded from
+    pub snapshot_version: String,
+}
+
+/// How to bring the accounts state on disk in line with the current options
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountsDbMigration {
+    /// Rebuild the accounts from the snapshot archives instead of loading the
+    /// local state
+    LoadFromSnapshotArchives,
+}
+
+impl AccountsDbManifest {
+    pub fn new(
+        account_paths: &[PathBuf],
+        accounts_file_provider: AccountsFileProvider,
+        snapshot_version: SnapshotVersion,
+    ) -> Self {
+        Self {
+            version: solana_version::version!().to_string(),
+            account_paths: account_paths.to_vec(),
+            accounts_file_format: format!("{accounts_file_provider:?}"),
+            snapshot_version: snapshot_version.to_string(),
+        }
+    }
+
+    /// Returns the manifest under `ledger_path`, or None if there is none
+    pub fn load(ledger_path: &Path) -> Option<Self> {
+        let path = ledger_path.join(ACCOUNTS_DB_MANIFEST_FILE);
+        let manifest = match fs::read(&path) {
+            Ok(manifest) => manifest,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                warn!("Failed to read {}: {err}", path.display());
+                return None;
+            }
+        };
+        serde_json::from_slice(&manifest)
+            .inspect_err(|err| warn!("Ignoring malformed {}: {err}", path.display()))
+            .ok()
+    }
+
+    pub fn write(&self, ledger_path: &Path) -> io::Result<()> {
+        let path = ledger_path.join(ACCOUNTS_DB_MANIFEST_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(tmp_path, path)
+    }
+
+    /// Returns the first option the local state depends on that differs
+    /// between `self` and `current`, with its previous and current values
+    fn changed_option(&self, current: &Self) -> Option<(&'static str, String, String)> {
+        if self.account_paths != current.account_paths {
+            return Some((
+                "account_paths",
+                format!("{:?}", self.account_paths),
+                format!("{:?}", current.account_paths),
+            ));
+        }
+        [
+            (
+                "accounts_file_format",
+                &self.accounts_file_format,
+                &current.accounts_file_format,
+            ),
+            (
+                "snapshot_version",
+                &self.snapshot_version,
+                &current.snapshot_version,
+            ),
+        ]
+        .into_iter()
+        .find(|(_option, previous, current)| previous != current)
+        .map(|(option, previous, current)| (option, previous.clone(), current.clone()))
+    }
+
+    /// Checks that the accounts state described by `self` can be loaded with
+    /// the `current` options.
+    ///
+    /// If it cannot, the accounts are rebuilt from the snapshot archives
+    /// instead, which do not depend on these options. Returns that migration,
+    /// unless snapshot archives are loaded at startup anyway, or an error
+    /// describing the option that changed if snapshot archives are never
+    /// loaded at startup and `allow_migration` is not set.
+    pub fn check(
+        &self,
+        current: &Self,
+        use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup,
+        allow_migration: bool,
+    ) -> Result<Option<AccountsDbMigration>, ValidatorError> {
+        let Some((option, previous, current)) = self.changed_option(current) else {
+            return Ok(None);
+        };
+        match use_snapshot_archives_at_startup {
+            UseSnapshotArchivesAtStartup::Always => Ok(None),
+            UseSnapshotArchivesAtStartup::WhenNewest => {
+                info!("accounts-db option {option} changed from {previous} to {current}");
+                Ok(Some(AccountsDbMigration::LoadFromSnapshotArchives))
+            }
+            UseSnapshotArchivesAtStartup::Never if allow_migration => {
+                info!("accounts-db option {option} changed from {previous} to {current}");
+                Ok(Some(AccountsDbMigration::LoadFromSnapshotArchives))
+            }
+            UseSnapshotArchivesAtStartup::Never => Err(ValidatorError::AccountsDbOptionChanged {
+                option,
+                previous,
+                current,
+                remedy: format!(
+                    "the local accounts state of the previous run cannot be loaded with the new \
+                     option. Restore the previous {option}, or rebuild the accounts from the \
+                     snapshot archives with --{} {} or --accounts-db-allow-migration",
+                    use_snapshot_archives_at_startup::cli::LONG_ARG,
+                    UseSnapshotArchivesAtStartup::WhenNewest,
+                ),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tempfile::TempDir};
+
+    fn manifest(account_paths: &[&str]) -> AccountsDbManifest {
+        let account_paths: Vec<_> = account_paths.iter().map(PathBuf::from).collect();
+        AccountsDbManifest::new(
+            &account_paths,
+            AccountsFileProvider::default(),
+            SnapshotVersion::default(),
+        )
+    }
+
+    #[test]
+    fn test_accounts_db_manifest_round_trip() {
+        let ledger_path = TempDir::new().unwrap();
+        assert_eq!(AccountsDbManifest::load(ledger_path.path()), None);
+
+        let manifest = manifest(&["/mnt/accounts"]);
+        manifest.write(ledger_path.path()).unwrap();
+        assert_eq!(AccountsDbManifest::load(ledger_path.path()), Some(manifest));
+
+        // Malformed manifests behave as missing ones
+        fs::write(ledger_path.path().join(ACCOUNTS_DB_MANIFEST_FILE), b"{").unwrap();
+        assert_eq!(AccountsDbManifest::load(ledger_path.path()), None);
+    }
+
+    #[test]
+    fn test_accounts_db_manifest_account_paths_changed() {
+        let previous = manifest(&["/mnt/accounts"]);
+        let current = manifest(&["/mnt/nvme/accounts"]);
+        for use_snapshot_archives_at_startup in [
+            UseSnapshotArchivesAtStartup::Always,
+            UseSnapshotArchivesAtStartup::Never,
+            UseSnapshotArchivesAtStartup::WhenNewest,
+        ] {
+            assert_eq!(
+                previous
+                    .check(&previous, use_snapshot_archives_at_startup, false)
+                    .unwrap(),
+                None
+            );
+        }
+        assert_eq!(
+            previous
+                .check(&current, UseSnapshotArchivesAtStartup::Always, false)
+                .unwrap(),
+            None
+        );
+
+        let err = previous
+            .check(&current, UseSnapshotArchivesAtStartup::Never, false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "accounts-db option account_paths changed from [\"/mnt/accounts\"] to \
+             [\"/mnt/nvme/accounts\"]: the local accounts state of the previous run cannot be \
+             loaded with the new option. Restore the previous account_paths, or rebuild the \
+             accounts from the snapshot archives with --use-snapshot-archives-at-startup \
+             when-newest or --accounts-db-allow-migration"
+        );
+    }
+
+    #[test]
+    fn test_accounts_db_manifest_storage_format_changed() {
+        let previous = AccountsDbManifest {
+            accounts_file_format: "HotStorage".to_string(),
+            ..manifest(&["/mnt/accounts"])
+        };
+        let current = manifest(&["/mnt/accounts"]);
+        let err = previous
+            .check(&current, UseSnapshotArchivesAtStartup::Never, false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "accounts-db option accounts_file_format changed from HotStorage to AppendVec: the \
+             local accounts state of the previous run cannot be loaded with the new option. \
+             Restore the previous accounts_file_format, or rebuild the accounts from the snapshot \
+             archives with --use-snapshot-archives-at-startup when-newest or \
+             --accounts-db-allow-migration"
+        );
+
+        let previous = AccountsDbManifest {
+            snapshot_version: "1.1.0".to_string(),
+            ..manifest(&["/mnt/accounts"])
+        };
+        assert_eq!(
+            previous
+                .check(&current, UseSnapshotArchivesAtStartup::WhenNewest, false)
+                .unwrap(),
+            Some(AccountsDbMigration::LoadFromSnapshotArchives)
+        );
+    }
+
+    #[test]
+    fn test_accounts_db_manifest_migration() {
+        let previous = manifest(&["/mnt/accounts"]);
+        let current = manifest(&["/mnt/accounts", "/mnt/nvme/accounts"]);
+        // Loading from the snapshot archives is already allowed
+        assert_eq!(
+            previous
+                .check(&current, UseSnapshotArchivesAtStartup::WhenNewest, false)
+                .unwrap(),
+            Some(AccountsDbMigration::LoadFromSnapshotArchives)
+        );
+        assert_eq!(
+            previous
+                .check(&current, UseSnapshotArchivesAtStartup::Never, true)
+                .unwrap(),
+            Some(AccountsDbMigration::LoadFromSnapshotArchives)
+        );
+    }
+}
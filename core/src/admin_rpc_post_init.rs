@@ -1,14 +1,21 @@
 use {
     crate::{
-        banking_stage::BankingControlMsg, cluster_slots_service::cluster_slots::ClusterSlots,
-        repair::repair_service::OutstandingShredRepairs,
+        advertised_addresses::AdvertisedAddresses,
+        banking_stage::BankingControlMsg,
+        cluster_slots_service::cluster_slots::ClusterSlots,
+        repair::{
+            repair_service::OutstandingShredRepairs, repair_state_snapshot::RepairStateSnapshotFn,
+        },
+        tpu_ingest_self_test::TpuIngestSelfTest,
     },
     agave_votor::event::VotorEventSender,
     solana_gossip::{cluster_info::ClusterInfo, node::NodeMultihoming},
     solana_ledger::blockstore::Blockstore,
     solana_pubkey::Pubkey,
     solana_runtime::{bank_forks::BankForks, snapshot_controller::SnapshotController},
+    solana_streamer::quic::SwQosConnections,
     solana_tls_utils::NotifyKeyUpdate,
+    solana_turbine::retransmit_stage::RetransmitDiagnostics,
     std::{
         collections::{HashMap, HashSet},
         net::UdpSocket,
@@ -81,13 +88,23 @@ pub struct AdminRpcRequestMetadataPostInit {
     pub bank_forks: Arc<RwLock<BankForks>>,
     pub vote_account: Pubkey,
     pub repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>,
+    /// Validators from which repairs are requested, `None` meaning all
+    pub repair_validators: Arc<RwLock<Option<HashSet<Pubkey>>>>,
     pub notifies: Arc<RwLock<KeyUpdaters>>,
     pub repair_socket: Arc<UdpSocket>,
     pub outstanding_repair_requests: Arc<RwLock<OutstandingShredRepairs>>,
     pub cluster_slots: Arc<ClusterSlots>,
+    /// Takes a consistent snapshot of the repair whitelist, the outstanding
+    /// repair requests and the cluster slots
+    pub repair_state_snapshot: Arc<RepairStateSnapshotFn>,
     pub node: Option<Arc<NodeMultihoming>>,
     pub banking_control_sender: mpsc::Sender<BankingControlMsg>,
     pub snapshot_controller: Arc<SnapshotController>,
     pub blockstore: Arc<Blockstore>,
     pub votor_event_sender: VotorEventSender,
+    pub tpu_connections: Option<SwQosConnections>,
+    pub tpu_forwards_connections: Option<SwQosConnections>,
+    pub ingest_self_test: Option<Arc<TpuIngestSelfTest>>,
+    pub advertised_addresses: Arc<AdvertisedAddresses>,
+    pub retransmit_diagnostics: Option<Arc<RetransmitDiagnostics>>,
 }
@@ -0,0 +1,152 @@
+//! Re-advertises the sockets of the node on a new IP address, for nodes whose
+//! public IP address changes while they run, e.g. behind a NAT with a dynamic
+//! WAN address.
+
+use {
+    solana_gossip::{cluster_info::ClusterInfo, contact_info::Error as ContactInfoError},
+    std::{
+        net::{IpAddr, SocketAddr},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    thiserror::Error,
+};
+
+// Guards against a flapping address detection re-advertising the node over
+// and over again.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AdvertisedAddressesError {
+    #[error(
+        "advertised addresses were refreshed {0:?} ago, the minimum interval is \
+         {MIN_REFRESH_INTERVAL:?}"
+    )]
+    RefreshedTooRecently(Duration),
+    #[error("unable to determine the validator's public IP address")]
+    UnknownPublicIpAddr,
+    #[error("failed to advertise {0}: {1}")]
+    InvalidIpAddr(IpAddr, ContactInfoError),
+}
+
+pub struct AdvertisedAddresses {
+    cluster_info: Arc<ClusterInfo>,
+    entrypoint_gossip_addrs: Vec<SocketAddr>,
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+impl AdvertisedAddresses {
+    pub fn new(cluster_info: Arc<ClusterInfo>, entrypoint_gossip_addrs: Vec<SocketAddr>) -> Self {
+        Self {
+            cluster_info,
+            entrypoint_gossip_addrs,
+            last_refresh: Mutex::default(),
+        }
+    }
+
+    /// Re-advertises all of this node's sockets on `new_ip`, keeping the
+    /// ports, and pushes the updated contact info to gossip right away. If
+    /// `new_ip` is `None` the public IP address is rediscovered by asking the
+    /// ip echo servers of the cluster entrypoints. Returns the advertised IP
+    /// address.
+    pub fn refresh(&self, new_ip: Option<IpAddr>) -> Result<IpAddr, AdvertisedAddressesError> {
+        // Held throughout, so that concurrent refreshes are rate limited too
+        let mut last_refresh = self.last_refresh.lock().unwrap();
+        if let Some(elapsed) = last_refresh.map(|last_refresh| last_refresh.elapsed()) {
+            if elapsed < MIN_REFRESH_INTERVAL {
+                return Err(AdvertisedAddressesError::RefreshedTooRecently(elapsed));
+            }
+        }
+        let new_ip = match new_ip {
+            Some(new_ip) => new_ip,
+            None => self.discover_public_ip_addr()?,
+        };
+        let old_ip = self
+            .cluster_info
+            .my_contact_info()
+            .gossip()
+            .map(|addr| addr.ip());
+        // The vote connection cache looks up our tpu address from cluster
+        // info on every send, so updating cluster info is sufficient.
+        self.cluster_info
+            .set_public_ip_addr(new_ip)
+            .map_err(|err| AdvertisedAddressesError::InvalidIpAddr(new_ip, err))?;
+        *last_refresh = Some(Instant::now());
+        info!("Advertised IP address changed from {old_ip:?} to {new_ip}");
+        datapoint_info!(
+            "validator-advertised-addresses-refresh",
+            ("old_ip", format!("{old_ip:?}"), String),
+            ("new_ip", new_ip.to_string(), String),
+        );
+        Ok(new_ip)
+    }
+
+    // Asks the ip echo servers of the entrypoints, in order, for this node's
+    // public IP address.
+    fn discover_public_ip_addr(&self) -> Result<IpAddr, AdvertisedAddressesError> {
+        let bind_ip_addr = self.cluster_info.bind_ip_addrs().active();
+        self.entrypoint_gossip_addrs
+            .iter()
+            .find_map(|entrypoint_addr| {
+                solana_net_utils::get_public_ip_addr_with_binding(entrypoint_addr, bind_ip_addr)
+                    .inspect_err(|err| {
+                        warn!("Failed to contact cluster entrypoint {entrypoint_addr}: {err}")
+                    })
+                    .ok()
+            })
+            .ok_or(AdvertisedAddressesError::UnknownPublicIpAddr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_gossip::contact_info::{ContactInfo, Protocol},
+        solana_keypair::Keypair,
+        solana_net_utils::SocketAddrSpace,
+        solana_signer::Signer,
+        std::net::Ipv4Addr,
+    };
+
+    #[test]
+    fn test_refresh_advertised_addresses() {
+        let keypair = Arc::new(Keypair::new());
+        let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let cluster_info = Arc::new(ClusterInfo::new(
+            contact_info.clone(),
+            keypair,
+            SocketAddrSpace::Unspecified,
+        ));
+        let advertised_addresses = AdvertisedAddresses::new(cluster_info.clone(), vec![]);
+
+        // Without entrypoints the public IP address cannot be discovered
+        assert_eq!(
+            advertised_addresses.refresh(None),
+            Err(AdvertisedAddressesError::UnknownPublicIpAddr)
+        );
+        assert_eq!(
+            advertised_addresses.refresh(Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED))),
+            Err(AdvertisedAddressesError::InvalidIpAddr(
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                ContactInfoError::UnspecifiedIpAddr(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            ))
+        );
+        assert_eq!(cluster_info.my_contact_info(), contact_info);
+
+        let new_ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+        assert_eq!(advertised_addresses.refresh(Some(new_ip)), Ok(new_ip));
+        let my_info = cluster_info.my_contact_info();
+        assert_eq!(
+            my_info.tpu(Protocol::QUIC).unwrap(),
+            SocketAddr::new(new_ip, contact_info.tpu(Protocol::QUIC).unwrap().port())
+        );
+
+        // Refreshing again right away is rejected
+        assert!(matches!(
+            advertised_addresses.refresh(Some(contact_info.gossip().unwrap().ip())),
+            Err(AdvertisedAddressesError::RefreshedTooRecently(_)),
+        ));
+        assert_eq!(cluster_info.my_contact_info(), my_info);
+    }
+}
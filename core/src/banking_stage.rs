@@ -11,7 +11,8 @@ use {
             consume_worker::ConsumeWorker,
             transaction_scheduler::{
                 scheduler_controller::{
-                    DEFAULT_SCHEDULER_PACING_FILL_TIME_MILLIS, SchedulerConfig, SchedulerController,
+                    DEFAULT_SCHEDULER_PACING_FILL_TIME_MILLIS, ReservedLane, SchedulerConfig,
+                    SchedulerController,
                 },
                 scheduler_error::SchedulerError,
             },
@@ -323,6 +324,16 @@ impl LikeClusterInfo for Arc<ClusterInfo> {
     }
 }
 
+/// Packets scheduled against compute units reserved for them in each leader
+/// slot, ahead of the other non-vote packets.
+///
+/// The packets must already be signature verified.
+pub struct ReservedIngest {
+    pub receiver: BankingPacketReceiver,
+    /// Compute units reserved in each leader slot, capped at the block limit.
+    pub compute_units: u64,
+}
+
 pub struct BankingStage {
     banking_shutdown_signal: CancellationToken,
     worker_exit_signal: Arc<AtomicBool>,
@@ -330,6 +341,7 @@ pub struct BankingStage {
     tpu_vote_receiver: BankingPacketReceiver,
     gossip_vote_receiver: BankingPacketReceiver,
     non_vote_receiver: BankingPacketReceiver,
+    reserved_ingest: Option<ReservedIngest>,
     transaction_recorder: TransactionRecorder,
     poh_recorder: Arc<RwLock<PohRecorder>>,
     bank_forks: Arc<RwLock<BankForks>>,
@@ -359,6 +371,7 @@ impl BankingStage {
         prioritization_fee_cache: Option<Arc<PrioritizationFeeCache>>,
         filter_keys: Arc<HashSet<Pubkey>>,
         priority_floor: Arc<SchedulerPriorityFloor>,
+        reserved_ingest: Option<ReservedIngest>,
     ) -> BankingStageHandle {
         let committer = Committer::new(
             transaction_status_sender,
@@ -375,6 +388,7 @@ impl BankingStage {
             tpu_vote_receiver,
             gossip_vote_receiver,
             non_vote_receiver,
+            reserved_ingest,
             transaction_recorder,
             poh_recorder,
             bank_forks,
@@ -511,7 +525,19 @@ impl BankingStage {
             receiver: self.non_vote_receiver.clone(),
             sharable_banks: sharable_banks.clone(),
             filter_keys: self.filter_keys.clone(),
+            reserved: false,
         };
+        let reserved_lane = self.reserved_ingest.as_ref().map(|reserved_ingest| {
+            ReservedLane::new(
+                TransactionViewReceiveAndBuffer {
+                    receiver: reserved_ingest.receiver.clone(),
+                    sharable_banks: sharable_banks.clone(),
+                    filter_keys: self.filter_keys.clone(),
+                    reserved: true,
+                },
+                reserved_ingest.compute_units,
+            )
+        });
 
         // Spawn vote worker.
         let mut threads = Vec::with_capacity(num_workers + 2);
@@ -578,6 +604,7 @@ impl BankingStage {
                         scheduler,
                         worker_metrics,
                         priority_floor,
+                        reserved_lane,
                     );
 
                     match scheduler_controller.run() {
@@ -670,6 +697,9 @@ mod external {
             }: AgaveSession,
         ) -> Result<Vec<JoinHandle<()>>, ()> {
             info!("Spawning external scheduler");
+            if self.reserved_ingest.is_some() {
+                warn!("The external scheduler does not receive the reserved ingest packets");
+            }
 
             static_assertions::const_assert!(
                 agave_scheduling_utils::handshake::MAX_WORKERS
@@ -835,6 +865,7 @@ mod tests {
         agave_banking_stage_ingress_types::BankingPacketBatch,
         crossbeam_channel::bounded,
         itertools::Itertools,
+        solana_cost_model::{cost_model::CostModel, cost_tracker::CostTrackerLimits},
         solana_entry::{
             entry::{self, EntrySlice},
             entry_or_marker::EntryOrMarker,
@@ -859,7 +890,9 @@ mod tests {
         solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
         solana_signer::Signer,
         solana_system_transaction as system_transaction,
-        solana_transaction::{Transaction, sanitized::SanitizedTransaction},
+        solana_transaction::{
+            Transaction, sanitized::SanitizedTransaction, versioned::VersionedTransaction,
+        },
         solana_vote::vote_transaction::new_tower_sync_transaction,
         solana_vote_program::vote_state::TowerSync,
         std::{sync::atomic::Ordering, thread::sleep, time::Instant},
@@ -920,6 +953,7 @@ mod tests {
             None,
             Arc::default(),
             Arc::new(SchedulerPriorityFloor::new()),
+            None,
         );
         drop(non_vote_sender);
         drop(tpu_vote_sender);
@@ -982,6 +1016,7 @@ mod tests {
             None,
             Arc::default(),
             Arc::new(SchedulerPriorityFloor::new()),
+            None,
         );
 
         // good tx, and no verify
@@ -1062,6 +1097,129 @@ mod tests {
         drop(entry_receiver);
     }
 
+    #[test]
+    fn test_banking_stage_reserved_ingest() {
+        agave_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_slow_genesis_config(1_000_000);
+        let (bank, bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+        let start_hash = bank.last_blockhash();
+        let transfer = || {
+            system_transaction::transfer(&mint_keypair, &solana_pubkey::new_rand(), 1, start_hash)
+        };
+
+        // The block only fits a few transfers, one of which is reserved
+        let transfer_cost = CostModel::calculate_cost(
+            &RuntimeTransaction::from_transaction_for_tests(transfer()),
+            &bank.feature_set,
+        )
+        .sum();
+        let block_limit = 4 * transfer_cost;
+        bank.write_cost_tracker()
+            .unwrap()
+            .set_limits(CostTrackerLimits::new(block_limit, block_limit, u64::MAX));
+
+        let banking_tracer = BankingTracer::new_disabled();
+        let Channels {
+            non_vote_sender,
+            non_vote_receiver,
+            tpu_vote_sender,
+            tpu_vote_receiver,
+            gossip_vote_sender,
+            gossip_vote_receiver,
+        } = banking_tracer.create_channels();
+        let (reserved_sender, reserved_receiver) = banking_tracer.create_channel_non_vote();
+
+        // Saturate the general channel before the reserved transaction arrives
+        let num_general_txs = 64;
+        let general_txs: Vec<_> = (0..num_general_txs).map(|_| transfer()).collect();
+        non_vote_sender
+            .send(BankingPacketBatch::new(to_packet_batches(&general_txs, 8)))
+            .unwrap();
+        let reserved_tx = transfer();
+        reserved_sender
+            .send(BankingPacketBatch::new(to_packet_batches(
+                &[reserved_tx.clone()],
+                1,
+            )))
+            .unwrap();
+
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(
+            Blockstore::open(ledger_path.path())
+                .expect("Expected to be able to open database ledger"),
+        );
+        let (
+            exit,
+            poh_recorder,
+            _poh_controller,
+            transaction_recorder,
+            poh_service,
+            entry_receiver,
+        ) = create_test_recorder(bank, blockstore, None, None);
+        let (replay_vote_sender, _replay_vote_receiver) = bounded(1024);
+
+        let banking_stage = BankingStage::new_num_threads(
+            BlockProductionMethod::CentralSchedulerGreedy,
+            poh_recorder.clone(),
+            transaction_recorder,
+            non_vote_receiver,
+            tpu_vote_receiver,
+            gossip_vote_receiver,
+            mpsc::channel(1).1,
+            DEFAULT_NUM_WORKERS,
+            SchedulerConfig {
+                scheduler_pacing: SchedulerPacing::Disabled,
+            },
+            None,
+            replay_vote_sender,
+            None,
+            bank_forks,
+            None,
+            Arc::default(),
+            Arc::new(SchedulerPriorityFloor::new()),
+            Some(ReservedIngest {
+                receiver: reserved_receiver,
+                compute_units: transfer_cost,
+            }),
+        );
+
+        let mut landed_txs = vec![];
+        let start = Instant::now();
+        while !landed_txs
+            .iter()
+            .any(|tx: &VersionedTransaction| tx.signatures == reserved_tx.signatures)
+        {
+            if let Ok((_bank, (EntryOrMarker::Entry(entry), _))) = entry_receiver.try_recv() {
+                landed_txs.extend(entry.transactions);
+            }
+            assert!(start.elapsed() < Duration::from_secs(10));
+            sleep(Duration::from_millis(10));
+        }
+
+        drop(non_vote_sender);
+        drop(tpu_vote_sender);
+        drop(gossip_vote_sender);
+        drop(reserved_sender);
+        banking_stage.join().unwrap();
+
+        exit.store(true, Ordering::Relaxed);
+        poh_service.join().unwrap();
+        drop(poh_recorder);
+        landed_txs.extend(entry_receiver.iter().flat_map(
+            |(_bank, (entry_or_marker, _tick_height))| entry_or_marker.unwrap_entry().transactions,
+        ));
+
+        // The general transactions filled the rest of the block, and did not
+        // all fit
+        let num_landed_general_txs = landed_txs.len() - 1;
+        assert!(num_landed_general_txs > 0);
+        assert!(num_landed_general_txs < num_general_txs);
+    }
+
     #[test]
     fn test_banking_stage_entryfication() {
         agave_logger::setup();
@@ -1138,6 +1296,7 @@ mod tests {
                 None,
                 Arc::default(),
                 Arc::new(SchedulerPriorityFloor::new()),
+                None,
             );
 
             // wait for banking_stage to eat the packets
@@ -1293,6 +1452,7 @@ mod tests {
             None,
             Arc::default(),
             Arc::new(SchedulerPriorityFloor::new()),
+            None,
         );
 
         let keypairs = (0..100).map(|_| Keypair::new()).collect_vec();
@@ -125,6 +125,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
         // Track metrics on filter.
         let mut num_scanned: usize = 0;
         let mut num_scheduled = Saturating::<usize>(0);
+        let mut cus_scheduled = Saturating::<u64>(0);
         let mut num_sent: usize = 0;
         let mut num_unschedulable_conflicts: usize = 0;
         let mut num_unschedulable_threads: usize = 0;
@@ -183,6 +184,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
                     }
 
                     num_scheduled += 1;
+                    cus_scheduled += cost;
                     self.common.batches.add_transaction_to_batch(
                         thread_id,
                         id.id,
@@ -220,6 +222,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
 
         num_sent += self.common.send_batches()?;
         let Saturating(num_scheduled) = num_scheduled;
+        let Saturating(cus_scheduled) = cus_scheduled;
         assert_eq!(
             num_scheduled, num_sent,
             "number of scheduled and sent transactions must match"
@@ -232,6 +235,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
             starting_queue_size,
             starting_buffer_size,
             num_scheduled,
+            cus_scheduled,
             num_unschedulable_conflicts,
             num_unschedulable_threads,
         })
@@ -68,7 +68,7 @@ pub(crate) struct ReceivingStats {
 }
 
 impl ReceivingStats {
-    fn accumulate(&mut self, other: ReceivingStats) {
+    pub(crate) fn accumulate(&mut self, other: ReceivingStats) {
         self.num_received += other.num_received;
         self.num_dropped_without_parsing += other.num_dropped_without_parsing;
         self.num_dropped_on_parsing_and_sanitization +=
@@ -104,6 +104,8 @@ pub(crate) struct TransactionViewReceiveAndBuffer {
     pub receiver: BankingPacketReceiver,
     pub sharable_banks: SharableBanks,
     pub filter_keys: Arc<HashSet<Pubkey>>,
+    /// Whether the transactions received are queued in the reserved lane.
+    pub reserved: bool,
 }
 
 impl ReceiveAndBuffer for TransactionViewReceiveAndBuffer {
@@ -352,7 +354,12 @@ impl TransactionViewReceiveAndBuffer {
                             enable_instruction_accounts_limit,
                             &self.filter_keys,
                         ) {
-                            Ok(state) => Ok(state),
+                            Ok(mut state) => {
+                                if self.reserved {
+                                    state.mark_reserved();
+                                }
+                                Ok(state)
+                            }
                             Err(
                                 PacketHandlingError::Sanitization
                                 | PacketHandlingError::ALTResolution,
@@ -605,6 +612,7 @@ mod tests {
             receiver,
             sharable_banks: bank_forks.read().unwrap().sharable_banks(),
             filter_keys,
+            reserved: false,
         };
         let container = TransactionViewStateContainer::with_capacity(TEST_CONTAINER_CAPACITY);
         (receive_and_buffer, container)
@@ -54,6 +54,8 @@ pub(crate) struct SchedulingSummary {
 
     /// Number of transactions scheduled.
     pub num_scheduled: usize,
+    /// Compute units of the transactions scheduled.
+    pub cus_scheduled: u64,
     /// Number of transactions that were not scheduled due to conflicts.
     pub num_unschedulable_conflicts: usize,
     /// Number of transactions that were skipped due to thread capacity.
@@ -4,7 +4,7 @@
 use {
     super::{
         receive_and_buffer::{DisconnectedError, ReceiveAndBuffer},
-        scheduler::Scheduler,
+        scheduler::{Scheduler, SchedulingSummary},
         scheduler_error::SchedulerError,
         scheduler_metrics::{SchedulerCountMetrics, SchedulerTimingMetrics, SchedulingDetails},
     },
@@ -14,14 +14,15 @@ use {
             consume_worker::ConsumeWorkerMetrics,
             decision_maker::{BufferedPacketsDecision, DecisionMaker},
             transaction_scheduler::{
-                receive_and_buffer::ReceivingStats, transaction_priority_id::TransactionPriorityId,
-                transaction_state_container::StateContainer,
+                receive_and_buffer::ReceivingStats,
+                transaction_priority_id::TransactionPriorityId,
+                transaction_state_container::{Lane, StateContainer},
             },
         },
         validator::SchedulerPacing,
     },
     agave_banking_stage_ingress_types::SchedulerPriorityFloor,
-    solana_clock::DEFAULT_MS_PER_SLOT,
+    solana_clock::{DEFAULT_MS_PER_SLOT, Slot},
     solana_cost_model::cost_tracker::SharedBlockCost,
     solana_measure::measure_us,
     solana_runtime::bank_forks::SharableBanks,
@@ -114,6 +115,56 @@ impl Drop for SaturationState {
     }
 }
 
+/// Transactions received on the reserved ingest channel, scheduled against
+/// compute units reserved for them in each leader slot, ahead of the general
+/// lane.
+pub(crate) struct ReservedLane<R> {
+    receive_and_buffer: R,
+    /// Compute units reserved in each leader slot.
+    compute_units: u64,
+    /// Compute units reserved in the current leader slot, capped at its block
+    /// limit.
+    slot_compute_units: u64,
+    /// Compute units of the reserved transactions scheduled in the current
+    /// leader slot.
+    slot_scheduled_cus: u64,
+}
+
+impl<R> ReservedLane<R> {
+    pub(crate) fn new(receive_and_buffer: R, compute_units: u64) -> Self {
+        Self {
+            receive_and_buffer,
+            compute_units,
+            slot_compute_units: 0,
+            slot_scheduled_cus: 0,
+        }
+    }
+
+    fn start_slot(&mut self, block_limit: u64) {
+        self.slot_compute_units = self.compute_units.min(block_limit);
+        self.slot_scheduled_cus = 0;
+    }
+
+    /// Compute units of the reservation left in the current leader slot.
+    fn remaining_cus(&self) -> u64 {
+        self.slot_compute_units
+            .saturating_sub(self.slot_scheduled_cus)
+    }
+
+    fn report(&self, slot: Slot) {
+        let utilization_pct = (self.slot_scheduled_cus as u128 * 100)
+            .checked_div(self.slot_compute_units as u128)
+            .unwrap_or_default();
+        datapoint_info!(
+            "banking_stage_scheduler_reserved_lane",
+            ("slot", slot, i64),
+            ("reserved_cus", self.slot_compute_units, i64),
+            ("scheduled_cus", self.slot_scheduled_cus, i64),
+            ("utilization_pct", utilization_pct, i64),
+        );
+    }
+}
+
 /// Controls packet and transaction flow into scheduler, and scheduling execution.
 pub(crate) struct SchedulerController<R, S>
 where
@@ -148,6 +199,8 @@ where
     recheck_chunk: Vec<TransactionPriorityId>,
     /// Saturation detection and priority floor publication.
     saturation_state: SaturationState,
+    /// Lane for the transactions of the reserved ingest channel, if any.
+    reserved_lane: Option<ReservedLane<R>>,
 }
 
 impl<R, S> SchedulerController<R, S>
@@ -164,6 +217,7 @@ where
         scheduler: S,
         worker_metrics: Vec<Arc<ConsumeWorkerMetrics>>,
         priority_floor: Arc<SchedulerPriorityFloor>,
+        reserved_lane: Option<ReservedLane<R>>,
     ) -> Self {
         priority_floor.clear();
         let container_capacity = TOTAL_BUFFERED_PACKETS;
@@ -183,6 +237,7 @@ where
             recheck_cursor: None,
             recheck_chunk: Vec::with_capacity(CHECK_CHUNK),
             saturation_state,
+            reserved_lane,
         }
     }
 
@@ -214,6 +269,11 @@ where
                 .maybe_report_and_reset_slot(new_leader_slot);
 
             if most_recent_leader_slot != new_leader_slot {
+                if let (Some(slot), Some(reserved_lane)) =
+                    (most_recent_leader_slot, self.reserved_lane.as_ref())
+                {
+                    reserved_lane.report(slot);
+                }
                 self.container.flush_held_transactions();
                 most_recent_leader_slot = new_leader_slot;
                 cost_pacer = decision.bank().map(|b| {
@@ -250,6 +310,11 @@ where
                         fill_time,
                     }
                 });
+                if let (Some(reserved_lane), Some(cost_pacer)) =
+                    (self.reserved_lane.as_mut(), cost_pacer.as_ref())
+                {
+                    reserved_lane.start_slot(cost_pacer.block_limit);
+                }
             }
 
             self.receive_completed()?;
@@ -296,25 +361,50 @@ where
                 let scheduling_budget = cost_pacer
                     .expect("cost pacer must be set for Consume")
                     .scheduling_budget(now);
-                let (scheduling_summary, schedule_time_us) = measure_us!(
-                    self.scheduler
-                        .schedule(&mut self.container, scheduling_budget,)?
-                );
-
-                self.count_metrics.update(|count_metrics| {
-                    count_metrics.num_scheduled += scheduling_summary.num_scheduled;
-                    count_metrics.num_unschedulable_conflicts +=
-                        scheduling_summary.num_unschedulable_conflicts;
-                    count_metrics.num_unschedulable_threads +=
-                        scheduling_summary.num_unschedulable_threads;
-                });
-
-                self.timing_metrics.update(|timing_metrics| {
-                    timing_metrics.schedule_time_us += schedule_time_us;
-                });
-                self.scheduling_details.update(&scheduling_summary);
-
-                scheduling_summary.num_scheduled
+                let mut num_scheduled = 0;
+
+                let reserved_cus = self
+                    .reserved_lane
+                    .as_ref()
+                    .map_or(0, ReservedLane::remaining_cus);
+                if reserved_cus > 0 && self.container.reserved_queue_size() > 0 {
+                    // The scheduler takes the compute units in flight out of
+                    // the budget, so add them back to schedule the reservation.
+                    let cus_in_flight: u64 = self
+                        .scheduler
+                        .scheduling_common_mut()
+                        .in_flight_tracker
+                        .cus_in_flight_per_thread()
+                        .iter()
+                        .sum();
+                    self.container.select_lane(Lane::Reserved);
+                    let scheduling_summary = self.schedule(
+                        scheduling_budget.min(cus_in_flight.saturating_add(reserved_cus)),
+                    );
+                    self.container.select_lane(Lane::General);
+                    let scheduling_summary = scheduling_summary?;
+                    if let Some(reserved_lane) = self.reserved_lane.as_mut() {
+                        reserved_lane.slot_scheduled_cus = reserved_lane
+                            .slot_scheduled_cus
+                            .saturating_add(scheduling_summary.cus_scheduled);
+                    }
+                    num_scheduled += scheduling_summary.num_scheduled;
+                }
+
+                // Hold back the rest of the reservation while reserved
+                // transactions are queued, the general lane gets it otherwise.
+                let held_back_cus = if self.container.reserved_queue_size() > 0 {
+                    self.reserved_lane
+                        .as_ref()
+                        .map_or(0, ReservedLane::remaining_cus)
+                } else {
+                    0
+                };
+                num_scheduled += self
+                    .schedule(scheduling_budget.saturating_sub(held_back_cus))?
+                    .num_scheduled;
+
+                num_scheduled
             }
             BufferedPacketsDecision::Forward => {
                 let (_, clear_time_us) = measure_us!(self.clear_container());
@@ -331,6 +421,26 @@ where
         Ok(scheduled)
     }
 
+    /// Schedule transactions from the selected lane of the container.
+    fn schedule(&mut self, budget: u64) -> Result<SchedulingSummary, SchedulerError> {
+        let (scheduling_summary, schedule_time_us) =
+            measure_us!(self.scheduler.schedule(&mut self.container, budget)?);
+
+        self.count_metrics.update(|count_metrics| {
+            count_metrics.num_scheduled += scheduling_summary.num_scheduled;
+            count_metrics.num_unschedulable_conflicts +=
+                scheduling_summary.num_unschedulable_conflicts;
+            count_metrics.num_unschedulable_threads += scheduling_summary.num_unschedulable_threads;
+        });
+
+        self.timing_metrics.update(|timing_metrics| {
+            timing_metrics.schedule_time_us += schedule_time_us;
+        });
+        self.scheduling_details.update(&scheduling_summary);
+
+        Ok(scheduling_summary)
+    }
+
     /// Update the scheduler priority floor.
     ///
     /// Semantics: when the retained scheduler buffer is nearly full, drop
@@ -356,9 +466,12 @@ where
     /// This only clears pending transactions, and does **not** clear in-flight transactions.
     fn clear_container(&mut self) {
         let mut num_dropped_on_clear = Saturating::<usize>(0);
-        while let Some(id) = self.container.pop() {
-            self.container.remove_by_id(id.id);
-            num_dropped_on_clear += 1;
+        for lane in [Lane::Reserved, Lane::General] {
+            self.container.select_lane(lane);
+            while let Some(id) = self.container.pop() {
+                self.container.remove_by_id(id.id);
+                num_dropped_on_clear += 1;
+            }
         }
 
         self.count_metrics.update(|count_metrics| {
@@ -452,9 +565,18 @@ where
         &mut self,
         decision: &BufferedPacketsDecision,
     ) -> Result<ReceivingStats, DisconnectedError> {
-        let receiving_stats = self
+        let mut receiving_stats = self
             .receive_and_buffer
             .receive_and_buffer_packets(&mut self.container, decision)?;
+        if let Some(reserved_lane) = self.reserved_lane.as_mut() {
+            // Only the disconnection of the general channel stops the scheduler.
+            if let Ok(reserved_stats) = reserved_lane
+                .receive_and_buffer
+                .receive_and_buffer_packets(&mut self.container, decision)
+            {
+                receiving_stats.accumulate(reserved_stats);
+            }
+        }
 
         self.count_metrics.update(|count_metrics| {
             let ReceivingStats {
@@ -580,6 +702,7 @@ mod tests {
             receiver,
             sharable_banks: bank_forks.read().unwrap().sharable_banks(),
             filter_keys: Arc::default(),
+            reserved: false,
         }
     }
 
@@ -635,6 +758,7 @@ mod tests {
             scheduler,
             vec![], // no actual workers with metrics to report, this can be empty
             Arc::new(SchedulerPriorityFloor::default()),
+            None,
         );
 
         (test_frame, scheduler_controller)
@@ -1082,6 +1206,117 @@ mod tests {
             .collect_vec();
         assert_eq!(message_hashes, vec![&tx1_hash]);
     }
+
+    #[test]
+    fn test_schedule_consume_reserved_lane() {
+        let (mut test_frame, mut scheduler_controller) =
+            create_test_frame(1, test_create_transaction_view_receive_and_buffer);
+        let TestFrame {
+            bank,
+            bank_forks,
+            mint_keypair,
+            shared_leader_state,
+            banking_packet_sender,
+            consume_work_receivers,
+            ..
+        } = &mut test_frame;
+
+        let (reserved_packet_sender, reserved_packet_receiver) = bounded(1024);
+        let reserved_receive_and_buffer = TransactionViewReceiveAndBuffer {
+            reserved: true,
+            ..test_create_transaction_view_receive_and_buffer(
+                reserved_packet_receiver,
+                bank_forks.clone(),
+            )
+        };
+        scheduler_controller.reserved_lane =
+            Some(ReservedLane::new(reserved_receive_and_buffer, u64::MAX));
+
+        shared_leader_state.store(Arc::new(LeaderState::new(
+            Some(bank.clone()),
+            bank.tick_height(),
+            None,
+            None,
+        )));
+
+        // The general transactions pay more than the reserved one
+        let general_txs = (0..4)
+            .map(|_| {
+                create_and_fund_prioritized_transfer(
+                    bank,
+                    mint_keypair,
+                    &Keypair::new(),
+                    &Pubkey::new_unique(),
+                    1,
+                    2000,
+                    bank.last_blockhash(),
+                )
+            })
+            .collect_vec();
+        let reserved_tx = create_and_fund_prioritized_transfer(
+            bank,
+            mint_keypair,
+            &Keypair::new(),
+            &Pubkey::new_unique(),
+            1,
+            1000,
+            bank.last_blockhash(),
+        );
+        let reserved_tx_hash = reserved_tx.message().hash();
+        banking_packet_sender
+            .send(to_banking_packet_batch(&general_txs))
+            .unwrap();
+        reserved_packet_sender
+            .send(to_banking_packet_batch(&[reserved_tx]))
+            .unwrap();
+
+        let decision = scheduler_controller
+            .decision_maker
+            .make_consume_or_forward_decision();
+        assert!(matches!(decision, BufferedPacketsDecision::Consume(_)));
+        while scheduler_controller
+            .receive_and_buffer_packets(&decision)
+            .map(|n| n.num_received > 0)
+            .unwrap_or_default()
+        {}
+        assert_eq!(scheduler_controller.container.reserved_queue_size(), 1);
+
+        // Only the first transaction scheduled fits in the block, and the
+        // reservation is capped at the block limit
+        let block_limit = 1;
+        let reserved_lane = scheduler_controller.reserved_lane.as_mut().unwrap();
+        reserved_lane.start_slot(block_limit);
+        assert_eq!(reserved_lane.remaining_cus(), block_limit);
+        let now = Instant::now();
+        let cost_pacer = CostPacer {
+            block_limit,
+            shared_block_cost: SharedBlockCost::new(0),
+            detection_time: now,
+            fill_time: None,
+        };
+        assert_eq!(
+            scheduler_controller
+                .process_transactions(&decision, Some(&cost_pacer), &now)
+                .unwrap(),
+            1
+        );
+        let consume_work = consume_work_receivers[0].try_recv().unwrap();
+        let message_hashes = consume_work
+            .transactions
+            .iter()
+            .map(|tx| tx.message_hash())
+            .collect_vec();
+        assert_eq!(message_hashes, vec![&reserved_tx_hash]);
+        assert!(consume_work_receivers[0].try_recv().is_err());
+        assert_eq!(
+            scheduler_controller
+                .reserved_lane
+                .as_ref()
+                .unwrap()
+                .remaining_cus(),
+            0
+        );
+    }
 }
 
 #[cfg(test)]
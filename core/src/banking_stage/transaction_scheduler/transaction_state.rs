@@ -20,6 +20,8 @@ pub(crate) struct TransactionState<Tx> {
     priority: u64,
     /// Estimated cost of the transaction.
     cost: u64,
+    /// Whether the transaction was received on the reserved ingest channel.
+    reserved: bool,
 }
 
 impl<Tx> TransactionState<Tx> {
@@ -30,9 +32,21 @@ impl<Tx> TransactionState<Tx> {
             max_age,
             priority,
             cost,
+            reserved: false,
         }
     }
 
+    /// Marks the transaction as received on the reserved ingest channel, so
+    /// that it is queued in the reserved lane.
+    pub(crate) fn mark_reserved(&mut self) {
+        self.reserved = true;
+    }
+
+    /// Return whether the transaction is queued in the reserved lane.
+    pub(crate) fn is_reserved(&self) -> bool {
+        self.reserved
+    }
+
     /// Return the priority of the transaction.
     /// This is *not* the same as the `compute_unit_price` of the transaction.
     /// The priority is used to order transactions for processing.
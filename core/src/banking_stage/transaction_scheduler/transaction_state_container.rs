@@ -2,12 +2,13 @@ use {
     super::{transaction_priority_id::TransactionPriorityId, transaction_state::TransactionState},
     crate::banking_stage::scheduler_messages::TransactionId,
     agave_transaction_view::resolved_transaction_view::ResolvedTransactionView,
+    itertools::Itertools,
     slab::{Slab, VacantEntry},
     solana_packet::PACKET_DATA_SIZE,
     solana_runtime_transaction::{
         runtime_transaction::RuntimeTransaction, transaction_with_meta::TransactionWithMeta,
     },
-    std::{collections::BTreeSet, iter::Rev, ops::Bound, sync::Arc},
+    std::{collections::BTreeSet, ops::Bound, sync::Arc},
 };
 
 /// This structure will hold `TransactionState` for the entirety of a
@@ -28,6 +29,10 @@ use {
 /// 2. A map of `TransactionId` to `TransactionState`, which is used to
 ///    track the state of each transaction.
 ///
+/// Transactions received on the reserved ingest channel are queued in a
+/// separate priority queue, the reserved lane, so that they can be scheduled
+/// against their own budget.
+///
 /// When `Pending`, the associated `TransactionId` is not in the queue, but
 /// is still in the map.
 /// The entry in the map should exist before insertion into the queue, and be
@@ -38,10 +43,19 @@ use {
 pub(crate) struct TransactionStateContainer<Tx: TransactionWithMeta> {
     capacity: usize,
     priority_queue: BTreeSet<TransactionPriorityId>,
+    reserved_queue: BTreeSet<TransactionPriorityId>,
+    selected_lane: Lane,
     id_to_transaction_state: Slab<TransactionState<Tx>>,
     held_transactions: Vec<TransactionPriorityId>,
 }
 
+/// The queues of a container that transactions are scheduled from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Lane {
+    General,
+    Reserved,
+}
+
 pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
     /// Create a new `TransactionStateContainer` with the given capacity.
     fn with_capacity(capacity: usize) -> Self;
@@ -56,6 +70,13 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
     /// Get the top transaction id in the priority queue.
     fn pop(&mut self) -> Option<TransactionPriorityId>;
 
+    /// Select the lane that `queue_size`, `is_empty`, and `pop` operate on.
+    /// Transactions are always pushed into the queue of their own lane.
+    fn select_lane(&mut self, lane: Lane);
+
+    /// Number of transactions queued in the reserved lane.
+    fn reserved_queue_size(&self) -> usize;
+
     /// Get mutable transaction state by id.
     fn get_mut_transaction_state(&mut self, id: TransactionId)
     -> Option<&mut TransactionState<Tx>>;
@@ -104,14 +125,16 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
 
     fn flush_held_transactions(&mut self);
 
+    /// Return the min and max priority queued in either lane.
     fn get_min_max_priority(&self) -> Option<(u64, u64)>;
 
     /// Return an iterator over priority IDs strictly below `cursor` in descending order,
     /// or all IDs in descending order if `cursor` is `None`.
+    /// The IDs of both lanes are merged.
     fn recheck_iter(
         &self,
         cursor: Option<&TransactionPriorityId>,
-    ) -> Rev<std::collections::btree_set::Range<'_, TransactionPriorityId>>;
+    ) -> impl Iterator<Item = &TransactionPriorityId>;
 }
 
 // Extra capacity is added because some additional space is needed when
@@ -123,13 +146,15 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
         Self {
             capacity,
             priority_queue: BTreeSet::new(),
+            reserved_queue: BTreeSet::new(),
+            selected_lane: Lane::General,
             id_to_transaction_state: Slab::with_capacity(capacity + EXTRA_CAPACITY),
             held_transactions: Vec::with_capacity(capacity),
         }
     }
 
     fn queue_size(&self) -> usize {
-        self.priority_queue.len()
+        self.selected_queue().len()
     }
 
     fn buffer_size(&self) -> usize {
@@ -137,11 +162,22 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
     }
 
     fn is_empty(&self) -> bool {
-        self.priority_queue.is_empty()
+        self.selected_queue().is_empty()
     }
 
     fn pop(&mut self) -> Option<TransactionPriorityId> {
-        self.priority_queue.pop_last()
+        match self.selected_lane {
+            Lane::General => self.priority_queue.pop_last(),
+            Lane::Reserved => self.reserved_queue.pop_last(),
+        }
+    }
+
+    fn select_lane(&mut self, lane: Lane) {
+        self.selected_lane = lane;
+    }
+
+    fn reserved_queue_size(&self) -> usize {
+        self.reserved_queue.len()
     }
 
     fn get_mut_transaction_state(
@@ -162,7 +198,15 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
         priority_ids: impl Iterator<Item = TransactionPriorityId>,
     ) -> usize {
         for id in priority_ids {
-            self.priority_queue.insert(id);
+            let reserved = self
+                .id_to_transaction_state
+                .get(id.id)
+                .is_some_and(TransactionState::is_reserved);
+            if reserved {
+                self.reserved_queue.insert(id);
+            } else {
+                self.priority_queue.insert(id);
+            }
         }
 
         // The number of items in the `id_to_transaction_state` map is
@@ -174,8 +218,18 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
             .len()
             .saturating_sub(self.capacity);
 
+        // Drop the lowest priority transactions, whichever lane they are in.
         for _ in 0..num_dropped {
-            let priority_id = self.priority_queue.pop_first().expect("queue is not empty");
+            let drop_reserved = match (self.priority_queue.first(), self.reserved_queue.first()) {
+                (Some(general), Some(reserved)) => reserved < general,
+                (general, _) => general.is_none(),
+            };
+            let queue = if drop_reserved {
+                &mut self.reserved_queue
+            } else {
+                &mut self.priority_queue
+            };
+            let priority_id = queue.pop_first().expect("queue is not empty");
             self.id_to_transaction_state.remove(priority_id.id);
         }
 
@@ -190,8 +244,12 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
         let state = self.id_to_transaction_state.remove(id);
         // Remove from queue if present. May not be present if the transaction was already popped
         // (in-flight/scheduling).
-        self.priority_queue
-            .remove(&TransactionPriorityId::new(state.priority(), id));
+        let priority_id = TransactionPriorityId::new(state.priority(), id);
+        if state.is_reserved() {
+            self.reserved_queue.remove(&priority_id);
+        } else {
+            self.priority_queue.remove(&priority_id);
+        }
     }
 
     fn flush_held_transactions(&mut self) {
@@ -201,8 +259,18 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
     }
 
     fn get_min_max_priority(&self) -> Option<(u64, u64)> {
-        let min = self.priority_queue.first()?.priority;
-        let max = self.priority_queue.last().unwrap().priority;
+        let queues = [&self.priority_queue, &self.reserved_queue];
+        let min = queues
+            .iter()
+            .filter_map(|queue| queue.first())
+            .min()?
+            .priority;
+        let max = queues
+            .iter()
+            .filter_map(|queue| queue.last())
+            .max()
+            .unwrap()
+            .priority;
 
         Some((min, max))
     }
@@ -210,14 +278,11 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
     fn recheck_iter(
         &self,
         cursor: Option<&TransactionPriorityId>,
-    ) -> Rev<std::collections::btree_set::Range<'_, TransactionPriorityId>> {
-        match cursor {
-            None => self.priority_queue.range(..).rev(),
-            Some(cursor) => self
-                .priority_queue
-                .range((Bound::Unbounded, Bound::Excluded(cursor)))
-                .rev(),
-        }
+    ) -> impl Iterator<Item = &TransactionPriorityId> {
+        let bound = cursor.map_or(Bound::Unbounded, Bound::Excluded);
+        let general = self.priority_queue.range((Bound::Unbounded, bound)).rev();
+        let reserved = self.reserved_queue.range((Bound::Unbounded, bound)).rev();
+        general.merge_by(reserved, |a, b| a > b)
     }
 }
 
@@ -242,6 +307,13 @@ impl<Tx: TransactionWithMeta> TransactionStateContainer<Tx> {
         self.push_ids_into_queue(std::iter::once(priority_id)) > 0
     }
 
+    fn selected_queue(&self) -> &BTreeSet<TransactionPriorityId> {
+        match self.selected_lane {
+            Lane::General => &self.priority_queue,
+            Lane::Reserved => &self.reserved_queue,
+        }
+    }
+
     fn get_vacant_map_entry(&mut self) -> VacantEntry<'_, TransactionState<Tx>> {
         assert!(self.id_to_transaction_state.len() < self.id_to_transaction_state.capacity());
         self.id_to_transaction_state.vacant_entry()
@@ -335,6 +407,16 @@ impl StateContainer<RuntimeTransactionView> for TransactionViewStateContainer {
         self.inner.pop()
     }
 
+    #[inline]
+    fn select_lane(&mut self, lane: Lane) {
+        self.inner.select_lane(lane);
+    }
+
+    #[inline]
+    fn reserved_queue_size(&self) -> usize {
+        self.inner.reserved_queue_size()
+    }
+
     #[inline]
     fn get_mut_transaction_state(
         &mut self,
@@ -380,7 +462,7 @@ impl StateContainer<RuntimeTransactionView> for TransactionViewStateContainer {
     fn recheck_iter(
         &self,
         cursor: Option<&TransactionPriorityId>,
-    ) -> Rev<std::collections::btree_set::Range<'_, TransactionPriorityId>> {
+    ) -> impl Iterator<Item = &TransactionPriorityId> {
         self.inner.recheck_iter(cursor)
     }
 }
@@ -626,4 +708,58 @@ mod tests {
         assert!(container.get_transaction(popped.id).is_none());
         assert!(container.is_empty());
     }
+
+    #[test]
+    fn test_reserved_lane() {
+        let mut container = TransactionStateContainer::with_capacity(3);
+        for priority in [0, 3] {
+            let (transaction, max_age, priority, cost) = test_transaction(priority);
+            container.insert_new_transaction(transaction, max_age, priority, cost);
+        }
+        let (transaction, max_age, priority, cost) = test_transaction(2);
+        let mut state = TransactionState::new(transaction, max_age, priority, cost);
+        state.mark_reserved();
+        let entry = container.get_vacant_map_entry();
+        let reserved_id = TransactionPriorityId::new(priority, entry.key());
+        entry.insert(state);
+        container.push_ids_into_queue(std::iter::once(reserved_id));
+        assert_eq!(container.queue_size(), 2);
+        assert_eq!(container.reserved_queue_size(), 1);
+
+        // Both lanes are rechecked, in priority order
+        let recheck_ids: Vec<_> = container.recheck_iter(None).copied().collect();
+        assert_eq!(
+            recheck_ids.iter().map(|id| id.priority).collect_vec(),
+            vec![3, 2, 0]
+        );
+        assert_eq!(recheck_ids[1], reserved_id);
+        let remaining: Vec<_> = container
+            .recheck_iter(Some(&reserved_id))
+            .copied()
+            .collect();
+        assert_eq!(remaining, recheck_ids[2..]);
+
+        // Going over capacity drops the lowest priority, here in the general
+        // lane
+        let (transaction, max_age, priority, cost) = test_transaction(5);
+        container.insert_new_transaction(transaction, max_age, priority, cost);
+        assert_eq!(container.queue_size(), 2);
+        assert_eq!(container.reserved_queue_size(), 1);
+        assert_eq!(container.get_min_max_priority(), Some((2, 5)));
+
+        // and then in the reserved lane
+        let (transaction, max_age, priority, cost) = test_transaction(6);
+        container.insert_new_transaction(transaction, max_age, priority, cost);
+        assert_eq!(container.reserved_queue_size(), 0);
+        assert_eq!(container.get_min_max_priority(), Some((3, 6)));
+        assert!(container.get_transaction(reserved_id.id).is_none());
+
+        container.select_lane(Lane::Reserved);
+        assert!(container.is_empty());
+        container.select_lane(Lane::General);
+        let priorities: Vec<_> = std::iter::from_fn(|| container.pop())
+            .map(|id| id.priority)
+            .collect();
+        assert_eq!(priorities, vec![6, 5, 3]);
+    }
 }
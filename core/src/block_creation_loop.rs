@@ -134,6 +134,9 @@ pub struct BlockCreationLoopConfig {
 
     /// Sender for packets to banking stage (used to re-inject transactions after sad leader handover).
     pub banking_stage_sender: BankingPacketSender,
+
+    /// Never produce blocks before this slot, regardless of voting.
+    pub min_leader_slot: Option<Slot>,
 }
 
 struct LeaderContext {
@@ -239,6 +242,7 @@ fn start_loop(config: BlockCreationLoopConfig, reward_certs_requestor: CertsRequ
         optimistic_parent_receiver,
         highest_finalized,
         banking_stage_sender,
+        min_leader_slot,
         sharable_banks: _,
     } = config;
 
@@ -378,6 +382,16 @@ fn start_loop(config: BlockCreationLoopConfig, reward_certs_requestor: CertsRequ
             continue;
         }
 
+        if let Some(min_leader_slot) = min_leader_slot {
+            if start_slot < min_leader_slot {
+                info!(
+                    "{my_pubkey}: window {start_slot}-{end_slot} starts before min leader slot \
+                     {min_leader_slot}, skipping"
+                );
+                continue;
+            }
+        }
+
         if let Err(e) = produce_window(
             fast_leader_handover,
             start_slot,
@@ -2,16 +2,27 @@ use {
     crate::{
         banking_trace::BankingPacketSender,
         consensus::vote_stake_tracker::VoteStakeTracker,
+        gossip_vote_landing::GossipVoteLandingTracker,
+        leader_window::LeaderWindow,
         optimistic_confirmation_verifier::OptimisticConfirmationVerifier,
+        optimistic_confirmation_violations::OptimisticConfirmationViolationLog,
         replay_stage::DUPLICATE_THRESHOLD,
+        restart_latency_tracker::RestartLatencyTracker,
         result::{Error, Result},
         sigverify_stage::GossipSigVerifyHandle,
+        slot_gap_classifier::SlotGapClassifier,
+        vote_account_monitor::VoteAccountMonitor,
+        vote_capture::{CapturedVote, VoteCapture, VoteSource},
+        vote_tracker_archive::VoteTrackerArchiver,
     },
     agave_banking_stage_ingress_types::BankingPacketBatch,
     agave_votor_messages::{VerifiedVoterSlotsSender, migration::MigrationStatus},
-    crossbeam_channel::{Receiver, RecvTimeoutError, Select, Sender, unbounded},
+    crossbeam_channel::{
+        Receiver, RecvTimeoutError, Select, Sender, TrySendError, bounded, unbounded,
+    },
+    histogram::Histogram,
     log::*,
-    solana_clock::{BankId, Slot},
+    solana_clock::{BankId, Epoch, Slot},
     solana_gossip::{
         cluster_info::{ClusterInfo, GOSSIP_SLEEP_MILLIS},
         crds::Cursor,
@@ -19,7 +30,11 @@ use {
     solana_hash::Hash,
     solana_ledger::blockstore::Blockstore,
     solana_measure::measure::Measure,
-    solana_perf::packet::{self, PacketBatch},
+    solana_perf::{
+        packet::{self, PacketBatch},
+        watchdog::Watchdog,
+    },
+    solana_poh::poh_recorder::SharedLeaderState,
     solana_pubkey::Pubkey,
     solana_rpc::{
         optimistically_confirmed_bank_tracker::{BankNotification, BankNotificationSenderConfig},
@@ -33,7 +48,7 @@ use {
         vote_sender_types::{ReplayVoteMessage, ReplayVoteReceiver},
     },
     solana_signature::Signature,
-    solana_time_utils::AtomicInterval,
+    solana_time_utils::{AtomicInterval, timestamp},
     solana_transaction::Transaction,
     solana_vote::{
         vote_parser::{self, ParsedVote},
@@ -41,11 +56,12 @@ use {
     },
     std::{
         cmp::max,
-        collections::{HashMap, hash_map::Entry},
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque, hash_map::Entry},
         iter::repeat,
+        num::NonZeroUsize,
         sync::{
-            Arc, RwLock,
-            atomic::{AtomicBool, Ordering},
+            Arc, Mutex, RwLock, RwLockReadGuard,
+            atomic::{AtomicBool, AtomicUsize, Ordering},
         },
         thread::{self, Builder, JoinHandle, sleep},
         time::{Duration, Instant},
@@ -56,17 +72,190 @@ use {
 pub type ThresholdConfirmedSlots = Vec<(Slot, Hash)>;
 pub type VerifiedVoteTransactionsSender = Sender<Vec<Transaction>>;
 pub type VerifiedVoteTransactionsReceiver = Receiver<Vec<Transaction>>;
-pub type GossipVerifiedVoteHashSender = Sender<(Pubkey, Slot, Hash)>;
-pub type GossipVerifiedVoteHashReceiver = Receiver<(Pubkey, Slot, Hash)>;
-pub type DuplicateConfirmedSlotsSender = Sender<ThresholdConfirmedSlots>;
+/// Each message carries the time the vote was verified by the vote listener.
+pub type GossipVerifiedVoteHashSender = Sender<(Pubkey, Slot, Hash, Instant)>;
+pub type GossipVerifiedVoteHashReceiver = Receiver<(Pubkey, Slot, Hash, Instant)>;
 pub type DuplicateConfirmedSlotsReceiver = Receiver<ThresholdConfirmedSlots>;
 
 const THRESHOLDS_TO_CHECK: [f64; 2] = [DUPLICATE_THRESHOLD, VOTE_THRESHOLD_SIZE];
+// Number of optimistically confirmed slots to keep around
+const MAX_RECENTLY_OPTIMISTICALLY_CONFIRMED: usize = 256;
+
+pub const DEFAULT_DUPLICATE_CONFIRMED_SLOTS_CHANNEL_CAPACITY: NonZeroUsize =
+    NonZeroUsize::new(1_024).unwrap();
+const DUPLICATE_CONFIRMED_SLOTS_REPORT_INTERVAL_MS: u64 = 2_000;
+// Number of verified votes held back while the forwarding gate is closed,
+// beyond which the oldest are dropped
+const MAX_SUPPRESSED_VOTES: usize = 4_096;
+
+/// Creates the bounded channel carrying duplicate confirmed slots from the
+/// vote listener to replay.
+pub fn duplicate_confirmed_slots_channel(
+    capacity: NonZeroUsize,
+) -> (
+    DuplicateConfirmedSlotsSender,
+    DuplicateConfirmedSlotsReceiver,
+) {
+    let (sender, receiver) = bounded(capacity.get());
+    let sender = DuplicateConfirmedSlotsSender {
+        sender,
+        pending: Mutex::default(),
+        stats: DuplicateConfirmedSlotsStats::default(),
+    };
+    (sender, receiver)
+}
+
+/// Sends duplicate confirmed slots to replay over a bounded channel.
+///
+/// If the channel is full, the slots are held back and sent as a single batch
+/// once replay frees up capacity. A later notification for a held back slot
+/// supersedes the earlier one, so distinct slots are never dropped while the
+/// backlog stays bounded by the number of distinct slots.
+pub struct DuplicateConfirmedSlotsSender {
+    sender: Sender<ThresholdConfirmedSlots>,
+    pending: Mutex<BTreeMap<Slot, Hash>>,
+    stats: DuplicateConfirmedSlotsStats,
+}
+
+#[derive(Default)]
+struct DuplicateConfirmedSlotsStats {
+    max_channel_len: AtomicUsize,
+    max_num_pending: AtomicUsize,
+    // Number of notifications held back because the channel was full.
+    num_overflows: AtomicUsize,
+    // Number of held back slots superseded by a later notification.
+    num_coalesced: AtomicUsize,
+    last_report: AtomicInterval,
+}
+
+impl DuplicateConfirmedSlotsSender {
+    /// Sends the slots to replay, holding them back if the channel is full.
+    pub fn send(&self, slots: ThresholdConfirmedSlots) {
+        let mut pending = self.pending.lock().unwrap();
+        if !pending.is_empty() {
+            // Keep the held back slots ahead of the new ones.
+            self.insert_pending(&mut pending, slots);
+            self.try_send_pending(&mut pending);
+            return;
+        }
+        match self.sender.try_send(slots) {
+            // If replay has exited there is nobody to notify.
+            Ok(()) | Err(TrySendError::Disconnected(_)) => (),
+            Err(TrySendError::Full(slots)) => {
+                self.stats.num_overflows.fetch_add(1, Ordering::Relaxed);
+                self.insert_pending(&mut pending, slots);
+            }
+        }
+        self.stats
+            .max_channel_len
+            .fetch_max(self.sender.len(), Ordering::Relaxed);
+    }
+
+    /// Sends the held back slots if the channel has capacity, and
+    /// periodically reports the channel stats.
+    pub fn flush(&self) {
+        let mut pending = self.pending.lock().unwrap();
+        if !pending.is_empty() {
+            self.try_send_pending(&mut pending);
+        }
+        if self
+            .stats
+            .last_report
+            .should_update(DUPLICATE_CONFIRMED_SLOTS_REPORT_INTERVAL_MS)
+        {
+            let stats = &self.stats;
+            datapoint_info!(
+                "duplicate_confirmed_slots_sender",
+                ("channel_len", self.sender.len(), i64),
+                (
+                    "max_channel_len",
+                    stats.max_channel_len.swap(0, Ordering::Relaxed),
+                    i64
+                ),
+                ("num_pending", pending.len(), i64),
+                (
+                    "max_num_pending",
+                    stats.max_num_pending.swap(0, Ordering::Relaxed),
+                    i64
+                ),
+                (
+                    "num_overflows",
+                    stats.num_overflows.swap(0, Ordering::Relaxed),
+                    i64
+                ),
+                (
+                    "num_coalesced",
+                    stats.num_coalesced.swap(0, Ordering::Relaxed),
+                    i64
+                ),
+            );
+        }
+    }
+
+    fn insert_pending(&self, pending: &mut BTreeMap<Slot, Hash>, slots: ThresholdConfirmedSlots) {
+        for (slot, hash) in slots {
+            if pending.insert(slot, hash).is_some() {
+                self.stats.num_coalesced.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.stats
+            .max_num_pending
+            .fetch_max(pending.len(), Ordering::Relaxed);
+    }
+
+    fn try_send_pending(&self, pending: &mut BTreeMap<Slot, Hash>) {
+        let slots = std::mem::take(pending).into_iter().collect();
+        match self.sender.try_send(slots) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => (),
+            Err(TrySendError::Full(slots)) => pending.extend(slots),
+        }
+    }
+}
+
+/// Time verified votes spend in a channel before being consumed, i.e. between
+/// the vote being verified and the consumer receiving it.
+#[derive(Default)]
+pub struct VerifiedVoteLatencyStats {
+    latency_us_hist: Histogram,
+}
+
+impl VerifiedVoteLatencyStats {
+    pub fn record(&mut self, observed_at: Instant) {
+        let latency_us = observed_at.elapsed().as_micros() as u64;
+        let _ = self.latency_us_hist.increment(latency_us);
+    }
+
+    pub fn num_votes(&self) -> u64 {
+        self.latency_us_hist.entries()
+    }
+
+    pub fn max_latency_us(&self) -> u64 {
+        self.latency_us_hist.maximum().unwrap_or(0)
+    }
+
+    /// Submits the latencies recorded since the last report, and clears them.
+    pub fn report(&mut self, name: &'static str) {
+        if self.num_votes() == 0 {
+            return;
+        }
+        let hist = &self.latency_us_hist;
+        datapoint_info!(
+            name,
+            ("num_votes", hist.entries(), i64),
+            ("latency_us_90pct", hist.percentile(90.0).unwrap_or(0), i64),
+            ("latency_us_mean", hist.mean().unwrap_or(0), i64),
+            ("latency_us_min", hist.minimum().unwrap_or(0), i64),
+            ("latency_us_max", hist.maximum().unwrap_or(0), i64),
+        );
+        self.latency_us_hist.clear();
+    }
+}
 
 /// Notification channels and context threaded through the vote confirmation
 /// pipeline. Groups the senders used to communicate threshold crossings
 /// (duplicate confirmation, optimistic confirmation, gossip verified votes,
-/// etc.) together with the migration status that gates some notifications.
+/// etc.) together with the migration status that gates some notifications,
+/// and the slots the votes are recorded for.
 struct ConfirmationNotifiers {
     gossip_verified_vote_hash_sender: GossipVerifiedVoteHashSender,
     verified_voter_slots_sender: VerifiedVoterSlotsSender,
@@ -74,6 +263,28 @@ struct ConfirmationNotifiers {
     bank_notification_sender: Option<BankNotificationSenderConfig>,
     duplicate_confirmed_slot_sender: Option<DuplicateConfirmedSlotsSender>,
     migration_status: Arc<MigrationStatus>,
+    vote_capture: Option<Arc<VoteCapture>>,
+    violation_log: Option<Arc<OptimisticConfirmationViolationLog>>,
+    slot_gap_classifier: Option<Arc<SlotGapClassifier>>,
+    vote_account_monitor: Option<Arc<VoteAccountMonitor>>,
+    restart_latency_tracker: Option<Arc<RestartLatencyTracker>>,
+    vote_tracker_archiver: Option<VoteTrackerArchiver>,
+    gossip_vote_landing_tracker: Option<Arc<GossipVoteLandingTracker>>,
+    /// Only the votes for these slots are captured, archived and tracked for
+    /// the propagated check, unless the set is empty. Optimistic confirmation
+    /// and notifications are not affected.
+    slot_filter: Option<Arc<RwLock<HashSet<Slot>>>>,
+}
+
+impl ConfirmationNotifiers {
+    /// Read locks the slots the votes are recorded for, or returns `None` if
+    /// the votes for every slot are recorded
+    fn slot_filter(&self) -> Option<RwLockReadGuard<'_, HashSet<Slot>>> {
+        self.slot_filter
+            .as_ref()
+            .map(|slot_filter| slot_filter.read().unwrap())
+            .filter(|slot_filter| !slot_filter.is_empty())
+    }
 }
 
 #[derive(Default)]
@@ -85,6 +296,12 @@ pub struct SlotVoteTracker {
     optimistic_votes_tracker: HashMap<Hash, VoteStakeTracker>,
     voted_slot_updates: Option<Vec<Pubkey>>,
     gossip_only_stake: u64,
+    // Milliseconds since the UNIX epoch at which the first vote for this slot
+    // was seen
+    first_vote_timestamp: Option<u64>,
+    // Milliseconds since the UNIX epoch at which this slot was optimistically
+    // confirmed
+    optimistic_confirmation_timestamp: Option<u64>,
 }
 
 impl SlotVoteTracker {
@@ -98,12 +315,73 @@ impl SlotVoteTracker {
     pub(crate) fn optimistic_votes_tracker(&self, hash: &Hash) -> Option<&VoteStakeTracker> {
         self.optimistic_votes_tracker.get(hash)
     }
+
+    /// Vote pubkeys that have voted for this slot, on gossip or in replay
+    pub(crate) fn voters(&self) -> impl Iterator<Item = &Pubkey> {
+        self.voted.keys()
+    }
+
+    pub(crate) fn num_voters(&self) -> usize {
+        self.voted.len()
+    }
+
+    /// Stake of the voters for this slot only seen on gossip
+    pub(crate) fn gossip_only_stake(&self) -> u64 {
+        self.gossip_only_stake
+    }
+
+    /// Milliseconds from the first vote seen for this slot to its optimistic
+    /// confirmation, None if not optimistically confirmed
+    pub(crate) fn confirmation_latency_ms(&self) -> Option<u32> {
+        let first_vote_timestamp = self.first_vote_timestamp?;
+        let latency = self
+            .optimistic_confirmation_timestamp?
+            .saturating_sub(first_vote_timestamp);
+        // u32::MAX stands for unconfirmed slots in the vote tracker archive
+        Some(u32::try_from(latency).unwrap_or(u32::MAX - 1))
+    }
+
+    fn on_vote_seen(&mut self) {
+        self.first_vote_timestamp.get_or_insert_with(timestamp);
+    }
+}
+
+/// The root bank the vote tracker last progressed with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct TrackedRoot {
+    slot: Slot,
+    hash: Hash,
+    epoch: Epoch,
+}
+
+impl TrackedRoot {
+    fn new(root_bank: &Bank) -> Self {
+        Self {
+            slot: root_bank.slot(),
+            hash: root_bank.hash(),
+            epoch: root_bank.epoch(),
+        }
+    }
+
+    /// Whether `new_root` does not descend from `self`, i.e. the root moved
+    /// backwards or was replaced by a different bank at the same slot, as
+    /// may happen during wen_restart or a hard fork recovery
+    fn is_regressed_by(&self, new_root: &Self) -> bool {
+        new_root.slot < self.slot
+            || new_root.epoch < self.epoch
+            || (new_root.slot == self.slot && new_root.hash != self.hash)
+    }
 }
 
 #[derive(Default)]
 pub struct VoteTracker {
     // Map from a slot to a set of validators who have voted for that slot
     slot_vote_trackers: RwLock<HashMap<Slot, Arc<RwLock<SlotVoteTracker>>>>,
+    // The most recent slots which crossed the optimistic confirmation
+    // threshold, oldest first
+    recently_optimistically_confirmed: Mutex<VecDeque<(Slot, Hash)>>,
+    // The root bank passed to the last `progress_with_new_root_bank()`
+    root: Mutex<Option<TrackedRoot>>,
 }
 
 impl VoteTracker {
@@ -135,6 +413,25 @@ impl VoteTracker {
         }
     }
 
+    fn record_optimistic_confirmation(&self, slot: Slot, hash: Hash) {
+        let mut confirmed = self.recently_optimistically_confirmed.lock().unwrap();
+        if confirmed.len() == MAX_RECENTLY_OPTIMISTICALLY_CONFIRMED {
+            confirmed.pop_front();
+        }
+        confirmed.push_back((slot, hash));
+    }
+
+    /// Returns the most recent slots which crossed the optimistic
+    /// confirmation threshold, oldest first
+    pub fn recently_optimistically_confirmed(&self) -> Vec<(Slot, Hash)> {
+        self.recently_optimistically_confirmed
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+
     fn purge_stale_state(&self, root_bank: &Bank) {
         // Purge any outdated slot data
         let new_root = root_bank.slot();
@@ -144,8 +441,78 @@ impl VoteTracker {
             .retain(|slot, _| *slot >= new_root);
     }
 
-    fn progress_with_new_root_bank(&self, root_bank: &Bank) {
+    /// Returns the slots rooted since the root the tracker last progressed
+    /// with, up to and including `root_bank`, in increasing order
+    fn new_roots(&self, root_bank: &Bank, blockstore: &Blockstore) -> Vec<Slot> {
+        let root = root_bank.slot();
+        let Some(previous_root) = self.root.lock().unwrap().map(|root| root.slot) else {
+            return vec![root];
+        };
+        if root <= previous_root {
+            return vec![];
+        }
+        // The ancestors of the root bank miss the roots before it was created
+        // from its parent, e.g. if it was loaded from a snapshot
+        let mut new_roots: BTreeSet<_> = root_bank
+            .ancestors
+            .keys()
+            .into_iter()
+            .filter(|slot| *slot > previous_root)
+            .collect();
+        match blockstore.rooted_slot_iterator(previous_root + 1) {
+            Ok(rooted_slots) => new_roots.extend(rooted_slots.take_while(|slot| *slot < root)),
+            Err(err) => warn!("Failed to read the roots after slot {previous_root}: {err}"),
+        }
+        new_roots.into_iter().collect()
+    }
+
+    /// Whether `root_bank` regressed the root the tracker last progressed
+    /// with
+    fn is_root_regression(&self, root_bank: &Bank) -> bool {
+        self.root
+            .lock()
+            .unwrap()
+            .is_some_and(|root| root.is_regressed_by(&TrackedRoot::new(root_bank)))
+    }
+
+    /// Returns true if `root_bank` regressed the previous root, in which case
+    /// the state tracked on top of the previous root is dropped
+    fn progress_with_new_root_bank(&self, root_bank: &Bank) -> bool {
+        let new_root = TrackedRoot::new(root_bank);
+        let previous_root = self.root.lock().unwrap().replace(new_root);
+        let regressed_root = previous_root.filter(|root| root.is_regressed_by(&new_root));
+        if let Some(previous_root) = regressed_root {
+            self.reset_for_root_regression(&previous_root, &new_root);
+        }
         self.purge_stale_state(root_bank);
+        regressed_root.is_some()
+    }
+
+    fn reset_for_root_regression(&self, previous_root: &TrackedRoot, new_root: &TrackedRoot) {
+        // The trackers above the new root were built on the previous fork,
+        // with the stakes of its root bank
+        let num_trackers_cleared = {
+            let mut slot_vote_trackers = self.slot_vote_trackers.write().unwrap();
+            let num_trackers = slot_vote_trackers.len();
+            slot_vote_trackers.retain(|slot, _| *slot <= new_root.slot);
+            num_trackers - slot_vote_trackers.len()
+        };
+        self.recently_optimistically_confirmed
+            .lock()
+            .unwrap()
+            .retain(|(slot, _)| *slot <= new_root.slot);
+        warn!(
+            "Vote listener root regressed from {previous_root:?} to {new_root:?}, cleared \
+             {num_trackers_cleared} slot vote trackers"
+        );
+        datapoint_warn!(
+            "cluster_info_vote_listener-root_regression",
+            ("previous_root", previous_root.slot, i64),
+            ("previous_epoch", previous_root.epoch, i64),
+            ("new_root", new_root.slot, i64),
+            ("new_epoch", new_root.epoch, i64),
+            ("num_trackers_cleared", num_trackers_cleared, i64),
+        );
     }
 }
 
@@ -409,8 +776,172 @@ impl VoteBuffer {
     }
 }
 
+/// How the gossip votes are batched for signature verification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoteVerifyBatchConfig {
+    /// Votes are held back until at least this many are pending
+    pub batch_size: NonZeroUsize,
+    /// Pending votes are verified once the oldest has waited this long, even
+    /// if there are fewer than `batch_size` of them
+    pub max_latency: Duration,
+}
+
+impl Default for VoteVerifyBatchConfig {
+    /// Verifies the votes as soon as gossip returns them
+    fn default() -> Self {
+        Self {
+            batch_size: NonZeroUsize::MIN,
+            max_latency: Duration::ZERO,
+        }
+    }
+}
+
+/// Decides whether the verified gossip votes are forwarded to banking, where
+/// they are only of use while this node is about to produce blocks. The votes
+/// are processed for confirmation either way, and the ones held back are
+/// forwarded once the gate opens.
+#[derive(Clone)]
+pub struct VoteForwardingGate {
+    /// Votes are forwarded while the leader slots of this node start within
+    /// this many slots, or are underway. Always forwarded if None.
+    leader_window_slots: Option<u64>,
+    shared_leader_state: SharedLeaderState,
+    ticks_per_slot: u64,
+}
+
+impl VoteForwardingGate {
+    pub fn new(
+        leader_window_slots: Option<u64>,
+        shared_leader_state: SharedLeaderState,
+        ticks_per_slot: u64,
+    ) -> Self {
+        Self {
+            leader_window_slots,
+            shared_leader_state,
+            ticks_per_slot,
+        }
+    }
+
+    fn should_forward(&self) -> bool {
+        let Some(leader_window_slots) = self.leader_window_slots else {
+            return true;
+        };
+        let leader_state = self.shared_leader_state.load();
+        leader_state.working_bank().is_some()
+            || LeaderWindow::new(&leader_state, self.ticks_per_slot).is_within(leader_window_slots)
+    }
+}
+
+/// Verified votes held back from banking while the forwarding gate is closed,
+/// one vote per packet batch. Only the newest `MAX_SUPPRESSED_VOTES` are kept.
+#[derive(Default)]
+struct SuppressedVotes {
+    packet_batches: VecDeque<PacketBatch>,
+}
+
+impl SuppressedVotes {
+    /// Holds back `packet_batches`, returning the number of the oldest votes
+    /// dropped to make room for them
+    fn push(&mut self, packet_batches: Vec<PacketBatch>) -> usize {
+        self.packet_batches.extend(packet_batches);
+        let num_dropped = self
+            .packet_batches
+            .len()
+            .saturating_sub(MAX_SUPPRESSED_VOTES);
+        self.packet_batches.drain(..num_dropped);
+        num_dropped
+    }
+
+    fn take(&mut self) -> Vec<PacketBatch> {
+        self.packet_batches.drain(..).collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.packet_batches.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct RecvLoopStats {
+    received_count: usize,
+    verify_batch_count: usize,
+    banking_channel_max_len: usize,
+    banking_channel_eviction_drops: usize,
+    // Verified votes not forwarded to banking as this node is not about to
+    // be leader
+    suppressed_forward_count: usize,
+    // Suppressed votes forwarded to banking once the gate opened
+    delayed_forward_count: usize,
+    // Suppressed votes dropped to make room for newer ones
+    suppressed_forward_drop_count: usize,
+    // Votes dropped as pushed longer than the max gossip vote age ago
+    stale_drop_count: usize,
+}
+
+/// Drops the votes pushed to gossip more than `max_age_ms` before `now`,
+/// returning the others in order
+fn drop_stale_votes(
+    wallclocks: Vec<u64>,
+    votes: Vec<Transaction>,
+    max_age_ms: u64,
+    now: u64,
+) -> Vec<Transaction> {
+    wallclocks
+        .into_iter()
+        .zip(votes)
+        .filter(|(wallclock, _)| now.saturating_sub(*wallclock) <= max_age_ms)
+        .map(|(_, vote)| vote)
+        .collect()
+}
+
+/// Gossip votes pending signature verification
+#[derive(Default)]
+struct VoteVerifyBatch {
+    votes: Vec<Transaction>,
+    // Time at which the oldest pending vote was received
+    oldest: Option<Instant>,
+}
+
+impl VoteVerifyBatch {
+    fn push(&mut self, votes: Vec<Transaction>, now: Instant) {
+        if votes.is_empty() {
+            return;
+        }
+        self.oldest.get_or_insert(now);
+        self.votes.extend(votes);
+    }
+
+    /// Returns the time left until the pending votes are due for
+    /// verification, None if there are none
+    fn time_to_flush(&self, config: &VoteVerifyBatchConfig, now: Instant) -> Option<Duration> {
+        let oldest = self.oldest?;
+        if self.votes.len() >= config.batch_size.get() {
+            return Some(Duration::ZERO);
+        }
+        Some(
+            config
+                .max_latency
+                .saturating_sub(now.saturating_duration_since(oldest)),
+        )
+    }
+
+    /// Takes the pending votes if they are due for verification
+    fn take_ready(
+        &mut self,
+        config: &VoteVerifyBatchConfig,
+        now: Instant,
+    ) -> Option<Vec<Transaction>> {
+        if self.time_to_flush(config, now)? > Duration::ZERO {
+            return None;
+        }
+        self.oldest = None;
+        Some(std::mem::take(&mut self.votes))
+    }
+}
+
 pub struct ClusterInfoVoteListener {
     thread_hdls: Vec<JoinHandle<()>>,
+    vote_tracker: Arc<VoteTracker>,
 }
 
 impl ClusterInfoVoteListener {
@@ -429,7 +960,20 @@ impl ClusterInfoVoteListener {
         blockstore: Arc<Blockstore>,
         bank_notification_sender: Option<BankNotificationSenderConfig>,
         duplicate_confirmed_slot_sender: DuplicateConfirmedSlotsSender,
+        vote_capture: Option<Arc<VoteCapture>>,
+        violation_log: Arc<OptimisticConfirmationViolationLog>,
+        slot_gap_classifier: Arc<SlotGapClassifier>,
+        vote_account_monitor: Arc<VoteAccountMonitor>,
+        restart_latency_tracker: Arc<RestartLatencyTracker>,
+        vote_tracker_archiver: Option<VoteTrackerArchiver>,
+        gossip_vote_landing_tracker: Arc<GossipVoteLandingTracker>,
+        vote_verify_batch_config: VoteVerifyBatchConfig,
+        vote_forwarding_gate: VoteForwardingGate,
+        max_gossip_vote_age_ms: Option<u64>,
+        slot_filter: Option<Arc<RwLock<HashSet<Slot>>>>,
+        watchdog: Arc<Watchdog>,
     ) -> Self {
+        let vote_tracker_for_queries = vote_tracker.clone();
         let (verified_vote_transactions_sender, verified_vote_transactions_receiver) = unbounded();
         let listen_thread = {
             let exit = exit.clone();
@@ -444,6 +988,9 @@ impl ClusterInfoVoteListener {
                         sharable_banks,
                         verified_packets_sender,
                         verified_vote_transactions_sender,
+                        vote_verify_batch_config,
+                        vote_forwarding_gate,
+                        max_gossip_vote_age_ms,
                     );
                 })
                 .unwrap()
@@ -461,6 +1008,14 @@ impl ClusterInfoVoteListener {
                     bank_notification_sender,
                     duplicate_confirmed_slot_sender: Some(duplicate_confirmed_slot_sender),
                     migration_status,
+                    vote_capture,
+                    violation_log: Some(violation_log),
+                    slot_gap_classifier: Some(slot_gap_classifier),
+                    vote_account_monitor: Some(vote_account_monitor),
+                    restart_latency_tracker: Some(restart_latency_tracker),
+                    vote_tracker_archiver,
+                    gossip_vote_landing_tracker: Some(gossip_vote_landing_tracker),
+                    slot_filter,
                 };
                 let _ = Self::process_votes_loop(
                     exit,
@@ -470,15 +1025,23 @@ impl ClusterInfoVoteListener {
                     replay_votes_receiver,
                     blockstore,
                     notifiers,
+                    &watchdog,
                 );
             })
             .unwrap();
 
         Self {
             thread_hdls: vec![listen_thread, process_thread],
+            vote_tracker: vote_tracker_for_queries,
         }
     }
 
+    /// Returns the most recent slots, and their hashes, which crossed the
+    /// optimistic confirmation threshold, oldest first
+    pub fn recently_optimistically_confirmed(&self) -> Vec<(Slot, Hash)> {
+        self.vote_tracker.recently_optimistically_confirmed()
+    }
+
     pub(crate) fn join(self) -> thread::Result<()> {
         self.thread_hdls.into_iter().try_for_each(JoinHandle::join)
     }
@@ -490,35 +1053,57 @@ impl ClusterInfoVoteListener {
         sharable_banks: SharableBanks,
         verified_packets_sender: BankingPacketSender,
         verified_vote_transactions_sender: VerifiedVoteTransactionsSender,
+        vote_verify_batch_config: VoteVerifyBatchConfig,
+        vote_forwarding_gate: VoteForwardingGate,
+        max_gossip_vote_age_ms: Option<u64>,
     ) -> Result<()> {
-        #[derive(Default)]
-        struct Stats {
-            received_count: usize,
-            banking_channel_max_len: usize,
-            banking_channel_eviction_drops: usize,
-        }
         const STATS_REPORT_INTERVAL: Duration = Duration::from_secs(1);
         let mut cursor = Cursor::default();
         let mut last_report = Instant::now();
-        let mut stats = Stats::default();
+        let mut stats = RecvLoopStats::default();
+        let mut batch = VoteVerifyBatch::default();
+        let mut suppressed_votes = SuppressedVotes::default();
         while !exit.load(Ordering::Relaxed) {
-            let votes = cluster_info.get_votes(&mut cursor);
-            if !votes.is_empty() {
-                stats.received_count += votes.len();
+            let (votes, num_stale) = match max_gossip_vote_age_ms {
+                None => (cluster_info.get_votes(&mut cursor), 0),
+                Some(max_age_ms) => {
+                    let (wallclocks, votes) = cluster_info.get_votes_with_wallclocks(&mut cursor);
+                    let num_votes = votes.len();
+                    let votes = drop_stale_votes(wallclocks, votes, max_age_ms, timestamp());
+                    let num_stale = num_votes - votes.len();
+                    (votes, num_stale)
+                }
+            };
+            stats.received_count += votes.len() + num_stale;
+            stats.stale_drop_count += num_stale;
+            batch.push(votes, Instant::now());
+            if let Some(votes) = batch.take_ready(&vote_verify_batch_config, Instant::now()) {
+                stats.verify_batch_count += 1;
                 let (vote_txs, packets) =
                     Self::verify_votes(votes, &mut gossip_sigverify_handle, &sharable_banks)?;
-                verified_vote_transactions_sender.send(vote_txs)?;
-                // Sample backlog before the push.
-                stats.banking_channel_max_len = stats
-                    .banking_channel_max_len
-                    .max(verified_packets_sender.len());
-                stats.banking_channel_eviction_drops +=
-                    verified_packets_sender.send(BankingPacketBatch::new(packets))?;
+                Self::send_verified_votes(
+                    vote_txs,
+                    packets,
+                    &vote_forwarding_gate,
+                    &mut suppressed_votes,
+                    &verified_vote_transactions_sender,
+                    &verified_packets_sender,
+                    &mut stats,
+                )?;
+            } else if !suppressed_votes.is_empty() {
+                Self::forward_votes(
+                    vec![],
+                    &vote_forwarding_gate,
+                    &mut suppressed_votes,
+                    &verified_packets_sender,
+                    &mut stats,
+                )?;
             }
             if last_report.elapsed() >= STATS_REPORT_INTERVAL {
                 datapoint_info!(
                     "cluster_info_vote_listener",
                     ("received_count", stats.received_count as i64, i64),
+                    ("verify_batch_count", stats.verify_batch_count as i64, i64),
                     (
                         "banking_channel_max_len",
                         stats.banking_channel_max_len as i64,
@@ -529,12 +1114,88 @@ impl ClusterInfoVoteListener {
                         stats.banking_channel_eviction_drops as i64,
                         i64
                     ),
+                    (
+                        "suppressed_forward_count",
+                        stats.suppressed_forward_count as i64,
+                        i64
+                    ),
+                    (
+                        "delayed_forward_count",
+                        stats.delayed_forward_count as i64,
+                        i64
+                    ),
+                    (
+                        "suppressed_forward_drop_count",
+                        stats.suppressed_forward_drop_count as i64,
+                        i64
+                    ),
+                    ("stale_drop_count", stats.stale_drop_count as i64, i64),
                 );
-                stats = Stats::default();
+                stats = RecvLoopStats::default();
                 last_report = Instant::now();
             }
-            sleep(Duration::from_millis(GOSSIP_SLEEP_MILLIS));
+            // Wake up early if the pending votes are due before the next poll
+            let sleep_duration = batch
+                .time_to_flush(&vote_verify_batch_config, Instant::now())
+                .unwrap_or(Duration::MAX)
+                .min(Duration::from_millis(GOSSIP_SLEEP_MILLIS));
+            sleep(sleep_duration);
+        }
+        Ok(())
+    }
+
+    /// Hands the verified votes to the vote processing, and to banking if the
+    /// gate lets them through
+    fn send_verified_votes(
+        vote_txs: Vec<Transaction>,
+        packets: Vec<PacketBatch>,
+        vote_forwarding_gate: &VoteForwardingGate,
+        suppressed_votes: &mut SuppressedVotes,
+        verified_vote_transactions_sender: &VerifiedVoteTransactionsSender,
+        verified_packets_sender: &BankingPacketSender,
+        stats: &mut RecvLoopStats,
+    ) -> Result<()> {
+        verified_vote_transactions_sender.send(vote_txs)?;
+        Self::forward_votes(
+            packets,
+            vote_forwarding_gate,
+            suppressed_votes,
+            verified_packets_sender,
+            stats,
+        )
+    }
+
+    /// Forwards the verified votes to banking, after the votes held back so
+    /// far, if the gate lets them through, or holds them back otherwise
+    fn forward_votes(
+        packets: Vec<PacketBatch>,
+        vote_forwarding_gate: &VoteForwardingGate,
+        suppressed_votes: &mut SuppressedVotes,
+        verified_packets_sender: &BankingPacketSender,
+        stats: &mut RecvLoopStats,
+    ) -> Result<()> {
+        if !vote_forwarding_gate.should_forward() {
+            stats.suppressed_forward_count += packets.len();
+            stats.suppressed_forward_drop_count += suppressed_votes.push(packets);
+            return Ok(());
+        }
+        let packets = if suppressed_votes.is_empty() {
+            packets
+        } else {
+            let mut suppressed_packets = suppressed_votes.take();
+            stats.delayed_forward_count += suppressed_packets.len();
+            suppressed_packets.extend(packets);
+            suppressed_packets
+        };
+        if packets.is_empty() {
+            return Ok(());
         }
+        // Sample backlog before the push.
+        stats.banking_channel_max_len = stats
+            .banking_channel_max_len
+            .max(verified_packets_sender.len());
+        stats.banking_channel_eviction_drops +=
+            verified_packets_sender.send(BankingPacketBatch::new(packets))?;
         Ok(())
     }
 
@@ -592,6 +1253,7 @@ impl ClusterInfoVoteListener {
         replay_votes_receiver: ReplayVoteReceiver,
         blockstore: Arc<Blockstore>,
         notifiers: ConfirmationNotifiers,
+        watchdog: &Watchdog,
     ) -> Result<()> {
         let mut confirmation_verifier =
             OptimisticConfirmationVerifier::new(sharable_banks.root().slot());
@@ -599,24 +1261,71 @@ impl ClusterInfoVoteListener {
         let mut last_process_root = Instant::now();
         let mut vote_processing_time = Some(VoteProcessingTiming::default());
         let mut replay_vote_buffer = VoteBuffer::new();
+        let heartbeat = watchdog.register("ClusterInfoVoteListener", Duration::from_secs(60));
         loop {
+            heartbeat.beat();
             if exit.load(Ordering::Relaxed) {
                 return Ok(());
             }
+            if let Some(sender) = &notifiers.duplicate_confirmed_slot_sender {
+                sender.flush();
+            }
 
             let root_bank = sharable_banks.root();
-            if last_process_root.elapsed().as_nanos() > root_bank.ns_per_slot {
+            // A regressed root is handled right away, the votes are otherwise
+            // tracked on top of the previous fork until the next root update
+            if vote_tracker.is_root_regression(&root_bank)
+                || last_process_root.elapsed().as_nanos() > root_bank.ns_per_slot
+            {
                 let unrooted_optimistic_slots = confirmation_verifier
                     .verify_for_unrooted_optimistic_slots(&root_bank, &blockstore);
                 // SlotVoteTracker's for all `slots` in `unrooted_optimistic_slots`
                 // should still be available because we haven't purged in
                 // `progress_with_new_root_bank()` yet, which is called below
-                OptimisticConfirmationVerifier::log_unrooted_optimistic_slots(
+                let violations = OptimisticConfirmationVerifier::log_unrooted_optimistic_slots(
                     &root_bank,
                     &vote_tracker,
                     &unrooted_optimistic_slots,
                 );
-                vote_tracker.progress_with_new_root_bank(&root_bank);
+                if let Some(violation_log) = &notifiers.violation_log {
+                    violation_log.record(violations);
+                    violation_log.on_new_root(root_bank.epoch());
+                }
+                if let Some(vote_capture) = &notifiers.vote_capture {
+                    if !unrooted_optimistic_slots.is_empty() {
+                        let reason = format!(
+                            "optimistically confirmed slots not rooted: \
+                             {unrooted_optimistic_slots:?}"
+                        );
+                        if let Err(err) = vote_capture.freeze_vote_capture_in_background(reason) {
+                            warn!("Failed to spawn the vote capture writer: {err}");
+                        }
+                    }
+                }
+                if let Some(slot_gap_classifier) = &notifiers.slot_gap_classifier {
+                    slot_gap_classifier.on_new_root(&root_bank, &blockstore, &vote_tracker);
+                }
+                if let Some(vote_account_monitor) = &notifiers.vote_account_monitor {
+                    vote_account_monitor.on_new_root(&root_bank);
+                }
+                if let Some(vote_tracker_archiver) = &notifiers.vote_tracker_archiver {
+                    vote_tracker_archiver.on_new_root(
+                        &root_bank,
+                        &vote_tracker.new_roots(&root_bank, &blockstore),
+                        &vote_tracker,
+                        notifiers.slot_filter().as_deref(),
+                    );
+                }
+                if let Some(gossip_vote_landing_tracker) = &notifiers.gossip_vote_landing_tracker {
+                    gossip_vote_landing_tracker.on_new_root(&root_bank);
+                }
+                if vote_tracker.progress_with_new_root_bank(&root_bank) {
+                    // The latest votes and optimistic slots were on the
+                    // previous fork
+                    latest_vote_slot_per_validator.clear();
+                    replay_vote_buffer = VoteBuffer::new();
+                    confirmation_verifier = OptimisticConfirmationVerifier::new(root_bank.slot());
+                }
                 replay_vote_buffer.prune_stale_slots(root_bank.slot());
                 last_process_root = Instant::now();
             }
@@ -742,6 +1451,7 @@ impl ClusterInfoVoteListener {
                 *vote_pubkey,
                 last_vote_slot,
                 last_vote_hash,
+                Instant::now(),
             ));
         }
 
@@ -750,12 +1460,13 @@ impl ClusterInfoVoteListener {
 
         if reached_duplicate_confirmed {
             if let Some(ref sender) = notifiers.duplicate_confirmed_slot_sender {
-                let _ = sender.send(vec![(last_vote_slot, last_vote_hash)]);
+                sender.send(vec![(last_vote_slot, last_vote_hash)]);
             }
         }
 
         if reached_optimistic_confirmed {
             new_optimistic_confirmed_slots.push((last_vote_slot, last_vote_hash));
+            vote_tracker.record_optimistic_confirmation(last_vote_slot, last_vote_hash);
             if let Some(ref sender) = notifiers.bank_notification_sender {
                 if notifiers
                     .migration_status
@@ -791,6 +1502,7 @@ impl ClusterInfoVoteListener {
         new_optimistic_confirmed_slots: &mut ThresholdConfirmedSlots,
         is_gossip_vote: bool,
         latest_vote_slot_per_validator: &mut HashMap<Pubkey, Slot>,
+        slot_filter: Option<&HashSet<Slot>>,
     ) {
         if vote.is_empty() {
             return;
@@ -805,6 +1517,37 @@ impl ClusterInfoVoteListener {
         let root = root_bank.slot();
         let vote_slots = vote.slots();
 
+        let is_recorded = slot_filter
+            .is_none_or(|slot_filter| vote_slots.iter().any(|slot| slot_filter.contains(slot)));
+        if let Some(vote_capture) = notifiers.vote_capture.as_ref().filter(|_| is_recorded) {
+            let epoch = root_bank.epoch_schedule().get_epoch(last_vote_slot);
+            let stake = root_bank
+                .epoch_stakes(epoch)
+                .map(|epoch_stakes| {
+                    epoch_stakes
+                        .stakes()
+                        .vote_accounts()
+                        .get_delegated_stake(vote_pubkey)
+                })
+                .unwrap_or_default();
+            vote_capture.record(CapturedVote {
+                timestamp: timestamp(),
+                vote_pubkey: *vote_pubkey,
+                slots: vote_slots.clone(),
+                hash: last_vote_hash,
+                source: if is_gossip_vote {
+                    VoteSource::Gossip
+                } else {
+                    VoteSource::Replay
+                },
+                stake,
+            });
+        }
+
+        if let Some(gossip_vote_landing_tracker) = &notifiers.gossip_vote_landing_tracker {
+            gossip_vote_landing_tracker.record_vote(vote_pubkey, last_vote_slot, is_gossip_vote);
+        }
+
         let is_new_vote = Self::process_last_vote_for_optimistic_confirmation(
             vote_tracker,
             last_vote_slot,
@@ -832,7 +1575,11 @@ impl ClusterInfoVoteListener {
         // Track all vote slots for propagated check (iterates from most recent to oldest)
         for slot in vote_slots
             .iter()
-            .filter(|slot| **slot > root && **slot >= *latest_vote_slot)
+            .filter(|slot| {
+                **slot > root
+                    && **slot >= *latest_vote_slot
+                    && slot_filter.is_none_or(|slot_filter| slot_filter.contains(*slot))
+            })
             .rev()
         {
             let slot = *slot;
@@ -858,9 +1605,11 @@ impl ClusterInfoVoteListener {
             if let Some(ref rpc_subscriptions) = notifiers.rpc_subscriptions {
                 rpc_subscriptions.notify_vote(*vote_pubkey, vote, vote_transaction_signature);
             }
-            let _ = notifiers
-                .verified_voter_slots_sender
-                .send((*vote_pubkey, vote_slots));
+            let _ = notifiers.verified_voter_slots_sender.send((
+                *vote_pubkey,
+                vote_slots,
+                Instant::now(),
+            ));
         }
     }
 
@@ -875,6 +1624,7 @@ impl ClusterInfoVoteListener {
     ) -> ThresholdConfirmedSlots {
         let mut diff: HashMap<Slot, HashMap<Pubkey, bool>> = HashMap::new();
         let mut new_optimistic_confirmed_slots = vec![];
+        let slot_filter = notifiers.slot_filter();
 
         // Process votes from gossip and ReplayStage
         let mut gossip_vote_txn_processing_time = Measure::start("gossip_vote_processing_time");
@@ -884,6 +1634,13 @@ impl ClusterInfoVoteListener {
             .zip(repeat(/*is_gossip:*/ true))
             .chain(replayed_votes.into_iter().zip(repeat(/*is_gossip:*/ false)));
         for ((vote_pubkey, vote, _switch_proof, signature), is_gossip) in votes {
+            if !is_gossip {
+                if let (Some(restart_latency_tracker), Some(vote_slot)) =
+                    (&notifiers.restart_latency_tracker, vote.last_voted_slot())
+                {
+                    restart_latency_tracker.on_replayed_vote(&vote_pubkey, vote_slot);
+                }
+            }
             Self::track_new_votes_and_notify_confirmations(
                 vote,
                 &vote_pubkey,
@@ -895,6 +1652,7 @@ impl ClusterInfoVoteListener {
                 &mut new_optimistic_confirmed_slots,
                 is_gossip,
                 latest_vote_slot_per_validator,
+                slot_filter.as_deref(),
             );
         }
         gossip_vote_txn_processing_time.stop();
@@ -918,6 +1676,7 @@ impl ClusterInfoVoteListener {
                 });
             }
             let mut w_slot_tracker = slot_tracker.write().unwrap();
+            w_slot_tracker.on_vote_seen();
             if w_slot_tracker.voted_slot_updates.is_none() {
                 w_slot_tracker.voted_slot_updates = Some(vec![]);
             }
@@ -974,10 +1733,17 @@ impl ClusterInfoVoteListener {
         let slot_tracker = vote_tracker.get_or_insert_slot_tracker(slot);
         // Insert vote and check for optimistic confirmation
         let mut w_slot_tracker = slot_tracker.write().unwrap();
+        w_slot_tracker.on_vote_seen();
 
-        w_slot_tracker
+        let (reached_threshold_results, is_new) = w_slot_tracker
             .get_or_insert_optimistic_votes_tracker(hash)
-            .add_vote_pubkey(pubkey, stake, total_epoch_stake, &THRESHOLDS_TO_CHECK)
+            .add_vote_pubkey(pubkey, stake, total_epoch_stake, &THRESHOLDS_TO_CHECK);
+        if reached_threshold_results[1] {
+            w_slot_tracker
+                .optimistic_confirmation_timestamp
+                .get_or_insert_with(timestamp);
+        }
+        (reached_threshold_results, is_new)
     }
 
     fn sum_stake(sum: &mut u64, epoch_stakes: Option<&VersionedEpochStakes>, pubkey: &Pubkey) {
@@ -991,13 +1757,13 @@ impl ClusterInfoVoteListener {
 mod tests {
     use {
         super::*,
-        crate::sigverify::GossipVerifiedVoteBatch,
-        crossbeam_channel::bounded,
+        crate::{banking_trace::BankingTracer, sigverify::GossipVerifiedVoteBatch},
         itertools::Itertools,
         solana_hash::Hash,
         solana_keypair::Keypair,
         solana_leader_schedule::SlotLeader,
         solana_perf::{packet, sigverify},
+        solana_poh::poh_recorder::LeaderState,
         solana_pubkey::Pubkey,
         solana_rpc::optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
         solana_runtime::{
@@ -1015,8 +1781,10 @@ mod tests {
         std::{
             collections::BTreeSet,
             iter::repeat_with,
+            num::NonZeroUsize,
             sync::{Arc, atomic::AtomicU64},
         },
+        tempfile::TempDir,
     };
 
     fn pre_send_for_tests(
@@ -1189,6 +1957,14 @@ mod tests {
             bank_notification_sender: None,
             duplicate_confirmed_slot_sender: None,
             migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
         };
         let mut replay_vote_buffer = VoteBuffer::new();
         ClusterInfoVoteListener::listen_and_confirm_votes(
@@ -1309,8 +2085,17 @@ mod tests {
             bank_notification_sender: None,
             duplicate_confirmed_slot_sender: None,
             migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
         };
         let mut replay_vote_buffer = VoteBuffer::new();
+        let verify_start = Instant::now();
         ClusterInfoVoteListener::listen_and_confirm_votes(
             &votes_txs_receiver,
             &vote_tracker,
@@ -1322,9 +2107,14 @@ mod tests {
             &mut latest_vote_slot_per_validator,
         )
         .unwrap();
+        let verify_end = Instant::now();
+        // Votes are stamped with the time they were verified
+        let is_verify_time =
+            |observed_at: Instant| verify_start <= observed_at && observed_at <= verify_end;
 
         let mut gossip_verified_votes: HashMap<Slot, HashMap<Hash, Vec<Pubkey>>> = HashMap::new();
-        for (pubkey, slot, hash) in gossip_verified_vote_hash_receiver.try_iter() {
+        for (pubkey, slot, hash, observed_at) in gossip_verified_vote_hash_receiver.try_iter() {
+            assert!(is_verify_time(observed_at));
             // send_vote_txs() will send each vote twice, but we should only get a notification
             // once for each via this channel
             let exists = gossip_verified_votes
@@ -1363,7 +2153,8 @@ mod tests {
             .chain(replay_vote_slots.clone())
             .collect();
         let mut pubkey_to_slots: HashMap<Pubkey, BTreeSet<Slot>> = HashMap::new();
-        for (received_pubkey, new_slots) in verified_voter_slots_receiver.try_iter() {
+        for (received_pubkey, new_slots, observed_at) in verified_voter_slots_receiver.try_iter() {
+            assert!(is_verify_time(observed_at));
             let already_received_slots = pubkey_to_slots.entry(received_pubkey).or_default();
             for new_slot in new_slots {
                 // `new_slot` should only be received once
@@ -1475,6 +2266,14 @@ mod tests {
             bank_notification_sender: None,
             duplicate_confirmed_slot_sender: None,
             migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
         };
         let mut replay_vote_buffer = VoteBuffer::new();
         ClusterInfoVoteListener::listen_and_confirm_votes(
@@ -1493,8 +2292,10 @@ mod tests {
         // subscribing via a channel
         let received_voter_slots = verified_voter_slots_receiver.try_iter().collect::<Vec<_>>();
         assert_eq!(received_voter_slots.len(), validator_voting_keypairs.len());
-        for (e, r) in expected_voter_slots.iter().zip(received_voter_slots.iter()) {
-            assert_eq!(e, r);
+        for ((e_pubkey, e_slots), (r_pubkey, r_slots, _observed_at)) in
+            expected_voter_slots.iter().zip(received_voter_slots.iter())
+        {
+            assert_eq!((e_pubkey, e_slots), (r_pubkey, r_slots));
         }
 
         // Check that all the votes were registered for each validator correctly
@@ -1566,6 +2367,14 @@ mod tests {
                 bank_notification_sender: None,
                 duplicate_confirmed_slot_sender: None,
                 migration_status: Arc::new(MigrationStatus::default()),
+                vote_capture: None,
+                violation_log: None,
+                slot_gap_classifier: None,
+                vote_account_monitor: None,
+                restart_latency_tracker: None,
+                vote_tracker_archiver: None,
+                gossip_vote_landing_tracker: None,
+                slot_filter: None,
             };
             let mut replay_vote_buffer = VoteBuffer::new();
             for &e in &events {
@@ -1938,6 +2747,14 @@ mod tests {
             bank_notification_sender: None,
             duplicate_confirmed_slot_sender: None,
             migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
         };
         ClusterInfoVoteListener::filter_and_confirm_with_new_votes(
             &vote_tracker,
@@ -1990,6 +2807,14 @@ mod tests {
             bank_notification_sender: None,
             duplicate_confirmed_slot_sender: None,
             migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
         };
         ClusterInfoVoteListener::filter_and_confirm_with_new_votes(
             &vote_tracker,
@@ -2105,6 +2930,254 @@ mod tests {
         run_test_verify_votes_1_pass(Some(Hash::default()));
     }
 
+    #[test]
+    fn test_drop_stale_votes() {
+        let votes: Vec<_> = (0..4)
+            .map(|slot| {
+                let vote_keypair = Keypair::new();
+                vote_transaction::new_tower_sync_transaction(
+                    TowerSync::from(vec![(slot, 1)]),
+                    Hash::default(),
+                    &Keypair::new(),
+                    &vote_keypair,
+                    &vote_keypair,
+                    None,
+                )
+            })
+            .collect();
+        let now = 10_000;
+        // Pushed 5s ago, right at the max age, 1ms past it, and ahead of the
+        // clock of this node
+        let wallclocks = vec![now - 5_000, now - 1_000, now - 1_001, now + 100];
+        assert_eq!(
+            drop_stale_votes(wallclocks.clone(), votes.clone(), 1_000, now),
+            vec![votes[1].clone(), votes[3].clone()]
+        );
+        assert_eq!(
+            drop_stale_votes(wallclocks, votes.clone(), 5_000, now),
+            votes
+        );
+        assert!(drop_stale_votes(vec![], vec![], 0, now).is_empty());
+    }
+
+    #[test]
+    fn test_vote_verify_batch() {
+        let config = VoteVerifyBatchConfig {
+            batch_size: NonZeroUsize::new(3).unwrap(),
+            max_latency: Duration::from_millis(50),
+        };
+        let vote_tx = test_vote_tx(None, None);
+        let start = Instant::now();
+        let mut batch = VoteVerifyBatch::default();
+        assert_eq!(batch.time_to_flush(&config, start), None);
+        batch.push(vec![], start);
+        assert_eq!(batch.take_ready(&config, start), None);
+
+        // Held back until there are enough votes
+        batch.push(vec![vote_tx.clone(); 2], start);
+        let now = start + Duration::from_millis(20);
+        assert_eq!(
+            batch.time_to_flush(&config, now),
+            Some(Duration::from_millis(30))
+        );
+        assert_eq!(batch.take_ready(&config, now), None);
+        batch.push(vec![vote_tx.clone(); 2], now);
+        assert_eq!(batch.take_ready(&config, now).unwrap().len(), 4);
+        assert_eq!(batch.time_to_flush(&config, now), None);
+
+        // Or until the oldest vote waited long enough
+        batch.push(vec![vote_tx], now);
+        assert_eq!(batch.take_ready(&config, now), None);
+        let now = now + config.max_latency;
+        assert_eq!(batch.time_to_flush(&config, now), Some(Duration::ZERO));
+        assert_eq!(batch.take_ready(&config, now).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_vote_verify_batching_throughput() {
+        let voting_keypairs: Vec<_> = repeat_with(ValidatorVoteKeypairs::new_rand)
+            .take(10)
+            .collect();
+        let GenesisConfigInfo { genesis_config, .. } =
+            genesis_utils::create_genesis_config_with_vote_accounts(
+                10_000, // mint_lamports
+                &voting_keypairs,
+                vec![100; voting_keypairs.len()], // stakes
+            );
+        let bank = Bank::new_for_tests(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank);
+        let sharable_banks = bank_forks.read().unwrap().sharable_banks();
+        let gossip_polls: Vec<Vec<_>> = (0..100)
+            .map(|_| {
+                voting_keypairs
+                    .iter()
+                    .map(|keypairs| test_vote_tx(Some(keypairs), None))
+                    .collect()
+            })
+            .collect();
+        let num_votes = gossip_polls.iter().map(Vec::len).sum::<usize>();
+
+        // Returns the number of verified votes and of verification round trips
+        let run = |config: VoteVerifyBatchConfig| {
+            let mut batch = VoteVerifyBatch::default();
+            let mut num_verified = 0;
+            let mut num_batches = 0;
+            let start = Instant::now();
+            // Polls are GOSSIP_SLEEP_MILLIS apart
+            let poll_time =
+                |i: usize| start + Duration::from_millis(i as u64 * GOSSIP_SLEEP_MILLIS);
+            for (i, votes) in gossip_polls.iter().enumerate() {
+                let now = poll_time(i);
+                batch.push(votes.clone(), now);
+                if let Some(votes) = batch.take_ready(&config, now) {
+                    let (vote_txs, _) = test_verify_votes(votes, &sharable_banks);
+                    num_verified += vote_txs.len();
+                    num_batches += 1;
+                }
+            }
+            let end = poll_time(gossip_polls.len()) + config.max_latency;
+            if let Some(votes) = batch.take_ready(&config, end) {
+                num_verified += test_verify_votes(votes, &sharable_banks).0.len();
+                num_batches += 1;
+            }
+            let elapsed = start.elapsed();
+            info!(
+                "{config:?}: verified {num_verified} votes in {num_batches} batches, {:.0} votes/s",
+                num_verified as f64 / elapsed.as_secs_f64()
+            );
+            (num_verified, num_batches)
+        };
+
+        let unbatched = run(VoteVerifyBatchConfig::default());
+        let batched = run(VoteVerifyBatchConfig {
+            batch_size: NonZeroUsize::new(100).unwrap(),
+            max_latency: Duration::from_secs(1),
+        });
+        assert_eq!(unbatched, (num_votes, gossip_polls.len()));
+        // Every vote is still verified, with a tenth of the round trips
+        let (batched_verified, batched_batches) = batched;
+        let (unbatched_verified, unbatched_batches) = unbatched;
+        assert_eq!(batched_verified, unbatched_verified);
+        assert_eq!(unbatched_batches, 10 * batched_batches);
+        // So each round trip verifies ten times as many votes
+        assert_eq!(
+            batched_verified / batched_batches,
+            10 * (unbatched_verified / unbatched_batches)
+        );
+    }
+
+    #[test]
+    fn test_vote_forwarding_gate() {
+        const TICKS_PER_SLOT: u64 = 64;
+        const NEXT_LEADER_SLOT_RANGE: (Slot, Slot) = (20, 23);
+        let mut shared_leader_state = SharedLeaderState::new(0, None, Some(NEXT_LEADER_SLOT_RANGE));
+        let always_forward =
+            VoteForwardingGate::new(None, shared_leader_state.clone(), TICKS_PER_SLOT);
+        let leader_window =
+            VoteForwardingGate::new(Some(4), shared_leader_state.clone(), TICKS_PER_SLOT);
+        let mut set_poh_slot = |slot: Slot, next_leader_slot_range: (Slot, Slot)| {
+            shared_leader_state.store(Arc::new(LeaderState::new(
+                None,
+                slot * TICKS_PER_SLOT,
+                None,
+                Some(next_leader_slot_range),
+            )))
+        };
+
+        let (verified_vote_transactions_sender, verified_vote_transactions_receiver) = unbounded();
+        let (verified_packets_sender, verified_packets_receiver) =
+            BankingTracer::channel_for_test();
+        let mut suppressed_votes = SuppressedVotes::default();
+        let mut stats = RecvLoopStats::default();
+        let vote_tx = test_vote_tx(None, None);
+        let num_forwarded_votes = || {
+            verified_packets_receiver
+                .try_iter()
+                .map(|packet_batches| packet_batches.len())
+                .sum::<usize>()
+        };
+        // Sends a verified vote through the gate, returning the number of
+        // votes banking received
+        let mut send_vote = |gate: &VoteForwardingGate, suppressed_votes: &mut SuppressedVotes| {
+            ClusterInfoVoteListener::send_verified_votes(
+                vec![vote_tx.clone()],
+                packet::to_packet_batches(&[vote_tx.clone()], 1),
+                gate,
+                suppressed_votes,
+                &verified_vote_transactions_sender,
+                &verified_packets_sender,
+                &mut stats,
+            )
+            .unwrap();
+            // The vote is processed for confirmation regardless
+            assert_eq!(
+                verified_vote_transactions_receiver.try_recv().unwrap(),
+                vec![vote_tx.clone()]
+            );
+            num_forwarded_votes()
+        };
+
+        assert_eq!(
+            send_vote(&always_forward, &mut SuppressedVotes::default()),
+            1
+        );
+        assert_eq!(send_vote(&leader_window, &mut suppressed_votes), 0);
+        set_poh_slot(15, NEXT_LEADER_SLOT_RANGE);
+        assert_eq!(send_vote(&leader_window, &mut suppressed_votes), 0);
+        // Forwarded from 4 slots before the leader slots until they are over,
+        // after the votes held back before
+        set_poh_slot(16, NEXT_LEADER_SLOT_RANGE);
+        assert_eq!(send_vote(&leader_window, &mut suppressed_votes), 3);
+        set_poh_slot(23, NEXT_LEADER_SLOT_RANGE);
+        assert_eq!(send_vote(&leader_window, &mut suppressed_votes), 1);
+        set_poh_slot(24, NEXT_LEADER_SLOT_RANGE);
+        assert_eq!(send_vote(&leader_window, &mut suppressed_votes), 0);
+        assert!(!suppressed_votes.is_empty());
+
+        // The votes held back are forwarded once the gate opens, without
+        // waiting for new votes
+        ClusterInfoVoteListener::forward_votes(
+            vec![],
+            &leader_window,
+            &mut suppressed_votes,
+            &verified_packets_sender,
+            &mut stats,
+        )
+        .unwrap();
+        assert_eq!(num_forwarded_votes(), 0);
+        set_poh_slot(36, (40, 43));
+        ClusterInfoVoteListener::forward_votes(
+            vec![],
+            &leader_window,
+            &mut suppressed_votes,
+            &verified_packets_sender,
+            &mut stats,
+        )
+        .unwrap();
+        assert_eq!(num_forwarded_votes(), 1);
+        assert!(suppressed_votes.is_empty());
+        assert_eq!(stats.suppressed_forward_count, 3);
+        assert_eq!(stats.delayed_forward_count, 3);
+        assert_eq!(stats.suppressed_forward_drop_count, 0);
+    }
+
+    #[test]
+    fn test_suppressed_votes() {
+        let vote_tx = test_vote_tx(None, None);
+        let packet_batches =
+            |num_votes| packet::to_packet_batches(&vec![vote_tx.clone(); num_votes], 1);
+        let mut suppressed_votes = SuppressedVotes::default();
+        assert!(suppressed_votes.is_empty());
+        assert_eq!(
+            suppressed_votes.push(packet_batches(MAX_SUPPRESSED_VOTES - 1)),
+            0
+        );
+        // Only the newest votes are kept
+        assert_eq!(suppressed_votes.push(packet_batches(3)), 2);
+        assert_eq!(suppressed_votes.take().len(), MAX_SUPPRESSED_VOTES);
+        assert!(suppressed_votes.is_empty());
+    }
+
     fn run_test_bad_vote(hash: Option<Hash>) {
         let voting_keypairs: Vec<_> = repeat_with(ValidatorVoteKeypairs::new_rand)
             .take(10)
@@ -2152,6 +3225,132 @@ mod tests {
         run_test_bad_vote(Some(Hash::default()));
     }
 
+    #[test]
+    fn test_duplicate_confirmed_slots_channel_coalescing() {
+        let (sender, receiver) = duplicate_confirmed_slots_channel(NonZeroUsize::new(4).unwrap());
+        let hashes: Vec<_> = (0..4).map(|_| Hash::new_unique()).collect();
+
+        // Fill up the channel
+        for slot in 0..4 {
+            sender.send(vec![(slot, hashes[0])]);
+        }
+        assert_eq!(receiver.len(), 4);
+
+        // Further notifications are held back, with later notifications for
+        // the same slot superseding earlier ones
+        for hash in &hashes[1..] {
+            for slot in 4..20 {
+                sender.send(vec![(slot, *hash)]);
+            }
+        }
+        assert_eq!(receiver.len(), 4);
+
+        let mut received = vec![];
+        while !receiver.is_empty() {
+            received.extend(receiver.try_iter().flatten());
+            sender.flush();
+        }
+        let expected: Vec<_> = (0..20)
+            .map(|slot| {
+                let hash = if slot < 4 { hashes[0] } else { hashes[3] };
+                (slot, hash)
+            })
+            .collect();
+        assert_eq!(received, expected);
+        assert!(sender.pending.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_vote_capture() {
+        let SetupComponents {
+            vote_tracker,
+            bank,
+            validator_voting_keypairs,
+            subscriptions,
+            ..
+        } = setup();
+        let ledger_path = TempDir::new().unwrap();
+        let vote_capture = Arc::new(VoteCapture::new(
+            NonZeroUsize::new(2).unwrap(),
+            ledger_path.path(),
+        ));
+        let (verified_voter_slots_sender, _verified_voter_slots_receiver) = unbounded();
+        let (gossip_verified_vote_hash_sender, _gossip_verified_vote_hash_receiver) = unbounded();
+        let notifiers = ConfirmationNotifiers {
+            gossip_verified_vote_hash_sender,
+            verified_voter_slots_sender,
+            rpc_subscriptions: Some(subscriptions),
+            bank_notification_sender: None,
+            duplicate_confirmed_slot_sender: None,
+            migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: Some(vote_capture.clone()),
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
+        };
+
+        let hash = Hash::new_unique();
+        let gossip_vote_txs: Vec<_> = validator_voting_keypairs[..2]
+            .iter()
+            .map(|keypairs| {
+                vote_transaction::new_tower_sync_transaction(
+                    TowerSync::from(vec![(1, 1)]),
+                    hash,
+                    &keypairs.node_keypair,
+                    &keypairs.vote_keypair,
+                    &keypairs.vote_keypair,
+                    None,
+                )
+            })
+            .collect();
+        let replay_vote_pubkey = validator_voting_keypairs[2].vote_keypair.pubkey();
+        let replay_votes = vec![(
+            replay_vote_pubkey,
+            VoteTransaction::from(Vote::new(vec![1], hash)),
+            None,
+            Signature::default(),
+        )];
+        ClusterInfoVoteListener::filter_and_confirm_with_new_votes(
+            &vote_tracker,
+            gossip_vote_txs,
+            replay_votes,
+            &bank,
+            &notifiers,
+            &mut None,
+            &mut HashMap::new(),
+        );
+
+        let path = vote_capture.freeze_vote_capture("test").unwrap();
+        let contents = std::fs::read_to_string(path).unwrap();
+        let records: Vec<_> = contents
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split_whitespace().skip(1).collect::<Vec<_>>())
+            .collect();
+        // Only the last two votes are kept, in the order they were processed
+        let gossip_vote_pubkey = validator_voting_keypairs[1].vote_keypair.pubkey();
+        let expected_record = |vote_pubkey: &Pubkey, source| {
+            vec![
+                vote_pubkey.to_string(),
+                String::from(source),
+                "100".to_string(),
+                hash.to_string(),
+                "[1]".to_string(),
+            ]
+        };
+        assert_eq!(
+            records,
+            vec![
+                expected_record(&gossip_vote_pubkey, "gossip"),
+                expected_record(&replay_vote_pubkey, "replay"),
+            ]
+        );
+    }
+
     #[test]
     fn test_track_new_votes_filter() {
         let validator_keypairs: Vec<_> =
@@ -2203,6 +3402,14 @@ mod tests {
             bank_notification_sender: None,
             duplicate_confirmed_slot_sender: None,
             migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
         };
         ClusterInfoVoteListener::track_new_votes_and_notify_confirmations(
             vote,
@@ -2215,6 +3422,7 @@ mod tests {
             &mut new_optimistic_confirmed_slots,
             true, /* is gossip */
             &mut latest_vote_slot_per_validator,
+            None,
         );
         assert_eq!(diff.keys().copied().sorted().collect_vec(), vec![1, 2, 6]);
 
@@ -2242,7 +3450,360 @@ mod tests {
             &mut new_optimistic_confirmed_slots,
             true, /* is gossip */
             &mut latest_vote_slot_per_validator,
+            None,
         );
         assert_eq!(diff.keys().copied().sorted().collect_vec(), vec![7, 8]);
     }
+
+    #[test]
+    fn test_track_new_votes_slot_filter() {
+        let SetupComponents {
+            vote_tracker,
+            bank,
+            validator_voting_keypairs,
+            ..
+        } = setup();
+        let ledger_path = TempDir::new().unwrap();
+        let vote_capture = Arc::new(VoteCapture::new(
+            NonZeroUsize::new(4).unwrap(),
+            ledger_path.path(),
+        ));
+        let (verified_voter_slots_sender, verified_voter_slots_receiver) = unbounded();
+        let (gossip_verified_vote_hash_sender, _gossip_verified_vote_hash_receiver) = unbounded();
+        let slot_filter = Arc::new(RwLock::new(HashSet::from([2])));
+        let notifiers = ConfirmationNotifiers {
+            gossip_verified_vote_hash_sender,
+            verified_voter_slots_sender,
+            rpc_subscriptions: None,
+            bank_notification_sender: None,
+            duplicate_confirmed_slot_sender: None,
+            migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: Some(vote_capture.clone()),
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: Some(slot_filter.clone()),
+        };
+        let gossip_vote_txs = |votes: &[(usize, Vec<(Slot, u32)>)]| {
+            votes
+                .iter()
+                .map(|(i, lockouts)| {
+                    let keypairs = &validator_voting_keypairs[*i];
+                    vote_transaction::new_tower_sync_transaction(
+                        TowerSync::from(lockouts.clone()),
+                        Hash::default(),
+                        &keypairs.node_keypair,
+                        &keypairs.vote_keypair,
+                        &keypairs.vote_keypair,
+                        None,
+                    )
+                })
+                .collect()
+        };
+
+        // The first validator votes on the filtered slot and the slots around
+        // it, the second only on the slot before it
+        ClusterInfoVoteListener::filter_and_confirm_with_new_votes(
+            &vote_tracker,
+            gossip_vote_txs(&[(0, vec![(1, 3), (2, 2), (3, 1)]), (1, vec![(1, 1)])]),
+            vec![],
+            &bank,
+            &notifiers,
+            &mut None,
+            &mut HashMap::new(),
+        );
+
+        // Only the filtered slot is tracked for the propagated check
+        let vote_pubkey = |i: usize| validator_voting_keypairs[i].vote_keypair.pubkey();
+        let voted = |slot| {
+            vote_tracker
+                .get_slot_vote_tracker(slot)
+                .map(|slot_tracker| {
+                    slot_tracker
+                        .read()
+                        .unwrap()
+                        .voted
+                        .keys()
+                        .copied()
+                        .collect_vec()
+                })
+                .unwrap_or_default()
+        };
+        assert_eq!(voted(1), vec![]);
+        assert_eq!(voted(2), vec![vote_pubkey(0)]);
+        assert_eq!(voted(3), vec![]);
+
+        // Optimistic confirmation and notifications see every vote
+        for (slot, i) in [(3, 0), (1, 1)] {
+            let slot_tracker = vote_tracker.get_slot_vote_tracker(slot).unwrap();
+            let slot_tracker = slot_tracker.read().unwrap();
+            let optimistic_votes = slot_tracker
+                .optimistic_votes_tracker(&Hash::default())
+                .unwrap();
+            assert!(optimistic_votes.voted().contains(&vote_pubkey(i)));
+        }
+        assert_eq!(verified_voter_slots_receiver.try_iter().count(), 2);
+
+        // Only the vote touching the filtered slot is captured
+        let vote_pubkeys = |vote_capture: &VoteCapture| {
+            let path = vote_capture.freeze_vote_capture("test").unwrap();
+            std::fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .map(|line| {
+                    line.split_whitespace()
+                        .nth(1)
+                        .unwrap()
+                        .parse::<Pubkey>()
+                        .unwrap()
+                })
+                .collect_vec()
+        };
+        assert_eq!(vote_pubkeys(&vote_capture), vec![vote_pubkey(0)]);
+
+        // An empty filter captures and tracks the votes for every slot
+        slot_filter.write().unwrap().clear();
+        ClusterInfoVoteListener::filter_and_confirm_with_new_votes(
+            &vote_tracker,
+            gossip_vote_txs(&[(1, vec![(1, 2), (3, 1)])]),
+            vec![],
+            &bank,
+            &notifiers,
+            &mut None,
+            &mut HashMap::new(),
+        );
+        assert_eq!(
+            vote_pubkeys(&vote_capture),
+            vec![vote_pubkey(0), vote_pubkey(1)]
+        );
+        assert_eq!(voted(3), vec![vote_pubkey(1)]);
+    }
+
+    #[test]
+    fn test_recently_optimistically_confirmed() {
+        let validator_keypairs: Vec<_> =
+            (0..2).map(|_| ValidatorVoteKeypairs::new_rand()).collect();
+        let GenesisConfigInfo { genesis_config, .. } =
+            genesis_utils::create_genesis_config_with_vote_accounts(
+                10_000,
+                &validator_keypairs,
+                vec![100; validator_keypairs.len()],
+            );
+        let bank = Bank::new_for_tests(&genesis_config);
+        let vote_tracker = VoteTracker::default();
+        let (verified_voter_slots_sender, _verified_voter_slots_receiver) = bounded(1024);
+        let (gossip_verified_vote_hash_sender, _gossip_verified_vote_hash_receiver) = bounded(1024);
+        let notifiers = ConfirmationNotifiers {
+            gossip_verified_vote_hash_sender,
+            verified_voter_slots_sender,
+            rpc_subscriptions: None,
+            bank_notification_sender: None,
+            duplicate_confirmed_slot_sender: None,
+            migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
+        };
+        let mut latest_vote_slot_per_validator = HashMap::new();
+        let hash = Hash::new_unique();
+
+        for (i, keypairs) in validator_keypairs.iter().enumerate() {
+            let (vote_pubkey, vote, _, signature) =
+                vote_parser::parse_vote_transaction(&vote_transaction::new_tower_sync_transaction(
+                    TowerSync::from(vec![(1, 1)]),
+                    hash,
+                    &keypairs.node_keypair,
+                    &keypairs.vote_keypair,
+                    &keypairs.vote_keypair,
+                    None,
+                ))
+                .unwrap();
+            let mut new_optimistic_confirmed_slots = vec![];
+            ClusterInfoVoteListener::track_new_votes_and_notify_confirmations(
+                vote,
+                &vote_pubkey,
+                signature,
+                &vote_tracker,
+                &bank,
+                &notifiers,
+                &mut HashMap::default(),
+                &mut new_optimistic_confirmed_slots,
+                true, /* is gossip */
+                &mut latest_vote_slot_per_validator,
+                None,
+            );
+            if i == 0 {
+                // Half of the stake is below the threshold
+                assert!(new_optimistic_confirmed_slots.is_empty());
+                assert!(vote_tracker.recently_optimistically_confirmed().is_empty());
+            }
+        }
+        assert_eq!(
+            vote_tracker.recently_optimistically_confirmed(),
+            vec![(1, hash)]
+        );
+
+        for slot in 2..=MAX_RECENTLY_OPTIMISTICALLY_CONFIRMED as Slot + 1 {
+            vote_tracker.record_optimistic_confirmation(slot, hash);
+        }
+        let confirmed = vote_tracker.recently_optimistically_confirmed();
+        assert_eq!(confirmed.len(), MAX_RECENTLY_OPTIMISTICALLY_CONFIRMED);
+        assert_eq!(confirmed[0], (2, hash));
+    }
+
+    #[test]
+    fn test_new_roots() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let bank2 = Arc::new(Bank::new_from_parent(
+            bank0.clone(),
+            SlotLeader::default(),
+            2,
+        ));
+        let bank3 = Arc::new(Bank::new_from_parent(
+            bank2.clone(),
+            SlotLeader::default(),
+            3,
+        ));
+        let ledger_path = solana_ledger::get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let vote_tracker = VoteTracker::default();
+
+        assert_eq!(vote_tracker.new_roots(&bank0, &blockstore), vec![0]);
+        vote_tracker.progress_with_new_root_bank(&bank0);
+        assert!(vote_tracker.new_roots(&bank0, &blockstore).is_empty());
+        // From the ancestors of the root bank
+        assert_eq!(vote_tracker.new_roots(&bank3, &blockstore), vec![2, 3]);
+        vote_tracker.progress_with_new_root_bank(&bank2);
+
+        // From the blockstore, for the roots before the root bank was
+        // squashed
+        let bank6 = Arc::new(Bank::new_from_parent(bank2, SlotLeader::default(), 6));
+        bank6.squash();
+        let bank7 = Arc::new(Bank::new_from_parent(bank6, SlotLeader::default(), 7));
+        blockstore.set_roots([4, 5].iter()).unwrap();
+        assert_eq!(
+            vote_tracker.new_roots(&bank7, &blockstore),
+            vec![4, 5, 6, 7]
+        );
+        // None after a regression
+        vote_tracker.progress_with_new_root_bank(&bank7);
+        assert!(vote_tracker.new_roots(&bank3, &blockstore).is_empty());
+    }
+
+    #[test]
+    fn test_root_regression() {
+        let validator_keypairs: Vec<_> =
+            (0..3).map(|_| ValidatorVoteKeypairs::new_rand()).collect();
+        // The previous fork only knows of the first two validators
+        let GenesisConfigInfo { genesis_config, .. } =
+            genesis_utils::create_genesis_config_with_vote_accounts(
+                10_000,
+                &validator_keypairs[..2],
+                vec![100; 2],
+            );
+        let previous_root_bank = Bank::new_from_parent(
+            Arc::new(Bank::new_for_tests(&genesis_config)),
+            SlotLeader::default(),
+            10,
+        );
+        let GenesisConfigInfo { genesis_config, .. } =
+            genesis_utils::create_genesis_config_with_vote_accounts(
+                10_000,
+                &validator_keypairs,
+                vec![100, 100, 50],
+            );
+        let new_root_bank = Bank::new_from_parent(
+            Arc::new(Bank::new_for_tests(&genesis_config)),
+            SlotLeader::default(),
+            5,
+        );
+
+        let vote_tracker = VoteTracker::default();
+        let (verified_voter_slots_sender, _verified_voter_slots_receiver) = bounded(1024);
+        let (gossip_verified_vote_hash_sender, _gossip_verified_vote_hash_receiver) = bounded(1024);
+        let notifiers = ConfirmationNotifiers {
+            gossip_verified_vote_hash_sender,
+            verified_voter_slots_sender,
+            rpc_subscriptions: None,
+            bank_notification_sender: None,
+            duplicate_confirmed_slot_sender: None,
+            migration_status: Arc::new(MigrationStatus::default()),
+            vote_capture: None,
+            violation_log: None,
+            slot_gap_classifier: None,
+            vote_account_monitor: None,
+            restart_latency_tracker: None,
+            vote_tracker_archiver: None,
+            gossip_vote_landing_tracker: None,
+            slot_filter: None,
+        };
+        let mut latest_vote_slot_per_validator = HashMap::new();
+        let hash = Hash::new_unique();
+        // Returns the slots optimistically confirmed by the vote
+        let mut vote = |keypairs: &ValidatorVoteKeypairs, root_bank: &Bank, slot: Slot| {
+            let (vote_pubkey, vote, _, signature) =
+                vote_parser::parse_vote_transaction(&vote_transaction::new_tower_sync_transaction(
+                    TowerSync::from(vec![(slot, 1)]),
+                    hash,
+                    &keypairs.node_keypair,
+                    &keypairs.vote_keypair,
+                    &keypairs.vote_keypair,
+                    None,
+                ))
+                .unwrap();
+            let mut new_optimistic_confirmed_slots = vec![];
+            ClusterInfoVoteListener::track_new_votes_and_notify_confirmations(
+                vote,
+                &vote_pubkey,
+                signature,
+                &vote_tracker,
+                root_bank,
+                &notifiers,
+                &mut HashMap::default(),
+                &mut new_optimistic_confirmed_slots,
+                true, /* is gossip */
+                &mut latest_vote_slot_per_validator,
+                None,
+            );
+            new_optimistic_confirmed_slots
+        };
+
+        assert!(!vote_tracker.progress_with_new_root_bank(&previous_root_bank));
+        assert!(vote(&validator_keypairs[1], &previous_root_bank, 12).is_empty());
+        vote_tracker.record_optimistic_confirmation(11, hash);
+        vote_tracker.record_optimistic_confirmation(4, hash);
+
+        // The root moves back to a lower slot of a different fork
+        assert!(vote_tracker.is_root_regression(&new_root_bank));
+        assert!(vote_tracker.progress_with_new_root_bank(&new_root_bank));
+        assert!(!vote_tracker.is_root_regression(&new_root_bank));
+        assert!(vote_tracker.get_slot_vote_tracker(12).is_none());
+        assert_eq!(
+            vote_tracker.recently_optimistically_confirmed(),
+            vec![(4, hash)]
+        );
+
+        // Had the vote of the second validator been kept, it would now make
+        // up the threshold along with the first one's, under the new stakes
+        assert!(vote(&validator_keypairs[0], &new_root_bank, 12).is_empty());
+        assert_eq!(
+            vote(&validator_keypairs[1], &new_root_bank, 12),
+            vec![(12, hash)]
+        );
+
+        // Advancing the root on the new fork is not a regression
+        let next_root_bank =
+            Bank::new_from_parent(Arc::new(new_root_bank), SlotLeader::default(), 6);
+        assert!(!vote_tracker.progress_with_new_root_bank(&next_root_bank));
+    }
 }
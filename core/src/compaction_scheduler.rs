@@ -0,0 +1,342 @@
+//! Defers the blockstore compactions around the leader slots of this node.
+//!
+//! Background compactions of the blockstore can spike the IO right when the
+//! node produces blocks. As the leader slots are known ahead of time, the
+//! compaction work that can wait is deferred a number of slots before the
+//! leader slots, and resumed once they are over.
+//!
+//! The slots spent with compactions deferred add up to a compaction debt,
+//! which the slots spent with compactions running pay off. Once the debt
+//! reaches its limit, compactions are resumed, and not deferred again until
+//! the debt is paid off, so that the debt cannot grow without bound when the
+//! leader windows come close together.
+
+use {
+    crate::leader_window::LeaderWindow,
+    solana_clock::Slot,
+    solana_ledger::blockstore::Blockstore,
+    solana_poh::poh_recorder::SharedLeaderState,
+    std::{
+        num::NonZeroUsize,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread::{self, Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+pub const DEFAULT_COMPACTION_DEFER_LEAD_SLOTS: u64 = 8;
+pub const DEFAULT_MAX_COMPACTION_DEBT_SLOTS: u64 = 2_000;
+
+/// How often the slot PoH is ticking through is checked
+const CHECK_SLOT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactionSchedulerConfig {
+    /// Number of slots before the leader slots of this node at which the
+    /// compactions are deferred
+    pub lead_slots: u64,
+    /// Number of threads running background compactions while deferred
+    pub num_deferred_compaction_threads: NonZeroUsize,
+    /// Compaction debt, in slots, at which compactions are resumed until the
+    /// debt is paid off
+    pub max_debt_slots: u64,
+}
+
+impl Default for CompactionSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            lead_slots: DEFAULT_COMPACTION_DEFER_LEAD_SLOTS,
+            num_deferred_compaction_threads: NonZeroUsize::MIN,
+            max_debt_slots: DEFAULT_MAX_COMPACTION_DEBT_SLOTS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CompactionAction {
+    Defer,
+    Restore,
+}
+
+/// Decides when compactions are deferred and restored
+#[derive(Debug)]
+struct CompactionSchedule {
+    config: CompactionSchedulerConfig,
+    last_slot: Option<Slot>,
+    deferred: bool,
+    /// Slots spent with compactions deferred, less the slots spent with
+    /// compactions running since
+    debt_slots: u64,
+    /// Whether compactions are kept running until the debt is paid off
+    catching_up: bool,
+}
+
+impl CompactionSchedule {
+    fn new(config: CompactionSchedulerConfig) -> Self {
+        Self {
+            config,
+            last_slot: None,
+            deferred: false,
+            debt_slots: 0,
+            catching_up: false,
+        }
+    }
+
+    /// Accounts for the slots elapsed since the last call, and returns the
+    /// action to take at the slot of `leader_window`, if compactions are to be
+    /// deferred or restored
+    fn on_slot(&mut self, leader_window: LeaderWindow) -> Option<CompactionAction> {
+        let slot = leader_window.slot;
+        let elapsed_slots = self
+            .last_slot
+            .map(|last_slot| slot.saturating_sub(last_slot))
+            .unwrap_or_default();
+        self.last_slot = Some(slot);
+        if self.deferred {
+            self.debt_slots = self.debt_slots.saturating_add(elapsed_slots);
+        } else {
+            self.debt_slots = self.debt_slots.saturating_sub(elapsed_slots);
+        }
+        if self.debt_slots >= self.config.max_debt_slots {
+            self.catching_up = true;
+        } else if self.debt_slots == 0 {
+            self.catching_up = false;
+        }
+
+        let defer = leader_window.is_within(self.config.lead_slots) && !self.catching_up;
+        if defer == self.deferred {
+            return None;
+        }
+        self.deferred = defer;
+        Some(if defer {
+            CompactionAction::Defer
+        } else {
+            CompactionAction::Restore
+        })
+    }
+}
+
+pub struct CompactionScheduler {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl CompactionScheduler {
+    pub fn new(
+        config: CompactionSchedulerConfig,
+        blockstore: Arc<Blockstore>,
+        shared_leader_state: SharedLeaderState,
+        ticks_per_slot: u64,
+        exit: Arc<AtomicBool>,
+    ) -> Self {
+        let thread_hdl = Builder::new()
+            .name("solCompactSched".to_string())
+            .spawn(move || {
+                let num_deferred_compaction_threads = config.num_deferred_compaction_threads;
+                let mut schedule = CompactionSchedule::new(config);
+                while !exit.load(Ordering::Relaxed) {
+                    let leader_window =
+                        LeaderWindow::new(&shared_leader_state.load(), ticks_per_slot);
+                    if let Some(action) = schedule.on_slot(leader_window) {
+                        let result = match action {
+                            CompactionAction::Defer => {
+                                blockstore.defer_compactions(num_deferred_compaction_threads)
+                            }
+                            CompactionAction::Restore => blockstore.restore_compactions(),
+                        };
+                        if let Err(err) = result {
+                            warn!("Blockstore compaction action {action:?} failed: {err}");
+                        }
+                        datapoint_info!(
+                            "compaction_scheduler",
+                            ("slot", leader_window.slot, i64),
+                            ("deferred", action == CompactionAction::Defer, bool),
+                            ("debt_slots", schedule.debt_slots, i64),
+                            ("catching_up", schedule.catching_up, bool),
+                        );
+                    }
+                    thread::sleep(CHECK_SLOT_INTERVAL);
+                }
+                // Leave the blockstore with its usual settings
+                if blockstore.compactions_deferred() {
+                    if let Err(err) = blockstore.restore_compactions() {
+                        warn!("Failed to restore blockstore compactions: {err}");
+                    }
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_ledger::get_tmp_ledger_path_auto_delete,
+        solana_poh::poh_recorder::LeaderState, std::time::Instant,
+    };
+
+    fn config(lead_slots: u64, max_debt_slots: u64) -> CompactionSchedulerConfig {
+        CompactionSchedulerConfig {
+            lead_slots,
+            max_debt_slots,
+            ..CompactionSchedulerConfig::default()
+        }
+    }
+
+    /// Runs the schedule over `slots`, returning the slots at which an action
+    /// is taken
+    fn run(
+        schedule: &mut CompactionSchedule,
+        slots: impl IntoIterator<Item = Slot>,
+        next_leader_slot_range: Option<(Slot, Slot)>,
+    ) -> Vec<(Slot, CompactionAction)> {
+        slots
+            .into_iter()
+            .filter_map(|slot| {
+                schedule
+                    .on_slot(leader_window(slot, next_leader_slot_range))
+                    .map(|action| (slot, action))
+            })
+            .collect()
+    }
+
+    fn leader_window(slot: Slot, next_leader_slot_range: Option<(Slot, Slot)>) -> LeaderWindow {
+        LeaderWindow {
+            slot,
+            next_leader_slot_range,
+        }
+    }
+
+    #[test]
+    fn test_compaction_schedule_leader_window() {
+        let mut schedule = CompactionSchedule::new(config(4, 100));
+        assert_eq!(run(&mut schedule, 0..10, None), vec![]);
+
+        // Deferred from 4 slots before the leader slots until they are over
+        assert_eq!(
+            run(&mut schedule, 10..30, Some((20, 23))),
+            vec![
+                (16, CompactionAction::Defer),
+                (24, CompactionAction::Restore)
+            ]
+        );
+        // 8 slots deferred, 5 paid off
+        assert_eq!(schedule.debt_slots, 3);
+
+        // Checked more than once per slot
+        let mut schedule = CompactionSchedule::new(config(4, 100));
+        assert_eq!(
+            schedule.on_slot(leader_window(16, Some((20, 23)))),
+            Some(CompactionAction::Defer)
+        );
+        assert_eq!(schedule.on_slot(leader_window(16, Some((20, 23)))), None);
+        assert_eq!(schedule.on_slot(leader_window(23, Some((20, 23)))), None);
+        assert_eq!(schedule.debt_slots, 7);
+        // PoH moved on to the next leader slots of this node
+        assert_eq!(
+            schedule.on_slot(leader_window(24, Some((40, 43)))),
+            Some(CompactionAction::Restore)
+        );
+        assert_eq!(schedule.debt_slots, 8);
+        assert_eq!(schedule.on_slot(leader_window(30, Some((40, 43)))), None);
+        assert_eq!(schedule.debt_slots, 2);
+        assert_eq!(
+            schedule.on_slot(leader_window(36, Some((40, 43)))),
+            Some(CompactionAction::Defer)
+        );
+        assert_eq!(schedule.debt_slots, 0);
+    }
+
+    #[test]
+    fn test_compaction_schedule_debt_forces_catch_up() {
+        let mut schedule = CompactionSchedule::new(config(4, 10));
+
+        // The debt limit is reached in the middle of the leader slots
+        assert_eq!(
+            run(&mut schedule, 0..17, Some((10, 19))),
+            vec![
+                (6, CompactionAction::Defer),
+                (16, CompactionAction::Restore)
+            ]
+        );
+        assert_eq!(schedule.debt_slots, 10);
+        assert!(schedule.catching_up);
+
+        // Leader slots coming up before the debt is paid off do not defer
+        // compactions
+        assert_eq!(run(&mut schedule, 17..24, Some((20, 23))), vec![]);
+        assert_eq!(schedule.debt_slots, 3);
+        assert!(schedule.catching_up);
+        assert_eq!(run(&mut schedule, 24..27, None), vec![]);
+        assert_eq!(schedule.debt_slots, 0);
+        assert!(!schedule.catching_up);
+
+        // Compactions are deferred again once the debt is paid off
+        assert_eq!(
+            run(&mut schedule, 27..40, Some((36, 37))),
+            vec![
+                (32, CompactionAction::Defer),
+                (38, CompactionAction::Restore)
+            ]
+        );
+        assert_eq!(schedule.debt_slots, 5);
+    }
+
+    #[test]
+    fn test_compaction_schedule_no_debt_allowed() {
+        let mut schedule = CompactionSchedule::new(config(4, 0));
+        assert_eq!(run(&mut schedule, 0..30, Some((20, 23))), vec![]);
+    }
+
+    #[test]
+    fn test_compaction_scheduler_blockstore() {
+        const TICKS_PER_SLOT: u64 = 64;
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+        let mut shared_leader_state = SharedLeaderState::new(0, None, Some((20, 23)));
+        let exit = Arc::new(AtomicBool::new(false));
+        let compaction_scheduler = CompactionScheduler::new(
+            config(4, 100),
+            blockstore.clone(),
+            shared_leader_state.clone(),
+            TICKS_PER_SLOT,
+            exit.clone(),
+        );
+        let mut set_poh_slot = |slot: Slot| {
+            shared_leader_state.store(Arc::new(LeaderState::new(
+                None,
+                slot * TICKS_PER_SLOT,
+                None,
+                Some((20, 23)),
+            )))
+        };
+        let wait_for_compactions_deferred = |deferred: bool| {
+            let start = Instant::now();
+            while blockstore.compactions_deferred() != deferred {
+                assert!(start.elapsed() < Duration::from_secs(10));
+                thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        thread::sleep(CHECK_SLOT_INTERVAL * 2);
+        assert!(!blockstore.compactions_deferred());
+        set_poh_slot(16);
+        wait_for_compactions_deferred(true);
+        set_poh_slot(24);
+        wait_for_compactions_deferred(false);
+
+        // Compactions deferred at exit are restored
+        set_poh_slot(20);
+        wait_for_compactions_deferred(true);
+        exit.store(true, Ordering::Relaxed);
+        compaction_scheduler.join().unwrap();
+        assert!(!blockstore.compactions_deferred());
+    }
+}
@@ -104,7 +104,7 @@ impl CompletedDataSetsService {
     pub fn new(
         completed_sets_receiver: CompletedDataSetsReceiver,
         blockstore: Arc<Blockstore>,
-        rpc_subscriptions: Arc<RpcSubscriptions>,
+        rpc_subscriptions: Option<Arc<RpcSubscriptions>>,
         deshred_transaction_notifier: Option<DeshredTransactionNotifierArc>,
         exit: Arc<AtomicBool>,
         max_slots: Arc<MaxSlots>,
@@ -121,7 +121,7 @@ impl CompletedDataSetsService {
                     if let Err(RecvTimeoutError::Disconnected) = Self::recv_completed_data_sets(
                         &completed_sets_receiver,
                         &blockstore,
-                        &rpc_subscriptions,
+                        rpc_subscriptions.as_deref(),
                         &deshred_transaction_notifier,
                         &max_slots,
                         &bank_forks,
@@ -138,7 +138,7 @@ impl CompletedDataSetsService {
     fn recv_completed_data_sets(
         completed_sets_receiver: &CompletedDataSetsReceiver,
         blockstore: &Blockstore,
-        rpc_subscriptions: &RpcSubscriptions,
+        rpc_subscriptions: Option<&RpcSubscriptions>,
         deshred_transaction_notifier: &Option<DeshredTransactionNotifierArc>,
         max_slots: &Arc<MaxSlots>,
         bank_forks: &RwLock<BankForks>,
@@ -174,9 +174,11 @@ impl CompletedDataSetsService {
                             &mut stats,
                         );
 
-                        let transactions = Self::get_transaction_signatures(entries);
-                        if !transactions.is_empty() {
-                            rpc_subscriptions.notify_signatures_received((slot, transactions));
+                        if let Some(rpc_subscriptions) = rpc_subscriptions {
+                            let transactions = Self::get_transaction_signatures(entries);
+                            if !transactions.is_empty() {
+                                rpc_subscriptions.notify_signatures_received((slot, transactions));
+                            }
                         }
                     }
                     Err(e) => warn!("completed-data-set-service deserialize error: {e:?}"),
@@ -559,7 +561,7 @@ pub mod test {
         CompletedDataSetsService::recv_completed_data_sets(
             &receiver,
             &blockstore,
-            &rpc_subscriptions,
+            Some(&rpc_subscriptions),
             &notifier,
             &max_slots,
             &bank_forks,
@@ -625,7 +627,7 @@ pub mod test {
         CompletedDataSetsService::recv_completed_data_sets(
             &receiver,
             &blockstore,
-            &rpc_subscriptions,
+            Some(&rpc_subscriptions),
             &notifier,
             &max_slots,
             &bank_forks,
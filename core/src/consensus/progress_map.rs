@@ -88,6 +88,9 @@ pub enum DeadSlotReason {
     /// Replay execution failed, but an `UpdateParent` marker could still make
     /// the failed prefix obsolete.
     ReplayFailureBeforeUpdateParent,
+    /// Pruned to stay within the maximum number of active banks. Unlike the
+    /// other reasons, the slot is not marked dead in blockstore.
+    PrunedActiveBank,
 }
 
 pub struct ForkProgress {
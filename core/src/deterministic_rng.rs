@@ -0,0 +1,67 @@
+//! Seeds the RNGs of the validator subsystems for reproducible runs.
+//!
+//! When `ValidatorConfig::deterministic_rng_seed` is set, each subsystem
+//! honoring it draws from an RNG seeded with a seed derived from the base seed
+//! and the stable label of the subsystem, so that runs with the same seed make
+//! the same random choices given the same inputs. The subsystems honoring it
+//! are:
+//! * the pruning of the staked connections of the TPU, TPU forwards and TPU
+//!   vote QUIC servers, and of the Alpenglow vote QUIC server,
+//! * the resets of the shred deduper of the retransmit stage,
+//! * the peers sampled for the shred repairs and the block id repairs.
+//!
+//! All other subsystems keep drawing from the thread local RNG. Without a base
+//! seed, or in builds without `dev-context-only-utils`, the subsystems above do
+//! as well.
+
+use {
+    rand::{RngCore, SeedableRng, rngs::SmallRng},
+    solana_sha256_hasher::hashv,
+};
+
+pub const TPU_QUIC_RNG_LABEL: &str = "tpu_quic";
+pub const TPU_FORWARDS_QUIC_RNG_LABEL: &str = "tpu_forwards_quic";
+pub const TPU_VOTE_QUIC_RNG_LABEL: &str = "tpu_vote_quic";
+pub const ALPENGLOW_VOTE_QUIC_RNG_LABEL: &str = "alpenglow_vote_quic";
+pub const RETRANSMIT_RNG_LABEL: &str = "retransmit";
+pub const REPAIR_RNG_LABEL: &str = "repair";
+pub const BLOCK_ID_REPAIR_RNG_LABEL: &str = "block_id_repair";
+
+/// Derives the seed of the RNG of the subsystem with the given label from the
+/// base seed, if any
+pub fn subsystem_rng_seed(base_seed: Option<u64>, label: &str) -> Option<u64> {
+    base_seed.map(|base_seed| {
+        let hash = hashv(&[&base_seed.to_le_bytes(), label.as_bytes()]);
+        u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+    })
+}
+
+/// Returns an RNG seeded with the seed, or the thread local RNG if None
+pub fn subsystem_rng(seed: Option<u64>) -> Box<dyn RngCore> {
+    match seed {
+        Some(seed) => Box::new(SmallRng::seed_from_u64(seed)),
+        None => Box::new(rand::rng()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::Rng};
+
+    #[test]
+    fn test_subsystem_rng_seed() {
+        assert_eq!(subsystem_rng_seed(None, REPAIR_RNG_LABEL), None);
+        let seed = subsystem_rng_seed(Some(42), REPAIR_RNG_LABEL);
+        assert!(seed.is_some());
+        assert_eq!(subsystem_rng_seed(Some(42), REPAIR_RNG_LABEL), seed);
+        assert_ne!(subsystem_rng_seed(Some(43), REPAIR_RNG_LABEL), seed);
+        assert_ne!(subsystem_rng_seed(Some(42), RETRANSMIT_RNG_LABEL), seed);
+
+        // Runs with the same seed draw the same values
+        let draw = |seed| {
+            let mut rng = subsystem_rng(seed);
+            (0..8).map(|_| rng.random::<u64>()).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(seed), draw(seed));
+    }
+}
@@ -0,0 +1,332 @@
+//! Cross-checks the votes validators broadcast on gossip against the votes
+//! which land on chain.
+//!
+//! A validator whose gossip votes never make it into a block, because it
+//! withholds them from the leaders or cannot reach them, looks like it takes
+//! part in consensus while its votes earn no credits and do not count towards
+//! the rooted forks. Each vote observed on gossip is a claim for its last voted
+//! slot, which is settled once the root is `LANDING_WINDOW_SLOTS` past the slot:
+//! it landed if a replayed block carried a vote of the same vote account for
+//! the same or a later last voted slot, as the tower may have moved on, or
+//! been refreshed, before one of its votes landed.
+
+use {
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    solana_runtime::bank::Bank,
+    std::{
+        collections::{BTreeSet, HashMap},
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// Number of slots past a voted slot its vote may take to land
+pub const LANDING_WINDOW_SLOTS: Slot = 64;
+// Bounds the unsettled slots kept per vote account, the oldest being dropped
+const MAX_PENDING_SLOTS_PER_VOTE_ACCOUNT: usize = 1_024;
+const REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GossipVoteDiscrepancy {
+    pub vote_account: Pubkey,
+    pub stake: u64,
+    /// Settled votes observed on gossip
+    pub num_gossip_votes: u64,
+    /// Settled votes observed on gossip which landed on chain
+    pub num_landed_votes: u64,
+}
+
+impl GossipVoteDiscrepancy {
+    pub fn num_missing_votes(&self) -> u64 {
+        self.num_gossip_votes.saturating_sub(self.num_landed_votes)
+    }
+
+    /// The missing votes weighted by stake, by which the discrepancies are
+    /// ranked
+    fn weight(&self) -> u128 {
+        u128::from(self.num_missing_votes()) * u128::from(self.stake)
+    }
+}
+
+#[derive(Default)]
+struct VoteAccountLanding {
+    stake: u64,
+    /// Last voted slots of the unsettled votes observed on gossip
+    gossip_slots: BTreeSet<Slot>,
+    /// Last voted slots of the unsettled votes observed in replayed blocks
+    landed_slots: BTreeSet<Slot>,
+    num_gossip_votes: u64,
+    num_landed_votes: u64,
+}
+
+impl VoteAccountLanding {
+    fn insert_slot(slots: &mut BTreeSet<Slot>, slot: Slot) {
+        if slots.insert(slot) && slots.len() > MAX_PENDING_SLOTS_PER_VOTE_ACCOUNT {
+            slots.pop_first();
+        }
+    }
+
+    /// Settles the votes for the slots up to `max_slot`. A vote landed if a
+    /// vote for the same or a later slot landed.
+    fn settle(&mut self, max_slot: Slot) {
+        let gossip_slots = self.gossip_slots.split_off(&(max_slot + 1));
+        let landed_slots = self.landed_slots.split_off(&(max_slot + 1));
+        let settled_gossip_slots = std::mem::replace(&mut self.gossip_slots, gossip_slots);
+        let settled_landed_slots = std::mem::replace(&mut self.landed_slots, landed_slots);
+        // All the unsettled landed slots are later than the settled ones
+        let landed_later = !self.landed_slots.is_empty();
+        self.num_gossip_votes += settled_gossip_slots.len() as u64;
+        self.num_landed_votes += settled_gossip_slots
+            .iter()
+            .filter(|slot| landed_later || settled_landed_slots.range(**slot..).next().is_some())
+            .count() as u64;
+    }
+}
+
+#[derive(Default)]
+struct GossipVoteLandingState {
+    root: Option<Slot>,
+    /// Only the vote accounts staked in the epoch of the root are tracked
+    vote_accounts: HashMap<Pubkey, VoteAccountLanding>,
+    last_report: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct GossipVoteLandingTracker {
+    state: Mutex<GossipVoteLandingState>,
+}
+
+impl GossipVoteLandingTracker {
+    /// Records a vote observed on gossip, or in a replayed block
+    pub(crate) fn record_vote(&self, vote_account: &Pubkey, last_vote_slot: Slot, is_gossip: bool) {
+        let mut state = self.state.lock().unwrap();
+        if state.root.is_some_and(|root| last_vote_slot <= root) {
+            return;
+        }
+        let Some(landing) = state.vote_accounts.get_mut(vote_account) else {
+            return;
+        };
+        let slots = if is_gossip {
+            &mut landing.gossip_slots
+        } else {
+            &mut landing.landed_slots
+        };
+        VoteAccountLanding::insert_slot(slots, last_vote_slot);
+    }
+
+    pub(crate) fn on_new_root(&self, root_bank: &Bank) {
+        let Some(epoch_stakes) = root_bank.epoch_stakes(root_bank.epoch()) else {
+            return;
+        };
+        let stakes = epoch_stakes
+            .stakes()
+            .vote_accounts()
+            .delegated_stakes()
+            .filter(|(_, stake)| *stake > 0)
+            .map(|(vote_account, stake)| (*vote_account, stake));
+        self.settle(root_bank.slot(), stakes);
+    }
+
+    fn settle(&self, root: Slot, stakes: impl IntoIterator<Item = (Pubkey, u64)>) {
+        let mut state = self.state.lock().unwrap();
+        if state.root.is_some_and(|prev_root| root <= prev_root) {
+            return;
+        }
+        state.root = Some(root);
+        let mut vote_accounts: HashMap<_, _> = stakes
+            .into_iter()
+            .map(|(vote_account, stake)| {
+                let mut landing = state
+                    .vote_accounts
+                    .remove(&vote_account)
+                    .unwrap_or_default();
+                landing.stake = stake;
+                (vote_account, landing)
+            })
+            .collect();
+        if let Some(max_slot) = root.checked_sub(LANDING_WINDOW_SLOTS) {
+            vote_accounts
+                .values_mut()
+                .for_each(|landing| landing.settle(max_slot));
+        }
+        state.vote_accounts = vote_accounts;
+
+        let now = Instant::now();
+        match state.last_report {
+            None => state.last_report = Some(now),
+            Some(last_report) if now.duration_since(last_report) >= REPORT_INTERVAL => {
+                report(root, &discrepancies(&state.vote_accounts));
+                state.last_report = Some(now);
+            }
+            Some(_) => (),
+        }
+    }
+
+    /// Returns the vote accounts with votes observed on gossip which did not
+    /// land on chain, the one with the most missing votes weighted by stake
+    /// first
+    pub fn discrepancies(&self) -> Vec<GossipVoteDiscrepancy> {
+        discrepancies(&self.state.lock().unwrap().vote_accounts)
+    }
+}
+
+fn discrepancies(
+    vote_accounts: &HashMap<Pubkey, VoteAccountLanding>,
+) -> Vec<GossipVoteDiscrepancy> {
+    let mut discrepancies: Vec<_> = vote_accounts
+        .iter()
+        .map(|(vote_account, landing)| GossipVoteDiscrepancy {
+            vote_account: *vote_account,
+            stake: landing.stake,
+            num_gossip_votes: landing.num_gossip_votes,
+            num_landed_votes: landing.num_landed_votes,
+        })
+        .filter(|discrepancy| discrepancy.num_missing_votes() > 0)
+        .collect();
+    discrepancies.sort_unstable_by(|a, b| {
+        b.weight()
+            .cmp(&a.weight())
+            .then_with(|| a.vote_account.cmp(&b.vote_account))
+    });
+    discrepancies
+}
+
+fn report(root: Slot, discrepancies: &[GossipVoteDiscrepancy]) {
+    let missing_stake: u64 = discrepancies
+        .iter()
+        .map(|discrepancy| discrepancy.stake)
+        .sum();
+    let top = discrepancies.first();
+    datapoint_info!(
+        "gossip_vote_landing",
+        ("root", root, i64),
+        ("num_vote_accounts", discrepancies.len(), i64),
+        ("missing_stake", missing_stake, i64),
+        (
+            "top_vote_account",
+            top.map(|top| top.vote_account.to_string()),
+            Option<String>
+        ),
+        ("top_stake", top.map(|top| top.stake), Option<i64>),
+        (
+            "top_num_gossip_votes",
+            top.map(|top| top.num_gossip_votes),
+            Option<i64>
+        ),
+        (
+            "top_num_landed_votes",
+            top.map(|top| top.num_landed_votes),
+            Option<i64>
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gossip_vote_landing() {
+        let tracker = GossipVoteLandingTracker::default();
+        let landing = Pubkey::new_unique();
+        let withholding = Pubkey::new_unique();
+        let heavy = Pubkey::new_unique();
+        let unstaked = Pubkey::new_unique();
+        let stakes = [(landing, 100), (withholding, 10), (heavy, 1_000)];
+        tracker.settle(0, stakes);
+
+        for slot in 1..=4 {
+            tracker.record_vote(&landing, slot, true);
+            tracker.record_vote(&landing, slot, false);
+            tracker.record_vote(&withholding, slot, true);
+            tracker.record_vote(&unstaked, slot, true);
+        }
+        // Only the votes of `withholding` for slots 1 and 3 land, the latter
+        // also covering its claim for slot 2 as the tower moved on, so only
+        // the claim for slot 4 is missing. A vote landing without being
+        // observed on gossip is not a claim
+        tracker.record_vote(&withholding, 1, false);
+        tracker.record_vote(&withholding, 3, false);
+        tracker.record_vote(&heavy, 5, false);
+
+        // Nothing is settled until the root is past the landing window
+        tracker.settle(LANDING_WINDOW_SLOTS, stakes);
+        assert!(tracker.discrepancies().is_empty());
+
+        // Up to slot 2, covered by the landed votes for slots 1 and 3
+        tracker.settle(LANDING_WINDOW_SLOTS + 2, stakes);
+        assert!(tracker.discrepancies().is_empty());
+
+        tracker.settle(LANDING_WINDOW_SLOTS + 10, stakes);
+        assert_eq!(
+            tracker.discrepancies(),
+            vec![GossipVoteDiscrepancy {
+                vote_account: withholding,
+                stake: 10,
+                num_gossip_votes: 4,
+                num_landed_votes: 3,
+            }]
+        );
+        let landing_state = &tracker.state.lock().unwrap().vote_accounts[&landing];
+        assert_eq!(landing_state.num_gossip_votes, 4);
+        assert_eq!(landing_state.num_landed_votes, 4);
+        assert!(landing_state.gossip_slots.is_empty());
+        assert!(landing_state.landed_slots.is_empty());
+    }
+
+    #[test]
+    fn test_gossip_vote_landing_ranking() {
+        let tracker = GossipVoteLandingTracker::default();
+        let light = Pubkey::new_unique();
+        let heavy = Pubkey::new_unique();
+        let stakes = [(light, 1), (heavy, 5)];
+        tracker.settle(0, stakes);
+        // 10 missing votes of 1 stake rank below 3 missing votes of 5 stake
+        for slot in 1..=10 {
+            tracker.record_vote(&light, slot, true);
+        }
+        for slot in 1..=3 {
+            tracker.record_vote(&heavy, slot, true);
+        }
+        // Votes for rooted slots are ignored
+        tracker.settle(5, stakes);
+        tracker.record_vote(&light, 5, true);
+        tracker.settle(LANDING_WINDOW_SLOTS + 10, stakes);
+        let discrepancies = tracker.discrepancies();
+        assert_eq!(
+            discrepancies
+                .iter()
+                .map(|discrepancy| (discrepancy.vote_account, discrepancy.num_missing_votes()))
+                .collect::<Vec<_>>(),
+            vec![(heavy, 3), (light, 10)]
+        );
+
+        // Vote accounts no longer staked are dropped
+        tracker.settle(LANDING_WINDOW_SLOTS + 11, [(heavy, 5)]);
+        assert_eq!(tracker.discrepancies().len(), 1);
+        assert!(
+            tracker
+                .state
+                .lock()
+                .unwrap()
+                .vote_accounts
+                .get(&light)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_gossip_vote_landing_is_bounded() {
+        let tracker = GossipVoteLandingTracker::default();
+        let vote_account = Pubkey::new_unique();
+        tracker.settle(0, [(vote_account, 1)]);
+        for slot in 1..=MAX_PENDING_SLOTS_PER_VOTE_ACCOUNT as Slot + 1 {
+            tracker.record_vote(&vote_account, slot, true);
+        }
+        let state = tracker.state.lock().unwrap();
+        let gossip_slots = &state.vote_accounts[&vote_account].gossip_slots;
+        assert_eq!(gossip_slots.len(), MAX_PENDING_SLOTS_PER_VOTE_ACCOUNT);
+        assert_eq!(gossip_slots.first(), Some(&2));
+    }
+}
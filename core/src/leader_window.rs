@@ -0,0 +1,75 @@
+//! Where PoH stands relative to the upcoming leader slots of this node, for the
+//! services that hold back work around them.
+
+use {solana_clock::Slot, solana_poh::poh_recorder::LeaderState};
+
+/// The slot PoH is ticking through and the next leader slots of this node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LeaderWindow {
+    pub(crate) slot: Slot,
+    pub(crate) next_leader_slot_range: Option<(Slot, Slot)>,
+}
+
+impl LeaderWindow {
+    pub(crate) fn new(leader_state: &LeaderState, ticks_per_slot: u64) -> Self {
+        Self {
+            slot: leader_state.tick_height() / ticks_per_slot,
+            next_leader_slot_range: leader_state.next_leader_slot_range(),
+        }
+    }
+
+    /// Number of slots until the next leader slot of this node, 0 once it is
+    /// reached, or None if the node has no upcoming leader slot
+    pub(crate) fn slots_to_leader(&self) -> Option<u64> {
+        self.next_leader_slot_range
+            .map(|(first_slot, _)| first_slot.saturating_sub(self.slot))
+    }
+
+    /// Whether the next leader slots of this node start within `lead_slots`,
+    /// or are underway
+    pub(crate) fn is_within(&self, lead_slots: u64) -> bool {
+        self.next_leader_slot_range
+            .is_some_and(|(first_slot, last_slot)| {
+                (first_slot.saturating_sub(lead_slots)..=last_slot).contains(&self.slot)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leader_window(slot: Slot, next_leader_slot_range: Option<(Slot, Slot)>) -> LeaderWindow {
+        LeaderWindow {
+            slot,
+            next_leader_slot_range,
+        }
+    }
+
+    #[test]
+    fn test_leader_window_new() {
+        let leader_state = LeaderState::new(None, 700, None, Some((14, 17)));
+        assert_eq!(
+            LeaderWindow::new(&leader_state, 64),
+            leader_window(10, Some((14, 17)))
+        );
+    }
+
+    #[test]
+    fn test_slots_to_leader() {
+        assert_eq!(leader_window(10, None).slots_to_leader(), None);
+        assert_eq!(leader_window(10, Some((14, 17))).slots_to_leader(), Some(4));
+        assert_eq!(leader_window(10, Some((10, 13))).slots_to_leader(), Some(0));
+        assert_eq!(leader_window(10, Some((8, 11))).slots_to_leader(), Some(0));
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(!leader_window(10, None).is_within(4));
+        assert!(!leader_window(9, Some((14, 17))).is_within(4));
+        assert!(leader_window(10, Some((14, 17))).is_within(4));
+        assert!(leader_window(17, Some((14, 17))).is_within(4));
+        assert!(!leader_window(18, Some((14, 17))).is_within(4));
+        assert!(leader_window(0, Some((2, 5))).is_within(4));
+    }
+}
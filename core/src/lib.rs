@@ -8,7 +8,9 @@
 //! command-line tools to spin up validators and a Rust library
 //!
 
+pub mod accounts_db_manifest;
 pub mod admin_rpc_post_init;
+pub mod advertised_addresses;
 pub mod banking_simulation;
 pub mod banking_stage;
 pub mod banking_trace;
@@ -16,19 +18,25 @@ pub(crate) mod block_creation_loop;
 pub mod cluster_info_vote_listener;
 pub mod cluster_slots_service;
 pub mod commitment_service;
+pub mod compaction_scheduler;
 pub mod completed_data_sets_service;
 pub mod consensus;
 pub mod cost_update_service;
+pub mod deterministic_rng;
 pub mod drop_bank_service;
 pub mod epoch_specs;
 pub mod fetch_stage;
 pub mod forwarding_stage;
 pub mod gen_keys;
+pub mod gossip_vote_landing;
+mod leader_window;
 pub mod next_leader;
 pub mod optimistic_confirmation_verifier;
+pub mod optimistic_confirmation_violations;
 pub mod repair;
 pub mod replay_stage;
 pub mod resource_limits;
+pub mod restart_latency_tracker;
 mod result;
 pub mod sample_performance_service;
 #[cfg(unix)]
@@ -36,17 +44,24 @@ mod scheduler_bindings_server;
 mod shred_fetch_stage;
 pub mod sigverify;
 pub mod sigverify_stage;
+pub mod slot_gap_classifier;
+pub mod slot_resource_accounting;
 pub mod snapshot_packager_service;
 pub mod staked_nodes_updater_service;
 pub mod stats_reporter_service;
 pub mod system_monitor_service;
 pub mod tpu;
+pub mod tpu_config_analysis;
 mod tpu_entry_notifier;
+pub mod tpu_ingest_self_test;
 mod transaction_priority;
 pub mod tvu;
 pub mod unfrozen_gossip_verified_vote_hashes;
 pub mod validator;
+pub mod vote_account_monitor;
+pub mod vote_capture;
 pub mod vote_simulator;
+pub mod vote_tracker_archive;
 pub mod voting_service;
 pub mod warm_quic_cache_service;
 pub mod window_service;
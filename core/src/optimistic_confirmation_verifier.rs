@@ -1,5 +1,8 @@
 use {
-    crate::cluster_info_vote_listener::VoteTracker,
+    crate::{
+        cluster_info_vote_listener::VoteTracker,
+        optimistic_confirmation_violations::OptimisticConfirmationViolation,
+    },
     solana_clock::Slot,
     solana_hash::Hash,
     solana_ledger::blockstore::Blockstore,
@@ -89,12 +92,14 @@ impl OptimisticConfirmationVerifier {
         format!("Optimistically confirmed slot {slot} was not rooted")
     }
 
+    // Returns the violations, along with the stake that had confirmed them
     pub fn log_unrooted_optimistic_slots(
         root_bank: &Bank,
         vote_tracker: &VoteTracker,
         unrooted_optimistic_slots: &[(Slot, Hash)],
-    ) {
+    ) -> Vec<OptimisticConfirmationViolation> {
         let root = root_bank.slot();
+        let mut violations = Vec::with_capacity(unrooted_optimistic_slots.len());
         for (optimistic_slot, hash) in unrooted_optimistic_slots.iter() {
             let epoch = root_bank.epoch_schedule().get_epoch(*optimistic_slot);
             let epoch_stakes = root_bank.epoch_stakes(epoch);
@@ -131,7 +136,15 @@ impl OptimisticConfirmationVerifier {
                 ("voted_stake", voted_stake, i64),
                 ("total_epoch_stake", total_epoch_stake, i64),
             );
+            violations.push(OptimisticConfirmationViolation {
+                slot: *optimistic_slot,
+                epoch,
+                hash: *hash,
+                detected_at: timestamp(),
+                confirmed_stake: voted_stake,
+            });
         }
+        violations
     }
 }
 
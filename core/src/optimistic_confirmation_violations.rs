@@ -0,0 +1,387 @@
+//! A persistent record of optimistic confirmation violations, i.e.
+//! optimistically confirmed slots that failed to become rooted.
+//!
+//! Violations are appended, one JSON object per line, to a file under the
+//! ledger path and loaded back on startup, so that the per-epoch report
+//! survives restarts. The file is rotated once it grows past
+//! `MAX_VIOLATIONS_FILE_BYTES`, keeping a single previous file around.
+
+use {
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    solana_clock::{Epoch, Slot},
+    solana_hash::Hash,
+    std::{
+        collections::BTreeMap,
+        fs::{self, File, OpenOptions},
+        io::{self, BufRead, BufReader, BufWriter, Write},
+        path::{Path, PathBuf},
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// Name of the file, under the ledger path, that violations are appended to
+pub const OPTIMISTIC_CONFIRMATION_VIOLATIONS_FILE: &str =
+    "optimistic_confirmation_violations.jsonl";
+const MAX_VIOLATIONS_FILE_BYTES: u64 = 1024 * 1024;
+// Violations are buffered and written out at most this often
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+// Violations beyond this many waiting to be written are dropped
+const MAX_PENDING_VIOLATIONS: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptimisticConfirmationViolation {
+    pub slot: Slot,
+    pub epoch: Epoch,
+    #[serde(
+        serialize_with = "serialize_hash",
+        deserialize_with = "deserialize_hash"
+    )]
+    pub hash: Hash,
+    /// Milliseconds since the UNIX epoch at which the violation was detected
+    pub detected_at: u64,
+    /// Stake that had optimistically confirmed `hash`
+    pub confirmed_stake: u64,
+}
+
+fn serialize_hash<S: Serializer>(hash: &Hash, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(hash)
+}
+
+fn deserialize_hash<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+    let hash = String::deserialize(deserializer)?;
+    hash.parse().map_err(serde::de::Error::custom)
+}
+
+/// Violations detected for slots of a single epoch
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OptimisticConfirmationViolationSummary {
+    pub epoch: Epoch,
+    pub num_violations: usize,
+    pub first_slot: Slot,
+    pub last_slot: Slot,
+    /// Largest stake that had confirmed any of the violating slots
+    pub max_confirmed_stake: u64,
+}
+
+impl OptimisticConfirmationViolationSummary {
+    fn new(violation: &OptimisticConfirmationViolation) -> Self {
+        Self {
+            epoch: violation.epoch,
+            num_violations: 1,
+            first_slot: violation.slot,
+            last_slot: violation.slot,
+            max_confirmed_stake: violation.confirmed_stake,
+        }
+    }
+
+    fn add(&mut self, violation: &OptimisticConfirmationViolation) {
+        self.num_violations += 1;
+        self.first_slot = self.first_slot.min(violation.slot);
+        self.last_slot = self.last_slot.max(violation.slot);
+        self.max_confirmed_stake = self.max_confirmed_stake.max(violation.confirmed_stake);
+    }
+
+    fn report(&self) {
+        datapoint_warn!(
+            "optimistic_confirmation_violations",
+            ("epoch", self.epoch, i64),
+            ("num_violations", self.num_violations, i64),
+            ("first_slot", self.first_slot, i64),
+            ("last_slot", self.last_slot, i64),
+            ("max_confirmed_stake", self.max_confirmed_stake, i64),
+        );
+    }
+}
+
+#[derive(Default)]
+struct ViolationLogState {
+    summaries: BTreeMap<Epoch, OptimisticConfirmationViolationSummary>,
+    pending: Vec<OptimisticConfirmationViolation>,
+    num_dropped: usize,
+    last_write: Option<Instant>,
+    root_epoch: Option<Epoch>,
+}
+
+pub struct OptimisticConfirmationViolationLog {
+    path: PathBuf,
+    write_interval: Duration,
+    state: Mutex<ViolationLogState>,
+}
+
+impl OptimisticConfirmationViolationLog {
+    /// Appends violations to `OPTIMISTIC_CONFIRMATION_VIOLATIONS_FILE` under
+    /// `ledger_path`, loading the violations already recorded there
+    pub fn new(ledger_path: &Path) -> Self {
+        Self::new_with_write_interval(ledger_path, WRITE_INTERVAL)
+    }
+
+    fn new_with_write_interval(ledger_path: &Path, write_interval: Duration) -> Self {
+        let path = ledger_path.join(OPTIMISTIC_CONFIRMATION_VIOLATIONS_FILE);
+        let mut state = ViolationLogState::default();
+        for path in [rotated_path(&path), path.clone()] {
+            match load_violations(&path) {
+                Ok(violations) => violations
+                    .iter()
+                    .for_each(|violation| state.add_to_summary(violation)),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => warn!(
+                    "Failed to load optimistic confirmation violations from {}: {err}",
+                    path.display()
+                ),
+            }
+        }
+        Self {
+            path,
+            write_interval,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn record(&self, violations: Vec<OptimisticConfirmationViolation>) {
+        if violations.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        for violation in violations {
+            state.add_to_summary(&violation);
+            if state.pending.len() < MAX_PENDING_VIOLATIONS {
+                state.pending.push(violation);
+            } else {
+                state.num_dropped += 1;
+            }
+        }
+        self.maybe_write(&mut state);
+    }
+
+    /// Writes out any pending violations, unless the last write was less
+    /// than the write interval ago. Emits the summary of the previous epoch once
+    /// `root_epoch` advances past it.
+    pub fn on_new_root(&self, root_epoch: Epoch) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(prev_root_epoch) = state.root_epoch.replace(root_epoch) {
+            if root_epoch > prev_root_epoch {
+                if let Some(summary) = state.summaries.get(&prev_root_epoch) {
+                    summary.report();
+                }
+            }
+        }
+        self.maybe_write(&mut state);
+    }
+
+    /// Returns the per-epoch summaries, in epoch order
+    pub fn report(&self) -> Vec<OptimisticConfirmationViolationSummary> {
+        self.state
+            .lock()
+            .unwrap()
+            .summaries
+            .values()
+            .copied()
+            .collect()
+    }
+
+    fn maybe_write(&self, state: &mut ViolationLogState) {
+        if state.pending.is_empty()
+            || state
+                .last_write
+                .is_some_and(|last_write| last_write.elapsed() < self.write_interval)
+        {
+            return;
+        }
+        self.write(state);
+    }
+
+    fn write(&self, state: &mut ViolationLogState) {
+        state.last_write = Some(Instant::now());
+        let violations = std::mem::take(&mut state.pending);
+        if let Err(err) = self.append(&violations) {
+            warn!(
+                "Failed to write {} optimistic confirmation violations to {}: {err}",
+                violations.len(),
+                self.path.display()
+            );
+        }
+        if state.num_dropped > 0 {
+            warn!(
+                "Dropped {} optimistic confirmation violations",
+                std::mem::take(&mut state.num_dropped)
+            );
+        }
+    }
+
+    fn append(&self, violations: &[OptimisticConfirmationViolation]) -> io::Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() > MAX_VIOLATIONS_FILE_BYTES)
+        {
+            fs::rename(&self.path, rotated_path(&self.path))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut writer = BufWriter::new(file);
+        for violation in violations {
+            serde_json::to_writer(&mut writer, violation)?;
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+}
+
+impl Drop for OptimisticConfirmationViolationLog {
+    fn drop(&mut self) {
+        let mut state = std::mem::take(self.state.get_mut().unwrap());
+        if !state.pending.is_empty() {
+            self.write(&mut state);
+        }
+    }
+}
+
+impl ViolationLogState {
+    fn add_to_summary(&mut self, violation: &OptimisticConfirmationViolation) {
+        self.summaries
+            .entry(violation.epoch)
+            .and_modify(|summary| summary.add(violation))
+            .or_insert_with(|| OptimisticConfirmationViolationSummary::new(violation));
+    }
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    path.with_extension("jsonl.1")
+}
+
+fn load_violations(path: &Path) -> io::Result<Vec<OptimisticConfirmationViolation>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut violations = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        match serde_json::from_str(&line) {
+            Ok(violation) => violations.push(violation),
+            // A partially written last line is expected after a crash
+            Err(err) => warn!(
+                "Skipping malformed optimistic confirmation violation in {}: {err}",
+                path.display()
+            ),
+        }
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tempfile::TempDir};
+
+    fn violation(
+        slot: Slot,
+        epoch: Epoch,
+        confirmed_stake: u64,
+    ) -> OptimisticConfirmationViolation {
+        OptimisticConfirmationViolation {
+            slot,
+            epoch,
+            hash: Hash::new_unique(),
+            detected_at: slot * 400,
+            confirmed_stake,
+        }
+    }
+
+    #[test]
+    fn test_violations_survive_restart() {
+        let ledger_path = TempDir::new().unwrap();
+        let log = OptimisticConfirmationViolationLog::new_with_write_interval(
+            ledger_path.path(),
+            Duration::MAX,
+        );
+        assert!(log.report().is_empty());
+
+        let v1 = violation(10, 0, 500);
+        log.record(vec![v1.clone()]);
+        // Rate limited, so written out on drop
+        let v2 = violation(40, 1, 700);
+        log.record(vec![v2.clone()]);
+        assert_eq!(log.state.lock().unwrap().pending, vec![v2.clone()]);
+        drop(log);
+
+        let contents = fs::read_to_string(
+            ledger_path
+                .path()
+                .join(OPTIMISTIC_CONFIRMATION_VIOLATIONS_FILE),
+        )
+        .unwrap();
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            serde_json::from_str::<OptimisticConfirmationViolation>(lines[0]).unwrap(),
+            v1
+        );
+        assert!(lines[1].contains(&format!("\"hash\":\"{}\"", v2.hash)));
+
+        let log = OptimisticConfirmationViolationLog::new(ledger_path.path());
+        log.record(vec![violation(12, 0, 900)]);
+        assert_eq!(
+            log.report(),
+            vec![
+                OptimisticConfirmationViolationSummary {
+                    epoch: 0,
+                    num_violations: 2,
+                    first_slot: 10,
+                    last_slot: 12,
+                    max_confirmed_stake: 900,
+                },
+                OptimisticConfirmationViolationSummary::new(&v2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violations_write_interval() {
+        let ledger_path = TempDir::new().unwrap();
+        let log = OptimisticConfirmationViolationLog::new_with_write_interval(
+            ledger_path.path(),
+            Duration::ZERO,
+        );
+        log.record(vec![violation(10, 0, 500)]);
+        log.record(vec![violation(40, 1, 700)]);
+        assert!(log.state.lock().unwrap().pending.is_empty());
+        assert_eq!(load_violations(&log.path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_violations_rotation() {
+        let ledger_path = TempDir::new().unwrap();
+        let path = ledger_path
+            .path()
+            .join(OPTIMISTIC_CONFIRMATION_VIOLATIONS_FILE);
+        let log = OptimisticConfirmationViolationLog::new(ledger_path.path());
+        let mut state = ViolationLogState::default();
+        let mut slot = 0;
+        while fs::metadata(&path).map_or(0, |metadata| metadata.len()) <= MAX_VIOLATIONS_FILE_BYTES
+        {
+            state.pending = (slot..slot + 1024)
+                .map(|slot| violation(slot, 0, 1))
+                .collect();
+            log.write(&mut state);
+            slot += 1024;
+        }
+        let last_violation = violation(slot, 0, 1);
+        state.pending = vec![last_violation.clone()];
+        log.write(&mut state);
+        assert!(rotated_path(&path).exists());
+        assert_eq!(load_violations(&path).unwrap(), vec![last_violation]);
+        drop(log);
+
+        // Both the rotated and the current file are loaded on startup
+        let log = OptimisticConfirmationViolationLog::new(ledger_path.path());
+        let report = log.report();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].num_violations, slot as usize + 1);
+    }
+
+    #[test]
+    fn test_violations_io_error_is_not_fatal() {
+        let ledger_path = TempDir::new().unwrap();
+        let log = OptimisticConfirmationViolationLog::new(&ledger_path.path().join("missing"));
+        log.record(vec![violation(10, 0, 500)]);
+        assert!(log.state.lock().unwrap().pending.is_empty());
+        assert_eq!(log.report().len(), 1);
+    }
+}
@@ -756,7 +756,7 @@ impl AncestorHashesService {
                 ancestor_hashes_request_socket,
                 &repair_info.cluster_slots,
                 serve_repair,
-                &repair_info.repair_validators,
+                &repair_info.repair_validators.read().unwrap(),
                 slot,
                 repair_stats,
                 outstanding_requests,
@@ -1362,8 +1362,9 @@ mod test {
                 cluster_slots: Arc::new(ClusterSlots::default_for_tests()),
                 epoch_schedule,
                 ancestor_duplicate_slots_sender,
-                repair_validators: None,
+                repair_validators: Arc::default(),
                 repair_whitelist,
+                deterministic_rng_seed: None,
             };
 
             let (ancestor_hashes_replay_update_sender, ancestor_hashes_replay_update_receiver) =
@@ -1496,7 +1497,7 @@ mod test {
             &ancestor_hashes_request_socket,
             &cluster_slots,
             &requester_serve_repair,
-            &repair_validators,
+            &repair_validators.read().unwrap(),
             dead_slot,
             &mut repair_stats,
             &outstanding_requests,
@@ -1546,7 +1547,7 @@ mod test {
             &ancestor_hashes_request_socket,
             &cluster_slots,
             &requester_serve_repair,
-            &repair_validators,
+            &repair_validators.read().unwrap(),
             dead_slot,
             &mut repair_stats,
             &outstanding_requests,
@@ -1606,7 +1607,7 @@ mod test {
             &ancestor_hashes_request_socket,
             &cluster_slots,
             &requester_serve_repair,
-            &repair_validators,
+            &repair_validators.read().unwrap(),
             dead_slot,
             &mut repair_stats,
             &outstanding_requests,
@@ -11,10 +11,14 @@ mod stats;
 use {
     super::{
         repair_service::OutstandingShredRepairs,
-        serve_repair::{REPAIR_PEERS_CACHE_CAPACITY, RepairPeers, ServeRepair, ShredRepairType},
+        serve_repair::{
+            REPAIR_PEERS_CACHE_CAPACITY, RepairPeers, ServeRepair, ShredRepairType,
+            refresh_repair_peers_cache,
+        },
         standard_repair_handler::StandardRepairHandler,
     },
     crate::{
+        deterministic_rng::{BLOCK_ID_REPAIR_RNG_LABEL, subsystem_rng, subsystem_rng_seed},
         repair::{
             outstanding_requests::OutstandingRequests,
             packet_threshold::DynamicPacketToProcessThreshold,
@@ -31,6 +35,7 @@ use {
     crossbeam_channel::select,
     lazy_lru::LruCache,
     log::{debug, info},
+    rand::RngCore,
     solana_clock::Slot,
     solana_gossip::ping_pong::{Ping, Pong},
     solana_keypair::signable::Signable,
@@ -165,6 +170,10 @@ struct RepairState {
     serve_repair: ServeRepair,
     /// Repair peers cache
     peers_cache: LruCache<u64, RepairPeers>,
+    /// Repair validators the entries of `peers_cache` were sampled from
+    repair_validators: Option<HashSet<Pubkey>>,
+    /// Samples the peers to send the requests to
+    rng: Box<dyn RngCore>,
 
     /// Metadata requests sent to the cluster
     outstanding_requests: OutstandingBlockIdRepairs,
@@ -325,6 +334,16 @@ impl BlockIdRepairService {
                         migration_status,
                     ),
                     peers_cache: LruCache::new(REPAIR_PEERS_CACHE_CAPACITY),
+                    repair_validators: context
+                        .repair_info
+                        .repair_validators
+                        .read()
+                        .unwrap()
+                        .clone(),
+                    rng: subsystem_rng(subsystem_rng_seed(
+                        context.repair_info.deterministic_rng_seed,
+                        BLOCK_ID_REPAIR_RNG_LABEL,
+                    )),
                     outstanding_requests: OutstandingBlockIdRepairs::default(),
                     outstanding_shred_requests: context.outstanding_shred_requests.clone(),
                     pending_repair_requests: BinaryHeap::default(),
@@ -931,6 +950,11 @@ impl BlockIdRepairService {
         root: Slot,
         state: &mut RepairState,
     ) {
+        refresh_repair_peers_cache(
+            &repair_info.repair_validators,
+            &mut state.repair_validators,
+            &mut state.peers_cache,
+        );
         let pending_count = state.pending_repair_requests.len();
         let max_batch_len = pending_count.min(MAX_REPAIR_REQUESTS_PER_ITERATION);
         let mut block_id_socket_batch: Vec<(Vec<u8>, SocketAddr)> =
@@ -957,6 +981,7 @@ impl BlockIdRepairService {
                     let Ok((bytes, addr, peer_pubkey)) = state
                         .serve_repair
                         .block_id_repair_request(
+                            &mut state.rng,
                             repair_info,
                             block_id_repair_type,
                             &mut state.peers_cache,
@@ -992,6 +1017,7 @@ impl BlockIdRepairService {
                     let Ok(Some((addr, bytes))) = state
                         .serve_repair
                         .repair_request(
+                            &mut state.rng,
                             repair_info,
                             shred_request,
                             &mut state.peers_cache,
@@ -1144,6 +1170,8 @@ mod tests {
         let state = RepairState {
             serve_repair,
             peers_cache: LruCache::new(REPAIR_PEERS_CACHE_CAPACITY),
+            repair_validators: None,
+            rng: Box::new(rand::rng()),
             outstanding_requests: OutstandingBlockIdRepairs::default(),
             outstanding_shred_requests: Arc::new(RwLock::new(OutstandingShredRepairs::default())),
             pending_repair_requests: BinaryHeap::default(),
@@ -9,10 +9,12 @@ pub mod repair_generic_traversal;
 pub mod repair_handler;
 pub mod repair_response;
 pub mod repair_service;
+pub mod repair_state_snapshot;
 pub mod repair_weight;
 pub mod repair_weighted_traversal;
 pub mod request_response;
 pub mod result;
+pub mod secondary_repair_handler;
 pub mod serve_repair;
 pub mod serve_repair_service;
 pub(crate) mod standard_repair_handler;
@@ -3,12 +3,53 @@ use {
     lazy_lru::LruCache,
     rand::{Rng, rng},
     solana_ledger::shred::Nonce,
+    solana_pubkey::Pubkey,
 };
 
 pub const DEFAULT_REQUEST_EXPIRATION_MS: u64 = 60_000;
 
+/// Number of peers whose recent response outcomes are kept
+const MAX_TRACKED_PEERS: usize = 4 * 1024;
+
+/// The response outcomes of a peer are halved every interval, so that they
+/// reflect the recent requests sent to the peer
+const PEER_OUTCOMES_DECAY_INTERVAL_MS: u64 = DEFAULT_REQUEST_EXPIRATION_MS;
+
+/// Recent outcomes of the responses to the requests sent to a peer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeerOutcomes {
+    /// Responses accepted
+    pub num_successes: u64,
+    /// Responses rejected, as invalid or past the expiration of the request
+    pub num_failures: u64,
+    decayed_at: u64,
+}
+
+impl PeerOutcomes {
+    fn new(now: u64) -> Self {
+        Self {
+            num_successes: 0,
+            num_failures: 0,
+            decayed_at: now,
+        }
+    }
+
+    /// Returns the outcomes as of `now`
+    pub fn decayed(mut self, now: u64) -> Self {
+        let num_intervals = now.saturating_sub(self.decayed_at) / PEER_OUTCOMES_DECAY_INTERVAL_MS;
+        if num_intervals > 0 {
+            let shift = num_intervals.min(u64::from(u64::BITS - 1)) as u32;
+            self.num_successes >>= shift;
+            self.num_failures >>= shift;
+            self.decayed_at += num_intervals * PEER_OUTCOMES_DECAY_INTERVAL_MS;
+        }
+        self
+    }
+}
+
 pub struct OutstandingRequests<T, U = ()> {
     requests: LruCache<Nonce, RequestStatus<T, U>>,
+    peer_outcomes: LruCache<Pubkey, PeerOutcomes>,
 }
 
 impl<T, S: ?Sized, U> OutstandingRequests<T, U>
@@ -28,6 +69,18 @@ where
         request: T,
         now: u64,
         metadata: Option<U>,
+    ) -> Nonce {
+        self.add_request_for_peer(request, None, now, metadata)
+    }
+
+    /// Similar to `add_request_with_metadata` but additionally records the
+    /// peer the request is sent to, accounting the responses to the peer
+    pub fn add_request_for_peer(
+        &mut self,
+        request: T,
+        peer: Option<Pubkey>,
+        now: u64,
+        metadata: Option<U>,
     ) -> Nonce {
         let num_expected_responses = request.num_expected_responses();
         let nonce = rng().random_range(0..Nonce::MAX);
@@ -38,6 +91,7 @@ where
                 num_expected_responses,
                 request,
                 metadata,
+                peer,
             },
         );
         nonce
@@ -65,6 +119,7 @@ where
         success_fn: impl Fn(&T) -> R,
     ) -> Option<R> {
         let mut should_delete = false;
+        let mut outcome = None;
         let response = self.requests.get_mut(&nonce).and_then(|status| {
             if status.num_expected_responses == 0 {
                 // No more expected responses
@@ -74,8 +129,10 @@ where
             if now >= status.expire_timestamp || !status.request.verify_response(response) {
                 // Invalid/expired response should invalidate this nonce.
                 should_delete = true;
+                outcome = status.peer.map(|peer| (peer, false));
                 return None;
             }
+            outcome = status.peer.map(|peer| (peer, true));
 
             status.num_expected_responses -= 1;
             if status.num_expected_responses == 0 && status.metadata.is_none() {
@@ -90,9 +147,55 @@ where
                 .pop(&nonce)
                 .expect("request must exist when marked for deletion");
         }
+        if let Some((peer, success)) = outcome {
+            self.record_peer_outcome(peer, success, now);
+        }
         response
     }
 
+    fn record_peer_outcome(&mut self, peer: Pubkey, success: bool, now: u64) {
+        if !self.peer_outcomes.contains_key(&peer) {
+            self.peer_outcomes.put(peer, PeerOutcomes::new(now));
+        }
+        let outcomes = self.peer_outcomes.get_mut(&peer).unwrap();
+        *outcomes = outcomes.decayed(now);
+        if success {
+            outcomes.num_successes += 1;
+        } else {
+            outcomes.num_failures += 1;
+        }
+    }
+
+    /// Requests which have not expired and still expect responses, along
+    /// with the peer they were sent to, if recorded
+    pub fn unexpired_requests(&self, now: u64) -> impl Iterator<Item = (&T, Option<&Pubkey>)> {
+        self.requests
+            .iter()
+            .map(|(_nonce, status)| status)
+            .filter(move |status| {
+                now < status.expire_timestamp && status.num_expected_responses > 0
+            })
+            .map(|status| (&status.request, status.peer.as_ref()))
+    }
+
+    /// Recent outcomes of the responses to the requests sent to each peer, as
+    /// of `now`
+    pub fn peer_outcomes(&self, now: u64) -> impl Iterator<Item = (&Pubkey, PeerOutcomes)> {
+        self.peer_outcomes
+            .iter()
+            .map(move |(peer, outcomes)| (peer, outcomes.decayed(now)))
+    }
+
+    /// Number of requests in the cache, including expired ones which have not
+    /// been evicted yet
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
     /// Fetches metadata associated with the nonce
     pub fn fetch_metadata_for_nonce(&self, nonce: u32) -> Option<U>
     where
@@ -107,6 +210,7 @@ impl<T, U> Default for OutstandingRequests<T, U> {
     fn default() -> Self {
         Self {
             requests: LruCache::new(16 * 1024),
+            peer_outcomes: LruCache::new(MAX_TRACKED_PEERS),
         }
     }
 }
@@ -116,6 +220,8 @@ pub struct RequestStatus<T, U> {
     num_expected_responses: u32,
     request: T,
     metadata: Option<U>,
+    /// Peer the request was sent to, if recorded
+    peer: Option<Pubkey>,
 }
 
 #[cfg(test)]
@@ -123,6 +229,7 @@ pub(crate) mod tests {
     use {
         super::*,
         crate::repair::{request_response::RequestResponse, serve_repair::ShredRepairType},
+        itertools::Itertools,
         solana_hash::Hash,
         solana_keypair::Keypair,
         solana_ledger::{blockstore_meta::BlockLocation, shred::Shredder},
@@ -311,4 +418,65 @@ pub(crate) mod tests {
             Some(BlockLocation::Alternate { block_id })
         );
     }
+
+    #[test]
+    fn test_peer_outcomes() {
+        let mut outstanding_requests = OutstandingRequests::<TestRequest>::default();
+        let now = timestamp();
+        let peer = Pubkey::new_unique();
+        let request = TestRequest {
+            expected_response: 42,
+            num_expected_responses: 2,
+        };
+        let nonce = outstanding_requests.add_request_for_peer(request, Some(peer), now, None);
+        // Requests sent to an unrecorded peer are not accounted
+        outstanding_requests.add_request(request, now);
+        assert_eq!(
+            outstanding_requests
+                .unexpired_requests(now)
+                .map(|(_request, peer)| peer.copied())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec![None, Some(peer)]
+        );
+        assert_eq!(
+            outstanding_requests
+                .unexpired_requests(now + DEFAULT_REQUEST_EXPIRATION_MS)
+                .count(),
+            0
+        );
+
+        assert!(
+            outstanding_requests
+                .register_response(nonce, &42, now, |_| ())
+                .is_some()
+        );
+        assert!(
+            outstanding_requests
+                .register_response(nonce, &7, now, |_| ())
+                .is_none()
+        );
+        let outcomes: Vec<_> = outstanding_requests.peer_outcomes(now).collect();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].0, &peer);
+        assert_eq!(
+            (outcomes[0].1.num_successes, outcomes[0].1.num_failures),
+            (1, 1)
+        );
+
+        // The outcomes fade away as the requests get older
+        for _ in 0..4 {
+            let nonce = outstanding_requests.add_request_for_peer(request, Some(peer), now, None);
+            assert!(
+                outstanding_requests
+                    .register_response(nonce, &42, now, |_| ())
+                    .is_some()
+            );
+        }
+        let (_, outcomes) = outstanding_requests
+            .peer_outcomes(now + PEER_OUTCOMES_DECAY_INTERVAL_MS)
+            .next()
+            .unwrap();
+        assert_eq!((outcomes.num_successes, outcomes.num_failures), (2, 0));
+    }
 }
@@ -2,6 +2,7 @@ use {
     super::{
         malicious_repair_handler::{MaliciousRepairConfig, MaliciousRepairHandler},
         repair_response::repair_response_packet_from_bytes,
+        secondary_repair_handler::SecondaryRepairHandler,
         serve_repair::ServeRepair,
         standard_repair_handler::StandardRepairHandler,
     },
@@ -29,6 +30,7 @@ use {
         collections::HashSet,
         net::SocketAddr,
         sync::{Arc, RwLock},
+        time::Duration,
     },
 };
 
@@ -225,6 +227,8 @@ pub trait RepairHandler {
     }
 }
 
+pub const DEFAULT_REPAIR_SERVE_BLOCKSTORE_CATCH_UP_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Clone, Debug, Default)]
 pub enum RepairHandlerType {
     #[default]
@@ -250,9 +254,30 @@ impl RepairHandlerType {
         }
     }
 
+    /// Creates the handler for `blockstore`, which serves requests from
+    /// `secondary_blockstore` instead, if any, for the slots it has in full.
+    /// `secondary_blockstore` must be opened with `AccessType::Secondary`.
+    pub fn to_handler_with_secondary(
+        &self,
+        blockstore: Arc<Blockstore>,
+        secondary_blockstore: Option<Arc<Blockstore>>,
+        identity: Arc<Keypair>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
+    ) -> Box<dyn RepairHandler + Send + Sync> {
+        let primary = self.to_handler(blockstore, identity.clone(), leader_schedule_cache.clone());
+        match secondary_blockstore {
+            None => primary,
+            Some(secondary_blockstore) => Box::new(SecondaryRepairHandler::new(
+                primary,
+                self.to_handler(secondary_blockstore, identity, leader_schedule_cache),
+            )),
+        }
+    }
+
     pub fn create_serve_repair(
         &self,
         blockstore: Arc<Blockstore>,
+        secondary_blockstore: Option<Arc<Blockstore>>,
         cluster_info: Arc<ClusterInfo>,
         sharable_banks: SharableBanks,
         serve_repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>,
@@ -265,7 +290,12 @@ impl RepairHandlerType {
             cluster_info,
             sharable_banks,
             serve_repair_whitelist,
-            self.to_handler(blockstore, identity_keypair, leader_schedule_cache),
+            self.to_handler_with_secondary(
+                blockstore,
+                secondary_blockstore,
+                identity_keypair,
+                leader_schedule_cache,
+            ),
             leader_state,
             migration_status,
         )
@@ -3,7 +3,9 @@
 use {
     super::standard_repair_handler::StandardRepairHandler,
     crate::{
+        cluster_info_vote_listener::VerifiedVoteLatencyStats,
         cluster_slots_service::cluster_slots::ClusterSlots,
+        deterministic_rng::{REPAIR_RNG_LABEL, subsystem_rng, subsystem_rng_seed},
         repair::{
             ancestor_hashes_service::{
                 AncestorHashesChannels, AncestorHashesReplayUpdateReceiver, AncestorHashesService,
@@ -13,14 +15,14 @@ use {
             repair_weight::RepairWeight,
             serve_repair::{
                 REPAIR_PEERS_CACHE_CAPACITY, RepairPeers, RepairProtocol, RepairRequestHeader,
-                ServeRepair, ShredRepairType,
+                ServeRepair, ShredRepairType, refresh_repair_peers_cache,
             },
         },
     },
     agave_votor_messages::{VerifiedVoterSlotsReceiver, migration::MigrationStatus},
     crossbeam_channel::{Receiver as CrossbeamReceiver, Sender as CrossbeamSender},
     lazy_lru::LruCache,
-    rand::prelude::IndexedRandom as _,
+    rand::{Rng, RngCore, prelude::IndexedRandom as _},
     solana_client::connection_cache::Protocol,
     solana_clock::Slot,
     solana_epoch_schedule::EpochSchedule,
@@ -319,6 +321,7 @@ pub struct RepairMetrics {
     pub stats: RepairStats,
     pub best_repairs_stats: BestRepairsStats,
     pub timing: RepairTiming,
+    pub verified_vote_latency: VerifiedVoteLatencyStats,
     pub last_report: Instant,
 }
 
@@ -328,6 +331,7 @@ impl Default for RepairMetrics {
             stats: RepairStats::default(),
             best_repairs_stats: BestRepairsStats::default(),
             timing: RepairTiming::default(),
+            verified_vote_latency: VerifiedVoteLatencyStats::default(),
             last_report: Instant::now(),
         }
     }
@@ -341,6 +345,8 @@ impl RepairMetrics {
             self.stats.report();
             self.timing.report();
             self.best_repairs_stats.report();
+            self.verified_vote_latency
+                .report("repair_service-verified_vote_latency");
             *self = Self::default();
         }
     }
@@ -548,10 +554,12 @@ pub struct RepairInfo {
     pub cluster_slots: Arc<ClusterSlots>,
     pub epoch_schedule: EpochSchedule,
     pub ancestor_duplicate_slots_sender: AncestorDuplicateSlotsSender,
-    // Validators from which repairs are requested
-    pub repair_validators: Option<HashSet<Pubkey>>,
+    // Validators from which repairs are requested, may be updated at runtime
+    pub repair_validators: Arc<RwLock<Option<HashSet<Pubkey>>>>,
     // Validators which should be given priority when serving
     pub repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>,
+    // Base seed of the RNGs sampling the repair peers, see deterministic_rng
+    pub deterministic_rng_seed: Option<u64>,
 }
 
 pub struct RepairSlotRange {
@@ -605,10 +613,14 @@ struct RepairTracker {
     serve_repair: ServeRepair,
     repair_metrics: RepairMetrics,
     peers_cache: LruCache<u64, RepairPeers>,
+    // The repair_validators the entries of peers_cache were sampled from
+    repair_validators: Option<HashSet<Pubkey>>,
     popular_pruned_forks_requests: HashSet<Slot>,
     // Maps a repair that may still be outstanding to the timestamp it was requested.
     outstanding_repairs: HashMap<ShredRepairType, u64>,
     repair_eligibility: RepairEligibility,
+    // Samples the peers to send the repairs to
+    rng: Box<dyn RngCore>,
 }
 
 pub struct RepairService {
@@ -659,6 +671,24 @@ impl RepairService {
         }
     }
 
+    // Drains the verified votes, grouping the voters by voted slot.
+    fn receive_verified_votes(
+        verified_voter_slots_receiver: &VerifiedVoterSlotsReceiver,
+        verified_vote_latency: &mut VerifiedVoteLatencyStats,
+    ) -> HashMap<Slot, Vec<Pubkey>> {
+        let mut slot_to_vote_pubkeys: HashMap<Slot, Vec<Pubkey>> = HashMap::new();
+        for (vote_pubkey, vote_slots, observed_at) in verified_voter_slots_receiver.try_iter() {
+            verified_vote_latency.record(observed_at);
+            for slot in vote_slots {
+                slot_to_vote_pubkeys
+                    .entry(slot)
+                    .or_default()
+                    .push(vote_pubkey);
+            }
+        }
+        slot_to_vote_pubkeys
+    }
+
     fn update_weighting_heuristic(
         blockstore: &Blockstore,
         root_bank: Arc<Bank>,
@@ -705,17 +735,10 @@ impl RepairService {
 
         // Add new votes to the weighting heuristic
         let mut get_votes_elapsed = Measure::start("get_votes_elapsed");
-        let mut slot_to_vote_pubkeys: HashMap<Slot, Vec<Pubkey>> = HashMap::new();
-        verified_voter_slots_receiver
-            .try_iter()
-            .for_each(|(vote_pubkey, vote_slots)| {
-                for slot in vote_slots {
-                    slot_to_vote_pubkeys
-                        .entry(slot)
-                        .or_default()
-                        .push(vote_pubkey);
-                }
-            });
+        let slot_to_vote_pubkeys = Self::receive_verified_votes(
+            verified_voter_slots_receiver,
+            &mut repair_metrics.verified_vote_latency,
+        );
         get_votes_elapsed.stop();
 
         let mut add_voters_elapsed = Measure::start("add_voters");
@@ -799,6 +822,7 @@ impl RepairService {
 
     fn build_and_send_repair_batch(
         serve_repair: &mut ServeRepair,
+        rng: &mut impl Rng,
         peers_cache: &mut LruCache<u64, RepairPeers>,
         repairs: Vec<ShredRepairType>,
         repair_info: &RepairInfo,
@@ -814,6 +838,7 @@ impl RepairService {
                 .filter_map(|repair_request| {
                     let (to, req) = serve_repair
                         .repair_request(
+                            rng,
                             repair_info,
                             repair_request,
                             peers_cache,
@@ -868,12 +893,20 @@ impl RepairService {
             serve_repair,
             repair_metrics,
             peers_cache,
+            repair_validators,
             popular_pruned_forks_requests,
             outstanding_repairs,
             repair_eligibility,
+            rng,
         } = repair_tracker;
         let root_bank = sharable_banks.root();
 
+        refresh_repair_peers_cache(
+            &repair_info.repair_validators,
+            repair_validators,
+            peers_cache,
+        );
+
         Self::update_weighting_heuristic(
             blockstore,
             root_bank.clone(),
@@ -906,6 +939,7 @@ impl RepairService {
 
         Self::build_and_send_repair_batch(
             serve_repair,
+            rng,
             peers_cache,
             repairs,
             repair_info,
@@ -945,9 +979,14 @@ impl RepairService {
             },
             repair_metrics: RepairMetrics::default(),
             peers_cache: LruCache::new(REPAIR_PEERS_CACHE_CAPACITY),
+            repair_validators: repair_info.repair_validators.read().unwrap().clone(),
             popular_pruned_forks_requests: HashSet::new(),
             outstanding_repairs: HashMap::new(),
             repair_eligibility: RepairEligibility::default(),
+            rng: subsystem_rng(subsystem_rng_seed(
+                repair_info.deterministic_rng_seed,
+                REPAIR_RNG_LABEL,
+            )),
         };
 
         while !exit.load(Ordering::Relaxed) {
@@ -1151,7 +1190,12 @@ impl RepairService {
         let nonce = outstanding_repair_requests
             .write()
             .unwrap()
-            .add_request_with_metadata(repair_request, timestamp(), Some(BlockLocation::Original));
+            .add_request_for_peer(
+                repair_request,
+                Some(pubkey),
+                timestamp(),
+                Some(BlockLocation::Original),
+            );
 
         // Create repair request
         let header =
@@ -1289,8 +1333,9 @@ impl RepairService {
                             .map_or(BlockLocation::Original, |block_id| {
                                 BlockLocation::Alternate { block_id }
                             });
-                        let nonce = outstanding_requests.add_request_with_metadata(
+                        let nonce = outstanding_requests.add_request_for_peer(
                             repair_type,
+                            Some(repair_pubkey),
                             timestamp(),
                             Some(location),
                         );
@@ -1421,6 +1466,30 @@ mod test {
         ClusterInfo::new(contact_info, keypair, SocketAddrSpace::Unspecified)
     }
 
+    #[test]
+    fn test_receive_verified_votes() {
+        const DELAY: Duration = Duration::from_millis(50);
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let voter0 = Pubkey::new_unique();
+        let voter1 = Pubkey::new_unique();
+        // Simulate votes which sat in a backed-up channel
+        let observed_at = Instant::now().checked_sub(DELAY).unwrap();
+        sender.send((voter0, vec![1, 2], observed_at)).unwrap();
+        sender.send((voter1, vec![2], observed_at)).unwrap();
+
+        let mut verified_vote_latency = VerifiedVoteLatencyStats::default();
+        let slot_to_vote_pubkeys =
+            RepairService::receive_verified_votes(&receiver, &mut verified_vote_latency);
+        assert_eq!(
+            slot_to_vote_pubkeys,
+            HashMap::from([(1, vec![voter0]), (2, vec![voter0, voter1])])
+        );
+        assert_eq!(verified_vote_latency.num_votes(), 2);
+        // Allow for the precision of the histogram buckets
+        let min_latency_us = DELAY.as_micros() as u64 * 99 / 100;
+        assert!(verified_vote_latency.max_latency_us() >= min_latency_us);
+    }
+
     #[test]
     pub fn test_request_repair_for_shred_from_address() {
         // Setup cluster and repair info
@@ -0,0 +1,288 @@
+//! A consistent view of the repair state of the node, for the admin RPC.
+//!
+//! The repair whitelist, the outstanding repair requests and the cluster slots
+//! are updated independently of one another, so reading them one after the
+//! other may e.g. show a repair outstanding for a peer no longer whitelisted.
+//! A snapshot takes the read locks of the whitelist and of the outstanding
+//! requests, in this order, and looks the cluster slots up while holding both.
+
+use {
+    crate::{
+        cluster_slots_service::cluster_slots::ClusterSlots,
+        repair::repair_service::OutstandingShredRepairs,
+    },
+    serde::{Deserialize, Serialize},
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    solana_time_utils::timestamp,
+    std::{
+        collections::{BTreeMap, HashSet},
+        sync::{Arc, RwLock},
+    },
+};
+
+/// Maximum number of groups of outstanding requests in a snapshot, the groups
+/// of the lowest slots being kept
+pub const MAX_OUTSTANDING_REPAIR_GROUPS: usize = 1_024;
+
+/// Takes a snapshot of the repair state of the node
+pub type RepairStateSnapshotFn = dyn Fn() -> RepairStateSnapshot + Send + Sync;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepairStateSnapshot {
+    /// Milliseconds since the UNIX epoch at which the snapshot was taken
+    pub timestamp: u64,
+    /// The repair whitelist, sorted
+    pub repair_whitelist: Vec<Pubkey>,
+    /// Number of outstanding shred repair requests, including the ones left
+    /// out of `outstanding_requests`
+    pub num_outstanding_requests: usize,
+    /// The outstanding shred repair requests grouped by slot and peer, sorted
+    pub outstanding_requests: Vec<OutstandingRepairGroup>,
+    /// Recent outcomes of the responses of each peer repairs were requested
+    /// from, sorted by peer
+    pub peer_outcomes: Vec<RepairPeerOutcomes>,
+    /// Peers claiming to have each slot of `outstanding_requests` in cluster
+    /// slots, sorted by slot
+    pub slot_claims: Vec<SlotClaims>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutstandingRepairGroup {
+    pub slot: Slot,
+    /// Peer the requests were sent to, None if not recorded
+    pub peer: Option<Pubkey>,
+    pub num_requests: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepairPeerOutcomes {
+    pub peer: Pubkey,
+    pub num_successes: u64,
+    pub num_failures: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotClaims {
+    pub slot: Slot,
+    /// Sorted
+    pub peers: Vec<Pubkey>,
+}
+
+pub fn snapshot_repair_state(
+    repair_whitelist: &RwLock<HashSet<Pubkey>>,
+    outstanding_repair_requests: &RwLock<OutstandingShredRepairs>,
+    cluster_slots: &ClusterSlots,
+) -> RepairStateSnapshot {
+    let repair_whitelist = repair_whitelist.read().unwrap();
+    let outstanding_repair_requests = outstanding_repair_requests.read().unwrap();
+    let now = timestamp();
+
+    let mut groups = BTreeMap::<(Slot, Option<Pubkey>), usize>::new();
+    let mut num_outstanding_requests = 0;
+    for (request, peer) in outstanding_repair_requests.unexpired_requests(now) {
+        num_outstanding_requests += 1;
+        *groups.entry((request.slot(), peer.copied())).or_default() += 1;
+    }
+    let outstanding_requests: Vec<_> = groups
+        .into_iter()
+        .take(MAX_OUTSTANDING_REPAIR_GROUPS)
+        .map(|((slot, peer), num_requests)| OutstandingRepairGroup {
+            slot,
+            peer,
+            num_requests,
+        })
+        .collect();
+
+    let mut peer_outcomes: Vec<_> = outstanding_repair_requests
+        .peer_outcomes(now)
+        .map(|(peer, outcomes)| RepairPeerOutcomes {
+            peer: *peer,
+            num_successes: outcomes.num_successes,
+            num_failures: outcomes.num_failures,
+        })
+        .collect();
+    peer_outcomes.sort_unstable_by_key(|outcomes| outcomes.peer);
+
+    let mut slots: Vec<_> = outstanding_requests
+        .iter()
+        .map(|group| group.slot)
+        .collect();
+    slots.dedup();
+    let slot_claims = slots
+        .into_iter()
+        .map(|slot| {
+            let mut peers: Vec<_> = cluster_slots
+                .lookup(slot)
+                .map(|supporters| supporters.keys().copied().collect())
+                .unwrap_or_default();
+            peers.sort_unstable();
+            SlotClaims { slot, peers }
+        })
+        .collect();
+
+    let mut repair_whitelist: Vec<_> = repair_whitelist.iter().copied().collect();
+    repair_whitelist.sort_unstable();
+    RepairStateSnapshot {
+        timestamp: now,
+        repair_whitelist,
+        num_outstanding_requests,
+        outstanding_requests,
+        peer_outcomes,
+        slot_claims,
+    }
+}
+
+/// Returns the function registered with the admin RPC to take snapshots of
+/// the repair state
+pub fn repair_state_snapshot_fn(
+    repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>,
+    outstanding_repair_requests: Arc<RwLock<OutstandingShredRepairs>>,
+    cluster_slots: Arc<ClusterSlots>,
+) -> Arc<RepairStateSnapshotFn> {
+    Arc::new(move || {
+        snapshot_repair_state(
+            &repair_whitelist,
+            &outstanding_repair_requests,
+            &cluster_slots,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::repair::serve_repair::ShredRepairType,
+        solana_keypair::Keypair,
+        solana_ledger::{blockstore_meta::BlockLocation, shred::Shredder},
+        std::collections::HashMap,
+    };
+
+    #[test]
+    fn test_snapshot_repair_state() {
+        let whitelisted = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let repair_whitelist = Arc::new(RwLock::new(HashSet::from([whitelisted])));
+        let cluster_slots = Arc::new(ClusterSlots::default_for_tests());
+        cluster_slots.fake_epoch_info_for_tests(HashMap::from([(whitelisted, 42), (other, 7)]));
+        cluster_slots.insert_node_id(5, whitelisted);
+        cluster_slots.insert_node_id(5, other);
+        cluster_slots.insert_node_id(9, other);
+
+        let outstanding_repair_requests = Arc::new(RwLock::new(OutstandingShredRepairs::default()));
+        let now = timestamp();
+        let answered_nonce = {
+            let mut outstanding_repair_requests = outstanding_repair_requests.write().unwrap();
+            for shred_index in 0..3 {
+                outstanding_repair_requests.add_request_for_peer(
+                    ShredRepairType::Shred(5, shred_index),
+                    Some(whitelisted),
+                    now,
+                    Some(BlockLocation::Original),
+                );
+            }
+            outstanding_repair_requests.add_request_for_peer(
+                ShredRepairType::Shred(5, 3),
+                Some(other),
+                now,
+                Some(BlockLocation::Original),
+            );
+            outstanding_repair_requests.add_request(ShredRepairType::HighestShred(9, 0), now);
+            outstanding_repair_requests.add_request_for_peer(
+                ShredRepairType::Shred(7, 0),
+                Some(other),
+                now,
+                None,
+            )
+        };
+        // A response for another slot counts as a failure of the peer, and
+        // drops the request
+        let shred = Shredder::single_shred_for_tests(8, &Keypair::new());
+        assert!(
+            outstanding_repair_requests
+                .write()
+                .unwrap()
+                .register_response(answered_nonce, shred.payload(), now, |_| ())
+                .is_none()
+        );
+
+        let snapshot = repair_state_snapshot_fn(
+            repair_whitelist.clone(),
+            outstanding_repair_requests.clone(),
+            cluster_slots.clone(),
+        )();
+        assert_eq!(snapshot.repair_whitelist, vec![whitelisted]);
+        assert_eq!(snapshot.num_outstanding_requests, 5);
+        let mut expected_groups = vec![
+            OutstandingRepairGroup {
+                slot: 5,
+                peer: Some(whitelisted),
+                num_requests: 3,
+            },
+            OutstandingRepairGroup {
+                slot: 5,
+                peer: Some(other),
+                num_requests: 1,
+            },
+        ];
+        expected_groups.sort_unstable_by_key(|group| (group.slot, group.peer));
+        expected_groups.push(OutstandingRepairGroup {
+            slot: 9,
+            peer: None,
+            num_requests: 1,
+        });
+        assert_eq!(snapshot.outstanding_requests, expected_groups);
+        // The groups account for all the outstanding requests
+        assert_eq!(
+            snapshot
+                .outstanding_requests
+                .iter()
+                .map(|group| group.num_requests)
+                .sum::<usize>(),
+            snapshot.num_outstanding_requests
+        );
+        assert_eq!(
+            snapshot.peer_outcomes,
+            vec![RepairPeerOutcomes {
+                peer: other,
+                num_successes: 0,
+                num_failures: 1,
+            }]
+        );
+        // The claims cover exactly the slots with outstanding requests
+        let mut claimers = vec![whitelisted, other];
+        claimers.sort_unstable();
+        assert_eq!(
+            snapshot.slot_claims,
+            vec![
+                SlotClaims {
+                    slot: 5,
+                    peers: claimers,
+                },
+                SlotClaims {
+                    slot: 9,
+                    peers: vec![other],
+                },
+            ]
+        );
+
+        // The snapshot round trips through the admin RPC encoding
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RepairStateSnapshot>(&json).unwrap(),
+            snapshot
+        );
+
+        // Clearing the whitelist is reflected in the next snapshot only
+        repair_whitelist.write().unwrap().clear();
+        assert_eq!(snapshot.repair_whitelist, vec![whitelisted]);
+        let snapshot = snapshot_repair_state(
+            &repair_whitelist,
+            &outstanding_repair_requests,
+            &cluster_slots,
+        );
+        assert!(snapshot.repair_whitelist.is_empty());
+        assert_eq!(snapshot.num_outstanding_requests, 5);
+    }
+}
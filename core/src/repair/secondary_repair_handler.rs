@@ -0,0 +1,338 @@
+//! Serves repairs from a secondary blockstore, to offload reads from the
+//! primary blockstore written by the validator.
+//!
+//! The secondary follows the primary by periodically catching up with it in
+//! the background. Requests for slots the secondary does not have in full
+//! yet, such as slots the primary is still receiving shreds for, are served by
+//! the primary.
+
+use {
+    super::repair_handler::RepairHandler,
+    solana_clock::Slot,
+    solana_hash::Hash,
+    solana_ledger::{blockstore::Blockstore, shred::Nonce},
+    solana_measure::measure_us,
+    solana_perf::packet::{Packet, PacketBatch, PacketBatchRecycler},
+    solana_time_utils::AtomicInterval,
+    std::{
+        net::SocketAddr,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        thread::{self, Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+const STATS_REPORT_INTERVAL_MS: u64 = 10_000;
+
+#[derive(Default)]
+struct SecondaryRepairStats {
+    secondary_requests: AtomicU64,
+    primary_fallbacks: AtomicU64,
+}
+
+impl SecondaryRepairStats {
+    fn report(&self) {
+        datapoint_info!(
+            "serve_repair-secondary_blockstore",
+            (
+                "secondary_requests",
+                self.secondary_requests.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "primary_fallbacks",
+                self.primary_fallbacks.swap(0, Ordering::Relaxed),
+                i64
+            ),
+        );
+    }
+}
+
+pub struct SecondaryRepairHandler {
+    primary: Box<dyn RepairHandler + Send + Sync>,
+    secondary: Box<dyn RepairHandler + Send + Sync>,
+    last_report: AtomicInterval,
+    stats: SecondaryRepairStats,
+}
+
+impl SecondaryRepairHandler {
+    /// `secondary` must be backed by a blockstore opened with
+    /// `AccessType::Secondary`, which is caught up with the primary by a
+    /// `SecondaryCatchUpService`.
+    pub fn new(
+        primary: Box<dyn RepairHandler + Send + Sync>,
+        secondary: Box<dyn RepairHandler + Send + Sync>,
+    ) -> Self {
+        Self {
+            primary,
+            secondary,
+            last_report: AtomicInterval::default(),
+            stats: SecondaryRepairStats::default(),
+        }
+    }
+
+    /// Returns the handler to serve a request for `slot`: the secondary if it
+    /// has the slot in full, otherwise the primary.
+    fn handler(&self, slot: Slot) -> &(dyn RepairHandler + Send + Sync) {
+        if self.last_report.should_update(STATS_REPORT_INTERVAL_MS) {
+            self.stats.report();
+        }
+        let is_full = self
+            .secondary
+            .blockstore()
+            .meta(slot)
+            .ok()
+            .flatten()
+            .is_some_and(|meta| meta.is_full());
+        if is_full {
+            self.stats
+                .secondary_requests
+                .fetch_add(1, Ordering::Relaxed);
+            self.secondary.as_ref()
+        } else {
+            self.stats.primary_fallbacks.fetch_add(1, Ordering::Relaxed);
+            self.primary.as_ref()
+        }
+    }
+}
+
+/// Catches a blockstore opened with `AccessType::Secondary` up with the
+/// primary in the background, so that serving repairs never waits on it.
+pub struct SecondaryCatchUpService {
+    t_catch_up: JoinHandle<()>,
+}
+
+impl SecondaryCatchUpService {
+    pub fn new(
+        blockstore: Arc<Blockstore>,
+        catch_up_interval: Duration,
+        exit: Arc<AtomicBool>,
+    ) -> Self {
+        let t_catch_up = Builder::new()
+            .name("solRprCatchUp".to_string())
+            .spawn(move || {
+                info!("SecondaryCatchUpService has started");
+                while !exit.load(Ordering::Relaxed) {
+                    thread::sleep(catch_up_interval);
+                    let (result, catch_up_us) = measure_us!(blockstore.try_catch_up_with_primary());
+                    if let Err(err) = &result {
+                        warn!(
+                            "Secondary repair blockstore failed to catch up with the primary: \
+                             {err}"
+                        );
+                    }
+                    datapoint_info!(
+                        "serve_repair-secondary_blockstore_catch_up",
+                        ("catch_up_us", catch_up_us, i64),
+                        ("error", result.is_err(), bool),
+                    );
+                }
+                info!("SecondaryCatchUpService has stopped");
+            })
+            .unwrap();
+        Self { t_catch_up }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.t_catch_up.join()
+    }
+}
+
+impl RepairHandler for SecondaryRepairHandler {
+    fn blockstore(&self) -> &Blockstore {
+        self.primary.blockstore()
+    }
+
+    fn repair_response_packet(
+        &self,
+        slot: Slot,
+        shred_index: u64,
+        dest: &SocketAddr,
+        nonce: Nonce,
+    ) -> Option<Packet> {
+        self.handler(slot)
+            .repair_response_packet(slot, shred_index, dest, nonce)
+    }
+
+    fn run_window_request(
+        &self,
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        slot: Slot,
+        shred_index: u64,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        self.handler(slot)
+            .run_window_request(recycler, from_addr, slot, shred_index, nonce)
+    }
+
+    fn run_window_request_for_block_id(
+        &self,
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        slot: Slot,
+        shred_index: u64,
+        block_id: Hash,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        self.handler(slot).run_window_request_for_block_id(
+            recycler,
+            from_addr,
+            slot,
+            shred_index,
+            block_id,
+            nonce,
+        )
+    }
+
+    fn run_highest_window_request(
+        &self,
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        slot: Slot,
+        highest_index: u64,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        self.handler(slot).run_highest_window_request(
+            recycler,
+            from_addr,
+            slot,
+            highest_index,
+            nonce,
+        )
+    }
+
+    fn run_orphan(
+        &self,
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        slot: Slot,
+        max_responses: usize,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        self.handler(slot)
+            .run_orphan(recycler, from_addr, slot, max_responses, nonce)
+    }
+
+    fn run_ancestor_hashes(
+        &self,
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        slot: Slot,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        self.handler(slot)
+            .run_ancestor_hashes(recycler, from_addr, slot, nonce)
+    }
+
+    fn run_parent_fec_set_count(
+        &self,
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        slot: Slot,
+        block_id: Hash,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        self.handler(slot)
+            .run_parent_fec_set_count(recycler, from_addr, slot, block_id, nonce)
+    }
+
+    fn run_fec_set_root(
+        &self,
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        slot: Slot,
+        block_id: Hash,
+        fec_set_index: u32,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        self.handler(slot).run_fec_set_root(
+            recycler,
+            from_addr,
+            slot,
+            block_id,
+            fec_set_index,
+            nonce,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::repair::standard_repair_handler::StandardRepairHandler,
+        solana_ledger::{
+            blockstore::make_slot_entries,
+            blockstore_options::{AccessType, BlockstoreOptions},
+            get_tmp_ledger_path_auto_delete,
+        },
+        std::net::{IpAddr, Ipv4Addr},
+        tempfile::TempDir,
+    };
+
+    #[test]
+    fn test_secondary_repair_handler() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let primary = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+        let (shreds, _) = make_slot_entries(1, 0, 10);
+        primary.insert_shreds(shreds, None, false).unwrap();
+
+        let secondary_path = TempDir::new().unwrap();
+        let secondary = Arc::new(
+            Blockstore::open_with_options(
+                ledger_path.path(),
+                BlockstoreOptions {
+                    access_type: AccessType::Secondary {
+                        secondary_path: secondary_path.path().to_path_buf(),
+                    },
+                    ..BlockstoreOptions::default()
+                },
+            )
+            .unwrap(),
+        );
+        let handler = SecondaryRepairHandler::new(
+            Box::new(StandardRepairHandler::new(primary.clone())),
+            Box::new(StandardRepairHandler::new(secondary.clone())),
+        );
+        let dest = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080);
+        let assert_response = |slot| {
+            let shred = primary.get_data_shred(slot, 0).unwrap().unwrap();
+            let packet = handler.repair_response_packet(slot, 0, &dest, 42).unwrap();
+            assert_eq!(packet.data(..shred.len()).unwrap(), &shred[..]);
+        };
+
+        // Slots the secondary has in full are served from the secondary
+        assert_response(1);
+        assert_eq!(handler.stats.secondary_requests.load(Ordering::Relaxed), 1);
+        assert_eq!(handler.stats.primary_fallbacks.load(Ordering::Relaxed), 0);
+
+        // Freshly inserted slots fall back to the primary until the secondary
+        // catches up
+        let (shreds, _) = make_slot_entries(2, 1, 10);
+        primary.insert_shreds(shreds, None, false).unwrap();
+        assert!(secondary.meta(2).unwrap().is_none());
+        assert_response(2);
+        assert_eq!(handler.stats.secondary_requests.load(Ordering::Relaxed), 1);
+        assert_eq!(handler.stats.primary_fallbacks.load(Ordering::Relaxed), 1);
+
+        // Serving requests does not catch the secondary up
+        assert!(secondary.meta(2).unwrap().is_none());
+
+        let exit = Arc::new(AtomicBool::new(false));
+        let catch_up_service =
+            SecondaryCatchUpService::new(secondary.clone(), Duration::ZERO, exit.clone());
+        while secondary.meta(2).unwrap().is_none() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        exit.store(true, Ordering::Relaxed);
+        catch_up_service.join().unwrap();
+        assert!(secondary.meta(2).unwrap().unwrap().is_full());
+        assert_response(2);
+        assert_eq!(handler.stats.secondary_requests.load(Ordering::Relaxed), 2);
+        assert_eq!(handler.stats.primary_fallbacks.load(Ordering::Relaxed), 1);
+    }
+}
@@ -98,6 +98,9 @@ const REPAIR_PING_CACHE_RATE_LIMIT_DELAY: Duration = Duration::from_secs(2);
 pub(crate) const REPAIR_RESPONSE_SERIALIZED_PING_BYTES: usize =
     4 /*enum discriminator*/ + PUBKEY_BYTES + REPAIR_PING_TOKEN_SIZE + SIGNATURE_BYTES;
 const SIGNED_REPAIR_TIME_WINDOW: Duration = Duration::from_secs(60 * 10); // 10 min
+// One in this many served requests is reserved for unstaked requesters, so
+// that staked requesters can not starve them.
+const UNSTAKED_REQUESTS_FAIRNESS_INTERVAL: usize = 8;
 
 #[cfg(test)]
 static_assertions::const_assert_eq!(MAX_ANCESTOR_RESPONSES, 30);
@@ -339,6 +342,8 @@ struct ServeRepairStats {
     dropped_requests_load_shed_sigverify: usize,
     dropped_requests_low_stake: usize,
     whitelisted_requests: usize,
+    reordered_requests: usize,
+    max_reorder_depth: usize,
     total_dropped_response_packets: usize,
     total_response_packets: usize,
     total_response_bytes_staked: usize,
@@ -687,6 +692,21 @@ impl RepairPeers {
     }
 }
 
+/// Clears `peers_cache` if the live `repair_validators` set no longer matches
+/// the set the cached repair peers were sampled from, so that a set updated
+/// at runtime takes effect on the next repair iteration.
+pub(crate) fn refresh_repair_peers_cache(
+    repair_validators: &RwLock<Option<HashSet<Pubkey>>>,
+    cached_repair_validators: &mut Option<HashSet<Pubkey>>,
+    peers_cache: &mut LruCache<Slot, RepairPeers>,
+) {
+    let repair_validators = repair_validators.read().unwrap();
+    if *repair_validators != *cached_repair_validators {
+        cached_repair_validators.clone_from(&repair_validators);
+        *peers_cache = LruCache::new(REPAIR_PEERS_CACHE_CAPACITY);
+    }
+}
+
 struct RepairRequestWithMeta {
     request: RepairProtocol,
     from_addr: SocketAddr,
@@ -1110,6 +1130,50 @@ impl ServeRepair {
         requests.into_iter().filter_map(decode_request).collect()
     }
 
+    /// Orders requests by the requester's stake, whitelisted requesters first,
+    /// keeping at most `max_requests` of them.
+    ///
+    /// Every `UNSTAKED_REQUESTS_FAIRNESS_INTERVAL`-th slot is reserved for an
+    /// unstaked requester, in arrival order, so that they are neither dropped
+    /// nor left without outbound data budget whenever staked requests are
+    /// queued. Requests are otherwise served in arrival order among equals.
+    fn prioritize_requests(
+        requests: Vec<RepairRequestWithMeta>,
+        max_requests: usize,
+        stats: &mut ServeRepairStats,
+    ) -> Vec<RepairRequestWithMeta> {
+        let num_requests = requests.len();
+        let (mut prioritized, unstaked): (Vec<_>, Vec<_>) = requests
+            .into_iter()
+            .enumerate()
+            .partition(|(_, request)| request.whitelisted || request.stake > 0);
+        prioritized.sort_by_key(|(_, request)| Reverse((request.whitelisted, request.stake)));
+        let mut prioritized = prioritized.into_iter().peekable();
+        let mut unstaked = unstaked.into_iter().peekable();
+
+        let mut out = Vec::with_capacity(num_requests.min(max_requests));
+        while out.len() < max_requests {
+            let is_unstaked_turn = out.len() % UNSTAKED_REQUESTS_FAIRNESS_INTERVAL
+                == UNSTAKED_REQUESTS_FAIRNESS_INTERVAL - 1;
+            let next = if is_unstaked_turn || prioritized.peek().is_none() {
+                unstaked.next().or_else(|| prioritized.next())
+            } else {
+                prioritized.next()
+            };
+            let Some((arrival_index, request)) = next else {
+                break;
+            };
+            let reorder_depth = arrival_index.saturating_sub(out.len());
+            if reorder_depth > 0 {
+                stats.reordered_requests += 1;
+                stats.max_reorder_depth = stats.max_reorder_depth.max(reorder_depth);
+            }
+            out.push(request);
+        }
+        stats.dropped_requests_low_stake += num_requests - out.len();
+        out
+    }
+
     /// Process messages from the network
     fn run_listen(
         &mut self,
@@ -1181,7 +1245,7 @@ impl ServeRepair {
         };
 
         let decode_start = Instant::now();
-        let mut decoded_requests = {
+        let decoded_requests = {
             // Estimate how much data budget we have left, 2x margin to prioritize staked,
             // apply byte cost multiplier here so we can operate in bytes inside decode_requests
             let effective_data_budget_estimate =
@@ -1202,11 +1266,8 @@ impl ServeRepair {
         stats.decode_time_us += decode_start.elapsed().as_micros() as u64;
         stats.whitelisted_requests += whitelisted_request_count.min(MAX_REQUESTS_PER_ITERATION);
 
-        if decoded_requests.len() > MAX_REQUESTS_PER_ITERATION {
-            stats.dropped_requests_low_stake += decoded_requests.len() - MAX_REQUESTS_PER_ITERATION;
-            decoded_requests.sort_unstable_by_key(|r| Reverse((r.whitelisted, r.stake)));
-            decoded_requests.truncate(MAX_REQUESTS_PER_ITERATION);
-        }
+        let decoded_requests =
+            Self::prioritize_requests(decoded_requests, MAX_REQUESTS_PER_ITERATION, stats);
 
         let handle_requests_start = Instant::now();
         self.handle_requests(
@@ -1256,6 +1317,8 @@ impl ServeRepair {
                 i64
             ),
             ("whitelisted_requests", stats.whitelisted_requests, i64),
+            ("reordered_requests", stats.reordered_requests, i64),
+            ("max_reorder_depth", stats.max_reorder_depth, i64),
             (
                 "total_dropped_response_packets",
                 stats.total_dropped_response_packets,
@@ -1612,6 +1675,7 @@ impl ServeRepair {
     /// For Alpenglow blocks we do a weighted selection where the weights are `peer_stake`
     pub(crate) fn repair_request(
         &self,
+        rng: &mut impl Rng,
         repair_info: &RepairInfo,
         repair_request: ShredRepairType,
         peers_cache: &mut LruCache<Slot, RepairPeers>,
@@ -1626,12 +1690,12 @@ impl ServeRepair {
         let repair_peers = self.repair_peers_from_cache(
             slot,
             &repair_info.cluster_slots,
-            &repair_info.repair_validators,
+            &repair_info.repair_validators.read().unwrap(),
             peers_cache,
             &identity_keypair,
             weight_source,
         )?;
-        let peer = repair_peers.sample(&mut rand::rng());
+        let peer = repair_peers.sample(rng);
         let location = repair_request
             .block_id()
             // Eager repair uses the Original blockstore column,
@@ -1640,8 +1704,9 @@ impl ServeRepair {
             .map_or(BlockLocation::Original, |block_id| {
                 BlockLocation::Alternate { block_id }
             });
-        let nonce = outstanding_requests.add_request_with_metadata(
+        let nonce = outstanding_requests.add_request_for_peer(
             repair_request,
+            Some(peer.pubkey),
             timestamp(),
             Some(location),
         );
@@ -1665,6 +1730,7 @@ impl ServeRepair {
     /// Only for use in Alpenglow blocks, peer selection is based on `peer_stake`
     pub(crate) fn block_id_repair_request(
         &self,
+        rng: &mut impl Rng,
         repair_info: &RepairInfo,
         repair_request: BlockIdRepairType,
         peers_cache: &mut LruCache<Slot, RepairPeers>,
@@ -1676,12 +1742,12 @@ impl ServeRepair {
         let repair_peers = self.repair_peers_from_cache(
             slot,
             &repair_info.cluster_slots,
-            &repair_info.repair_validators,
+            &repair_info.repair_validators.read().unwrap(),
             peers_cache,
             &identity_keypair,
             weight_source,
         )?;
-        let peer = repair_peers.sample(&mut rand::rng());
+        let peer = repair_peers.sample(rng);
         let nonce = outstanding_requests.add_request(repair_request, timestamp());
 
         let out = self.map_block_id_repair_request(
@@ -2058,6 +2124,58 @@ mod tests {
         PacketRef::from(packet).to_bytes_packet()
     }
 
+    #[test]
+    fn test_prioritize_requests() {
+        let from_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8080));
+        // Interleaved requests from a high stake, an unstaked and a low stake
+        // requester, with the slot being the arrival index
+        let make_requests = || -> Vec<_> {
+            (0..12)
+                .map(|slot| RepairRequestWithMeta {
+                    request: RepairProtocol::Orphan {
+                        header: repair_request_header_for_tests(),
+                        slot,
+                    },
+                    from_addr,
+                    stake: [1_000, 0, 10][slot as usize % 3],
+                    whitelisted: false,
+                })
+                .collect()
+        };
+        let slots = |requests: Vec<RepairRequestWithMeta>| -> Vec<Slot> {
+            requests
+                .into_iter()
+                .map(|r| match r.request {
+                    RepairProtocol::Orphan { slot, .. } => slot,
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+
+        // Staked requests are drained in stake order, except for every 8th
+        // slot which goes to the oldest unstaked request
+        let mut stats = ServeRepairStats::default();
+        let requests = ServeRepair::prioritize_requests(make_requests(), 1024, &mut stats);
+        assert_eq!(slots(requests), [0, 3, 6, 9, 2, 5, 8, 1, 11, 4, 7, 10]);
+        assert_eq!(stats.dropped_requests_low_stake, 0);
+        assert_eq!(stats.reordered_requests, 5);
+        assert_eq!(stats.max_reorder_depth, 6);
+
+        // The fairness floor keeps an unstaked request even when the staked
+        // requests alone exceed the limit
+        let mut stats = ServeRepairStats::default();
+        let requests = ServeRepair::prioritize_requests(make_requests(), 8, &mut stats);
+        assert_eq!(slots(requests), [0, 3, 6, 9, 2, 5, 8, 1]);
+        assert_eq!(stats.dropped_requests_low_stake, 4);
+
+        // Whitelisted requesters come first regardless of stake
+        let mut requests = make_requests();
+        requests[1].whitelisted = true;
+        let requests =
+            ServeRepair::prioritize_requests(requests, 4, &mut ServeRepairStats::default());
+        assert_eq!(slots(requests), [1, 0, 3, 6]);
+    }
+
     #[test]
     fn test_check_well_formed_repair_request() {
         let mut rng = rand::rng();
@@ -2553,8 +2671,9 @@ mod tests {
             cluster_slots,
             epoch_schedule,
             ancestor_duplicate_slots_sender,
-            repair_validators,
+            repair_validators: Arc::new(RwLock::new(repair_validators)),
             repair_whitelist: Arc::new(RwLock::new(HashSet::default())),
+            deterministic_rng_seed: None,
         }
     }
 
@@ -2578,6 +2697,7 @@ mod tests {
         );
         let mut outstanding_requests = OutstandingShredRepairs::default();
         let rv = serve_repair.repair_request(
+            &mut rand::rng(),
             &repair_info,
             ShredRepairType::Shred(0, 0),
             &mut LruCache::new(100),
@@ -2602,6 +2722,7 @@ mod tests {
         cluster_info.insert_info(nxt.clone());
         let rv = serve_repair
             .repair_request(
+                &mut rand::rng(),
                 &repair_info,
                 ShredRepairType::Shred(0, 0),
                 &mut LruCache::new(100),
@@ -2633,6 +2754,7 @@ mod tests {
             //this randomly picks an option, so eventually it should pick both
             let rv = serve_repair
                 .repair_request(
+                    &mut rand::rng(),
                     &repair_info,
                     ShredRepairType::Shred(0, 0),
                     &mut LruCache::new(100),
@@ -2928,6 +3050,7 @@ mod tests {
         let mut outstanding_shred_requests = OutstandingShredRepairs::default();
         assert_matches!(
             serve_repair.repair_request(
+                &mut rand::rng(),
                 &repair_info,
                 ShredRepairType::Shred(slot, 0),
                 &mut peers_cache,
@@ -2941,6 +3064,7 @@ mod tests {
         let mut outstanding_block_id_requests = OutstandingRequests::default();
         assert_matches!(
             serve_repair.block_id_repair_request(
+                &mut rand::rng(),
                 &repair_info,
                 BlockIdRepairType::ParentAndFecSetCount {
                     slot,
@@ -2968,7 +3092,7 @@ mod tests {
         cluster_info.insert_info(contact_info2.clone());
         cluster_info.insert_info(contact_info3.clone());
         let identity_keypair = cluster_info.keypair();
-        let mut repair_info = new_test_repair_info(
+        let repair_info = new_test_repair_info(
             cluster_info.clone(),
             bank_forks.clone(),
             cluster_slots,
@@ -2986,7 +3110,7 @@ mod tests {
         // then no repairs should be generated
         for pubkey in &[solana_pubkey::new_rand(), *me.pubkey()] {
             let known_validators = Some(vec![*pubkey].into_iter().collect());
-            repair_info.repair_validators = known_validators.clone();
+            *repair_info.repair_validators.write().unwrap() = known_validators.clone();
             assert!(
                 serve_repair
                     .repair_peers(&known_validators, 1, &identity_keypair.pubkey())
@@ -2994,6 +3118,7 @@ mod tests {
             );
             assert_matches!(
                 serve_repair.repair_request(
+                    &mut rand::rng(),
                     &repair_info,
                     ShredRepairType::Shred(0, 0),
                     &mut LruCache::new(100),
@@ -3006,13 +3131,14 @@ mod tests {
 
         // If known validator exists in gossip, should return repair successfully
         let known_validators = Some(vec![*contact_info2.pubkey()].into_iter().collect());
-        repair_info.repair_validators = known_validators.clone();
+        *repair_info.repair_validators.write().unwrap() = known_validators.clone();
         let repair_peers =
             serve_repair.repair_peers(&known_validators, 1, &identity_keypair.pubkey());
         assert_eq!(repair_peers.len(), 1);
         assert_eq!(repair_peers[0].pubkey(), contact_info2.pubkey());
         assert_matches!(
             serve_repair.repair_request(
+                &mut rand::rng(),
                 &repair_info,
                 ShredRepairType::Shred(0, 0),
                 &mut LruCache::new(100),
@@ -3024,7 +3150,7 @@ mod tests {
 
         // Using no known validators should default to all
         // validator's available in gossip, excluding myself
-        repair_info.repair_validators = None;
+        *repair_info.repair_validators.write().unwrap() = None;
         let repair_peers: HashSet<Pubkey> = serve_repair
             .repair_peers(&None, 1, &identity_keypair.pubkey())
             .into_iter()
@@ -3035,6 +3161,7 @@ mod tests {
         assert!(repair_peers.contains(contact_info3.pubkey()));
         assert_matches!(
             serve_repair.repair_request(
+                &mut rand::rng(),
                 &repair_info,
                 ShredRepairType::Shred(0, 0),
                 &mut LruCache::new(100),
@@ -3045,6 +3172,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_repair_request_after_repair_validators_update() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank);
+        let cluster_slots = Arc::new(ClusterSlots::default_for_tests());
+        let cluster_info = Arc::new(new_test_cluster_info());
+        let contact_info2 = ContactInfo::new_localhost(&solana_pubkey::new_rand(), timestamp());
+        let contact_info3 = ContactInfo::new_localhost(&solana_pubkey::new_rand(), timestamp());
+        cluster_info.insert_info(contact_info2.clone());
+        cluster_info.insert_info(contact_info3.clone());
+        let repair_info = new_test_repair_info(
+            cluster_info.clone(),
+            bank_forks.clone(),
+            cluster_slots,
+            Some(HashSet::from([*contact_info2.pubkey()])),
+        );
+        let serve_repair = ServeRepair::new_for_test(
+            cluster_info,
+            bank_forks,
+            Arc::new(RwLock::new(HashSet::default())),
+        );
+        let mut cached_repair_validators = repair_info.repair_validators.read().unwrap().clone();
+        let mut peers_cache = LruCache::new(REPAIR_PEERS_CACHE_CAPACITY);
+        let mut outstanding_requests = OutstandingShredRepairs::default();
+        let mut request_peer = |index, peers_cache: &mut LruCache<Slot, RepairPeers>| {
+            let request = ShredRepairType::Shred(1, index);
+            assert_matches!(
+                serve_repair.repair_request(
+                    &mut rand::rng(),
+                    &repair_info,
+                    request,
+                    peers_cache,
+                    &mut RepairStats::default(),
+                    &mut outstanding_requests,
+                ),
+                Ok(Some(_))
+            );
+            outstanding_requests
+                .unexpired_requests(timestamp())
+                .find(|(outstanding, _)| **outstanding == request)
+                .and_then(|(_, peer)| peer.copied())
+                .unwrap()
+        };
+        assert_eq!(request_peer(0, &mut peers_cache), *contact_info2.pubkey());
+
+        // Swap the set while repair is under way, the peers cached for the
+        // slot are still sampled until the cache is refreshed
+        *repair_info.repair_validators.write().unwrap() =
+            Some(HashSet::from([*contact_info3.pubkey()]));
+        assert_eq!(request_peer(1, &mut peers_cache), *contact_info2.pubkey());
+        refresh_repair_peers_cache(
+            &repair_info.repair_validators,
+            &mut cached_repair_validators,
+            &mut peers_cache,
+        );
+        assert_eq!(
+            cached_repair_validators,
+            Some(HashSet::from([*contact_info3.pubkey()]))
+        );
+        for index in 2..10 {
+            assert_eq!(
+                request_peer(index, &mut peers_cache),
+                *contact_info3.pubkey()
+            );
+        }
+    }
+
     #[test]
     fn test_verify_shred_response() {
         fn new_test_data_shred(slot: Slot, index: u32) -> Shred {
@@ -5,7 +5,8 @@ use {
         banking_trace::BankingTracer,
         block_creation_loop::ReplayHighestFrozen,
         cluster_info_vote_listener::{
-            DuplicateConfirmedSlotsReceiver, GossipVerifiedVoteHashReceiver, VoteTracker,
+            DuplicateConfirmedSlotsReceiver, GossipVerifiedVoteHashReceiver,
+            VerifiedVoteLatencyStats, VoteTracker,
         },
         cluster_slots_service::{ClusterSlotsUpdateSender, cluster_slots::ClusterSlots},
         commitment_service::TowerCommitmentAggregationData,
@@ -28,6 +29,8 @@ use {
                 AncestorDuplicateSlotsReceiver, DumpedSlotsSender, PopularPrunedForksReceiver,
             },
         },
+        restart_latency_tracker::{RestartLatencyTracker, RestartPhase},
+        slot_resource_accounting::SlotResourceAccounting,
         unfrozen_gossip_verified_vote_hashes::UnfrozenGossipVerifiedVoteHashes,
         voting_service::VoteOp,
         window_service::DuplicateSlotReceiver,
@@ -61,13 +64,13 @@ use {
         blockstore_meta::BlockLocation,
         blockstore_processor::{
             self, AsyncVerificationProgress, BlockstoreProcessorError, ChainedBlockIdCheck,
-            ConfirmationProgress, ExecuteBatchesInternalMetrics, ReplaySlotStats,
+            ConfirmationProgress, ExecuteBatchesInternalMetrics, ReplaySlotStats, SkipVerification,
             TransactionStatusSender, check_chained_block_id,
         },
         entry_notifier_service::EntryNotifierSender,
         leader_schedule_cache::LeaderScheduleCache,
     },
-    solana_measure::measure::Measure,
+    solana_measure::{measure::Measure, measure_us},
     solana_poh::{
         poh_controller::PohController,
         poh_recorder::{
@@ -103,7 +106,7 @@ use {
         num::{NonZeroUsize, Saturating},
         result,
         sync::{
-            Arc, RwLock,
+            Arc, Mutex, RwLock,
             atomic::{AtomicBool, AtomicU64, Ordering},
         },
         thread::{self, Builder, JoinHandle},
@@ -112,13 +115,16 @@ use {
     thiserror::Error,
 };
 
+mod active_bank_limit;
 mod dead_slots;
+mod leader_schedule_precompute;
 mod update_parent;
 
 use {
     dead_slots::{
         DeadSlotContext, DeadSlotDuplicateContext, DeadSlotNotifications, mark_replay_dead_slot,
     },
+    leader_schedule_precompute::LeaderSchedulePrecompute,
     update_parent::{
         ChildBankReplayStart, child_bank_replay_start, handle_abandoned_bank,
         handle_update_parent_interrupts, process_soft_dead_slots,
@@ -262,6 +268,7 @@ struct ProcessActiveBanksContext {
     replay_tx_thread_pool: ThreadPool,
     prioritization_fee_cache: Option<Arc<PrioritizationFeeCache>>,
     migration_status: Arc<MigrationStatus>,
+    slot_resource_accounting: Option<SlotResourceAccounting>,
 }
 
 struct ProcessBankForksContext {
@@ -424,6 +431,17 @@ pub struct ReplayStageConfig {
     // Stops voting until this slot has been reached. Should be used to avoid
     // duplicate voting which can lead to slashing.
     pub wait_to_vote_slot: Option<Slot>,
+    // Never produce blocks before this slot, regardless of voting. Unlike
+    // `wait_for_vote_to_start_leader`, this is independent of landing a vote.
+    pub min_leader_slot: Option<Slot>,
+    // Prune the fork tips with the least observed vote stake once there are
+    // more than this many unfrozen banks, to bound the replay work a flood of
+    // forking shreds can cause.
+    pub max_active_banks: Option<NonZeroUsize>,
+    // Compute the leader schedule rooted at the next epoch boundary in the
+    // background once the root is within this many slots of the boundary,
+    // instead of on the replay thread when the boundary is rooted.
+    pub precompute_leader_schedule_slots: Option<u64>,
     pub replay_forks_threads: NonZeroUsize,
     pub replay_transactions_threads: NonZeroUsize,
     pub blockstore: Arc<Blockstore>,
@@ -439,6 +457,8 @@ pub struct ReplayStageConfig {
     pub banking_tracer: Arc<BankingTracer>,
     pub snapshot_controller: Option<Arc<SnapshotController>>,
     pub replay_highest_frozen: Arc<ReplayHighestFrozen>,
+    pub restart_latency_tracker: Arc<RestartLatencyTracker>,
+    pub slot_resource_accounting: Option<SlotResourceAccounting>,
 }
 
 pub struct ReplaySenders {
@@ -470,7 +490,7 @@ pub struct ReplayReceivers {
     pub duplicate_slots_receiver: Receiver<u64>,
     pub ancestor_duplicate_slots_receiver: Receiver<AncestorDuplicateSlotToRepair>,
     pub duplicate_confirmed_slots_receiver: Receiver<Vec<(u64, Hash)>>,
-    pub gossip_verified_vote_hash_receiver: Receiver<(Pubkey, u64, Hash)>,
+    pub gossip_verified_vote_hash_receiver: GossipVerifiedVoteHashReceiver,
     pub popular_pruned_forks_receiver: Receiver<Vec<u64>>,
     pub bank_forks_controller_receiver: BankForksCommandReceiver,
     pub latest_switch_request: LatestSwitchRequest,
@@ -510,6 +530,8 @@ struct ReplayLoopTiming {
     generate_new_bank_forks_write_lock_us: Saturating<u64>,
     // When processing multiple forks concurrently, only captures the longest fork
     replay_blockstore_us: u64,
+    num_pruned_active_banks: u64,
+    gossip_verified_vote_latency: VerifiedVoteLatencyStats,
 }
 impl ReplayLoopTiming {
     #[allow(clippy::too_many_arguments)]
@@ -719,7 +741,14 @@ impl ReplayLoopTiming {
                     self.replay_blockstore_us as i64,
                     i64
                 ),
+                (
+                    "num_pruned_active_banks",
+                    self.num_pruned_active_banks as i64,
+                    i64
+                ),
             );
+            self.gossip_verified_vote_latency
+                .report("replay-loop-gossip-verified-vote-latency");
             *self = ReplayLoopTiming::default();
             self.last_submit = now;
         }
@@ -728,6 +757,8 @@ impl ReplayLoopTiming {
 
 pub struct ReplayStage {
     t_replay: JoinHandle<()>,
+    /// Shared with the replay thread, which starts the precomputes
+    leader_schedule_precompute: Option<Arc<Mutex<LeaderSchedulePrecompute>>>,
 }
 
 impl ReplayStage {
@@ -745,6 +776,9 @@ impl ReplayStage {
             wait_for_vote_to_start_leader,
             tower_storage,
             wait_to_vote_slot,
+            min_leader_slot,
+            max_active_banks,
+            precompute_leader_schedule_slots,
             replay_forks_threads,
             replay_transactions_threads,
             blockstore,
@@ -760,6 +794,8 @@ impl ReplayStage {
             banking_tracer,
             snapshot_controller,
             replay_highest_frozen,
+            restart_latency_tracker,
+            slot_resource_accounting,
         } = config;
 
         let ReplaySenders {
@@ -811,6 +847,9 @@ impl ReplayStage {
             .map_or(0, |hfs| hfs.slot());
         *replay_highest_frozen.highest_frozen_slot.lock().unwrap() = highest_frozen_slot;
 
+        let leader_schedule_precompute = precompute_leader_schedule_slots
+            .map(|slots| Arc::new(Mutex::new(LeaderSchedulePrecompute::new(slots))));
+        let replay_leader_schedule_precompute = leader_schedule_precompute.clone();
         let run_replay = move || {
             let _exit = Finalizer::new(exit.clone());
 
@@ -923,6 +962,7 @@ impl ReplayStage {
                 replay_tx_thread_pool,
                 prioritization_fee_cache: prioritization_fee_cache.clone(),
                 migration_status: migration_status.clone(),
+                slot_resource_accounting,
             };
             let process_bank_forks_context = ProcessBankForksContext {
                 bank_forks: bank_forks.clone(),
@@ -993,6 +1033,27 @@ impl ReplayStage {
                     &mut progress,
                     &mut replay_timing,
                 );
+                if let Some(leader_schedule_precompute) = &replay_leader_schedule_precompute {
+                    let root_bank = bank_forks.read().unwrap().root_bank();
+                    leader_schedule_precompute
+                        .lock()
+                        .unwrap()
+                        .maybe_start(&root_bank, &leader_schedule_cache);
+                }
+                if let Some(max_active_banks) = max_active_banks {
+                    let pruned_slots = active_bank_limit::prune_excess_active_banks(
+                        max_active_banks.get(),
+                        poh_shared_leader_state
+                            .load()
+                            .working_bank()
+                            .map(|bank| bank.slot()),
+                        &bank_forks,
+                        &vote_tracker,
+                        &mut progress,
+                        &replay_vote_sender,
+                    );
+                    replay_timing.num_pruned_active_banks += pruned_slots.len() as u64;
+                }
                 generate_new_bank_forks_time.stop();
 
                 // We either have a bank currently, OR there is a pending message to either reset or set
@@ -1019,6 +1080,9 @@ impl ReplayStage {
                     &own_vote_sender,
                 );
                 let did_complete_bank = !new_frozen_slots.is_empty();
+                if did_complete_bank {
+                    restart_latency_tracker.record(RestartPhase::FirstBankFrozen);
+                }
                 replay_active_banks_time.stop();
 
                 // VAT health check
@@ -1151,6 +1215,7 @@ impl ReplayStage {
                         &mut tbft_structs.unfrozen_gossip_verified_vote_hashes,
                         &tbft_structs.heaviest_subtree_fork_choice,
                         &mut latest_validator_votes_for_frozen_banks,
+                        &mut replay_timing.gossip_verified_vote_latency,
                     );
                     for _ in gossip_verified_vote_hash_receiver.try_iter() {}
                     process_unfrozen_gossip_verified_vote_hashes_time.stop();
@@ -1516,6 +1581,7 @@ impl ReplayStage {
                             &mut skipped_slots_info,
                             &banking_tracer,
                             has_new_vote_been_rooted,
+                            min_leader_slot,
                             migration_status.as_ref(),
                         ) {
                             Self::log_leader_change(
@@ -1596,7 +1662,10 @@ impl ReplayStage {
             .spawn(run_replay)
             .unwrap();
 
-        Ok(Self { t_replay })
+        Ok(Self {
+            t_replay,
+            leader_schedule_precompute,
+        })
     }
 
     fn alpenglow_handle_newly_frozen_banks(
@@ -2233,39 +2302,19 @@ impl ReplayStage {
         }
     }
 
-    fn purge_unconfirmed_slot(
-        slot_to_purge: Slot,
-        ancestors: &mut HashMap<Slot, HashSet<Slot>>,
-        descendants: &mut HashMap<Slot, HashSet<Slot>>,
-        progress: &mut ProgressMap,
+    /// Removes the banks for `slots` from `bank_forks`, clearing their state from
+    /// accounts, the status cache and the program cache. Returns the `BankId`s of
+    /// the removed banks.
+    fn dump_banks<'a>(
+        slots: impl Iterator<Item = &'a Slot> + Clone,
         root_bank: &Bank,
         bank_forks: &RwLock<BankForks>,
-        blockstore: &Blockstore,
-    ) {
-        warn!("purging slot {slot_to_purge}");
-
-        // Doesn't need to be root bank, just needs a common bank to
-        // access the status cache and accounts
-        let slot_descendants = descendants.get(&slot_to_purge).cloned();
-        if slot_descendants.is_none() {
-            // Root has already moved past this slot, no need to purge it
-            if root_bank.slot() <= slot_to_purge {
-                blockstore.clear_unconfirmed_slot(slot_to_purge);
-            }
-
-            return;
-        }
-
-        // Clear the ancestors/descendants map to keep them
-        // consistent
-        let slot_descendants = slot_descendants.unwrap();
-        Self::purge_ancestors_descendants(slot_to_purge, &slot_descendants, ancestors, descendants);
-
+        write_bank_hash_details: bool,
+    ) -> Vec<(Slot, BankId)> {
         let banks_to_remove: Vec<_> = {
             let bank_forks = bank_forks.read().unwrap();
-            slot_descendants
-                .iter()
-                .chain(std::iter::once(&slot_to_purge))
+            slots
+                .clone()
                 .filter_map(|slot| bank_forks.get_with_scheduler(*slot))
                 .collect()
         };
@@ -2277,12 +2326,7 @@ impl ReplayStage {
         // from BankForks
         let (slots_to_purge, removed_banks): (Vec<(Slot, BankId)>, Vec<BankWithScheduler>) = {
             let mut w_bank_forks = bank_forks.write().unwrap();
-            w_bank_forks.dump_slots(
-                slot_descendants
-                    .iter()
-                    .chain(std::iter::once(&slot_to_purge)),
-                true,
-            )
+            w_bank_forks.dump_slots(slots, write_bank_hash_details)
         };
 
         // Clear the accounts for these slots so that any ongoing RPC scans fail.
@@ -2295,15 +2339,60 @@ impl ReplayStage {
         // `remove_unrooted_slots()` call.
         drop(removed_banks);
 
-        for (slot, slot_id) in slots_to_purge {
+        for (slot, _) in &slots_to_purge {
             // Clear the slot signatures from status cache for this slot.
             // TODO: What about RPC queries that had already cloned the Bank for this slot
             // and are looking up the signature for this slot?
-            root_bank.clear_slot_signatures(slot);
+            root_bank.clear_slot_signatures(*slot);
 
             // Remove cached entries of the programs that were deployed in this slot.
-            root_bank.prune_program_cache_by_deployment_slot(slot);
+            root_bank.prune_program_cache_by_deployment_slot(*slot);
+        }
+        slots_to_purge
+    }
+
+    fn purge_unconfirmed_slot(
+        slot_to_purge: Slot,
+        ancestors: &mut HashMap<Slot, HashSet<Slot>>,
+        descendants: &mut HashMap<Slot, HashSet<Slot>>,
+        progress: &mut ProgressMap,
+        root_bank: &Bank,
+        bank_forks: &RwLock<BankForks>,
+        blockstore: &Blockstore,
+    ) {
+        warn!("purging slot {slot_to_purge}");
+
+        // Doesn't need to be root bank, just needs a common bank to
+        // access the status cache and accounts
+        let slot_descendants = descendants.get(&slot_to_purge).cloned();
+        if slot_descendants.is_none() {
+            // Root has already moved past this slot, no need to purge it
+            if root_bank.slot() <= slot_to_purge {
+                blockstore.clear_unconfirmed_slot(slot_to_purge);
+            }
+            // The bank may have been pruned for exceeding the maximum number of
+            // active banks, clear its dead progress so that it can be replayed
+            // once repaired
+            let _ = progress.remove(&slot_to_purge);
 
+            return;
+        }
+
+        // Clear the ancestors/descendants map to keep them
+        // consistent
+        let slot_descendants = slot_descendants.unwrap();
+        Self::purge_ancestors_descendants(slot_to_purge, &slot_descendants, ancestors, descendants);
+
+        let slots_to_purge = Self::dump_banks(
+            slot_descendants
+                .iter()
+                .chain(std::iter::once(&slot_to_purge)),
+            root_bank,
+            bank_forks,
+            true,
+        );
+
+        for (slot, slot_id) in slots_to_purge {
             if let Some(bank_hash) = blockstore.get_bank_hash(slot) {
                 // If a descendant was successfully replayed and chained from a duplicate it must
                 // also be a duplicate. In this case we *need* to repair it, so we clear from
@@ -2700,8 +2789,10 @@ impl ReplayStage {
         unfrozen_gossip_verified_vote_hashes: &mut UnfrozenGossipVerifiedVoteHashes,
         heaviest_subtree_fork_choice: &HeaviestSubtreeForkChoice,
         latest_validator_votes_for_frozen_banks: &mut LatestValidatorVotesForFrozenBanks,
+        gossip_verified_vote_latency: &mut VerifiedVoteLatencyStats,
     ) {
-        for (pubkey, slot, hash) in gossip_verified_vote_hash_receiver.try_iter() {
+        for (pubkey, slot, hash, observed_at) in gossip_verified_vote_hash_receiver.try_iter() {
+            gossip_verified_vote_latency.record(observed_at);
             let is_frozen = heaviest_subtree_fork_choice.contains_block(&(slot, hash));
             // cluster_info_vote_listener will ensure it doesn't push duplicates
             unfrozen_gossip_verified_vote_hashes.add_vote(
@@ -2844,6 +2935,7 @@ impl ReplayStage {
     /// - Startup verification is not complete,
     /// - Bank forks already contains a bank for this leader slot
     /// - We have not landed a vote yet and the `wait_for_vote_to_start_leader` flag is set
+    /// - The leader slot is before `min_leader_slot`
     /// - We have failed the propagated check
     ///
     /// Returns Some new working bank slot if created and inserted into bank forks.
@@ -2861,6 +2953,7 @@ impl ReplayStage {
         skipped_slots_info: &mut SkippedSlotsInfo,
         banking_tracer: &Arc<BankingTracer>,
         has_new_vote_been_rooted: bool,
+        min_leader_slot: Option<Slot>,
         migration_status: &MigrationStatus,
     ) -> Option<Slot> {
         assert!(!migration_status.is_alpenglow_enabled());
@@ -2903,6 +2996,16 @@ impl ReplayStage {
                 return None;
             }
 
+            if let Some(min_leader_slot) = min_leader_slot {
+                if poh_slot < min_leader_slot {
+                    info!(
+                        "Leader slot {poh_slot} is before min leader slot {min_leader_slot}, so \
+                         skipping my leader slot"
+                    );
+                    return None;
+                }
+            }
+
             let next_leader_id = &next_leader.id;
             trace!("{my_pubkey} leader {next_leader_id} at poh slot: {poh_slot}");
 
@@ -3005,7 +3108,7 @@ impl ReplayStage {
             &process_active_banks_context.replay_tx_thread_pool,
             &mut w_replay_stats,
             &mut w_replay_progress,
-            false,
+            SkipVerification::NONE,
             process_active_banks_context
                 .transaction_status_sender
                 .as_ref(),
@@ -4231,6 +4334,11 @@ impl ReplayStage {
                     bank_complete_time.as_us(),
                     is_unified_scheduler_enabled,
                 );
+                if let Some(slot_resource_accounting) =
+                    &process_active_banks_context.slot_resource_accounting
+                {
+                    slot_resource_accounting.record_replay(bank, &r_replay_stats);
+                }
                 execute_timings.accumulate(&r_replay_stats.batch_execute.totals);
             } else {
                 trace!(
@@ -5281,6 +5389,11 @@ impl ReplayStage {
                     trace!("child already active or frozen {child_slot}");
                     continue;
                 }
+                if progress.is_dead(child_slot).unwrap_or_default() {
+                    // Pruned for exceeding the maximum number of active banks
+                    trace!("child already pruned {child_slot}");
+                    continue;
+                }
 
                 debug_assert!(!progress.contains_key(&child_slot));
 
@@ -5316,15 +5429,27 @@ impl ReplayStage {
                 if options.vote_only_bank {
                     info!("Replaying block in slot {child_slot} in VoM");
                 }
-                let child_bank = Self::new_bank_from_parent_with_notify(
-                    parent_bank.clone(),
-                    child_slot,
-                    root,
-                    leader,
-                    rpc_subscriptions,
-                    slot_status_notifier,
-                    options,
-                );
+                let (child_bank, new_bank_us) =
+                    measure_us!(Self::new_bank_from_parent_with_notify(
+                        parent_bank.clone(),
+                        child_slot,
+                        root,
+                        leader,
+                        rpc_subscriptions,
+                        slot_status_notifier,
+                        options,
+                    ));
+                if child_bank.epoch() > parent_bank.epoch() {
+                    // Crossing the epoch boundary activates stakes, computes rewards and
+                    // saves the epoch stakes inline, stalling replay of this fork
+                    datapoint_info!(
+                        "replay_stage-epoch_boundary",
+                        ("slot", child_slot, i64),
+                        ("parent_slot", parent_slot, i64),
+                        ("epoch", child_bank.epoch(), i64),
+                        ("new_bank_us", new_bank_us, i64),
+                    );
+                }
                 blockstore_processor::set_alpenglow_ticks(&child_bank, migration_status);
 
                 let empty: Vec<Pubkey> = vec![];
@@ -5467,7 +5592,11 @@ impl ReplayStage {
     }
 
     pub fn join(self) -> thread::Result<()> {
-        self.t_replay.join().map(|_| ())
+        self.t_replay.join()?;
+        if let Some(leader_schedule_precompute) = self.leader_schedule_precompute {
+            leader_schedule_precompute.lock().unwrap().join()?;
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,98 @@
+//! Enforcement of the maximum number of active banks.
+//!
+//! A flood of shreds for many competing forks makes replay create, and keep
+//! replaying, a bank for every one of them. Once the number of active
+//! (unfrozen) banks exceeds the configured maximum, the fork tips with the
+//! least observed vote stake are dropped from `BankForks`. They are only
+//! marked dead in the progress map, not in blockstore.
+
+use {
+    super::{ReplayStage, dead_slots::send_invalid_bank},
+    crate::{
+        cluster_info_vote_listener::VoteTracker,
+        consensus::progress_map::{DeadSlotReason, ForkProgress, ProgressMap},
+    },
+    solana_clock::Slot,
+    solana_runtime::{bank_forks::BankForks, vote_sender_types::ReplayVoteSender},
+    std::sync::RwLock,
+};
+
+/// Prunes unfrozen banks until at most `max_active_banks` remain, returning
+/// the pruned slots.
+///
+/// Banks are pruned in order of increasing vote stake observed for their slot,
+/// oldest first among equals. The bank with the most observed stake, i.e. the
+/// tip of the heaviest fork, is never pruned. Neither is `protected_slot`, the
+/// bank PoH is currently building on, nor the root or any other frozen bank.
+pub(super) fn prune_excess_active_banks(
+    max_active_banks: usize,
+    protected_slot: Option<Slot>,
+    bank_forks: &RwLock<BankForks>,
+    vote_tracker: &VoteTracker,
+    progress: &mut ProgressMap,
+    replay_vote_sender: &ReplayVoteSender,
+) -> Vec<Slot> {
+    let (root_bank, num_active_banks, banks_to_prune) = {
+        let bank_forks = bank_forks.read().unwrap();
+        let active_bank_slots = bank_forks.active_bank_slots();
+        let num_active_banks = active_bank_slots.len();
+        let num_excess = num_active_banks.saturating_sub(max_active_banks);
+        if num_excess == 0 {
+            return vec![];
+        }
+
+        let mut candidates: Vec<_> = active_bank_slots
+            .into_iter()
+            .filter(|slot| Some(*slot) != protected_slot)
+            .filter_map(|slot| bank_forks.get(slot))
+            .map(|bank| {
+                let observed_stake: u64 = vote_tracker
+                    .get_slot_vote_tracker(bank.slot())
+                    .map(|slot_vote_tracker| {
+                        slot_vote_tracker
+                            .read()
+                            .unwrap()
+                            .voters()
+                            .map(|vote_pubkey| bank.epoch_vote_account_stake(vote_pubkey))
+                            .sum()
+                    })
+                    .unwrap_or_default();
+                (observed_stake, bank)
+            })
+            .collect();
+        candidates.sort_unstable_by_key(|(observed_stake, bank)| (*observed_stake, bank.slot()));
+        // Keep the heaviest bank, the most recent one among equals
+        candidates.pop();
+        candidates.truncate(num_excess);
+        (bank_forks.root_bank(), num_active_banks, candidates)
+    };
+    if banks_to_prune.is_empty() {
+        return vec![];
+    }
+
+    let pruned_slots: Vec<_> = banks_to_prune.iter().map(|(_, bank)| bank.slot()).collect();
+    for (observed_stake, bank) in &banks_to_prune {
+        let slot = bank.slot();
+        warn!(
+            "pruning active bank {slot} with observed stake {observed_stake}, {num_active_banks} \
+             active banks exceeds the maximum of {max_active_banks}"
+        );
+        // Keep the dead progress entry so that `generate_new_bank_forks()`
+        // does not recreate the bank
+        progress
+            .entry(slot)
+            .or_insert_with(|| ForkProgress::new(bank.last_blockhash(), None, None, 0, 0, None))
+            .mark_dead(DeadSlotReason::PrunedActiveBank);
+        send_invalid_bank(bank, replay_vote_sender);
+    }
+    drop(banks_to_prune);
+    ReplayStage::dump_banks(pruned_slots.iter(), &root_bank, bank_forks, false);
+
+    datapoint_warn!(
+        "replay_stage-prune_excess_active_banks",
+        ("num_active_banks", num_active_banks, i64),
+        ("num_pruned", pruned_slots.len(), i64),
+        ("pruned_slots", format!("{pruned_slots:?}"), String),
+    );
+    pruned_slots
+}
@@ -0,0 +1,101 @@
+//! Precomputation of the leader schedule rooted at the next epoch boundary.
+//!
+//! Creating the first bank of an epoch activates the stakes of the new epoch,
+//! and rooting it makes the leader schedule cache compute the schedule of the
+//! new leader schedule epoch, both on the replay thread. When enabled, both are
+//! instead computed in the background from the root bank once the root gets
+//! close to the boundary. The activated stake is only reused by the banks
+//! crossing the boundary if their stake delegations are unchanged, and the
+//! schedule is only used if it matches the epoch stakes computed at the
+//! boundary.
+
+use {
+    log::*,
+    solana_clock::Epoch,
+    solana_ledger::leader_schedule_cache::LeaderScheduleCache,
+    solana_measure::measure_us,
+    solana_runtime::bank::Bank,
+    std::{
+        sync::Arc,
+        thread::{self, Builder, JoinHandle},
+    },
+};
+
+pub(super) struct LeaderSchedulePrecompute {
+    slots_before_boundary: u64,
+    /// Leader schedule epoch the last precompute was started for
+    last_precomputed_epoch: Option<Epoch>,
+    thread_hdl: Option<JoinHandle<()>>,
+}
+
+impl LeaderSchedulePrecompute {
+    pub(super) fn new(slots_before_boundary: u64) -> Self {
+        Self {
+            slots_before_boundary,
+            last_precomputed_epoch: None,
+            thread_hdl: None,
+        }
+    }
+
+    /// Starts precomputing the leader schedule that rooting the next epoch
+    /// boundary computes, once `root_bank` is within the configured number of
+    /// slots of that boundary. At most one precompute runs per boundary.
+    pub(super) fn maybe_start(
+        &mut self,
+        root_bank: &Arc<Bank>,
+        leader_schedule_cache: &Arc<LeaderScheduleCache>,
+    ) {
+        let epoch_schedule = root_bank.epoch_schedule();
+        let first_slot_in_next_epoch =
+            epoch_schedule.get_first_slot_in_epoch(root_bank.epoch().saturating_add(1));
+        if root_bank.slot().saturating_add(self.slots_before_boundary) < first_slot_in_next_epoch {
+            return;
+        }
+        let leader_schedule_epoch =
+            epoch_schedule.get_leader_schedule_epoch(first_slot_in_next_epoch);
+        if self.last_precomputed_epoch == Some(leader_schedule_epoch)
+            || root_bank
+                .epoch_vote_accounts(leader_schedule_epoch)
+                .is_some()
+            || self
+                .thread_hdl
+                .as_ref()
+                .is_some_and(|thread_hdl| !thread_hdl.is_finished())
+        {
+            return;
+        }
+        self.last_precomputed_epoch = Some(leader_schedule_epoch);
+
+        let root_bank = root_bank.clone();
+        let leader_schedule_cache = leader_schedule_cache.clone();
+        let thread_hdl = Builder::new()
+            .name("solLdrSchedPre".to_string())
+            .spawn(move || {
+                let (vote_accounts, precompute_stakes_us) =
+                    measure_us!(root_bank.precompute_next_epoch_stakes());
+                let (_, compute_us) = measure_us!(
+                    leader_schedule_cache
+                        .precompute_leader_schedule(leader_schedule_epoch, vote_accounts.as_ref())
+                );
+                datapoint_info!(
+                    "replay_stage-precompute_leader_schedule",
+                    ("root", root_bank.slot(), i64),
+                    ("epoch", leader_schedule_epoch, i64),
+                    ("precompute_stakes_us", precompute_stakes_us, i64),
+                    ("compute_us", compute_us, i64),
+                );
+            });
+        match thread_hdl {
+            Ok(thread_hdl) => self.thread_hdl = Some(thread_hdl),
+            Err(err) => warn!(
+                "Failed to spawn leader schedule precompute for epoch {leader_schedule_epoch}: \
+                 {err}"
+            ),
+        }
+    }
+
+    /// Waits for the running precompute, if any
+    pub(super) fn join(&mut self) -> thread::Result<()> {
+        self.thread_hdl.take().map_or(Ok(()), JoinHandle::join)
+    }
+}
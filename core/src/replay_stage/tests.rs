@@ -32,6 +32,7 @@ use {
         },
         entry::{self, Entry},
     },
+    solana_epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH},
     solana_genesis_config as genesis_config,
     solana_gossip::{crds::Cursor, node::Node},
     solana_hash::Hash,
@@ -122,6 +123,7 @@ impl ProcessActiveBanksContext {
             replay_tx_thread_pool,
             prioritization_fee_cache: None,
             migration_status,
+            slot_resource_accounting: None,
         }
     }
 }
@@ -4497,18 +4499,26 @@ fn test_gossip_vote_doesnt_affect_fork_choice() {
         4
     );
 
-    // Cast a vote for slot 3 on one fork
+    // Cast a vote for slot 3 on one fork, which sat in the channel for a while
     let vote_slot = 3;
     let vote_bank = bank_forks.read().unwrap().get(vote_slot).unwrap();
+    let delay = Duration::from_millis(50);
+    let observed_at = Instant::now().checked_sub(delay).unwrap();
     gossip_verified_vote_hash_sender
-        .send((vote_pubkey, vote_slot, vote_bank.hash()))
+        .send((vote_pubkey, vote_slot, vote_bank.hash(), observed_at))
         .expect("Send should succeed");
+    let mut gossip_verified_vote_latency = VerifiedVoteLatencyStats::default();
     ReplayStage::process_gossip_verified_vote_hashes(
         &gossip_verified_vote_hash_receiver,
         &mut unfrozen_gossip_verified_vote_hashes,
         &tbft_structs.heaviest_subtree_fork_choice,
         &mut latest_validator_votes_for_frozen_banks,
+        &mut gossip_verified_vote_latency,
     );
+    assert_eq!(gossip_verified_vote_latency.num_votes(), 1);
+    // Allow for the precision of the histogram buckets
+    let min_latency_us = delay.as_micros() as u64 * 99 / 100;
+    assert!(gossip_verified_vote_latency.max_latency_us() >= min_latency_us);
 
     // Pick the best fork. Gossip votes shouldn't affect fork choice
     tbft_structs
@@ -5637,6 +5647,7 @@ fn test_dumped_slot_not_causing_panic() {
             &mut SkippedSlotsInfo::default(),
             &banking_tracer,
             has_new_vote_been_rooted,
+            None,
             &MigrationStatus::default(),
         )
         .is_none()
@@ -6317,6 +6328,7 @@ fn test_skip_leader_slot_for_existing_slot() {
             &mut SkippedSlotsInfo::default(),
             &banking_tracer,
             has_new_vote_been_rooted,
+            None,
             &MigrationStatus::default(),
         )
         .is_none()
@@ -6348,6 +6360,7 @@ fn test_skip_leader_slot_for_existing_slot() {
             &mut SkippedSlotsInfo::default(),
             &banking_tracer,
             has_new_vote_been_rooted,
+            None,
             &MigrationStatus::default(),
         )
         .is_some()
@@ -6364,6 +6377,88 @@ fn test_skip_leader_slot_for_existing_slot() {
     assert_eq!(working_bank.parent_slot(), initial_slot);
 }
 
+#[test]
+fn test_skip_leader_slot_before_min_leader_slot() {
+    agave_logger::setup();
+
+    let ReplayBlockstoreComponents {
+        blockstore,
+        my_pubkey,
+        leader_schedule_cache,
+        poh_recorder,
+        mut poh_controller,
+        vote_simulator,
+        rpc_subscriptions,
+        ..
+    } = replay_blockstore_components(None, 1, None);
+
+    let VoteSimulator {
+        bank_forks,
+        mut progress,
+        ..
+    } = vote_simulator;
+
+    let working_bank = bank_forks.read().unwrap().working_bank();
+    ReplayStage::reset_poh_recorder(
+        &my_pubkey,
+        &blockstore,
+        working_bank.clone(),
+        &mut poh_controller,
+        &leader_schedule_cache,
+    );
+    wait_for_poh_service(&poh_controller);
+
+    // Tick until PoH reaches our next leader slot
+    let num_poh_ticks = working_bank.ticks_per_slot() * working_bank.hashes_per_tick().unwrap();
+    let poh_recorder = Arc::new(poh_recorder);
+    let leader_slot = loop {
+        let mut w_poh_recorder = poh_recorder.write().unwrap();
+        if let PohLeaderStatus::Reached { poh_slot, .. } =
+            w_poh_recorder.reached_leader_slot(&my_pubkey)
+        {
+            break poh_slot;
+        }
+        for _ in 0..num_poh_ticks {
+            w_poh_recorder.tick();
+        }
+    };
+
+    let (retransmit_slots_sender, _) = bounded(1024);
+    let (banking_tracer, _) = BankingTracer::new(None).unwrap();
+    let rpc_subscriptions = Some(rpc_subscriptions);
+    let mut maybe_start_leader = |min_leader_slot| {
+        ReplayStage::maybe_start_leader(
+            &my_pubkey,
+            &bank_forks,
+            &poh_recorder,
+            &mut poh_controller,
+            &leader_schedule_cache,
+            rpc_subscriptions.as_deref(),
+            &None,
+            &mut progress,
+            &retransmit_slots_sender,
+            &mut SkippedSlotsInfo::default(),
+            &banking_tracer,
+            true, // has_new_vote_been_rooted
+            min_leader_slot,
+            &MigrationStatus::default(),
+        )
+    };
+
+    // Block production is suppressed before the min leader slot, even though
+    // a vote has landed
+    assert_eq!(maybe_start_leader(Some(leader_slot + 1)), None);
+    assert!(bank_forks.read().unwrap().get(leader_slot).is_none());
+
+    // Block production resumes once the min leader slot is reached
+    assert_eq!(maybe_start_leader(Some(leader_slot)), Some(leader_slot));
+    wait_for_poh_service(&poh_controller);
+    assert_eq!(
+        bank_forks.read().unwrap().working_bank().slot(),
+        leader_slot
+    );
+}
+
 #[test]
 #[should_panic(expected = "Additional duplicate confirmed notification for slot 6")]
 fn test_mark_slots_duplicate_confirmed() {
@@ -6610,3 +6705,131 @@ fn test_process_duplicate_confirmed_slots(same_batch: bool) {
         &mut PurgeRepairSlotCounter::default(),
     );
 }
+
+#[test]
+fn test_prune_excess_active_banks() {
+    let (vote_simulator, blockstore) = setup_default_forks(1, None::<GenerateVotes>);
+    let VoteSimulator {
+        bank_forks,
+        mut progress,
+        validator_keypairs,
+        ..
+    } = vote_simulator;
+    let blockstore = Arc::new(blockstore);
+    let heaviest_bank = bank_forks.read().unwrap().get(6).unwrap();
+
+    // Flood of competing forks off the heaviest bank
+    let fork_slots: Vec<Slot> = (7..=16).collect();
+    for slot in &fork_slots {
+        let bank = Bank::new_from_parent(heaviest_bank.clone(), SlotLeader::default(), *slot);
+        bank_forks.write().unwrap().insert(bank);
+    }
+    assert_eq!(bank_forks.read().unwrap().active_bank_slots().len(), 10);
+
+    // The cluster is voting on slot 12, and PoH is building on slot 16
+    let vote_tracker = VoteTracker::default();
+    let vote_pubkey = validator_keypairs
+        .values()
+        .next()
+        .unwrap()
+        .vote_keypair
+        .pubkey();
+    vote_tracker.insert_vote(12, vote_pubkey);
+    let poh_slot = 16;
+
+    let (replay_vote_sender, replay_vote_receiver) = bounded(1024);
+    let pruned_slots = active_bank_limit::prune_excess_active_banks(
+        4,
+        Some(poh_slot),
+        &bank_forks,
+        &vote_tracker,
+        &mut progress,
+        &replay_vote_sender,
+    );
+
+    // The unvoted forks are pruned oldest first
+    assert_eq!(pruned_slots, vec![7, 8, 9, 10, 11, 13]);
+    let mut active_bank_slots = bank_forks.read().unwrap().active_bank_slots();
+    active_bank_slots.sort_unstable();
+    assert_eq!(active_bank_slots, vec![12, 14, 15, 16]);
+    assert!(bank_forks.read().unwrap().get(6).is_some());
+    assert!(bank_forks.read().unwrap().get(0).is_some());
+    for slot in &pruned_slots {
+        assert!(bank_forks.read().unwrap().get(*slot).is_none());
+        assert_eq!(
+            progress.dead_reason(*slot),
+            Some(&DeadSlotReason::PrunedActiveBank)
+        );
+        // The slot is not marked dead in blockstore
+        assert!(!blockstore.is_dead(*slot));
+    }
+    assert_eq!(replay_vote_receiver.try_iter().count(), pruned_slots.len());
+
+    // Already within the limit
+    assert!(
+        active_bank_limit::prune_excess_active_banks(
+            4,
+            Some(poh_slot),
+            &bank_forks,
+            &vote_tracker,
+            &mut progress,
+            &replay_vote_sender,
+        )
+        .is_empty()
+    );
+
+    // The heaviest bank is kept even if it is not protected
+    let pruned_slots = active_bank_limit::prune_excess_active_banks(
+        1,
+        None,
+        &bank_forks,
+        &vote_tracker,
+        &mut progress,
+        &replay_vote_sender,
+    );
+    assert_eq!(pruned_slots, vec![14, 15, 16]);
+    assert_eq!(bank_forks.read().unwrap().active_bank_slots(), vec![12]);
+}
+
+#[test]
+fn test_leader_schedule_precompute_maybe_start() {
+    let GenesisConfigInfo {
+        mut genesis_config, ..
+    } = create_genesis_config(10_000);
+    genesis_config.epoch_schedule =
+        EpochSchedule::custom(MINIMUM_SLOTS_PER_EPOCH, MINIMUM_SLOTS_PER_EPOCH, false);
+    let (bank0, bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+    let leader_schedule_cache = Arc::new(LeaderScheduleCache::new_from_bank(&bank0));
+    let first_slot_in_next_epoch = bank0.epoch_schedule().get_first_slot_in_epoch(1);
+    let leader_schedule_epoch = bank0.get_leader_schedule_epoch(first_slot_in_next_epoch);
+    assert!(bank0.epoch_vote_accounts(leader_schedule_epoch).is_none());
+    let mut leader_schedule_precompute = LeaderSchedulePrecompute::new(4);
+
+    // Too far from the epoch boundary
+    let bank = Bank::new_from_parent_with_bank_forks(
+        &bank_forks,
+        bank0,
+        SlotLeader::default(),
+        first_slot_in_next_epoch - 5,
+    );
+    leader_schedule_precompute.maybe_start(&bank, &leader_schedule_cache);
+    leader_schedule_precompute.join().unwrap();
+    assert_eq!(
+        leader_schedule_cache.precomputed_leader_schedule_epoch(),
+        None
+    );
+
+    // Within the configured number of slots of the boundary
+    let bank = Bank::new_from_parent_with_bank_forks(
+        &bank_forks,
+        bank,
+        SlotLeader::default(),
+        first_slot_in_next_epoch - 4,
+    );
+    leader_schedule_precompute.maybe_start(&bank, &leader_schedule_cache);
+    leader_schedule_precompute.join().unwrap();
+    assert_eq!(
+        leader_schedule_cache.precomputed_leader_schedule_epoch(),
+        Some(leader_schedule_epoch)
+    );
+}
@@ -0,0 +1,297 @@
+//! Tracks how long the validator takes to get from process start back to
+//! voting.
+//!
+//! Restarts go through a number of phases, some of which overlap, e.g. replay
+//! may freeze banks before the remaining services are started. The time at
+//! which each phase is first reached is recorded, and a single summary is
+//! reported once the node lands its first vote, or freezes its first bank if
+//! voting is disabled.
+
+use {
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RestartPhase {
+    BlockstoreOpened,
+    SnapshotLoaded,
+    LedgerProcessed,
+    ServicesStarted,
+    /// Replay froze its first bank
+    FirstBankFrozen,
+    FirstVoteSubmitted,
+    /// A vote of this node for a slot above the restart slot was observed in
+    /// a replayed block
+    FirstVoteLanded,
+}
+
+impl RestartPhase {
+    const ALL: [Self; 7] = [
+        Self::BlockstoreOpened,
+        Self::SnapshotLoaded,
+        Self::LedgerProcessed,
+        Self::ServicesStarted,
+        Self::FirstBankFrozen,
+        Self::FirstVoteSubmitted,
+        Self::FirstVoteLanded,
+    ];
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RestartLatencySummary {
+    /// The phases reached so far, in order, with the time since process start
+    /// at which each was reached. A phase is never reported as reached before
+    /// the phases preceding it.
+    pub phases: Vec<(RestartPhase, Duration)>,
+    /// True once the final phase is reached and the summary is reported
+    pub complete: bool,
+}
+
+impl RestartLatencySummary {
+    pub fn get(&self, phase: RestartPhase) -> Option<Duration> {
+        self.phases
+            .iter()
+            .find_map(|(reached, elapsed)| (*reached == phase).then_some(*elapsed))
+    }
+
+    fn report(&self) {
+        // Time spent in `phase`, since the previous phase reached
+        let phase_ms = |phase| {
+            let elapsed = self.get(phase)?;
+            let prev_elapsed = self
+                .phases
+                .iter()
+                .take_while(|(reached, _)| *reached < phase)
+                .last()
+                .map(|(_, elapsed)| *elapsed)
+                .unwrap_or_default();
+            Some(elapsed.saturating_sub(prev_elapsed).as_millis() as i64)
+        };
+        let total_ms = self
+            .phases
+            .last()
+            .map(|(_, elapsed)| elapsed.as_millis() as i64)
+            .unwrap_or_default();
+        datapoint_info!(
+            "restart_latency",
+            ("total_ms", total_ms, i64),
+            (
+                "blockstore_opened_ms",
+                phase_ms(RestartPhase::BlockstoreOpened),
+                Option<i64>
+            ),
+            (
+                "snapshot_loaded_ms",
+                phase_ms(RestartPhase::SnapshotLoaded),
+                Option<i64>
+            ),
+            (
+                "ledger_processed_ms",
+                phase_ms(RestartPhase::LedgerProcessed),
+                Option<i64>
+            ),
+            (
+                "services_started_ms",
+                phase_ms(RestartPhase::ServicesStarted),
+                Option<i64>
+            ),
+            (
+                "first_bank_frozen_ms",
+                phase_ms(RestartPhase::FirstBankFrozen),
+                Option<i64>
+            ),
+            (
+                "first_vote_submitted_ms",
+                phase_ms(RestartPhase::FirstVoteSubmitted),
+                Option<i64>
+            ),
+            (
+                "first_vote_landed_ms",
+                phase_ms(RestartPhase::FirstVoteLanded),
+                Option<i64>
+            ),
+        );
+    }
+}
+
+#[derive(Default)]
+struct RestartLatencyState {
+    reached: [Option<Instant>; RestartPhase::ALL.len()],
+    /// Highest slot processed from the local ledger before the node resumed
+    restart_slot: Option<Slot>,
+    complete: bool,
+}
+
+pub struct RestartLatencyTracker {
+    process_start: Instant,
+    vote_account: Pubkey,
+    final_phase: RestartPhase,
+    state: Mutex<RestartLatencyState>,
+}
+
+impl RestartLatencyTracker {
+    pub fn new(process_start: Instant, vote_account: Pubkey, voting_disabled: bool) -> Self {
+        let final_phase = if voting_disabled {
+            RestartPhase::FirstBankFrozen
+        } else {
+            RestartPhase::FirstVoteLanded
+        };
+        Self {
+            process_start,
+            vote_account,
+            final_phase,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Records that `phase` is reached now, unless it was reached before.
+    ///
+    /// Reports the summary once both the final phase and the start of all
+    /// services are reached.
+    pub fn record(&self, phase: RestartPhase) {
+        self.record_at(phase, Instant::now());
+    }
+
+    fn record_at(&self, phase: RestartPhase, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        if state.complete {
+            return;
+        }
+        state.reached[phase as usize].get_or_insert(now);
+        if state.reached[self.final_phase as usize].is_some()
+            && state.reached[RestartPhase::ServicesStarted as usize].is_some()
+        {
+            state.complete = true;
+            let summary = self.summarize(&state);
+            drop(state);
+            info!("Restart latency: {summary:?}");
+            summary.report();
+        }
+    }
+
+    /// Sets the highest slot processed from the local ledger before the node
+    /// resumed. Only votes for later slots count as landed after the restart.
+    pub(crate) fn set_restart_slot(&self, restart_slot: Slot) {
+        self.state.lock().unwrap().restart_slot = Some(restart_slot);
+    }
+
+    /// Records the first vote of this node observed in a replayed block
+    pub(crate) fn on_replayed_vote(&self, vote_pubkey: &Pubkey, vote_slot: Slot) {
+        if *vote_pubkey != self.vote_account {
+            return;
+        }
+        // Replayed blocks may still hold votes this node cast before the
+        // restart
+        let is_restarted_vote = self
+            .state
+            .lock()
+            .unwrap()
+            .restart_slot
+            .is_some_and(|restart_slot| vote_slot > restart_slot);
+        if is_restarted_vote {
+            self.record(RestartPhase::FirstVoteLanded);
+        }
+    }
+
+    pub fn summary(&self) -> RestartLatencySummary {
+        self.summarize(&self.state.lock().unwrap())
+    }
+
+    fn summarize(&self, state: &RestartLatencyState) -> RestartLatencySummary {
+        let mut elapsed_floor = Duration::ZERO;
+        let phases = RestartPhase::ALL
+            .into_iter()
+            .filter_map(|phase| {
+                let reached = state.reached[phase as usize]?;
+                elapsed_floor =
+                    elapsed_floor.max(reached.saturating_duration_since(self.process_start));
+                Some((phase, elapsed_floor))
+            })
+            .collect();
+        RestartLatencySummary {
+            phases,
+            complete: state.complete,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_latency_tracker() {
+        let process_start = Instant::now();
+        let vote_account = Pubkey::new_unique();
+        let tracker = RestartLatencyTracker::new(process_start, vote_account, false);
+        let at = |secs| process_start + Duration::from_secs(secs);
+
+        tracker.record_at(RestartPhase::BlockstoreOpened, at(1));
+        tracker.record_at(RestartPhase::LedgerProcessed, at(5));
+        // Only the first time a phase is reached counts
+        tracker.record_at(RestartPhase::BlockstoreOpened, at(3));
+        // Replay froze a bank before the remaining services were started
+        tracker.record_at(RestartPhase::FirstBankFrozen, at(6));
+        tracker.record_at(RestartPhase::ServicesStarted, at(7));
+        tracker.record_at(RestartPhase::FirstVoteSubmitted, at(8));
+        tracker.set_restart_slot(10);
+        tracker.on_replayed_vote(&Pubkey::new_unique(), 11);
+        assert!(!tracker.summary().complete);
+
+        tracker.record_at(RestartPhase::FirstVoteLanded, at(9));
+        let summary = tracker.summary();
+        assert!(summary.complete);
+        assert_eq!(
+            summary.phases,
+            vec![
+                (RestartPhase::BlockstoreOpened, Duration::from_secs(1)),
+                (RestartPhase::LedgerProcessed, Duration::from_secs(5)),
+                (RestartPhase::ServicesStarted, Duration::from_secs(7)),
+                (RestartPhase::FirstBankFrozen, Duration::from_secs(7)),
+                (RestartPhase::FirstVoteSubmitted, Duration::from_secs(8)),
+                (RestartPhase::FirstVoteLanded, Duration::from_secs(9)),
+            ]
+        );
+        assert_eq!(summary.get(RestartPhase::SnapshotLoaded), None);
+
+        // Nothing changes once the summary is reported
+        tracker.record_at(RestartPhase::SnapshotLoaded, at(10));
+        assert_eq!(tracker.summary(), summary);
+    }
+
+    #[test]
+    fn test_restart_latency_tracker_voting_disabled() {
+        let process_start = Instant::now();
+        let vote_account = Pubkey::new_unique();
+        let tracker = RestartLatencyTracker::new(process_start, vote_account, true);
+
+        tracker.record(RestartPhase::FirstBankFrozen);
+        assert!(!tracker.summary().complete);
+        tracker.record(RestartPhase::ServicesStarted);
+        assert!(tracker.summary().complete);
+    }
+
+    #[test]
+    fn test_restart_latency_tracker_replayed_vote() {
+        let vote_account = Pubkey::new_unique();
+        let tracker = RestartLatencyTracker::new(Instant::now(), vote_account, false);
+        tracker.record(RestartPhase::ServicesStarted);
+        // Votes replayed before the restart slot is known
+        tracker.on_replayed_vote(&vote_account, 11);
+        tracker.set_restart_slot(10);
+        // Votes cast before the restart
+        tracker.on_replayed_vote(&vote_account, 9);
+        tracker.on_replayed_vote(&vote_account, 10);
+        assert!(!tracker.summary().complete);
+
+        tracker.on_replayed_vote(&vote_account, 11);
+        let summary = tracker.summary();
+        assert!(summary.complete);
+        assert!(summary.get(RestartPhase::FirstVoteLanded).is_some());
+    }
+}
@@ -5,10 +5,11 @@
 use {
     crate::{
         banking_trace::BankingPacketSender, sigverify_stage::SigVerifyServiceError,
+        tpu_ingest_self_test::IngestSelfTestTap,
         transaction_priority::calculate_priority_from_bytes,
     },
     agave_banking_stage_ingress_types::{BankingPacketBatch, SchedulerPriorityFloor},
-    crossbeam_channel::{Receiver, Sender, TrySendError, bounded},
+    crossbeam_channel::{Receiver, Sender, TrySendError, bounded, never},
     solana_measure::measure_us,
     solana_perf::{
         deduper::{self, Deduper},
@@ -65,6 +66,8 @@ pub(crate) struct SigVerifyWorkerState {
     /// check (e.g. for the vote worker, which is governed by a separate
     /// priority policy in banking stage).
     priority_floor: Option<Arc<SchedulerPriorityFloor>>,
+    /// Takes the packets of the ingest self-test out of the verified batches
+    ingest_self_test_tap: Option<Arc<IngestSelfTestTap>>,
 }
 
 impl SigVerifyWorkerState {
@@ -73,12 +76,14 @@ impl SigVerifyWorkerState {
         deduper: Arc<Deduper<2, [u8]>>,
         stats: SigVerifyWorkerStats,
         priority_floor: Option<Arc<SchedulerPriorityFloor>>,
+        ingest_self_test_tap: Option<Arc<IngestSelfTestTap>>,
     ) -> Self {
         Self {
             banking_stage_sender,
             deduper,
             stats,
             priority_floor,
+            ingest_self_test_tap,
         }
     }
 }
@@ -139,12 +144,15 @@ pub(crate) struct SigVerifyWorkerSenders {
 struct WorkerPoolChannels {
     non_vote_receiver: Receiver<PacketBatch>,
     tpu_vote_receiver: Receiver<PacketBatch>,
+    /// Packets of the reserved ingest channel, never ready if there is none
+    reserved_receiver: Receiver<PacketBatch>,
     gossip_receiver: Receiver<GossipVerifyTask>,
     gossip_verified_vote_sender: Sender<GossipVerifiedVoteBatch>,
     forward_stage_sender: Sender<(BankingPacketBatch, bool)>,
     sharable_banks: SharableBanks,
     non_vote_state: SigVerifyWorkerState,
     tpu_vote_state: SigVerifyWorkerState,
+    reserved_state: Option<SigVerifyWorkerState>,
 }
 
 pub(crate) struct SigVerifyWorkerPool {
@@ -174,17 +182,24 @@ impl SigVerifyWorkerPool {
         sharable_banks: SharableBanks,
         non_vote_state: SigVerifyWorkerState,
         tpu_vote_state: SigVerifyWorkerState,
+        reserved: Option<(Receiver<PacketBatch>, SigVerifyWorkerState)>,
     ) -> Self {
         let (gossip_sender, gossip_receiver) = bounded(SIGVERIFY_GOSSIP_VOTE_WORK_CHANNEL_SIZE);
+        let (reserved_receiver, reserved_state) = match reserved {
+            Some((reserved_receiver, reserved_state)) => (reserved_receiver, Some(reserved_state)),
+            None => (never(), None),
+        };
         let channels = WorkerPoolChannels {
             non_vote_receiver,
             tpu_vote_receiver,
+            reserved_receiver,
             gossip_receiver,
             gossip_verified_vote_sender: senders.gossip_verified_vote_sender,
             forward_stage_sender: senders.forward_stage_sender,
             sharable_banks,
             non_vote_state,
             tpu_vote_state,
+            reserved_state,
         };
         let exit = Arc::new(AtomicBool::new(false));
         let worker_hdls = (0..num_workers.get())
@@ -250,6 +265,22 @@ impl SigVerifyWorkerPool {
                     Err(_) => false,
                 }
             }
+            recv(&channels.reserved_receiver) -> maybe_work => {
+                // The reserved packets are neither forwarded nor dropped for
+                // the priority floor, they are scheduled in their own lane.
+                match (maybe_work, &channels.reserved_state) {
+                    (Ok(batch), Some(reserved_state)) => Self::run_transaction_task(
+                        batch,
+                        false,
+                        &channels.forward_stage_sender,
+                        false,
+                        false,
+                        &channels.sharable_banks,
+                        reserved_state,
+                    ),
+                    _ => false,
+                }
+            }
             recv(&channels.gossip_receiver) -> maybe_work => {
                 match maybe_work {
                     Ok(work) => Self::run_gossip_task(
@@ -333,6 +364,9 @@ impl SigVerifyWorkerPool {
             .stats
             .total_verify_time_us
             .fetch_add(verify_time_us as usize, Ordering::Relaxed);
+        if let Some(tap) = state.ingest_self_test_tap.as_ref() {
+            tap.filter(&mut batch);
+        }
 
         let banking_packet_batch = BankingPacketBatch::new(vec![batch]);
         // Sample backlog before the push: measures consumer health without
@@ -11,6 +11,7 @@ use {
             GossipSigVerifier, GossipVerifiedVoteBatch, SigVerifyWorkerPool,
             SigVerifyWorkerSenders, SigVerifyWorkerState, SigVerifyWorkerStats,
         },
+        tpu_ingest_self_test::IngestSelfTestTap,
     },
     agave_banking_stage_ingress_types::{BankingPacketBatch, SchedulerPriorityFloor},
     core::time::Duration,
@@ -157,6 +158,8 @@ impl SigVerifyStage {
         forward_non_votes: bool,
         sharable_banks: SharableBanks,
         scheduler_priority_floor: Option<Arc<SchedulerPriorityFloor>>,
+        ingest_self_test_tap: Option<Arc<IngestSelfTestTap>>,
+        reserved_channels: Option<(Receiver<PacketBatch>, BankingPacketSender)>,
     ) -> (Self, GossipSigVerifyHandle) {
         let (gossip_verified_vote_sender, verified_vote_receiver) = unbounded();
         let non_vote_stats = SigVerifierStats::default();
@@ -165,6 +168,34 @@ impl SigVerifyStage {
         let mut rng = rand::rng();
         let non_vote_deduper = Arc::new(Deduper::<2, [u8]>::new(&mut rng, DEDUPER_NUM_BITS));
         let tpu_vote_deduper = Arc::new(Deduper::<2, [u8]>::new(&mut rng, DEDUPER_NUM_BITS));
+        let non_vote_worker_stats = SigVerifyWorkerStats {
+            total_batches: non_vote_stats.total_batches.clone(),
+            total_packets: non_vote_stats.total_packets.clone(),
+            total_dedup: non_vote_stats.total_dedup.clone(),
+            total_dedup_time_us: non_vote_stats.total_dedup_time_us.clone(),
+            total_valid_packets: non_vote_stats.total_valid_packets.clone(),
+            total_verify_time_us: non_vote_stats.total_verify_time_us.clone(),
+            max_pre_send_len: non_vote_stats.max_pre_send_len.clone(),
+            eviction_drops: non_vote_stats.eviction_drops.clone(),
+            total_dropped_below_priority_floor: non_vote_stats
+                .total_dropped_below_priority_floor
+                .clone(),
+            total_priority_floor_time_us: non_vote_stats.total_priority_floor_time_us.clone(),
+        };
+        // The reserved packets are verified like the other non-vote packets,
+        // and share their deduper and stats.
+        let reserved = reserved_channels.map(|(reserved_receiver, reserved_sender)| {
+            (
+                reserved_receiver,
+                SigVerifyWorkerState::new(
+                    reserved_sender,
+                    non_vote_deduper.clone(),
+                    non_vote_worker_stats.clone(),
+                    None, // reserved packets are not dropped for priority-floor
+                    None,
+                ),
+            )
+        });
         let worker_pool = SigVerifyWorkerPool::new(
             num_workers,
             packet_receiver,
@@ -178,23 +209,9 @@ impl SigVerifyStage {
             SigVerifyWorkerState::new(
                 non_vote_sender,
                 non_vote_deduper.clone(),
-                SigVerifyWorkerStats {
-                    total_batches: non_vote_stats.total_batches.clone(),
-                    total_packets: non_vote_stats.total_packets.clone(),
-                    total_dedup: non_vote_stats.total_dedup.clone(),
-                    total_dedup_time_us: non_vote_stats.total_dedup_time_us.clone(),
-                    total_valid_packets: non_vote_stats.total_valid_packets.clone(),
-                    total_verify_time_us: non_vote_stats.total_verify_time_us.clone(),
-                    max_pre_send_len: non_vote_stats.max_pre_send_len.clone(),
-                    eviction_drops: non_vote_stats.eviction_drops.clone(),
-                    total_dropped_below_priority_floor: non_vote_stats
-                        .total_dropped_below_priority_floor
-                        .clone(),
-                    total_priority_floor_time_us: non_vote_stats
-                        .total_priority_floor_time_us
-                        .clone(),
-                },
+                non_vote_worker_stats,
                 scheduler_priority_floor,
+                ingest_self_test_tap.clone(),
             ),
             SigVerifyWorkerState::new(
                 tpu_vote_sender,
@@ -216,7 +233,9 @@ impl SigVerifyStage {
                         .clone(),
                 },
                 None, // votes are not dropped for priority-floor
+                ingest_self_test_tap,
             ),
+            reserved,
         );
         let servicer_thread_hdl = Self::servicer(
             exit.clone(),
@@ -425,6 +444,8 @@ mod tests {
             false,
             sharable_banks,
             None,
+            None,
+            None,
         );
 
         let now = Instant::now();
@@ -500,6 +521,8 @@ mod tests {
             false,
             sharable_banks,
             None,
+            None,
+            None,
         );
 
         let mut bytes_batch = BytesPacketBatch::with_capacity(1);
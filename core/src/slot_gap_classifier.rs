@@ -0,0 +1,288 @@
+//! Classifies the slots skipped by the rooted fork, as observed by this node.
+//!
+//! A slot without a block on the rooted fork either was never produced by its
+//! leader, or was produced but never made it to this node. The signals this
+//! node has for the slot at the time a descendant of it roots tell the two
+//! apart: shreds received over turbine or repair, and votes for the slot seen
+//! by the vote listener.
+
+use {
+    crate::cluster_info_vote_listener::VoteTracker,
+    solana_clock::{Epoch, Slot},
+    solana_ledger::{blockstore::Blockstore, leader_schedule_cache::LeaderScheduleCache},
+    solana_pubkey::Pubkey,
+    solana_runtime::bank::Bank,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::{Arc, Mutex},
+    },
+};
+
+// Root advances spanning more slots than this, e.g. while catching up, are
+// not classified as the vote listener has no votes for most of those slots.
+const MAX_CLASSIFIED_SLOTS_PER_ROOT: u64 = 4 * 1024;
+// Number of epochs to keep the per-leader counts for
+const NUM_RETAINED_EPOCHS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotGapKind {
+    /// No shreds and no votes were observed for the slot
+    LeaderSkipped,
+    /// Votes for the slot were observed but this node never completed it
+    NotReceived,
+    Unknown,
+}
+
+/// What this node observed for a slot which is missing from the rooted fork
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotGapSignals {
+    /// Any shred of the slot was received, over turbine or repair
+    pub shreds_observed: bool,
+    /// All shreds of the slot were received
+    pub is_full: bool,
+    pub votes_observed: bool,
+}
+
+impl SlotGapKind {
+    pub fn classify(signals: &SlotGapSignals) -> Self {
+        if signals.votes_observed && !signals.is_full {
+            Self::NotReceived
+        } else if !signals.votes_observed && !signals.shreds_observed {
+            Self::LeaderSkipped
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// Number of slots of a leader missing from the rooted fork, by kind
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotGapCounts {
+    pub leader_skipped: usize,
+    pub not_received: usize,
+    pub unknown: usize,
+}
+
+impl SlotGapCounts {
+    fn add(&mut self, kind: SlotGapKind) {
+        match kind {
+            SlotGapKind::LeaderSkipped => self.leader_skipped += 1,
+            SlotGapKind::NotReceived => self.not_received += 1,
+            SlotGapKind::Unknown => self.unknown += 1,
+        }
+    }
+}
+
+#[derive(Default)]
+struct SlotGapState {
+    root: Option<Slot>,
+    root_epoch: Epoch,
+    gaps: BTreeMap<Epoch, HashMap<Pubkey, SlotGapCounts>>,
+}
+
+pub struct SlotGapClassifier {
+    leader_schedule_cache: Arc<LeaderScheduleCache>,
+    state: Mutex<SlotGapState>,
+}
+
+impl SlotGapClassifier {
+    pub fn new(leader_schedule_cache: Arc<LeaderScheduleCache>) -> Self {
+        Self {
+            leader_schedule_cache,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Classifies the slots skipped since the previous root, and reports the
+    /// counts of the previous epoch once `root_bank` is in a new epoch.
+    ///
+    /// Must be called before `vote_tracker` purges the votes for slots older
+    /// than `root_bank`.
+    pub(crate) fn on_new_root(
+        &self,
+        root_bank: &Bank,
+        blockstore: &Blockstore,
+        vote_tracker: &VoteTracker,
+    ) {
+        let root = root_bank.slot();
+        let mut state = self.state.lock().unwrap();
+        let Some(prev_root) = state.root else {
+            state.root = Some(root);
+            state.root_epoch = root_bank.epoch();
+            return;
+        };
+        // Keep the previous root if it is not older, so that the slots after
+        // it are not classified again
+        if root <= prev_root {
+            return;
+        }
+        state.root = Some(root);
+        if root - prev_root <= MAX_CLASSIFIED_SLOTS_PER_ROOT {
+            for slot in prev_root + 1..root {
+                if root_bank.ancestors.contains_key(&slot) || blockstore.is_root(slot) {
+                    continue;
+                }
+                let signals = Self::signals(slot, blockstore, vote_tracker);
+                let kind = SlotGapKind::classify(&signals);
+                let Some(leader) = self
+                    .leader_schedule_cache
+                    .slot_leader_at(slot, Some(root_bank))
+                else {
+                    continue;
+                };
+                let epoch = root_bank.epoch_schedule().get_epoch(slot);
+                state
+                    .gaps
+                    .entry(epoch)
+                    .or_default()
+                    .entry(leader.id)
+                    .or_default()
+                    .add(kind);
+            }
+        }
+
+        let root_epoch = root_bank.epoch();
+        if root_epoch > state.root_epoch {
+            if let Some(gaps) = state.gaps.get(&state.root_epoch) {
+                Self::report(state.root_epoch, gaps);
+            }
+            state.root_epoch = root_epoch;
+            while state.gaps.len() > NUM_RETAINED_EPOCHS {
+                state.gaps.pop_first();
+            }
+        }
+    }
+
+    /// Returns the counts of slots missing from the rooted fork in `epoch`,
+    /// by scheduled leader
+    pub fn leader_gaps(&self, epoch: Epoch) -> HashMap<Pubkey, SlotGapCounts> {
+        self.state
+            .lock()
+            .unwrap()
+            .gaps
+            .get(&epoch)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn signals(slot: Slot, blockstore: &Blockstore, vote_tracker: &VoteTracker) -> SlotGapSignals {
+        let slot_meta = blockstore.meta(slot).ok().flatten();
+        let votes_observed =
+            vote_tracker
+                .get_slot_vote_tracker(slot)
+                .is_some_and(|slot_vote_tracker| {
+                    slot_vote_tracker.read().unwrap().voters().next().is_some()
+                });
+        SlotGapSignals {
+            shreds_observed: slot_meta.is_some(),
+            is_full: slot_meta.is_some_and(|slot_meta| slot_meta.is_full()),
+            votes_observed,
+        }
+    }
+
+    fn report(epoch: Epoch, gaps: &HashMap<Pubkey, SlotGapCounts>) {
+        for (leader, counts) in gaps {
+            datapoint_info!(
+                "slot_gaps",
+                ("epoch", epoch, i64),
+                ("leader", leader.to_string(), String),
+                ("leader_skipped", counts.leader_skipped, i64),
+                ("not_received", counts.not_received, i64),
+                ("unknown", counts.unknown, i64),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_leader_schedule::SlotLeader,
+        solana_ledger::{
+            blockstore::make_slot_entries,
+            genesis_utils::{GenesisConfigInfo, create_genesis_config},
+            get_tmp_ledger_path_auto_delete,
+        },
+    };
+
+    #[test]
+    fn test_classify_slot_gap() {
+        let classify = |shreds_observed, is_full, votes_observed| {
+            SlotGapKind::classify(&SlotGapSignals {
+                shreds_observed,
+                is_full,
+                votes_observed,
+            })
+        };
+        assert_eq!(classify(false, false, false), SlotGapKind::LeaderSkipped);
+        assert_eq!(classify(false, false, true), SlotGapKind::NotReceived);
+        assert_eq!(classify(true, false, true), SlotGapKind::NotReceived);
+        assert_eq!(classify(true, false, false), SlotGapKind::Unknown);
+        assert_eq!(classify(true, true, false), SlotGapKind::Unknown);
+        assert_eq!(classify(true, true, true), SlotGapKind::Unknown);
+    }
+
+    #[test]
+    fn test_slot_gap_classifier() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let leader_schedule_cache = Arc::new(LeaderScheduleCache::new_from_bank(&bank0));
+        let new_child = |parent: &Arc<Bank>, slot| {
+            let leader = leader_schedule_cache.slot_leader_at(slot, None).unwrap();
+            Arc::new(Bank::new_from_parent(parent.clone(), leader, slot))
+        };
+        // Slots 1, 2 and 4 are missing from the fork 0 -> 3 -> 5
+        let bank3 = new_child(&bank0, 3);
+        let bank5 = new_child(&bank3, 5);
+
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let vote_tracker = VoteTracker::default();
+        // Votes, but no shreds, for slot 1
+        vote_tracker.insert_vote(1, Pubkey::new_unique());
+        // Some shreds, but no votes, for slot 4
+        let (mut shreds, _) = make_slot_entries(4, 3, 10);
+        shreds.pop();
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        assert!(!blockstore.meta(4).unwrap().unwrap().is_full());
+
+        let classifier = SlotGapClassifier::new(leader_schedule_cache.clone());
+        classifier.on_new_root(&bank0, &blockstore, &vote_tracker);
+        assert!(classifier.leader_gaps(0).is_empty());
+        classifier.on_new_root(&bank5, &blockstore, &vote_tracker);
+
+        let mut expected: HashMap<Pubkey, SlotGapCounts> = HashMap::new();
+        for (slot, kind) in [
+            (1, SlotGapKind::NotReceived),
+            (2, SlotGapKind::LeaderSkipped),
+            (4, SlotGapKind::Unknown),
+        ] {
+            let SlotLeader { id, .. } = leader_schedule_cache.slot_leader_at(slot, None).unwrap();
+            expected.entry(id).or_default().add(kind);
+        }
+        assert_eq!(classifier.leader_gaps(0), expected);
+        let counts = expected
+            .values()
+            .fold(SlotGapCounts::default(), |total, counts| SlotGapCounts {
+                leader_skipped: total.leader_skipped + counts.leader_skipped,
+                not_received: total.not_received + counts.not_received,
+                unknown: total.unknown + counts.unknown,
+            });
+        assert_eq!(
+            counts,
+            SlotGapCounts {
+                leader_skipped: 1,
+                not_received: 1,
+                unknown: 1,
+            }
+        );
+
+        // Roots that did not advance are ignored, and do not move the root
+        // back, which would classify the slots after them again
+        classifier.on_new_root(&bank3, &blockstore, &vote_tracker);
+        assert_eq!(classifier.leader_gaps(0), expected);
+        classifier.on_new_root(&bank5, &blockstore, &vote_tracker);
+        assert_eq!(classifier.leader_gaps(0), expected);
+    }
+}
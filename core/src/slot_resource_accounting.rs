@@ -0,0 +1,362 @@
+//! Attributes the resources used by the validator to the slots it replays.
+//!
+//! Replay records the time it spent on each slot, along with the accounts
+//! loaded and stored by its bank, once the slot is complete. The service
+//! thread merges these with the bytes the blockstore wrote for the slot once
+//! the slot is rooted, reports the merged record and keeps the records of the
+//! most recently rooted slots around. Slots that are never rooted are dropped.
+
+use {
+    crossbeam_channel::{Receiver, RecvTimeoutError, Sender, unbounded},
+    solana_clock::Slot,
+    solana_ledger::{blockstore::Blockstore, blockstore_processor::ReplaySlotStats},
+    solana_runtime::{bank::Bank, bank_forks::SharableBanks},
+    solana_svm_timings::ExecuteTimingType,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{
+            Arc, RwLock,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread::{self, Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+/// Number of rooted slots whose records are kept around
+pub const MAX_SLOT_RESOURCE_RECORDS: usize = 512;
+
+const ROOT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Resources used for a single slot
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotResourceUsage {
+    pub slot: Slot,
+    /// Wall clock time spent confirming the slot, including the verification
+    /// of its entries, in microseconds
+    pub replay_wall_us: u64,
+    /// Time spent processing the transactions of the slot, summed over the
+    /// replay threads, in microseconds
+    pub replay_cpu_us: u64,
+    pub num_loaded_accounts: u64,
+    pub loaded_accounts_bytes: u64,
+    pub num_stored_accounts: u64,
+    pub stored_accounts_bytes: u64,
+    /// Payload bytes of the shreds inserted into the blockstore
+    pub shred_bytes_written: u64,
+    /// Bytes of the transaction statuses written to the blockstore
+    pub transaction_status_bytes_written: u64,
+}
+
+impl SlotResourceUsage {
+    fn report(&self) {
+        datapoint_info!(
+            "slot_resource_usage",
+            ("slot", self.slot, i64),
+            ("replay_wall_us", self.replay_wall_us, i64),
+            ("replay_cpu_us", self.replay_cpu_us, i64),
+            ("num_loaded_accounts", self.num_loaded_accounts, i64),
+            ("loaded_accounts_bytes", self.loaded_accounts_bytes, i64),
+            ("num_stored_accounts", self.num_stored_accounts, i64),
+            ("stored_accounts_bytes", self.stored_accounts_bytes, i64),
+            ("shred_bytes_written", self.shred_bytes_written, i64),
+            (
+                "transaction_status_bytes_written",
+                self.transaction_status_bytes_written,
+                i64
+            ),
+        );
+    }
+}
+
+/// A replayed slot waiting to be rooted
+struct ReplayedSlot {
+    parent_slot: Slot,
+    usage: SlotResourceUsage,
+}
+
+/// Handle to record the replay of slots, and to read the records of the
+/// recently rooted slots
+#[derive(Clone)]
+pub struct SlotResourceAccounting {
+    replayed_sender: Sender<ReplayedSlot>,
+    records: Arc<RwLock<VecDeque<SlotResourceUsage>>>,
+}
+
+impl SlotResourceAccounting {
+    fn new() -> (Self, Receiver<ReplayedSlot>) {
+        let (replayed_sender, replayed_receiver) = unbounded();
+        let accounting = Self {
+            replayed_sender,
+            records: Arc::default(),
+        };
+        (accounting, replayed_receiver)
+    }
+
+    /// Records the resources used to replay `bank`, once its replay is
+    /// complete
+    pub fn record_replay(&self, bank: &Bank, replay_stats: &ReplaySlotStats) {
+        let execute_timings = &replay_stats.batch_execute.totals.metrics;
+        let replay_cpu_us = [
+            ExecuteTimingType::CheckUs,
+            ExecuteTimingType::ValidateFeesUs,
+            ExecuteTimingType::LoadUs,
+            ExecuteTimingType::ExecuteUs,
+            ExecuteTimingType::StoreUs,
+        ]
+        .into_iter()
+        .map(|timing_type| execute_timings[timing_type].0)
+        .fold(0, u64::saturating_add);
+        let load_stats = bank.get_accounts_load_stats();
+        let store_stats = bank.get_bank_hash_stats();
+        let usage = SlotResourceUsage {
+            slot: bank.slot(),
+            replay_wall_us: replay_stats.confirmation_elapsed,
+            replay_cpu_us,
+            num_loaded_accounts: load_stats.num_loaded_accounts,
+            loaded_accounts_bytes: load_stats.loaded_data_len,
+            num_stored_accounts: store_stats.num_updated_accounts,
+            stored_accounts_bytes: store_stats.total_data_len,
+            ..SlotResourceUsage::default()
+        };
+        // The service is gone once the validator exits
+        let _ = self.replayed_sender.send(ReplayedSlot {
+            parent_slot: bank.parent_slot(),
+            usage,
+        });
+    }
+
+    /// Returns the records of the most recently rooted slots, oldest first
+    pub fn rooted_slots_usage(&self) -> Vec<SlotResourceUsage> {
+        self.records.read().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Merges the records of the replayed slots as they are rooted
+struct SlotResourceCollector {
+    blockstore: Arc<Blockstore>,
+    replayed_slots: HashMap<Slot, ReplayedSlot>,
+    records: Arc<RwLock<VecDeque<SlotResourceUsage>>>,
+    root: Slot,
+}
+
+impl SlotResourceCollector {
+    fn on_replayed(&mut self, replayed_slot: ReplayedSlot) {
+        if replayed_slot.usage.slot > self.root {
+            self.replayed_slots
+                .insert(replayed_slot.usage.slot, replayed_slot);
+        }
+    }
+
+    fn on_new_root(&mut self, root: Slot) {
+        self.root = root;
+        let mut rooted = vec![];
+        let mut slot = root;
+        while let Some(ReplayedSlot { parent_slot, usage }) = self.replayed_slots.remove(&slot) {
+            rooted.push(usage);
+            slot = parent_slot;
+        }
+        // The remaining slots up to the root were on other forks
+        self.replayed_slots.retain(|slot, _| *slot > root);
+
+        for usage in rooted.iter_mut().rev() {
+            if let Some(write_stats) = self.blockstore.slots_stats.get_write_stats(usage.slot) {
+                usage.shred_bytes_written = write_stats.shred_bytes;
+                usage.transaction_status_bytes_written = write_stats.transaction_status_bytes;
+            }
+            usage.report();
+        }
+
+        let mut records = self.records.write().unwrap();
+        records.extend(rooted.into_iter().rev());
+        let num_excess = records.len().saturating_sub(MAX_SLOT_RESOURCE_RECORDS);
+        records.drain(..num_excess);
+    }
+}
+
+pub struct SlotResourceAccountingService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl SlotResourceAccountingService {
+    pub fn new(
+        blockstore: Arc<Blockstore>,
+        sharable_banks: SharableBanks,
+        exit: Arc<AtomicBool>,
+    ) -> (Self, SlotResourceAccounting) {
+        let (accounting, replayed_receiver) = SlotResourceAccounting::new();
+        let mut collector = SlotResourceCollector {
+            blockstore,
+            replayed_slots: HashMap::new(),
+            records: accounting.records.clone(),
+            root: sharable_banks.root().slot(),
+        };
+        let thread_hdl = Builder::new()
+            .name("solSlotResource".to_string())
+            .spawn(move || {
+                while !exit.load(Ordering::Relaxed) {
+                    match replayed_receiver.recv_timeout(ROOT_CHECK_INTERVAL) {
+                        Ok(replayed_slot) => collector.on_replayed(replayed_slot),
+                        Err(RecvTimeoutError::Timeout) => (),
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    // Slots are replayed before they are rooted, so drain the
+                    // channel before looking at the root
+                    for replayed_slot in replayed_receiver.try_iter() {
+                        collector.on_replayed(replayed_slot);
+                    }
+                    let root = sharable_banks.root().slot();
+                    if root > collector.root {
+                        collector.on_new_root(root);
+                    }
+                }
+            })
+            .unwrap();
+        (Self { thread_hdl }, accounting)
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        agave_votor_messages::migration::MigrationStatus,
+        solana_entry::entry::{create_ticks, next_entry},
+        solana_keypair::Keypair,
+        solana_leader_schedule::SlotLeader,
+        solana_ledger::{
+            blockstore::entries_to_test_shreds,
+            blockstore_processor::{
+                ConfirmationProgress, SkipVerification, confirm_slot, create_thread_pool,
+            },
+            genesis_utils::{GenesisConfigInfo, create_genesis_config},
+            get_tmp_ledger_path_auto_delete,
+        },
+        solana_pubkey::Pubkey,
+        solana_runtime::bank_forks::BankForks,
+        solana_signer::Signer,
+        solana_system_transaction as system_transaction,
+        solana_transaction_status::TransactionStatusMeta,
+    };
+
+    #[test]
+    fn test_slot_resource_accounting() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000_000);
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+        let bank_forks = BankForks::new_rw_arc(Bank::new_for_tests(&genesis_config));
+        let replay_tx_thread_pool = create_thread_pool(1);
+        let (accounting, replayed_receiver) = SlotResourceAccounting::new();
+        let mut collector = SlotResourceCollector {
+            blockstore: blockstore.clone(),
+            replayed_slots: HashMap::new(),
+            records: accounting.records.clone(),
+            root: 0,
+        };
+
+        // Replay a small ledger of three slots with a transfer each, slot 2
+        // being on another fork
+        for (slot, parent_slot) in [(1, 0), (2, 0), (3, 1)] {
+            let parent_bank = bank_forks.read().unwrap().get(parent_slot).unwrap();
+            let bank = Bank::new_from_parent(parent_bank.clone(), SlotLeader::default(), slot);
+            let bank = bank_forks.write().unwrap().insert(bank);
+            let blockhash = parent_bank.last_blockhash();
+            let tx = system_transaction::transfer(
+                &mint_keypair,
+                &Keypair::new().pubkey(),
+                genesis_config.rent.minimum_balance(0),
+                blockhash,
+            );
+            let mut entries = vec![next_entry(&blockhash, 1, vec![tx.clone()])];
+            entries.extend(create_ticks(
+                (slot - parent_slot) * genesis_config.ticks_per_slot,
+                0,
+                entries[0].hash,
+            ));
+            blockstore
+                .insert_shreds(
+                    entries_to_test_shreds(&entries, slot, parent_slot, true, 0),
+                    None,
+                    false,
+                )
+                .unwrap();
+
+            let mut replay_stats = ReplaySlotStats::default();
+            confirm_slot(
+                &blockstore,
+                &bank,
+                &replay_tx_thread_pool,
+                &mut replay_stats,
+                &mut ConfirmationProgress::new(blockhash),
+                SkipVerification::ALL,
+                None,
+                None,
+                None,
+                None,
+                false,
+                None,
+                None,
+                &MigrationStatus::default(),
+            )
+            .unwrap();
+            bank.freeze();
+            blockstore
+                .write_transaction_status(
+                    slot,
+                    tx.signatures[0],
+                    tx.message.account_keys.iter().map(|key| (key, true)),
+                    TransactionStatusMeta {
+                        fee: 5_000,
+                        pre_balances: vec![1, 2, 3],
+                        post_balances: vec![1, 2, 3],
+                        ..TransactionStatusMeta::default()
+                    },
+                    0,
+                )
+                .unwrap();
+            accounting.record_replay(&bank, &replay_stats);
+        }
+
+        replayed_receiver
+            .try_iter()
+            .for_each(|replayed_slot| collector.on_replayed(replayed_slot));
+        collector.on_new_root(3);
+        let records = accounting.rooted_slots_usage();
+        assert_eq!(
+            records.iter().map(|usage| usage.slot).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        for usage in records {
+            assert!(usage.replay_wall_us > 0, "{usage:?}");
+            assert!(usage.num_loaded_accounts > 0, "{usage:?}");
+            assert!(usage.num_stored_accounts > 0, "{usage:?}");
+            assert!(usage.shred_bytes_written > 0, "{usage:?}");
+            assert!(usage.transaction_status_bytes_written > 0, "{usage:?}");
+        }
+        // The slot on the other fork is dropped
+        assert!(collector.replayed_slots.is_empty());
+
+        // Only the records of the most recently rooted slots are kept
+        for slot in 4..MAX_SLOT_RESOURCE_RECORDS as Slot + 5 {
+            collector.on_replayed(ReplayedSlot {
+                parent_slot: slot - 1,
+                usage: SlotResourceUsage {
+                    slot,
+                    ..SlotResourceUsage::default()
+                },
+            });
+        }
+        collector.on_new_root(MAX_SLOT_RESOURCE_RECORDS as Slot + 4);
+        let records = accounting.rooted_slots_usage();
+        assert_eq!(records.len(), MAX_SLOT_RESOURCE_RECORDS);
+        assert_eq!(records[0].slot, 5);
+    }
+}
@@ -1,10 +1,12 @@
 mod snapshot_gossip_manager;
 use {
+    crate::accounts_db_manifest::AccountsDbManifest,
     agave_fs::io_setup::IoSetupState,
     agave_snapshots::{
         SnapshotKind, paths as snapshot_paths, snapshot_config::SnapshotConfig,
         snapshot_hash::StartingSnapshotHashes,
     },
+    rayon::{ThreadPool, ThreadPoolBuilder},
     snapshot_gossip_manager::SnapshotGossipManager,
     solana_accounts_db::account_storage_entry::AccountStorageEntry,
     solana_clock::Slot,
@@ -18,6 +20,8 @@ use {
         snapshot_utils,
     },
     std::{
+        num::NonZeroUsize,
+        path::PathBuf,
         sync::{
             Arc, Mutex,
             atomic::{AtomicBool, Ordering},
@@ -37,6 +41,12 @@ impl SnapshotPackagerService {
     /// If there are no snapshot packages to handle, limit how often we re-check
     const LOOP_LIMITER: Duration = Duration::from_millis(100);
 
+    /// By default packaging a snapshot does not run in parallel
+    pub const DEFAULT_NUM_THREADS: NonZeroUsize = NonZeroUsize::MIN;
+
+    /// `accounts_db_manifest` is written under the ledger path that comes with
+    /// it whenever a bank snapshot is serialized, if any
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         pending_snapshot_packages: Arc<Mutex<PendingSnapshotPackages>>,
         starting_snapshot_hashes: Option<StartingSnapshotHashes>,
@@ -46,7 +56,13 @@ impl SnapshotPackagerService {
         snapshot_controller: Arc<SnapshotController>,
         enable_gossip_push: bool,
         niceness_adj: i8,
+        num_threads: NonZeroUsize,
+        accounts_db_manifest: Option<(AccountsDbManifest, PathBuf)>,
     ) -> Self {
+        // Snapshot packages are still handled one at a time, in order, so a full snapshot is
+        // always packaged before its incrementals. The pool only parallelizes work within a
+        // single package.
+        let thread_pool = Self::build_thread_pool(num_threads);
         let t_snapshot_packager = Builder::new()
             .name("solSnapshotPkgr".to_string())
             .spawn(move || {
@@ -131,7 +147,7 @@ impl SnapshotPackagerService {
                         snapshot_config.snapshot_version,
                         snapshot_package.bank_snapshot_package,
                         snapshot_package.snapshot_storages.as_slice(),
-                        exit_backpressure.is_none(),
+                        exit_backpressure.is_none().then_some(&thread_pool),
                         &io_setup,
                     );
 
@@ -145,6 +161,13 @@ impl SnapshotPackagerService {
                         exit.store(true, Ordering::Relaxed);
                         break;
                     };
+                    if let Some((accounts_db_manifest, ledger_path)) = &accounts_db_manifest {
+                        // The local state to restart from was just written with the
+                        // current options
+                        if let Err(err) = accounts_db_manifest.write(ledger_path) {
+                            warn!("Failed to write the accounts-db manifest: {err}");
+                        }
+                    }
 
                     // Snapshot archive is unlikely to be read back soon, so allow direct-io now.
                     let io_setup = io_setup.with_direct_io(snapshot_config.use_direct_io);
@@ -224,6 +247,14 @@ impl SnapshotPackagerService {
         self.t_snapshot_packager.join()
     }
 
+    fn build_thread_pool(num_threads: NonZeroUsize) -> ThreadPool {
+        ThreadPoolBuilder::new()
+            .num_threads(num_threads.get())
+            .thread_name(|i| format!("solSnapPkg{i:02}"))
+            .build()
+            .expect("new rayon threadpool")
+    }
+
     /// Returns the next snapshot package to handle
     fn get_next_snapshot_package(
         pending_snapshot_packages: &Mutex<PendingSnapshotPackages>,
@@ -258,7 +289,7 @@ impl SnapshotPackagerService {
                 snapshot_config.snapshot_version,
                 bank_snapshot_package,
                 snapshot_storages.as_slice(),
-                false,
+                None,
                 &io_setup,
             );
             if let Err(err) = result {
@@ -354,3 +385,80 @@ struct TeardownState {
     /// when the snapshot archive was written.
     bank_snapshot_package: Option<BankSnapshotPackage>,
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        agave_snapshots::snapshot_hash::{FullSnapshotHash, SnapshotHash},
+        solana_gossip::node::Node,
+        solana_hash::Hash,
+        solana_keypair::Keypair,
+        solana_net_utils::SocketAddrSpace,
+        solana_signer::Signer,
+    };
+
+    #[test]
+    fn test_build_thread_pool() {
+        for num_threads in [1, 3] {
+            let thread_pool =
+                SnapshotPackagerService::build_thread_pool(NonZeroUsize::new(num_threads).unwrap());
+            assert_eq!(thread_pool.current_num_threads(), num_threads);
+            assert_eq!(thread_pool.install(rayon::current_num_threads), num_threads);
+            let thread_name = thread_pool.install(|| thread::current().name().map(str::to_string));
+            assert!(thread_name.unwrap().starts_with("solSnapPkg"));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_gossip_push() {
+        for enable_gossip_push in [false, true] {
+            let keypair = Arc::new(Keypair::new());
+            let pubkey = keypair.pubkey();
+            let node_info = Node::new_localhost_with_pubkey(&pubkey);
+            let cluster_info = Arc::new(ClusterInfo::new(
+                node_info.info,
+                keypair,
+                SocketAddrSpace::Unspecified,
+            ));
+            let (snapshot_request_sender, _snapshot_request_receiver) =
+                crossbeam_channel::unbounded();
+            let snapshot_controller = Arc::new(SnapshotController::new(
+                snapshot_request_sender,
+                SnapshotConfig::default(),
+                0,
+            ));
+            let full_snapshot_hash = (42, Hash::new_unique());
+            let starting_snapshot_hashes = StartingSnapshotHashes {
+                full: FullSnapshotHash((full_snapshot_hash.0, SnapshotHash(full_snapshot_hash.1))),
+                incremental: None,
+            };
+            let exit = Arc::new(AtomicBool::new(true));
+
+            // The starting snapshot hashes are pushed, if at all, before the
+            // service first checks for exit
+            SnapshotPackagerService::new(
+                Arc::default(),
+                Some(starting_snapshot_hashes),
+                exit,
+                None,
+                cluster_info.clone(),
+                snapshot_controller,
+                enable_gossip_push,
+                0,
+                SnapshotPackagerService::DEFAULT_NUM_THREADS,
+                None,
+            )
+            .join()
+            .unwrap();
+
+            cluster_info.flush_push_queue();
+            let snapshot_hashes = cluster_info.get_snapshot_hashes_for_node(&pubkey);
+            if enable_gossip_push {
+                assert_eq!(snapshot_hashes.unwrap().full, full_snapshot_hash);
+            } else {
+                assert!(snapshot_hashes.is_none());
+            }
+        }
+    }
+}
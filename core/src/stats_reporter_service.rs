@@ -1,5 +1,6 @@
 use {
     crossbeam_channel::{Receiver, RecvTimeoutError},
+    solana_perf::watchdog::Watchdog,
     std::{
         result::Result,
         sync::{
@@ -19,11 +20,14 @@ impl StatsReporterService {
     pub fn new(
         reporting_receiver: Receiver<Box<dyn FnOnce() + Send>>,
         exit: Arc<AtomicBool>,
+        watchdog: Arc<Watchdog>,
     ) -> Self {
         let thread_hdl = Builder::new()
             .name("solStatsReport".to_owned())
             .spawn(move || {
+                let heartbeat = watchdog.register("StatsReporterService", Duration::from_secs(60));
                 loop {
+                    heartbeat.beat();
                     if exit.load(Ordering::Relaxed) {
                         return;
                     }
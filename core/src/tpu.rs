@@ -5,36 +5,45 @@ use {
     crate::{
         admin_rpc_post_init::{KeyUpdaterType, KeyUpdaters},
         banking_stage::{
-            BankingControlMsg, BankingStage, BankingStageHandle,
+            BankingControlMsg, BankingStage, BankingStageHandle, ReservedIngest,
             transaction_scheduler::scheduler_controller::SchedulerConfig,
         },
-        banking_trace::{Channels, TracerThread},
+        banking_trace::{BankingPacketSender, Channels, TracerThread},
         cluster_info_vote_listener::{
             ClusterInfoVoteListener, DuplicateConfirmedSlotsSender, GossipVerifiedVoteHashSender,
-            VoteTracker,
+            VoteForwardingGate, VoteTracker, VoteVerifyBatchConfig,
         },
         fetch_stage::FetchStage,
         forwarding_stage::{
             ForwardAddressGetter, ForwardingClientConfig, SpawnForwardingStageResult,
             spawn_forwarding_stage,
         },
+        gossip_vote_landing::GossipVoteLandingTracker,
+        optimistic_confirmation_violations::OptimisticConfirmationViolationLog,
+        restart_latency_tracker::RestartLatencyTracker,
         sigverify_stage::SigVerifyStage,
+        slot_gap_classifier::SlotGapClassifier,
         staked_nodes_updater_service::StakedNodesUpdaterService,
         tpu_entry_notifier::TpuEntryNotifier,
+        tpu_ingest_self_test::IngestSelfTestTap,
         validator::{BlockProductionMethod, GeneratorConfig},
+        vote_account_monitor::VoteAccountMonitor,
+        vote_capture::VoteCapture,
+        vote_tracker_archive::VoteTrackerArchiver,
     },
     agave_banking_stage_ingress_types::SchedulerPriorityFloor,
     agave_votor::event::VotorEventSender,
     agave_votor_messages::VerifiedVoterSlotsSender,
     agave_xdp::transmitter::XdpSender,
-    crossbeam_channel::{Receiver, bounded, unbounded},
+    crossbeam_channel::{Receiver, Sender, bounded, unbounded},
     solana_clock::Slot,
-    solana_gossip::cluster_info::ClusterInfo,
+    solana_gossip::{cluster_info::ClusterInfo, node::Node},
     solana_keypair::Keypair,
     solana_ledger::{
         blockstore::Blockstore, blockstore_processor::TransactionStatusSender,
         entry_notifier_service::EntryNotifierSender,
     },
+    solana_perf::{packet::PacketBatch, watchdog::Watchdog},
     solana_poh::{
         poh_recorder::{PohRecorder, WorkingBankEntryOrMarker},
         transaction_recorder::TransactionRecorder,
@@ -52,8 +61,10 @@ use {
     solana_streamer::{
         evicting_sender::EvictingSender,
         quic::{
-            SimpleQosQuicStreamerConfig, SpawnServerResult, SwQosQuicStreamerConfig,
-            spawn_simple_qos_server, spawn_stake_weighted_qos_server,
+            SimpleQosConnections, SimpleQosQuicStreamerConfig, SpawnServerResult, SwQosConnections,
+            SwQosQuicStreamerConfig, reuseport_endpoint_rebinder,
+            spawn_simple_qos_server_with_connections,
+            spawn_stake_weighted_qos_server_with_connections,
         },
         quic_socket::QuicSocket,
         streamer::StakedNodes,
@@ -84,21 +95,66 @@ pub struct TpuSockets {
     pub vote_forwarding_client: UdpSocket,
 }
 
+/// The reserved ingest channel of the TPU. Its packets are signature verified
+/// like the other non-vote packets, then scheduled against the compute units
+/// reserved for them.
+pub struct TpuReservedIngest {
+    pub packet_receiver: Receiver<PacketBatch>,
+    /// Sends the verified packets to `banking.receiver`
+    pub verified_sender: BankingPacketSender,
+    pub banking: ReservedIngest,
+}
+
 // Conservatively allow 20 TPS per validator.
 pub const MAX_VOTES_PER_SECOND: u64 = 20;
 
 /// Size of the channel between streamer and TPU sigverify stage. The values have been selected to
 /// be conservative max of obsersed on mnb during high-load events.
-const TPU_CHANNEL_SIZE: usize = 50_000;
+pub const TPU_CHANNEL_SIZE: usize = 50_000;
 
 /// Size of the channel between the vote streamer and the TPU sigverify stage.
 /// Chosen based on nominal voting load for a cluster with ~2000 validators + some margin.
-pub(crate) const TPU_VOTE_CHANNEL_SIZE: usize = 4_000;
+pub const TPU_VOTE_CHANNEL_SIZE: usize = 4_000;
 
 /// Size of the channel between the TPU forwards streamer and the fetch stage.
 /// Mirrors `TPU_CHANNEL_SIZE`; the streamer uses `try_send`, so an over-full
 /// channel drops packets (tracked via streamer metrics) rather than blocking.
-const TPU_FORWARD_CHANNEL_SIZE: usize = 50_000;
+pub const TPU_FORWARD_CHANNEL_SIZE: usize = 50_000;
+
+/// Sizes of the channels the TPU QUIC servers send their packets to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TpuChannelSizes {
+    pub tpu: NonZeroUsize,
+    pub tpu_forwards: NonZeroUsize,
+    pub vote: NonZeroUsize,
+}
+
+impl Default for TpuChannelSizes {
+    fn default() -> Self {
+        Self {
+            tpu: NonZeroUsize::new(TPU_CHANNEL_SIZE).unwrap(),
+            tpu_forwards: NonZeroUsize::new(TPU_FORWARD_CHANNEL_SIZE).unwrap(),
+            vote: NonZeroUsize::new(TPU_VOTE_CHANNEL_SIZE).unwrap(),
+        }
+    }
+}
+
+/// The channels the TPU QUIC servers send their packets to
+pub(crate) struct TpuPacketChannels {
+    pub(crate) tpu: (Sender<PacketBatch>, Receiver<PacketBatch>),
+    pub(crate) tpu_forwards: (Sender<PacketBatch>, Receiver<PacketBatch>),
+    pub(crate) vote: (Sender<PacketBatch>, Receiver<PacketBatch>),
+}
+
+impl TpuPacketChannels {
+    pub(crate) fn new(channel_sizes: TpuChannelSizes) -> Self {
+        Self {
+            tpu: bounded(channel_sizes.tpu.get()),
+            tpu_forwards: bounded(channel_sizes.tpu_forwards.get()),
+            vote: bounded(channel_sizes.vote.get()),
+        }
+    }
+}
 
 pub struct Tpu {
     fetch_stage: FetchStage,
@@ -109,6 +165,10 @@ pub struct Tpu {
     broadcast_stage: BroadcastStage,
     tpu_quic_t: thread::JoinHandle<()>,
     tpu_forwards_quic_t: thread::JoinHandle<()>,
+    tpu_connections: SwQosConnections,
+    tpu_forwards_connections: SwQosConnections,
+    tpu_vote_connections: SimpleQosConnections,
+    ingest_self_test_tap: Arc<IngestSelfTestTap>,
     tpu_entry_notifier: Option<TpuEntryNotifier>,
     staked_nodes_updater_service: StakedNodesUpdaterService,
     tracer_thread_hdl: TracerThread,
@@ -149,9 +209,10 @@ impl Tpu {
         shared_staked_nodes_overrides: Arc<RwLock<HashMap<Pubkey, u64>>>,
         banking_tracer_channels: Channels,
         tracer_thread_hdl: TracerThread,
-        tpu_quic_server_config: SwQosQuicStreamerConfig,
-        tpu_fwd_quic_server_config: SwQosQuicStreamerConfig,
-        vote_quic_server_config: SimpleQosQuicStreamerConfig,
+        mut tpu_quic_server_config: SwQosQuicStreamerConfig,
+        mut tpu_fwd_quic_server_config: SwQosQuicStreamerConfig,
+        mut vote_quic_server_config: SimpleQosQuicStreamerConfig,
+        channel_sizes: TpuChannelSizes,
         prioritization_fee_cache: Option<Arc<PrioritizationFeeCache>>,
         tpu_sigverify_threads: NonZeroUsize,
         block_production_method: BlockProductionMethod,
@@ -165,6 +226,18 @@ impl Tpu {
         scheduler_bindings: Option<(PathBuf, mpsc::Sender<BankingControlMsg>)>,
         cancel: CancellationToken,
         votor_event_sender: VotorEventSender,
+        vote_capture: Option<Arc<VoteCapture>>,
+        optimistic_confirmation_violation_log: Arc<OptimisticConfirmationViolationLog>,
+        slot_gap_classifier: Arc<SlotGapClassifier>,
+        vote_account_monitor: Arc<VoteAccountMonitor>,
+        restart_latency_tracker: Arc<RestartLatencyTracker>,
+        vote_tracker_archiver: Option<VoteTrackerArchiver>,
+        gossip_vote_landing_tracker: Arc<GossipVoteLandingTracker>,
+        reserved_ingest: Option<TpuReservedIngest>,
+        gossip_vote_forwarding_leader_window_slots: Option<u64>,
+        max_gossip_vote_age_ms: Option<u64>,
+        vote_listener_slot_filter: Option<Arc<RwLock<HashSet<Slot>>>>,
+        watchdog: Arc<Watchdog>,
     ) -> Self {
         let TpuSockets {
             vote: tpu_vote_sockets,
@@ -175,12 +248,13 @@ impl Tpu {
             vote_forwarding_client: vote_forwarding_client_socket,
         } = sockets;
 
-        let (packet_sender, packet_receiver) = bounded(TPU_CHANNEL_SIZE);
-        let (vote_packet_sender, vote_packet_receiver) = bounded(TPU_VOTE_CHANNEL_SIZE);
+        let TpuPacketChannels {
+            tpu: (packet_sender, packet_receiver),
+            tpu_forwards: (forwarded_packet_sender, forwarded_packet_receiver),
+            vote: (vote_packet_sender, vote_packet_receiver),
+        } = TpuPacketChannels::new(channel_sizes);
         let evicting_vote_sender =
             EvictingSender::new(vote_packet_sender.clone(), vote_packet_receiver.clone());
-        let (forwarded_packet_sender, forwarded_packet_receiver) =
-            bounded(TPU_FORWARD_CHANNEL_SIZE);
         let fetch_stage = FetchStage::new_with_sender(
             tpu_vote_sockets,
             exit.clone(),
@@ -207,6 +281,20 @@ impl Tpu {
             gossip_vote_receiver,
         } = banking_tracer_channels;
 
+        // The QUIC sockets of the node are bound with SO_REUSEPORT, so a dead
+        // endpoint can be rebound alongside the sockets still bound to its
+        // address
+        let endpoint_rebinder = reuseport_endpoint_rebinder(Node::quic_server_socket_config());
+        for quic_streamer_config in [
+            &mut vote_quic_server_config.quic_streamer_config,
+            &mut tpu_quic_server_config.quic_streamer_config,
+            &mut tpu_fwd_quic_server_config.quic_streamer_config,
+        ] {
+            quic_streamer_config
+                .endpoint_rebinder
+                .get_or_insert_with(|| endpoint_rebinder.clone());
+        }
+
         // Streamer for Votes:
         let quic_vote_sockets: Vec<QuicSocket> =
             tpu_vote_quic_sockets.into_iter().map(Into::into).collect();
@@ -217,7 +305,8 @@ impl Tpu {
                 key_updater: vote_streamer_key_updater,
             },
             _banlist,
-        ) = spawn_simple_qos_server(
+            tpu_vote_connections,
+        ) = spawn_simple_qos_server_with_connections(
             "solQuicTVo",
             "quic_streamer_tpu_vote",
             quic_vote_sockets,
@@ -237,11 +326,14 @@ impl Tpu {
         // Streamer for TPU
         let transactions_quic_sockets =
             into_quic_sockets(transactions_quic_sockets, quic_xdp_sender.clone());
-        let SpawnServerResult {
-            endpoints: _,
-            thread: tpu_quic_t,
-            key_updater,
-        } = spawn_stake_weighted_qos_server(
+        let (
+            SpawnServerResult {
+                endpoints: _,
+                thread: tpu_quic_t,
+                key_updater,
+            },
+            tpu_connections,
+        ) = spawn_stake_weighted_qos_server_with_connections(
             "solQuicTpu",
             "quic_streamer_tpu",
             transactions_quic_sockets,
@@ -257,11 +349,14 @@ impl Tpu {
         // Streamer for TPU forward
         let transactions_forwards_quic_sockets =
             into_quic_sockets(transactions_forwards_quic_sockets, quic_xdp_sender);
-        let SpawnServerResult {
-            endpoints: _,
-            thread: tpu_forwards_quic_t,
-            key_updater: forwards_key_updater,
-        } = spawn_stake_weighted_qos_server(
+        let (
+            SpawnServerResult {
+                endpoints: _,
+                thread: tpu_forwards_quic_t,
+                key_updater: forwards_key_updater,
+            },
+            tpu_forwards_connections,
+        ) = spawn_stake_weighted_qos_server_with_connections(
             "solQuicTpuFwd",
             "quic_streamer_tpu_forwards",
             transactions_forwards_quic_sockets,
@@ -280,6 +375,17 @@ impl Tpu {
         // a priority floor under saturation; sigverify reads it and drops
         // below-floor packets ahead of signature verification.
         let scheduler_priority_floor = Arc::new(SchedulerPriorityFloor::new());
+        let ingest_self_test_tap = Arc::new(IngestSelfTestTap::default());
+        let (reserved_channels, reserved_ingest) = reserved_ingest
+            .map(|reserved_ingest| {
+                let TpuReservedIngest {
+                    packet_receiver,
+                    verified_sender,
+                    banking,
+                } = reserved_ingest;
+                ((packet_receiver, verified_sender), banking)
+            })
+            .unzip();
 
         let (sigverify_stage, gossip_sigverify_handle) = SigVerifyStage::new(
             packet_receiver,
@@ -291,8 +397,18 @@ impl Tpu {
             enable_block_production_forwarding,
             bank_forks.read().unwrap().sharable_banks(),
             Some(scheduler_priority_floor.clone()),
+            Some(ingest_self_test_tap.clone()),
+            reserved_channels,
         );
 
+        let vote_forwarding_gate = {
+            let poh_recorder = poh_recorder.read().unwrap();
+            VoteForwardingGate::new(
+                gossip_vote_forwarding_leader_window_slots,
+                poh_recorder.shared_leader_state(),
+                poh_recorder.ticks_per_slot(),
+            )
+        };
         let cluster_info_vote_listener = ClusterInfoVoteListener::new(
             exit.clone(),
             cluster_info.clone(),
@@ -307,6 +423,18 @@ impl Tpu {
             blockstore.clone(),
             bank_notification_sender,
             duplicate_confirmed_slot_sender,
+            vote_capture,
+            optimistic_confirmation_violation_log,
+            slot_gap_classifier,
+            vote_account_monitor,
+            restart_latency_tracker,
+            vote_tracker_archiver,
+            gossip_vote_landing_tracker,
+            VoteVerifyBatchConfig::default(),
+            vote_forwarding_gate,
+            max_gossip_vote_age_ms,
+            vote_listener_slot_filter,
+            watchdog,
         );
 
         let banking_stage = BankingStage::new_num_threads(
@@ -326,6 +454,7 @@ impl Tpu {
             prioritization_fee_cache,
             filter_keys,
             scheduler_priority_floor,
+            reserved_ingest,
         );
 
         #[cfg(unix)]
@@ -389,6 +518,10 @@ impl Tpu {
             broadcast_stage,
             tpu_quic_t,
             tpu_forwards_quic_t,
+            tpu_connections,
+            tpu_forwards_connections,
+            tpu_vote_connections,
+            ingest_self_test_tap,
             tpu_entry_notifier,
             staked_nodes_updater_service,
             tracer_thread_hdl,
@@ -396,6 +529,27 @@ impl Tpu {
         }
     }
 
+    /// Connections of the TPU QUIC server
+    pub fn tpu_connections(&self) -> &SwQosConnections {
+        &self.tpu_connections
+    }
+
+    /// Connections of the TPU forwards QUIC server
+    pub fn tpu_forwards_connections(&self) -> &SwQosConnections {
+        &self.tpu_forwards_connections
+    }
+
+    /// Connections of the TPU vote QUIC server
+    pub fn tpu_vote_connections(&self) -> &SimpleQosConnections {
+        &self.tpu_vote_connections
+    }
+
+    /// Tap of the sigverify workers counting the packets of the ingest
+    /// self-test
+    pub fn ingest_self_test_tap(&self) -> &Arc<IngestSelfTestTap> {
+        &self.ingest_self_test_tap
+    }
+
     pub fn join(self) -> thread::Result<()> {
         let results = vec![
             self.fetch_stage.join(),
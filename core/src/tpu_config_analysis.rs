@@ -0,0 +1,550 @@
+//! Checks the TPU QUIC server configurations jointly for limits which cannot
+//! all be met.
+//!
+//! The QUIC servers hand each packet they ingest to a bounded channel with
+//! `try_send`, so a configuration whose stream limits outpace the channels,
+//! or which admits no connection at all, only shows as dropped packets. The
+//! analysis computes the highest rate at which each server may ingest packets
+//! and the time its channel takes to fill at that rate, and reports the
+//! limits which cannot be met along with the values to set them to.
+
+use {
+    crate::validator::ValidatorTpuConfig,
+    solana_streamer::{
+        nonblocking::swqos::SwQosConfig,
+        quic::{DEFAULT_MAX_UNSTAKED_CONNECTIONS, SwQosQuicStreamerConfig},
+    },
+    std::{fmt, time::Duration},
+};
+
+/// Time the sigverify stage may go without draining the channels of the QUIC
+/// servers, which the channels must be able to buffer at the highest stream
+/// rate
+pub const DEFAULT_TPU_DRAIN_WINDOW: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TpuServer {
+    Tpu,
+    TpuForwards,
+    Vote,
+}
+
+impl fmt::Display for TpuServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tpu => "TPU",
+            Self::TpuForwards => "TPU forwards",
+            Self::Vote => "TPU vote",
+        })
+    }
+}
+
+/// How much a QUIC server may ingest, and how long its channel buffers it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TpuIngestCapacity {
+    pub server: TpuServer,
+    /// Highest number of streams the server accepts per millisecond, None if
+    /// only limited per connection
+    pub max_streams_per_ms: Option<u64>,
+    /// Number of connections the server holds at once
+    pub max_connections: usize,
+    pub channel_size: usize,
+    /// Time the channel takes to fill at `max_streams_per_ms` when not
+    /// drained, None if the rate is not limited or zero
+    pub fill_time: Option<Duration>,
+}
+
+/// The limit of a QUIC server which cannot be met
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TpuConfigConstraint {
+    /// The server admits no connection
+    NoConnections,
+    /// The server accepts no stream
+    NoStreams,
+    /// The main TPU server admits only the peers staked in the cluster, with
+    /// no stake overrides for the peers expected to send transactions
+    NoUnstakedConnections,
+    /// The channel fills up at the stream rate within the drain window
+    ChannelFillsWithinDrainWindow {
+        fill_time: Duration,
+        drain_window: Duration,
+    },
+    /// The channel cannot hold a packet for each connection, so the packets
+    /// of some connections are evicted before the channel is drained
+    ChannelSmallerThanConnections,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TpuConfigIssue {
+    pub server: TpuServer,
+    pub constraint: TpuConfigConstraint,
+    /// The values to set to lift the constraint
+    pub suggestion: String,
+}
+
+impl fmt::Display for TpuConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let server = self.server;
+        match self.constraint {
+            TpuConfigConstraint::NoConnections => {
+                write!(f, "{server} QUIC server admits no connection")?
+            }
+            TpuConfigConstraint::NoStreams => write!(f, "{server} QUIC server accepts no stream")?,
+            TpuConfigConstraint::NoUnstakedConnections => write!(
+                f,
+                "{server} QUIC server admits no unstaked connection and no stake is overridden"
+            )?,
+            TpuConfigConstraint::ChannelFillsWithinDrainWindow {
+                fill_time,
+                drain_window,
+            } => write!(
+                f,
+                "{server} QUIC server channel fills up in {fill_time:?} at the stream limit, \
+                 within the drain window of {drain_window:?}"
+            )?,
+            TpuConfigConstraint::ChannelSmallerThanConnections => write!(
+                f,
+                "{server} QUIC server channel holds fewer packets than the server connections"
+            )?,
+        }
+        write!(f, ": {}", self.suggestion)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TpuConfigAnalysis {
+    pub capacities: Vec<TpuIngestCapacity>,
+    pub issues: Vec<TpuConfigIssue>,
+}
+
+/// Analyzes the QUIC server configurations of the TPU given whether stakes
+/// are overridden, the channels having to buffer `drain_window` worth of
+/// packets at the stream limits
+pub fn analyze_tpu_config(
+    tpu_config: &ValidatorTpuConfig,
+    has_staked_nodes_overrides: bool,
+    drain_window: Duration,
+) -> TpuConfigAnalysis {
+    let mut analysis = TpuConfigAnalysis::default();
+    analyze_swqos_server(
+        TpuServer::Tpu,
+        &tpu_config.tpu_quic_server_config,
+        tpu_config.channel_sizes.tpu.get(),
+        drain_window,
+        &mut analysis,
+    );
+    let tpu_qos_config = &tpu_config.tpu_quic_server_config.qos_config;
+    if tpu_qos_config.max_unstaked_connections == 0 && !has_staked_nodes_overrides {
+        analysis.issues.push(TpuConfigIssue {
+            server: TpuServer::Tpu,
+            constraint: TpuConfigConstraint::NoUnstakedConnections,
+            suggestion: format!(
+                "set max_unstaked_connections to {DEFAULT_MAX_UNSTAKED_CONNECTIONS}, or override \
+                 the stakes of the peers sending transactions"
+            ),
+        });
+    }
+    analyze_swqos_server(
+        TpuServer::TpuForwards,
+        &tpu_config.tpu_fwd_quic_server_config,
+        tpu_config.channel_sizes.tpu_forwards.get(),
+        drain_window,
+        &mut analysis,
+    );
+
+    // The vote server limits the streams of each connection only, and evicts
+    // the oldest votes from its channel rather than dropping new ones
+    let vote_qos_config = &tpu_config.vote_quic_server_config.qos_config;
+    let vote_channel_size = tpu_config.channel_sizes.vote.get();
+    analysis.capacities.push(TpuIngestCapacity {
+        server: TpuServer::Vote,
+        max_streams_per_ms: None,
+        max_connections: vote_qos_config.max_staked_connections,
+        channel_size: vote_channel_size,
+        fill_time: None,
+    });
+    if vote_qos_config.max_staked_connections == 0 || vote_qos_config.max_connections_per_peer == 0
+    {
+        analysis.issues.push(TpuConfigIssue {
+            server: TpuServer::Vote,
+            constraint: TpuConfigConstraint::NoConnections,
+            suggestion: "set max_staked_connections and max_connections_per_peer to at least 1"
+                .to_string(),
+        });
+    }
+    if vote_qos_config.max_streams_per_second == 0 {
+        analysis.issues.push(TpuConfigIssue {
+            server: TpuServer::Vote,
+            constraint: TpuConfigConstraint::NoStreams,
+            suggestion: "set max_streams_per_second to at least 1".to_string(),
+        });
+    }
+    if vote_channel_size < vote_qos_config.max_staked_connections {
+        analysis.issues.push(TpuConfigIssue {
+            server: TpuServer::Vote,
+            constraint: TpuConfigConstraint::ChannelSmallerThanConnections,
+            suggestion: format!(
+                "set the vote channel size to at least {}, or max_staked_connections to at most \
+                 {vote_channel_size}",
+                vote_qos_config.max_staked_connections
+            ),
+        });
+    }
+    analysis
+}
+
+fn analyze_swqos_server(
+    server: TpuServer,
+    config: &SwQosQuicStreamerConfig,
+    channel_size: usize,
+    drain_window: Duration,
+    analysis: &mut TpuConfigAnalysis,
+) {
+    let SwQosConfig {
+        max_streams_per_ms,
+        max_connections_per_staked_peer,
+        max_connections_per_unstaked_peer,
+        ..
+    } = config.qos_config;
+    let max_connections = config.qos_config.total_connection_capacity();
+    let fill_time = channel_fill_time(channel_size, max_streams_per_ms);
+    analysis.capacities.push(TpuIngestCapacity {
+        server,
+        max_streams_per_ms: Some(max_streams_per_ms),
+        max_connections,
+        channel_size,
+        fill_time,
+    });
+
+    if max_connections == 0
+        || (max_connections_per_staked_peer == 0 && max_connections_per_unstaked_peer == 0)
+    {
+        analysis.issues.push(TpuConfigIssue {
+            server,
+            constraint: TpuConfigConstraint::NoConnections,
+            suggestion: "set max_staked_connections or max_unstaked_connections, along with the \
+                         matching connections per peer, to at least 1"
+                .to_string(),
+        });
+    }
+    match fill_time {
+        None => analysis.issues.push(TpuConfigIssue {
+            server,
+            constraint: TpuConfigConstraint::NoStreams,
+            suggestion: "set max_streams_per_ms to at least 1".to_string(),
+        }),
+        Some(fill_time) if fill_time <= drain_window => {
+            let drain_window_ms = drain_window.as_millis().max(1) as u64;
+            let max_sustainable_streams_per_ms = (channel_size as u64 / drain_window_ms)
+                .saturating_sub(1)
+                .max(1);
+            analysis.issues.push(TpuConfigIssue {
+                server,
+                constraint: TpuConfigConstraint::ChannelFillsWithinDrainWindow {
+                    fill_time,
+                    drain_window,
+                },
+                suggestion: format!(
+                    "set max_streams_per_ms to at most {max_sustainable_streams_per_ms}"
+                ),
+            });
+        }
+        Some(_) => (),
+    }
+}
+
+/// Time a channel of `channel_size` packets takes to fill at
+/// `max_streams_per_ms`, None if the rate is zero
+fn channel_fill_time(channel_size: usize, max_streams_per_ms: u64) -> Option<Duration> {
+    let fill_time_us = (channel_size as u64)
+        .saturating_mul(1_000)
+        .checked_div(max_streams_per_ms)?;
+    Some(Duration::from_micros(fill_time_us))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::tpu::{
+            TPU_CHANNEL_SIZE, TPU_FORWARD_CHANNEL_SIZE, TPU_VOTE_CHANNEL_SIZE, TpuPacketChannels,
+        },
+        std::num::NonZeroUsize,
+    };
+
+    fn constraints(analysis: &TpuConfigAnalysis) -> Vec<(TpuServer, TpuConfigConstraint)> {
+        analysis
+            .issues
+            .iter()
+            .map(|issue| (issue.server, issue.constraint))
+            .collect()
+    }
+
+    #[test]
+    fn test_channel_fill_time() {
+        assert_eq!(channel_fill_time(50_000, 0), None);
+        assert_eq!(
+            channel_fill_time(50_000, 500),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            channel_fill_time(4_000, 3),
+            Some(Duration::from_micros(1_333_333))
+        );
+        assert_eq!(channel_fill_time(1, u64::MAX), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_analyze_tpu_config_known_good() {
+        let tpu_config = ValidatorTpuConfig::new_for_tests();
+        // The forwards server admits only staked peers, as it should
+        assert_eq!(
+            tpu_config
+                .tpu_fwd_quic_server_config
+                .qos_config
+                .max_unstaked_connections,
+            0
+        );
+        for has_staked_nodes_overrides in [false, true] {
+            let analysis = analyze_tpu_config(
+                &tpu_config,
+                has_staked_nodes_overrides,
+                DEFAULT_TPU_DRAIN_WINDOW,
+            );
+            assert!(analysis.issues.is_empty(), "{:?}", analysis.issues);
+            assert_eq!(
+                analysis
+                    .capacities
+                    .iter()
+                    .map(|capacity| capacity.server)
+                    .collect::<Vec<_>>(),
+                vec![TpuServer::Tpu, TpuServer::TpuForwards, TpuServer::Vote]
+            );
+            let tpu = &analysis.capacities[0];
+            assert_eq!(tpu.channel_size, TPU_CHANNEL_SIZE);
+            assert!(tpu.fill_time.unwrap() > DEFAULT_TPU_DRAIN_WINDOW);
+        }
+    }
+
+    #[test]
+    fn test_analyze_tpu_config_channel_fills_within_drain_window() {
+        let mut tpu_config = ValidatorTpuConfig::new_for_tests();
+        tpu_config
+            .tpu_quic_server_config
+            .qos_config
+            .max_streams_per_ms = 5_000;
+        let analysis = analyze_tpu_config(&tpu_config, false, DEFAULT_TPU_DRAIN_WINDOW);
+        assert_eq!(
+            constraints(&analysis),
+            vec![(
+                TpuServer::Tpu,
+                TpuConfigConstraint::ChannelFillsWithinDrainWindow {
+                    fill_time: Duration::from_millis(10),
+                    drain_window: DEFAULT_TPU_DRAIN_WINDOW,
+                }
+            )]
+        );
+        // The suggested rate no longer fills the channel within the window
+        assert_eq!(
+            analysis.issues[0].suggestion,
+            "set max_streams_per_ms to at most 999"
+        );
+        tpu_config
+            .tpu_quic_server_config
+            .qos_config
+            .max_streams_per_ms = 999;
+        assert!(
+            analyze_tpu_config(&tpu_config, false, DEFAULT_TPU_DRAIN_WINDOW)
+                .issues
+                .is_empty()
+        );
+        // Filling up right at the end of the window leaves no headroom
+        tpu_config
+            .tpu_quic_server_config
+            .qos_config
+            .max_streams_per_ms = 1_000;
+        assert_eq!(
+            analyze_tpu_config(&tpu_config, false, DEFAULT_TPU_DRAIN_WINDOW)
+                .issues
+                .len(),
+            1
+        );
+
+        // A longer drain window flags the default rate as well
+        let tpu_config = ValidatorTpuConfig::new_for_tests();
+        let analysis = analyze_tpu_config(&tpu_config, false, Duration::from_millis(200));
+        assert_eq!(
+            constraints(&analysis)
+                .into_iter()
+                .map(|(server, _)| server)
+                .collect::<Vec<_>>(),
+            vec![TpuServer::Tpu, TpuServer::TpuForwards]
+        );
+    }
+
+    /// Checks the analysis against the capacities of the channels the TPU
+    /// creates for the config
+    fn assert_channel_sizes(analysis: &TpuConfigAnalysis, tpu_config: &ValidatorTpuConfig) {
+        let TpuPacketChannels {
+            tpu,
+            tpu_forwards,
+            vote,
+        } = TpuPacketChannels::new(tpu_config.channel_sizes);
+        assert_eq!(
+            analysis
+                .capacities
+                .iter()
+                .map(|capacity| Some(capacity.channel_size))
+                .collect::<Vec<_>>(),
+            vec![
+                tpu.0.capacity(),
+                tpu_forwards.0.capacity(),
+                vote.0.capacity()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_tpu_config_channel_sizes() {
+        let mut tpu_config = ValidatorTpuConfig::new_for_tests();
+        let analysis = analyze_tpu_config(&tpu_config, false, DEFAULT_TPU_DRAIN_WINDOW);
+        assert_channel_sizes(&analysis, &tpu_config);
+        assert_eq!(
+            analysis
+                .capacities
+                .iter()
+                .map(|capacity| capacity.channel_size)
+                .collect::<Vec<_>>(),
+            vec![
+                TPU_CHANNEL_SIZE,
+                TPU_FORWARD_CHANNEL_SIZE,
+                TPU_VOTE_CHANNEL_SIZE
+            ]
+        );
+
+        // A larger TPU channel and a smaller vote channel
+        tpu_config.channel_sizes.tpu = NonZeroUsize::new(100_000).unwrap();
+        tpu_config.channel_sizes.vote = NonZeroUsize::new(2_000).unwrap();
+        let analysis = analyze_tpu_config(&tpu_config, false, DEFAULT_TPU_DRAIN_WINDOW);
+        assert!(analysis.issues.is_empty(), "{:?}", analysis.issues);
+        assert_channel_sizes(&analysis, &tpu_config);
+        assert_eq!(
+            analysis
+                .capacities
+                .iter()
+                .map(|capacity| capacity.channel_size)
+                .collect::<Vec<_>>(),
+            vec![100_000, TPU_FORWARD_CHANNEL_SIZE, 2_000]
+        );
+        assert_eq!(
+            analysis.capacities[0].fill_time,
+            Some(Duration::from_millis(200))
+        );
+
+        // A TPU channel too small for the stream rate
+        tpu_config.channel_sizes.tpu = NonZeroUsize::new(5_000).unwrap();
+        assert_eq!(
+            constraints(&analyze_tpu_config(
+                &tpu_config,
+                false,
+                DEFAULT_TPU_DRAIN_WINDOW
+            )),
+            vec![(
+                TpuServer::Tpu,
+                TpuConfigConstraint::ChannelFillsWithinDrainWindow {
+                    fill_time: Duration::from_millis(10),
+                    drain_window: DEFAULT_TPU_DRAIN_WINDOW,
+                }
+            )]
+        );
+
+        // A vote channel unable to hold a vote per staked connection
+        let mut tpu_config = ValidatorTpuConfig::new_for_tests();
+        tpu_config.channel_sizes.vote = NonZeroUsize::new(1_999).unwrap();
+        let analysis = analyze_tpu_config(&tpu_config, false, DEFAULT_TPU_DRAIN_WINDOW);
+        assert_eq!(
+            constraints(&analysis),
+            vec![(
+                TpuServer::Vote,
+                TpuConfigConstraint::ChannelSmallerThanConnections
+            )]
+        );
+        assert_eq!(
+            analysis.issues[0].to_string(),
+            "TPU vote QUIC server channel holds fewer packets than the server connections: set \
+             the vote channel size to at least 2000, or max_staked_connections to at most 1999"
+        );
+    }
+
+    #[test]
+    fn test_analyze_tpu_config_no_unstaked_connections() {
+        let mut tpu_config = ValidatorTpuConfig::new_for_tests();
+        tpu_config
+            .tpu_quic_server_config
+            .qos_config
+            .max_unstaked_connections = 0;
+        let analysis = analyze_tpu_config(&tpu_config, false, DEFAULT_TPU_DRAIN_WINDOW);
+        assert_eq!(
+            constraints(&analysis),
+            vec![(TpuServer::Tpu, TpuConfigConstraint::NoUnstakedConnections)]
+        );
+        assert_eq!(
+            analysis.issues[0].to_string(),
+            "TPU QUIC server admits no unstaked connection and no stake is overridden: set \
+             max_unstaked_connections to 2000, or override the stakes of the peers sending \
+             transactions"
+        );
+        // Overridden stakes let the intended peers in
+        assert!(
+            analyze_tpu_config(&tpu_config, true, DEFAULT_TPU_DRAIN_WINDOW)
+                .issues
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_analyze_tpu_config_pathological() {
+        let mut tpu_config = ValidatorTpuConfig::new_for_tests();
+        let qos_config = &mut tpu_config.tpu_quic_server_config.qos_config;
+        qos_config.max_staked_connections = 0;
+        qos_config.max_unstaked_connections = 0;
+        tpu_config
+            .tpu_fwd_quic_server_config
+            .qos_config
+            .max_streams_per_ms = 0;
+        tpu_config
+            .vote_quic_server_config
+            .qos_config
+            .max_connections_per_peer = 0;
+        tpu_config
+            .vote_quic_server_config
+            .qos_config
+            .max_streams_per_second = 0;
+        let analysis = analyze_tpu_config(&tpu_config, true, DEFAULT_TPU_DRAIN_WINDOW);
+        assert_eq!(
+            constraints(&analysis),
+            vec![
+                (TpuServer::Tpu, TpuConfigConstraint::NoConnections),
+                (TpuServer::TpuForwards, TpuConfigConstraint::NoStreams),
+                (TpuServer::Vote, TpuConfigConstraint::NoConnections),
+                (TpuServer::Vote, TpuConfigConstraint::NoStreams),
+            ]
+        );
+        assert_eq!(analysis.capacities[0].max_connections, 0);
+        assert_eq!(analysis.capacities[1].fill_time, None);
+
+        // Connections allowed in the tables but not per peer
+        let mut tpu_config = ValidatorTpuConfig::new_for_tests();
+        let qos_config = &mut tpu_config.tpu_quic_server_config.qos_config;
+        qos_config.max_connections_per_staked_peer = 0;
+        qos_config.max_connections_per_unstaked_peer = 0;
+        assert_eq!(
+            constraints(&analyze_tpu_config(
+                &tpu_config,
+                true,
+                DEFAULT_TPU_DRAIN_WINDOW
+            )),
+            vec![(TpuServer::Tpu, TpuConfigConstraint::NoConnections)]
+        );
+    }
+}
@@ -0,0 +1,787 @@
+//! Measures the rates at which the TPU of the node ingests packets.
+//!
+//! The self-test connects to the TPU and TPU vote QUIC servers of the node over
+//! loopback, once with the node identity and once with a new unstaked
+//! identity, and sends each server signed transactions at a rate that doubles
+//! every step. The transactions reference a marker blockhash that the
+//! [`IngestSelfTestTap`] of the sigverify workers looks for: the tap counts the
+//! ones that passed signature verification and marks them discard, so banking
+//! stage drops them. The sustained rate of a client is the highest rate at
+//! which nearly all of its transactions were counted by the tap.
+
+use {
+    crate::leader_window::LeaderWindow,
+    agave_transaction_view::transaction_view::SanitizedTransactionView,
+    rayon::{ThreadPool, ThreadPoolBuilder, prelude::*},
+    serde::{Deserialize, Serialize},
+    solana_gossip::{cluster_info::ClusterInfo, contact_info::Protocol},
+    solana_hash::Hash,
+    solana_instruction::Instruction,
+    solana_keypair::Keypair,
+    solana_perf::packet::PacketBatch,
+    solana_poh::poh_recorder::SharedLeaderState,
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    solana_streamer::{
+        nonblocking::{loopback_client::LoopbackClient, quic::ConnectionSummary},
+        quic::{SimpleQosConnections, SwQosConnections},
+        streamer::StakedNodes,
+    },
+    solana_time_utils::timestamp,
+    solana_transaction::Transaction,
+    std::{
+        net::SocketAddr,
+        sync::{
+            Arc, RwLock,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+    thiserror::Error,
+    tokio::time::{sleep, sleep_until, timeout, timeout_at},
+};
+
+/// Interval between two sends of a step, each sending its share of the
+/// packets of the step at once
+const SEND_INTERVAL: Duration = Duration::from_millis(10);
+/// Time given to the sigverify workers to drain the packets of a step before
+/// they are counted
+const DRAIN_TIME: Duration = Duration::from_millis(100);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Maximum time waited for the TPU server to drop the connections of the
+/// self-test after they were closed
+const CLEANUP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Number of threads signing the transactions of the steps, kept low so the
+/// self-test does not compete with the rest of the node for the cores
+const NUM_SIGNING_THREADS: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestSelfTestConfig {
+    /// Upper bound on the time spent sending, over all the clients
+    pub max_duration_ms: u64,
+    pub step_duration_ms: u64,
+    /// Packets per second sent in the first step of each client
+    pub initial_rate: u64,
+    pub max_rate: u64,
+    /// Fraction of the packets of a step that may be lost for the step to
+    /// count as sustained
+    pub max_drop_ratio: f64,
+    /// The self-test doesn't run, and stops, while the next leader slot of the
+    /// node is at most this many slots away
+    pub min_slots_to_leader: u64,
+}
+
+impl Default for IngestSelfTestConfig {
+    fn default() -> Self {
+        Self {
+            max_duration_ms: 10_000,
+            step_duration_ms: 250,
+            initial_rate: 1_000,
+            max_rate: 1_024_000,
+            max_drop_ratio: 0.01,
+            min_slots_to_leader: 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IngestSelfTestServer {
+    Tpu,
+    TpuVote,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestSelfTestStep {
+    /// Packets per second offered
+    pub rate: u64,
+    pub num_packets: u64,
+    /// Packets the client sent in full within the step
+    pub num_sent: u64,
+    /// Packets that passed signature verification
+    pub num_ingested: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestSelfTestClientResult {
+    pub server: IngestSelfTestServer,
+    pub identity: Pubkey,
+    /// Whether `identity` is the identity of the node, the other client using
+    /// a new unstaked identity
+    pub node_identity: bool,
+    pub stake: u64,
+    /// Highest rate, in packets per second, of the steps that lost at most
+    /// `max_drop_ratio` of their packets, 0 if none did
+    pub sustained_rate: u64,
+    pub steps: Vec<IngestSelfTestStep>,
+    /// Why the client failed to connect, if it did
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestSelfTestResult {
+    /// Milliseconds since the UNIX epoch at which the self-test started
+    pub timestamp: u64,
+    pub duration_ms: u64,
+    pub clients: Vec<IngestSelfTestClientResult>,
+    /// Whether the self-test stopped early because a leader slot of the node
+    /// came within `min_slots_to_leader`
+    pub interrupted: bool,
+    /// Whether the TPU and TPU vote servers no longer list any connection of
+    /// the self-test
+    pub connections_cleaned_up: bool,
+}
+
+impl IngestSelfTestResult {
+    /// Sustained rate of the client of `server` with the node identity, or
+    /// with the unstaked identity
+    pub fn sustained_rate(&self, server: IngestSelfTestServer, node_identity: bool) -> Option<u64> {
+        self.clients
+            .iter()
+            .find(|client| client.server == server && client.node_identity == node_identity)
+            .map(|client| client.sustained_rate)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IngestSelfTestError {
+    #[error("a self-test is already running")]
+    AlreadyRunning,
+    #[error("the next leader slot is {0} slots away")]
+    LeaderSlotTooClose(u64),
+    #[error("the node advertises no {0:?} QUIC address")]
+    NoServerAddress(IngestSelfTestServer),
+    #[error("failed to run the self-test: {0}")]
+    Runtime(String),
+}
+
+/// Counts the self-test packets that passed signature verification, and
+/// marks them discard so that banking stage drops them
+#[derive(Default)]
+pub struct IngestSelfTestTap {
+    active: AtomicBool,
+    target: RwLock<TapTarget>,
+    num_ingested: AtomicU64,
+}
+
+#[derive(Clone, Copy, Default)]
+struct TapTarget {
+    marker: Hash,
+    /// Only the packets of this fee payer are counted, so that the late
+    /// packets of a step don't count toward the next one
+    fee_payer: Pubkey,
+}
+
+impl IngestSelfTestTap {
+    /// Returns false if the tap is already active
+    fn activate(&self, marker: Hash) -> bool {
+        let mut target = self.target.write().unwrap();
+        if self.active.load(Ordering::Acquire) {
+            return false;
+        }
+        target.marker = marker;
+        self.active.store(true, Ordering::Release);
+        true
+    }
+
+    fn deactivate(&self) {
+        self.active.store(false, Ordering::Release);
+    }
+
+    fn watch(&self, fee_payer: Pubkey) {
+        self.target.write().unwrap().fee_payer = fee_payer;
+        self.num_ingested.store(0, Ordering::Relaxed);
+    }
+
+    fn num_ingested(&self) -> u64 {
+        self.num_ingested.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn filter(&self, batch: &mut PacketBatch) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        let target = *self.target.read().unwrap();
+        let mut num_ingested = 0;
+        for mut packet in batch.iter_mut() {
+            let Some(data) = packet.data(..) else {
+                continue;
+            };
+            let Ok(view) = SanitizedTransactionView::try_new_sanitized(data, true) else {
+                continue;
+            };
+            if view.recent_blockhash() != &target.marker {
+                continue;
+            }
+            let is_watched = view.static_account_keys().first() == Some(&target.fee_payer);
+            if is_watched && !packet.meta().discard() {
+                num_ingested += 1;
+            }
+            packet.meta_mut().set_discard(true);
+        }
+        if num_ingested > 0 {
+            self.num_ingested.fetch_add(num_ingested, Ordering::Relaxed);
+        }
+    }
+}
+
+struct ActiveTap<'a>(&'a IngestSelfTestTap);
+
+impl Drop for ActiveTap<'_> {
+    fn drop(&mut self) {
+        self.0.deactivate();
+    }
+}
+
+pub struct TpuIngestSelfTest {
+    tap: Arc<IngestSelfTestTap>,
+    cluster_info: Arc<ClusterInfo>,
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+    tpu_connections: SwQosConnections,
+    tpu_vote_connections: SimpleQosConnections,
+    leader_state: SharedLeaderState,
+    ticks_per_slot: u64,
+}
+
+impl TpuIngestSelfTest {
+    pub fn new(
+        tap: Arc<IngestSelfTestTap>,
+        cluster_info: Arc<ClusterInfo>,
+        staked_nodes: Arc<RwLock<StakedNodes>>,
+        tpu_connections: SwQosConnections,
+        tpu_vote_connections: SimpleQosConnections,
+        leader_state: SharedLeaderState,
+        ticks_per_slot: u64,
+    ) -> Self {
+        Self {
+            tap,
+            cluster_info,
+            staked_nodes,
+            tpu_connections,
+            tpu_vote_connections,
+            leader_state,
+            ticks_per_slot,
+        }
+    }
+
+    fn check_leader_distance(&self, min_slots_to_leader: u64) -> Result<(), IngestSelfTestError> {
+        let leader_window = LeaderWindow::new(&self.leader_state.load(), self.ticks_per_slot);
+        match leader_window.slots_to_leader() {
+            Some(slots) if slots <= min_slots_to_leader => {
+                Err(IngestSelfTestError::LeaderSlotTooClose(slots))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs the self-test, blocking until it is done
+    pub fn run(
+        &self,
+        config: &IngestSelfTestConfig,
+    ) -> Result<IngestSelfTestResult, IngestSelfTestError> {
+        self.check_leader_distance(config.min_slots_to_leader)?;
+        let contact_info = self.cluster_info.my_contact_info();
+        let server_addrs = [
+            (IngestSelfTestServer::Tpu, contact_info.tpu(Protocol::QUIC)),
+            (
+                IngestSelfTestServer::TpuVote,
+                contact_info.tpu_vote(Protocol::QUIC),
+            ),
+        ]
+        .into_iter()
+        .map(|(server, addr)| {
+            addr.map(|addr| (server, addr))
+                .ok_or(IngestSelfTestError::NoServerAddress(server))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let marker = Hash::new_from_array(rand::random());
+        if !self.tap.activate(marker) {
+            return Err(IngestSelfTestError::AlreadyRunning);
+        }
+        let _active_tap = ActiveTap(&self.tap);
+        let signing_thread_pool = build_signing_thread_pool()
+            .map_err(|err| IngestSelfTestError::Runtime(err.to_string()))?;
+        let identities = [self.cluster_info.keypair(), Arc::new(Keypair::new())];
+
+        let start = Instant::now();
+        let timestamp = timestamp();
+        // The clients run on a runtime of their own, off the thread of the
+        // caller which may be running a runtime already
+        let (clients, interrupted, client_addrs) =
+            thread::scope(|scope| -> Result<_, IngestSelfTestError> {
+                let handle = thread::Builder::new()
+                    .name("solIngestTest".to_string())
+                    .spawn_scoped(scope, || -> Result<_, IngestSelfTestError> {
+                        let runtime = tokio::runtime::Builder::new_current_thread()
+                            .enable_all()
+                            .build()
+                            .map_err(|err| IngestSelfTestError::Runtime(err.to_string()))?;
+                        Ok(runtime.block_on(self.run_clients(
+                            config,
+                            &signing_thread_pool,
+                            marker,
+                            &server_addrs,
+                            &identities,
+                            start,
+                        )))
+                    })
+                    .map_err(|err| IngestSelfTestError::Runtime(err.to_string()))?;
+                handle.join().unwrap_or_else(|_| {
+                    Err(IngestSelfTestError::Runtime(
+                        "self-test panicked".to_string(),
+                    ))
+                })
+            })?;
+
+        let connections_cleaned_up = self.wait_for_cleanup(&client_addrs);
+        let result = IngestSelfTestResult {
+            timestamp,
+            duration_ms: start.elapsed().as_millis() as u64,
+            clients,
+            interrupted,
+            connections_cleaned_up,
+        };
+        report_result(&result);
+        Ok(result)
+    }
+
+    /// Runs the clients one after the other, each with an equal share of the
+    /// time left, and returns their results, whether they were interrupted by
+    /// an upcoming leader slot and the addresses the clients connected from
+    async fn run_clients(
+        &self,
+        config: &IngestSelfTestConfig,
+        signing_thread_pool: &ThreadPool,
+        marker: Hash,
+        server_addrs: &[(IngestSelfTestServer, SocketAddr)],
+        identities: &[Arc<Keypair>],
+        start: Instant,
+    ) -> (
+        Vec<IngestSelfTestClientResult>,
+        bool,
+        Vec<(IngestSelfTestServer, Pubkey, SocketAddr)>,
+    ) {
+        let deadline = start + Duration::from_millis(config.max_duration_ms);
+        let num_clients = server_addrs.len() * identities.len();
+        let mut clients = Vec::with_capacity(num_clients);
+        let mut client_addrs = vec![];
+        let mut interrupted = false;
+        for &(server, server_addr) in server_addrs {
+            for (i, identity) in identities.iter().enumerate() {
+                let num_clients_left = (num_clients - clients.len()) as u32;
+                let client_deadline = Instant::now()
+                    + deadline.saturating_duration_since(Instant::now()) / num_clients_left;
+                let mut result = IngestSelfTestClientResult {
+                    server,
+                    identity: identity.pubkey(),
+                    node_identity: i == 0,
+                    stake: self
+                        .staked_nodes
+                        .read()
+                        .unwrap()
+                        .get_node_stake(&identity.pubkey())
+                        .unwrap_or_default(),
+                    sustained_rate: 0,
+                    steps: vec![],
+                    error: None,
+                };
+                if !interrupted {
+                    match timeout(
+                        CONNECT_TIMEOUT,
+                        LoopbackClient::connect(server_addr, identity),
+                    )
+                    .await
+                    {
+                        Ok(Ok(client)) => {
+                            if let Ok(local_addr) = client.local_addr() {
+                                client_addrs.push((server, identity.pubkey(), local_addr));
+                            }
+                            interrupted = run_steps(
+                                &client,
+                                &self.tap,
+                                signing_thread_pool,
+                                server,
+                                marker,
+                                config,
+                                client_deadline,
+                                &mut result,
+                                || {
+                                    self.check_leader_distance(config.min_slots_to_leader)
+                                        .is_ok()
+                                },
+                            )
+                            .await;
+                            let _ = timeout(CLOSE_TIMEOUT, client.close()).await;
+                        }
+                        Ok(Err(err)) => result.error = Some(err.to_string()),
+                        Err(_) => result.error = Some("timed out connecting".to_string()),
+                    }
+                }
+                clients.push(result);
+            }
+        }
+        (clients, interrupted, client_addrs)
+    }
+
+    /// Returns true once the TPU and TPU vote servers list none of the
+    /// connections from `client_addrs`, false if they still do after
+    /// `CLEANUP_TIMEOUT`
+    fn wait_for_cleanup(
+        &self,
+        client_addrs: &[(IngestSelfTestServer, Pubkey, SocketAddr)],
+    ) -> bool {
+        let deadline = Instant::now() + CLEANUP_TIMEOUT;
+        let is_cleaned_up = |server, connections: Vec<ConnectionSummary>| {
+            connections.iter().all(|connection| {
+                !client_addrs
+                    .iter()
+                    .filter(|(client_server, ..)| *client_server == server)
+                    .any(|(_, identity, addr)| {
+                        connection.remote_pubkey.as_ref() == Some(identity)
+                            && connection
+                                .remote_address
+                                .is_some_and(|remote_addr| remote_addr.port() == addr.port())
+                    })
+            })
+        };
+        loop {
+            let cleaned_up = is_cleaned_up(IngestSelfTestServer::Tpu, self.tpu_connections.dump())
+                && is_cleaned_up(
+                    IngestSelfTestServer::TpuVote,
+                    self.tpu_vote_connections.dump(),
+                );
+            if cleaned_up || Instant::now() >= deadline {
+                return cleaned_up;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+/// Runs the steps of a client until one loses more than `max_drop_ratio` of
+/// its packets, the deadline passes or `can_continue` returns false, in which
+/// case this returns true
+#[allow(clippy::too_many_arguments)]
+async fn run_steps(
+    client: &LoopbackClient,
+    tap: &IngestSelfTestTap,
+    signing_thread_pool: &ThreadPool,
+    server: IngestSelfTestServer,
+    marker: Hash,
+    config: &IngestSelfTestConfig,
+    deadline: Instant,
+    result: &mut IngestSelfTestClientResult,
+    can_continue: impl Fn() -> bool,
+) -> bool {
+    let step_duration = Duration::from_millis(config.step_duration_ms.max(1));
+    let mut rate = config.initial_rate.max(1);
+    while rate <= config.max_rate && Instant::now() + step_duration + DRAIN_TIME <= deadline {
+        if !can_continue() {
+            return true;
+        }
+        let step = run_step(
+            client,
+            tap,
+            signing_thread_pool,
+            server,
+            marker,
+            rate,
+            step_duration,
+        )
+        .await;
+        let min_num_ingested = step.num_packets as f64 * (1.0 - config.max_drop_ratio);
+        let sustained = step.num_ingested as f64 >= min_num_ingested;
+        result.steps.push(step);
+        if !sustained {
+            break;
+        }
+        result.sustained_rate = rate;
+        rate = rate.saturating_mul(2);
+    }
+    false
+}
+
+async fn run_step(
+    client: &LoopbackClient,
+    tap: &IngestSelfTestTap,
+    signing_thread_pool: &ThreadPool,
+    server: IngestSelfTestServer,
+    marker: Hash,
+    rate: u64,
+    step_duration: Duration,
+) -> IngestSelfTestStep {
+    let fee_payer = Keypair::new();
+    let num_packets = (rate as u128 * step_duration.as_millis() / 1_000).max(1) as u64;
+    let payloads =
+        signing_thread_pool.install(|| make_payloads(server, &fee_payer, marker, num_packets));
+    tap.watch(fee_payer.pubkey());
+
+    let num_sends = (step_duration.as_millis() / SEND_INTERVAL.as_millis()).max(1) as usize;
+    let start = tokio::time::Instant::now();
+    let step_end = start + step_duration;
+    let mut num_sent = 0;
+    for (i, payloads) in payloads
+        .chunks(payloads.len().div_ceil(num_sends))
+        .enumerate()
+    {
+        match timeout_at(step_end, client.send_all(payloads)).await {
+            Ok(sent) => num_sent += sent as u64,
+            Err(_) => break,
+        }
+        sleep_until(start + SEND_INTERVAL * (i as u32 + 1)).await;
+    }
+    sleep(DRAIN_TIME).await;
+    IngestSelfTestStep {
+        rate,
+        num_packets,
+        num_sent,
+        num_ingested: tap.num_ingested(),
+    }
+}
+
+fn build_signing_thread_pool() -> Result<ThreadPool, rayon::ThreadPoolBuildError> {
+    ThreadPoolBuilder::new()
+        .num_threads(NUM_SIGNING_THREADS)
+        .thread_name(|i| format!("solIngestSign{i:02}"))
+        .build()
+}
+
+/// Returns `num_packets` distinct transactions of `fee_payer` referencing
+/// `marker`: transfers for the TPU, and simple vote transactions for the TPU
+/// vote server which rejects anything else
+fn make_payloads(
+    server: IngestSelfTestServer,
+    fee_payer: &Keypair,
+    marker: Hash,
+    num_packets: u64,
+) -> Vec<Vec<u8>> {
+    (0..num_packets)
+        .into_par_iter()
+        .map(|i| {
+            let transaction = match server {
+                IngestSelfTestServer::Tpu => {
+                    solana_system_transaction::transfer(fee_payer, &fee_payer.pubkey(), i, marker)
+                }
+                IngestSelfTestServer::TpuVote => Transaction::new_signed_with_payer(
+                    &[Instruction::new_with_bytes(
+                        solana_sdk_ids::vote::id(),
+                        &i.to_le_bytes(),
+                        vec![],
+                    )],
+                    Some(&fee_payer.pubkey()),
+                    &[fee_payer],
+                    marker,
+                ),
+            };
+            bincode::serialize(&transaction).unwrap()
+        })
+        .collect()
+}
+
+fn report_result(result: &IngestSelfTestResult) {
+    let rate = |server, node_identity| {
+        result
+            .sustained_rate(server, node_identity)
+            .unwrap_or_default() as i64
+    };
+    datapoint_info!(
+        "tpu_ingest_self_test",
+        ("duration_ms", result.duration_ms as i64, i64),
+        (
+            "tpu_node_identity_rate",
+            rate(IngestSelfTestServer::Tpu, true),
+            i64
+        ),
+        (
+            "tpu_unstaked_rate",
+            rate(IngestSelfTestServer::Tpu, false),
+            i64
+        ),
+        (
+            "tpu_vote_node_identity_rate",
+            rate(IngestSelfTestServer::TpuVote, true),
+            i64
+        ),
+        (
+            "tpu_vote_unstaked_rate",
+            rate(IngestSelfTestServer::TpuVote, false),
+            i64
+        ),
+        ("interrupted", result.interrupted, bool),
+        (
+            "connections_cleaned_up",
+            result.connections_cleaned_up,
+            bool
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_perf::packet::to_packet_batches,
+        solana_streamer::{
+            nonblocking::{swqos::SwQosConfig, testing_utilities::setup_quic_server},
+            quic::QuicStreamerConfig,
+        },
+    };
+
+    #[test]
+    fn test_tap_filter() {
+        let tap = IngestSelfTestTap::default();
+        let marker = Hash::new_unique();
+        let watched = Keypair::new();
+        let other = Keypair::new();
+        let transactions = [
+            solana_system_transaction::transfer(&watched, &other.pubkey(), 1, marker),
+            solana_system_transaction::transfer(&watched, &other.pubkey(), 2, marker),
+            solana_system_transaction::transfer(&other, &watched.pubkey(), 1, marker),
+            solana_system_transaction::transfer(&watched, &other.pubkey(), 3, Hash::default()),
+        ];
+        let mut batch = to_packet_batches(&transactions, transactions.len()).remove(0);
+        batch.get_mut(1).unwrap().meta_mut().set_discard(true);
+        let discards = |batch: &PacketBatch| -> Vec<bool> {
+            batch.iter().map(|packet| packet.meta().discard()).collect()
+        };
+
+        // An inactive tap lets everything through
+        tap.filter(&mut batch);
+        assert_eq!(discards(&batch), vec![false, true, false, false]);
+
+        assert!(tap.activate(marker));
+        assert!(!tap.activate(Hash::new_unique()));
+        tap.watch(watched.pubkey());
+        tap.filter(&mut batch);
+        // All the marked packets are discarded, only the valid ones of the
+        // watched fee payer are counted
+        assert_eq!(discards(&batch), vec![true, true, true, false]);
+        assert_eq!(tap.num_ingested(), 1);
+
+        tap.watch(other.pubkey());
+        assert_eq!(tap.num_ingested(), 0);
+        tap.deactivate();
+        assert!(tap.activate(marker));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_steps() {
+        let server = setup_quic_server(
+            None,
+            QuicStreamerConfig::default_for_tests(),
+            SwQosConfig::default(),
+        );
+        let tap = Arc::new(IngestSelfTestTap::default());
+        let marker = Hash::new_unique();
+        assert!(tap.activate(marker));
+        // Stands in for the sigverify workers
+        let verifier = {
+            let receiver = server.receiver.clone();
+            let tap = tap.clone();
+            thread::spawn(move || {
+                while let Ok(mut batch) = receiver.recv() {
+                    tap.filter(&mut batch);
+                }
+            })
+        };
+
+        let identity = Keypair::new();
+        let client = LoopbackClient::connect(server.server_address, &identity)
+            .await
+            .unwrap();
+        let config = IngestSelfTestConfig {
+            step_duration_ms: 100,
+            initial_rate: 100,
+            max_rate: 800,
+            ..IngestSelfTestConfig::default()
+        };
+        let mut result = IngestSelfTestClientResult {
+            server: IngestSelfTestServer::Tpu,
+            identity: identity.pubkey(),
+            node_identity: true,
+            stake: 0,
+            sustained_rate: 0,
+            steps: vec![],
+            error: None,
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let signing_thread_pool = build_signing_thread_pool().unwrap();
+        let interrupted = run_steps(
+            &client,
+            &tap,
+            &signing_thread_pool,
+            IngestSelfTestServer::Tpu,
+            marker,
+            &config,
+            deadline,
+            &mut result,
+            || true,
+        )
+        .await;
+        assert!(!interrupted);
+        assert!(result.sustained_rate >= config.initial_rate, "{result:?}");
+        let step = &result.steps[0];
+        assert_eq!(step.rate, config.initial_rate);
+        assert_eq!(step.num_packets, 10);
+        assert_eq!(step.num_sent, step.num_packets);
+        assert_eq!(step.num_ingested, step.num_packets);
+
+        // A leader slot coming up interrupts the steps
+        let mut result = IngestSelfTestClientResult {
+            steps: vec![],
+            ..result
+        };
+        assert!(
+            run_steps(
+                &client,
+                &tap,
+                &signing_thread_pool,
+                IngestSelfTestServer::Tpu,
+                marker,
+                &config,
+                deadline,
+                &mut result,
+                || false,
+            )
+            .await
+        );
+        assert!(result.steps.is_empty());
+
+        client.close().await;
+        server.cancel.cancel();
+        server.join_handle.await.unwrap();
+        verifier.join().unwrap();
+    }
+
+    #[test]
+    fn test_make_payloads() {
+        let fee_payer = Keypair::new();
+        let marker = Hash::new_unique();
+        for server in [IngestSelfTestServer::Tpu, IngestSelfTestServer::TpuVote] {
+            let payloads = make_payloads(server, &fee_payer, marker, 16);
+            assert_eq!(payloads.len(), 16);
+            let mut unique = payloads.clone();
+            unique.sort_unstable();
+            unique.dedup();
+            assert_eq!(unique.len(), payloads.len());
+            // The vote server only accepts simple vote transactions
+            let mut batch = to_packet_batches(
+                &payloads
+                    .iter()
+                    .map(|payload| bincode::deserialize::<Transaction>(payload).unwrap())
+                    .collect::<Vec<_>>(),
+                payloads.len(),
+            )
+            .remove(0);
+            solana_perf::sigverify::ed25519_verify_serial(
+                &mut batch,
+                server == IngestSelfTestServer::TpuVote,
+                false,
+            );
+            assert!(batch.iter().all(|packet| !packet.meta().discard()));
+        }
+    }
+}
@@ -14,6 +14,9 @@ use {
         completed_data_sets_service::CompletedDataSetsSender,
         consensus::{Tower, tower_storage::TowerStorage},
         cost_update_service::CostUpdateService,
+        deterministic_rng::{
+            ALPENGLOW_VOTE_QUIC_RNG_LABEL, RETRANSMIT_RNG_LABEL, subsystem_rng_seed,
+        },
         drop_bank_service::DropBankService,
         epoch_specs::EpochSpecs,
         repair::{
@@ -21,7 +24,9 @@ use {
             repair_service::{OutstandingShredRepairs, RepairInfo, RepairServiceChannels},
         },
         replay_stage::{ReplayReceivers, ReplaySenders, ReplayStage, ReplayStageConfig},
+        restart_latency_tracker::RestartLatencyTracker,
         shred_fetch_stage::{SHRED_FETCH_CHANNEL_SIZE, ShredFetchStage},
+        slot_resource_accounting::SlotResourceAccounting,
         voting_service::VotingService,
         warm_quic_cache_service::WarmQuicCacheService,
         window_service::{WindowService, WindowServiceChannels},
@@ -58,6 +63,7 @@ use {
         leader_schedule_cache::LeaderScheduleCache,
         shred::filter::TurbineMode,
     },
+    solana_net_utils::sockets::SocketConfiguration,
     solana_poh::{poh_controller::PohController, poh_recorder::PohRecorder},
     solana_pubkey::Pubkey,
     solana_rpc::{
@@ -77,10 +83,16 @@ use {
     solana_streamer::{
         evicting_sender::EvictingSender,
         nonblocking::simple_qos::SimpleQosConfig,
-        quic::{QuicStreamerConfig, SpawnServerResult, spawn_simple_qos_server},
+        quic::{
+            QuicStreamerConfig, SpawnServerResult, reuseport_endpoint_rebinder,
+            spawn_simple_qos_server,
+        },
         streamer::StakedNodes,
     },
-    solana_turbine::{XdpSender as TurbineXdpSender, retransmit_stage::RetransmitStage},
+    solana_turbine::{
+        XdpSender as TurbineXdpSender,
+        retransmit_stage::{RetransmitDiagnostics, RetransmitStage},
+    },
     std::{
         collections::HashSet,
         net::UdpSocket,
@@ -137,16 +149,24 @@ pub struct TvuSockets {
 pub struct TvuConfig {
     pub max_ledger_shreds: Option<u64>,
     pub shred_version: u16,
-    // Validators from which repairs are requested
-    pub repair_validators: Option<HashSet<Pubkey>>,
+    // Validators from which repairs are requested, may be updated at runtime
+    pub repair_validators: Arc<RwLock<Option<HashSet<Pubkey>>>>,
     // Validators which should be given priority when serving repairs
     pub repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>,
     pub wait_for_vote_to_start_leader: bool,
+    // Never produce blocks before this slot, regardless of voting
+    pub min_leader_slot: Option<Slot>,
+    // Maximum number of unfrozen banks before the least voted fork tips are pruned
+    pub max_active_banks: Option<NonZeroUsize>,
+    // Slots before an epoch boundary at which to precompute the next leader schedule
+    pub precompute_leader_schedule_slots: Option<u64>,
     pub replay_forks_threads: NonZeroUsize,
     pub replay_transactions_threads: NonZeroUsize,
     pub shred_sigverify_threads: NonZeroUsize,
     pub bls_sigverify_threads: NonZeroUsize,
     pub turbine_xdp_sender: Option<TurbineXdpSender>,
+    // Base seed of the RNGs of the subsystems, see deterministic_rng
+    pub deterministic_rng_seed: Option<u64>,
 }
 
 impl Default for TvuConfig {
@@ -154,14 +174,18 @@ impl Default for TvuConfig {
         Self {
             max_ledger_shreds: None,
             shred_version: 0,
-            repair_validators: None,
+            repair_validators: Arc::default(),
             repair_whitelist: Arc::new(RwLock::new(HashSet::default())),
             wait_for_vote_to_start_leader: false,
+            min_leader_slot: None,
+            max_active_banks: None,
+            precompute_leader_schedule_slots: None,
             replay_forks_threads: NonZeroUsize::new(1).expect("1 is non-zero"),
             replay_transactions_threads: NonZeroUsize::new(1).expect("1 is non-zero"),
             shred_sigverify_threads: NonZeroUsize::new(1).expect("1 is non-zero"),
             bls_sigverify_threads: NonZeroUsize::new(1).expect("1 is non-zero"),
             turbine_xdp_sender: None,
+            deterministic_rng_seed: None,
         }
     }
 }
@@ -245,6 +269,8 @@ impl Tvu {
         vote_connection_cache: Arc<ConnectionCache>,
         votor_init: AlpenglowInitializationState,
         reward_votes_sender: Sender<AddVoteMessage>,
+        restart_latency_tracker: Arc<RestartLatencyTracker>,
+        slot_resource_accounting: Option<SlotResourceAccounting>,
     ) -> Result<Self, String> {
         let migration_status = bank_forks.read().unwrap().migration_status();
 
@@ -297,6 +323,10 @@ impl Tvu {
             ) = {
                 let quic_server_params = QuicStreamerConfig {
                     num_threads: NonZeroUsize::new(4.min(num_cpus::get())).unwrap(),
+                    // The node binds the BLS socket with SO_REUSEPORT
+                    endpoint_rebinder: Some(reuseport_endpoint_rebinder(
+                        SocketConfiguration::default(),
+                    )),
                     ..Default::default()
                 };
                 let qos_config = SimpleQosConfig {
@@ -305,6 +335,10 @@ impl Tvu {
                     max_staked_connections: MAX_ALPENGLOW_VOTE_ACCOUNTS * 2,
                     // Two staked connection per validator to account for hotspares
                     max_connections_per_peer: 2,
+                    prune_rng_seed: subsystem_rng_seed(
+                        tvu_config.deterministic_rng_seed,
+                        ALPENGLOW_VOTE_QUIC_RNG_LABEL,
+                    ),
                 };
                 spawn_simple_qos_server(
                     "solQuicBLS",
@@ -403,6 +437,7 @@ impl Tvu {
             slot_status_notifier.clone(),
             tvu_config.turbine_xdp_sender,
             votor_event_sender.clone(),
+            subsystem_rng_seed(tvu_config.deterministic_rng_seed, RETRANSMIT_RNG_LABEL),
         );
 
         let (ancestor_duplicate_slots_sender, ancestor_duplicate_slots_receiver) = unbounded();
@@ -442,6 +477,7 @@ impl Tvu {
                 ancestor_duplicate_slots_sender,
                 repair_validators: tvu_config.repair_validators,
                 repair_whitelist: tvu_config.repair_whitelist,
+                deterministic_rng_seed: tvu_config.deterministic_rng_seed,
                 cluster_info: cluster_info.clone(),
                 cluster_slots: cluster_slots.clone(),
             };
@@ -569,6 +605,9 @@ impl Tvu {
             wait_for_vote_to_start_leader: tvu_config.wait_for_vote_to_start_leader,
             tower_storage: tower_storage.clone(),
             wait_to_vote_slot,
+            min_leader_slot: tvu_config.min_leader_slot,
+            max_active_banks: tvu_config.max_active_banks,
+            precompute_leader_schedule_slots: tvu_config.precompute_leader_schedule_slots,
             replay_forks_threads: tvu_config.replay_forks_threads,
             replay_transactions_threads: tvu_config.replay_transactions_threads,
             blockstore: blockstore.clone(),
@@ -584,6 +623,8 @@ impl Tvu {
             banking_tracer,
             snapshot_controller,
             replay_highest_frozen,
+            restart_latency_tracker,
+            slot_resource_accounting,
         };
 
         let voting_service = VotingService::new(
@@ -592,6 +633,7 @@ impl Tvu {
             poh_recorder.clone(),
             tower_storage,
             vote_connection_cache.clone(),
+            restart_latency_tracker.clone(),
         );
 
         let bls_voting_service = BLSVotingService::new(
@@ -658,6 +700,10 @@ impl Tvu {
         })
     }
 
+    pub fn retransmit_diagnostics(&self) -> &Arc<RetransmitDiagnostics> {
+        self.retransmit_stage.diagnostics()
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.retransmit_stage.join()?;
         self.window_service.join()?;
@@ -736,7 +782,7 @@ pub mod tests {
         solana_tpu_client::tpu_client::{DEFAULT_TPU_CONNECTION_POOL_SIZE, DEFAULT_VOTE_USE_QUIC},
         std::{
             sync::atomic::{AtomicU64, Ordering},
-            time::Duration,
+            time::{Duration, Instant},
         },
     };
 
@@ -911,6 +957,12 @@ pub mod tests {
                 bank_forks_controller_receiver,
             },
             reward_votes_sender,
+            Arc::new(RestartLatencyTracker::new(
+                Instant::now(),
+                Pubkey::default(),
+                false,
+            )),
+            None, // slot_resource_accounting
         )
         .expect("assume success");
         exit.store(true, Ordering::Relaxed);
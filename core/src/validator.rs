@@ -3,34 +3,70 @@
 pub use solana_perf::report_target_features;
 use {
     crate::{
+        accounts_db_manifest::{AccountsDbManifest, AccountsDbMigration},
         admin_rpc_post_init::{AdminRpcRequestMetadataPostInit, KeyUpdaterType, KeyUpdaters},
+        advertised_addresses::{AdvertisedAddresses, AdvertisedAddressesError},
         banking_stage::{
-            BankingStage, transaction_scheduler::scheduler_controller::SchedulerConfig,
+            BankingStage, ReservedIngest,
+            transaction_scheduler::scheduler_controller::SchedulerConfig,
         },
         banking_trace::{self, BankingTracer, TraceError},
         block_creation_loop::{BlockCreationLoop, BlockCreationLoopConfig, ReplayHighestFrozen},
-        cluster_info_vote_listener::VoteTracker,
+        cluster_info_vote_listener::{
+            DEFAULT_DUPLICATE_CONFIRMED_SLOTS_CHANNEL_CAPACITY, VoteTracker,
+            duplicate_confirmed_slots_channel,
+        },
+        compaction_scheduler::{CompactionScheduler, CompactionSchedulerConfig},
         completed_data_sets_service::CompletedDataSetsService,
         consensus::{
             ExternalRootSource, Tower, reconcile_blockstore_roots_with_external_source,
             tower_storage::{NullTowerStorage, TowerStorage},
         },
+        deterministic_rng::{
+            TPU_FORWARDS_QUIC_RNG_LABEL, TPU_QUIC_RNG_LABEL, TPU_VOTE_QUIC_RNG_LABEL,
+            subsystem_rng_seed,
+        },
         forwarding_stage::ForwardingClientConfig,
+        gossip_vote_landing::{GossipVoteDiscrepancy, GossipVoteLandingTracker},
+        optimistic_confirmation_violations::{
+            OptimisticConfirmationViolationLog, OptimisticConfirmationViolationSummary,
+        },
         repair::{
-            self, repair_handler::RepairHandlerType, serve_repair_service::ServeRepairService,
+            self,
+            repair_handler::{
+                DEFAULT_REPAIR_SERVE_BLOCKSTORE_CATCH_UP_INTERVAL, RepairHandlerType,
+            },
+            repair_service::OutstandingShredRepairs,
+            repair_state_snapshot::{
+                RepairStateSnapshot, RepairStateSnapshotFn, repair_state_snapshot_fn,
+            },
+            secondary_repair_handler::SecondaryCatchUpService,
+            serve_repair_service::ServeRepairService,
         },
         resource_limits::{ResourceLimitError, adjust_nofile_limit},
+        restart_latency_tracker::{RestartLatencySummary, RestartLatencyTracker, RestartPhase},
         sample_performance_service::SamplePerformanceService,
+        slot_gap_classifier::{SlotGapClassifier, SlotGapCounts},
+        slot_resource_accounting::{
+            SlotResourceAccounting, SlotResourceAccountingService, SlotResourceUsage,
+        },
         snapshot_packager_service::SnapshotPackagerService,
         stats_reporter_service::StatsReporterService,
         system_monitor_service::{
             SystemMonitorService, SystemMonitorStatsReportConfig, verify_net_stats_access,
         },
-        tpu::{Tpu, TpuSockets},
+        tpu::{Tpu, TpuChannelSizes, TpuReservedIngest, TpuSockets},
+        tpu_config_analysis::{DEFAULT_TPU_DRAIN_WINDOW, analyze_tpu_config},
+        tpu_ingest_self_test::{
+            IngestSelfTestConfig, IngestSelfTestError, IngestSelfTestResult, TpuIngestSelfTest,
+        },
         tvu::{AlpenglowInitializationState, Tvu, TvuConfig, TvuSockets},
+        vote_account_monitor::{VoteAccountChange, VoteAccountMonitor},
+        vote_capture::VoteCapture,
+        vote_tracker_archive::{VoteTrackerArchiveConfig, VoteTrackerArchiveService},
     },
     agave_snapshots::{
-        SnapshotInterval, snapshot_archive_info::SnapshotArchiveInfoGetter as _,
+        SnapshotInterval, UnpackProgress, snapshot_archive_info::SnapshotArchiveInfoGetter as _,
         snapshot_config::SnapshotConfig, snapshot_hash::StartingSnapshotHashes,
     },
     agave_votor::{
@@ -40,19 +76,22 @@ use {
     },
     agave_xdp::transmitter::{Transmitter, TransmitterBuilder},
     anyhow::{Result, anyhow},
-    crossbeam_channel::{Receiver, bounded, unbounded},
+    crossbeam_channel::{Receiver, Sender, bounded, unbounded},
     serde::{Deserialize, Serialize},
     solana_account::ReadableAccount,
     solana_accounts_db::{
-        accounts_db::{ACCOUNTS_DB_CONFIG_FOR_TESTING, AccountsDbConfig},
-        accounts_update_notifier_interface::AccountsUpdateNotifier,
+        accounts_db::{
+            ACCOUNTS_DB_CONFIG_FOR_TESTING, AccountShrinkThreshold, AccountsDb, AccountsDbConfig,
+        },
+        accounts_file::AccountsFileProvider,
+        accounts_update_notifier_interface::{AccountsUpdateNotifier, SnapshotStreamSession},
         utils::validate_account_paths_for_direct_io,
     },
     solana_client::connection_cache::{ConnectionCache, Protocol},
-    solana_clock::Slot,
+    solana_clock::{Epoch, Slot},
     solana_cluster_type::ClusterType,
     solana_entry::poh::compute_hash_time,
-    solana_epoch_schedule::MAX_LEADER_SCHEDULE_EPOCH_OFFSET,
+    solana_epoch_schedule::{EpochSchedule, MAX_LEADER_SCHEDULE_EPOCH_OFFSET},
     solana_genesis_config::GenesisConfig,
     solana_genesis_utils::{
         MAX_GENESIS_ARCHIVE_UNPACKED_SIZE, OpenGenesisConfigError, open_genesis_config,
@@ -71,6 +110,7 @@ use {
         },
         contact_info::ContactInfo,
         crds_gossip_pull::CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS,
+        gossip_export::{GossipExportConfig, GossipExportService, GossipExporter},
         gossip_service::GossipService,
         node::{Node, NodeMultihoming},
     },
@@ -85,17 +125,21 @@ use {
             MAX_REPLAY_WAKE_UP_SIGNALS, MAX_UPDATE_PARENT_SIGNALS, PurgeType, UpdateParentReceiver,
         },
         blockstore_metric_report_service::BlockstoreMetricReportService,
-        blockstore_options::{BLOCKSTORE_DIRECTORY_ROCKS_LEVEL, BlockstoreOptions},
+        blockstore_options::{AccessType, BLOCKSTORE_DIRECTORY_ROCKS_LEVEL, BlockstoreOptions},
         blockstore_processor::{self, TransactionStatusSender},
         entry_notifier_interface::EntryNotifierArc,
         entry_notifier_service::{EntryNotifierSender, EntryNotifierService},
         leader_schedule_cache::LeaderScheduleCache,
-        shred::filter::TurbineMode,
+        shred::{ShredId, filter::TurbineMode},
         use_snapshot_archives_at_startup::UseSnapshotArchivesAtStartup,
     },
     solana_measure::measure::Measure,
     solana_metrics::{datapoint_info, metrics::metrics_config_sanity_check},
     solana_net_utils::SocketAddrSpace,
+    solana_perf::{
+        packet::PacketBatch,
+        watchdog::{Watchdog, WatchdogService},
+    },
     solana_poh::{
         poh_controller::PohController,
         poh_recorder::PohRecorder,
@@ -138,13 +182,19 @@ use {
     solana_shred_version::compute_shred_version,
     solana_signer::Signer,
     solana_streamer::{
-        nonblocking::{simple_qos::SimpleQosConfig, swqos::SwQosConfig},
+        nonblocking::{
+            memory_budget::TpuIngestMemoryBudget, simple_qos::SimpleQosConfig, swqos::SwQosConfig,
+        },
         quic::{QuicStreamerConfig, SimpleQosQuicStreamerConfig, SwQosQuicStreamerConfig},
         streamer::StakedNodes,
     },
     solana_time_utils::timestamp,
     solana_tpu_client::tpu_client::{DEFAULT_TPU_CONNECTION_POOL_SIZE, DEFAULT_VOTE_USE_QUIC},
-    solana_turbine::{self, XdpSender as TurbineXdpSender, broadcast_stage::BroadcastStageType},
+    solana_turbine::{
+        self, XdpSender as TurbineXdpSender,
+        broadcast_stage::BroadcastStageType,
+        retransmit_stage::{TurbineDiagnosticsError, TurbineTreeDiagnostics},
+    },
     solana_unified_scheduler_pool::DefaultSchedulerPool,
     solana_validator_exit::Exit,
     solana_vote_program::vote_state::{VoteStateV4, handler::VoteStateHandler},
@@ -152,7 +202,8 @@ use {
         borrow::Cow,
         cmp,
         collections::{HashMap, HashSet},
-        net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+        io,
+        net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
         num::{NonZeroU64, NonZeroUsize},
         path::{Path, PathBuf},
         str::FromStr,
@@ -173,7 +224,19 @@ use {
 const MAX_COMPLETED_DATA_SETS_IN_CHANNEL: usize = 100_000;
 const WAIT_FOR_SUPERMAJORITY_THRESHOLD_PERCENT: u64 = 80;
 
-#[derive(Clone, EnumCount, EnumIter, EnumString, VariantNames, Default, IntoStaticStr, Display)]
+#[derive(
+    Clone,
+    Debug,
+    EnumCount,
+    EnumIter,
+    EnumString,
+    VariantNames,
+    Default,
+    IntoStaticStr,
+    Display,
+    PartialEq,
+    Eq,
+)]
 #[strum(serialize_all = "kebab-case")]
 pub enum BlockVerificationMethod {
     #[default]
@@ -299,6 +362,97 @@ impl SchedulerPacing {
     }
 }
 
+/// What to do when the genesis config in the ledger does not match
+/// `ValidatorConfig::expected_genesis_hash`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GenesisHashMismatchPolicy {
+    /// Refuse to start with `ValidatorError::GenesisHashMismatch`
+    #[default]
+    Abort,
+    /// Log an error and keep starting up, e.g. for forensic tooling that
+    /// intentionally loads a different genesis
+    WarnAndContinue,
+}
+
+/// What to do when the bank at the `wait_for_supermajority` slot does not
+/// match `ValidatorConfig::expected_bank_hash`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BankHashMismatchPolicy {
+    /// Refuse to start with `ValidatorError::BankHashMismatch`
+    #[default]
+    Abort,
+    /// Log an error and keep starting up, e.g. to inspect state while
+    /// debugging a cluster restart
+    WarnAndContinue,
+}
+
+/// The accounts db settings in effect, after defaults have been applied to
+/// `ValidatorConfig::accounts_db_config`
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountsDbConfigSummary {
+    pub account_paths: Vec<PathBuf>,
+    pub shrink_ratio: AccountShrinkThreshold,
+    /// The low and high watermark sizes for the read cache, in bytes
+    pub read_cache_limit_bytes: (usize, usize),
+    pub write_cache_limit_bytes: Option<u64>,
+    pub ancient_append_vec_offset: Option<i64>,
+    pub ancient_storage_ideal_size: u64,
+    pub max_ancient_storages: usize,
+    pub num_index_bins: usize,
+    pub num_foreground_threads: usize,
+    pub num_background_threads: usize,
+    pub skip_initial_hash_calc: bool,
+}
+
+/// Summary of the stakes the TPU servers use to prioritize connections
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakedNodesSummary {
+    /// Number of nodes with non-zero stake
+    pub count: usize,
+    pub total_stake: u64,
+    pub max_stake: u64,
+    pub min_stake: u64,
+}
+
+/// Progress of the PoH recorder
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PohStatus {
+    /// Slot of the bank PoH is recording into, None if this node is not
+    /// leader
+    pub slot: Option<Slot>,
+    pub tick_height: u64,
+}
+
+/// The peer allowlists a validator was configured with, `None` meaning that
+/// all peers are allowed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerAllowlists {
+    pub known_validators: Option<HashSet<Pubkey>>,
+    pub repair_validators: Option<HashSet<Pubkey>>,
+    pub gossip_validators: Option<HashSet<Pubkey>>,
+}
+
+impl From<&ValidatorConfig> for PeerAllowlists {
+    fn from(config: &ValidatorConfig) -> Self {
+        Self {
+            known_validators: config.known_validators.clone(),
+            repair_validators: config.repair_validators.clone(),
+            gossip_validators: config.gossip_validators.clone(),
+        }
+    }
+}
+
+/// The time spent in each of the phases of `Validator::new` that are timed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StartupTimings {
+    pub validate_account_paths: Duration,
+    /// Opening the blockstore and loading the bank forks from a snapshot or
+    /// genesis
+    pub load_blockstore: Duration,
+    /// The whole of `Validator::new`
+    pub total: Duration,
+}
+
 /// Configuration for the block generator invalidator for replay.
 #[derive(Clone, Debug)]
 pub struct GeneratorConfig {
@@ -320,12 +474,18 @@ pub struct ValidatorConfig {
     /// Log messages go to `stderr` if `None`
     pub log_config: Option<ValidatorLogConfig>,
     pub expected_genesis_hash: Option<Hash>,
+    pub genesis_hash_mismatch_policy: GenesisHashMismatchPolicy,
     pub expected_bank_hash: Option<Hash>,
+    pub bank_hash_mismatch_policy: BankHashMismatchPolicy,
     pub expected_shred_version: Option<u16>,
     pub voting_disabled: bool,
     pub account_paths: Vec<PathBuf>,
     pub account_snapshot_paths: Vec<PathBuf>,
     pub rpc_config: JsonRpcConfig,
+    /// Whether to run the CompletedDataSetsService, which by default it does
+    /// only along with the full RPC API. Without RPC, it only notifies the
+    /// geyser plugins.
+    pub enable_completed_data_sets: Option<bool>,
     /// Specifies which plugins to start up with
     pub on_start_geyser_plugin_config_files: Option<Vec<PathBuf>>,
     pub geyser_plugin_always_enabled: bool,
@@ -333,6 +493,9 @@ pub struct ValidatorConfig {
     pub pubsub_config: PubSubConfig,
     pub snapshot_config: SnapshotConfig,
     pub max_ledger_shreds: Option<u64>,
+    /// Defer the blockstore compactions around the leader slots of this node,
+    /// if set
+    pub compaction_scheduler_config: Option<CompactionSchedulerConfig>,
     pub blockstore_options: BlockstoreOptions,
     pub broadcast_stage_type: BroadcastStageType,
     pub turbine_mode: TurbineMode,
@@ -342,12 +505,19 @@ pub struct ValidatorConfig {
     pub known_validators: Option<HashSet<Pubkey>>, // None = trust all
     pub repair_validators: Option<HashSet<Pubkey>>, // None = repair from all
     pub repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>, // Empty = repair with all
+    /// If set, serve repairs from a secondary blockstore kept in this directory,
+    /// which follows the primary blockstore
+    pub repair_serve_blockstore_path: Option<PathBuf>,
+    /// How often the secondary blockstore serving repairs catches up with the
+    /// primary blockstore
+    pub repair_serve_blockstore_catch_up_interval: Duration,
     pub gossip_validators: Option<HashSet<Pubkey>>, // None = gossip with all
     pub should_check_duplicate_instance: bool,
     pub max_genesis_archive_unpacked_size: u64,
-    /// Run PoH, transaction signature and other transaction verification during blockstore
-    /// processing.
-    pub run_verification: bool,
+    /// Run PoH verification during blockstore processing.
+    pub run_poh_verification: bool,
+    /// Run transaction signature verification during blockstore processing.
+    pub run_sig_verification: bool,
     pub require_tower: bool,
     pub require_vote_history: bool,
     pub tower_storage: Arc<dyn TowerStorage>,
@@ -358,6 +528,12 @@ pub struct ValidatorConfig {
     pub contact_save_interval: u64,
     pub send_transaction_service_config: SendTransactionServiceConfig,
     pub no_poh_speed_test: bool,
+    /// Number of hashes to time in the PoH speed check, one slot worth of
+    /// hashes if None
+    pub poh_speed_check_samples: Option<u64>,
+    /// Fail the PoH speed check, instead of skipping it, if the root bank has
+    /// no hashes per tick
+    pub require_hashes_per_tick: bool,
     pub no_os_memory_stats_reporting: bool,
     pub no_os_network_stats_reporting: bool,
     pub no_os_cpu_stats_reporting: bool,
@@ -370,21 +546,39 @@ pub struct ValidatorConfig {
     pub warp_slot: Option<Slot>,
     pub accounts_db_skip_shrink: bool,
     pub accounts_db_force_initial_clean: bool,
+    /// Rebuild the accounts from the snapshot archives, instead of failing to
+    /// start, if the local accounts state cannot be loaded with the current
+    /// accounts-db options and snapshot archives are never used at startup
+    pub accounts_db_allow_migration: bool,
     pub staked_nodes_overrides: Arc<RwLock<HashMap<Pubkey, u64>>>,
     pub validator_exit: Arc<RwLock<Exit>>,
     pub validator_exit_backpressure: HashMap<String, Arc<AtomicBool>>,
     pub no_wait_for_vote_to_start_leader: bool,
     pub wait_to_vote_slot: Option<Slot>,
+    /// Never produce blocks before this slot, regardless of voting
+    pub min_leader_slot: Option<Slot>,
+    /// Maximum number of unfrozen banks replay keeps before pruning the fork
+    /// tips with the least observed vote stake
+    pub max_active_banks: Option<NonZeroUsize>,
+    /// Precompute the leader schedule of the next epoch once the root is
+    /// within this many slots of the epoch boundary
+    pub precompute_leader_schedule_slots: Option<u64>,
     pub runtime_config: RuntimeConfig,
     pub banking_trace_dir_byte_limit: banking_trace::DirByteLimit,
     pub block_verification_method: BlockVerificationMethod,
     pub block_production_method: BlockProductionMethod,
     pub block_production_num_workers: NonZeroUsize,
     pub block_production_scheduler_config: SchedulerConfig,
+    /// Compute units reserved in each leader slot for the transactions sent
+    /// through `Validator::reserved_ingest_sender`
+    pub reserved_leader_compute_units: u64,
     pub enable_block_production_forwarding: bool,
     pub enable_scheduler_bindings: bool,
     pub generator_config: Option<GeneratorConfig>,
     pub use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup,
+    /// Keep the incomplete and old bank snapshots found at startup, e.g. to
+    /// inspect them after a crash, instead of purging them
+    pub preserve_bank_snapshots_at_startup: bool,
     pub unified_scheduler_handler_threads: Option<usize>,
     pub ip_echo_server_threads: NonZeroUsize,
     pub rayon_global_threads: NonZeroUsize,
@@ -397,21 +591,73 @@ pub struct ValidatorConfig {
     pub repair_handler_type: RepairHandlerType,
     // Thread niceness adjustment for snapshot packager service
     pub snapshot_packager_niceness_adj: i8,
+    pub snapshot_packaging_threads: NonZeroUsize,
+    /// Advertise the hashes of the snapshots taken by this node over gossip
+    pub snapshot_gossip_push: bool,
+    /// Number of recently processed votes to keep for debugging optimistic
+    /// confirmation anomalies, disabled if None
+    pub vote_capture_capacity: Option<NonZeroUsize>,
+    /// Forward the gossip votes to banking only while the leader slots of
+    /// this node start within this many slots, always if None
+    pub gossip_vote_forwarding_leader_window_slots: Option<u64>,
+    /// Drop the votes received over gossip which were pushed longer than this
+    /// many milliseconds ago, processing votes of any age if None
+    pub max_gossip_vote_age_ms: Option<u64>,
+    /// Only capture and archive the votes for these slots in the vote
+    /// listener, as long as the set is not empty, to investigate a given range
+    /// of slots. Confirmations are still tracked for every slot
+    pub vote_listener_slot_filter: Option<Arc<RwLock<HashSet<Slot>>>>,
+    /// Bytes of memory shared by the TPU, TPU forward and vote QUIC servers
+    /// for the packets they ingest, unbounded if None
+    pub tpu_ingest_memory_budget_bytes: Option<NonZeroUsize>,
+    /// Close the connections to the TPU QUIC server as soon as they are
+    /// established until the node is running
+    pub tpu_refuse_connections_until_ready: bool,
+    /// Capacity of the channel carrying duplicate confirmed slots to replay
+    pub duplicate_confirmed_slots_channel_capacity: NonZeroUsize,
+    /// Periodically export the contact infos held in gossip, disabled if None
+    pub gossip_export: Option<GossipExportConfig>,
+    /// Archive the vote aggregates of the rooted slots, disabled if None
+    pub vote_tracker_archive: Option<VoteTrackerArchiveConfig>,
+    /// Time at which the process started, for the restart latency summary.
+    /// The start of `Validator::new` if None.
+    pub process_start_time: Option<Instant>,
+    /// Base seed of the RNGs of the subsystems listed in `deterministic_rng`,
+    /// for reproducible runs in tests. The subsystems draw from the thread
+    /// local RNG if None, or if built without `dev-context-only-utils`.
+    pub deterministic_rng_seed: Option<u64>,
+    /// Fail to start, rather than warn, if the TPU QUIC server limits cannot
+    /// all be met, see `tpu_config_analysis`
+    pub strict_tpu_config: bool,
 }
 
 impl ValidatorConfig {
+    /// Returns the base seed of the subsystem RNGs, which only builds with
+    /// `dev-context-only-utils` honor
+    fn deterministic_rng_seed(&self) -> Option<u64> {
+        if cfg!(feature = "dev-context-only-utils") {
+            self.deterministic_rng_seed
+        } else {
+            None
+        }
+    }
+
     pub fn default_for_test() -> Self {
         Self {
             log_config: None,
             expected_genesis_hash: None,
+            genesis_hash_mismatch_policy: GenesisHashMismatchPolicy::default(),
             expected_bank_hash: None,
+            bank_hash_mismatch_policy: BankHashMismatchPolicy::default(),
             expected_shred_version: None,
             voting_disabled: false,
             max_ledger_shreds: None,
+            compaction_scheduler_config: None,
             blockstore_options: BlockstoreOptions::default_for_tests(),
             account_paths: Vec::new(),
             account_snapshot_paths: Vec::new(),
             rpc_config: JsonRpcConfig::default_for_test(),
+            enable_completed_data_sets: None,
             on_start_geyser_plugin_config_files: None,
             geyser_plugin_always_enabled: false,
             rpc_addrs: None,
@@ -426,9 +672,13 @@ impl ValidatorConfig {
             repair_validators: None,
             should_check_duplicate_instance: true,
             repair_whitelist: Arc::new(RwLock::new(HashSet::default())),
+            repair_serve_blockstore_path: None,
+            repair_serve_blockstore_catch_up_interval:
+                DEFAULT_REPAIR_SERVE_BLOCKSTORE_CATCH_UP_INTERVAL,
             gossip_validators: None,
             max_genesis_archive_unpacked_size: MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             require_tower: false,
             require_vote_history: false,
             tower_storage: Arc::new(NullTowerStorage::default()),
@@ -439,6 +689,8 @@ impl ValidatorConfig {
             contact_save_interval: DEFAULT_CONTACT_SAVE_INTERVAL_MILLIS,
             send_transaction_service_config: SendTransactionServiceConfig::default(),
             no_poh_speed_test: true,
+            poh_speed_check_samples: None,
+            require_hashes_per_tick: false,
             no_os_memory_stats_reporting: true,
             no_os_network_stats_reporting: true,
             no_os_cpu_stats_reporting: true,
@@ -451,23 +703,29 @@ impl ValidatorConfig {
             warp_slot: None,
             accounts_db_skip_shrink: false,
             accounts_db_force_initial_clean: false,
+            accounts_db_allow_migration: false,
             staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
             validator_exit: Arc::new(RwLock::new(Exit::default())),
             validator_exit_backpressure: HashMap::default(),
             no_wait_for_vote_to_start_leader: true,
             accounts_db_config: ACCOUNTS_DB_CONFIG_FOR_TESTING,
             wait_to_vote_slot: None,
+            min_leader_slot: None,
+            max_active_banks: None,
+            precompute_leader_schedule_slots: None,
             runtime_config: RuntimeConfig::default(),
             banking_trace_dir_byte_limit: 0,
             block_verification_method: BlockVerificationMethod::default(),
             block_production_method: BlockProductionMethod::default(),
             block_production_num_workers: BankingStage::default_num_workers(),
             block_production_scheduler_config: SchedulerConfig::default(),
+            reserved_leader_compute_units: 0,
             // enable forwarding by default for tests
             enable_block_production_forwarding: true,
             enable_scheduler_bindings: false,
             generator_config: None,
             use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup::default(),
+            preserve_bank_snapshots_at_startup: false,
             unified_scheduler_handler_threads: None,
             // Fix threadpools to small and reasonable sizes; unit tests should
             // not be creating excessive load and benches can configure more
@@ -481,6 +739,21 @@ impl ValidatorConfig {
             voting_service_test_override: None,
             repair_handler_type: RepairHandlerType::default(),
             snapshot_packager_niceness_adj: 0,
+            snapshot_gossip_push: true,
+            snapshot_packaging_threads: SnapshotPackagerService::DEFAULT_NUM_THREADS,
+            vote_capture_capacity: None,
+            gossip_vote_forwarding_leader_window_slots: None,
+            max_gossip_vote_age_ms: None,
+            vote_listener_slot_filter: None,
+            tpu_ingest_memory_budget_bytes: None,
+            tpu_refuse_connections_until_ready: false,
+            duplicate_confirmed_slots_channel_capacity:
+                DEFAULT_DUPLICATE_CONFIRMED_SLOTS_CHANNEL_CAPACITY,
+            gossip_export: None,
+            vote_tracker_archive: None,
+            process_start_time: None,
+            deterministic_rng_seed: None,
+            strict_tpu_config: false,
         }
     }
 
@@ -508,10 +781,22 @@ pub enum ValidatorStartProgress {
     DownloadingSnapshot {
         slot: Slot,
         rpc_addr: SocketAddr,
+        /// Number of the peers tried so far to download from, counting the
+        /// current one
+        #[serde(default)]
+        attempt: usize,
     },
     CleaningBlockStore,
     CleaningAccounts,
     LoadingLedger,
+    UnpackingSnapshot {
+        bytes_unpacked: u64,
+        total_bytes: u64,
+    },
+    BuildingAccountsIndex {
+        accounts_indexed: u64,
+    },
+    MigratingAccountsDb,
     ProcessingLedger {
         slot: Slot,
         max_slot: Slot,
@@ -587,6 +872,8 @@ pub struct ValidatorTpuConfig {
     pub tpu_fwd_quic_server_config: SwQosQuicStreamerConfig,
     /// QUIC server config for Vote
     pub vote_quic_server_config: SimpleQosQuicStreamerConfig,
+    /// Sizes of the channels each QUIC server sends its packets to
+    pub channel_sizes: TpuChannelSizes,
     /// Number of threads to use for signature verification
     pub sigverify_threads: NonZeroUsize,
 }
@@ -634,6 +921,7 @@ impl ValidatorTpuConfig {
             tpu_quic_server_config,
             tpu_fwd_quic_server_config,
             vote_quic_server_config,
+            channel_sizes: TpuChannelSizes::default(),
             sigverify_threads,
         }
     }
@@ -654,8 +942,31 @@ pub struct Validator {
     system_monitor_service: Option<SystemMonitorService>,
     sample_performance_service: Option<SamplePerformanceService>,
     stats_reporter_service: StatsReporterService,
+    watchdog: Arc<Watchdog>,
+    watchdog_service: WatchdogService,
     gossip_service: GossipService,
+    gossip_exporter: Option<Arc<GossipExporter>>,
+    gossip_export_service: Option<GossipExportService>,
+    vote_tracker_archive_service: Option<VoteTrackerArchiveService>,
     serve_repair_service: ServeRepairService,
+    secondary_catch_up_service: Option<SecondaryCatchUpService>,
+    outstanding_repair_requests: Arc<RwLock<OutstandingShredRepairs>>,
+    repair_state_snapshot: Arc<RepairStateSnapshotFn>,
+    ingest_self_test: Arc<TpuIngestSelfTest>,
+    block_verification_method: BlockVerificationMethod,
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+    optimistic_confirmation_violation_log: Arc<OptimisticConfirmationViolationLog>,
+    ledger_path: PathBuf,
+    accounts_db_manifest: AccountsDbManifest,
+    slot_gap_classifier: Arc<SlotGapClassifier>,
+    vote_account_monitor: Arc<VoteAccountMonitor>,
+    restart_latency_tracker: Arc<RestartLatencyTracker>,
+    gossip_vote_landing_tracker: Arc<GossipVoteLandingTracker>,
+    vote_capture: Option<Arc<VoteCapture>>,
+    reserved_ingest_sender: Option<Sender<PacketBatch>>,
+    slot_resource_accounting: SlotResourceAccounting,
+    vote_account: Pubkey,
+    leader_schedule_cache: Arc<LeaderScheduleCache>,
     completed_data_sets_service: Option<CompletedDataSetsService>,
     snapshot_packager_service: SnapshotPackagerService,
     poh_recorder: Arc<RwLock<PohRecorder>>,
@@ -664,6 +975,13 @@ pub struct Validator {
     tpu: Tpu,
     tvu: Tvu,
     ip_echo_server: Option<solana_net_utils::IpEchoServer>,
+    max_slots: Arc<MaxSlots>,
+    block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+    advertised_addresses: Arc<AdvertisedAddresses>,
+    peer_allowlists: RwLock<PeerAllowlists>,
+    repair_validators: Arc<RwLock<Option<HashSet<Pubkey>>>>,
+    startup_timings: StartupTimings,
+    epoch_schedule: EpochSchedule,
     pub cluster_info: Arc<ClusterInfo>,
     pub bank_forks: Arc<RwLock<BankForks>>,
     pub blockstore: Arc<Blockstore>,
@@ -673,6 +991,8 @@ pub struct Validator {
     /// notifications.
     _contact_info_notifier: Option<GeyserContactInfoNotifier>,
     blockstore_metric_report_service: BlockstoreMetricReportService,
+    compaction_scheduler: Option<CompactionScheduler>,
+    slot_resource_accounting_service: SlotResourceAccountingService,
     accounts_background_service: AccountsBackgroundService,
     xdp_transmitter: Option<Transmitter>,
     // This runtime is used to run the client owned by SendTransactionService.
@@ -740,14 +1060,36 @@ impl Validator {
         const DEBUG_ASSERTION_STATUS: &str = "disabled";
         info!("debug-assertion status: {DEBUG_ASSERTION_STATUS}");
 
+        check_tpu_config(&tpu_config, config)?;
         let ValidatorTpuConfig {
             vote_use_quic,
             tpu_connection_pool_size,
-            tpu_quic_server_config,
-            tpu_fwd_quic_server_config,
-            vote_quic_server_config,
+            mut tpu_quic_server_config,
+            mut tpu_fwd_quic_server_config,
+            mut vote_quic_server_config,
+            channel_sizes: tpu_channel_sizes,
             sigverify_threads: tpu_sigverify_threads,
         } = tpu_config;
+        if let Some(max_bytes) = config.tpu_ingest_memory_budget_bytes {
+            let memory_budget = Arc::new(TpuIngestMemoryBudget::new(max_bytes.get()));
+            for quic_streamer_config in [
+                &mut tpu_quic_server_config.quic_streamer_config,
+                &mut tpu_fwd_quic_server_config.quic_streamer_config,
+                &mut vote_quic_server_config.quic_streamer_config,
+            ] {
+                quic_streamer_config.memory_budget = Some(memory_budget.clone());
+            }
+        }
+        let tpu_ready = config
+            .tpu_refuse_connections_until_ready
+            .then(|| Arc::new(AtomicBool::new(false)));
+        tpu_quic_server_config.quic_streamer_config.ready = tpu_ready.clone();
+        tpu_quic_server_config.qos_config.prune_rng_seed =
+            subsystem_rng_seed(config.deterministic_rng_seed(), TPU_QUIC_RNG_LABEL);
+        tpu_fwd_quic_server_config.qos_config.prune_rng_seed =
+            subsystem_rng_seed(config.deterministic_rng_seed(), TPU_FORWARDS_QUIC_RNG_LABEL);
+        vote_quic_server_config.qos_config.prune_rng_seed =
+            subsystem_rng_seed(config.deterministic_rng_seed(), TPU_VOTE_QUIC_RNG_LABEL);
 
         let start_time = Instant::now();
 
@@ -770,6 +1112,12 @@ impl Validator {
 
         info!("identity pubkey: {id}");
         info!("vote account pubkey: {vote_account}");
+        let restart_latency_tracker = Arc::new(RestartLatencyTracker::new(
+            config.process_start_time.unwrap_or(start_time),
+            *vote_account,
+            config.voting_disabled,
+        ));
+        let watchdog = Arc::new(Watchdog::default());
 
         if !config.no_os_network_stats_reporting {
             verify_net_stats_access().map_err(|e| {
@@ -836,10 +1184,36 @@ impl Validator {
         validate_account_paths(config)?;
         timer.stop();
         info!("Validating accounts paths done. {timer}");
+        let mut startup_timings = StartupTimings {
+            validate_account_paths: Duration::from_micros(timer.as_us()),
+            ..StartupTimings::default()
+        };
+
+        // The accounts-db creates its storage files in the default format
+        let accounts_db_manifest = AccountsDbManifest::new(
+            &config.account_paths,
+            AccountsFileProvider::default(),
+            config.snapshot_config.snapshot_version,
+        );
+        let accounts_db_migration = AccountsDbManifest::load(ledger_path)
+            .map(|previous_manifest| {
+                previous_manifest.check(
+                    &accounts_db_manifest,
+                    config.use_snapshot_archives_at_startup,
+                    config.accounts_db_allow_migration,
+                )
+            })
+            .transpose()?
+            .flatten();
 
-        snapshot_utils::purge_incomplete_bank_snapshots(&config.snapshot_config.bank_snapshots_dir);
-        snapshot_utils::purge_old_bank_snapshots_at_startup(
-            &config.snapshot_config.bank_snapshots_dir,
+        purge_bank_snapshots_at_startup(
+            config,
+            |bank_snapshots_dir| {
+                snapshot_utils::purge_incomplete_bank_snapshots(bank_snapshots_dir)
+            },
+            |bank_snapshots_dir| {
+                snapshot_utils::purge_old_bank_snapshots_at_startup(bank_snapshots_dir)
+            },
         );
 
         // token used to cancel tpu-client-next, streamer and BLS streamer.
@@ -900,6 +1274,7 @@ impl Validator {
 
         let dependency_tracker = Arc::new(DependencyTracker::default());
 
+        let mut timer = Measure::start("load_blockstore");
         let (
             bank_forks,
             blockstore,
@@ -923,6 +1298,8 @@ impl Validator {
             &genesis_config,
             exit.clone(),
             &start_progress,
+            &restart_latency_tracker,
+            accounts_db_migration,
             accounts_update_notifier,
             transaction_notifier,
             entry_notifier,
@@ -930,13 +1307,38 @@ impl Validator {
                 .rpc_addrs
                 .is_some()
                 .then(|| dependency_tracker.clone()),
-        )
-        .map_err(ValidatorError::Other)?;
+        )?;
+        timer.stop();
+        info!("Loading blockstore done. {timer}");
+        startup_timings.load_blockstore = Duration::from_micros(timer.as_us());
+        // Records the format the loaded accounts-db actually stores accounts in
+        let accounts_db_manifest = AccountsDbManifest::new(
+            &config.account_paths,
+            bank_forks
+                .read()
+                .unwrap()
+                .root_bank()
+                .accounts()
+                .accounts_db
+                .accounts_file_provider(),
+            config.snapshot_config.snapshot_version,
+        );
+        if accounts_db_migration.is_some() {
+            // The accounts state on disk now matches the current options,
+            // even if this run does not shut down cleanly
+            if let Err(err) = accounts_db_manifest.write(ledger_path) {
+                warn!("Failed to write the accounts-db manifest: {err}");
+            }
+        }
 
         let migration_status = bank_forks.read().unwrap().migration_status();
 
         if !config.no_poh_speed_test && !migration_status.is_alpenglow_enabled() {
-            check_poh_speed(&bank_forks.read().unwrap().root_bank(), None)?;
+            check_poh_speed(
+                &bank_forks.read().unwrap().root_bank(),
+                config.poh_speed_check_samples,
+                config.require_hashes_per_tick,
+            )?;
         }
 
         let (root_slot, hard_forks) = {
@@ -988,10 +1390,18 @@ impl Validator {
                 .set_trim_keep_pubkeys(known_validators.iter().copied())
                 .expect("set_trim_keep_pubkeys should succeed as ClusterInfo was just created");
         }
+        let entrypoint_gossip_addrs = cluster_entrypoints
+            .iter()
+            .filter_map(ContactInfo::gossip)
+            .collect();
         cluster_info.set_entrypoints(cluster_entrypoints);
         cluster_info.restore_contact_info(ledger_path, config.contact_save_interval);
         cluster_info.set_bind_ip_addrs(node.bind_ip_addrs.clone());
         let cluster_info = Arc::new(cluster_info);
+        let advertised_addresses = Arc::new(AdvertisedAddresses::new(
+            cluster_info.clone(),
+            entrypoint_gossip_addrs,
+        ));
         let node_multihoming = Arc::new(NodeMultihoming::from(&node));
         migration_status.set_pubkey(cluster_info.id());
 
@@ -1020,7 +1430,6 @@ impl Validator {
             .validator_exit_backpressure
             .get(SnapshotPackagerService::NAME)
             .cloned();
-        let enable_gossip_push = true;
         let snapshot_packager_service = SnapshotPackagerService::new(
             pending_snapshot_packages.clone(),
             starting_snapshot_hashes,
@@ -1028,8 +1437,10 @@ impl Validator {
             exit_backpressure,
             cluster_info.clone(),
             snapshot_controller.clone(),
-            enable_gossip_push,
+            config.snapshot_gossip_push,
             config.snapshot_packager_niceness_adj,
+            config.snapshot_packaging_threads,
+            Some((accounts_db_manifest.clone(), ledger_path.to_path_buf())),
         );
         let snapshot_request_handler = SnapshotRequestHandler {
             snapshot_controller: snapshot_controller.clone(),
@@ -1046,6 +1457,7 @@ impl Validator {
                 snapshot_request_handler,
                 pruned_banks_request_handler,
             },
+            watchdog.clone(),
         );
         info!(
             "Using: block-verification-method: {}, block-production-method: {}",
@@ -1100,6 +1512,22 @@ impl Validator {
             info!("Disabled banking trace");
         }
         let banking_tracer_channels = banking_tracer.create_channels();
+        let (reserved_ingest_sender, reserved_ingest) = if config.reserved_leader_compute_units > 0
+        {
+            let (packet_sender, packet_receiver) = unbounded();
+            let (verified_sender, verified_receiver) = banking_tracer.create_channel_non_vote();
+            let reserved_ingest = TpuReservedIngest {
+                packet_receiver,
+                verified_sender,
+                banking: ReservedIngest {
+                    receiver: verified_receiver,
+                    compute_units: config.reserved_leader_compute_units,
+                },
+            };
+            (Some(packet_sender), Some(reserved_ingest))
+        } else {
+            (None, None)
+        };
 
         let scheduler_pool = DefaultSchedulerPool::new(
             config.unified_scheduler_handler_threads,
@@ -1130,6 +1558,7 @@ impl Validator {
             blockstore_root_scan,
             &snapshot_controller,
             config,
+            &restart_latency_tracker,
         );
 
         maybe_warp_slot(
@@ -1240,8 +1669,6 @@ impl Validator {
             json_rpc_service,
             rpc_subscriptions,
             pubsub_service,
-            completed_data_sets_sender,
-            completed_data_sets_service,
             rpc_completed_slots_service,
             sample_performance_service,
             optimistically_confirmed_bank_tracker,
@@ -1324,27 +1751,6 @@ impl Validator {
                 Some(pubsub_service)
             };
 
-            let (completed_data_sets_sender, completed_data_sets_service) =
-                if !config.rpc_config.full_api {
-                    (None, None)
-                } else {
-                    let (completed_data_sets_sender, completed_data_sets_receiver) =
-                        bounded(MAX_COMPLETED_DATA_SETS_IN_CHANNEL);
-                    let completed_data_sets_service = CompletedDataSetsService::new(
-                        completed_data_sets_receiver,
-                        blockstore.clone(),
-                        rpc_subscriptions.clone(),
-                        deshred_transaction_notifier.clone(),
-                        exit.clone(),
-                        max_slots.clone(),
-                        bank_forks.clone(),
-                    );
-                    (
-                        Some(completed_data_sets_sender),
-                        Some(completed_data_sets_service),
-                    )
-                };
-
             let rpc_completed_slots_service =
                 if config.rpc_config.full_api || geyser_plugin_service.is_some() {
                     let (completed_slots_sender, completed_slots_receiver) =
@@ -1394,17 +1800,36 @@ impl Validator {
                 Some(json_rpc_service),
                 Some(rpc_subscriptions),
                 pubsub_service,
-                completed_data_sets_sender,
-                completed_data_sets_service,
                 rpc_completed_slots_service,
                 sample_performance_service,
                 optimistically_confirmed_bank_tracker,
                 bank_notification_sender_config,
             )
         } else {
-            (None, None, None, None, None, None, None, None, None)
+            (None, None, None, None, None, None, None)
         };
 
+        let (completed_data_sets_sender, completed_data_sets_service) =
+            if !should_run_completed_data_sets_service(config) {
+                (None, None)
+            } else {
+                let (completed_data_sets_sender, completed_data_sets_receiver) =
+                    bounded(MAX_COMPLETED_DATA_SETS_IN_CHANNEL);
+                let completed_data_sets_service = CompletedDataSetsService::new(
+                    completed_data_sets_receiver,
+                    blockstore.clone(),
+                    rpc_subscriptions.clone(),
+                    deshred_transaction_notifier.clone(),
+                    exit.clone(),
+                    max_slots.clone(),
+                    bank_forks.clone(),
+                );
+                (
+                    Some(completed_data_sets_sender),
+                    Some(completed_data_sets_service),
+                )
+            };
+
         let ip_echo_server = match node.sockets.ip_echo {
             None => None,
             Some(tcp_listener) => Some(solana_net_utils::ip_echo_server(
@@ -1417,7 +1842,8 @@ impl Validator {
         let (stats_reporter_sender, stats_reporter_receiver) = unbounded();
 
         let stats_reporter_service =
-            StatsReporterService::new(stats_reporter_receiver, exit.clone());
+            StatsReporterService::new(stats_reporter_receiver, exit.clone(), watchdog.clone());
+        let watchdog_service = WatchdogService::new(watchdog.clone(), exit.clone());
 
         let epoch_specs: Box<dyn solana_gossip::epoch_specs::EpochSpecs> =
             Box::new(crate::epoch_specs::EpochSpecs::from(bank_forks.clone()));
@@ -1431,11 +1857,56 @@ impl Validator {
             Some(stats_reporter_sender.clone()),
             exit.clone(),
         );
+        let gossip_exporter = config.gossip_export.clone().map(|gossip_export_config| {
+            Arc::new(GossipExporter::new(
+                cluster_info.clone(),
+                Some(Box::new(crate::epoch_specs::EpochSpecs::from(
+                    bank_forks.clone(),
+                ))),
+                gossip_export_config,
+            ))
+        });
+        let gossip_export_service = gossip_exporter
+            .clone()
+            .map(|gossip_exporter| GossipExportService::new(gossip_exporter, exit.clone()));
+        let secondary_repair_blockstore = config
+            .repair_serve_blockstore_path
+            .as_ref()
+            .map(|secondary_path| {
+                Blockstore::open_with_options(
+                    blockstore.ledger_path(),
+                    BlockstoreOptions {
+                        access_type: AccessType::Secondary {
+                            secondary_path: secondary_path.clone(),
+                        },
+                        ..config.blockstore_options.clone()
+                    },
+                )
+                .map(Arc::new)
+                .map_err(|err| {
+                    ValidatorError::Other(format!(
+                        "Failed to open the secondary blockstore at {secondary_path:?} to serve \
+                         repairs from: {err:?}"
+                    ))
+                })
+            })
+            .transpose()?;
+        let secondary_catch_up_service =
+            secondary_repair_blockstore
+                .clone()
+                .map(|secondary_repair_blockstore| {
+                    SecondaryCatchUpService::new(
+                        secondary_repair_blockstore,
+                        config.repair_serve_blockstore_catch_up_interval,
+                        exit.clone(),
+                    )
+                });
         let serve_repair = {
             let bank_forks_r = bank_forks.read().unwrap();
             let leader_state = poh_recorder.read().unwrap().shared_leader_state();
             config.repair_handler_type.create_serve_repair(
                 blockstore.clone(),
+                secondary_repair_blockstore,
                 cluster_info.clone(),
                 bank_forks_r.sharable_banks(),
                 config.repair_whitelist.clone(),
@@ -1455,7 +1926,23 @@ impl Validator {
         )?;
 
         let blockstore_metric_report_service =
-            BlockstoreMetricReportService::new(blockstore.clone(), exit.clone());
+            BlockstoreMetricReportService::new(blockstore.clone(), exit.clone(), watchdog.clone());
+        let compaction_scheduler = config.compaction_scheduler_config.clone().map(|config| {
+            let poh_recorder = poh_recorder.read().unwrap();
+            CompactionScheduler::new(
+                config,
+                blockstore.clone(),
+                poh_recorder.shared_leader_state(),
+                poh_recorder.ticks_per_slot(),
+                exit.clone(),
+            )
+        });
+        let (slot_resource_accounting_service, slot_resource_accounting) =
+            SlotResourceAccountingService::new(
+                blockstore.clone(),
+                bank_forks.read().unwrap().sharable_banks(),
+                exit.clone(),
+            );
 
         let wait_for_vote_to_start_leader =
             !waited_for_supermajority && !config.no_wait_for_vote_to_start_leader;
@@ -1510,6 +1997,7 @@ impl Validator {
             optimistic_parent_receiver: optimistic_parent_receiver.clone(),
             highest_finalized: highest_finalized.clone(),
             banking_stage_sender: banking_stage_sender_for_bcl,
+            min_leader_slot: config.min_leader_slot,
             sharable_banks: bank_forks.read().unwrap().sharable_banks(),
         };
         let (block_creation_loop, reward_votes_sender) =
@@ -1526,7 +2014,8 @@ impl Validator {
         let (retransmit_slots_sender, retransmit_slots_receiver) = unbounded();
         let (verified_vote_sender, verified_vote_receiver) = unbounded();
         let (gossip_verified_vote_hash_sender, gossip_verified_vote_hash_receiver) = unbounded();
-        let (duplicate_confirmed_slot_sender, duplicate_confirmed_slots_receiver) = unbounded();
+        let (duplicate_confirmed_slot_sender, duplicate_confirmed_slots_receiver) =
+            duplicate_confirmed_slots_channel(config.duplicate_confirmed_slots_channel_capacity);
 
         let entry_notification_sender = entry_notifier_service
             .as_ref()
@@ -1550,8 +2039,7 @@ impl Validator {
 
         migration_status.log_phase();
 
-        let outstanding_repair_requests =
-            Arc::<RwLock<repair::repair_service::OutstandingShredRepairs>>::default();
+        let outstanding_repair_requests = Arc::<RwLock<OutstandingShredRepairs>>::default();
         let root_bank = bank_forks.read().unwrap().root_bank();
         let cluster_slots = Arc::new({
             crate::cluster_slots_service::cluster_slots::ClusterSlots::new(
@@ -1595,6 +2083,7 @@ impl Validator {
             None
         };
 
+        let repair_validators = Arc::new(RwLock::new(config.repair_validators.clone()));
         let tvu = Tvu::new(
             vote_account,
             authorized_voter_keypairs,
@@ -1620,7 +2109,7 @@ impl Validator {
             config.vote_history_storage.clone(),
             &leader_schedule_cache,
             exit.clone(),
-            block_commitment_cache,
+            block_commitment_cache.clone(),
             config.turbine_mode.clone(),
             transaction_status_sender.clone(),
             entry_notification_sender.clone(),
@@ -1636,14 +2125,18 @@ impl Validator {
             TvuConfig {
                 max_ledger_shreds: config.max_ledger_shreds,
                 shred_version: node.info.shred_version(),
-                repair_validators: config.repair_validators.clone(),
+                repair_validators: repair_validators.clone(),
                 repair_whitelist: config.repair_whitelist.clone(),
                 wait_for_vote_to_start_leader,
+                min_leader_slot: config.min_leader_slot,
+                max_active_banks: config.max_active_banks,
+                precompute_leader_schedule_slots: config.precompute_leader_schedule_slots,
                 replay_forks_threads: config.replay_forks_threads,
                 replay_transactions_threads: config.replay_transactions_threads,
                 shred_sigverify_threads: config.tvu_shred_sigverify_threads,
                 bls_sigverify_threads: config.tvu_bls_sigverify_threads,
                 turbine_xdp_sender: turbine_xdp_sender.clone(),
+                deterministic_rng_seed: config.deterministic_rng_seed(),
             },
             &max_slots,
             block_metadata_notifier,
@@ -1674,6 +2167,8 @@ impl Validator {
                 highest_finalized,
             },
             reward_votes_sender,
+            restart_latency_tracker.clone(),
+            Some(slot_resource_accounting.clone()),
         )
         .map_err(ValidatorError::Other)?;
 
@@ -1690,6 +2185,23 @@ impl Validator {
                 node_multihoming: node_multihoming.clone(),
             }
         };
+        let optimistic_confirmation_violation_log =
+            Arc::new(OptimisticConfirmationViolationLog::new(ledger_path));
+        let slot_gap_classifier = Arc::new(SlotGapClassifier::new(leader_schedule_cache.clone()));
+        let vote_account_monitor = Arc::new(VoteAccountMonitor::new(*vote_account));
+        let gossip_vote_landing_tracker = Arc::new(GossipVoteLandingTracker::default());
+        let vote_capture = config
+            .vote_capture_capacity
+            .map(|capacity| Arc::new(VoteCapture::new(capacity, ledger_path)));
+        let (vote_tracker_archive_service, vote_tracker_archiver) = config
+            .vote_tracker_archive
+            .clone()
+            .map(VoteTrackerArchiveService::new)
+            .transpose()
+            .map_err(|err| {
+                ValidatorError::Other(format!("Failed to start the vote tracker archive: {err}"))
+            })?
+            .unzip();
         let (banking_control_sender, banking_control_receiver) = mpsc::channel(1);
         let tpu = Tpu::new_with_client(
             &cluster_info,
@@ -1733,6 +2245,7 @@ impl Validator {
             tpu_quic_server_config,
             tpu_fwd_quic_server_config,
             vote_quic_server_config,
+            tpu_channel_sizes,
             prioritization_fee_cache,
             tpu_sigverify_threads,
             config.block_production_method.clone(),
@@ -1751,19 +2264,68 @@ impl Validator {
             }),
             cancel,
             votor_event_sender.clone(),
+            vote_capture.clone(),
+            optimistic_confirmation_violation_log.clone(),
+            slot_gap_classifier.clone(),
+            vote_account_monitor.clone(),
+            restart_latency_tracker.clone(),
+            vote_tracker_archiver,
+            gossip_vote_landing_tracker.clone(),
+            reserved_ingest,
+            config.gossip_vote_forwarding_leader_window_slots,
+            config.max_gossip_vote_age_ms,
+            config.vote_listener_slot_filter.clone(),
+            watchdog.clone(),
         );
 
+        // Startup is done, so stop throwing all the cores at calculating the accounts hash
+        let accounts_db = bank_forks
+            .read()
+            .unwrap()
+            .root_bank()
+            .rc
+            .accounts
+            .accounts_db
+            .clone();
+        let startup_accounts_hash_threads = accounts_db.num_hash_threads();
+        accounts_db.use_steady_state_hash_threads();
+
+        startup_timings.total = start_time.elapsed();
         datapoint_info!(
             "validator-new",
             ("id", id.to_string(), String),
             ("version", solana_version::version!(), String),
             ("cluster_type", genesis_config.cluster_type as u32, i64),
-            ("elapsed_ms", start_time.elapsed().as_millis() as i64, i64),
+            ("elapsed_ms", startup_timings.total.as_millis() as i64, i64),
+            (
+                "validate_account_paths_ms",
+                startup_timings.validate_account_paths.as_millis() as i64,
+                i64
+            ),
+            (
+                "load_blockstore_ms",
+                startup_timings.load_blockstore.as_millis() as i64,
+                i64
+            ),
             ("waited_for_supermajority", waited_for_supermajority, bool),
             ("shred_version", shred_version as i64, i64),
+            (
+                "startup_accounts_hash_threads",
+                startup_accounts_hash_threads as i64,
+                i64
+            ),
+            (
+                "steady_state_accounts_hash_threads",
+                accounts_db.num_hash_threads() as i64,
+                i64
+            ),
         );
 
         *start_progress.write().unwrap() = ValidatorStartProgress::Running;
+        if let Some(tpu_ready) = &tpu_ready {
+            tpu_ready.store(true, Ordering::Relaxed);
+        }
+        restart_latency_tracker.record(RestartPhase::ServicesStarted);
         if let Some(json_rpc_service) = &json_rpc_service {
             key_notifiers.write().unwrap().add(
                 KeyUpdaterType::RpcService,
@@ -1771,28 +2333,75 @@ impl Validator {
             );
         }
 
+        let repair_state_snapshot = repair_state_snapshot_fn(
+            config.repair_whitelist.clone(),
+            outstanding_repair_requests.clone(),
+            cluster_slots.clone(),
+        );
+        let ingest_self_test = {
+            let poh_recorder = poh_recorder.read().unwrap();
+            Arc::new(TpuIngestSelfTest::new(
+                tpu.ingest_self_test_tap().clone(),
+                cluster_info.clone(),
+                staked_nodes.clone(),
+                tpu.tpu_connections().clone(),
+                tpu.tpu_vote_connections().clone(),
+                poh_recorder.shared_leader_state(),
+                poh_recorder.ticks_per_slot(),
+            ))
+        };
         *admin_rpc_service_post_init.write().unwrap() = Some(AdminRpcRequestMetadataPostInit {
             bank_forks: bank_forks.clone(),
             cluster_info: cluster_info.clone(),
             vote_account: *vote_account,
             repair_whitelist: config.repair_whitelist.clone(),
+            repair_validators: repair_validators.clone(),
             notifies: key_notifiers,
             repair_socket: Arc::new(node.sockets.repair),
-            outstanding_repair_requests,
+            outstanding_repair_requests: outstanding_repair_requests.clone(),
             cluster_slots,
+            repair_state_snapshot: repair_state_snapshot.clone(),
             node: Some(node_multihoming),
             banking_control_sender,
             snapshot_controller,
             blockstore: blockstore.clone(),
             votor_event_sender,
+            tpu_connections: Some(tpu.tpu_connections().clone()),
+            tpu_forwards_connections: Some(tpu.tpu_forwards_connections().clone()),
+            ingest_self_test: Some(ingest_self_test.clone()),
+            advertised_addresses: advertised_addresses.clone(),
+            retransmit_diagnostics: Some(tvu.retransmit_diagnostics().clone()),
         });
 
         Ok(Self {
             log_config: config.log_config.clone(),
             exit,
             stats_reporter_service,
+            watchdog,
+            watchdog_service,
             gossip_service,
+            gossip_exporter,
+            gossip_export_service,
+            vote_tracker_archive_service,
             serve_repair_service,
+            secondary_catch_up_service,
+            outstanding_repair_requests,
+            repair_state_snapshot,
+            ingest_self_test,
+            block_verification_method: config.block_verification_method.clone(),
+            staked_nodes,
+            optimistic_confirmation_violation_log,
+            ledger_path: ledger_path.to_path_buf(),
+            accounts_db_manifest,
+            slot_gap_classifier,
+            vote_account_monitor,
+            restart_latency_tracker,
+            gossip_vote_landing_tracker,
+            vote_capture,
+            reserved_ingest_sender,
+            slot_resource_accounting,
+            vote_account: *vote_account,
+            leader_schedule_cache,
             json_rpc_service,
             pubsub_service,
             rpc_completed_slots_service,
@@ -1809,6 +2418,13 @@ impl Validator {
             block_creation_loop,
             poh_recorder,
             ip_echo_server,
+            max_slots,
+            block_commitment_cache,
+            advertised_addresses,
+            peer_allowlists: RwLock::new(PeerAllowlists::from(config)),
+            repair_validators,
+            startup_timings,
+            epoch_schedule: genesis_config.epoch_schedule.clone(),
             validator_exit: config.validator_exit.clone(),
             cluster_info,
             bank_forks,
@@ -1816,6 +2432,8 @@ impl Validator {
             geyser_plugin_service,
             _contact_info_notifier: contact_info_notifier,
             blockstore_metric_report_service,
+            compaction_scheduler,
+            slot_resource_accounting_service,
             accounts_background_service,
             xdp_transmitter,
             _tpu_client_next_runtime: tpu_client_next_runtime,
@@ -1880,6 +2498,243 @@ impl Validator {
         self.join();
     }
 
+    /// Returns the current identity of this node, which may have changed
+    /// since startup
+    pub fn identity(&self) -> Pubkey {
+        self.cluster_info.id()
+    }
+
+    /// Returns the vote account this node votes with
+    pub fn vote_account(&self) -> Pubkey {
+        self.vote_account
+    }
+
+    /// Returns the known, repair and gossip validator allowlists in use
+    pub fn peer_allowlists(&self) -> PeerAllowlists {
+        PeerAllowlists {
+            repair_validators: self.repair_validators.read().unwrap().clone(),
+            ..self.peer_allowlists.read().unwrap().clone()
+        }
+    }
+
+    /// Replaces the known validators, `None` trusting all validators. Gossip
+    /// preserves the new set when trimming its table from then on. The repair
+    /// allowlist is configured separately, and the known validators consulted
+    /// while bootstrapping from a snapshot only matter at startup, so neither
+    /// is affected.
+    pub fn set_known_validators(&self, known_validators: Option<HashSet<Pubkey>>) {
+        set_known_validators(&self.cluster_info, &self.peer_allowlists, known_validators)
+    }
+
+    /// Replaces the validators repairs are requested from, `None` repairing
+    /// from all validators. Repair picks up the new set on its next iteration.
+    pub fn set_repair_validators(&self, repair_validators: Option<HashSet<Pubkey>>) {
+        info!("Setting repair validators to {repair_validators:?}");
+        *self.repair_validators.write().unwrap() = repair_validators;
+    }
+
+    /// Returns the time spent in each of the timed phases of startup
+    pub fn startup_timings(&self) -> StartupTimings {
+        self.startup_timings
+    }
+
+    /// Returns the epoch schedule of the cluster, as set in its genesis config
+    pub fn epoch_schedule(&self) -> EpochSchedule {
+        self.epoch_schedule.clone()
+    }
+
+    /// Returns true if a service thread registered with the watchdog has
+    /// stopped making progress
+    pub fn is_degraded(&self) -> bool {
+        self.watchdog.is_degraded()
+    }
+
+    /// Returns how many slots the working bank trails the highest slot seen by
+    /// retransmit, or `None` if retransmit has not observed any slot yet
+    pub fn slots_behind(&self) -> Option<u64> {
+        slots_behind(&self.max_slots, &self.bank_forks)
+    }
+
+    /// Returns the number of repair requests sent by this node which are
+    /// still tracked, including the expired ones which have not been evicted
+    /// yet
+    pub fn outstanding_repair_count(&self) -> usize {
+        outstanding_repair_count(&self.outstanding_repair_requests)
+    }
+
+    /// Returns a consistent view of the repair whitelist, the outstanding
+    /// repair requests and the peers claiming their slots
+    pub fn repair_state_snapshot(&self) -> RepairStateSnapshot {
+        (self.repair_state_snapshot)()
+    }
+
+    /// Measures the rates at which the TPU ingests packets from a loopback
+    /// client with the node identity and one with an unstaked identity,
+    /// blocking until done
+    pub fn run_ingest_self_test(
+        &self,
+        config: &IngestSelfTestConfig,
+    ) -> Result<IngestSelfTestResult, IngestSelfTestError> {
+        self.ingest_self_test.run(config)
+    }
+
+    /// Returns the highest slot rooted by a supermajority of the stake, as
+    /// tracked by the block commitment cache
+    pub fn highest_super_majority_root(&self) -> Slot {
+        highest_super_majority_root(&self.block_commitment_cache)
+    }
+
+    /// Returns the slot and the tick height the PoH recorder is at
+    pub fn poh_status(&self) -> PohStatus {
+        poh_status(&self.poh_recorder)
+    }
+
+    /// Returns the method this node verifies blocks with
+    pub fn block_verification_method(&self) -> BlockVerificationMethod {
+        self.block_verification_method.clone()
+    }
+
+    /// Returns whether a scheduler pool is installed in the bank forks, for
+    /// the blocks to be verified with the unified scheduler
+    pub fn scheduler_pool_installed(&self) -> bool {
+        scheduler_pool_installed(&self.bank_forks)
+    }
+
+    /// Returns the number of staked nodes the TPU servers recognize and their
+    /// stakes
+    pub fn staked_nodes_summary(&self) -> StakedNodesSummary {
+        staked_nodes_summary(&self.staked_nodes)
+    }
+
+    /// Returns the accounts db settings this node is running with
+    pub fn accounts_db_config_summary(&self) -> AccountsDbConfigSummary {
+        let root_bank = self.bank_forks.read().unwrap().root_bank();
+        accounts_db_config_summary(&root_bank.accounts().accounts_db)
+    }
+
+    /// Returns the optimistic confirmation violations detected by this node,
+    /// including those detected before a restart, summarized per epoch
+    pub fn optimistic_confirmation_violation_report(
+        &self,
+    ) -> Vec<OptimisticConfirmationViolationSummary> {
+        self.optimistic_confirmation_violation_log.report()
+    }
+
+    /// Returns, by scheduled leader, the number of slots of `epoch` missing
+    /// from the rooted fork and why this node thinks they are missing
+    pub fn slot_gaps(&self, epoch: Epoch) -> HashMap<Pubkey, SlotGapCounts> {
+        self.slot_gap_classifier.leader_gaps(epoch)
+    }
+
+    /// Returns the changes to the commission and authorities of this node's
+    /// vote account observed since startup, oldest first
+    pub fn vote_account_changes(&self) -> Vec<VoteAccountChange> {
+        self.vote_account_monitor.changes()
+    }
+
+    /// Returns the staked vote accounts whose votes observed on gossip did not
+    /// all land on chain, the most missing votes weighted by stake first
+    pub fn gossip_vote_discrepancies(&self) -> Vec<GossipVoteDiscrepancy> {
+        self.gossip_vote_landing_tracker.discrepancies()
+    }
+
+    /// Writes the votes most recently processed by the vote listener to a file
+    /// under the ledger path and returns its path, or None if the votes are
+    /// not captured
+    pub fn freeze_vote_capture(&self, reason: &str) -> Option<io::Result<PathBuf>> {
+        self.vote_capture
+            .as_ref()
+            .map(|vote_capture| vote_capture.freeze_vote_capture(reason))
+    }
+
+    /// Returns the time taken by each phase of the restart so far, complete
+    /// once this node lands its first vote, or freezes its first bank if
+    /// voting is disabled
+    pub fn restart_latency(&self) -> RestartLatencySummary {
+        self.restart_latency_tracker.summary()
+    }
+
+    /// Returns the sender of the packets scheduled against the compute units
+    /// reserved in each leader slot, or None if no compute units are reserved.
+    /// The packets are signature verified and deduplicated like the TPU packets.
+    pub fn reserved_ingest_sender(&self) -> Option<Sender<PacketBatch>> {
+        self.reserved_ingest_sender.clone()
+    }
+
+    /// Returns the resources used by each of the most recently rooted slots
+    /// replayed by this node, oldest first
+    pub fn slot_resource_usage(&self) -> Vec<SlotResourceUsage> {
+        self.slot_resource_accounting.rooted_slots_usage()
+    }
+
+    /// Returns the number of entries waiting to be notified to the geyser
+    /// plugins, or None if no plugin is notified of entries
+    pub fn entry_notifier_backlog(&self) -> Option<usize> {
+        self.entry_notifier_service
+            .as_ref()
+            .map(EntryNotifierService::backlog)
+    }
+
+    /// Compacts the blockstore entries of the slots within \[`start_slot`,
+    /// `end_slot`\], blocking until done. Slots above the root are still being
+    /// written to and are refused.
+    pub fn compact_blockstore(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<(), ValidatorError> {
+        compact_blockstore(&self.blockstore, start_slot, end_slot)
+    }
+
+    /// Returns true if this node's current identity is the scheduled leader
+    /// for `slot`, false if it is not or the leader is not known yet
+    pub fn is_leader_for_slot(&self, slot: Slot) -> bool {
+        is_leader_for_slot(
+            &self.leader_schedule_cache,
+            &self.bank_forks,
+            &self.cluster_info.id(),
+            slot,
+        )
+    }
+
+    /// Returns this node's retransmit children and parent in the turbine tree
+    /// of the shred, along with the inputs the tree was computed from
+    pub fn turbine_tree(
+        &self,
+        shred: ShredId,
+    ) -> Result<TurbineTreeDiagnostics, TurbineDiagnosticsError> {
+        self.tvu.retransmit_diagnostics().get_turbine_tree(shred)
+    }
+
+    /// Exports the contact infos currently held in gossip right away and
+    /// returns the path of the written file. Requires `gossip_export` to be
+    /// configured.
+    pub fn export_gossip(&self) -> Result<PathBuf, ValidatorError> {
+        let Some(gossip_exporter) = &self.gossip_exporter else {
+            return Err(ValidatorError::Other(
+                "gossip export is not enabled".to_string(),
+            ));
+        };
+        gossip_exporter
+            .export()
+            .map_err(|err| ValidatorError::Other(format!("failed to export gossip: {err}")))
+    }
+
+    /// Pushes this node's contact info to gossip right away with a fresh
+    /// wallclock, so that peers pick up any changes sooner.
+    pub fn refresh_contact_info(&self) {
+        self.cluster_info.refresh_my_gossip_contact_info();
+    }
+
+    /// Re-advertises this node's sockets on `new_ip`, or on the rediscovered
+    /// public IP address, see [`AdvertisedAddresses::refresh`]
+    pub fn refresh_advertised_addresses(
+        &self,
+        new_ip: Option<IpAddr>,
+    ) -> Result<IpAddr, AdvertisedAddressesError> {
+        self.advertised_addresses.refresh(new_ip)
+    }
+
     fn print_node_info(node: &Node) {
         info!("{:?}", node.info);
         info!(
@@ -1966,15 +2821,30 @@ impl Validator {
             .expect("snapshot_packager_service");
 
         self.gossip_service.join().expect("gossip_service");
+        if let Some(gossip_export_service) = self.gossip_export_service {
+            gossip_export_service.join().expect("gossip_export_service");
+        }
         self.serve_repair_service
             .join()
             .expect("serve_repair_service");
+        if let Some(secondary_catch_up_service) = self.secondary_catch_up_service {
+            secondary_catch_up_service
+                .join()
+                .expect("secondary_catch_up_service");
+        }
         self.stats_reporter_service
             .join()
             .expect("stats_reporter_service");
+        self.watchdog_service.join().expect("watchdog_service");
         self.blockstore_metric_report_service
             .join()
             .expect("ledger_metric_report_service");
+        if let Some(compaction_scheduler) = self.compaction_scheduler {
+            compaction_scheduler.join().expect("compaction_scheduler");
+        }
+        self.slot_resource_accounting_service
+            .join()
+            .expect("slot_resource_accounting_service");
         self.accounts_background_service
             .join()
             .expect("accounts_background_service");
@@ -1983,6 +2853,16 @@ impl Validator {
         }
         self.tpu.join().expect("tpu");
         self.tvu.join().expect("tvu");
+        // Finishes the archive file once the vote listener is gone
+        if let Some(vote_tracker_archive_service) = self.vote_tracker_archive_service {
+            vote_tracker_archive_service
+                .join()
+                .expect("vote_tracker_archive_service");
+        }
+        // Waits for the captures frozen by the vote listener to hit the disk
+        if let Some(vote_capture) = &self.vote_capture {
+            vote_capture.join().expect("vote_capture");
+        }
         if let Some(completed_data_sets_service) = self.completed_data_sets_service {
             completed_data_sets_service
                 .join()
@@ -1995,41 +2875,158 @@ impl Validator {
         if let Some(geyser_plugin_service) = self.geyser_plugin_service {
             geyser_plugin_service.join().expect("geyser_plugin_service");
         }
+
+        if let Err(err) = self.accounts_db_manifest.write(&self.ledger_path) {
+            warn!("Failed to write the accounts-db manifest: {err}");
+        }
     }
 }
 
-fn active_vote_account_exists_in_bank(bank: &Bank, vote_account: &Pubkey) -> bool {
-    if let Some(account) = &bank.get_account(vote_account) {
-        if let Ok(vote_state) = VoteStateV4::deserialize(account.data(), vote_account) {
-            return !vote_state.votes.is_empty();
-        }
+fn slots_behind(max_slots: &MaxSlots, bank_forks: &RwLock<BankForks>) -> Option<u64> {
+    let max_retransmit_slot = max_slots.retransmit.load(Ordering::Relaxed);
+    if max_retransmit_slot == 0 {
+        return None;
     }
-    false
+    let working_slot = bank_forks.read().unwrap().working_bank().slot();
+    Some(max_retransmit_slot.saturating_sub(working_slot))
 }
 
-/// Should we require that a vote history file is present
-pub fn should_require_vote_history_file(
-    bank: &Bank,
-    vote_account: &Pubkey,
-    identity: &Pubkey,
-) -> bool {
-    let Some(genesis_certificate) = bank.get_alpenglow_genesis_certificate() else {
-        // Vote history is only used when Alpenglow is active
-        return false;
-    };
+fn outstanding_repair_count(
+    outstanding_repair_requests: &RwLock<OutstandingShredRepairs>,
+) -> usize {
+    outstanding_repair_requests.read().unwrap().len()
+}
 
-    let Some(Ok(vote_state)) = bank
-        .get_account(vote_account)
-        .map(|acct| acct.deserialize_data())
-    else {
-        // Must have a vote account
-        return false;
-    };
+fn highest_super_majority_root(block_commitment_cache: &RwLock<BlockCommitmentCache>) -> Slot {
+    block_commitment_cache
+        .read()
+        .unwrap()
+        .highest_super_majority_root()
+}
 
-    let Ok(vote_state_handler) = VoteStateHandler::try_new_from_vote_state_versions(vote_state)
-    else {
-        return false;
-    };
+fn scheduler_pool_installed(bank_forks: &RwLock<BankForks>) -> bool {
+    bank_forks.read().unwrap().has_scheduler_pool()
+}
+
+fn poh_status(poh_recorder: &RwLock<PohRecorder>) -> PohStatus {
+    let poh_recorder = poh_recorder.read().unwrap();
+    PohStatus {
+        slot: poh_recorder.bank().map(|bank| bank.slot()),
+        tick_height: poh_recorder.tick_height(),
+    }
+}
+
+fn staked_nodes_summary(staked_nodes: &RwLock<StakedNodes>) -> StakedNodesSummary {
+    let staked_nodes = staked_nodes.read().unwrap();
+    let (count, max_stake, min_stake) = staked_nodes.staked_nodes().fold(
+        (0, 0, u64::MAX),
+        |(count, max_stake, min_stake), (_, stake)| {
+            (count + 1, max_stake.max(stake), min_stake.min(stake))
+        },
+    );
+    StakedNodesSummary {
+        count,
+        total_stake: staked_nodes.total_stake(),
+        max_stake,
+        min_stake: if count == 0 { 0 } else { min_stake },
+    }
+}
+
+fn accounts_db_config_summary(accounts_db: &AccountsDb) -> AccountsDbConfigSummary {
+    AccountsDbConfigSummary {
+        account_paths: accounts_db.paths.clone(),
+        shrink_ratio: accounts_db.shrink_ratio(),
+        read_cache_limit_bytes: accounts_db.read_cache_limit_bytes(),
+        write_cache_limit_bytes: accounts_db.write_cache_limit_bytes(),
+        ancient_append_vec_offset: accounts_db.ancient_append_vec_offset,
+        ancient_storage_ideal_size: accounts_db.ancient_storage_ideal_size,
+        max_ancient_storages: accounts_db.max_ancient_storages,
+        num_index_bins: accounts_db.accounts_index.bins(),
+        num_foreground_threads: accounts_db.thread_pool_foreground.current_num_threads(),
+        num_background_threads: accounts_db.thread_pool_background.current_num_threads(),
+        skip_initial_hash_calc: accounts_db.skip_initial_hash_calc,
+    }
+}
+
+fn is_leader_for_slot(
+    leader_schedule_cache: &LeaderScheduleCache,
+    bank_forks: &RwLock<BankForks>,
+    identity: &Pubkey,
+    slot: Slot,
+) -> bool {
+    let root_bank = bank_forks.read().unwrap().root_bank();
+    leader_schedule_cache
+        .slot_leader_at(slot, Some(&root_bank))
+        .is_some_and(|leader| leader.id == *identity)
+}
+
+fn set_known_validators(
+    cluster_info: &ClusterInfo,
+    peer_allowlists: &RwLock<PeerAllowlists>,
+    known_validators: Option<HashSet<Pubkey>>,
+) {
+    info!("Setting known validators to {known_validators:?}");
+    cluster_info.update_trim_keep_pubkeys(known_validators.clone());
+    peer_allowlists.write().unwrap().known_validators = known_validators;
+}
+
+fn compact_blockstore(
+    blockstore: &Blockstore,
+    start_slot: Slot,
+    end_slot: Slot,
+) -> Result<(), ValidatorError> {
+    if start_slot > end_slot {
+        return Err(ValidatorError::Other(format!(
+            "invalid slot range to compact: {start_slot} to {end_slot}"
+        )));
+    }
+    let root = blockstore.max_root();
+    if end_slot > root {
+        return Err(ValidatorError::Other(format!(
+            "cannot compact slots above the root {root}: {start_slot} to {end_slot}"
+        )));
+    }
+
+    info!("Compacting slots {start_slot} to {end_slot} in blockstore");
+    let mut timer = Measure::start("blockstore compaction");
+    blockstore.compact_slots(start_slot, end_slot);
+    timer.stop();
+    info!("Compacting slots done. {timer}");
+    Ok(())
+}
+
+fn active_vote_account_exists_in_bank(bank: &Bank, vote_account: &Pubkey) -> bool {
+    if let Some(account) = &bank.get_account(vote_account) {
+        if let Ok(vote_state) = VoteStateV4::deserialize(account.data(), vote_account) {
+            return !vote_state.votes.is_empty();
+        }
+    }
+    false
+}
+
+/// Should we require that a vote history file is present
+pub fn should_require_vote_history_file(
+    bank: &Bank,
+    vote_account: &Pubkey,
+    identity: &Pubkey,
+) -> bool {
+    let Some(genesis_certificate) = bank.get_alpenglow_genesis_certificate() else {
+        // Vote history is only used when Alpenglow is active
+        return false;
+    };
+
+    let Some(Ok(vote_state)) = bank
+        .get_account(vote_account)
+        .map(|acct| acct.deserialize_data())
+    else {
+        // Must have a vote account
+        return false;
+    };
+
+    let Ok(vote_state_handler) = VoteStateHandler::try_new_from_vote_state_versions(vote_state)
+    else {
+        return false;
+    };
 
     if vote_state_handler.node_pubkey() != identity {
         // We are starting up or set-identity with a dummy keypair
@@ -2081,8 +3078,19 @@ fn restore_vote_history(
     }
 }
 
-fn check_poh_speed(bank: &Bank, maybe_hash_samples: Option<u64>) -> Result<(), ValidatorError> {
+fn check_poh_speed(
+    bank: &Bank,
+    maybe_hash_samples: Option<u64>,
+    require_hashes_per_tick: bool,
+) -> Result<(), ValidatorError> {
     let Some(hashes_per_tick) = bank.hashes_per_tick() else {
+        if require_hashes_per_tick {
+            return Err(ValidatorError::Other(
+                "Unable to read hashes per tick from Bank, which is required for the PoH speed \
+                 check"
+                    .to_string(),
+            ));
+        }
         warn!("Unable to read hashes per tick from Bank, skipping PoH speed check");
         return Ok(());
     };
@@ -2112,6 +3120,25 @@ fn check_poh_speed(bank: &Bank, maybe_hash_samples: Option<u64>) -> Result<(), V
     Ok(())
 }
 
+/// Purges the incomplete and old bank snapshots with the given functions,
+/// unless the config asks to preserve them
+fn purge_bank_snapshots_at_startup(
+    config: &ValidatorConfig,
+    purge_incomplete_bank_snapshots: impl FnOnce(&Path),
+    purge_old_bank_snapshots: impl FnOnce(&Path),
+) {
+    let bank_snapshots_dir = &config.snapshot_config.bank_snapshots_dir;
+    if config.preserve_bank_snapshots_at_startup {
+        warn!(
+            "Preserving the incomplete and old bank snapshots in {}",
+            bank_snapshots_dir.display()
+        );
+        return;
+    }
+    purge_incomplete_bank_snapshots(bank_snapshots_dir);
+    purge_old_bank_snapshots(bank_snapshots_dir);
+}
+
 fn maybe_cluster_restart_with_hard_fork(config: &ValidatorConfig, root_slot: Slot) -> Option<Slot> {
     // detect cluster restart (hard fork) indirectly via wait_for_supermajority...
     if let Some(wait_slot_for_supermajority) = config.wait_for_supermajority {
@@ -2295,10 +3322,21 @@ fn load_genesis(
 
     if let Some(expected_genesis_hash) = config.expected_genesis_hash {
         if genesis_hash != expected_genesis_hash {
-            return Err(ValidatorError::GenesisHashMismatch(
-                genesis_hash,
-                expected_genesis_hash,
-            ));
+            match config.genesis_hash_mismatch_policy {
+                GenesisHashMismatchPolicy::Abort => {
+                    return Err(ValidatorError::GenesisHashMismatch(
+                        genesis_hash,
+                        expected_genesis_hash,
+                    ));
+                }
+                GenesisHashMismatchPolicy::WarnAndContinue => {
+                    error!(
+                        "Genesis hash mismatch: actual={genesis_hash}, \
+                         expected={expected_genesis_hash}. Continuing startup because the genesis \
+                         hash mismatch policy is WarnAndContinue"
+                    );
+                }
+            }
         }
     }
 
@@ -2312,6 +3350,8 @@ fn load_blockstore(
     genesis_config: &GenesisConfig,
     exit: Arc<AtomicBool>,
     start_progress: &Arc<RwLock<ValidatorStartProgress>>,
+    restart_latency_tracker: &RestartLatencyTracker,
+    accounts_db_migration: Option<AccountsDbMigration>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
     transaction_notifier: Option<TransactionNotifierArc>,
     entry_notifier: Option<EntryNotifierArc>,
@@ -2331,13 +3371,14 @@ fn load_blockstore(
         DroppedSlotsReceiver,
         Option<EntryNotifierService>,
     ),
-    String,
+    ValidatorError,
 > {
     info!("loading ledger from {ledger_path:?}...");
     *start_progress.write().unwrap() = ValidatorStartProgress::LoadingLedger;
 
     let mut process_options = blockstore_processor::ProcessOptions {
-        run_verification: config.run_verification,
+        run_poh_verification: config.run_poh_verification,
+        run_sig_verification: config.run_sig_verification,
         halt_at_slot: None,
         new_hard_forks: config.new_hard_forks.clone(),
         debug_keys: config.debug_keys.clone(),
@@ -2348,27 +3389,60 @@ fn load_blockstore(
         use_snapshot_archives_at_startup: config.use_snapshot_archives_at_startup,
         ..blockstore_processor::ProcessOptions::default()
     };
+    if let Some(accounts_db_migration) = accounts_db_migration {
+        warn!("Migrating the accounts state: {accounts_db_migration:?}");
+        *start_progress.write().unwrap() = ValidatorStartProgress::MigratingAccountsDb;
+        match accounts_db_migration {
+            AccountsDbMigration::LoadFromSnapshotArchives => {
+                process_options.use_snapshot_archives_at_startup =
+                    UseSnapshotArchivesAtStartup::Always;
+            }
+        }
+    }
+
+    let unpack_progress = Arc::new(UnpackProgress::default());
+    let num_accounts_indexed = Arc::new(AtomicU64::new(0));
+    process_options.accounts_db_config.num_accounts_indexed = Some(num_accounts_indexed.clone());
+    let snapshot_config = SnapshotConfig {
+        unpack_progress: Some(unpack_progress.clone()),
+        ..config.snapshot_config.clone()
+    };
 
     let (blockstore, bank_from_snapshot_opt) = thread::scope(|scope| {
         let load_snapshot_handle = thread::Builder::new()
             .name("solBnkFrkSnap".into())
             .spawn_scoped(scope, || {
-                bank_forks_utils::try_load_bank_forks_from_snapshot(
+                let bank_from_snapshot_result = bank_forks_utils::try_load_bank_forks_from_snapshot(
                     genesis_config,
                     &config.account_paths,
-                    &config.snapshot_config,
+                    &snapshot_config,
                     &process_options,
                     accounts_update_notifier.clone(),
                     exit.clone(),
-                )
+                );
+                if matches!(bank_from_snapshot_result, Ok(Some(_))) {
+                    restart_latency_tracker.record(RestartPhase::SnapshotLoaded);
+                }
+                bank_from_snapshot_result
             })
             .expect("should spawn thread");
         let blockstore =
             Blockstore::open_with_options(ledger_path, config.blockstore_options.clone())
-                .map_err(|err| format!("Failed to open Blockstore: {err:?}"))?;
+                .map_err(ValidatorError::Blockstore)?;
+        restart_latency_tracker.record(RestartPhase::BlockstoreOpened);
+
+        let prior_start_progress = *start_progress.read().unwrap();
+        report_snapshot_load_progress(
+            &unpack_progress,
+            &num_accounts_indexed,
+            Duration::from_millis(100),
+            || load_snapshot_handle.is_finished(),
+            |progress| *start_progress.write().unwrap() = progress,
+        );
+        *start_progress.write().unwrap() = prior_start_progress;
         let bank_from_snapshot_result = load_snapshot_handle.join().expect("join thread");
 
-        Ok::<_, String>((Arc::new(blockstore), bank_from_snapshot_result.transpose()))
+        Ok::<_, ValidatorError>((Arc::new(blockstore), bank_from_snapshot_result.transpose()))
     })?;
 
     // following boot sequence (esp BankForks) could set root. so stash the original value
@@ -2396,6 +3470,9 @@ fn load_blockstore(
     let entry_notifier_service = entry_notifier
         .map(|entry_notifier| EntryNotifierService::new(entry_notifier, exit.clone()));
 
+    let snapshot_stream_notifier = accounts_update_notifier
+        .clone()
+        .filter(|_| matches!(bank_from_snapshot_opt, Some(Ok(_))));
     let (bank_forks, starting_snapshot_hashes) = bank_from_snapshot_opt
         .unwrap_or_else(|| {
             // Clean run from genesis — must not use any existing state from previous runs.
@@ -2418,7 +3495,15 @@ fn load_blockstore(
                 exit.clone(),
             )
         })
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| ValidatorError::Other(err.to_string()))?;
+
+    if let Some(notifier) = snapshot_stream_notifier {
+        let root_bank = bank_forks.read().unwrap().root_bank();
+        notifier.start_snapshot_stream(SnapshotStreamSession::new(
+            &root_bank.accounts().accounts_db,
+            root_bank.slot(),
+        ));
+    }
 
     let mut leader_schedule_cache =
         LeaderScheduleCache::new_from_bank(&bank_forks.read().unwrap().root_bank());
@@ -2454,6 +3539,44 @@ fn load_blockstore(
     ))
 }
 
+/// Returns the start progress to report while a snapshot is being loaded, or
+/// `None` if loading has not yet begun unpacking an archive or indexing accounts
+fn snapshot_load_progress(
+    unpack_progress: &UnpackProgress,
+    num_accounts_indexed: &AtomicU64,
+) -> Option<ValidatorStartProgress> {
+    let accounts_indexed = num_accounts_indexed.load(Ordering::Relaxed);
+    if accounts_indexed > 0 {
+        return Some(ValidatorStartProgress::BuildingAccountsIndex { accounts_indexed });
+    }
+    let total_bytes = unpack_progress.total_bytes.load(Ordering::Relaxed);
+    (total_bytes > 0).then(|| ValidatorStartProgress::UnpackingSnapshot {
+        bytes_unpacked: unpack_progress.bytes_unpacked.load(Ordering::Relaxed),
+        total_bytes,
+    })
+}
+
+/// Reports the start progress of loading a snapshot every `interval` until
+/// `is_finished`, and once more after it is, so the last progress is reported
+fn report_snapshot_load_progress(
+    unpack_progress: &UnpackProgress,
+    num_accounts_indexed: &AtomicU64,
+    interval: Duration,
+    is_finished: impl Fn() -> bool,
+    mut report: impl FnMut(ValidatorStartProgress),
+) {
+    loop {
+        let finished = is_finished();
+        if let Some(progress) = snapshot_load_progress(unpack_progress, num_accounts_indexed) {
+            report(progress);
+        }
+        if finished {
+            break;
+        }
+        thread::sleep(interval);
+    }
+}
+
 pub struct ProcessBlockStore<'a> {
     id: &'a Pubkey,
     vote_account: &'a Pubkey,
@@ -2468,6 +3591,7 @@ pub struct ProcessBlockStore<'a> {
     blockstore_root_scan: Option<BlockstoreRootScan>,
     snapshot_controller: &'a SnapshotController,
     config: &'a ValidatorConfig,
+    restart_latency_tracker: &'a RestartLatencyTracker,
     tower: Option<Tower>,
     vote_history: Option<VoteHistory>,
 }
@@ -2488,6 +3612,7 @@ impl<'a> ProcessBlockStore<'a> {
         blockstore_root_scan: BlockstoreRootScan,
         snapshot_controller: &'a SnapshotController,
         config: &'a ValidatorConfig,
+        restart_latency_tracker: &'a RestartLatencyTracker,
     ) -> Self {
         Self {
             id,
@@ -2503,6 +3628,7 @@ impl<'a> ProcessBlockStore<'a> {
             blockstore_root_scan: Some(blockstore_root_scan),
             snapshot_controller,
             config,
+            restart_latency_tracker,
             tower: None,
             vote_history: None,
         }
@@ -2551,6 +3677,10 @@ impl<'a> ProcessBlockStore<'a> {
             format!("Failed to load ledger: {err:?}")
         })?;
         exit.store(true, Ordering::Relaxed);
+        self.restart_latency_tracker
+            .set_restart_slot(self.bank_forks.read().unwrap().highest_slot());
+        self.restart_latency_tracker
+            .record(RestartPhase::LedgerProcessed);
 
         if let Some(blockstore_root_scan) = self.blockstore_root_scan.take() {
             blockstore_root_scan.join();
@@ -2690,6 +3820,14 @@ fn maybe_warp_slot(
     Ok(())
 }
 
+/// Returns whether the CompletedDataSetsService runs, which by default it
+/// does only along with the full RPC API
+fn should_run_completed_data_sets_service(config: &ValidatorConfig) -> bool {
+    config
+        .enable_completed_data_sets
+        .unwrap_or(config.rpc_addrs.is_some() && config.rpc_config.full_api)
+}
+
 /// Returns the starting slot at which the blockstore should be scanned for
 /// shreds with an incorrect shred version, or None if the check is unnecessary
 fn should_cleanup_blockstore_incorrect_shred_versions(
@@ -2882,6 +4020,14 @@ fn initialize_rpc_transaction_history_services(
 
 #[derive(Error, Debug)]
 pub enum ValidatorError {
+    #[error("accounts-db option {option} changed from {previous} to {current}: {remedy}")]
+    AccountsDbOptionChanged {
+        option: &'static str,
+        previous: String,
+        current: String,
+        remedy: String,
+    },
+
     #[error("bank hash mismatch: actual={0}, expected={1}")]
     BankHashMismatch(Hash, Hash),
 
@@ -2914,10 +4060,48 @@ pub enum ValidatorError {
     #[error("shred version mismatch: actual {actual}, expected {expected}")]
     ShredVersionMismatch { actual: u16, expected: u16 },
 
+    #[error("TPU configuration cannot meet its limits: {0}")]
+    TpuConfig(String),
+
     #[error(transparent)]
     TraceError(#[from] TraceError),
 }
 
+fn check_tpu_config(
+    tpu_config: &ValidatorTpuConfig,
+    config: &ValidatorConfig,
+) -> Result<(), ValidatorError> {
+    let has_staked_nodes_overrides = !config.staked_nodes_overrides.read().unwrap().is_empty();
+    let analysis = analyze_tpu_config(
+        tpu_config,
+        has_staked_nodes_overrides,
+        DEFAULT_TPU_DRAIN_WINDOW,
+    );
+    for capacity in &analysis.capacities {
+        info!(
+            "{} QUIC server ingest capacity: {capacity:?}",
+            capacity.server
+        );
+    }
+    if analysis.issues.is_empty() {
+        return Ok(());
+    }
+    if config.strict_tpu_config {
+        return Err(ValidatorError::TpuConfig(
+            analysis
+                .issues
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+        ));
+    }
+    for issue in &analysis.issues {
+        warn!("{issue}");
+    }
+    Ok(())
+}
+
 // Return if the validator waited on other nodes to start. In this case
 // it should not wait for one of it's votes to land to produce blocks
 // because if the whole network is waiting, then it will stall.
@@ -2955,10 +4139,24 @@ fn wait_for_supermajority(
 
             if let Some(expected_bank_hash) = config.expected_bank_hash {
                 if bank.hash() != expected_bank_hash {
-                    return Err(ValidatorError::BankHashMismatch(
-                        bank.hash(),
-                        expected_bank_hash,
-                    ));
+                    match config.bank_hash_mismatch_policy {
+                        BankHashMismatchPolicy::Abort => {
+                            return Err(ValidatorError::BankHashMismatch(
+                                bank.hash(),
+                                expected_bank_hash,
+                            ));
+                        }
+                        BankHashMismatchPolicy::WarnAndContinue => {
+                            error!(
+                                "Bank hash mismatch at slot {}: actual={}, expected={}. \
+                                 Continuing startup because the bank hash mismatch policy is \
+                                 WarnAndContinue",
+                                bank.slot(),
+                                bank.hash(),
+                                expected_bank_hash,
+                            );
+                        }
+                    }
                 }
             }
 
@@ -3000,78 +4198,127 @@ fn wait_for_supermajority(
     }
 }
 
+/// Breakdown of the activated stake by whether the respective node is online
+#[derive(Debug, Default, PartialEq)]
+struct GossipStakeReport {
+    online_stake: u64,
+    offline_stake: u64,
+    total_activated_stake: u64,
+    /// Part of `online_stake` whose node does not share our shred version
+    wrong_shred_version_stake: u64,
+    /// Offline nodes and their stake, sorted by reverse stake weight
+    offline_nodes: Vec<(u64, Pubkey)>,
+}
+
+impl GossipStakeReport {
+    fn percent(&self, stake: u64) -> f64 {
+        (stake as f64 / self.total_activated_stake as f64) * 100.
+    }
+
+    fn online_stake_percent(&self) -> u64 {
+        self.percent(self.online_stake) as u64
+    }
+}
+
+// Get the activated stake percentage of the nodes in `online_pubkeys`, given
+// the activated stake and node pubkey of each vote account
+fn stake_percent_online(
+    vote_accounts: impl IntoIterator<Item = (u64, Pubkey)>,
+    online_pubkeys: &HashSet<Pubkey>,
+    same_shred_pubkeys: &HashSet<Pubkey>,
+) -> GossipStakeReport {
+    let mut report = GossipStakeReport::default();
+    for (activated_stake, node_pubkey) in vote_accounts {
+        report.total_activated_stake += activated_stake;
+
+        if activated_stake == 0 {
+            continue;
+        }
+
+        if online_pubkeys.contains(&node_pubkey) {
+            trace!("observed {node_pubkey} in gossip, (activated_stake={activated_stake})");
+            report.online_stake += activated_stake;
+            if !same_shred_pubkeys.contains(&node_pubkey) {
+                report.wrong_shred_version_stake += activated_stake;
+            }
+        } else {
+            report.offline_stake += activated_stake;
+            report.offline_nodes.push((activated_stake, node_pubkey));
+        }
+    }
+    report.offline_nodes.sort_by_key(|a| cmp::Reverse(a.0)); // sort by reverse stake weight
+    report
+}
+
 // Get the activated stake percentage (based on the provided bank) that is visible in gossip
 fn get_stake_percent_in_gossip(bank: &Bank, cluster_info: &ClusterInfo, log: bool) -> u64 {
-    let mut online_stake = 0;
-    let mut offline_stake = 0;
-    let mut offline_nodes = vec![];
-
-    let mut total_activated_stake = 0;
     let now = timestamp();
+    let my_id = cluster_info.id();
+    let my_shred_version = cluster_info.my_shred_version();
     // Nodes contact infos are saved to disk and restored on validator startup.
     // Staked nodes entries will not expire until an epoch after. So it
     // is necessary here to filter for recent entries to establish liveness.
-    let peers: HashMap<_, _> = cluster_info
-        .tvu_peers(ContactInfo::clone)
+    let peers: Vec<_> = cluster_info
+        .tvu_peers(|node| (*node.pubkey(), node.wallclock(), node.shred_version()))
         .into_iter()
-        .filter(|node| {
-            let age = now.saturating_sub(node.wallclock());
+        .filter(|(_, wallclock, _)| {
+            let age = now.saturating_sub(*wallclock);
             // Contact infos are refreshed twice during this period.
             age < CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS
         })
-        .map(|node| (*node.pubkey(), node))
         .collect();
-    let my_id = cluster_info.id();
-
-    for (activated_stake, vote_account) in bank.vote_accounts().values() {
-        let activated_stake = *activated_stake;
-        total_activated_stake += activated_stake;
+    // This node is online
+    let online_pubkeys: HashSet<_> = peers
+        .iter()
+        .map(|(pubkey, _, _)| *pubkey)
+        .chain(std::iter::once(my_id))
+        .collect();
+    let same_shred_pubkeys: HashSet<_> = peers
+        .iter()
+        .filter(|(_, _, shred_version)| *shred_version == my_shred_version)
+        .map(|(pubkey, _, _)| *pubkey)
+        .chain(std::iter::once(my_id))
+        .collect();
 
-        if activated_stake == 0 {
-            continue;
-        }
-        let vote_state_node_pubkey = *vote_account.node_pubkey();
+    let report = stake_percent_online(
+        bank.vote_accounts()
+            .values()
+            .map(|(activated_stake, vote_account)| (*activated_stake, *vote_account.node_pubkey())),
+        &online_pubkeys,
+        &same_shred_pubkeys,
+    );
 
-        if peers.contains_key(&vote_state_node_pubkey) {
-            trace!(
-                "observed {vote_state_node_pubkey} in gossip, (activated_stake={activated_stake})"
+    if log {
+        info!(
+            "{:.3}% of active stake visible in gossip",
+            report.percent(report.online_stake)
+        );
+        if report.wrong_shred_version_stake > 0 {
+            warn!(
+                "{:.3}% of active stake visible in gossip has a shred version other than {}",
+                report.percent(report.wrong_shred_version_stake),
+                my_shred_version,
             );
-            online_stake += activated_stake;
-        } else if vote_state_node_pubkey == my_id {
-            online_stake += activated_stake; // This node is online
-        } else {
-            offline_stake += activated_stake;
-            offline_nodes.push((activated_stake, vote_state_node_pubkey));
         }
-    }
-
-    let online_stake_percentage = (online_stake as f64 / total_activated_stake as f64) * 100.;
-    if log {
-        info!("{online_stake_percentage:.3}% of active stake visible in gossip");
 
-        if !offline_nodes.is_empty() {
+        if !report.offline_nodes.is_empty() {
             info!(
                 "{:.3}% of active stake is not visible in gossip",
-                (offline_stake as f64 / total_activated_stake as f64) * 100.
+                report.percent(report.offline_stake)
             );
-            offline_nodes.sort_by_key(|a| cmp::Reverse(a.0)); // sort by reverse stake weight
-            for (stake, identity) in offline_nodes {
-                info!(
-                    "    {:.3}% - {}",
-                    (stake as f64 / total_activated_stake as f64) * 100.,
-                    identity
-                );
+            for (stake, identity) in &report.offline_nodes {
+                info!("    {:.3}% - {}", report.percent(*stake), identity);
             }
         }
         datapoint_info!(
             "wfsm_gossip",
-            ("online_stake", online_stake, i64),
-            ("offline_stake", offline_stake, i64),
-            ("total_activated_stake", total_activated_stake, i64),
+            ("online_stake", report.online_stake, i64),
+            ("offline_stake", report.offline_stake, i64),
+            ("total_activated_stake", report.total_activated_stake, i64),
         );
     }
 
-    online_stake_percentage as u64
+    report.online_stake_percent()
 }
 
 fn validate_account_paths(config: &ValidatorConfig) -> std::io::Result<()> {
@@ -3114,11 +4361,18 @@ pub fn is_snapshot_config_valid(snapshot_config: &SnapshotConfig) -> bool {
 mod tests {
     use {
         super::*,
+        crate::{
+            repair::serve_repair::ShredRepairType, tpu_ingest_self_test::IngestSelfTestServer,
+        },
         crossbeam_channel::{RecvTimeoutError, bounded},
+        solana_accounts_db::{
+            accounts_db::DEFAULT_MAX_ANCIENT_STORAGES,
+            accounts_index::{ACCOUNTS_INDEX_CONFIG_FOR_TESTING, AccountsIndexConfig},
+        },
         solana_entry::entry,
         solana_genesis_config::create_genesis_config,
         solana_gossip::contact_info::ContactInfo,
-        solana_leader_schedule::SlotLeader,
+        solana_leader_schedule::{LeaderSchedule, SlotLeader},
         solana_ledger::{
             blockstore, create_new_tmp_ledger, genesis_utils::create_genesis_config_with_leader,
             get_tmp_ledger_path_auto_delete,
@@ -3126,7 +4380,14 @@ mod tests {
         solana_poh_config::PohConfig,
         solana_sha256_hasher::hash,
         solana_vote_program::vote_state::{LandedVote, Lockout, VoteStateVersions},
-        std::{fs::remove_dir_all, num::NonZeroU64, thread, time::Duration},
+        std::{
+            cell::RefCell,
+            fs::remove_dir_all,
+            num::{NonZeroU64, NonZeroUsize},
+            thread,
+            time::Duration,
+        },
+        tempfile::TempDir,
     };
 
     #[test]
@@ -3237,18 +4498,25 @@ mod tests {
         let leader_node = Node::new_localhost_with_pubkey(&leader_keypair.pubkey());
 
         let validator_keypair = Keypair::new();
-        let validator_node = Node::new_localhost_with_pubkey(&validator_keypair.pubkey());
+        let validator_identity = validator_keypair.pubkey();
+        let validator_node = Node::new_localhost_with_pubkey(&validator_identity);
         let genesis_config =
             create_genesis_config_with_leader(10_000, &leader_keypair.pubkey(), 1000)
                 .genesis_config;
         let (validator_ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_config);
 
         let voting_keypair = Arc::new(Keypair::new());
+        let vote_account = voting_keypair.pubkey();
         let config = ValidatorConfig {
             rpc_addrs: Some((
                 validator_node.info.rpc().unwrap(),
                 validator_node.info.rpc_pubsub().unwrap(),
             )),
+            accounts_db_config: AccountsDbConfig {
+                num_startup_hash_threads: Some(NonZeroUsize::new(2).unwrap()),
+                num_steady_state_hash_threads: Some(NonZeroUsize::MIN),
+                ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+            },
             ..ValidatorConfig::default_for_test()
         };
         let start_progress = Arc::new(RwLock::new(ValidatorStartProgress::default()));
@@ -3256,7 +4524,7 @@ mod tests {
             validator_node,
             Arc::new(validator_keypair),
             &validator_ledger_path,
-            &voting_keypair.pubkey(),
+            &vote_account,
             Arc::new(RwLock::new(vec![voting_keypair])),
             vec![leader_node.info],
             &config,
@@ -3272,69 +4540,296 @@ mod tests {
             *start_progress.read().unwrap(),
             ValidatorStartProgress::Running
         );
+        assert_eq!(validator.identity(), validator_identity);
+        assert_eq!(validator.vote_account(), vote_account);
+        assert_eq!(
+            validator.block_verification_method(),
+            BlockVerificationMethod::UnifiedScheduler
+        );
+        assert!(validator.scheduler_pool_installed());
+        // Once running, the accounts hash is calculated with the steady state number of threads
+        assert_eq!(
+            validator
+                .bank_forks
+                .read()
+                .unwrap()
+                .root_bank()
+                .rc
+                .accounts
+                .accounts_db
+                .num_hash_threads(),
+            1
+        );
         validator.close();
         remove_dir_all(validator_ledger_path).unwrap();
     }
 
     #[test]
-    fn test_should_cleanup_blockstore_incorrect_shred_versions() {
+    fn test_run_ingest_self_test() {
         agave_logger::setup();
+        let leader_keypair = Keypair::new();
+        let leader_node = Node::new_localhost_with_pubkey(&leader_keypair.pubkey());
 
-        let ledger_path = get_tmp_ledger_path_auto_delete!();
-        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let validator_keypair = Keypair::new();
+        let validator_node = Node::new_localhost_with_pubkey(&validator_keypair.pubkey());
+        let genesis_config =
+            create_genesis_config_with_leader(10_000, &leader_keypair.pubkey(), 1000)
+                .genesis_config;
+        let (validator_ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_config);
 
-        let mut validator_config = ValidatorConfig::default_for_test();
-        let mut hard_forks = HardForks::default();
-        let mut root_slot;
+        let voting_keypair = Arc::new(Keypair::new());
+        let vote_account = voting_keypair.pubkey();
+        let config = ValidatorConfig {
+            rpc_addrs: Some((
+                validator_node.info.rpc().unwrap(),
+                validator_node.info.rpc_pubsub().unwrap(),
+            )),
+            ..ValidatorConfig::default_for_test()
+        };
+        let validator = Validator::new(
+            validator_node,
+            Arc::new(validator_keypair),
+            &validator_ledger_path,
+            &vote_account,
+            Arc::new(RwLock::new(vec![voting_keypair])),
+            vec![leader_node.info],
+            &config,
+            None, // rpc_to_plugin_manager_receiver
+            Arc::new(RwLock::new(ValidatorStartProgress::default())),
+            SocketAddrSpace::Unspecified,
+            ValidatorTpuConfig::new_for_tests(),
+            Arc::new(RwLock::new(None)),
+            None,
+        )
+        .expect("assume successful validator start");
 
-        // Do check from root_slot + 1 if wait_for_supermajority (10) == root_slot (10)
-        root_slot = 10;
-        validator_config.wait_for_supermajority = Some(root_slot);
-        assert_eq!(
-            should_cleanup_blockstore_incorrect_shred_versions(
-                &validator_config,
-                &blockstore,
-                root_slot,
-                &hard_forks
-            )
-            .unwrap(),
-            Some(root_slot + 1)
-        );
+        let self_test_config = IngestSelfTestConfig {
+            max_duration_ms: 4_000,
+            step_duration_ms: 100,
+            initial_rate: 100,
+            min_slots_to_leader: 0,
+            ..IngestSelfTestConfig::default()
+        };
+        let result = validator.run_ingest_self_test(&self_test_config).unwrap();
+        assert!(!result.interrupted);
+        assert!(result.connections_cleaned_up);
+        for node_identity in [true, false] {
+            let rate = result
+                .sustained_rate(IngestSelfTestServer::Tpu, node_identity)
+                .unwrap();
+            assert!(rate >= self_test_config.initial_rate, "{result:?}");
+        }
+        assert!(result.clients.iter().all(|client| client.stake == 0));
 
-        // No check if wait_for_supermajority (10) < root_slot (15) (no hard forks)
-        // Arguably operator error to pass a value for wait_for_supermajority in this case
-        root_slot = 15;
-        assert_eq!(
-            should_cleanup_blockstore_incorrect_shred_versions(
-                &validator_config,
-                &blockstore,
-                root_slot,
-                &hard_forks
-            )
-            .unwrap(),
-            None,
-        );
+        // The tap is released once done, so the self-test can run again
+        assert!(validator.run_ingest_self_test(&self_test_config).is_ok());
 
-        // Emulate cluster restart at slot 10
-        // No check if wait_for_supermajority (10) < root_slot (15) (empty blockstore)
-        hard_forks.register(10);
-        assert_eq!(
-            should_cleanup_blockstore_incorrect_shred_versions(
-                &validator_config,
-                &blockstore,
-                root_slot,
-                &hard_forks
-            )
-            .unwrap(),
-            None,
-        );
+        validator.close();
+        remove_dir_all(validator_ledger_path).unwrap();
+    }
 
-        // Insert some shreds at newer slots than hard fork
-        let entries = entry::create_ticks(1, 0, Hash::default());
-        for i in 20..35 {
-            let shreds = blockstore::entries_to_test_shreds(
-                &entries,
-                i,     // slot
+    #[test]
+    fn test_refresh_advertised_addresses() {
+        agave_logger::setup();
+        let leader_keypair = Keypair::new();
+        let leader_node = Node::new_localhost_with_pubkey(&leader_keypair.pubkey());
+
+        let validator_keypair = Keypair::new();
+        let validator_node = Node::new_localhost_with_pubkey(&validator_keypair.pubkey());
+        let old_info = validator_node.info.clone();
+        let genesis_config =
+            create_genesis_config_with_leader(10_000, &leader_keypair.pubkey(), 1000)
+                .genesis_config;
+        let (validator_ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_config);
+
+        let voting_keypair = Arc::new(Keypair::new());
+        let config = ValidatorConfig {
+            rpc_addrs: Some((
+                validator_node.info.rpc().unwrap(),
+                validator_node.info.rpc_pubsub().unwrap(),
+            )),
+            ..ValidatorConfig::default_for_test()
+        };
+        let start_progress = Arc::new(RwLock::new(ValidatorStartProgress::default()));
+        let validator = Validator::new(
+            validator_node,
+            Arc::new(validator_keypair),
+            &validator_ledger_path,
+            &voting_keypair.pubkey(),
+            Arc::new(RwLock::new(vec![voting_keypair])),
+            vec![leader_node.info],
+            &config,
+            None, // rpc_to_plugin_manager_receiver
+            start_progress,
+            SocketAddrSpace::Unspecified,
+            ValidatorTpuConfig::new_for_tests(),
+            Arc::new(RwLock::new(None)),
+            None,
+        )
+        .expect("assume successful validator start");
+
+        assert_eq!(validator.peer_allowlists(), PeerAllowlists::from(&config));
+
+        let repair_validators = HashSet::from([Pubkey::new_unique()]);
+        validator.set_repair_validators(Some(repair_validators.clone()));
+        assert_eq!(
+            validator.peer_allowlists().repair_validators,
+            Some(repair_validators)
+        );
+        validator.set_repair_validators(None);
+        assert_eq!(validator.peer_allowlists(), PeerAllowlists::from(&config));
+
+        let epoch_schedule = validator.epoch_schedule();
+        assert_eq!(epoch_schedule, genesis_config.epoch_schedule);
+        assert_eq!(
+            epoch_schedule.slots_per_epoch,
+            genesis_config.epoch_schedule.slots_per_epoch
+        );
+        assert_eq!(
+            epoch_schedule.leader_schedule_slot_offset,
+            genesis_config.epoch_schedule.leader_schedule_slot_offset
+        );
+        assert_eq!(epoch_schedule.warmup, genesis_config.epoch_schedule.warmup);
+
+        let startup_timings = validator.startup_timings();
+        assert!(startup_timings.load_blockstore > Duration::ZERO);
+        assert!(startup_timings.total >= startup_timings.load_blockstore);
+
+        let wallclock = validator.cluster_info.my_contact_info().wallclock();
+        thread::sleep(Duration::from_millis(2));
+        validator.refresh_contact_info();
+        let my_info = validator.cluster_info.my_contact_info();
+        assert!(my_info.wallclock() > wallclock);
+        assert_eq!(
+            validator
+                .cluster_info
+                .lookup_contact_info(my_info.pubkey(), ContactInfo::wallclock),
+            Some(my_info.wallclock())
+        );
+
+        let new_ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+        assert_eq!(
+            validator
+                .refresh_advertised_addresses(Some(new_ip))
+                .unwrap(),
+            new_ip
+        );
+        let my_info = validator.cluster_info.my_contact_info();
+        let gossip_info = validator
+            .cluster_info
+            .lookup_contact_info(my_info.pubkey(), ContactInfo::clone)
+            .unwrap();
+        assert_eq!(gossip_info, my_info);
+        assert!(my_info.wallclock() >= old_info.wallclock());
+        let old_gossip = old_info.gossip().unwrap();
+        assert_eq!(
+            my_info.gossip().unwrap(),
+            SocketAddr::new(new_ip, old_gossip.port())
+        );
+        let old_tpu = old_info.tpu(Protocol::QUIC).unwrap();
+        assert_eq!(
+            my_info.tpu(Protocol::QUIC).unwrap(),
+            SocketAddr::new(new_ip, old_tpu.port())
+        );
+
+        // Refreshing again right away is rejected
+        assert!(matches!(
+            validator.refresh_advertised_addresses(Some(old_gossip.ip())),
+            Err(AdvertisedAddressesError::RefreshedTooRecently(_)),
+        ));
+        assert_eq!(validator.cluster_info.my_contact_info(), my_info);
+
+        validator.close();
+        remove_dir_all(validator_ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_should_run_completed_data_sets_service() {
+        let mut config = ValidatorConfig::default_for_test();
+        let rpc_addr = SocketAddr::from(([127, 0, 0, 1], 8899));
+        for (rpc, full_api, enable_completed_data_sets, expected) in [
+            // Derived from the full RPC API by default
+            (true, false, None, false),
+            (true, true, None, true),
+            (false, true, None, false),
+            // Overridden either way, with or without RPC
+            (true, false, Some(true), true),
+            (true, true, Some(false), false),
+            (true, true, Some(true), true),
+            (true, false, Some(false), false),
+            (false, false, Some(true), true),
+        ] {
+            config.rpc_addrs = rpc.then_some((rpc_addr, rpc_addr));
+            config.rpc_config.full_api = full_api;
+            config.enable_completed_data_sets = enable_completed_data_sets;
+            assert_eq!(
+                should_run_completed_data_sets_service(&config),
+                expected,
+                "rpc: {rpc}, full_api: {full_api}, enable_completed_data_sets: \
+                 {enable_completed_data_sets:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_cleanup_blockstore_incorrect_shred_versions() {
+        agave_logger::setup();
+
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        let mut validator_config = ValidatorConfig::default_for_test();
+        let mut hard_forks = HardForks::default();
+        let mut root_slot;
+
+        // Do check from root_slot + 1 if wait_for_supermajority (10) == root_slot (10)
+        root_slot = 10;
+        validator_config.wait_for_supermajority = Some(root_slot);
+        assert_eq!(
+            should_cleanup_blockstore_incorrect_shred_versions(
+                &validator_config,
+                &blockstore,
+                root_slot,
+                &hard_forks
+            )
+            .unwrap(),
+            Some(root_slot + 1)
+        );
+
+        // No check if wait_for_supermajority (10) < root_slot (15) (no hard forks)
+        // Arguably operator error to pass a value for wait_for_supermajority in this case
+        root_slot = 15;
+        assert_eq!(
+            should_cleanup_blockstore_incorrect_shred_versions(
+                &validator_config,
+                &blockstore,
+                root_slot,
+                &hard_forks
+            )
+            .unwrap(),
+            None,
+        );
+
+        // Emulate cluster restart at slot 10
+        // No check if wait_for_supermajority (10) < root_slot (15) (empty blockstore)
+        hard_forks.register(10);
+        assert_eq!(
+            should_cleanup_blockstore_incorrect_shred_versions(
+                &validator_config,
+                &blockstore,
+                root_slot,
+                &hard_forks
+            )
+            .unwrap(),
+            None,
+        );
+
+        // Insert some shreds at newer slots than hard fork
+        let entries = entry::create_ticks(1, 0, Hash::default());
+        for i in 20..35 {
+            let shreds = blockstore::entries_to_test_shreds(
+                &entries,
+                i,     // slot
                 i - 1, // parent_slot
                 true,  // is_full_slot
                 1,     // version
@@ -3588,6 +5083,337 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_wait_for_supermajority_bank_hash_mismatch_policy() {
+        agave_logger::setup();
+        let node_keypair = Arc::new(Keypair::new());
+        let node_pubkey = node_keypair.pubkey();
+        let cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_pubkey, timestamp()),
+            node_keypair,
+            SocketAddrSpace::Unspecified,
+        );
+
+        // This node holds all the stake, so the supermajority is reached immediately
+        let genesis_config =
+            create_genesis_config_with_leader(10_000, &node_pubkey, 1000).genesis_config;
+        let bank_forks = BankForks::new_rw_arc(Bank::new_for_tests(&genesis_config));
+        let rpc_override_health_check = Arc::new(AtomicBool::new(false));
+        let start_progress = Arc::new(RwLock::new(ValidatorStartProgress::default()));
+
+        let mut config = ValidatorConfig::default_for_test();
+        config.wait_for_supermajority = Some(0);
+        config.expected_bank_hash = Some(hash(&[1]));
+
+        assert_eq!(
+            config.bank_hash_mismatch_policy,
+            BankHashMismatchPolicy::Abort
+        );
+        assert!(matches!(
+            wait_for_supermajority(
+                &config,
+                None,
+                &bank_forks,
+                &cluster_info,
+                rpc_override_health_check.clone(),
+                &start_progress,
+            ),
+            Err(ValidatorError::BankHashMismatch(_, _)),
+        ));
+
+        config.bank_hash_mismatch_policy = BankHashMismatchPolicy::WarnAndContinue;
+        assert!(
+            wait_for_supermajority(
+                &config,
+                None,
+                &bank_forks,
+                &cluster_info,
+                rpc_override_health_check,
+                &start_progress,
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_genesis_hash_mismatch_policy() {
+        agave_logger::setup();
+        let genesis_config = create_genesis_config(10_000).0;
+        let (ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_config);
+
+        let mut config = ValidatorConfig::default_for_test();
+        config.expected_genesis_hash = Some(genesis_config.hash());
+        assert_eq!(
+            load_genesis(&config, &ledger_path).unwrap().hash(),
+            genesis_config.hash()
+        );
+
+        config.expected_genesis_hash = Some(hash(&[1]));
+        assert_eq!(
+            config.genesis_hash_mismatch_policy,
+            GenesisHashMismatchPolicy::Abort
+        );
+        assert!(matches!(
+            load_genesis(&config, &ledger_path),
+            Err(ValidatorError::GenesisHashMismatch(actual, expected))
+                if actual == genesis_config.hash() && expected == hash(&[1]),
+        ));
+
+        config.genesis_hash_mismatch_policy = GenesisHashMismatchPolicy::WarnAndContinue;
+        assert_eq!(
+            load_genesis(&config, &ledger_path).unwrap().hash(),
+            genesis_config.hash()
+        );
+        remove_dir_all(ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_stake_percent_online() {
+        let nodes: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let vote_accounts = [
+            (40, nodes[0]),
+            (30, nodes[1]),
+            (20, nodes[2]),
+            (10, nodes[3]),
+            (0, Pubkey::new_unique()),
+        ];
+
+        // Nobody online
+        let report = stake_percent_online(vote_accounts, &HashSet::new(), &HashSet::new());
+        assert_eq!(report.online_stake_percent(), 0);
+        assert_eq!(report.offline_stake, 100);
+        assert_eq!(report.total_activated_stake, 100);
+        assert_eq!(
+            report.offline_nodes,
+            vec![
+                (40, nodes[0]),
+                (30, nodes[1]),
+                (20, nodes[2]),
+                (10, nodes[3])
+            ],
+        );
+
+        // Nodes online with the wrong shred version still count as online
+        let online_pubkeys = HashSet::from([nodes[0], nodes[2], Pubkey::new_unique()]);
+        let same_shred_pubkeys = HashSet::from([nodes[0]]);
+        let report = stake_percent_online(vote_accounts, &online_pubkeys, &same_shred_pubkeys);
+        assert_eq!(
+            report,
+            GossipStakeReport {
+                online_stake: 60,
+                offline_stake: 40,
+                total_activated_stake: 100,
+                wrong_shred_version_stake: 20,
+                offline_nodes: vec![(30, nodes[1]), (10, nodes[3])],
+            }
+        );
+        assert_eq!(report.online_stake_percent(), 60);
+
+        // Everybody online
+        let online_pubkeys: HashSet<_> = nodes.iter().copied().collect();
+        let report = stake_percent_online(vote_accounts, &online_pubkeys, &online_pubkeys);
+        assert_eq!(report.online_stake_percent(), 100);
+        assert_eq!(report.wrong_shred_version_stake, 0);
+        assert!(report.offline_nodes.is_empty());
+
+        // No activated stake at all
+        let report = stake_percent_online([], &online_pubkeys, &online_pubkeys);
+        assert_eq!(report.online_stake_percent(), 0);
+    }
+
+    #[test]
+    fn test_snapshot_load_progress() {
+        let unpack_progress = UnpackProgress::default();
+        let num_accounts_indexed = AtomicU64::new(0);
+        assert_eq!(
+            snapshot_load_progress(&unpack_progress, &num_accounts_indexed),
+            None
+        );
+
+        unpack_progress.total_bytes.store(1_000, Ordering::Relaxed);
+        unpack_progress.bytes_unpacked.store(250, Ordering::Relaxed);
+        assert_eq!(
+            snapshot_load_progress(&unpack_progress, &num_accounts_indexed),
+            Some(ValidatorStartProgress::UnpackingSnapshot {
+                bytes_unpacked: 250,
+                total_bytes: 1_000,
+            })
+        );
+
+        unpack_progress
+            .bytes_unpacked
+            .store(1_000, Ordering::Relaxed);
+        num_accounts_indexed.store(42, Ordering::Relaxed);
+        assert_eq!(
+            snapshot_load_progress(&unpack_progress, &num_accounts_indexed),
+            Some(ValidatorStartProgress::BuildingAccountsIndex {
+                accounts_indexed: 42
+            })
+        );
+    }
+
+    #[test]
+    fn test_report_snapshot_load_progress() {
+        let genesis_config = create_genesis_config(1_000_000).0;
+        let bank = Bank::new_for_tests(&genesis_config);
+        bank.fill_bank_with_ticks_for_tests();
+        bank.set_block_id(Some(Hash::default()));
+
+        let bank_snapshots_dir = TempDir::new().unwrap();
+        let snapshot_archives_dir = TempDir::new().unwrap();
+        let unpack_progress = Arc::new(UnpackProgress::default());
+        let snapshot_config = SnapshotConfig {
+            full_snapshot_archives_dir: snapshot_archives_dir.path().to_path_buf(),
+            incremental_snapshot_archives_dir: snapshot_archives_dir.path().to_path_buf(),
+            bank_snapshots_dir: bank_snapshots_dir.path().to_path_buf(),
+            unpack_progress: Some(unpack_progress.clone()),
+            ..SnapshotConfig::default()
+        };
+        let snapshot_archive_info =
+            snapshot_bank_utils::bank_to_full_snapshot_archive(&snapshot_config, &bank).unwrap();
+        let archive_size = std::fs::metadata(snapshot_archive_info.path())
+            .unwrap()
+            .len();
+
+        let (_tmp_dir, accounts_dir) = snapshot_utils::create_tmp_accounts_dir_for_tests();
+        let num_accounts_indexed = Arc::new(AtomicU64::new(0));
+        let process_options = blockstore_processor::ProcessOptions {
+            accounts_db_config: AccountsDbConfig {
+                num_accounts_indexed: Some(num_accounts_indexed.clone()),
+                ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+            },
+            use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup::Always,
+            ..blockstore_processor::ProcessOptions::default()
+        };
+        let mut observed_progress = vec![];
+        let bank_from_snapshot = thread::scope(|scope| {
+            let load_snapshot_handle = scope.spawn(|| {
+                bank_forks_utils::try_load_bank_forks_from_snapshot(
+                    &genesis_config,
+                    &[accounts_dir],
+                    &snapshot_config,
+                    &process_options,
+                    None,
+                    Arc::default(),
+                )
+            });
+            // Poll without sleeping so that unpacking the small test archive
+            // cannot complete between two reports
+            report_snapshot_load_progress(
+                &unpack_progress,
+                &num_accounts_indexed,
+                Duration::ZERO,
+                || load_snapshot_handle.is_finished(),
+                |progress| {
+                    if observed_progress.last() != Some(&progress) {
+                        observed_progress.push(progress);
+                    }
+                },
+            );
+            load_snapshot_handle.join().unwrap()
+        });
+        assert!(bank_from_snapshot.unwrap().is_some());
+
+        // The archive is unpacked before its accounts are indexed, and the
+        // last progress reported is the number of accounts indexed in total
+        let accounts_indexed = num_accounts_indexed.load(Ordering::Relaxed);
+        assert!(accounts_indexed > 0);
+        assert_eq!(
+            observed_progress.last(),
+            Some(&ValidatorStartProgress::BuildingAccountsIndex { accounts_indexed })
+        );
+        let (unpacking, indexing) = observed_progress.split_at(
+            observed_progress
+                .iter()
+                .position(|progress| {
+                    matches!(
+                        progress,
+                        ValidatorStartProgress::BuildingAccountsIndex { .. }
+                    )
+                })
+                .unwrap(),
+        );
+        assert!(!unpacking.is_empty());
+        assert!(unpacking.iter().all(|progress| matches!(
+            progress,
+            ValidatorStartProgress::UnpackingSnapshot {
+                bytes_unpacked,
+                total_bytes,
+            } if *bytes_unpacked <= archive_size && *total_bytes == archive_size
+        )));
+        assert!(indexing.iter().all(|progress| matches!(
+            progress,
+            ValidatorStartProgress::BuildingAccountsIndex { accounts_indexed }
+                if *accounts_indexed > 0
+        )));
+    }
+
+    #[test]
+    fn test_peer_allowlists() {
+        let mut config = ValidatorConfig::default_for_test();
+        assert_eq!(PeerAllowlists::from(&config), PeerAllowlists::default());
+
+        let known_validators = HashSet::from([Pubkey::new_unique(), Pubkey::new_unique()]);
+        let repair_validators = HashSet::from([Pubkey::new_unique()]);
+        config.known_validators = Some(known_validators.clone());
+        config.repair_validators = Some(repair_validators.clone());
+        assert_eq!(
+            PeerAllowlists::from(&config),
+            PeerAllowlists {
+                known_validators: Some(known_validators),
+                repair_validators: Some(repair_validators),
+                gossip_validators: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_known_validators() {
+        let mut config = ValidatorConfig::default_for_test();
+        let repair_validators = HashSet::from([Pubkey::new_unique()]);
+        config.known_validators = Some(HashSet::from([Pubkey::new_unique()]));
+        config.repair_validators = Some(repair_validators.clone());
+        let peer_allowlists = RwLock::new(PeerAllowlists::from(&config));
+
+        let node_keypair = Arc::new(Keypair::new());
+        let cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+            node_keypair,
+            SocketAddrSpace::Unspecified,
+        );
+        cluster_info
+            .set_trim_keep_pubkeys(config.known_validators.clone().unwrap())
+            .unwrap();
+
+        let known_validators = HashSet::from([Pubkey::new_unique(), Pubkey::new_unique()]);
+        set_known_validators(
+            &cluster_info,
+            &peer_allowlists,
+            Some(known_validators.clone()),
+        );
+        assert_eq!(
+            cluster_info.trim_keep_pubkeys(),
+            Some(known_validators.clone())
+        );
+        assert_eq!(
+            *peer_allowlists.read().unwrap(),
+            PeerAllowlists {
+                known_validators: Some(known_validators),
+                repair_validators: Some(repair_validators.clone()),
+                gossip_validators: None,
+            }
+        );
+
+        // Trust all validators
+        set_known_validators(&cluster_info, &peer_allowlists, None);
+        assert_eq!(cluster_info.trim_keep_pubkeys(), None);
+        assert_eq!(peer_allowlists.read().unwrap().known_validators, None);
+        assert_eq!(
+            peer_allowlists.read().unwrap().repair_validators,
+            Some(repair_validators)
+        );
+    }
+
     #[test]
     fn test_is_snapshot_config_valid() {
         fn new_snapshot_config(
@@ -3669,7 +5495,267 @@ mod tests {
             ..GenesisConfig::default()
         };
         let bank = Bank::new_for_tests(&genesis_config);
-        assert!(check_poh_speed(&bank, Some(10_000)).is_err());
+        assert!(check_poh_speed(&bank, Some(10_000), false).is_err());
+    }
+
+    #[test]
+    fn test_slots_behind() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(1);
+        let bank_forks = BankForks::new_rw_arc(Bank::new_for_tests(&genesis_config));
+        let bank = Bank::new_from_parent(
+            bank_forks.read().unwrap().root_bank(),
+            SlotLeader::default(),
+            5,
+        );
+        bank_forks.write().unwrap().insert(bank);
+        let max_slots = MaxSlots::default();
+
+        // retransmit hasn't observed anything yet
+        assert_eq!(slots_behind(&max_slots, &bank_forks), None);
+
+        max_slots.retransmit.store(12, Ordering::Relaxed);
+        assert_eq!(slots_behind(&max_slots, &bank_forks), Some(7));
+
+        // working bank ahead of retransmit saturates to zero
+        max_slots.retransmit.store(3, Ordering::Relaxed);
+        assert_eq!(slots_behind(&max_slots, &bank_forks), Some(0));
+    }
+
+    #[test]
+    fn test_outstanding_repair_count() {
+        let outstanding_repair_requests = RwLock::<OutstandingShredRepairs>::default();
+        assert_eq!(outstanding_repair_count(&outstanding_repair_requests), 0);
+
+        {
+            let mut outstanding_repair_requests = outstanding_repair_requests.write().unwrap();
+            for shred_index in 0..3 {
+                outstanding_repair_requests
+                    .add_request(ShredRepairType::Shred(5, shred_index), timestamp());
+            }
+            outstanding_repair_requests.add_request(ShredRepairType::Orphan(7), timestamp());
+        }
+        assert_eq!(outstanding_repair_count(&outstanding_repair_requests), 4);
+    }
+
+    #[test]
+    fn test_poh_status() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+        let genesis_config = create_genesis_config(2).0;
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let (poh_recorder, _entry_receiver) = PohRecorder::new(
+            0,
+            Hash::default(),
+            bank.clone(),
+            Some((4, 4)),
+            genesis_config.ticks_per_slot,
+            blockstore,
+            &Arc::new(LeaderScheduleCache::default()),
+            &PohConfig::default(),
+            Arc::new(AtomicBool::default()),
+        );
+        let poh_recorder = RwLock::new(poh_recorder);
+        assert_eq!(poh_status(&poh_recorder), PohStatus::default());
+
+        poh_recorder
+            .write()
+            .unwrap()
+            .set_bank_for_test(bank.clone());
+        assert_eq!(
+            poh_status(&poh_recorder),
+            PohStatus {
+                slot: Some(bank.slot()),
+                tick_height: 0,
+            }
+        );
+
+        {
+            let mut poh_recorder = poh_recorder.write().unwrap();
+            poh_recorder.clear_bank_for_test();
+            poh_recorder.tick();
+            poh_recorder.tick();
+        }
+        assert_eq!(
+            poh_status(&poh_recorder),
+            PohStatus {
+                slot: None,
+                tick_height: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_scheduler_pool_installed() {
+        let genesis_config = create_genesis_config(2).0;
+        let bank_forks = BankForks::new_rw_arc(Bank::new_for_tests(&genesis_config));
+        assert!(!scheduler_pool_installed(&bank_forks));
+
+        let scheduler_pool = DefaultSchedulerPool::new(None, None, None, None, None);
+        bank_forks
+            .write()
+            .unwrap()
+            .install_scheduler_pool(scheduler_pool);
+        assert!(scheduler_pool_installed(&bank_forks));
+    }
+
+    #[test]
+    fn test_highest_super_majority_root() {
+        let block_commitment_cache = RwLock::new(BlockCommitmentCache::new_for_tests());
+        assert_eq!(highest_super_majority_root(&block_commitment_cache), 0);
+
+        block_commitment_cache
+            .write()
+            .unwrap()
+            .set_highest_super_majority_root(42);
+        assert_eq!(highest_super_majority_root(&block_commitment_cache), 42);
+    }
+
+    #[test]
+    fn test_staked_nodes_summary() {
+        let staked_nodes = RwLock::<StakedNodes>::default();
+        assert_eq!(
+            staked_nodes_summary(&staked_nodes),
+            StakedNodesSummary::default()
+        );
+
+        let overridden = Pubkey::new_unique();
+        let stakes = HashMap::from([
+            (Pubkey::new_unique(), 10),
+            (Pubkey::new_unique(), 40),
+            (Pubkey::new_unique(), 0),
+            (overridden, 1_000),
+        ]);
+        let overrides = HashMap::from([(overridden, 25), (Pubkey::new_unique(), 5)]);
+        *staked_nodes.write().unwrap() = StakedNodes::new(Arc::new(stakes), overrides);
+        assert_eq!(
+            staked_nodes_summary(&staked_nodes),
+            StakedNodesSummary {
+                count: 4,
+                total_stake: 80,
+                max_stake: 40,
+                min_stake: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_accounts_db_config_summary() {
+        let account_path = TempDir::new().unwrap();
+        let accounts_db_config = AccountsDbConfig {
+            index: Some(AccountsIndexConfig {
+                bins: Some(32),
+                ..ACCOUNTS_INDEX_CONFIG_FOR_TESTING
+            }),
+            shrink_ratio: AccountShrinkThreshold::IndividualStore { shrink_ratio: 0.5 },
+            read_cache_limit_bytes: Some((1_000, 2_000)),
+            write_cache_limit_bytes: Some(3_000),
+            ancient_append_vec_offset: Some(-5),
+            ancient_storage_ideal_size: Some(4_000),
+            max_ancient_storages: Some(6),
+            skip_initial_hash_calc: true,
+            num_foreground_threads: NonZeroUsize::new(3),
+            num_background_threads: NonZeroUsize::new(2),
+            ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+        };
+        let accounts_db = AccountsDb::new_with_config(
+            vec![account_path.path().to_path_buf()],
+            accounts_db_config,
+            None,
+            Arc::default(),
+        );
+        assert_eq!(
+            accounts_db_config_summary(&accounts_db),
+            AccountsDbConfigSummary {
+                account_paths: vec![account_path.path().to_path_buf()],
+                shrink_ratio: AccountShrinkThreshold::IndividualStore { shrink_ratio: 0.5 },
+                read_cache_limit_bytes: (1_000, 2_000),
+                write_cache_limit_bytes: Some(3_000),
+                ancient_append_vec_offset: Some(-5),
+                ancient_storage_ideal_size: 4_000,
+                max_ancient_storages: 6,
+                num_index_bins: 32,
+                num_foreground_threads: 3,
+                num_background_threads: 2,
+                skip_initial_hash_calc: true,
+            }
+        );
+
+        // Unset fields resolve to the accounts db defaults
+        let accounts_db = AccountsDb::new_with_config(
+            vec![account_path.path().to_path_buf()],
+            ACCOUNTS_DB_CONFIG_FOR_TESTING,
+            None,
+            Arc::default(),
+        );
+        let summary = accounts_db_config_summary(&accounts_db);
+        assert_eq!(summary.shrink_ratio, AccountShrinkThreshold::default());
+        assert_eq!(summary.max_ancient_storages, DEFAULT_MAX_ANCIENT_STORAGES);
+        assert!(summary.num_foreground_threads > 0);
+    }
+
+    #[test]
+    fn test_is_leader_for_slot() {
+        let genesis_config = create_genesis_config(1_000_000).0;
+        let bank_forks = BankForks::new_rw_arc(Bank::new_for_tests(&genesis_config));
+        let mut leader_schedule_cache =
+            LeaderScheduleCache::new_from_bank(&bank_forks.read().unwrap().root_bank());
+        let identity = Pubkey::new_unique();
+        let leader = SlotLeader {
+            id: identity,
+            vote_address: Pubkey::new_unique(),
+        };
+        let other = SlotLeader::new_unique();
+        // Leader for the first 4 slots of every 8
+        leader_schedule_cache.set_fixed_leader_schedule(Some(FixedSchedule {
+            leader_schedule: Arc::new(LeaderSchedule::new_from_schedule(
+                vec![leader, other],
+                NonZeroUsize::new(4).unwrap(),
+            )),
+        }));
+
+        let is_leader =
+            |slot| is_leader_for_slot(&leader_schedule_cache, &bank_forks, &identity, slot);
+        assert!((0..4).all(is_leader));
+        assert!(!(4..8).any(is_leader));
+        assert!(is_leader(8));
+        assert!(!is_leader(12));
+        assert!(!is_leader_for_slot(
+            &leader_schedule_cache,
+            &bank_forks,
+            &other.id,
+            0
+        ));
+    }
+
+    #[test]
+    fn test_compact_blockstore() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let (shreds, _) = blockstore::make_many_slot_entries(0, 10, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        blockstore.set_roots([0, 1, 2, 3, 4, 5].iter()).unwrap();
+
+        assert_matches!(
+            compact_blockstore(&blockstore, 4, 6),
+            Err(ValidatorError::Other(_))
+        );
+        assert_matches!(
+            compact_blockstore(&blockstore, 3, 2),
+            Err(ValidatorError::Other(_))
+        );
+
+        // The shreds are still in the memtable until the range is compacted
+        let has_data_shred_files = || {
+            blockstore
+                .live_files_metadata()
+                .unwrap()
+                .iter()
+                .any(|file| file.column_family_name == "data_shred")
+        };
+        assert!(!has_data_shred_files());
+        compact_blockstore(&blockstore, 0, 5).unwrap();
+        assert!(has_data_shred_files());
+        assert!((0..10).all(|slot| blockstore.is_full(slot)));
     }
 
     #[test]
@@ -3685,6 +5771,46 @@ mod tests {
             ..GenesisConfig::default()
         };
         let bank = Bank::new_for_tests(&genesis_config);
-        check_poh_speed(&bank, Some(10_000)).unwrap();
+        check_poh_speed(&bank, Some(10_000), false).unwrap();
+    }
+
+    #[test]
+    fn test_poh_speed_no_hashes_per_tick_required() {
+        agave_logger::setup();
+        let poh_config = PohConfig {
+            target_tick_duration: target_tick_duration(),
+            hashes_per_tick: None,
+            ..PohConfig::default()
+        };
+        let genesis_config = GenesisConfig {
+            poh_config,
+            ..GenesisConfig::default()
+        };
+        let bank = Bank::new_for_tests(&genesis_config);
+        assert_matches!(
+            check_poh_speed(&bank, Some(10_000), true),
+            Err(ValidatorError::Other(_))
+        );
+    }
+
+    #[test]
+    fn test_purge_bank_snapshots_at_startup() {
+        let mut config = ValidatorConfig::default_for_test();
+        let bank_snapshots_dir = config.snapshot_config.bank_snapshots_dir.clone();
+        let purged = RefCell::new(vec![]);
+        let purge = |name| {
+            let (bank_snapshots_dir, purged) = (&bank_snapshots_dir, &purged);
+            move |dir: &Path| {
+                assert_eq!(dir, bank_snapshots_dir);
+                purged.borrow_mut().push(name);
+            }
+        };
+        purge_bank_snapshots_at_startup(&config, purge("incomplete"), purge("old"));
+        assert_eq!(*purged.borrow(), vec!["incomplete", "old"]);
+
+        purged.borrow_mut().clear();
+        config.preserve_bank_snapshots_at_startup = true;
+        purge_bank_snapshots_at_startup(&config, purge("incomplete"), purge("old"));
+        assert!(purged.borrow().is_empty());
     }
 }
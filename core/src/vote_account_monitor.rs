@@ -0,0 +1,316 @@
+//! Watches the vote account this node votes with for changes to its
+//! commission and authorities.
+//!
+//! These are rarely changed on purpose, and an unauthorized or accidental
+//! change, e.g. a commission increase or a withdrawer rotation, otherwise goes
+//! unnoticed until much later. Every change is logged and recorded as observed
+//! in the root bank.
+
+use {
+    solana_account::ReadableAccount,
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    solana_runtime::bank::Bank,
+    solana_vote_program::vote_state::VoteStateV4,
+    std::{collections::VecDeque, sync::Mutex},
+};
+
+// Number of changes to keep around
+const MAX_VOTE_ACCOUNT_CHANGES: usize = 64;
+
+/// The fields of a vote account which are not expected to change
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoteAccountSettings {
+    pub node_pubkey: Pubkey,
+    pub authorized_withdrawer: Pubkey,
+    /// The most recently authorized voter, which may only take effect in a
+    /// future epoch
+    pub authorized_voter: Option<Pubkey>,
+    pub inflation_rewards_commission_bps: u16,
+    pub block_revenue_commission_bps: u16,
+}
+
+impl VoteAccountSettings {
+    fn new(vote_state: &VoteStateV4) -> Self {
+        Self {
+            node_pubkey: vote_state.node_pubkey,
+            authorized_withdrawer: vote_state.authorized_withdrawer,
+            authorized_voter: vote_state.authorized_voters.last().map(|(_, voter)| *voter),
+            inflation_rewards_commission_bps: vote_state.inflation_rewards_commission_bps,
+            block_revenue_commission_bps: vote_state.block_revenue_commission_bps,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoteAccountChange {
+    /// Root slot at which the change was first observed
+    pub slot: Slot,
+    pub old: VoteAccountSettings,
+    /// None if the vote account was closed
+    pub new: Option<VoteAccountSettings>,
+}
+
+impl VoteAccountChange {
+    fn report(&self, vote_account: &Pubkey) {
+        let new = self.new.as_ref();
+        let format_pubkey =
+            |pubkey: Option<&Pubkey>| pubkey.map(Pubkey::to_string).unwrap_or_default();
+        datapoint_warn!(
+            "vote_account_change",
+            ("slot", self.slot, i64),
+            ("vote_account", vote_account.to_string(), String),
+            ("closed", new.is_none(), bool),
+            ("old_node_pubkey", self.old.node_pubkey.to_string(), String),
+            (
+                "new_node_pubkey",
+                format_pubkey(new.map(|new| &new.node_pubkey)),
+                String
+            ),
+            (
+                "old_authorized_withdrawer",
+                self.old.authorized_withdrawer.to_string(),
+                String
+            ),
+            (
+                "new_authorized_withdrawer",
+                format_pubkey(new.map(|new| &new.authorized_withdrawer)),
+                String
+            ),
+            (
+                "old_authorized_voter",
+                format_pubkey(self.old.authorized_voter.as_ref()),
+                String
+            ),
+            (
+                "new_authorized_voter",
+                format_pubkey(new.and_then(|new| new.authorized_voter.as_ref())),
+                String
+            ),
+            (
+                "old_inflation_rewards_commission_bps",
+                self.old.inflation_rewards_commission_bps,
+                i64
+            ),
+            (
+                "new_inflation_rewards_commission_bps",
+                new.map(|new| new.inflation_rewards_commission_bps),
+                Option<i64>
+            ),
+            (
+                "old_block_revenue_commission_bps",
+                self.old.block_revenue_commission_bps,
+                i64
+            ),
+            (
+                "new_block_revenue_commission_bps",
+                new.map(|new| new.block_revenue_commission_bps),
+                Option<i64>
+            ),
+        );
+    }
+}
+
+#[derive(Default)]
+struct VoteAccountMonitorState {
+    root: Option<Slot>,
+    settings: Option<VoteAccountSettings>,
+    changes: VecDeque<VoteAccountChange>,
+}
+
+pub struct VoteAccountMonitor {
+    vote_account: Pubkey,
+    state: Mutex<VoteAccountMonitorState>,
+}
+
+impl VoteAccountMonitor {
+    pub fn new(vote_account: Pubkey) -> Self {
+        Self {
+            vote_account,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Compares the vote account in `root_bank` with the one in the previous
+    /// root bank, recording any change.
+    ///
+    /// A vote account which does not exist yet, or cannot be deserialized, is
+    /// not a change; the first vote account observed is the baseline.
+    pub(crate) fn on_new_root(&self, root_bank: &Bank) {
+        let root = root_bank.slot();
+        if self
+            .state
+            .lock()
+            .unwrap()
+            .root
+            .is_some_and(|prev_root| root <= prev_root)
+        {
+            return;
+        }
+        let settings = root_bank
+            .get_account(&self.vote_account)
+            .and_then(|account| VoteStateV4::deserialize(account.data(), &self.vote_account).ok())
+            .map(|vote_state| VoteAccountSettings::new(&vote_state));
+
+        let mut state = self.state.lock().unwrap();
+        state.root = Some(root);
+        let Some(old) = std::mem::replace(&mut state.settings, settings.clone()) else {
+            return;
+        };
+        if settings.as_ref() == Some(&old) {
+            return;
+        }
+        error!(
+            "Vote account {} changed at root {root}: {old:?} -> {settings:?}",
+            self.vote_account
+        );
+        let change = VoteAccountChange {
+            slot: root,
+            old,
+            new: settings,
+        };
+        change.report(&self.vote_account);
+        if state.changes.len() == MAX_VOTE_ACCOUNT_CHANGES {
+            state.changes.pop_front();
+        }
+        state.changes.push_back(change);
+    }
+
+    /// Returns the changes observed since startup, oldest first
+    pub fn changes(&self) -> Vec<VoteAccountChange> {
+        self.state.lock().unwrap().changes.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_account::{AccountSharedData, state_traits::StateMut},
+        solana_leader_schedule::SlotLeader,
+        solana_runtime::genesis_utils::{GenesisConfigInfo, create_genesis_config},
+        solana_vote_program::vote_state::VoteStateVersions,
+        std::sync::Arc,
+    };
+
+    fn store_vote_state(bank: &Bank, vote_account: &Pubkey, vote_state: &VoteStateV4) {
+        let mut account =
+            AccountSharedData::new(1, VoteStateV4::size_of(), &solana_vote_program::id());
+        account
+            .set_state(&VoteStateVersions::new_v4(vote_state.clone()))
+            .unwrap();
+        bank.store_account(vote_account, &account);
+    }
+
+    #[test]
+    fn test_vote_account_monitor() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let new_child = |parent: &Arc<Bank>| {
+            Arc::new(Bank::new_from_parent(
+                parent.clone(),
+                SlotLeader::default(),
+                parent.slot() + 1,
+            ))
+        };
+        let vote_account = Pubkey::new_unique();
+        let monitor = VoteAccountMonitor::new(vote_account);
+
+        // The vote account does not exist yet
+        monitor.on_new_root(&bank0);
+        assert!(monitor.state.lock().unwrap().settings.is_none());
+
+        let bank1 = new_child(&bank0);
+        let mut vote_state = VoteStateV4 {
+            node_pubkey: Pubkey::new_unique(),
+            authorized_withdrawer: Pubkey::new_unique(),
+            inflation_rewards_commission_bps: 500,
+            ..VoteStateV4::default()
+        };
+        store_vote_state(&bank1, &vote_account, &vote_state);
+        monitor.on_new_root(&bank1);
+        let settings1 = VoteAccountSettings::new(&vote_state);
+        assert_eq!(
+            monitor.state.lock().unwrap().settings,
+            Some(settings1.clone())
+        );
+        assert!(monitor.changes().is_empty());
+
+        // Unchanged
+        let bank2 = new_child(&bank1);
+        monitor.on_new_root(&bank2);
+        assert!(monitor.changes().is_empty());
+
+        let bank3 = new_child(&bank2);
+        vote_state.inflation_rewards_commission_bps = 10_000;
+        store_vote_state(&bank3, &vote_account, &vote_state);
+        monitor.on_new_root(&bank3);
+        let settings3 = VoteAccountSettings::new(&vote_state);
+        assert_eq!(
+            monitor.changes(),
+            vec![VoteAccountChange {
+                slot: 3,
+                old: settings1,
+                new: Some(settings3.clone()),
+            }]
+        );
+
+        // Roots that did not advance are ignored
+        vote_state.authorized_withdrawer = Pubkey::new_unique();
+        store_vote_state(&bank3, &vote_account, &vote_state);
+        monitor.on_new_root(&bank3);
+        assert_eq!(monitor.changes().len(), 1);
+
+        let bank4 = new_child(&bank3);
+        monitor.on_new_root(&bank4);
+        let changes = monitor.changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].old, settings3);
+        assert_eq!(
+            changes[1].new.as_ref().unwrap().authorized_withdrawer,
+            vote_state.authorized_withdrawer
+        );
+
+        // Closing the vote account is a change too
+        let bank5 = new_child(&bank4);
+        bank5.store_account(&vote_account, &AccountSharedData::default());
+        monitor.on_new_root(&bank5);
+        let changes = monitor.changes();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[2].new, None);
+    }
+
+    #[test]
+    fn test_vote_account_monitor_history_is_bounded() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let mut bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let vote_account = Pubkey::new_unique();
+        let monitor = VoteAccountMonitor::new(vote_account);
+        for commission in 0..=MAX_VOTE_ACCOUNT_CHANGES as u16 + 1 {
+            bank = Arc::new(Bank::new_from_parent(
+                bank.clone(),
+                SlotLeader::default(),
+                bank.slot() + 1,
+            ));
+            let vote_state = VoteStateV4 {
+                inflation_rewards_commission_bps: commission,
+                ..VoteStateV4::default()
+            };
+            store_vote_state(&bank, &vote_account, &vote_state);
+            monitor.on_new_root(&bank);
+        }
+        let changes = monitor.changes();
+        assert_eq!(changes.len(), MAX_VOTE_ACCOUNT_CHANGES);
+        assert_eq!(changes[0].old.inflation_rewards_commission_bps, 1);
+        assert_eq!(
+            changes
+                .last()
+                .unwrap()
+                .new
+                .as_ref()
+                .unwrap()
+                .inflation_rewards_commission_bps,
+            MAX_VOTE_ACCOUNT_CHANGES as u16 + 1
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! A bounded capture of the votes most recently processed by the
+//! `ClusterInfoVoteListener`.
+//!
+//! When optimistic confirmation behaves unexpectedly, the capture is frozen to
+//! a file so that the exact sequence of votes around the anomaly can be
+//! replayed offline.
+
+use {
+    solana_clock::Slot,
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    solana_time_utils::timestamp,
+    std::{
+        collections::VecDeque,
+        fmt::{self, Display},
+        fs::{self, File},
+        io::{self, BufWriter, Write},
+        num::NonZeroUsize,
+        path::{Path, PathBuf},
+        sync::{
+            Mutex,
+            atomic::{AtomicU64, Ordering},
+        },
+        thread::{self, Builder, JoinHandle},
+    },
+};
+
+/// Name of the directory, under the ledger path, that frozen captures are written to
+pub const VOTE_CAPTURE_DIR: &str = "vote_capture";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteSource {
+    Gossip,
+    Replay,
+}
+
+impl Display for VoteSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gossip => write!(f, "gossip"),
+            Self::Replay => write!(f, "replay"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedVote {
+    /// Milliseconds since the UNIX epoch at which the vote was processed
+    pub timestamp: u64,
+    pub vote_pubkey: Pubkey,
+    pub slots: Vec<Slot>,
+    /// Hash of the last voted slot
+    pub hash: Hash,
+    pub source: VoteSource,
+    /// Stake of the vote account at the time the vote was processed
+    pub stake: u64,
+}
+
+impl Display for CapturedVote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {:?}",
+            self.timestamp, self.vote_pubkey, self.source, self.stake, self.hash, self.slots,
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct VoteCapture {
+    capacity: NonZeroUsize,
+    votes: Mutex<VecDeque<CapturedVote>>,
+    capture_dir: PathBuf,
+    /// Tells apart the captures frozen within the same millisecond
+    num_frozen: AtomicU64,
+    /// Threads writing captures frozen in the background
+    writers: Mutex<Vec<JoinHandle<io::Result<PathBuf>>>>,
+}
+
+impl VoteCapture {
+    /// Keeps the last `capacity` processed votes; frozen captures are written to
+    /// `VOTE_CAPTURE_DIR` under `ledger_path`
+    pub fn new(capacity: NonZeroUsize, ledger_path: &Path) -> Self {
+        Self {
+            capacity,
+            votes: Mutex::new(VecDeque::with_capacity(capacity.get())),
+            capture_dir: ledger_path.join(VOTE_CAPTURE_DIR),
+            num_frozen: AtomicU64::default(),
+            writers: Mutex::default(),
+        }
+    }
+
+    pub fn record(&self, vote: CapturedVote) {
+        let mut votes = self.votes.lock().unwrap();
+        if votes.len() == self.capacity.get() {
+            votes.pop_front();
+        }
+        votes.push_back(vote);
+    }
+
+    /// Writes the captured votes, oldest first, to a new file and returns its path
+    pub fn freeze_vote_capture(&self, reason: &str) -> io::Result<PathBuf> {
+        self.freeze(reason.to_string()).write(&self.capture_dir)
+    }
+
+    /// Same as `freeze_vote_capture`, but writes the file from a background
+    /// thread so as not to hold up the caller. The thread is joined by `join`.
+    pub fn freeze_vote_capture_in_background(&self, reason: String) -> io::Result<()> {
+        let frozen = self.freeze(reason);
+        let capture_dir = self.capture_dir.clone();
+        let writer = Builder::new()
+            .name("solVoteCapture".to_string())
+            .spawn(move || {
+                frozen
+                    .write(&capture_dir)
+                    .inspect_err(|err| warn!("Failed to write vote capture: {err}"))
+            })?;
+        let mut writers = self.writers.lock().unwrap();
+        // The finished writers have nothing left to report
+        writers.retain(|writer| !writer.is_finished());
+        writers.push(writer);
+        Ok(())
+    }
+
+    /// Waits for the captures being written in the background, and returns the
+    /// paths of the files written
+    pub fn join(&self) -> thread::Result<Vec<PathBuf>> {
+        let writers = std::mem::take(&mut *self.writers.lock().unwrap());
+        let mut paths = Vec::with_capacity(writers.len());
+        for writer in writers {
+            // Failures to write were already logged
+            if let Ok(path) = writer.join()? {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    fn freeze(&self, reason: String) -> FrozenVoteCapture {
+        FrozenVoteCapture {
+            reason,
+            frozen_at: timestamp(),
+            sequence: self.num_frozen.fetch_add(1, Ordering::Relaxed),
+            votes: self.votes.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+}
+
+/// The captured votes at the time the capture was frozen
+struct FrozenVoteCapture {
+    reason: String,
+    frozen_at: u64,
+    sequence: u64,
+    votes: Vec<CapturedVote>,
+}
+
+impl FrozenVoteCapture {
+    fn write(self, capture_dir: &Path) -> io::Result<PathBuf> {
+        let Self {
+            reason,
+            frozen_at,
+            sequence,
+            votes,
+        } = self;
+        fs::create_dir_all(capture_dir)?;
+        let path = capture_dir.join(format!("vote_capture-{frozen_at}-{sequence}"));
+        // Never overwrites an earlier capture, e.g. one from before a restart
+        let mut writer = BufWriter::new(File::create_new(&path)?);
+        writeln!(writer, "# reason: {reason}")?;
+        writeln!(writer, "# timestamp vote_pubkey source stake hash slots")?;
+        for vote in &votes {
+            writeln!(writer, "{vote}")?;
+        }
+        writer.flush()?;
+
+        datapoint_warn!(
+            "vote-capture-frozen",
+            ("reason", reason, String),
+            ("num_votes", votes.len(), i64),
+            ("path", path.display().to_string(), String),
+        );
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::collections::HashSet, tempfile::TempDir};
+
+    fn captured_vote(timestamp: u64, slot: Slot, source: VoteSource) -> CapturedVote {
+        CapturedVote {
+            timestamp,
+            vote_pubkey: Pubkey::new_unique(),
+            slots: vec![slot - 1, slot],
+            hash: Hash::new_unique(),
+            source,
+            stake: slot * 100,
+        }
+    }
+
+    #[test]
+    fn test_freeze_vote_capture() {
+        let ledger_path = TempDir::new().unwrap();
+        let vote_capture = VoteCapture::new(NonZeroUsize::new(3).unwrap(), ledger_path.path());
+        let votes: Vec<_> = (1..=5)
+            .map(|i| {
+                let source = if i % 2 == 0 {
+                    VoteSource::Gossip
+                } else {
+                    VoteSource::Replay
+                };
+                captured_vote(i, i + 10, source)
+            })
+            .collect();
+        for vote in votes.iter().cloned() {
+            vote_capture.record(vote);
+        }
+
+        let path = vote_capture
+            .freeze_vote_capture("optimistic slot not rooted")
+            .unwrap();
+        assert!(path.starts_with(ledger_path.path().join(VOTE_CAPTURE_DIR)));
+
+        // Only the last `capacity` votes are kept, in the order they were processed
+        let contents = fs::read_to_string(path).unwrap();
+        let mut lines = contents.lines();
+        assert_eq!(lines.next(), Some("# reason: optimistic slot not rooted"));
+        assert!(lines.next().unwrap().starts_with('#'));
+        let expected: Vec<_> = votes[2..].iter().map(ToString::to_string).collect();
+        assert_eq!(lines.collect::<Vec<_>>(), expected);
+        assert_eq!(
+            expected[0],
+            format!(
+                "3 {} replay 1300 {} [12, 13]",
+                votes[2].vote_pubkey, votes[2].hash
+            ),
+        );
+    }
+
+    #[test]
+    fn test_freeze_vote_capture_in_background() {
+        let ledger_path = TempDir::new().unwrap();
+        let vote_capture = VoteCapture::new(NonZeroUsize::new(2).unwrap(), ledger_path.path());
+        let vote = captured_vote(1, 11, VoteSource::Gossip);
+        vote_capture.record(vote.clone());
+
+        vote_capture
+            .freeze_vote_capture_in_background("test".to_string())
+            .unwrap();
+        // The votes recorded after the freeze are not part of the capture
+        vote_capture.record(captured_vote(2, 12, VoteSource::Replay));
+        vote_capture
+            .freeze_vote_capture_in_background("test".to_string())
+            .unwrap();
+        let paths = vote_capture.join().unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(vote_capture.join().unwrap().is_empty());
+
+        let records = |path: &Path| {
+            fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(records(&paths[0]), vec![vote.to_string()]);
+        assert_eq!(records(&paths[1]).len(), 2);
+    }
+
+    #[test]
+    fn test_freeze_vote_capture_file_names() {
+        let ledger_path = TempDir::new().unwrap();
+        let vote_capture = VoteCapture::new(NonZeroUsize::new(2).unwrap(), ledger_path.path());
+        vote_capture.record(captured_vote(1, 11, VoteSource::Gossip));
+
+        // Captures frozen within the same millisecond get files of their own
+        let paths: HashSet<_> = (0..10)
+            .map(|_| vote_capture.freeze_vote_capture("test").unwrap())
+            .collect();
+        assert_eq!(paths.len(), 10);
+        assert_eq!(
+            fs::read_dir(ledger_path.path().join(VOTE_CAPTURE_DIR))
+                .unwrap()
+                .count(),
+            10
+        );
+    }
+}
@@ -0,0 +1,763 @@
+//! Archival of the per-slot vote aggregates of the `VoteTracker`.
+//!
+//! Whenever the root advances, the vote listener hands the aggregates of the
+//! newly rooted slots to the [`VoteTrackerArchiver`], before their trackers
+//! are purged. A dedicated thread appends them to binary files, rotated once
+//! they would exceed [`VoteTrackerArchiveConfig::max_file_bytes`]. Slots are
+//! archived at most once, in increasing order, so that the records of a file
+//! are sorted by slot.
+//!
+//! All integers are little-endian. A file is laid out as:
+//!
+//! ```text
+//! header, 16 bytes
+//!     magic                 [u8; 8]  b"AGVTARCH"
+//!     version               u32      1
+//!     record_size           u32      size of a record, 32 for version 1
+//! records, record_size bytes each
+//!     slot                  u64
+//!     total_voted_stake     u64      stake of the voters, on gossip or in replay
+//!     gossip_only_stake     u64      stake of the voters only seen on gossip
+//!     num_voters            u32
+//!     confirmation_latency  u32      milliseconds from the first vote seen for
+//!                                    the slot to its optimistic confirmation,
+//!                                    u32::MAX if not optimistically confirmed
+//! index, 16 bytes per entry, one entry every INDEX_INTERVAL records
+//!     slot                  u64      slot of the record
+//!     record_index          u64      position of the record in the file
+//! footer, 24 bytes
+//!     num_records           u64
+//!     num_index_entries     u64
+//!     magic                 [u8; 8]  b"AGVTINDX"
+//! ```
+//!
+//! The index and footer are written when the file is rotated or the archiver
+//! shuts down. Files left without them, e.g. after a crash, are still read
+//! back, up to their last complete record.
+
+use {
+    crate::cluster_info_vote_listener::VoteTracker,
+    crossbeam_channel::{Receiver, Sender, TrySendError, bounded},
+    solana_clock::Slot,
+    solana_runtime::bank::Bank,
+    solana_time_utils::timestamp,
+    std::{
+        collections::HashSet,
+        fs::{self, File},
+        io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        num::NonZeroU64,
+        path::{Path, PathBuf},
+        sync::{
+            Mutex,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread::{self, Builder, JoinHandle},
+    },
+};
+
+pub const DEFAULT_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+/// Size of a file holding a single record, below which files cannot be rotated
+/// by size
+pub const MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES: u64 = file_size(1);
+
+const VOTE_TRACKER_ARCHIVE_FILE_PREFIX: &str = "vote-tracker-archive-";
+const VOTE_TRACKER_ARCHIVE_FILE_EXTENSION: &str = ".bin";
+
+const HEADER_MAGIC: &[u8; 8] = b"AGVTARCH";
+const FOOTER_MAGIC: &[u8; 8] = b"AGVTINDX";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 16;
+const RECORD_SIZE: usize = 32;
+const INDEX_ENTRY_SIZE: u64 = 16;
+const FOOTER_SIZE: u64 = 24;
+/// Number of records per index entry
+const INDEX_INTERVAL: u64 = 64;
+
+/// Number of batches of records queued for the archive thread, beyond which
+/// the batches are dropped
+const ARCHIVE_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct VoteTrackerArchiveConfig {
+    /// Directory the archive files are written to
+    pub dir: PathBuf,
+    /// Size, index and footer included, beyond which a file is rotated
+    pub max_file_bytes: NonZeroU64,
+}
+
+/// The vote aggregates of a rooted slot
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotVoteRecord {
+    pub slot: Slot,
+    /// Stake of the voters for the slot, on gossip or in replay
+    pub total_voted_stake: u64,
+    /// Stake of the voters for the slot only seen on gossip
+    pub gossip_only_stake: u64,
+    pub num_voters: u32,
+    /// Milliseconds from the first vote seen for the slot to its optimistic
+    /// confirmation, None if not optimistically confirmed
+    pub confirmation_latency_ms: Option<u32>,
+}
+
+impl SlotVoteRecord {
+    fn encode(&self) -> [u8; RECORD_SIZE] {
+        let mut buf = [0u8; RECORD_SIZE];
+        buf[0..8].copy_from_slice(&self.slot.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.total_voted_stake.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.gossip_only_stake.to_le_bytes());
+        buf[24..28].copy_from_slice(&self.num_voters.to_le_bytes());
+        let confirmation_latency_ms = self.confirmation_latency_ms.unwrap_or(u32::MAX);
+        buf[28..32].copy_from_slice(&confirmation_latency_ms.to_le_bytes());
+        buf
+    }
+
+    fn decode(buf: &[u8; RECORD_SIZE]) -> Self {
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap());
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap());
+        let confirmation_latency_ms = u32_at(28);
+        Self {
+            slot: u64_at(0),
+            total_voted_stake: u64_at(8),
+            gossip_only_stake: u64_at(16),
+            num_voters: u32_at(24),
+            confirmation_latency_ms: (confirmation_latency_ms != u32::MAX)
+                .then_some(confirmation_latency_ms),
+        }
+    }
+}
+
+/// Size of a file holding `num_records` records, once finished
+const fn file_size(num_records: u64) -> u64 {
+    let num_index_entries = num_records.div_ceil(INDEX_INTERVAL);
+    HEADER_SIZE
+        .saturating_add(num_records.saturating_mul(RECORD_SIZE as u64))
+        .saturating_add(num_index_entries.saturating_mul(INDEX_ENTRY_SIZE))
+        .saturating_add(FOOTER_SIZE)
+}
+
+struct ArchiveFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    num_records: u64,
+    index: Vec<(Slot, u64)>,
+}
+
+impl ArchiveFile {
+    fn create(path: PathBuf) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writer.write_all(HEADER_MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(RECORD_SIZE as u32).to_le_bytes())?;
+        Ok(Self {
+            path,
+            writer,
+            num_records: 0,
+            index: vec![],
+        })
+    }
+
+    fn append(&mut self, record: &SlotVoteRecord) -> io::Result<()> {
+        self.writer.write_all(&record.encode())?;
+        if self.num_records % INDEX_INTERVAL == 0 {
+            self.index.push((record.slot, self.num_records));
+        }
+        self.num_records += 1;
+        Ok(())
+    }
+
+    /// Writes the index and footer
+    fn finish(mut self) -> io::Result<PathBuf> {
+        for (slot, record_index) in &self.index {
+            self.writer.write_all(&slot.to_le_bytes())?;
+            self.writer.write_all(&record_index.to_le_bytes())?;
+        }
+        self.writer.write_all(&self.num_records.to_le_bytes())?;
+        self.writer
+            .write_all(&(self.index.len() as u64).to_le_bytes())?;
+        self.writer.write_all(FOOTER_MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.path)
+    }
+}
+
+/// Appends records to the archive files in a directory, rotating them by size
+pub struct VoteTrackerArchiveWriter {
+    config: VoteTrackerArchiveConfig,
+    file: Option<ArchiveFile>,
+    last_timestamp: u64,
+}
+
+impl VoteTrackerArchiveWriter {
+    pub fn new(config: VoteTrackerArchiveConfig) -> io::Result<Self> {
+        if config.max_file_bytes.get() < MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "vote tracker archive max file bytes {} is less than the size of a file \
+                     holding a single record, {MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES}",
+                    config.max_file_bytes,
+                ),
+            ));
+        }
+        Ok(Self {
+            config,
+            file: None,
+            last_timestamp: 0,
+        })
+    }
+
+    pub fn append(&mut self, record: &SlotVoteRecord) -> io::Result<()> {
+        let num_records = self.file.as_ref().map(|file| file.num_records);
+        if num_records.is_some_and(|num_records| {
+            file_size(num_records + 1) > self.config.max_file_bytes.get()
+        }) {
+            self.finish()?;
+        }
+        if self.file.is_none() {
+            fs::create_dir_all(&self.config.dir)?;
+            // Keep file names unique even if files are rotated within the same
+            // millisecond
+            let timestamp = timestamp().max(self.last_timestamp + 1);
+            self.last_timestamp = timestamp;
+            let path = self.config.dir.join(format!(
+                "{VOTE_TRACKER_ARCHIVE_FILE_PREFIX}{timestamp}{VOTE_TRACKER_ARCHIVE_FILE_EXTENSION}"
+            ));
+            self.file = Some(ArchiveFile::create(path)?);
+        }
+        self.file.as_mut().unwrap().append(record)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Finishes the current file, if any, and returns its path. The next
+    /// record goes to a new file.
+    pub fn finish(&mut self) -> io::Result<Option<PathBuf>> {
+        self.file.take().map(ArchiveFile::finish).transpose()
+    }
+}
+
+/// Hands the aggregates of the newly rooted slots to the archive thread.
+pub struct VoteTrackerArchiver {
+    sender: Sender<Vec<SlotVoteRecord>>,
+    // The highest slot archived so far
+    last_archived_slot: Mutex<Option<Slot>>,
+    num_dropped_records: AtomicUsize,
+}
+
+impl VoteTrackerArchiver {
+    /// Archives `new_roots`, the slots rooted since the last call in
+    /// increasing order up to the slot of `root_bank`, from their trackers in
+    /// `vote_tracker`, which must not have been purged up to `root_bank` yet.
+    /// If `slot_filter` is set, only the slots in it are archived.
+    pub fn on_new_root(
+        &self,
+        root_bank: &Bank,
+        new_roots: &[Slot],
+        vote_tracker: &VoteTracker,
+        slot_filter: Option<&HashSet<Slot>>,
+    ) {
+        let root = root_bank.slot();
+        let mut last_archived_slot = self.last_archived_slot.lock().unwrap();
+        if last_archived_slot.is_some_and(|slot| slot >= root) {
+            return;
+        }
+        let records: Vec<_> = new_roots
+            .iter()
+            .copied()
+            .filter(|slot| last_archived_slot.is_none_or(|last_slot| *slot > last_slot))
+            .filter(|slot| slot_filter.is_none_or(|slot_filter| slot_filter.contains(slot)))
+            .filter_map(|slot| Some((slot, vote_tracker.get_slot_vote_tracker(slot)?)))
+            .map(|(slot, slot_vote_tracker)| {
+                let epoch = root_bank.epoch_schedule().get_epoch(slot);
+                let epoch_stakes = root_bank.epoch_stakes(epoch);
+                let slot_vote_tracker = slot_vote_tracker.read().unwrap();
+                let total_voted_stake = epoch_stakes
+                    .map(|epoch_stakes| {
+                        let vote_accounts = epoch_stakes.stakes().vote_accounts();
+                        slot_vote_tracker
+                            .voters()
+                            .map(|pubkey| vote_accounts.get_delegated_stake(pubkey))
+                            .sum::<u64>()
+                    })
+                    .unwrap_or_default();
+                SlotVoteRecord {
+                    slot,
+                    total_voted_stake,
+                    gossip_only_stake: slot_vote_tracker.gossip_only_stake(),
+                    num_voters: u32::try_from(slot_vote_tracker.num_voters()).unwrap_or(u32::MAX),
+                    confirmation_latency_ms: slot_vote_tracker.confirmation_latency_ms(),
+                }
+            })
+            .collect();
+        *last_archived_slot = Some(root);
+        if records.is_empty() {
+            return;
+        }
+        match self.sender.try_send(records) {
+            Ok(()) => (),
+            Err(TrySendError::Full(records) | TrySendError::Disconnected(records)) => {
+                let num_dropped_records = self
+                    .num_dropped_records
+                    .fetch_add(records.len(), Ordering::Relaxed)
+                    + records.len();
+                datapoint_info!(
+                    "vote_tracker_archive_dropped",
+                    ("root", root, i64),
+                    ("num_records", records.len(), i64),
+                    ("num_dropped_records", num_dropped_records, i64),
+                );
+            }
+        }
+    }
+
+    /// Number of records dropped as the archive thread fell behind
+    pub fn num_dropped_records(&self) -> usize {
+        self.num_dropped_records.load(Ordering::Relaxed)
+    }
+}
+
+/// Writes the records handed over by a [`VoteTrackerArchiver`]. The thread
+/// finishes the current file and exits once the archiver is dropped.
+pub struct VoteTrackerArchiveService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl VoteTrackerArchiveService {
+    pub fn new(config: VoteTrackerArchiveConfig) -> io::Result<(Self, VoteTrackerArchiver)> {
+        let writer = VoteTrackerArchiveWriter::new(config)?;
+        let (sender, receiver) = bounded(ARCHIVE_CHANNEL_CAPACITY);
+        let thread_hdl = Builder::new()
+            .name("solVoteArchive".to_string())
+            .spawn(move || Self::run(writer, receiver))
+            .unwrap();
+        let archiver = VoteTrackerArchiver {
+            sender,
+            last_archived_slot: Mutex::default(),
+            num_dropped_records: AtomicUsize::default(),
+        };
+        Ok((Self { thread_hdl }, archiver))
+    }
+
+    fn run(mut writer: VoteTrackerArchiveWriter, receiver: Receiver<Vec<SlotVoteRecord>>) {
+        let mut num_write_errors = 0usize;
+        for records in receiver {
+            let result = records
+                .iter()
+                .try_for_each(|record| writer.append(record))
+                .and_then(|()| writer.flush());
+            if let Err(err) = result {
+                num_write_errors += 1;
+                warn!(
+                    "Failed to archive the vote aggregates of {} slots: {err}",
+                    records.len()
+                );
+                // Start over with a new file
+                if let Err(err) = writer.finish() {
+                    warn!("Failed to finish vote tracker archive file: {err}");
+                }
+                datapoint_info!(
+                    "vote_tracker_archive_write_error",
+                    ("num_records", records.len(), i64),
+                    ("num_write_errors", num_write_errors, i64),
+                );
+            }
+        }
+        if let Err(err) = writer.finish() {
+            warn!("Failed to finish vote tracker archive file: {err}");
+        }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+/// Returns the paths of the archive files in `dir`, oldest first.
+pub fn list_vote_tracker_archives(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut archives = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let timestamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(VOTE_TRACKER_ARCHIVE_FILE_PREFIX))
+            .and_then(|name| name.strip_suffix(VOTE_TRACKER_ARCHIVE_FILE_EXTENSION))
+            .and_then(|timestamp| timestamp.parse::<u64>().ok());
+        if let Some(timestamp) = timestamp {
+            archives.push((timestamp, path));
+        }
+    }
+    archives.sort_unstable();
+    Ok(archives.into_iter().map(|(_, path)| path).collect())
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads back a file written by a [`VoteTrackerArchiveWriter`].
+pub struct VoteTrackerArchiveReader {
+    reader: BufReader<File>,
+    record_size: u64,
+    num_records: u64,
+    /// Slot and position of every `INDEX_INTERVAL`th record, empty if the
+    /// file was not finished
+    index: Vec<(Slot, u64)>,
+}
+
+impl VoteTrackerArchiveReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let file_len = reader.get_ref().metadata()?.len();
+        let mut header = [0u8; HEADER_SIZE as usize];
+        reader.read_exact(&mut header)?;
+        if &header[0..8] != HEADER_MAGIC {
+            return Err(invalid_data("not a vote tracker archive"));
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported vote tracker archive version {version}"
+            )));
+        }
+        // Records may grow fields at their end in later versions
+        let record_size = u64::from(u32::from_le_bytes(header[12..16].try_into().unwrap()));
+        if record_size < RECORD_SIZE as u64 {
+            return Err(invalid_data(format!("invalid record size {record_size}")));
+        }
+
+        let mut archive = Self {
+            reader,
+            record_size,
+            num_records: (file_len - HEADER_SIZE) / record_size,
+            index: vec![],
+        };
+        if let Some((num_records, index)) = archive.read_footer(file_len)? {
+            archive.num_records = num_records;
+            archive.index = index;
+        }
+        Ok(archive)
+    }
+
+    /// Reads the index, returning None if the file was not finished
+    fn read_footer(&mut self, file_len: u64) -> io::Result<Option<(u64, Vec<(Slot, u64)>)>> {
+        if file_len < HEADER_SIZE + FOOTER_SIZE {
+            return Ok(None);
+        }
+        let mut footer = [0u8; FOOTER_SIZE as usize];
+        self.reader.seek(SeekFrom::Start(file_len - FOOTER_SIZE))?;
+        self.reader.read_exact(&mut footer)?;
+        let num_records = u64::from_le_bytes(footer[0..8].try_into().unwrap());
+        let num_index_entries = u64::from_le_bytes(footer[8..16].try_into().unwrap());
+        // A torn record may end with the magic, the sizes tell them apart
+        let expected_len = num_records
+            .checked_mul(self.record_size)
+            .zip(num_index_entries.checked_mul(INDEX_ENTRY_SIZE))
+            .and_then(|(records_len, index_len)| records_len.checked_add(index_len))
+            .and_then(|len| len.checked_add(HEADER_SIZE + FOOTER_SIZE));
+        if &footer[16..24] != FOOTER_MAGIC || expected_len != Some(file_len) {
+            return Ok(None);
+        }
+        self.reader.seek(SeekFrom::Start(
+            HEADER_SIZE + num_records * self.record_size,
+        ))?;
+        let mut index = Vec::with_capacity(num_index_entries as usize);
+        let mut entry = [0u8; INDEX_ENTRY_SIZE as usize];
+        for _ in 0..num_index_entries {
+            self.reader.read_exact(&mut entry)?;
+            index.push((
+                u64::from_le_bytes(entry[0..8].try_into().unwrap()),
+                u64::from_le_bytes(entry[8..16].try_into().unwrap()),
+            ));
+        }
+        Ok(Some((num_records, index)))
+    }
+
+    pub fn len(&self) -> u64 {
+        self.num_records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_records == 0
+    }
+
+    /// Whether the file has its index and footer
+    pub fn is_finished(&self) -> bool {
+        !self.index.is_empty() || self.num_records == 0
+    }
+
+    fn read_next(&mut self) -> io::Result<SlotVoteRecord> {
+        let mut buf = [0u8; RECORD_SIZE];
+        self.reader.read_exact(&mut buf)?;
+        self.reader
+            .seek_relative(self.record_size as i64 - RECORD_SIZE as i64)?;
+        Ok(SlotVoteRecord::decode(&buf))
+    }
+
+    fn seek_record(&mut self, record_index: u64) -> io::Result<()> {
+        self.reader
+            .seek(SeekFrom::Start(
+                HEADER_SIZE + record_index * self.record_size,
+            ))
+            .map(|_| ())
+    }
+
+    /// Returns the record at `record_index` in the file
+    pub fn record(&mut self, record_index: u64) -> io::Result<Option<SlotVoteRecord>> {
+        if record_index >= self.num_records {
+            return Ok(None);
+        }
+        self.seek_record(record_index)?;
+        self.read_next().map(Some)
+    }
+
+    /// Looks up the record of `slot`, starting from the closest index entry
+    pub fn find_slot(&mut self, slot: Slot) -> io::Result<Option<SlotVoteRecord>> {
+        let start = match self
+            .index
+            .partition_point(|(index_slot, _)| *index_slot <= slot)
+        {
+            0 => 0,
+            i => self.index[i - 1].1,
+        };
+        self.seek_record(start)?;
+        for _ in start..self.num_records {
+            let record = self.read_next()?;
+            if record.slot >= slot {
+                return Ok((record.slot == slot).then_some(record));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Iterates over the records of the file, in order
+    pub fn iter(&mut self) -> io::Result<impl Iterator<Item = io::Result<SlotVoteRecord>> + '_> {
+        self.seek_record(0)?;
+        let num_records = self.num_records;
+        Ok((0..num_records).map(move |_| self.read_next()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_leader_schedule::SlotLeader,
+        solana_runtime::genesis_utils::{
+            GenesisConfigInfo, ValidatorVoteKeypairs, create_genesis_config_with_vote_accounts,
+        },
+        solana_signer::Signer,
+        std::sync::Arc,
+        tempfile::TempDir,
+    };
+
+    fn test_record(slot: Slot) -> SlotVoteRecord {
+        SlotVoteRecord {
+            slot,
+            total_voted_stake: slot * 1_000 + 7,
+            gossip_only_stake: slot * 10,
+            num_voters: (slot % 1_500) as u32,
+            confirmation_latency_ms: (slot % 3 != 0).then_some((slot % 800) as u32),
+        }
+    }
+
+    #[test]
+    fn test_vote_tracker_archive_rotation() {
+        let dir = TempDir::new().unwrap();
+        // Room for 100 records per file
+        let max_file_bytes = file_size(100);
+        let mut writer = VoteTrackerArchiveWriter::new(VoteTrackerArchiveConfig {
+            dir: dir.path().to_path_buf(),
+            max_file_bytes: NonZeroU64::new(max_file_bytes).unwrap(),
+        })
+        .unwrap();
+        let records: Vec<_> = (0..350).map(|i| test_record(1_000 + 2 * i)).collect();
+        for record in &records {
+            writer.append(record).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let paths = list_vote_tracker_archives(dir.path()).unwrap();
+        assert_eq!(paths.len(), 4);
+        let mut read_back = vec![];
+        for (i, path) in paths.iter().enumerate() {
+            assert!(fs::metadata(path).unwrap().len() <= max_file_bytes);
+            let mut reader = VoteTrackerArchiveReader::open(path).unwrap();
+            assert!(reader.is_finished());
+            // The files are rotated at the record boundaries
+            assert_eq!(reader.len(), if i < 3 { 100 } else { 50 });
+            assert_eq!(reader.record(0).unwrap(), Some(records[i * 100]));
+            read_back.extend(reader.iter().unwrap().map(Result::unwrap));
+        }
+        assert_eq!(read_back, records);
+
+        // Random access through the index
+        let mut reader = VoteTrackerArchiveReader::open(&paths[1]).unwrap();
+        assert_eq!(reader.index.len(), 2);
+        assert_eq!(reader.find_slot(1_200).unwrap(), Some(records[100]));
+        assert_eq!(reader.find_slot(1_330).unwrap(), Some(records[165]));
+        assert_eq!(reader.find_slot(1_398).unwrap(), Some(records[199]));
+        assert_eq!(reader.find_slot(1_331).unwrap(), None);
+        assert_eq!(reader.find_slot(1_000).unwrap(), None);
+        assert_eq!(reader.find_slot(1_400).unwrap(), None);
+        assert_eq!(reader.record(100).unwrap(), None);
+    }
+
+    #[test]
+    fn test_vote_tracker_archive_unfinished_file() {
+        let dir = TempDir::new().unwrap();
+        let mut writer = VoteTrackerArchiveWriter::new(VoteTrackerArchiveConfig {
+            dir: dir.path().to_path_buf(),
+            max_file_bytes: NonZeroU64::new(DEFAULT_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES).unwrap(),
+        })
+        .unwrap();
+        let records: Vec<_> = (0..200).map(test_record).collect();
+        for record in &records {
+            writer.append(record).unwrap();
+        }
+        writer.flush().unwrap();
+        let path = list_vote_tracker_archives(dir.path()).unwrap().remove(0);
+
+        // Left without its footer and with a torn record, as after a crash
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&test_record(200).encode()[..RECORD_SIZE - 1])
+            .unwrap();
+        let mut reader = VoteTrackerArchiveReader::open(&path).unwrap();
+        assert!(!reader.is_finished());
+        assert_eq!(reader.len(), 200);
+        let read_back: Vec<_> = reader.iter().unwrap().map(Result::unwrap).collect();
+        assert_eq!(read_back, records);
+        assert_eq!(reader.find_slot(150).unwrap(), Some(records[150]));
+    }
+
+    #[test]
+    fn test_vote_tracker_archive_service() {
+        let dir = TempDir::new().unwrap();
+        let (service, archiver) = VoteTrackerArchiveService::new(VoteTrackerArchiveConfig {
+            dir: dir.path().to_path_buf(),
+            max_file_bytes: NonZeroU64::new(file_size(64)).unwrap(),
+        })
+        .unwrap();
+        let records: Vec<_> = (0..300).map(test_record).collect();
+        for batch in records.chunks(7) {
+            archiver.sender.send(batch.to_vec()).unwrap();
+        }
+        drop(archiver);
+        service.join().unwrap();
+
+        let read_back: Vec<_> = list_vote_tracker_archives(dir.path())
+            .unwrap()
+            .iter()
+            .flat_map(|path| {
+                let mut reader = VoteTrackerArchiveReader::open(path).unwrap();
+                assert!(reader.is_finished());
+                reader
+                    .iter()
+                    .unwrap()
+                    .map(Result::unwrap)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(read_back, records);
+    }
+
+    #[test]
+    fn test_vote_tracker_archive_min_max_file_bytes() {
+        let dir = TempDir::new().unwrap();
+        let writer_with_max_file_bytes = |max_file_bytes| {
+            VoteTrackerArchiveWriter::new(VoteTrackerArchiveConfig {
+                dir: dir.path().to_path_buf(),
+                max_file_bytes: NonZeroU64::new(max_file_bytes).unwrap(),
+            })
+        };
+        let err = writer_with_max_file_bytes(MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES - 1)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // A file per record
+        let mut writer =
+            writer_with_max_file_bytes(MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES).unwrap();
+        for slot in 0..3 {
+            writer.append(&test_record(slot)).unwrap();
+        }
+        writer.finish().unwrap();
+        let paths = list_vote_tracker_archives(dir.path()).unwrap();
+        assert_eq!(paths.len(), 3);
+        for path in paths {
+            assert_eq!(
+                fs::metadata(&path).unwrap().len(),
+                MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES
+            );
+        }
+    }
+
+    #[test]
+    fn test_vote_tracker_archiver_on_new_root() {
+        let validator_voting_keypairs: Vec<_> =
+            (0..3).map(|_| ValidatorVoteKeypairs::new_rand()).collect();
+        let voters: Vec<_> = validator_voting_keypairs
+            .iter()
+            .map(|keypairs| keypairs.vote_keypair.pubkey())
+            .collect();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+            10_000,
+            &validator_voting_keypairs,
+            vec![100, 200, 300],
+        );
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let bank1 = Arc::new(Bank::new_from_parent(
+            bank0.clone(),
+            SlotLeader::default(),
+            1,
+        ));
+        let bank2 = Arc::new(Bank::new_from_parent(bank1, SlotLeader::default(), 2));
+        let bank4 = Arc::new(Bank::new_from_parent(bank2, SlotLeader::default(), 4));
+
+        let vote_tracker = VoteTracker::default();
+        vote_tracker.insert_vote(1, voters[0]);
+        vote_tracker.insert_vote(1, voters[1]);
+        vote_tracker.insert_vote(2, voters[2]);
+        // Slot 3 is on another fork
+        vote_tracker.insert_vote(3, voters[0]);
+        for voter in &voters {
+            vote_tracker.insert_vote(4, *voter);
+        }
+
+        let (sender, receiver) = bounded(ARCHIVE_CHANNEL_CAPACITY);
+        let archiver = VoteTrackerArchiver {
+            sender,
+            last_archived_slot: Mutex::default(),
+            num_dropped_records: AtomicUsize::default(),
+        };
+        let record = |slot, total_voted_stake, num_voters| SlotVoteRecord {
+            slot,
+            total_voted_stake,
+            gossip_only_stake: 0,
+            num_voters,
+            confirmation_latency_ms: None,
+        };
+
+        // No votes for the first root
+        archiver.on_new_root(&bank0, &[0], &vote_tracker, None);
+        assert!(receiver.try_recv().is_err());
+
+        archiver.on_new_root(&bank4, &[1, 2, 4], &vote_tracker, None);
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            vec![record(1, 300, 2), record(2, 300, 1), record(4, 600, 3)],
+        );
+
+        // Slots are archived at most once
+        archiver.on_new_root(&bank4, &[4], &vote_tracker, None);
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(archiver.num_dropped_records(), 0);
+    }
+}
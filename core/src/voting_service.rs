@@ -2,6 +2,7 @@ use {
     crate::{
         consensus::tower_storage::{SavedTowerVersions, TowerStorage},
         next_leader::upcoming_leader_tpu_vote_sockets,
+        restart_latency_tracker::{RestartLatencyTracker, RestartPhase},
     },
     crossbeam_channel::Receiver,
     solana_client::connection_cache::ConnectionCache,
@@ -84,6 +85,7 @@ impl VotingService {
         poh_recorder: Arc<RwLock<PohRecorder>>,
         tower_storage: Arc<dyn TowerStorage>,
         connection_cache: Arc<ConnectionCache>,
+        restart_latency_tracker: Arc<RestartLatencyTracker>,
     ) -> Self {
         let thread_hdl = Builder::new()
             .name("solVoteService".to_string())
@@ -97,6 +99,7 @@ impl VotingService {
                             vote_op,
                             connection_cache.clone(),
                         );
+                        restart_latency_tracker.record(RestartPhase::FirstVoteSubmitted);
                     }
                 }
             })
@@ -640,10 +640,16 @@ fn test_snapshots_with_background_services() {
         snapshot_controller.clone(),
         false,
         0,
+        SnapshotPackagerService::DEFAULT_NUM_THREADS,
+        None,
     );
 
-    let accounts_background_service =
-        AccountsBackgroundService::new(bank_forks.clone(), exit.clone(), abs_request_handler);
+    let accounts_background_service = AccountsBackgroundService::new(
+        bank_forks.clone(),
+        exit.clone(),
+        abs_request_handler,
+        Arc::default(),
+    );
 
     let mut latest_full_snapshot_slot = None;
     let mut latest_incremental_snapshot_slot = None;
@@ -801,6 +807,8 @@ fn test_fastboot_snapshots_teardown(exit_backpressure: bool) {
         snapshot_controller.clone(),
         false,
         0,
+        SnapshotPackagerService::DEFAULT_NUM_THREADS,
+        None,
     );
 
     let mint_keypair = &snapshot_test_config.genesis_config_info.mint_keypair;
@@ -0,0 +1,179 @@
+//! Checks that the startup failures of `Validator::new` surface as the
+//! concrete `ValidatorError` they were raised with, so that callers can tell
+//! them apart through the returned `anyhow::Error`.
+
+use {
+    solana_core::validator::{Validator, ValidatorConfig, ValidatorError, ValidatorTpuConfig},
+    solana_genesis_config::GenesisConfig,
+    solana_gossip::node::Node,
+    solana_hash::Hash,
+    solana_keypair::Keypair,
+    solana_ledger::{
+        blockstore_options::BLOCKSTORE_DIRECTORY_ROCKS_LEVEL, create_new_tmp_ledger_auto_delete,
+        genesis_utils::create_genesis_config_with_leader,
+    },
+    solana_net_utils::SocketAddrSpace,
+    solana_shred_version::compute_shred_version,
+    solana_signer::Signer,
+    std::{
+        fs,
+        path::Path,
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+    tempfile::TempDir,
+};
+
+fn genesis_config() -> GenesisConfig {
+    create_genesis_config_with_leader(10_000, &Keypair::new().pubkey(), 1_000).genesis_config
+}
+
+/// Starts a validator on `ledger_path`, which is expected to fail, and
+/// returns the error
+fn start_validator_err(ledger_path: &Path, config: &ValidatorConfig) -> ValidatorError {
+    let identity_keypair = Keypair::new();
+    let node = Node::new_localhost_with_pubkey(&identity_keypair.pubkey());
+    let voting_keypair = Arc::new(Keypair::new());
+    let result = Validator::new(
+        node,
+        Arc::new(identity_keypair),
+        ledger_path,
+        &voting_keypair.pubkey(),
+        Arc::new(RwLock::new(vec![voting_keypair])),
+        vec![],
+        config,
+        None, // rpc_to_plugin_manager_receiver
+        Arc::default(),
+        SocketAddrSpace::Unspecified,
+        ValidatorTpuConfig::new_for_tests(),
+        Arc::new(RwLock::new(None)),
+        None,
+    );
+    // Stop the services started before the failure
+    config.validator_exit.write().unwrap().exit();
+    let err = match result {
+        Ok(validator) => {
+            validator.close();
+            panic!("validator started");
+        }
+        Err(err) => err,
+    };
+    err.downcast::<ValidatorError>()
+        .unwrap_or_else(|err| panic!("not a ValidatorError: {err:?}"))
+}
+
+#[test]
+fn test_validator_new_open_genesis_config_error() {
+    let ledger_path = TempDir::new().unwrap();
+    let err = start_validator_err(ledger_path.path(), &ValidatorConfig::default_for_test());
+    assert!(
+        matches!(err, ValidatorError::OpenGenesisConfig(_)),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_validator_new_genesis_hash_mismatch() {
+    let genesis_config = genesis_config();
+    let (ledger_path, _blockhash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+    let expected_genesis_hash = Hash::new_unique();
+    let config = ValidatorConfig {
+        expected_genesis_hash: Some(expected_genesis_hash),
+        ..ValidatorConfig::default_for_test()
+    };
+    let err = start_validator_err(ledger_path.path(), &config);
+    assert!(
+        matches!(
+            err,
+            ValidatorError::GenesisHashMismatch(actual, expected)
+                if actual == genesis_config.hash() && expected == expected_genesis_hash
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_validator_new_blockstore_error() {
+    let (ledger_path, _blockhash) = create_new_tmp_ledger_auto_delete!(&genesis_config());
+    // Replace the blockstore with a file that cannot be opened as one
+    let blockstore_path = ledger_path.path().join(BLOCKSTORE_DIRECTORY_ROCKS_LEVEL);
+    fs::remove_dir_all(&blockstore_path).unwrap();
+    fs::write(&blockstore_path, b"").unwrap();
+    let err = start_validator_err(ledger_path.path(), &ValidatorConfig::default_for_test());
+    assert!(matches!(err, ValidatorError::Blockstore(_)), "{err:?}");
+}
+
+#[test]
+fn test_validator_new_poh_too_slow() {
+    let mut genesis_config = genesis_config();
+    // No machine hashes a slot's worth of ticks in a nanosecond per tick
+    genesis_config.poh_config.hashes_per_tick = Some(1_000);
+    genesis_config.poh_config.target_tick_duration = Duration::from_nanos(1);
+    let (ledger_path, _blockhash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+    let config = ValidatorConfig {
+        no_poh_speed_test: false,
+        ..ValidatorConfig::default_for_test()
+    };
+    let err = start_validator_err(ledger_path.path(), &config);
+    assert!(
+        matches!(err, ValidatorError::PohTooSlow { mine, target } if mine < target),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_validator_new_poh_speed_check_samples() {
+    let mut genesis_config = genesis_config();
+    // Timing a slot worth of hashes would not finish
+    genesis_config.poh_config.hashes_per_tick = Some(1 << 40);
+    genesis_config.poh_config.target_tick_duration = Duration::from_nanos(1);
+    let (ledger_path, _blockhash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+    let config = ValidatorConfig {
+        no_poh_speed_test: false,
+        poh_speed_check_samples: Some(1_000),
+        ..ValidatorConfig::default_for_test()
+    };
+    let err = start_validator_err(ledger_path.path(), &config);
+    assert!(matches!(err, ValidatorError::PohTooSlow { .. }), "{err:?}");
+}
+
+#[test]
+fn test_validator_new_shred_version_mismatch() {
+    let genesis_config = genesis_config();
+    let (ledger_path, _blockhash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+    // The shred version the cluster used before the hard fork
+    let expected_shred_version = compute_shred_version(&genesis_config.hash(), None);
+    let config = ValidatorConfig {
+        new_hard_forks: Some(vec![1]),
+        expected_shred_version: Some(expected_shred_version),
+        ..ValidatorConfig::default_for_test()
+    };
+    let err = start_validator_err(ledger_path.path(), &config);
+    assert!(
+        matches!(
+            err,
+            ValidatorError::ShredVersionMismatch { actual, expected }
+                if actual != expected && expected == expected_shred_version
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_validator_new_not_enough_ledger_data() {
+    let (ledger_path, _blockhash) = create_new_tmp_ledger_auto_delete!(&genesis_config());
+    let wait_for_supermajority_slot = 10;
+    let config = ValidatorConfig {
+        wait_for_supermajority: Some(wait_for_supermajority_slot),
+        ..ValidatorConfig::default_for_test()
+    };
+    let err = start_validator_err(ledger_path.path(), &config);
+    assert!(
+        matches!(
+            err,
+            ValidatorError::NotEnoughLedgerData(current, needed)
+                if current < needed && needed == wait_for_supermajority_slot
+        ),
+        "{err:?}"
+    );
+}
@@ -692,6 +692,27 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         true
     }
 
+    /// Check if the plugin wants the accounts of the snapshot the validator
+    /// started from streamed in pubkey order, resuming after
+    /// `account_snapshot_stream_cursor()` whenever the plugin is loaded or
+    /// reloaded, instead of being notified of them while the snapshot is
+    /// restored. Default is false.
+    ///
+    /// Streamed accounts are passed to `update_account()` with `is_startup`
+    /// set, followed by `notify_end_of_startup()` once the stream completes.
+    /// Returning an error from `update_account()` stops the stream until the
+    /// plugin is reloaded.
+    fn account_snapshot_stream_enabled(&self) -> bool {
+        false
+    }
+
+    /// The pubkey of the last streamed snapshot account the plugin has
+    /// processed, to resume the snapshot stream after, or `None` to stream
+    /// from the first account.
+    fn account_snapshot_stream_cursor(&self) -> Option<[u8; 32]> {
+        None
+    }
+
     /// Check if the plugin is interested in transaction data
     /// Default is false -- if the plugin is interested in
     /// transaction data, please return true.
@@ -1,6 +1,6 @@
 /// Module responsible for notifying plugins of account updates
 use {
-    crate::geyser_plugin_manager::GeyserPluginManager,
+    crate::{geyser_plugin_manager::GeyserPluginManager, snapshot_stream::SnapshotStreamer},
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         ReplicaAccountInfoV3, ReplicaAccountInfoVersions,
     },
@@ -8,7 +8,7 @@ use {
     log::*,
     solana_account::{AccountSharedData, ReadableAccount},
     solana_accounts_db::accounts_update_notifier_interface::{
-        AccountForGeyser, AccountsUpdateNotifierInterface,
+        AccountForGeyser, AccountsUpdateNotifierInterface, SnapshotStreamSession,
     },
     solana_clock::Slot,
     solana_pubkey::Pubkey,
@@ -19,6 +19,7 @@ use {
 pub(crate) struct AccountsUpdateNotifierImpl {
     plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
     snapshot_notifications_enabled: bool,
+    snapshot_streamer: Arc<SnapshotStreamer>,
 }
 
 impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
@@ -56,7 +57,12 @@ impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
             return;
         }
 
-        for plugin in plugin_manager.plugins.iter() {
+        // Plugins streaming the snapshot are notified once their stream completes
+        for plugin in plugin_manager
+            .plugins
+            .iter()
+            .filter(|plugin| !plugin.account_snapshot_stream_enabled())
+        {
             match plugin.notify_end_of_startup() {
                 Err(err) => {
                     error!(
@@ -74,16 +80,22 @@ impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
             }
         }
     }
+
+    fn start_snapshot_stream(&self, session: SnapshotStreamSession) {
+        self.snapshot_streamer.start(session, &self.plugin_manager);
+    }
 }
 
 impl AccountsUpdateNotifierImpl {
     pub fn new(
         plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
         snapshot_notifications_enabled: bool,
+        snapshot_streamer: Arc<SnapshotStreamer>,
     ) -> Self {
         AccountsUpdateNotifierImpl {
             plugin_manager,
             snapshot_notifications_enabled,
+            snapshot_streamer,
         }
     }
 
@@ -134,7 +146,9 @@ impl AccountsUpdateNotifierImpl {
             return;
         }
         for plugin in plugin_manager.plugins.iter() {
-            if !plugin.account_data_notifications_enabled() {
+            if !plugin.account_data_notifications_enabled()
+                || (is_startup && plugin.account_snapshot_stream_enabled())
+            {
                 continue;
             }
             match plugin.update_account(
@@ -239,7 +253,7 @@ mod tests {
                 }),
             ],
         })));
-        let notifier = AccountsUpdateNotifierImpl::new(plugin_manager, false);
+        let notifier = AccountsUpdateNotifierImpl::new(plugin_manager, false, Arc::default());
         let account = AccountSharedData::new(1, 0, &Pubkey::new_unique());
         let pubkey = Pubkey::new_unique();
 
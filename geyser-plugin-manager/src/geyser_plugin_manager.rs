@@ -83,10 +83,13 @@ impl GeyserPluginManager {
         false
     }
 
-    /// Check if there is any plugin interested in account data from snapshot
+    /// Check if there is any plugin interested in account data from snapshot,
+    /// notified while the snapshot is restored rather than streamed afterwards
     pub fn account_data_snapshot_notifications_enabled(&self) -> bool {
         for plugin in &self.plugins {
-            if plugin.account_data_snapshot_notifications_enabled() {
+            if plugin.account_data_snapshot_notifications_enabled()
+                && !plugin.account_snapshot_stream_enabled()
+            {
                 return true;
             }
         }
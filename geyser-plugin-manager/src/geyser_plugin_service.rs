@@ -8,6 +8,7 @@ use {
         geyser_plugin_manager::{GeyserPluginManager, GeyserPluginManagerRequest},
         slot_status_notifier::SlotStatusNotifierImpl,
         slot_status_observer::SlotStatusObserver,
+        snapshot_stream::{SnapshotStreamProgress, SnapshotStreamer},
         transaction_notifier::TransactionNotifierImpl,
     },
     arc_swap::ArcSwap,
@@ -24,6 +25,7 @@ use {
         transaction_notifier_interface::TransactionNotifierArc,
     },
     std::{
+        collections::HashMap,
         path::{Path, PathBuf},
         sync::{
             Arc, RwLock,
@@ -48,6 +50,7 @@ pub struct GeyserPluginService {
     entry_notifier: Option<EntryNotifierArc>,
     block_metadata_notifier: Option<BlockMetadataNotifierArc>,
     slot_status_notifier: Option<SlotStatusNotifier>,
+    snapshot_streamer: Arc<SnapshotStreamer>,
 }
 
 impl GeyserPluginService {
@@ -112,11 +115,13 @@ impl GeyserPluginService {
         let entry_notifications_enabled =
             plugin_manager.load().entry_notifications_enabled() || geyser_plugin_always_enabled;
 
+        let snapshot_streamer = Arc::<SnapshotStreamer>::default();
         let accounts_update_notifier: Option<AccountsUpdateNotifier> =
             if account_data_notifications_enabled {
                 let accounts_update_notifier = AccountsUpdateNotifierImpl::new(
                     plugin_manager.clone(),
                     account_data_snapshot_notifications_enabled,
+                    snapshot_streamer.clone(),
                 );
                 Some(Arc::new(accounts_update_notifier))
             } else {
@@ -175,7 +180,13 @@ impl GeyserPluginService {
         // Initialize plugin manager rpc handler thread if needed
         if let Some((request_receiver, exit)) = rpc_to_plugin_manager_receiver_and_exit {
             let plugin_manager = plugin_manager.clone();
-            Self::start_manager_rpc_handler(plugin_manager, request_receiver, exit)
+            let snapshot_streamer = snapshot_streamer.clone();
+            Self::start_manager_rpc_handler(
+                plugin_manager,
+                snapshot_streamer,
+                request_receiver,
+                exit,
+            )
         };
 
         info!("Started GeyserPluginService");
@@ -188,6 +199,7 @@ impl GeyserPluginService {
             entry_notifier,
             block_metadata_notifier,
             slot_status_notifier,
+            snapshot_streamer,
         })
     }
 
@@ -233,10 +245,17 @@ impl GeyserPluginService {
         self.slot_status_notifier.clone()
     }
 
+    /// Returns the progress of streaming the snapshot accounts to each plugin
+    /// which asked for them, by plugin name
+    pub fn snapshot_stream_progress(&self) -> HashMap<String, SnapshotStreamProgress> {
+        self.snapshot_streamer.progress()
+    }
+
     pub fn join(self) -> thread::Result<()> {
         if let Some(mut slot_status_observer) = self.slot_status_observer {
             slot_status_observer.join()?;
         }
+        self.snapshot_streamer.join()?;
 
         let empty_plugin_manager = GeyserPluginManager {
             plugins: Vec::new(),
@@ -261,6 +280,7 @@ impl GeyserPluginService {
 
     fn start_manager_rpc_handler(
         plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
+        snapshot_streamer: Arc<SnapshotStreamer>,
         request_receiver: Receiver<GeyserPluginManagerRequest>,
         exit: Arc<AtomicBool>,
     ) {
@@ -287,6 +307,9 @@ impl GeyserPluginService {
                                     name,
                                     config_file,
                                 );
+                                if reload_result.is_ok() {
+                                    snapshot_streamer.resume(&plugin_manager, name);
+                                }
                                 response_sender
                                     .send(reload_result)
                                     .expect("Admin rpc service will be waiting for response");
@@ -298,6 +321,9 @@ impl GeyserPluginService {
                             } => {
                                 let load_result =
                                     GeyserPluginManager::load_plugin(&plugin_manager, config_file);
+                                if let Ok(name) = &load_result {
+                                    snapshot_streamer.resume(&plugin_manager, name);
+                                }
                                 response_sender
                                     .send(load_result)
                                     .expect("Admin rpc service will be waiting for response");
@@ -9,6 +9,7 @@ pub mod geyser_plugin_manager;
 pub mod geyser_plugin_service;
 pub mod slot_status_notifier;
 pub mod slot_status_observer;
+pub mod snapshot_stream;
 pub mod transaction_notifier;
 
 pub use geyser_plugin_manager::GeyserPluginManagerRequest;
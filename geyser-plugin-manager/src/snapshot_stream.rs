@@ -0,0 +1,400 @@
+/// Module responsible for streaming the accounts of the snapshot the validator
+/// started from to the plugins which asked for it, in pubkey order.
+///
+/// Plugins report the last account they processed as a cursor, so that a
+/// plugin which is reloaded after it stopped mid-stream resumes after that
+/// account instead of being sent every account again.
+use {
+    crate::geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        ReplicaAccountInfoV3, ReplicaAccountInfoVersions,
+    },
+    arc_swap::ArcSwap,
+    log::*,
+    solana_account::ReadableAccount,
+    solana_accounts_db::accounts_update_notifier_interface::SnapshotStreamSession,
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex, RwLock, Weak},
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+// How often the progress of a stream is published to `progress()`
+const PROGRESS_UPDATE_INTERVAL: usize = 1024;
+// How often the progress of a stream is reported as a datapoint
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Progress of streaming the snapshot accounts to a plugin
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SnapshotStreamProgress {
+    /// The slot of the snapshot
+    pub slot: Slot,
+    /// Pubkeys in the accounts index when the stream started
+    pub num_pubkeys: usize,
+    /// Pubkeys at or before the cursor the stream resumed after
+    pub num_skipped: usize,
+    /// Pubkeys visited by the stream so far, including those of zero lamport
+    /// accounts, which are not streamed
+    pub num_visited: usize,
+    /// Accounts streamed to the plugin so far
+    pub accounts_streamed: usize,
+    pub is_complete: bool,
+}
+
+impl SnapshotStreamProgress {
+    pub fn percent_complete(&self) -> f64 {
+        if self.is_complete || self.num_pubkeys == 0 {
+            return 100.0;
+        }
+        let num_done = self.num_skipped + self.num_visited;
+        (num_done as f64 * 100.0 / self.num_pubkeys as f64).min(100.0)
+    }
+
+    fn report(&self, plugin_name: &str) {
+        solana_metrics::datapoint_info!(
+            "geyser_plugin_snapshot_stream",
+            "plugin" => plugin_name,
+            ("slot", self.slot, i64),
+            ("accounts_streamed", self.accounts_streamed, i64),
+            ("percent_complete", self.percent_complete(), f64),
+            ("is_complete", self.is_complete, bool),
+        );
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SnapshotStreamer {
+    /// Released once every stream completed, since clean does not advance past
+    /// the snapshot slot while the session is alive
+    session: Arc<RwLock<Option<SnapshotStreamSession>>>,
+    progress: Arc<RwLock<HashMap<String, SnapshotStreamProgress>>>,
+    stream_threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl SnapshotStreamer {
+    /// Starts streaming the accounts of `session` to every loaded plugin which
+    /// asked for them, and to any plugin which asks for them once loaded, until
+    /// the streams of all those plugins complete.
+    pub(crate) fn start(
+        &self,
+        session: SnapshotStreamSession,
+        plugin_manager: &ArcSwap<GeyserPluginManager>,
+    ) {
+        *self.session.write().unwrap() = Some(session);
+        for plugin in &plugin_manager.load().plugins {
+            self.stream_to(plugin);
+        }
+    }
+
+    /// Streams the accounts to the plugin named `name`, which was just loaded
+    /// or reloaded, after the cursor it reports.
+    pub(crate) fn resume(&self, plugin_manager: &ArcSwap<GeyserPluginManager>, name: &str) {
+        if let Some(plugin) = plugin_manager
+            .load()
+            .plugins
+            .iter()
+            .find(|plugin| plugin.name() == name)
+        {
+            self.stream_to(plugin);
+        }
+    }
+
+    /// Returns the progress of the latest stream to each plugin, by plugin name
+    pub(crate) fn progress(&self) -> HashMap<String, SnapshotStreamProgress> {
+        self.progress.read().unwrap().clone()
+    }
+
+    /// Waits for the streams to stop, which they do once the accounts db is
+    /// dropped
+    pub(crate) fn join(&self) -> thread::Result<()> {
+        for stream_thread in self.stream_threads.lock().unwrap().drain(..) {
+            stream_thread.join()?;
+        }
+        Ok(())
+    }
+
+    fn stream_to(&self, plugin: &Arc<LoadedGeyserPlugin>) {
+        if !plugin.account_snapshot_stream_enabled() {
+            return;
+        }
+        let streamer_session = self.session.read().unwrap();
+        let Some(session) = streamer_session.clone() else {
+            return;
+        };
+        // Registered before the session lock is released, so that the session
+        // is not released while this stream has yet to complete
+        let name = plugin.name().to_string();
+        if self
+            .progress
+            .write()
+            .unwrap()
+            .entry(name.clone())
+            .or_default()
+            .is_complete
+        {
+            // Plugins are sent the accounts, and notified of the end of
+            // startup, once
+            info!("The snapshot stream to plugin {name} already completed");
+            return;
+        }
+        drop(streamer_session);
+        // Only a weak reference is held while streaming so that the plugin can
+        // be unloaded mid-stream, which stops the stream.
+        let plugin = Arc::downgrade(plugin);
+        let streamer_session = self.session.clone();
+        let progress = self.progress.clone();
+        let stream_thread = thread::Builder::new()
+            .name("solGeyserSnapStr".to_string())
+            .spawn(move || {
+                stream_snapshot(&session, &plugin, &progress);
+                let mut streamer_session = streamer_session.write().unwrap();
+                if progress
+                    .read()
+                    .unwrap()
+                    .values()
+                    .all(|progress| progress.is_complete)
+                    && streamer_session.take().is_some()
+                {
+                    info!("Every snapshot stream completed, releasing the snapshot");
+                }
+            })
+            .unwrap();
+        let mut stream_threads = self.stream_threads.lock().unwrap();
+        stream_threads.retain(|stream_thread| !stream_thread.is_finished());
+        stream_threads.push(stream_thread);
+    }
+}
+
+fn stream_snapshot(
+    session: &SnapshotStreamSession,
+    plugin: &Weak<LoadedGeyserPlugin>,
+    progress_by_plugin: &RwLock<HashMap<String, SnapshotStreamProgress>>,
+) {
+    let Some((name, cursor)) = plugin.upgrade().map(|plugin| {
+        let cursor = plugin.account_snapshot_stream_cursor().map(Pubkey::from);
+        (plugin.name().to_string(), cursor)
+    }) else {
+        return;
+    };
+    let Some(partitions) = session.partition(cursor) else {
+        return;
+    };
+    let mut progress = SnapshotStreamProgress {
+        slot: session.slot(),
+        num_pubkeys: partitions.num_pubkeys(),
+        num_skipped: partitions.num_pubkeys() - partitions.num_after_cursor(),
+        ..SnapshotStreamProgress::default()
+    };
+    info!(
+        "Streaming {} snapshot accounts of slot {} to plugin {name}, after {}",
+        partitions.num_after_cursor(),
+        session.slot(),
+        cursor.map_or("the start".to_string(), |cursor| cursor.to_string()),
+    );
+    let publish = |progress: &SnapshotStreamProgress| {
+        progress_by_plugin
+            .write()
+            .unwrap()
+            .insert(name.clone(), *progress);
+    };
+    publish(&progress);
+
+    let mut last_report = Instant::now();
+    let is_complete = session.stream(partitions, |pubkey, account| {
+        let Some(plugin) = plugin.upgrade() else {
+            info!("Plugin {name} was unloaded, stopping its snapshot stream");
+            return false;
+        };
+        if let Some((account, slot)) = account {
+            let account = ReplicaAccountInfoV3 {
+                pubkey: pubkey.as_ref(),
+                lamports: account.lamports(),
+                owner: account.owner().as_ref(),
+                executable: account.executable(),
+                rent_epoch: account.rent_epoch(),
+                data: account.data(),
+                write_version: 0,
+                txn: None,
+            };
+            if let Err(err) =
+                plugin.update_account(ReplicaAccountInfoVersions::V0_0_3(&account), slot, true)
+            {
+                error!(
+                    "Failed to stream snapshot account {pubkey} to plugin {name}, stopping the \
+                     stream until the plugin is reloaded: {err}"
+                );
+                return false;
+            }
+            progress.accounts_streamed += 1;
+        }
+        progress.num_visited += 1;
+        if progress.num_visited % PROGRESS_UPDATE_INTERVAL == 0 {
+            publish(&progress);
+        }
+        if last_report.elapsed() >= PROGRESS_REPORT_INTERVAL {
+            progress.report(&name);
+            last_report = Instant::now();
+        }
+        true
+    });
+
+    if is_complete {
+        progress.is_complete = true;
+        if let Some(plugin) = plugin.upgrade() {
+            if let Err(err) = plugin.notify_end_of_startup() {
+                error!("Failed to notify the end of the snapshot stream to plugin {name}: {err}");
+            }
+        }
+        info!(
+            "Streamed {} snapshot accounts to plugin {name}",
+            progress.accounts_streamed
+        );
+    }
+    publish(&progress);
+    progress.report(&name);
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        agave_geyser_plugin_interface::geyser_plugin_interface::{
+            GeyserPlugin, GeyserPluginError, Result as PluginResult,
+        },
+        libloading::Library,
+        solana_account::AccountSharedData,
+        solana_accounts_db::accounts_db::AccountsDb,
+    };
+
+    #[derive(Debug, Default)]
+    struct StreamState {
+        /// Pubkeys of the accounts processed, across plugin reloads
+        streamed: Vec<Pubkey>,
+        num_end_of_startup: usize,
+    }
+
+    #[derive(Debug)]
+    struct TestStreamPlugin {
+        state: Arc<Mutex<StreamState>>,
+        /// Fail, as if disconnected, once this many accounts were processed
+        fail_at: Option<usize>,
+    }
+
+    impl GeyserPlugin for TestStreamPlugin {
+        fn name(&self) -> &'static str {
+            "test_stream_plugin"
+        }
+
+        fn update_account(
+            &self,
+            account: ReplicaAccountInfoVersions,
+            _slot: Slot,
+            is_startup: bool,
+        ) -> PluginResult<()> {
+            assert!(is_startup);
+            let ReplicaAccountInfoVersions::V0_0_3(account) = account else {
+                panic!("unexpected account info version");
+            };
+            let mut state = self.state.lock().unwrap();
+            if self.fail_at == Some(state.streamed.len()) {
+                return Err(GeyserPluginError::AccountsUpdateError {
+                    msg: "disconnected".to_string(),
+                });
+            }
+            state
+                .streamed
+                .push(Pubkey::try_from(account.pubkey).unwrap());
+            Ok(())
+        }
+
+        fn notify_end_of_startup(&self) -> PluginResult<()> {
+            self.state.lock().unwrap().num_end_of_startup += 1;
+            Ok(())
+        }
+
+        fn account_snapshot_stream_enabled(&self) -> bool {
+            true
+        }
+
+        fn account_snapshot_stream_cursor(&self) -> Option<[u8; 32]> {
+            let state = self.state.lock().unwrap();
+            state.streamed.last().map(Pubkey::to_bytes)
+        }
+    }
+
+    fn loaded_test_plugin(plugin: TestStreamPlugin) -> Arc<LoadedGeyserPlugin> {
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::this().unwrap();
+
+        Arc::new(LoadedGeyserPlugin::new(
+            Library::from(library),
+            Box::new(plugin),
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_snapshot_stream_resumes_after_cursor() {
+        let accounts_db = Arc::new(AccountsDb::new_single_for_tests());
+        let mut pubkeys: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(100)
+            .collect();
+        pubkeys.sort_unstable();
+        let account = AccountSharedData::new(1, 0, &Pubkey::default());
+        for pubkey in &pubkeys {
+            accounts_db.store_for_tests((0, [(pubkey, &account)].as_slice()));
+        }
+        accounts_db.add_root_and_flush_write_cache(0);
+
+        let state = Arc::new(Mutex::new(StreamState::default()));
+        let plugin_manager = ArcSwap::from_pointee(GeyserPluginManager {
+            plugins: vec![loaded_test_plugin(TestStreamPlugin {
+                state: state.clone(),
+                fail_at: Some(40),
+            })],
+        });
+        let streamer = SnapshotStreamer::default();
+        streamer.start(SnapshotStreamSession::new(&accounts_db, 0), &plugin_manager);
+        streamer.join().unwrap();
+        assert_eq!(state.lock().unwrap().streamed, pubkeys[..40]);
+        assert_eq!(state.lock().unwrap().num_end_of_startup, 0);
+        let progress = streamer.progress()["test_stream_plugin"];
+        assert!(!progress.is_complete);
+        assert_eq!(progress.accounts_streamed, 40);
+        assert_eq!(progress.percent_complete(), 40.0);
+
+        // Reload the plugin, which resumes the stream after its cursor
+        plugin_manager.store(Arc::new(GeyserPluginManager {
+            plugins: vec![loaded_test_plugin(TestStreamPlugin {
+                state: state.clone(),
+                fail_at: None,
+            })],
+        }));
+        streamer.resume(&plugin_manager, "test_stream_plugin");
+        streamer.join().unwrap();
+        // Every account was streamed exactly once overall
+        assert_eq!(state.lock().unwrap().streamed, pubkeys);
+        assert_eq!(state.lock().unwrap().num_end_of_startup, 1);
+        let progress = streamer.progress()["test_stream_plugin"];
+        assert!(progress.is_complete);
+        assert_eq!(progress.num_skipped, 40);
+        assert_eq!(progress.accounts_streamed, 60);
+        assert_eq!(progress.percent_complete(), 100.0);
+
+        // The snapshot is released once every stream completed
+        assert!(streamer.session.read().unwrap().is_none());
+        assert_eq!(accounts_db.scan_tracker.min_ongoing_scan_root(), None);
+
+        // Reloading once the stream completed streams nothing more
+        streamer.resume(&plugin_manager, "test_stream_plugin");
+        streamer.join().unwrap();
+        assert_eq!(state.lock().unwrap().streamed, pubkeys);
+        assert_eq!(state.lock().unwrap().num_end_of_startup, 1);
+    }
+}
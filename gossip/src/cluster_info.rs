@@ -85,7 +85,7 @@ use {
         rc::Rc,
         result::Result,
         sync::{
-            Arc, Mutex, OnceLock, RwLock, RwLockReadGuard,
+            Arc, Mutex, RwLock, RwLockReadGuard,
             atomic::{AtomicBool, Ordering},
         },
         thread::{Builder, JoinHandle, sleep},
@@ -181,7 +181,7 @@ pub struct ClusterInfo {
     /// Network entrypoints
     entrypoints: RwLock<Vec<ContactInfo>>,
     /// Additional pubkeys to preserve during CRDS table trimming.
-    known_validators: OnceLock<HashSet<Pubkey>>,
+    known_validators: RwLock<Option<HashSet<Pubkey>>>,
     outbound_budget: DataBudget,
     my_contact_info: RwLock<ContactInfo>,
     ping_cache: Mutex<PingCache>,
@@ -206,7 +206,7 @@ impl ClusterInfo {
             gossip: CrdsGossip::default(),
             keypair: ArcSwap::from(keypair),
             entrypoints: RwLock::default(),
-            known_validators: OnceLock::new(),
+            known_validators: RwLock::default(),
             outbound_budget: DataBudget::default(),
             my_contact_info: RwLock::new(contact_info),
             ping_cache: Mutex::new(PingCache::new(
@@ -301,7 +301,23 @@ impl ClusterInfo {
         pubkeys: impl IntoIterator<Item = Pubkey>,
     ) -> Result<(), HashSet<Pubkey>> {
         let pubkeys = pubkeys.into_iter().collect();
-        self.known_validators.set(pubkeys)
+        let mut known_validators = self.known_validators.write().unwrap();
+        if known_validators.is_some() {
+            return Err(pubkeys);
+        }
+        *known_validators = Some(pubkeys);
+        Ok(())
+    }
+
+    /// Replaces the pubkeys preserved during CRDS trim, `None` clearing them.
+    /// Takes effect from the next trim.
+    pub fn update_trim_keep_pubkeys(&self, pubkeys: Option<HashSet<Pubkey>>) {
+        *self.known_validators.write().unwrap() = pubkeys;
+    }
+
+    /// Returns the pubkeys preserved during CRDS trim, if any.
+    pub fn trim_keep_pubkeys(&self) -> Option<HashSet<Pubkey>> {
+        self.known_validators.read().unwrap().clone()
     }
 
     /// Attach a channel that receives an owned `ContactInfoSnapshot` for
@@ -516,6 +532,14 @@ impl ClusterInfo {
         Ok(())
     }
 
+    /// Re-advertises all of this node's sockets on the given IP address,
+    /// keeping the ports, and pushes the updated contact info to gossip.
+    pub fn set_public_ip_addr(&self, ip_addr: IpAddr) -> Result<(), ContactInfoError> {
+        self.my_contact_info.write().unwrap().set_ip_addr(ip_addr)?;
+        self.refresh_my_gossip_contact_info();
+        Ok(())
+    }
+
     pub fn lookup_contact_info<R>(
         &self,
         id: &Pubkey,
@@ -1017,6 +1041,24 @@ impl ClusterInfo {
         (labels, txs)
     }
 
+    /// Returns votes and the wallclocks they were pushed at inserted since the
+    /// given cursor.
+    pub fn get_votes_with_wallclocks(&self, cursor: &mut Cursor) -> (Vec<u64>, Vec<Transaction>) {
+        let (wallclocks, txs): (_, Vec<_>) = self
+            .time_gossip_read_lock("get_votes", &self.stats.get_votes)
+            .get_votes(cursor)
+            .map(|vote| {
+                let wallclock = vote.value.wallclock();
+                let CrdsData::Vote(_, vote) = vote.value.data() else {
+                    panic!("this should not happen!");
+                };
+                (wallclock, vote.transaction().clone())
+            })
+            .unzip();
+        self.stats.get_votes_count.add_relaxed(txs.len() as u64);
+        (wallclocks, txs)
+    }
+
     pub fn push_duplicate_shred(
         &self,
         shred: &Shred,
@@ -1175,7 +1217,9 @@ impl ClusterInfo {
             .collect()
     }
 
-    fn refresh_my_gossip_contact_info(&self) {
+    /// Bumps the wallclock of this node's contact info and re-publishes it to
+    /// gossip.
+    pub fn refresh_my_gossip_contact_info(&self) {
         let keypair = self.keypair();
         let node = {
             let mut node = self.my_contact_info.write().unwrap();
@@ -1451,7 +1495,14 @@ impl ClusterInfo {
             .map(ContactInfo::pubkey)
             .copied()
             .chain(std::iter::once(self.id()))
-            .chain(self.known_validators.get().into_iter().flatten().copied())
+            .chain(
+                self.known_validators
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .flatten()
+                    .copied(),
+            )
             .collect();
         self.stats.trim_crds_table.add_relaxed(1);
         let mut gossip_crds = self.gossip.crds.write().unwrap();
@@ -3276,6 +3327,12 @@ mod tests {
         // make sure timestamp filter works
         let votes = cluster_info.get_votes(&mut cursor);
         assert_eq!(votes, vec![]);
+
+        let now = timestamp();
+        let (wallclocks, votes) = cluster_info.get_votes_with_wallclocks(&mut Cursor::default());
+        assert_eq!(votes, vec![tx]);
+        assert_eq!(wallclocks.len(), 1);
+        assert!(wallclocks[0] <= now);
     }
 
     fn new_vote_transaction(slots: Vec<Slot>) -> Transaction {
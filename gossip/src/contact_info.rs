@@ -404,6 +404,27 @@ impl ContactInfo {
         Ok(())
     }
 
+    /// Moves every socket to the given IP address, keeping the respective
+    /// ports. Leaves self unchanged if the IP address is not valid.
+    pub fn set_ip_addr(&mut self, ip_addr: IpAddr) -> Result<(), Error> {
+        let mut port = 0u16;
+        let sockets: Vec<_> = self
+            .sockets
+            .iter()
+            .map(|entry| {
+                port += entry.offset;
+                (entry.key, SocketAddr::new(ip_addr, port))
+            })
+            .collect();
+        for (_, socket) in &sockets {
+            sanitize_socket(socket)?;
+        }
+        for (key, socket) in sockets {
+            self.set_socket(key, socket)?;
+        }
+        Ok(())
+    }
+
     // Removes the socket associated with the specified key.
     fn remove_socket(&mut self, key: u8) {
         if let Some(index) = self.sockets.iter().position(|entry| entry.key == key) {
@@ -1001,6 +1022,36 @@ mod tests {
         assert_matches!(node.alpenglow(), None);
     }
 
+    #[test]
+    fn test_set_ip_addr() {
+        let ip_addr = IpAddr::V4(Ipv4Addr::new(10, 1, 0, 1));
+        let mut node = ContactInfo::new_localhost(&Keypair::new().pubkey(), /*wallclock:*/ 0);
+        let old = node.clone();
+        node.set_ip_addr(ip_addr).unwrap();
+        assert_eq!(node.addrs, [ip_addr]);
+        assert_eq!(node.sockets.len(), old.sockets.len());
+        assert_matches!(sanitize_entries(&node.addrs, &node.sockets), Ok(()));
+        for entry in &old.sockets {
+            let socket = node.get_socket(entry.key).unwrap();
+            assert_eq!(socket.ip(), ip_addr);
+            assert_eq!(socket.port(), old.get_socket(entry.key).unwrap().port());
+            assert_eq!(node.cache[usize::from(entry.key)], socket);
+        }
+        assert_eq!(node.gossip().unwrap(), SocketAddr::new(ip_addr, 8000));
+        assert_eq!(
+            node.tpu(Protocol::QUIC).unwrap(),
+            SocketAddr::new(ip_addr, 8009)
+        );
+
+        // Invalid IP addresses leave the node unchanged.
+        let other = node.clone();
+        assert_matches!(
+            node.set_ip_addr(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            Err(Error::UnspecifiedIpAddr(_))
+        );
+        assert_eq!(node, other);
+    }
+
     #[test]
     fn test_check_duplicate() {
         let mut rng = rand::rng();
@@ -0,0 +1,309 @@
+//! Periodic export of the contact infos held in gossip, for offline analysis
+//! of the network topology.
+//!
+//! Each export is a gzip compressed, bincode serialized [`GossipExport`]
+//! written to a timestamped file. Only the most recent
+//! [`GossipExportConfig::max_files`] exports are kept.
+
+use {
+    crate::{
+        cluster_info::ClusterInfo,
+        contact_info::{ContactInfo, Protocol},
+        epoch_specs::EpochSpecs,
+    },
+    flate2::{Compression, read::GzDecoder, write::GzEncoder},
+    serde::{Deserialize, Serialize},
+    solana_pubkey::Pubkey,
+    solana_time_utils::timestamp,
+    std::{
+        fs::{self, File},
+        io::{self, BufReader, BufWriter, Write},
+        net::SocketAddr,
+        num::NonZeroUsize,
+        path::{Path, PathBuf},
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread::{self, Builder, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+const GOSSIP_EXPORT_FILE_PREFIX: &str = "gossip-export-";
+const GOSSIP_EXPORT_FILE_EXTENSION: &str = ".bin.gz";
+
+#[derive(Clone, Debug)]
+pub struct GossipExportConfig {
+    /// How often the contact infos are exported
+    pub interval: Duration,
+    /// Directory the exports are written to
+    pub dir: PathBuf,
+    /// Number of most recent exports to keep
+    pub max_files: NonZeroUsize,
+}
+
+/// A point-in-time snapshot of the contact infos held in gossip.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GossipExport {
+    /// Milliseconds since the UNIX epoch at which the snapshot was taken
+    pub timestamp: u64,
+    /// Identity of the node which took the snapshot
+    pub node: Pubkey,
+    pub contact_infos: Vec<ExportedContactInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedContactInfo {
+    pub pubkey: Pubkey,
+    pub wallclock: u64,
+    pub shred_version: u16,
+    /// Stake in the current epoch, if known
+    pub stake: Option<u64>,
+    pub gossip: Option<SocketAddr>,
+    pub rpc: Option<SocketAddr>,
+    pub rpc_pubsub: Option<SocketAddr>,
+    pub serve_repair_udp: Option<SocketAddr>,
+    pub serve_repair_quic: Option<SocketAddr>,
+    pub tpu_quic: Option<SocketAddr>,
+    pub tpu_forwards_quic: Option<SocketAddr>,
+    pub tpu_vote_udp: Option<SocketAddr>,
+    pub tpu_vote_quic: Option<SocketAddr>,
+    pub tvu_udp: Option<SocketAddr>,
+    pub tvu_quic: Option<SocketAddr>,
+    pub alpenglow: Option<SocketAddr>,
+}
+
+impl ExportedContactInfo {
+    pub fn new(node: &ContactInfo, stake: Option<u64>) -> Self {
+        Self {
+            pubkey: *node.pubkey(),
+            wallclock: node.wallclock(),
+            shred_version: node.shred_version(),
+            stake,
+            gossip: node.gossip(),
+            rpc: node.rpc(),
+            rpc_pubsub: node.rpc_pubsub(),
+            serve_repair_udp: node.serve_repair(Protocol::UDP),
+            serve_repair_quic: node.serve_repair(Protocol::QUIC),
+            tpu_quic: node.tpu(Protocol::QUIC),
+            tpu_forwards_quic: node.tpu_forwards(Protocol::QUIC),
+            tpu_vote_udp: node.tpu_vote(Protocol::UDP),
+            tpu_vote_quic: node.tpu_vote(Protocol::QUIC),
+            tvu_udp: node.tvu(Protocol::UDP),
+            tvu_quic: node.tvu(Protocol::QUIC),
+            alpenglow: node.alpenglow(),
+        }
+    }
+}
+
+/// Writes snapshots of the contact infos held in gossip to disk.
+pub struct GossipExporter {
+    cluster_info: Arc<ClusterInfo>,
+    config: GossipExportConfig,
+    // Serializes exports, so that concurrent exports get distinct file names
+    // and do not race on rotation.
+    state: Mutex<ExporterState>,
+}
+
+struct ExporterState {
+    epoch_specs: Option<Box<dyn EpochSpecs>>,
+    last_timestamp: u64,
+}
+
+impl GossipExporter {
+    pub fn new(
+        cluster_info: Arc<ClusterInfo>,
+        epoch_specs: Option<Box<dyn EpochSpecs>>,
+        config: GossipExportConfig,
+    ) -> Self {
+        Self {
+            cluster_info,
+            config,
+            state: Mutex::new(ExporterState {
+                epoch_specs,
+                last_timestamp: 0,
+            }),
+        }
+    }
+
+    pub fn config(&self) -> &GossipExportConfig {
+        &self.config
+    }
+
+    /// Exports the contact infos currently held in gossip and returns the path
+    /// of the written file.
+    pub fn export(&self) -> io::Result<PathBuf> {
+        let mut state = self.state.lock().unwrap();
+        let stakes = state
+            .epoch_specs
+            .as_mut()
+            .map(|epoch_specs| epoch_specs.current_epoch_staked_nodes());
+        // Only clones the contact infos under the gossip read lock.
+        let contact_infos: Vec<_> = self
+            .cluster_info
+            .all_peers()
+            .iter()
+            .map(|(node, _)| {
+                let stake = stakes
+                    .as_ref()
+                    .and_then(|stakes| stakes.get(node.pubkey()).copied());
+                ExportedContactInfo::new(node, stake)
+            })
+            .collect();
+        // Keep file names unique even if exports happen within the same
+        // millisecond.
+        let timestamp = timestamp().max(state.last_timestamp + 1);
+        state.last_timestamp = timestamp;
+        let export = GossipExport {
+            timestamp,
+            node: self.cluster_info.id(),
+            contact_infos,
+        };
+
+        fs::create_dir_all(&self.config.dir)?;
+        let path = self.config.dir.join(format!(
+            "{GOSSIP_EXPORT_FILE_PREFIX}{timestamp}{GOSSIP_EXPORT_FILE_EXTENSION}"
+        ));
+        let mut encoder =
+            GzEncoder::new(BufWriter::new(File::create(&path)?), Compression::default());
+        bincode::serialize_into(&mut encoder, &export).map_err(io::Error::other)?;
+        encoder.finish()?.flush()?;
+
+        let num_removed = self.remove_old_exports()?;
+        datapoint_info!(
+            "gossip-export",
+            ("num_contact_infos", export.contact_infos.len(), i64),
+            ("num_removed", num_removed, i64),
+        );
+        Ok(path)
+    }
+
+    // Removes all but the most recent `max_files` exports, returning the
+    // number of removed files.
+    fn remove_old_exports(&self) -> io::Result<usize> {
+        let mut exports = list_gossip_exports(&self.config.dir)?;
+        let num_removed = exports.len().saturating_sub(self.config.max_files.get());
+        for path in exports.drain(..num_removed) {
+            fs::remove_file(path)?;
+        }
+        Ok(num_removed)
+    }
+}
+
+/// Returns the paths of the gossip exports in `dir`, oldest first.
+pub fn list_gossip_exports(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut exports = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let timestamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(GOSSIP_EXPORT_FILE_PREFIX))
+            .and_then(|name| name.strip_suffix(GOSSIP_EXPORT_FILE_EXTENSION))
+            .and_then(|timestamp| timestamp.parse::<u64>().ok());
+        if let Some(timestamp) = timestamp {
+            exports.push((timestamp, path));
+        }
+    }
+    exports.sort_unstable();
+    Ok(exports.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Reads back a file written by [`GossipExporter::export`].
+pub fn read_gossip_export(path: &Path) -> io::Result<GossipExport> {
+    let decoder = GzDecoder::new(BufReader::new(File::open(path)?));
+    bincode::deserialize_from(decoder).map_err(io::Error::other)
+}
+
+/// Periodically runs a [`GossipExporter`].
+pub struct GossipExportService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl GossipExportService {
+    pub fn new(exporter: Arc<GossipExporter>, exit: Arc<AtomicBool>) -> Self {
+        let thread_hdl = Builder::new()
+            .name("solGossipExport".to_string())
+            .spawn(move || {
+                let interval = exporter.config().interval;
+                let mut last_export = Instant::now();
+                while !exit.load(Ordering::Relaxed) {
+                    if last_export.elapsed() >= interval {
+                        if let Err(err) = exporter.export() {
+                            warn!("Failed to export gossip contact infos: {err}");
+                        }
+                        last_export = Instant::now();
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::epoch_specs::TestEpochSpecs, solana_keypair::Keypair,
+        solana_net_utils::SocketAddrSpace, solana_signer::Signer, std::collections::HashMap,
+        tempfile::TempDir,
+    };
+
+    #[test]
+    fn test_gossip_export() {
+        let keypair = Arc::new(Keypair::new());
+        let cluster_info = Arc::new(ClusterInfo::new(
+            ContactInfo::new_localhost(&keypair.pubkey(), timestamp()),
+            keypair,
+            SocketAddrSpace::Unspecified,
+        ));
+        let peers: Vec<_> = (0..4)
+            .map(|_| ContactInfo::new_localhost(&Pubkey::new_unique(), timestamp()))
+            .collect();
+        for peer in &peers {
+            cluster_info.insert_info(peer.clone());
+        }
+        let staked_node = *peers[0].pubkey();
+        let epoch_specs = TestEpochSpecs {
+            staked_nodes: Arc::new(HashMap::from([(staked_node, 42)])),
+            slots_in_epoch: 32,
+            epoch_duration: Duration::from_secs(10),
+        };
+        let dir = TempDir::new().unwrap();
+        let exporter = GossipExporter::new(
+            cluster_info.clone(),
+            Some(Box::new(epoch_specs)),
+            GossipExportConfig {
+                interval: Duration::from_secs(3600),
+                dir: dir.path().to_path_buf(),
+                max_files: NonZeroUsize::new(2).unwrap(),
+            },
+        );
+
+        let path = exporter.export().unwrap();
+        let export = read_gossip_export(&path).unwrap();
+        assert_eq!(export.node, cluster_info.id());
+        let mut expected: Vec<_> = peers
+            .iter()
+            .chain([&cluster_info.my_contact_info()])
+            .map(|node| {
+                let stake = (node.pubkey() == &staked_node).then_some(42);
+                ExportedContactInfo::new(node, stake)
+            })
+            .collect();
+        let mut contact_infos = export.contact_infos;
+        expected.sort_unstable_by_key(|node| node.pubkey);
+        contact_infos.sort_unstable_by_key(|node| node.pubkey);
+        assert_eq!(contact_infos, expected);
+
+        // Only the most recent exports are kept
+        let paths: Vec<_> = (0..3).map(|_| exporter.export().unwrap()).collect();
+        assert_eq!(list_gossip_exports(dir.path()).unwrap(), paths[1..]);
+    }
+}
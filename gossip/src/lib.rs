@@ -22,6 +22,7 @@ pub mod duplicate_shred_listener;
 pub mod epoch_slots;
 pub mod epoch_specs;
 pub mod gossip_error;
+pub mod gossip_export;
 pub mod gossip_service;
 pub mod node;
 #[macro_use]
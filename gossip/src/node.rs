@@ -85,6 +85,12 @@ impl Node {
         }
     }
 
+    /// Socket configuration of the sockets the QUIC servers of the node
+    /// receive on, to rebind them with
+    pub fn quic_server_socket_config() -> SocketConfig {
+        Self::create_socket_configs().primarily_read_quic
+    }
+
     /// create localhost node for tests
     pub fn new_localhost() -> Self {
         let pubkey = solana_pubkey::new_rand();
@@ -299,6 +305,11 @@ impl Node {
         let (alpenglow_port, alpenglow) =
             bind_in_range_with_config(bind_ip_addr, port_range, socket_configs.read_write)
                 .expect("Alpenglow port bind should succeed");
+        // Lets the QUIC server rebind the socket should it die
+        let alpenglow = bind_more_with_config(alpenglow, 1, socket_configs.read_write)
+            .expect("Alpenglow port bind should succeed")
+            .pop()
+            .unwrap();
 
         let (_, block_id_repair) =
             bind_in_range_with_config(bind_ip_addr, port_range, socket_configs.read_write)
@@ -261,7 +261,8 @@ pub fn parse_process_options(ledger_path: &Path, arg_matches: &ArgMatches<'_>) -
         limit_load_slot_count_from_snapshot,
         run_final_accounts_hash_calc,
         debug_keys,
-        run_verification,
+        run_poh_verification: run_verification,
+        run_sig_verification: run_verification,
         allow_dead_slots,
         halt_at_slot,
         use_snapshot_archives_at_startup,
@@ -382,6 +383,9 @@ pub fn get_accounts_db_config(
         scan_filter_for_shrinking,
         num_background_threads: None,
         num_foreground_threads: None,
+        num_startup_hash_threads: None,
+        num_steady_state_hash_threads: None,
+        num_accounts_indexed: None,
     }
 }
 
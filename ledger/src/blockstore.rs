@@ -32,6 +32,7 @@ use {
     itertools::Itertools,
     log::*,
     lru::LruCache,
+    prost::Message as _,
     rand::Rng,
     rayon::iter::{IntoParallelIterator, ParallelIterator},
     rocksdb::{DBRawIterator, LiveFile},
@@ -54,7 +55,9 @@ use {
     solana_sha256_hasher::hashv,
     solana_signature::Signature,
     solana_signer::Signer,
-    solana_storage_proto::{StoredExtendedRewards, StoredTransactionStatusMeta},
+    solana_storage_proto::{
+        StoredExtendedRewards, StoredTransactionStatusMeta, convert::generated,
+    },
     solana_time_utils::timestamp,
     solana_transaction::{
         TransactionVerificationMode,
@@ -319,6 +322,9 @@ pub struct Blockstore {
     // A sender that feeds into the BlockstoreCleanupService request channel
     // to enable manual Blockstore purge requests to be issued
     pub(crate) manual_purge_request_sender: Mutex<Option<Sender<Slot>>>,
+    // Whether the compaction work that can wait is deferred, see
+    // Blockstore::defer_compactions()
+    compactions_deferred: AtomicBool,
     pub slots_stats: SlotsStats,
 }
 
@@ -658,6 +664,7 @@ impl Blockstore {
             max_root,
             lowest_cleanup_slot: RwLock::<Slot>::default(),
             manual_purge_request_sender: Mutex::default(),
+            compactions_deferred: AtomicBool::default(),
             slots_stats: SlotsStats::default(),
         };
         blockstore.cleanup_old_entries()?;
@@ -2731,6 +2738,7 @@ impl Blockstore {
             shred.fec_set_index(),
             shred_source,
             None,
+            shred.payload().len(),
         );
 
         // insert coding shred into rocks
@@ -3540,6 +3548,7 @@ impl Blockstore {
             shred.fec_set_index(),
             shred_source,
             Some(slot_meta),
+            shred.payload().len(),
         );
 
         trace!("inserted shred into slot {slot:?} and index {index:?}");
@@ -4269,11 +4278,13 @@ impl Blockstore {
     where
         F: FnMut(&Pubkey, Slot, u32, Signature, bool) -> Result<()>,
     {
-        let status = status.into();
+        let status: generated::TransactionStatusMeta = status.into();
         let transaction_index = u32::try_from(transaction_index)
             .map_err(|_| BlockstoreError::TransactionIndexOverflow)?;
         self.transaction_status_cf
             .put_protobuf((signature, slot), &status)?;
+        self.slots_stats
+            .record_transaction_status(slot, status.encoded_len());
 
         for (address, writeable) in keys_with_writable {
             write_fn(address, slot, transaction_index, signature, writeable)?;
@@ -5369,11 +5380,41 @@ impl Blockstore {
         *self.lowest_cleanup_slot.read().unwrap()
     }
 
+    /// Makes the updates of the primary instance visible to a blockstore opened
+    /// with `AccessType::Secondary`.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()
+    }
+
     /// Returns whether the blockstore has primary (read and write) access
     pub fn is_primary_access(&self) -> bool {
         self.db.is_primary_access()
     }
 
+    /// Defers the compaction work that can wait, i.e. the cleanup of the
+    /// ledger by the BlockstoreCleanupService, and lowers the number of
+    /// threads running background compactions to `num_compaction_threads`,
+    /// until Self::restore_compactions() is called.
+    pub fn defer_compactions(&self, num_compaction_threads: NonZeroUsize) -> Result<()> {
+        self.db.set_num_compaction_threads(
+            num_compaction_threads.min(self.db.num_compaction_threads()),
+        )?;
+        self.compactions_deferred.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Undoes Self::defer_compactions()
+    pub fn restore_compactions(&self) -> Result<()> {
+        self.db
+            .set_num_compaction_threads(self.db.num_compaction_threads())?;
+        self.compactions_deferred.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn compactions_deferred(&self) -> bool {
+        self.compactions_deferred.load(Ordering::Relaxed)
+    }
+
     /// Scan for any ancestors of the supplied `start_root` that are not
     /// marked as roots themselves. Mark any found slots as roots since
     /// the ancestor of a root is also inherently a root. Returns the
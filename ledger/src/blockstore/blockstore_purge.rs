@@ -356,6 +356,43 @@ impl Blockstore {
             .delete_file_in_range(from_slot, to_slot)
     }
 
+    /// Synchronously compacts the slot-id based column families within
+    /// \[`from_slot`, `to_slot`\], reclaiming the disk space of entries purged
+    /// from that range without waiting on RocksDB's background compactions.
+    pub fn compact_slots(&self, from_slot: Slot, to_slot: Slot) {
+        let mut compact_timer = Measure::start("compact_slots");
+        self.meta_cf.compact_range(from_slot, to_slot);
+        self.bank_hash_cf.compact_range(from_slot, to_slot);
+        self.roots_cf.compact_range(from_slot, to_slot);
+        self.data_shred_cf.compact_range(from_slot, to_slot);
+        self.code_shred_cf.compact_range(from_slot, to_slot);
+        self.dead_slots_cf.compact_range(from_slot, to_slot);
+        self.duplicate_slots_cf.compact_range(from_slot, to_slot);
+        self.erasure_meta_cf.compact_range(from_slot, to_slot);
+        self.orphans_cf.compact_range(from_slot, to_slot);
+        self.index_cf.compact_range(from_slot, to_slot);
+        self.rewards_cf.compact_range(from_slot, to_slot);
+        self.blocktime_cf.compact_range(from_slot, to_slot);
+        self.perf_samples_cf.compact_range(from_slot, to_slot);
+        self.block_height_cf.compact_range(from_slot, to_slot);
+        self.optimistic_slots_cf.compact_range(from_slot, to_slot);
+        self.merkle_root_meta_cf.compact_range(from_slot, to_slot);
+        self.alt_meta_cf.compact_range(from_slot, to_slot);
+        self.alt_index_cf.compact_range(from_slot, to_slot);
+        self.alt_data_shred_cf.compact_range(from_slot, to_slot);
+        self.alt_merkle_root_meta_cf
+            .compact_range(from_slot, to_slot);
+        self.double_merkle_meta_cf.compact_range(from_slot, to_slot);
+        compact_timer.stop();
+
+        datapoint_info!(
+            "blockstore-compact",
+            ("from_slot", from_slot as i64, i64),
+            ("to_slot", to_slot as i64, i64),
+            ("compact_us", compact_timer.as_us() as i64, i64),
+        );
+    }
+
     /// Returns true if the special columns, TransactionStatus and
     /// AddressSignatures, are both empty.
     ///
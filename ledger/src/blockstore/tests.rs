@@ -7591,3 +7591,23 @@ fn test_connectivity_does_not_propagate_through_incomplete_slot() {
     assert!(!blockstore.meta(60).unwrap().unwrap().is_connected());
     assert!(!blockstore.meta(70).unwrap().unwrap().is_connected());
 }
+
+#[test]
+fn test_defer_compactions() {
+    let ledger_path = get_tmp_ledger_path_auto_delete!();
+    let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+    assert!(!blockstore.compactions_deferred());
+
+    blockstore
+        .defer_compactions(NonZeroUsize::new(1).unwrap())
+        .unwrap();
+    assert!(blockstore.compactions_deferred());
+    // Deferring again is a no-op
+    blockstore
+        .defer_compactions(NonZeroUsize::new(1).unwrap())
+        .unwrap();
+    assert!(blockstore.compactions_deferred());
+
+    blockstore.restore_compactions().unwrap();
+    assert!(!blockstore.compactions_deferred());
+}
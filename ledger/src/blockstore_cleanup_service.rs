@@ -77,7 +77,11 @@ impl BlockstoreCleanupService {
                         break;
                     }
 
-                    if last_check_time.elapsed() > CHECK_FOR_CLEANUP_INTERVAL {
+                    // Cleanup is postponed while compactions are deferred,
+                    // and is then checked for as soon as they are restored
+                    if last_check_time.elapsed() > CHECK_FOR_CLEANUP_INTERVAL
+                        && !blockstore.compactions_deferred()
+                    {
                         Self::cleanup_ledger(
                             &blockstore,
                             &cleanup_request_sender,
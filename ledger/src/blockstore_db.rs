@@ -91,11 +91,14 @@ pub(crate) struct Rocks {
     oldest_slot: OldestSlot,
     column_options: Arc<LedgerColumnOptions>,
     write_batch_perf_status: PerfSamplingStatus,
+    /// The number of background compaction threads the database was opened with
+    num_compaction_threads: NonZeroUsize,
 }
 
 impl Rocks {
     pub(crate) fn open(path: PathBuf, options: BlockstoreOptions) -> Result<Rocks> {
         let recovery_mode = options.recovery_mode.clone();
+        let num_compaction_threads = options.num_rocksdb_compaction_threads;
 
         fs::create_dir_all(&path)?;
 
@@ -126,6 +129,18 @@ impl Rocks {
                     error_if_log_file_exists,
                 )?
             }
+            AccessType::Secondary { ref secondary_path } => {
+                info!(
+                    "Opening Rocks with secondary access at {secondary_path:?}. This additional \
+                     access could temporarily degrade other accesses, such as by agave-validator"
+                );
+                DB::open_cf_descriptors_as_secondary(
+                    &db_options,
+                    &path,
+                    secondary_path,
+                    cf_descriptors,
+                )?
+            }
         };
 
         // Delete the now unused program_costs column if it is present
@@ -146,6 +161,7 @@ impl Rocks {
             oldest_slot,
             column_options,
             write_batch_perf_status: PerfSamplingStatus::default(),
+            num_compaction_threads,
         };
 
         rocks.configure_compaction();
@@ -388,6 +404,12 @@ impl Rocks {
         Ok(())
     }
 
+    /// Synchronously compacts the keys within \[`from_key`, `to_key`\].
+    fn compact_range_cf<K: AsRef<[u8]>>(&self, cf: &ColumnFamily, from_key: K, to_key: K) {
+        self.db
+            .compact_range_cf(cf, Some(from_key.as_ref()), Some(to_key.as_ref()));
+    }
+
     pub(crate) fn iterator_cf(
         &self,
         cf: &ColumnFamily,
@@ -426,6 +448,25 @@ impl Rocks {
         }
     }
 
+    /// Replays the updates the primary instance made since the last call. Only
+    /// valid with `AccessType::Secondary`.
+    pub(crate) fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
+    pub(crate) fn num_compaction_threads(&self) -> NonZeroUsize {
+        self.num_compaction_threads
+    }
+
+    /// Changes the number of threads that run background compactions, which
+    /// otherwise remains the number the database was opened with
+    pub(crate) fn set_num_compaction_threads(&self, num_threads: NonZeroUsize) -> Result<()> {
+        self.db
+            .set_options(&[("max_background_compactions", &num_threads.get().to_string())])?;
+        Ok(())
+    }
+
     pub(crate) fn is_primary_access(&self) -> bool {
         self.access_type == AccessType::Primary
             || self.access_type == AccessType::PrimaryForMaintenance
@@ -787,6 +828,18 @@ where
         self.backend
             .delete_file_in_range_cf(self.handle(), from_key, to_key)
     }
+
+    /// Compact the entries whose slot is within \[`from`, `to`\].
+    pub fn compact_range(&self, from: Slot, to: Slot)
+    where
+        C: Column + ColumnName,
+    {
+        // Like delete_range_in_batch(), include every entry of the `to` slot
+        let from_key = <C as Column>::key(&C::as_index(from));
+        let to_key = <C as Column>::key(&C::as_index(to.saturating_add(1)));
+        self.backend
+            .compact_range_cf(self.handle(), from_key, to_key);
+    }
 }
 
 impl<C> LedgerColumn<C>
@@ -1187,7 +1240,10 @@ fn should_enable_compression<C: 'static + Column + ColumnName>() -> bool {
 
 // If the access type is read-only, we don't need to open all of the columns
 fn must_open_all_column_families(access_type: &AccessType) -> bool {
-    !matches!(access_type, AccessType::ReadOnly)
+    !matches!(
+        access_type,
+        AccessType::ReadOnly | AccessType::Secondary { .. }
+    )
 }
 
 #[cfg(test)]
@@ -2,6 +2,7 @@
 
 use {
     crate::blockstore::Blockstore,
+    solana_perf::watchdog::Watchdog,
     std::{
         string::ToString,
         sync::{
@@ -23,13 +24,20 @@ pub struct BlockstoreMetricReportService {
 }
 
 impl BlockstoreMetricReportService {
-    pub fn new(blockstore: Arc<Blockstore>, exit: Arc<AtomicBool>) -> Self {
+    pub fn new(
+        blockstore: Arc<Blockstore>,
+        exit: Arc<AtomicBool>,
+        watchdog: Arc<Watchdog>,
+    ) -> Self {
         let t_cf_metric = Builder::new()
             .name("solRocksCfMtrcs".to_string())
             .spawn(move || {
                 info!("BlockstoreMetricReportService has started");
+                let heartbeat =
+                    watchdog.register("BlockstoreMetricReportService", Duration::from_secs(120));
                 let mut last_report_time = Instant::now();
                 loop {
+                    heartbeat.beat();
                     if exit.load(Ordering::Relaxed) {
                         break;
                     }
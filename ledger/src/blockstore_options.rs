@@ -1,7 +1,7 @@
 use {
     crate::blockstore_db::{default_num_compaction_threads, default_num_flush_threads},
     rocksdb::{DBCompressionType as RocksCompressionType, DBRecoveryMode},
-    std::num::NonZeroUsize,
+    std::{num::NonZeroUsize, path::PathBuf},
 };
 
 /// The subdirectory under ledger directory where the Blockstore lives
@@ -50,6 +50,13 @@ pub enum AccessType {
     /// Read only access; multiple processes can obtain ReadOnly access.
     /// ReadOnly instance gets a static view of the database at creation time.
     ReadOnly,
+    /// Read only access that follows a Primary instance, which may be running
+    /// in another process. Rocks keeps its own info logs and metadata under
+    /// `secondary_path`. Updates made by the primary become visible after
+    /// [`Blockstore::try_catch_up_with_primary`].
+    ///
+    /// [`Blockstore::try_catch_up_with_primary`]: crate::blockstore::Blockstore::try_catch_up_with_primary
+    Secondary { secondary_path: PathBuf },
 }
 
 #[derive(Debug, Clone, PartialEq)]
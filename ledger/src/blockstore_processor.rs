@@ -871,8 +871,10 @@ pub type ProcessSlotCallback = Arc<dyn Fn(&Bank) + Sync + Send>;
 
 #[derive(Default, Clone)]
 pub struct ProcessOptions {
-    /// Run PoH, transaction signature and other transaction verification on the entries.
-    pub run_verification: bool,
+    /// Run PoH and tick verification on the entries.
+    pub run_poh_verification: bool,
+    /// Run transaction signature verification on the entries.
+    pub run_sig_verification: bool,
     pub halt_at_slot: Option<Slot>,
     pub slot_callback: Option<ProcessSlotCallback>,
     pub new_hard_forks: Option<Vec<Slot>>,
@@ -1128,7 +1130,10 @@ fn confirm_full_slot(
     migration_status: &MigrationStatus,
 ) -> result::Result<(), BlockstoreProcessorError> {
     let mut confirmation_timing = ConfirmationTiming::default();
-    let skip_verification = !opts.run_verification;
+    let skip_verification = SkipVerification {
+        poh: !opts.run_poh_verification,
+        signatures: !opts.run_sig_verification,
+    };
     let slot = bank.slot();
     let bank_id = bank.bank_id();
     defer! {
@@ -1664,6 +1669,26 @@ impl AsyncVerificationProgress {
     }
 }
 
+/// Entry verifications to skip while confirming a slot
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SkipVerification {
+    /// Skip PoH and tick verification
+    pub poh: bool,
+    /// Skip transaction signature verification
+    pub signatures: bool,
+}
+
+impl SkipVerification {
+    pub const NONE: Self = Self {
+        poh: false,
+        signatures: false,
+    };
+    pub const ALL: Self = Self {
+        poh: true,
+        signatures: true,
+    };
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn confirm_slot(
     blockstore: &Blockstore,
@@ -1671,7 +1696,7 @@ pub fn confirm_slot(
     replay_tx_thread_pool: &ThreadPool,
     timing: &mut ConfirmationTiming,
     progress: &mut ConfirmationProgress,
-    skip_verification: bool,
+    skip_verification: SkipVerification,
     transaction_status_sender: Option<&TransactionStatusSender>,
     entry_notification_sender: Option<&EntryNotifierSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
@@ -1726,7 +1751,7 @@ fn confirm_slot_with_entries(
     replay_tx_thread_pool: &ThreadPool,
     timing: &mut ConfirmationTiming,
     progress: &mut ConfirmationProgress,
-    skip_verification: bool,
+    skip_verification: SkipVerification,
     transaction_status_sender: Option<&TransactionStatusSender>,
     entry_notification_sender: Option<&EntryNotifierSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
@@ -1774,7 +1799,7 @@ fn confirm_slot_with_components(
     replay_tx_thread_pool: &ThreadPool,
     timing: &mut ConfirmationTiming,
     progress: &mut ConfirmationProgress,
-    skip_verification: bool,
+    skip_verification: SkipVerification,
     transaction_status_sender: Option<&TransactionStatusSender>,
     entry_notification_sender: Option<&EntryNotifierSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
@@ -1921,7 +1946,7 @@ fn confirm_slot_entries(
     slot_entries_load_result: (Vec<Entry>, u64, bool),
     timing: &mut ConfirmationTiming,
     progress: &mut ConfirmationProgress,
-    skip_verification: bool,
+    skip_verification: SkipVerification,
     transaction_status_sender: Option<&TransactionStatusSender>,
     entry_notification_sender: Option<&EntryNotifierSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
@@ -1978,7 +2003,7 @@ fn confirm_slot_entries(
          num_txs: {num_txs}, slot_full: {slot_full}",
     );
 
-    if !skip_verification {
+    if !skip_verification.poh {
         let tick_hash_count = &mut progress.tick_hash_count;
         verify_ticks(bank, &entries, slot_full, tick_hash_count, migration_status).map_err(
             |err| {
@@ -2000,7 +2025,7 @@ fn confirm_slot_entries(
     }
 
     let last_entry_hash = entries.last().map(|e| e.hash);
-    if !skip_verification {
+    if !skip_verification.poh {
         let start_hash = progress.last_entry;
         let verify_entries = entry::entries_to_verification_data(&entries);
         progress.async_verification().spawn(
@@ -2060,7 +2085,7 @@ fn confirm_slot_entries(
         }
     };
     let bank_id = bank.bank_id();
-    if skip_verification {
+    if skip_verification.signatures {
         if let Some(replay_vote_sender) = replay_vote_sender {
             let message_hashes = unverified_signatures.vote_transaction_message_hashes();
             if !message_hashes.is_empty() {
@@ -3155,7 +3180,7 @@ pub mod tests {
                 // just pass the original session if it is a Primary variant
                 test_process_blockstore(genesis_config, blockstore, opts)
             }
-            AccessType::ReadOnly => {
+            AccessType::ReadOnly | AccessType::Secondary { .. } => {
                 let read_only_blockstore = Blockstore::open_with_options(
                     blockstore.ledger_path(),
                     BlockstoreOptions {
@@ -3227,7 +3252,8 @@ pub mod tests {
             &genesis_config,
             &blockstore,
             &ProcessOptions {
-                run_verification: true,
+                run_poh_verification: true,
+                run_sig_verification: true,
                 ..ProcessOptions::default()
             },
             blockstore_access_type.clone(),
@@ -3238,7 +3264,7 @@ pub mod tests {
         match blockstore_access_type {
             // In ReadOnly access even though a dead slot
             // will be identified, it won't actually be marked dead.
-            AccessType::ReadOnly => {
+            AccessType::ReadOnly | AccessType::Secondary { .. } => {
                 assert_eq!(dead_slots.len(), 0);
             }
             AccessType::Primary | AccessType::PrimaryForMaintenance => {
@@ -3282,7 +3308,8 @@ pub mod tests {
             &genesis_config,
             &blockstore,
             &ProcessOptions {
-                run_verification: true,
+                run_poh_verification: true,
+                run_sig_verification: true,
                 ..ProcessOptions::default()
             },
         );
@@ -3296,7 +3323,8 @@ pub mod tests {
             &genesis_config,
             &blockstore,
             &ProcessOptions {
-                run_verification: true,
+                run_poh_verification: true,
+                run_sig_verification: true,
                 ..ProcessOptions::default()
             },
         );
@@ -3349,7 +3377,8 @@ pub mod tests {
         );
 
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let (bank_forks, ..) = test_process_blockstore(&genesis_config, &blockstore, &opts);
@@ -3412,7 +3441,8 @@ pub mod tests {
         fill_blockstore_slot_with_ticks(&blockstore, ticks_per_slot, 2, 1, blockhash);
 
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let (bank_forks, ..) = test_process_blockstore(&genesis_config, &blockstore, &opts);
@@ -3428,7 +3458,8 @@ pub mod tests {
                slot 2 (all ticks)
         */
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         fill_blockstore_slot_with_ticks(&blockstore, ticks_per_slot, 3, 0, blockhash);
@@ -3495,7 +3526,8 @@ pub mod tests {
         blockstore.set_roots([0, 1, 4].iter()).unwrap();
 
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let (bank_forks, ..) = test_process_blockstore(&genesis_config, &blockstore, &opts);
@@ -3575,7 +3607,8 @@ pub mod tests {
         blockstore.set_roots([0, 1].iter()).unwrap();
 
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let (bank_forks, ..) = test_process_blockstore(&genesis_config, &blockstore, &opts);
@@ -3775,7 +3808,8 @@ pub mod tests {
 
         // Check that we can properly restart the ledger / leader scheduler doesn't fail
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let (bank_forks, ..) = test_process_blockstore(&genesis_config, &blockstore, &opts);
@@ -3926,7 +3960,8 @@ pub mod tests {
             )
             .unwrap();
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let (bank_forks, ..) = test_process_blockstore(&genesis_config, &blockstore, &opts);
@@ -3956,7 +3991,8 @@ pub mod tests {
 
         let blockstore = Blockstore::open(ledger_path.path()).unwrap();
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let (bank_forks, ..) = test_process_blockstore(&genesis_config, &blockstore, &opts);
@@ -4874,7 +4910,8 @@ pub mod tests {
 
         // Specify halting at slot 0
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             halt_at_slot: Some(0),
             ..ProcessOptions::default()
         };
@@ -4926,7 +4963,8 @@ pub mod tests {
         let bank_forks = BankForks::new_rw_arc(Bank::new_for_tests(&genesis_config));
         let bank0 = bank_forks.read().unwrap().get_with_scheduler(0).unwrap();
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
         let replay_tx_thread_pool = create_thread_pool(1);
@@ -5362,7 +5400,8 @@ pub mod tests {
         }
 
         let opts = ProcessOptions {
-            run_verification: true,
+            run_poh_verification: true,
+            run_sig_verification: true,
             ..ProcessOptions::default()
         };
 
@@ -5552,7 +5591,7 @@ pub mod tests {
             (slot_entries, 0, slot_full),
             &mut ConfirmationTiming::default(),
             &mut progress,
-            false,
+            SkipVerification::NONE,
             None,
             None,
             None,
@@ -5647,7 +5686,7 @@ pub mod tests {
             (vec![entry], 0, false),
             &mut timing,
             &mut progress,
-            false,
+            SkipVerification::NONE,
             Some(&transaction_status_sender),
             None,
             None,
@@ -5695,7 +5734,7 @@ pub mod tests {
             (vec![entry], 0, false),
             &mut timing,
             &mut progress,
-            false,
+            SkipVerification::NONE,
             Some(&transaction_status_sender),
             None,
             None,
@@ -5741,6 +5780,73 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_confirm_slot_entries_skip_verification() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100 * LAMPORTS_PER_SOL);
+        let genesis_hash = genesis_config.hash();
+        let (bank, _bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+        let bank = BankWithScheduler::new_without_scheduler(bank);
+        let replay_tx_thread_pool = create_thread_pool(1);
+        let confirm =
+            |entry: Entry, skip_verification| -> result::Result<(), BlockstoreProcessorError> {
+                let mut progress = ConfirmationProgress::new(genesis_hash);
+                confirm_slot_entries(
+                    &bank,
+                    &replay_tx_thread_pool,
+                    (vec![entry], 0, false),
+                    &mut ConfirmationTiming::default(),
+                    &mut progress,
+                    skip_verification,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    &MigrationStatus::default(),
+                )?;
+                progress.wait_for_all_verification_results(&mut 0, &mut 0)
+            };
+        // Each transaction pays a new account so that it is never a duplicate
+        let transfer =
+            || system_transaction::transfer(&mint_keypair, &Pubkey::new_unique(), 1, genesis_hash);
+        let bad_signature_entry = || {
+            let mut tx = transfer();
+            tx.signatures[0] = Keypair::new().sign_message(&tx.message_data());
+            Entry::new(&genesis_hash, 1, vec![tx])
+        };
+        let bad_poh_entry = || Entry::new(&Hash::new_unique(), 1, vec![transfer()]);
+
+        // Skipping PoH verification still verifies signatures
+        let skip_poh = SkipVerification {
+            poh: true,
+            signatures: false,
+        };
+        assert_matches!(
+            confirm(bad_signature_entry(), skip_poh),
+            Err(BlockstoreProcessorError::InvalidTransaction(
+                TransactionError::SignatureFailure
+            ))
+        );
+        assert_matches!(confirm(bad_poh_entry(), skip_poh), Ok(()));
+
+        // Skipping signature verification still verifies PoH
+        let skip_signatures = SkipVerification {
+            poh: false,
+            signatures: true,
+        };
+        assert_matches!(
+            confirm(bad_poh_entry(), skip_signatures),
+            Err(BlockstoreProcessorError::InvalidBlock(
+                BlockError::InvalidEntryHash
+            ))
+        );
+        assert_matches!(confirm(bad_signature_entry(), skip_signatures), Ok(()));
+    }
+
     #[test]
     fn test_async_verification_progress_drop() {
         let exit_barrier = Arc::new(Barrier::new(2));
@@ -6249,7 +6355,7 @@ pub mod tests {
                 &replay_tx_thread_pool,
                 &mut ConfirmationTiming::default(),
                 &mut ConfirmationProgress::new(bank0.last_blockhash()),
-                false,
+                SkipVerification::NONE,
                 None,
                 None,
                 None,
@@ -6286,7 +6392,7 @@ pub mod tests {
                 &replay_tx_thread_pool,
                 &mut ConfirmationTiming::default(),
                 &mut ConfirmationProgress::new(bank0.last_blockhash()),
-                true,
+                SkipVerification::ALL,
                 None,
                 None,
                 None,
@@ -75,7 +75,81 @@ impl EntryNotifierService {
         self.sender.clone()
     }
 
+    /// Returns the number of entries queued but not yet notified
+    pub fn backlog(&self) -> usize {
+        self.sender.len()
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.thread_hdl.join()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::entry_notifier_interface::EntryNotifier, solana_hash::Hash,
+        std::time::Instant,
+    };
+
+    /// Blocks every notification until released
+    struct BlockingEntryNotifier(Receiver<()>);
+
+    impl EntryNotifier for BlockingEntryNotifier {
+        fn notify_entry(
+            &self,
+            _slot: Slot,
+            _index: usize,
+            _entry: &EntrySummary,
+            _starting_transaction_index: usize,
+        ) {
+            let _ = self.0.recv();
+        }
+    }
+
+    fn wait_for_backlog(service: &EntryNotifierService, backlog: usize) {
+        let start = Instant::now();
+        while service.backlog() != backlog {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_backlog() {
+        let (release_sender, release_receiver) = unbounded();
+        let exit = Arc::new(AtomicBool::new(false));
+        let service = EntryNotifierService::new(
+            Arc::new(BlockingEntryNotifier(release_receiver)),
+            exit.clone(),
+        );
+        assert_eq!(service.backlog(), 0);
+
+        for index in 0..3 {
+            service
+                .sender()
+                .send(EntryNotification {
+                    slot: 1,
+                    index,
+                    entry: EntrySummary {
+                        num_hashes: 1,
+                        hash: Hash::default(),
+                        num_transactions: 0,
+                    },
+                    starting_transaction_index: 0,
+                })
+                .unwrap();
+        }
+        // The first entry is being notified
+        wait_for_backlog(&service, 2);
+
+        release_sender.send(()).unwrap();
+        wait_for_backlog(&service, 1);
+
+        drop(release_sender);
+        wait_for_backlog(&service, 0);
+
+        exit.store(true, Ordering::Relaxed);
+        service.join().unwrap();
+    }
+}
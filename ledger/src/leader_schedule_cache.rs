@@ -5,12 +5,14 @@ use {
     solana_clock::{Epoch, Slot},
     solana_epoch_schedule::EpochSchedule,
     solana_leader_schedule::{FixedSchedule, LeaderSchedule, SlotLeader},
+    solana_measure::measure_us,
     solana_pubkey::Pubkey,
     solana_runtime::{bank::Bank, leader_schedule_utils},
+    solana_vote::vote_account::VoteAccountsHashMap,
     std::{
         collections::{HashMap, VecDeque, hash_map::Entry},
         sync::{
-            Arc, RwLock,
+            Arc, Mutex, RwLock,
             atomic::{AtomicU64, Ordering},
         },
     },
@@ -26,6 +28,38 @@ impl Default for CacheCapacity {
     }
 }
 
+/// (vote address, node pubkey, stake) of every vote account a leader schedule
+/// was derived from, sorted by vote address.
+type ScheduleInputs = Vec<(Pubkey, Pubkey, u64)>;
+
+fn schedule_inputs(vote_accounts: &VoteAccountsHashMap) -> ScheduleInputs {
+    vote_accounts
+        .iter()
+        .map(|(vote_address, (stake, vote_account))| {
+            (*vote_address, *vote_account.node_pubkey(), *stake)
+        })
+        .sorted_unstable()
+        .collect()
+}
+
+/// A leader schedule computed ahead of time from predicted epoch stakes.
+struct PrecomputedSchedule {
+    epoch: Epoch,
+    inputs: ScheduleInputs,
+    schedule: Arc<LeaderSchedule>,
+}
+
+/// Outcomes of handing precomputed leader schedules to the cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrecomputedScheduleCounts {
+    /// Precomputed schedules whose inputs matched the epoch stakes and were
+    /// used instead of computing the schedule again
+    pub reused: u64,
+    /// Precomputed schedules discarded because their inputs did not match
+    /// the epoch stakes
+    pub mismatched: u64,
+}
+
 #[derive(Default)]
 pub struct LeaderScheduleCache {
     // Map from an epoch to a leader schedule for that epoch
@@ -34,6 +68,9 @@ pub struct LeaderScheduleCache {
     max_epoch: AtomicU64,
     max_schedules: CacheCapacity,
     fixed_schedule: Option<Arc<FixedSchedule>>,
+    precomputed_schedule: Mutex<Option<PrecomputedSchedule>>,
+    precomputed_schedules_reused: AtomicU64,
+    precomputed_schedules_mismatched: AtomicU64,
 }
 
 impl LeaderScheduleCache {
@@ -49,6 +86,9 @@ impl LeaderScheduleCache {
             max_epoch: AtomicU64::new(max_epoch),
             max_schedules: CacheCapacity::default(),
             fixed_schedule: None,
+            precomputed_schedule: Mutex::default(),
+            precomputed_schedules_reused: AtomicU64::default(),
+            precomputed_schedules_mismatched: AtomicU64::default(),
         };
 
         // Calculate the schedule for all epochs in epoch stakes
@@ -81,6 +121,49 @@ impl LeaderScheduleCache {
         }
     }
 
+    /// Computes the leader schedule of `epoch` from predicted epoch stakes
+    /// ahead of time, so that rooting the epoch boundary does not have to.
+    ///
+    /// The precomputed schedule is only used once the authoritative epoch
+    /// stakes of `epoch` are known and match `vote_accounts`; otherwise it is
+    /// discarded and the schedule is computed as usual.
+    pub fn precompute_leader_schedule(&self, epoch: Epoch, vote_accounts: &VoteAccountsHashMap) {
+        if self.fixed_schedule.is_some() || self.get_epoch_leader_schedule(epoch).is_some() {
+            return;
+        }
+        let Some(schedule) = leader_schedule_utils::leader_schedule_from_vote_accounts(
+            epoch,
+            &self.epoch_schedule,
+            vote_accounts,
+        ) else {
+            return;
+        };
+        *self.precomputed_schedule.lock().unwrap() = Some(PrecomputedSchedule {
+            epoch,
+            inputs: schedule_inputs(vote_accounts),
+            schedule: Arc::new(schedule),
+        });
+    }
+
+    /// Returns the epoch of the precomputed leader schedule that is waiting to
+    /// be handed to the cache, if any.
+    pub fn precomputed_leader_schedule_epoch(&self) -> Option<Epoch> {
+        self.precomputed_schedule
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|precomputed| precomputed.epoch)
+    }
+
+    pub fn precomputed_schedule_counts(&self) -> PrecomputedScheduleCounts {
+        PrecomputedScheduleCounts {
+            reused: self.precomputed_schedules_reused.load(Ordering::Relaxed),
+            mismatched: self
+                .precomputed_schedules_mismatched
+                .load(Ordering::Relaxed),
+        }
+    }
+
     pub fn slot_leader_at(&self, slot: Slot, bank: Option<&Bank>) -> Option<SlotLeader> {
         if let Some(bank) = bank {
             self.slot_leader_at_else_compute(slot, bank)
@@ -202,10 +285,50 @@ impl LeaderScheduleCache {
         }
     }
 
+    /// Takes the precomputed schedule of `epoch` if it was derived from the
+    /// same stakes as `vote_accounts`.
+    fn take_precomputed_schedule(
+        &self,
+        epoch: Epoch,
+        vote_accounts: &VoteAccountsHashMap,
+    ) -> Option<Arc<LeaderSchedule>> {
+        let mut precomputed_schedule = self.precomputed_schedule.lock().unwrap();
+        if precomputed_schedule.as_ref()?.epoch != epoch {
+            return None;
+        }
+        let precomputed = precomputed_schedule.take()?;
+        if precomputed.inputs == schedule_inputs(vote_accounts) {
+            self.precomputed_schedules_reused
+                .fetch_add(1, Ordering::Relaxed);
+            Some(precomputed.schedule)
+        } else {
+            self.precomputed_schedules_mismatched
+                .fetch_add(1, Ordering::Relaxed);
+            warn!("Discarding precomputed leader schedule for epoch {epoch}: epoch stakes changed");
+            None
+        }
+    }
+
     fn compute_leader_schedule(&self, epoch: Epoch, bank: &Bank) -> Option<Arc<LeaderSchedule>> {
-        let leader_schedule = leader_schedule_utils::leader_schedule(epoch, bank);
+        let vote_accounts = bank.epoch_vote_accounts(epoch)?;
+        let precomputed_schedule = self.take_precomputed_schedule(epoch, vote_accounts);
+        let precomputed = precomputed_schedule.is_some();
+        let (leader_schedule, compute_us) = measure_us!(precomputed_schedule.or_else(|| {
+            leader_schedule_utils::leader_schedule_from_vote_accounts(
+                epoch,
+                bank.epoch_schedule(),
+                vote_accounts,
+            )
+            .map(Arc::new)
+        }));
+        datapoint_info!(
+            "leader_schedule_cache-compute_leader_schedule",
+            ("epoch", epoch, i64),
+            ("slot", bank.slot(), i64),
+            ("compute_us", compute_us, i64),
+            ("precomputed", precomputed, bool),
+        );
         leader_schedule.map(|leader_schedule| {
-            let leader_schedule = Arc::new(leader_schedule);
             let (ref mut cached_schedules, ref mut order) = *self.cached_schedules.write().unwrap();
             // Check to see if schedule exists in case somebody already inserted in the time we were
             // waiting for the lock
@@ -250,9 +373,12 @@ mod tests {
         },
         solana_keypair::Keypair,
         solana_leader_schedule::{LeaderSchedule, NUM_CONSECUTIVE_LEADER_SLOTS, SlotLeader},
-        solana_runtime::stake_utils,
+        solana_runtime::{bank_forks::BankForks, stake_utils},
         solana_signer::Signer,
-        std::{sync::Arc, thread::Builder},
+        std::{
+            sync::{Arc, RwLock},
+            thread::Builder,
+        },
     };
 
     #[test]
@@ -625,4 +751,117 @@ mod tests {
         assert_eq!(bank2.get_epoch_and_slot_index(224).0, 3);
         assert!(cache.slot_leader_at(224, Some(bank2.as_ref())).is_none());
     }
+
+    fn new_bank_before_and_after_epoch_boundary(
+        slots_per_epoch: u64,
+    ) -> (Arc<Bank>, Arc<RwLock<BankForks>>, Slot) {
+        let GenesisConfigInfo {
+            mut genesis_config, ..
+        } = create_genesis_config(10_000 * bootstrap_validator_stake_lamports());
+        genesis_config.epoch_schedule =
+            EpochSchedule::custom(slots_per_epoch, slots_per_epoch, false);
+        let (bank, bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+        let last_slot_in_epoch = bank.epoch_schedule().get_last_slot_in_epoch(0);
+        let bank = Bank::new_from_parent_with_bank_forks(
+            bank_forks.as_ref(),
+            bank,
+            SlotLeader::default(),
+            last_slot_in_epoch,
+        );
+        (bank, bank_forks, last_slot_in_epoch + 1)
+    }
+
+    #[test]
+    fn test_precomputed_leader_schedule_reused() {
+        let (bank, bank_forks, first_slot_in_next_epoch) =
+            new_bank_before_and_after_epoch_boundary(MINIMUM_SLOTS_PER_EPOCH);
+        let cache = LeaderScheduleCache::new_from_bank(&bank);
+        let precompute_cache = LeaderScheduleCache::new_from_bank(&bank);
+
+        let leader_schedule_epoch = bank.get_leader_schedule_epoch(first_slot_in_next_epoch);
+        assert!(bank.epoch_vote_accounts(leader_schedule_epoch).is_none());
+        precompute_cache.precompute_leader_schedule(
+            leader_schedule_epoch,
+            bank.precompute_next_epoch_stakes().as_ref(),
+        );
+        assert_eq!(
+            precompute_cache.precomputed_leader_schedule_epoch(),
+            Some(leader_schedule_epoch)
+        );
+
+        // Cross the epoch boundary and root the first bank of the new epoch
+        let bank = Bank::new_from_parent_with_bank_forks(
+            bank_forks.as_ref(),
+            bank,
+            SlotLeader::default(),
+            first_slot_in_next_epoch,
+        );
+        cache.set_root(&bank);
+        precompute_cache.set_root(&bank);
+        // Crossing the boundary reused the precomputed activated stake
+        assert_eq!(bank.precomputed_activated_stake_counts().reused, 1);
+
+        assert_eq!(
+            cache
+                .get_epoch_leader_schedule(leader_schedule_epoch)
+                .unwrap(),
+            precompute_cache
+                .get_epoch_leader_schedule(leader_schedule_epoch)
+                .unwrap()
+        );
+        assert_eq!(
+            cache.precomputed_schedule_counts(),
+            PrecomputedScheduleCounts::default()
+        );
+        assert_eq!(
+            precompute_cache.precomputed_schedule_counts(),
+            PrecomputedScheduleCounts {
+                reused: 1,
+                mismatched: 0,
+            }
+        );
+        assert_eq!(precompute_cache.precomputed_leader_schedule_epoch(), None);
+    }
+
+    #[test]
+    fn test_precomputed_leader_schedule_mismatch() {
+        let (bank, bank_forks, first_slot_in_next_epoch) =
+            new_bank_before_and_after_epoch_boundary(MINIMUM_SLOTS_PER_EPOCH);
+        let cache = LeaderScheduleCache::new_from_bank(&bank);
+        let precompute_cache = LeaderScheduleCache::new_from_bank(&bank);
+
+        // Predict with a stake that will not be in the epoch stakes
+        let leader_schedule_epoch = bank.get_leader_schedule_epoch(first_slot_in_next_epoch);
+        let mut vote_accounts = bank.precompute_next_epoch_stakes().as_ref().clone();
+        for (stake, _vote_account) in vote_accounts.values_mut() {
+            *stake += 1;
+        }
+        precompute_cache.precompute_leader_schedule(leader_schedule_epoch, &vote_accounts);
+
+        let bank = Bank::new_from_parent_with_bank_forks(
+            bank_forks.as_ref(),
+            bank,
+            SlotLeader::default(),
+            first_slot_in_next_epoch,
+        );
+        cache.set_root(&bank);
+        precompute_cache.set_root(&bank);
+
+        // The authoritative schedule is used regardless of the prediction
+        assert_eq!(
+            cache
+                .get_epoch_leader_schedule(leader_schedule_epoch)
+                .unwrap(),
+            precompute_cache
+                .get_epoch_leader_schedule(leader_schedule_epoch)
+                .unwrap()
+        );
+        assert_eq!(
+            precompute_cache.precomputed_schedule_counts(),
+            PrecomputedScheduleCounts {
+                reused: 0,
+                mismatched: 1,
+            }
+        );
+    }
 }
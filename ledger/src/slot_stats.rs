@@ -1,9 +1,17 @@
 use {
     crate::blockstore_meta::{BlockLocation, SlotMeta},
     bitflags::bitflags,
+    dashmap::DashMap,
     lru::LruCache,
     solana_clock::Slot,
-    std::{collections::HashMap, num::NonZeroUsize, sync::Mutex},
+    std::{
+        collections::HashMap,
+        num::NonZeroUsize,
+        sync::{
+            Mutex,
+            atomic::{AtomicU64, Ordering},
+        },
+    },
 };
 
 const SLOTS_STATS_CACHE_CAPACITY: NonZeroUsize = NonZeroUsize::new(300).unwrap();
@@ -32,11 +40,22 @@ struct LocationShredStats {
     is_full: bool,
 }
 
+/// Bytes written to the blockstore for a slot
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotWriteStats {
+    /// Payload bytes of the data and coding shreds inserted
+    pub shred_bytes: u64,
+    /// Encoded bytes of the transaction statuses written
+    pub transaction_status_bytes: u64,
+}
+
 #[derive(Clone, Default)]
 pub struct SlotStats {
     original: LocationShredStats,
     alternate: LocationShredStats,
     flags: SlotFlags,
+    /// Payload bytes of the data and coding shreds inserted
+    shred_bytes: u64,
 }
 
 impl SlotStats {
@@ -103,12 +122,16 @@ impl SlotStats {
 
 pub struct SlotsStats {
     pub stats: Mutex<LruCache<Slot, SlotStats>>,
+    /// Encoded bytes of the transaction statuses written per slot. Kept apart
+    /// from `stats` as it is updated for every transaction.
+    transaction_status_bytes: DashMap<Slot, AtomicU64>,
 }
 
 impl Default for SlotsStats {
     fn default() -> Self {
         Self {
             stats: Mutex::new(LruCache::new(SLOTS_STATS_CACHE_CAPACITY)),
+            transaction_status_bytes: DashMap::default(),
         }
     }
 }
@@ -143,10 +166,12 @@ impl SlotsStats {
         fec_set_index: u32,
         source: ShredSource,
         slot_meta: Option<&SlotMeta>,
+        payload_len: usize,
     ) {
         let (slot_full_reporting_info, evicted) = {
             let mut stats = self.stats.lock().unwrap();
             let (slot_stats, evicted) = Self::get_or_default_with_eviction_check(&mut stats, slot);
+            slot_stats.shred_bytes += payload_len as u64;
             let location_stats = slot_stats.location_stats_mut(location);
             match source {
                 ShredSource::Recovered => location_stats.num_recovered += 1,
@@ -206,6 +231,39 @@ impl SlotsStats {
         }
     }
 
+    pub(crate) fn record_transaction_status(&self, slot: Slot, encoded_len: usize) {
+        if let Some(bytes) = self.transaction_status_bytes.get(&slot) {
+            bytes.fetch_add(encoded_len as u64, Ordering::Relaxed);
+            return;
+        }
+        // Only as many slots as in the stats cache are tracked
+        let min_slot = slot.saturating_sub(SLOTS_STATS_CACHE_CAPACITY.get() as Slot);
+        self.transaction_status_bytes
+            .retain(|tracked_slot, _| *tracked_slot > min_slot);
+        self.transaction_status_bytes
+            .entry(slot)
+            .or_default()
+            .fetch_add(encoded_len as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the bytes written for the slot, if it is still tracked
+    pub fn get_write_stats(&self, slot: Slot) -> Option<SlotWriteStats> {
+        let shred_bytes = self
+            .stats
+            .lock()
+            .unwrap()
+            .peek(&slot)
+            .map(|slot_stats| slot_stats.shred_bytes);
+        let transaction_status_bytes = self
+            .transaction_status_bytes
+            .get(&slot)
+            .map(|bytes| bytes.load(Ordering::Relaxed));
+        (shred_bytes.is_some() || transaction_status_bytes.is_some()).then(|| SlotWriteStats {
+            shred_bytes: shred_bytes.unwrap_or_default(),
+            transaction_status_bytes: transaction_status_bytes.unwrap_or_default(),
+        })
+    }
+
     fn add_flag(&self, slot: Slot, flag: SlotFlags) {
         let evicted = {
             let mut stats = self.stats.lock().unwrap();
@@ -8,18 +8,22 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
     ValidatorConfig {
         log_config: config.log_config.clone(),
         expected_genesis_hash: config.expected_genesis_hash,
+        genesis_hash_mismatch_policy: config.genesis_hash_mismatch_policy,
         expected_bank_hash: config.expected_bank_hash,
+        bank_hash_mismatch_policy: config.bank_hash_mismatch_policy,
         expected_shred_version: config.expected_shred_version,
         voting_disabled: config.voting_disabled,
         account_paths: config.account_paths.clone(),
         account_snapshot_paths: config.account_snapshot_paths.clone(),
         rpc_config: config.rpc_config.clone(),
+        enable_completed_data_sets: config.enable_completed_data_sets,
         on_start_geyser_plugin_config_files: config.on_start_geyser_plugin_config_files.clone(),
         geyser_plugin_always_enabled: config.geyser_plugin_always_enabled,
         rpc_addrs: config.rpc_addrs,
         pubsub_config: config.pubsub_config.clone(),
         snapshot_config: config.snapshot_config.clone(),
         max_ledger_shreds: config.max_ledger_shreds,
+        compaction_scheduler_config: config.compaction_scheduler_config.clone(),
         blockstore_options: config.blockstore_options.clone(),
         broadcast_stage_type: config.broadcast_stage_type.clone(),
         turbine_mode: config.turbine_mode.clone(),
@@ -30,9 +34,12 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         should_check_duplicate_instance: config.should_check_duplicate_instance,
         repair_validators: config.repair_validators.clone(),
         repair_whitelist: config.repair_whitelist.clone(),
+        repair_serve_blockstore_path: config.repair_serve_blockstore_path.clone(),
+        repair_serve_blockstore_catch_up_interval: config.repair_serve_blockstore_catch_up_interval,
         gossip_validators: config.gossip_validators.clone(),
         max_genesis_archive_unpacked_size: config.max_genesis_archive_unpacked_size,
-        run_verification: config.run_verification,
+        run_poh_verification: config.run_poh_verification,
+        run_sig_verification: config.run_sig_verification,
         require_tower: config.require_tower,
         require_vote_history: config.require_vote_history,
         tower_storage: config.tower_storage.clone(),
@@ -43,6 +50,8 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         contact_save_interval: config.contact_save_interval,
         send_transaction_service_config: config.send_transaction_service_config.clone(),
         no_poh_speed_test: config.no_poh_speed_test,
+        poh_speed_check_samples: config.poh_speed_check_samples,
+        require_hashes_per_tick: config.require_hashes_per_tick,
         no_os_memory_stats_reporting: config.no_os_memory_stats_reporting,
         no_os_network_stats_reporting: config.no_os_network_stats_reporting,
         no_os_cpu_stats_reporting: config.no_os_cpu_stats_reporting,
@@ -52,6 +61,7 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         warp_slot: config.warp_slot,
         accounts_db_skip_shrink: config.accounts_db_skip_shrink,
         accounts_db_force_initial_clean: config.accounts_db_force_initial_clean,
+        accounts_db_allow_migration: config.accounts_db_allow_migration,
         staked_nodes_overrides: config.staked_nodes_overrides.clone(),
         validator_exit: Arc::new(RwLock::new(Exit::default())),
         validator_exit_backpressure: config
@@ -64,16 +74,21 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         no_wait_for_vote_to_start_leader: config.no_wait_for_vote_to_start_leader,
         accounts_db_config: config.accounts_db_config.clone(),
         wait_to_vote_slot: config.wait_to_vote_slot,
+        min_leader_slot: config.min_leader_slot,
+        max_active_banks: config.max_active_banks,
+        precompute_leader_schedule_slots: config.precompute_leader_schedule_slots,
         runtime_config: config.runtime_config.clone(),
         banking_trace_dir_byte_limit: config.banking_trace_dir_byte_limit,
         block_verification_method: config.block_verification_method.clone(),
         block_production_method: config.block_production_method.clone(),
         block_production_num_workers: config.block_production_num_workers,
         block_production_scheduler_config: config.block_production_scheduler_config.clone(),
+        reserved_leader_compute_units: config.reserved_leader_compute_units,
         enable_block_production_forwarding: config.enable_block_production_forwarding,
         enable_scheduler_bindings: config.enable_scheduler_bindings,
         generator_config: config.generator_config.clone(),
         use_snapshot_archives_at_startup: config.use_snapshot_archives_at_startup,
+        preserve_bank_snapshots_at_startup: config.preserve_bank_snapshots_at_startup,
         unified_scheduler_handler_threads: config.unified_scheduler_handler_threads,
         ip_echo_server_threads: config.ip_echo_server_threads,
         rayon_global_threads: config.rayon_global_threads,
@@ -85,6 +100,22 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         voting_service_test_override: config.voting_service_test_override.clone(),
         repair_handler_type: config.repair_handler_type.clone(),
         snapshot_packager_niceness_adj: config.snapshot_packager_niceness_adj,
+        snapshot_packaging_threads: config.snapshot_packaging_threads,
+        snapshot_gossip_push: config.snapshot_gossip_push,
+        vote_capture_capacity: config.vote_capture_capacity,
+        gossip_vote_forwarding_leader_window_slots: config
+            .gossip_vote_forwarding_leader_window_slots,
+        max_gossip_vote_age_ms: config.max_gossip_vote_age_ms,
+        vote_listener_slot_filter: config.vote_listener_slot_filter.clone(),
+        tpu_ingest_memory_budget_bytes: config.tpu_ingest_memory_budget_bytes,
+        tpu_refuse_connections_until_ready: config.tpu_refuse_connections_until_ready,
+        duplicate_confirmed_slots_channel_capacity: config
+            .duplicate_confirmed_slots_channel_capacity,
+        gossip_export: config.gossip_export.clone(),
+        vote_tracker_archive: config.vote_tracker_archive.clone(),
+        process_start_time: config.process_start_time,
+        deterministic_rng_seed: config.deterministic_rng_seed,
+        strict_tpu_config: config.strict_tpu_config,
     }
 }
 
@@ -29,13 +29,19 @@ use {
             malicious_repair_handler::MaliciousRepairConfig, repair_handler::RepairHandlerType,
         },
         replay_stage::DUPLICATE_THRESHOLD,
+        restart_latency_tracker::RestartPhase,
         validator::{BlockProductionMethod, BlockVerificationMethod, ValidatorConfig},
     },
     solana_download_utils::download_snapshot_archive,
     solana_entry::entry::create_ticks,
     solana_epoch_schedule::MINIMUM_SLOTS_PER_EPOCH,
     solana_genesis_utils::open_genesis_config,
-    solana_gossip::{crds_data::MAX_VOTES, gossip_service::discover_validators},
+    solana_gossip::{
+        contact_info::{ContactInfo, Protocol},
+        crds_data::MAX_VOTES,
+        gossip_export::{GossipExportConfig, list_gossip_exports, read_gossip_export},
+        gossip_service::discover_validators,
+    },
     solana_hard_forks::HardForks,
     solana_hash::Hash,
     solana_keypair::{Keypair, keypair_from_seed},
@@ -48,7 +54,8 @@ use {
         blockstore_processor::{self, ProcessOptions},
         leader_schedule_cache::LeaderScheduleCache,
         shred::{
-            DATA_SHREDS_PER_FEC_BLOCK, ProcessShredsStats, ReedSolomonCache, Shred, Shredder,
+            DATA_SHREDS_PER_FEC_BLOCK, ProcessShredsStats, ReedSolomonCache, Shred, ShredId,
+            ShredType, Shredder,
             filter::{TurbineMode, TurbineModeKind},
         },
         use_snapshot_archives_at_startup::UseSnapshotArchivesAtStartup,
@@ -86,9 +93,13 @@ use {
     solana_system_interface::program as system_program,
     solana_system_transaction as system_transaction,
     solana_transaction_error::TransportError,
-    solana_turbine::broadcast_stage::{
-        BroadcastStageType,
-        broadcast_duplicates_run::{BroadcastDuplicatesConfig, ClusterPartition},
+    solana_turbine::{
+        broadcast_stage::{
+            BroadcastStageType,
+            broadcast_duplicates_run::{BroadcastDuplicatesConfig, ClusterPartition},
+        },
+        cluster_nodes::Error as ClusterNodesError,
+        retransmit_stage::TurbineDiagnosticsError,
     },
     solana_vote::{
         vote_parser::{self},
@@ -101,6 +112,7 @@ use {
         fs,
         io::Read,
         iter,
+        net::{IpAddr, Ipv4Addr, SocketAddr},
         num::{NonZeroU64, NonZeroUsize},
         path::Path,
         sync::{
@@ -288,6 +300,277 @@ fn test_two_unbalanced_stakes() {
     cluster_tests::verify_ledger_ticks(&leader_ledger, num_ticks_per_slot as usize);
 }
 
+/// Triggers a gossip export on every node and checks that it lists the
+/// contact info and stake of every node in the cluster.
+#[test]
+#[serial]
+fn test_gossip_export() {
+    agave_logger::setup_with_default(RUST_LOG_FILTER);
+    let num_nodes = 2;
+    let export_dirs: Vec<_> = (0..num_nodes)
+        .map(|_| tempfile::tempdir_in(farf_dir()).unwrap())
+        .collect();
+    let mut validator_configs =
+        make_identical_validator_configs(&ValidatorConfig::default_for_test(), num_nodes);
+    for (validator_config, export_dir) in validator_configs.iter_mut().zip(&export_dirs) {
+        validator_config.gossip_export = Some(GossipExportConfig {
+            // Only exports triggered by the test are written
+            interval: Duration::from_secs(3600),
+            dir: export_dir.path().to_path_buf(),
+            max_files: NonZeroUsize::new(1).unwrap(),
+        });
+    }
+    let mut cluster = LocalCluster::new(
+        &mut ClusterConfig {
+            node_stakes: vec![DEFAULT_NODE_STAKE; num_nodes],
+            validator_configs,
+            ..ClusterConfig::default()
+        },
+        SocketAddrSpace::Unspecified,
+    );
+
+    let node_pubkeys: HashSet<_> = cluster.validators.keys().copied().collect();
+    for validator_info in cluster.validators.values() {
+        let validator = validator_info.validator.as_ref().unwrap();
+        let export_dir = &validator_info.config.gossip_export.as_ref().unwrap().dir;
+        // Gossip may still be propagating, so retry until every node is known
+        let start = Instant::now();
+        let export = loop {
+            let path = validator.export_gossip().unwrap();
+            assert_eq!(list_gossip_exports(export_dir).unwrap(), vec![path.clone()]);
+            let export = read_gossip_export(&path).unwrap();
+            let exported_pubkeys: HashSet<_> = export
+                .contact_infos
+                .iter()
+                .map(|node| node.pubkey)
+                .collect();
+            if exported_pubkeys.is_superset(&node_pubkeys) {
+                break export;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(60),
+                "{exported_pubkeys:?}"
+            );
+            sleep(Duration::from_millis(500));
+        };
+        assert_eq!(export.node, validator.cluster_info.id());
+        for node in &export.contact_infos {
+            let expected = validator
+                .cluster_info
+                .lookup_contact_info(&node.pubkey, ContactInfo::clone)
+                .unwrap();
+            assert_eq!(node.gossip, expected.gossip());
+            assert_eq!(node.tvu_udp, expected.tvu(Protocol::UDP));
+            assert_eq!(node.shred_version, expected.shred_version());
+            if node_pubkeys.contains(&node.pubkey) {
+                assert_eq!(node.stake, Some(DEFAULT_NODE_STAKE));
+            }
+        }
+    }
+    cluster.exit();
+}
+
+/// Checks that the other nodes pick up the sockets a node re-advertises on a
+/// new IP address.
+#[test]
+#[serial]
+fn test_refresh_advertised_addresses() {
+    agave_logger::setup_with_default(RUST_LOG_FILTER);
+    let num_nodes = 2;
+    let mut cluster = LocalCluster::new_with_equal_stakes(
+        num_nodes,
+        DEFAULT_MINT_LAMPORTS,
+        DEFAULT_NODE_STAKE,
+        SocketAddrSpace::Unspecified,
+    );
+    let entry_point_id = *cluster.entry_point_info.pubkey();
+    let refreshed_id = *cluster
+        .validators
+        .keys()
+        .find(|id| **id != entry_point_id)
+        .unwrap();
+
+    let refreshed = cluster.validators[&refreshed_id]
+        .validator
+        .as_ref()
+        .unwrap();
+    let old_info = refreshed.cluster_info.my_contact_info();
+    let new_ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+    assert_eq!(
+        refreshed
+            .refresh_advertised_addresses(Some(new_ip))
+            .unwrap(),
+        new_ip
+    );
+
+    let entry_point = cluster.validators[&entry_point_id]
+        .validator
+        .as_ref()
+        .unwrap();
+    let start = Instant::now();
+    let observed_info = loop {
+        let observed_info = entry_point
+            .cluster_info
+            .lookup_contact_info(&refreshed_id, ContactInfo::clone)
+            .unwrap();
+        if observed_info.gossip().map(|addr| addr.ip()) == Some(new_ip) {
+            break observed_info;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(60),
+            "{observed_info:?}"
+        );
+        sleep(Duration::from_millis(500));
+    };
+    assert_eq!(
+        observed_info.tpu(Protocol::QUIC),
+        Some(SocketAddr::new(
+            new_ip,
+            old_info.tpu(Protocol::QUIC).unwrap().port()
+        ))
+    );
+    assert_eq!(
+        observed_info.tvu(Protocol::UDP),
+        Some(SocketAddr::new(
+            new_ip,
+            old_info.tvu(Protocol::UDP).unwrap().port()
+        ))
+    );
+    cluster.exit();
+}
+
+/// Checks that the turbine trees the nodes report for a shred they
+/// retransmitted fit together: each node is among the children of its parent.
+#[test]
+#[serial]
+fn test_turbine_tree_diagnostics() {
+    agave_logger::setup_with_default(RUST_LOG_FILTER);
+    let num_nodes = 3;
+    let mut cluster = LocalCluster::new_with_equal_stakes(
+        num_nodes,
+        DEFAULT_MINT_LAMPORTS,
+        DEFAULT_NODE_STAKE,
+        SocketAddrSpace::Unspecified,
+    );
+    let validators: HashMap<_, _> = cluster
+        .validators
+        .iter()
+        .map(|(pubkey, info)| (*pubkey, info.validator.as_ref().unwrap()))
+        .collect();
+
+    let start = Instant::now();
+    let trees = loop {
+        // Every node but the leader retransmitted the shreds of a rooted slot
+        let slot = validators
+            .values()
+            .map(|validator| validator.bank_forks.read().unwrap().root())
+            .min()
+            .unwrap();
+        let shred = ShredId::new(slot, 0, ShredType::Data);
+        let trees: HashMap<_, _> = validators
+            .iter()
+            .filter_map(|(pubkey, validator)| match validator.turbine_tree(shred) {
+                Ok(tree) => Some((*pubkey, tree)),
+                Err(TurbineDiagnosticsError::ClusterNodes(ClusterNodesError::Loopback {
+                    ..
+                })) => None,
+                Err(err) => panic!("{err}"),
+            })
+            .collect();
+        if slot > 0 && trees.len() == num_nodes - 1 && trees.values().all(|tree| tree.observed) {
+            break trees;
+        }
+        assert!(start.elapsed() < Duration::from_secs(120), "{trees:?}");
+        sleep(Duration::from_millis(500));
+    };
+
+    for (pubkey, diagnostics) in &trees {
+        let tree = &diagnostics.tree;
+        match &tree.parent {
+            // The root of the tree receives the shred from the leader
+            None => assert_eq!(tree.root_distance, 0),
+            Some(parent) => {
+                assert_eq!(parent.stake, DEFAULT_NODE_STAKE);
+                let parent_tree = &trees[&parent.pubkey].tree;
+                assert_eq!(parent_tree.root_distance + 1, tree.root_distance);
+                assert!(
+                    parent_tree
+                        .children
+                        .iter()
+                        .any(|child| child.pubkey == *pubkey),
+                    "{trees:?}"
+                );
+            }
+        }
+        for child in &tree.children {
+            assert_eq!(
+                trees[&child.pubkey]
+                    .tree
+                    .parent
+                    .as_ref()
+                    .map(|parent| parent.pubkey),
+                Some(*pubkey)
+            );
+        }
+    }
+    cluster.exit();
+}
+
+/// Checks that every node reports its restart latency summary, once it lands
+/// its first vote or, with voting disabled, freezes its first bank.
+#[test]
+#[serial]
+fn test_restart_latency_summary() {
+    agave_logger::setup_with_default(RUST_LOG_FILTER);
+    let node_stakes = vec![3 * DEFAULT_NODE_STAKE, DEFAULT_NODE_STAKE];
+    let mut validator_configs =
+        make_identical_validator_configs(&ValidatorConfig::default_for_test(), node_stakes.len());
+    validator_configs[1].voting_disabled = true;
+    let mut cluster = LocalCluster::new(
+        &mut ClusterConfig {
+            mint_lamports: DEFAULT_MINT_LAMPORTS + node_stakes.iter().sum::<u64>(),
+            node_stakes,
+            validator_configs,
+            ..ClusterConfig::default()
+        },
+        SocketAddrSpace::Unspecified,
+    );
+
+    for validator_info in cluster.validators.values() {
+        let validator = validator_info.validator.as_ref().unwrap();
+        let start = Instant::now();
+        let summary = loop {
+            let summary = validator.restart_latency();
+            if summary.complete {
+                break summary;
+            }
+            assert!(start.elapsed() < Duration::from_secs(120), "{summary:?}");
+            sleep(Duration::from_millis(500));
+        };
+        assert!(
+            summary
+                .phases
+                .windows(2)
+                .all(|phases| phases[0].0 < phases[1].0 && phases[0].1 <= phases[1].1),
+            "{summary:?}"
+        );
+        for phase in [
+            RestartPhase::BlockstoreOpened,
+            RestartPhase::LedgerProcessed,
+            RestartPhase::ServicesStarted,
+            RestartPhase::FirstBankFrozen,
+        ] {
+            assert!(summary.get(phase).is_some(), "{phase:?} {summary:?}");
+        }
+        if validator_info.config.voting_disabled {
+            assert_eq!(summary.get(RestartPhase::FirstVoteSubmitted), None);
+        } else {
+            assert!(summary.get(RestartPhase::FirstVoteLanded).is_some());
+        }
+    }
+    cluster.exit();
+}
+
 /// Exercises TPU forwarding by sending transactions to a validator that never leads.
 /// Other spend tests assume direct leader delivery; this one proves the forwarding path itself.
 #[test]
@@ -2223,7 +2506,8 @@ fn create_snapshot_to_hard_fork(
     let process_options = ProcessOptions {
         halt_at_slot: Some(snapshot_slot),
         new_hard_forks: Some(hard_forks),
-        run_verification: false,
+        run_poh_verification: false,
+        run_sig_verification: false,
         ..ProcessOptions::default()
     };
     let ledger_path = blockstore.ledger_path();
@@ -309,6 +309,17 @@ pub fn bind_more_with_config(
     }
 }
 
+/// Binds one more socket to an address bound by `bind_more_with_config`, e.g.
+/// to replace one of the sockets bound to it
+pub fn bind_one_more_with_config(
+    ip_addr: IpAddr,
+    port: u16,
+    mut config: SocketConfiguration,
+) -> io::Result<UdpSocket> {
+    config.reuseport = true;
+    bind_to_with_config(ip_addr, port, config)
+}
+
 #[cfg(test)]
 mod tests {
     use {
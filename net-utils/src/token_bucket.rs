@@ -251,6 +251,10 @@ where
     countdown_to_shrink: AtomicUsize,
     approx_len: AtomicUsize,
     shrink_interval: usize,
+    /// total number of entries evicted by the LRU policy
+    num_evicted: AtomicU64,
+    /// total time spent evicting entries, in microseconds
+    shrink_time_us: AtomicU64,
 }
 
 impl<K> KeyedRateLimiter<K>
@@ -282,6 +286,8 @@ where
             countdown_to_shrink: AtomicUsize::new(shrink_interval),
             approx_len: AtomicUsize::new(0),
             shrink_interval,
+            num_evicted: AtomicU64::new(0),
+            shrink_time_us: AtomicU64::new(0),
         }
     }
 
@@ -348,6 +354,18 @@ where
         self.approx_len.load(Ordering::Relaxed)
     }
 
+    /// Returns the total amount of entries evicted so far.
+    #[inline]
+    pub fn num_evicted(&self) -> u64 {
+        self.num_evicted.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total time spent evicting entries so far, in microseconds.
+    #[inline]
+    pub fn shrink_time_us(&self) -> u64 {
+        self.shrink_time_us.load(Ordering::Relaxed)
+    }
+
     // apply lazy-LRU eviction policy to each DashMap shard.
     // Allowing side-effects here since overflows here are not
     // actually possible
@@ -358,6 +376,8 @@ where
         if target_shard_size == 0 {
             return;
         }
+        let start = Instant::now();
+        let mut num_evicted = 0;
         let mut entries = Vec::with_capacity(target_shard_size * 2);
         for shardlock in self.data.shards() {
             let mut shard = shardlock.write();
@@ -376,6 +396,7 @@ where
             entries.select_nth_unstable_by_key(target_shard_size, |(_, last_update, _)| {
                 Reverse(*last_update)
             });
+            num_evicted += entries.len() - target_shard_size;
 
             shard.extend(
                 entries
@@ -387,6 +408,16 @@ where
             actual_len += shard.len();
         }
         self.approx_len.store(actual_len, Ordering::Relaxed);
+        self.num_evicted
+            .fetch_add(num_evicted as u64, Ordering::Relaxed);
+        self.shrink_time_us
+            .fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Applies the eviction policy to all shards right away. Meant to be
+    /// called periodically when auto-shrinking is disabled.
+    pub fn shrink(&self) {
+        self.maybe_shrink();
     }
 
     /// Set the auto-shrink interval. Set to 0 to disable shrinking.
@@ -395,6 +426,7 @@ where
     /// large is good for perf (at cost of memory use)
     pub fn set_shrink_interval(&mut self, interval: usize) {
         self.shrink_interval = interval;
+        self.countdown_to_shrink.store(interval, Ordering::Relaxed);
     }
 
     /// Get the auto-shrink interval.
@@ -545,10 +577,29 @@ pub mod test {
             None,
             "Very old record should have been erased"
         );
+        assert!(rl.num_evicted() > 0, "Evictions should be accounted for");
         rl.consume_tokens(ip2, 100)
             .expect("New bucket should have been made for ip2");
     }
 
+    #[test]
+    fn test_keyed_rate_limiter_manual_shrink() {
+        let prototype_bucket = TokenBucket::new(100, 100, 1000.0);
+        let mut rl = KeyedRateLimiter::new(8, prototype_bucket, 2);
+        rl.set_shrink_interval(0);
+        for ip in 0..64 {
+            let ip = IpAddr::V4(Ipv4Addr::from_bits(ip));
+            rl.consume_tokens(ip, 50).unwrap();
+        }
+        // Nothing is evicted while inserting
+        assert_eq!(rl.len_approx(), 64);
+        assert_eq!(rl.num_evicted(), 0);
+
+        rl.shrink();
+        assert!(rl.len_approx() <= 8);
+        assert_eq!(rl.num_evicted() as usize, 64 - rl.len_approx());
+    }
+
     #[test]
     #[should_panic(expected = "must be >= shard_amount")]
     fn test_keyed_rate_limiter_capacity_less_than_shards_panics() {
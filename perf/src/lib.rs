@@ -9,6 +9,7 @@ pub mod sigverify;
 #[cfg(feature = "dev-context-only-utils")]
 pub mod test_tx;
 pub mod thread;
+pub mod watchdog;
 
 #[macro_use]
 extern crate log;
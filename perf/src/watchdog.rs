@@ -0,0 +1,447 @@
+//! Heartbeat based detection of stuck service threads.
+//!
+//! A long-running service loop registers with the [`Watchdog`] of its validator
+//! before entering the loop, and then beats the returned [`Heartbeat`] once per
+//! iteration:
+//!
+//! ```ignore
+//! let heartbeat = watchdog.register("AccountsBackgroundService", Duration::from_secs(600));
+//! loop {
+//!     heartbeat.beat();
+//!     ...
+//! }
+//! ```
+//!
+//! [`WatchdogService`] periodically checks that every registered heartbeat
+//! advanced within its budget, and reports the ones that did not along with
+//! where their thread is stuck.
+
+use std::{
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread::{self, Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// A counter bumped by a service loop on every iteration.
+///
+/// The registration ends when the last reference is dropped, i.e. when the
+/// service thread exits.
+pub struct Heartbeat {
+    name: &'static str,
+    budget: Duration,
+    thread_name: Option<String>,
+    #[cfg(target_os = "linux")]
+    thread_id: libc::pid_t,
+    beats: AtomicU64,
+}
+
+impl Heartbeat {
+    #[inline]
+    pub fn beat(&self) {
+        self.beats.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns where the thread which registered the heartbeat is stuck, if it
+    /// could be captured.
+    fn capture_state(&self) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        {
+            thread_state::capture(self.thread_id)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+}
+
+struct Registration {
+    heartbeat: Weak<Heartbeat>,
+    last_beats: u64,
+    last_progress: Instant,
+    stalled: bool,
+}
+
+/// Keeps track of the heartbeats registered by the services of a validator.
+#[derive(Default)]
+pub struct Watchdog {
+    registrations: Mutex<Vec<Registration>>,
+    degraded: AtomicBool,
+}
+
+impl Watchdog {
+    /// Registers a heartbeat which must be beaten at least once every `budget`.
+    /// The calling thread is recorded for reporting.
+    pub fn register(&self, name: &'static str, budget: Duration) -> Arc<Heartbeat> {
+        let heartbeat = Arc::new(Heartbeat {
+            name,
+            budget,
+            thread_name: thread::current().name().map(str::to_string),
+            #[cfg(target_os = "linux")]
+            thread_id: thread_state::current_thread_id(),
+            beats: AtomicU64::default(),
+        });
+        self.registrations.lock().unwrap().push(Registration {
+            heartbeat: Arc::downgrade(&heartbeat),
+            last_beats: 0,
+            last_progress: Instant::now(),
+            stalled: false,
+        });
+        heartbeat
+    }
+
+    /// Returns true if any registered heartbeat was stalled at the last check.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Checks every registered heartbeat and returns the names of the ones
+    /// that stalled since the last check.
+    pub fn check(&self) -> Vec<&'static str> {
+        let now = Instant::now();
+        let mut newly_stalled = vec![];
+        {
+            let mut registrations = self.registrations.lock().unwrap();
+            registrations.retain_mut(|registration| {
+                let Some(heartbeat) = registration.heartbeat.upgrade() else {
+                    // The service exited
+                    return false;
+                };
+                let beats = heartbeat.beats.load(Ordering::Relaxed);
+                if beats != registration.last_beats {
+                    if registration.stalled {
+                        info!("watchdog: {} is making progress again", heartbeat.name);
+                    }
+                    registration.last_beats = beats;
+                    registration.last_progress = now;
+                    registration.stalled = false;
+                } else if !registration.stalled {
+                    let elapsed = now.duration_since(registration.last_progress);
+                    if elapsed > heartbeat.budget {
+                        registration.stalled = true;
+                        newly_stalled.push((heartbeat.clone(), elapsed));
+                    }
+                }
+                true
+            });
+            let degraded = registrations
+                .iter()
+                .any(|registration| registration.stalled);
+            self.degraded.store(degraded, Ordering::Relaxed);
+        }
+
+        // Capturing the state waits on the stalled thread, so it is done
+        // without holding up registrations
+        newly_stalled
+            .into_iter()
+            .map(|(heartbeat, elapsed)| {
+                let state = heartbeat.capture_state();
+                error!(
+                    "watchdog: {} on thread {:?} has not made progress in {elapsed:?} (budget: \
+                     {:?}), thread state:\n{}",
+                    heartbeat.name,
+                    heartbeat.thread_name,
+                    heartbeat.budget,
+                    state.as_deref().unwrap_or("<unavailable>"),
+                );
+                datapoint_error!(
+                    "watchdog-stalled-service",
+                    ("name", heartbeat.name, String),
+                    ("elapsed_ms", elapsed.as_millis() as i64, i64),
+                    ("state_captured", state.is_some(), bool),
+                );
+                heartbeat.name
+            })
+            .collect()
+    }
+}
+
+/// Periodically checks the heartbeats registered with a [`Watchdog`].
+pub struct WatchdogService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl WatchdogService {
+    pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(watchdog: Arc<Watchdog>, exit: Arc<AtomicBool>) -> Self {
+        let thread_hdl = Builder::new()
+            .name("solWatchdog".to_string())
+            .spawn(move || {
+                let mut last_check = Instant::now();
+                while !exit.load(Ordering::Relaxed) {
+                    if last_check.elapsed() >= Self::CHECK_INTERVAL {
+                        watchdog.check();
+                        last_check = Instant::now();
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+/// Captures where another thread of this process is stuck.
+///
+/// Walking the stack of a thread from a signal handler is not async signal
+/// safe, so everything is captured on the calling thread instead: the kernel
+/// reports the state of the thread, the syscall it is blocked in and where in
+/// user space it was interrupted, which is then symbolized. The thread is also
+/// signaled, and its handler only sets a flag, to tell whether it still runs
+/// at all.
+#[cfg(target_os = "linux")]
+mod thread_state {
+    use std::{
+        ffi::c_void,
+        fmt::Write,
+        fs, mem,
+        sync::{
+            Mutex, Once,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    // SIGUSR1 is taken to reopen the log file
+    const SIGNAL: libc::c_int = libc::SIGUSR2;
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    // A single capture is in flight at a time. Each capture is tagged so that
+    // a late handler of a timed out capture is not mistaken for the current
+    // one.
+    static CAPTURE_LOCK: Mutex<u64> = Mutex::new(0);
+    static REQUESTED: AtomicU64 = AtomicU64::new(0);
+    static HANDLED: AtomicU64 = AtomicU64::new(0);
+    static INSTALL_HANDLER: Once = Once::new();
+    static HANDLER_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+    pub(super) fn current_thread_id() -> libc::pid_t {
+        // SAFETY: gettid has no preconditions
+        unsafe { libc::syscall(libc::SYS_gettid) as libc::pid_t }
+    }
+
+    extern "C" fn handle_signal(_signal: libc::c_int) {
+        let capture = REQUESTED.swap(0, Ordering::Acquire);
+        if capture != 0 {
+            HANDLED.store(capture, Ordering::Release);
+        }
+    }
+
+    // Installs the handler unless another one was installed for the signal,
+    // which is then never sent.
+    fn install_handler() {
+        // SAFETY: the actions are fully initialized before they are used
+        unsafe {
+            let mut old_action: libc::sigaction = mem::zeroed();
+            libc::sigaction(SIGNAL, std::ptr::null(), &mut old_action);
+            if old_action.sa_sigaction != libc::SIG_DFL {
+                warn!("watchdog: SIGUSR2 is handled elsewhere, stalled threads are not signaled");
+                return;
+            }
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handle_signal as usize;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(SIGNAL, &action, std::ptr::null_mut()) == 0 {
+                HANDLER_INSTALLED.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns what the kernel reports about the thread `thread_id`, or None
+    /// if it could not be read.
+    pub(super) fn capture(thread_id: libc::pid_t) -> Option<String> {
+        let mut last_capture = CAPTURE_LOCK.lock().unwrap();
+        INSTALL_HANDLER.call_once(install_handler);
+
+        let task_dir = format!("/proc/self/task/{thread_id}");
+        let stat = fs::read_to_string(format!("{task_dir}/stat")).ok()?;
+        let wchan = fs::read_to_string(format!("{task_dir}/wchan")).unwrap_or_default();
+        let syscall = fs::read_to_string(format!("{task_dir}/syscall")).ok()?;
+
+        let mut state = String::new();
+        // The state follows the command, which may contain spaces
+        let thread_state = stat
+            .rsplit_once(')')
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .unwrap_or("?");
+        writeln!(state, "state: {thread_state}, wchan: {}", wchan.trim()).unwrap();
+        let mut fields = syscall.split_whitespace();
+        match fields.next() {
+            Some("running") | None => writeln!(state, "syscall: none (running)").unwrap(),
+            Some(number) => {
+                writeln!(state, "syscall: {number}").unwrap();
+                // The stack pointer and the program counter come last
+                let fields = fields.collect::<Vec<_>>();
+                if let [.., sp, pc] = fields[..] {
+                    writeln!(state, "sp: {sp}").unwrap();
+                    let pc = usize::from_str_radix(pc.trim_start_matches("0x"), 16).ok()?;
+                    write_symbol(&mut state, pc as *mut c_void);
+                }
+            }
+        }
+
+        if HANDLER_INSTALLED.load(Ordering::Relaxed) {
+            *last_capture += 1;
+            let handled = signal(thread_id, *last_capture);
+            writeln!(state, "handled signal: {handled}").unwrap();
+        }
+        Some(state)
+    }
+
+    // Returns true if the thread handled the signal in time.
+    fn signal(thread_id: libc::pid_t, capture: u64) -> bool {
+        REQUESTED.store(capture, Ordering::Release);
+        // SAFETY: tgkill only signals a thread of this process
+        let result = unsafe { libc::syscall(libc::SYS_tgkill, libc::getpid(), thread_id, SIGNAL) };
+        if result != 0 {
+            REQUESTED.store(0, Ordering::Relaxed);
+            return false;
+        }
+        let start = Instant::now();
+        while HANDLED.load(Ordering::Acquire) != capture {
+            if start.elapsed() > TIMEOUT {
+                REQUESTED.store(0, Ordering::Relaxed);
+                return false;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    fn write_symbol(state: &mut String, pc: *mut c_void) {
+        let mut resolved = false;
+        backtrace::resolve(pc, |symbol| {
+            resolved = true;
+            match symbol.name() {
+                Some(name) => writeln!(state, "pc: {pc:?} {name:#}"),
+                None => writeln!(state, "pc: {pc:?}"),
+            }
+            .unwrap();
+            if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                writeln!(state, "    at {}:{line}", file.display()).unwrap();
+            }
+        });
+        if !resolved {
+            writeln!(state, "pc: {pc:?}").unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_detects_stalled_heartbeat() {
+        const BUDGET: Duration = Duration::from_millis(100);
+        let watchdog = Watchdog::default();
+        let exit = Arc::new(AtomicBool::new(false));
+        let stop_beating = Arc::new(AtomicBool::new(false));
+        let heartbeat = watchdog.register("test-service", BUDGET);
+        let service = Builder::new()
+            .spawn({
+                let exit = exit.clone();
+                let stop_beating = stop_beating.clone();
+                move || {
+                    while !exit.load(Ordering::Relaxed) {
+                        if !stop_beating.load(Ordering::Relaxed) {
+                            heartbeat.beat();
+                        }
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
+            })
+            .unwrap();
+
+        for _ in 0..5 {
+            thread::sleep(BUDGET);
+            assert!(watchdog.check().is_empty());
+            assert!(!watchdog.is_degraded());
+        }
+
+        stop_beating.store(true, Ordering::Relaxed);
+        let start = Instant::now();
+        let (stalled, detected_after) = loop {
+            thread::sleep(BUDGET / 5);
+            // Not counting the time taken to capture the thread state
+            let detected_after = start.elapsed();
+            let stalled = watchdog.check();
+            if !stalled.is_empty() {
+                break (stalled, detected_after);
+            }
+        };
+        assert_eq!(stalled, vec!["test-service"]);
+        // Detected shortly after the budget elapses
+        assert!(detected_after < BUDGET * 5, "{detected_after:?}");
+        assert!(watchdog.is_degraded());
+        // Only reported once
+        thread::sleep(BUDGET * 2);
+        assert!(watchdog.check().is_empty());
+        assert!(watchdog.is_degraded());
+
+        stop_beating.store(false, Ordering::Relaxed);
+        thread::sleep(BUDGET / 5);
+        assert!(watchdog.check().is_empty());
+        assert!(!watchdog.is_degraded());
+
+        // Once the service exits its heartbeat is no longer tracked
+        exit.store(true, Ordering::Relaxed);
+        service.join().unwrap();
+        watchdog.check();
+        assert!(watchdog.registrations.lock().unwrap().is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_watchdog_captures_state_of_stalled_thread() {
+        #[inline(never)]
+        fn stalled_service_loop(watchdog: &Watchdog, exit: &AtomicBool) {
+            let _heartbeat = watchdog.register("stalled-service", Duration::ZERO);
+            while !exit.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        let watchdog = Arc::new(Watchdog::default());
+        let exit = Arc::new(AtomicBool::new(false));
+        let service = Builder::new()
+            .spawn({
+                let watchdog = watchdog.clone();
+                let exit = exit.clone();
+                move || stalled_service_loop(&watchdog, &exit)
+            })
+            .unwrap();
+        let heartbeat = loop {
+            let registrations = watchdog.registrations.lock().unwrap();
+            if let Some(heartbeat) = registrations
+                .first()
+                .and_then(|registration| registration.heartbeat.upgrade())
+            {
+                break heartbeat;
+            }
+            drop(registrations);
+            thread::sleep(Duration::from_millis(1));
+        };
+
+        // The service sleeps, and handles the signal in between
+        let state = heartbeat.capture_state().unwrap();
+        assert!(
+            state.contains(&format!("syscall: {}", libc::SYS_clock_nanosleep)),
+            "{state}"
+        );
+        assert!(state.contains("handled signal: true"), "{state}");
+
+        exit.store(true, Ordering::Relaxed);
+        service.join().unwrap();
+    }
+}
@@ -260,7 +260,7 @@ mod tests {
         let port = response_recv_socket.local_addr().unwrap().port();
         let server_addr = SocketAddr::new(addr, port);
         let SpawnServerResult {
-            endpoints: mut response_recv_endpoints,
+            endpoints: response_recv_endpoints,
             thread: response_recv_thread,
             key_updater: _,
         } = solana_streamer::quic::spawn_stake_weighted_qos_server(
@@ -289,6 +289,7 @@ mod tests {
         });
 
         let response_recv_endpoint = response_recv_endpoints
+            .get()
             .pop()
             .expect("at least one endpoint");
         drop(response_recv_endpoints);
@@ -20,6 +20,7 @@ use {
     rayon::iter::{IntoParallelIterator, ParallelIterator},
     solana_clock::{BankId, Slot},
     solana_measure::{measure::Measure, measure_us},
+    solana_perf::watchdog::Watchdog,
     stats::StatsManager,
     std::{
         boxed::Box,
@@ -427,6 +428,7 @@ impl AccountsBackgroundService {
         bank_forks: Arc<RwLock<BankForks>>,
         exit: Arc<AtomicBool>,
         request_handlers: AbsRequestHandlers,
+        watchdog: Arc<Watchdog>,
     ) -> Self {
         let is_running = Arc::new(AtomicBool::new(true));
         let stop = Arc::new(AtomicBool::new(false));
@@ -445,8 +447,12 @@ impl AccountsBackgroundService {
                     let mut last_snapshot_end_time = None;
                     let mut previous_clean_time = Instant::now();
                     let mut previous_shrink_time = Instant::now();
+                    // Cleaning, shrinking and snapshot requests can legitimately take a while
+                    let heartbeat =
+                        watchdog.register("AccountsBackgroundService", Duration::from_secs(600));
 
                     loop {
+                        heartbeat.beat();
                         if exit.load(Ordering::Relaxed) || stop.load(Ordering::Relaxed) {
                             break;
                         }
@@ -35,6 +35,7 @@
 //! already been signed and verified.
 pub use {
     crate::slot_params::DEFAULT_MAX_ENTRY_BYTES_PER_SLOT,
+    activated_stake_precompute::PrecomputedActivatedStakeCounts,
     partitioned_epoch_rewards::KeyedRewardsAndNumPartitions, solana_leader_schedule::SlotLeader,
     solana_reward_info::RewardType,
 };
@@ -74,6 +75,7 @@ use {
         transaction_batch::{OwnedOrBorrowed, TransactionBatch},
     },
     accounts_lt_hash::AccountsLtHashAsyncProgress,
+    activated_stake_precompute::ActivatedStakePrecompute,
     agave_bls_cert_verify::cert_verify::{self, Error as CertVerifyError},
     agave_feature_set::{self as feature_set, FeatureSet},
     agave_precompiles::{get_precompile, get_precompiles, is_precompile},
@@ -229,6 +231,7 @@ struct VerifyAccountsHashConfig {
 }
 
 mod accounts_lt_hash;
+mod activated_stake_precompute;
 mod address_lookup_table;
 pub mod bank_hash_details;
 pub mod builtins;
@@ -652,7 +655,9 @@ impl PartialEq for Bank {
             block_id,
             expected_bank_hash: _,
             bank_hash_stats: _,
+            accounts_load_stats: _,
             epoch_rewards_calculation_cache: _,
+            activated_stake_precompute: _,
             block_component_processor: _,
             // Ignore new fields explicitly if they do not impact PartialEq.
             // Adding ".." will remove compile-time checks that if a new field
@@ -998,11 +1003,18 @@ pub struct Bank {
     /// Accounts stats for computing the bank hash
     bank_hash_stats: AtomicBankHashStats,
 
+    /// Accounts loaded by the transactions of this bank
+    accounts_load_stats: AtomicAccountsLoadStats,
+
     /// The cache of epoch rewards calculation results
     /// This is used to avoid recalculating the same epoch rewards at epoch boundary.
     /// The hashmap is keyed by parent_hash.
     epoch_rewards_calculation_cache: Arc<Mutex<HashMap<Hash, Arc<PartitionedRewardsCalculation>>>>,
 
+    /// Stake activated at the next epoch boundary, calculated ahead of time by
+    /// `precompute_next_epoch_stakes()`
+    activated_stake_precompute: Arc<ActivatedStakePrecompute>,
+
     /// Block component processor for validating block headers/footers and clock bounds. We
     /// currently write to this during replay, as we process block components one at a time, and
     /// read from this once replay is complete.
@@ -1118,6 +1130,34 @@ impl AtomicBankHashStats {
     }
 }
 
+/// Accounts loaded by the transactions of a bank
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountsLoadStats {
+    pub num_loaded_accounts: u64,
+    pub loaded_data_len: u64,
+}
+
+#[derive(Debug, Default)]
+struct AtomicAccountsLoadStats {
+    num_loaded_accounts: AtomicU64,
+    loaded_data_len: AtomicU64,
+}
+
+impl AtomicAccountsLoadStats {
+    fn record(&self, account: &AccountSharedData) {
+        self.num_loaded_accounts.fetch_add(1, Relaxed);
+        self.loaded_data_len
+            .fetch_add(account.data().len() as u64, Relaxed);
+    }
+
+    fn load(&self) -> AccountsLoadStats {
+        AccountsLoadStats {
+            num_loaded_accounts: self.num_loaded_accounts.load(Relaxed),
+            loaded_data_len: self.loaded_data_len.load(Relaxed),
+        }
+    }
+}
+
 struct NewEpochBundle {
     stake_history: CowStakeHistory,
     /// Vote accounts computed from the stakes cache for the current
@@ -1129,6 +1169,8 @@ struct NewEpochBundle {
     filtered_distribution_vote_accounts: VoteAccounts,
     rewards_calculation: Arc<PartitionedRewardsCalculation>,
     calculate_activated_stake_time_us: u64,
+    /// Whether the activated stake was precomputed by `precompute_next_epoch_stakes()`
+    reused_precomputed_activated_stake: bool,
     update_rewards_with_thread_pool_time_us: u64,
 }
 
@@ -1218,7 +1260,9 @@ impl Bank {
             block_id: RwLock::new(None),
             expected_bank_hash: RwLock::new(None),
             bank_hash_stats: AtomicBankHashStats::default(),
+            accounts_load_stats: AtomicAccountsLoadStats::default(),
             epoch_rewards_calculation_cache: Arc::new(Mutex::new(HashMap::default())),
+            activated_stake_precompute: Arc::default(),
             block_component_processor: RwLock::new(BlockComponentProcessor::default()),
         };
 
@@ -1477,7 +1521,9 @@ impl Bank {
             block_id: RwLock::new(None),
             expected_bank_hash: RwLock::new(None),
             bank_hash_stats: AtomicBankHashStats::default(),
+            accounts_load_stats: AtomicAccountsLoadStats::default(),
             epoch_rewards_calculation_cache: parent.epoch_rewards_calculation_cache.clone(),
+            activated_stake_precompute: parent.activated_stake_precompute.clone(),
             block_component_processor: RwLock::new(BlockComponentProcessor::default()),
         };
 
@@ -1715,16 +1761,39 @@ impl Bank {
         // snapshot of stakes in epoch stakes
         let stakes = self.stakes_cache.stakes();
         let stake_delegations = stakes.stake_delegations_vec();
+        let new_rate_activation_epoch = self.new_warmup_cooldown_rate_epoch();
+        let use_fixed_point_stake_math = self.use_fixed_point_stake_math();
         let (
-            (stake_history, unfiltered_distribution_vote_accounts),
+            (
+                reused_precomputed_activated_stake,
+                stake_history,
+                unfiltered_distribution_vote_accounts,
+            ),
             calculate_activated_stake_time_us,
-        ) = measure_us!(stakes.calculate_activated_stake(
-            self.epoch(),
-            thread_pool,
-            self.new_warmup_cooldown_rate_epoch(),
-            &stake_delegations,
-            self.use_fixed_point_stake_math(),
-        ));
+        ) = measure_us!({
+            let precomputed = self.activated_stake_precompute.with_matching(
+                &stakes,
+                self.epoch(),
+                new_rate_activation_epoch,
+                use_fixed_point_stake_math,
+                |activated_stake| stakes.activated_stake_history_and_vote_accounts(activated_stake),
+            );
+            let reused_precomputed_activated_stake = precomputed.is_some();
+            let (stake_history, vote_accounts) = precomputed.unwrap_or_else(|| {
+                stakes.calculate_activated_stake(
+                    self.epoch(),
+                    thread_pool,
+                    new_rate_activation_epoch,
+                    &stake_delegations,
+                    use_fixed_point_stake_math,
+                )
+            });
+            (
+                reused_precomputed_activated_stake,
+                stake_history,
+                vote_accounts,
+            )
+        });
 
         // Apply stake rewards and commission using the distribution vote-account
         // snapshot that matches VAT admission filtering when enabled.
@@ -1755,6 +1824,7 @@ impl Bank {
             filtered_distribution_vote_accounts,
             rewards_calculation,
             calculate_activated_stake_time_us,
+            reused_precomputed_activated_stake,
             update_rewards_with_thread_pool_time_us,
         }
     }
@@ -1783,6 +1853,7 @@ impl Bank {
             filtered_distribution_vote_accounts,
             rewards_calculation,
             calculate_activated_stake_time_us,
+            reused_precomputed_activated_stake,
             update_rewards_with_thread_pool_time_us,
         } = self.compute_new_epoch_caches_and_rewards(
             thread_pool,
@@ -1834,6 +1905,7 @@ impl Bank {
             NewEpochTimings {
                 apply_feature_activations_time_us,
                 calculate_activated_stake_time_us,
+                reused_precomputed_activated_stake,
                 update_epoch_stakes_time_us,
                 update_rewards_with_thread_pool_time_us,
                 begin_partitioned_rewards_time_us,
@@ -2135,7 +2207,9 @@ impl Bank {
             accounts_lt_hash_async_progress: AccountsLtHashAsyncProgress::new(),
             block_id: RwLock::new(fields.block_id),
             bank_hash_stats: AtomicBankHashStats::new(&fields.bank_hash_stats),
+            accounts_load_stats: AtomicAccountsLoadStats::default(),
             epoch_rewards_calculation_cache: Arc::new(Mutex::new(HashMap::default())),
+            activated_stake_precompute: Arc::default(),
             expected_bank_hash: RwLock::new(None),
             block_component_processor: RwLock::new(BlockComponentProcessor::default()),
         };
@@ -6468,6 +6542,11 @@ impl Bank {
         self.bank_hash_stats.load()
     }
 
+    /// Returns the accounts loaded by the transactions of this bank so far
+    pub fn get_accounts_load_stats(&self) -> AccountsLoadStats {
+        self.accounts_load_stats.load()
+    }
+
     pub fn clear_epoch_rewards_cache(&self) {
         self.epoch_rewards_calculation_cache.lock().unwrap().clear();
     }
@@ -6512,6 +6591,55 @@ impl Bank {
         }
     }
 
+    /// Predicts the vote accounts the first bank of the next epoch will save
+    /// into epoch stakes, by activating this bank's stakes for the next epoch
+    /// the same way `compute_new_epoch_caches_and_rewards` does.
+    ///
+    /// The activated stake is kept for the banks crossing the epoch boundary
+    /// which descend from this bank, and reused by them as long as their stake
+    /// delegations are unchanged.
+    ///
+    /// Stake and feature changes landing before the epoch boundary are not
+    /// accounted for, so the result must be verified against the
+    /// authoritative epoch stakes before being relied upon.
+    pub fn precompute_next_epoch_stakes(&self) -> VoteAccounts {
+        let stakes = self.stakes_cache.stakes();
+        let stake_delegations = stakes.stake_delegations_vec();
+        let next_epoch = self.epoch().saturating_add(1);
+        let new_rate_activation_epoch = self.new_warmup_cooldown_rate_epoch();
+        let use_fixed_point_stake_math = self.use_fixed_point_stake_math();
+        let activated_stake = stakes.calculate_activated_stake_delegations(
+            next_epoch,
+            rewards_calculation_thread_pool(),
+            new_rate_activation_epoch,
+            &stake_delegations,
+            use_fixed_point_stake_math,
+        );
+        let (_stake_history, vote_accounts) =
+            stakes.activated_stake_history_and_vote_accounts(&activated_stake);
+        self.activated_stake_precompute.insert(
+            &stakes,
+            next_epoch,
+            new_rate_activation_epoch,
+            use_fixed_point_stake_math,
+            activated_stake,
+        );
+        if self.feature_set.snapshot().validator_admission_ticket {
+            vote_accounts.clone_and_filter_for_vat(
+                MAX_ALPENGLOW_VOTE_ACCOUNTS,
+                self.minimum_vote_account_balance_for_vat(),
+            )
+        } else {
+            vote_accounts
+        }
+    }
+
+    /// Returns how often the stake precomputed by `precompute_next_epoch_stakes()`
+    /// was reused when crossing an epoch boundary
+    pub fn precomputed_activated_stake_counts(&self) -> PrecomputedActivatedStakeCounts {
+        self.activated_stake_precompute.counts()
+    }
+
     /// Calculates and sets block id for `bank`.
     ///
     /// This fn operates recursively. Since calculating the block id requires
@@ -6628,9 +6756,14 @@ impl InvokeContextCallback for Bank {
 
 impl TransactionProcessingCallback for Bank {
     fn get_account_shared_data(&self, pubkey: &Pubkey) -> Option<(AccountSharedData, Slot)> {
-        self.rc
+        let account = self
+            .rc
             .accounts
-            .load_with_fixed_root(&self.ancestors, pubkey)
+            .load_with_fixed_root(&self.ancestors, pubkey);
+        if let Some((account, _slot)) = &account {
+            self.accounts_load_stats.record(account);
+        }
+        account
     }
 
     fn inspect_account(&self, _address: &Pubkey, _account_state: AccountState, _is_writable: bool) {
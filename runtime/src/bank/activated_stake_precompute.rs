@@ -0,0 +1,107 @@
+//! Stake activated at the next epoch boundary, calculated ahead of time.
+//!
+//! Activating the stakes of the new epoch is done inline by the first bank of
+//! an epoch, stalling replay of its fork. A bank close to the boundary can
+//! calculate it in the background instead. The first bank of the next epoch
+//! reuses it if its stake delegations are still the ones it was calculated
+//! from, and calculates it again otherwise.
+
+use {
+    crate::{
+        stake_account::StakeAccount,
+        stakes::{ActivatedStake, Stakes},
+    },
+    imbl::HashMap as ImblHashMap,
+    solana_clock::Epoch,
+    solana_pubkey::Pubkey,
+    solana_stake_interface::state::Delegation,
+    std::sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+#[derive(Debug)]
+struct PrecomputedActivatedStake {
+    next_epoch: Epoch,
+    new_rate_activation_epoch: Option<Epoch>,
+    use_fixed_point_stake_math: bool,
+    /// The stake delegations the activated stake was calculated from
+    stake_delegations: ImblHashMap<Pubkey, StakeAccount<Delegation>>,
+    activated_stake: ActivatedStake,
+}
+
+/// Outcomes of the banks crossing an epoch boundary looking up the precomputed
+/// activated stake.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrecomputedActivatedStakeCounts {
+    /// Lookups which reused the precomputed activated stake
+    pub reused: u64,
+    /// Lookups which found activated stake precomputed from other stake
+    /// delegations or for another epoch
+    pub mismatched: u64,
+}
+
+/// Shared by a bank with all of its descendants, so that every fork crossing
+/// the epoch boundary can reuse the same precomputed activated stake.
+#[derive(Debug, Default)]
+pub(crate) struct ActivatedStakePrecompute {
+    precomputed: Mutex<Option<PrecomputedActivatedStake>>,
+    reused: AtomicU64,
+    mismatched: AtomicU64,
+}
+
+impl ActivatedStakePrecompute {
+    pub(crate) fn insert(
+        &self,
+        stakes: &Stakes<StakeAccount<Delegation>>,
+        next_epoch: Epoch,
+        new_rate_activation_epoch: Option<Epoch>,
+        use_fixed_point_stake_math: bool,
+        activated_stake: ActivatedStake,
+    ) {
+        *self.precomputed.lock().unwrap() = Some(PrecomputedActivatedStake {
+            next_epoch,
+            new_rate_activation_epoch,
+            use_fixed_point_stake_math,
+            stake_delegations: stakes.stake_delegations().clone(),
+            activated_stake,
+        });
+    }
+
+    /// Calls `f` with the activated stake precomputed from the same stake
+    /// delegations and parameters, if any.
+    pub(crate) fn with_matching<R>(
+        &self,
+        stakes: &Stakes<StakeAccount<Delegation>>,
+        next_epoch: Epoch,
+        new_rate_activation_epoch: Option<Epoch>,
+        use_fixed_point_stake_math: bool,
+        f: impl FnOnce(&ActivatedStake) -> R,
+    ) -> Option<R> {
+        let precomputed = self.precomputed.lock().unwrap();
+        let precomputed = precomputed.as_ref()?;
+        // Cloning the stake delegations shares their nodes, so they are still
+        // the same map only if no stake delegation was updated since
+        if precomputed.next_epoch == next_epoch
+            && precomputed.new_rate_activation_epoch == new_rate_activation_epoch
+            && precomputed.use_fixed_point_stake_math == use_fixed_point_stake_math
+            && precomputed
+                .stake_delegations
+                .ptr_eq(stakes.stake_delegations())
+        {
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            Some(f(&precomputed.activated_stake))
+        } else {
+            self.mismatched.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    pub(crate) fn counts(&self) -> PrecomputedActivatedStakeCounts {
+        PrecomputedActivatedStakeCounts {
+            reused: self.reused.load(Ordering::Relaxed),
+            mismatched: self.mismatched.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -11,6 +11,7 @@ use {
 pub(crate) struct NewEpochTimings {
     pub(crate) apply_feature_activations_time_us: u64,
     pub(crate) calculate_activated_stake_time_us: u64,
+    pub(crate) reused_precomputed_activated_stake: bool,
     pub(crate) update_epoch_stakes_time_us: u64,
     pub(crate) update_rewards_with_thread_pool_time_us: u64,
     pub(crate) begin_partitioned_rewards_time_us: u64,
@@ -76,6 +77,11 @@ pub(crate) fn report_new_epoch_metrics(
             timings.calculate_activated_stake_time_us,
             i64
         ),
+        (
+            "reused_precomputed_activated_stake",
+            timings.reused_precomputed_activated_stake,
+            bool
+        ),
         (
             "update_epoch_stakes_us",
             timings.update_epoch_stakes_time_us,
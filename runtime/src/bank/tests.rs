@@ -1199,6 +1199,35 @@ fn test_one_tx_two_out_atomic_pass() {
     assert_eq!(bank.get_balance(&key2), amount);
 }
 
+#[test]
+fn test_accounts_load_stats() {
+    let (genesis_config, mint_keypair) = create_genesis_config_no_tx_fee(LAMPORTS_PER_SOL);
+    let (bank, _bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+    let data_pubkey = solana_pubkey::new_rand();
+    bank.store_account(
+        &data_pubkey,
+        &AccountSharedData::new(LAMPORTS_PER_SOL, 42, &system_program::id()),
+    );
+    // The sysvars loaded by the bank itself are counted as well
+    let initial_stats = bank.get_accounts_load_stats();
+
+    // Only the accounts that exist are counted
+    for pubkey in [
+        mint_keypair.pubkey(),
+        data_pubkey,
+        solana_pubkey::new_rand(),
+    ] {
+        bank.get_account_shared_data(&pubkey);
+    }
+    assert_eq!(
+        bank.get_accounts_load_stats(),
+        AccountsLoadStats {
+            num_loaded_accounts: initial_stats.num_loaded_accounts + 2,
+            loaded_data_len: initial_stats.loaded_data_len + 42,
+        }
+    );
+}
+
 // This test demonstrates that fees are paid even when a program fails.
 #[test]
 fn test_detect_failed_duplicate_transactions() {
@@ -11803,6 +11832,69 @@ fn test_bank_epoch_stakes() {
     }
 }
 
+#[test]
+fn test_precompute_next_epoch_stakes() {
+    let voting_keypairs: Vec<_> = (0..3).map(|_| ValidatorVoteKeypairs::new_rand()).collect();
+    let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+        1_000_000_000,
+        &voting_keypairs,
+        vec![100, 200, 300],
+    );
+    let new_epoch_bank = |precompute: bool, delegate_stake: bool| {
+        let (bank0, bank_forks) =
+            Bank::new_for_tests(&genesis_config).wrap_with_bank_forks_for_tests();
+        if precompute {
+            let _vote_accounts = bank0.precompute_next_epoch_stakes();
+        }
+        if delegate_stake {
+            let vote_pubkey = voting_keypairs[0].vote_keypair.pubkey();
+            let vote_account = bank0.get_account(&vote_pubkey).unwrap();
+            let stake_pubkey = Pubkey::new_unique();
+            let stake_account = stake_utils::create_stake_account(
+                &stake_pubkey,
+                &vote_pubkey,
+                &vote_account,
+                &genesis_config.rent,
+                1_000_000_000,
+            );
+            bank0.store_account(&stake_pubkey, &stake_account);
+        }
+        let first_slot_in_next_epoch = bank0.epoch_schedule().get_first_slot_in_epoch(1);
+        let bank1 = Bank::new_from_parent(bank0, SlotLeader::default(), first_slot_in_next_epoch);
+        (bank1, bank_forks)
+    };
+
+    // The stakes activated at the boundary are the same with and without precomputing them
+    let (bank1, _bank_forks) = new_epoch_bank(false, false);
+    assert_eq!(
+        bank1.precomputed_activated_stake_counts(),
+        PrecomputedActivatedStakeCounts::default()
+    );
+    let (precomputed_bank1, _precomputed_bank_forks) = new_epoch_bank(true, false);
+    assert_eq!(
+        precomputed_bank1.precomputed_activated_stake_counts(),
+        PrecomputedActivatedStakeCounts {
+            reused: 1,
+            mismatched: 0,
+        }
+    );
+    assert!(*precomputed_bank1.stakes_cache.stakes() == *bank1.stakes_cache.stakes());
+    assert_eq!(precomputed_bank1.epoch_stakes, bank1.epoch_stakes);
+
+    // Stake delegated after precomputing is not missed
+    let (bank1, _bank_forks) = new_epoch_bank(false, true);
+    let (precomputed_bank1, _precomputed_bank_forks) = new_epoch_bank(true, true);
+    assert_eq!(
+        precomputed_bank1.precomputed_activated_stake_counts(),
+        PrecomputedActivatedStakeCounts {
+            reused: 0,
+            mismatched: 1,
+        }
+    );
+    assert!(*precomputed_bank1.stakes_cache.stakes() == *bank1.stakes_cache.stakes());
+    assert_eq!(precomputed_bank1.epoch_stakes, bank1.epoch_stakes);
+}
+
 /// Ensure rehash() does *not* change the bank hash if accounts are unmodified
 #[test]
 fn test_rehash_accounts_unmodified() {
@@ -283,6 +283,10 @@ impl BankForks {
         );
     }
 
+    pub fn has_scheduler_pool(&self) -> bool {
+        self.scheduler_pool.is_some()
+    }
+
     pub fn insert(&mut self, bank: Bank) -> BankWithScheduler {
         self.insert_with_scheduling_mode(SchedulingMode::BlockVerification, bank)
     }
@@ -101,6 +101,7 @@ pub fn bank_fields_from_snapshot_archives(
         incremental_snapshot_archive_info.as_ref(),
         &account_paths,
         &io_setup,
+        None,
     )?;
 
     bank_fields_from_snapshots(
@@ -191,6 +192,7 @@ pub fn bank_from_snapshot_archives(
         incremental_snapshot_archive_info,
         account_paths,
         &io_setup,
+        snapshot_config.unpack_progress.as_ref(),
     )?;
 
     if let Some(incremental_storage) = incremental_storage {
@@ -733,7 +735,7 @@ pub fn bank_to_full_snapshot_archive(
         snapshot_config.snapshot_version,
         snapshot_package.bank_snapshot_package,
         snapshot_storages.as_slice(),
-        false, // we do not intend to fastboot, so skip flushing and hard linking the storages
+        None, // we do not intend to fastboot, so skip flushing and hard linking the storages
         &io_setup,
     )?;
 
@@ -806,7 +808,7 @@ pub fn bank_to_incremental_snapshot_archive(
         snapshot_config.snapshot_version,
         snapshot_package.bank_snapshot_package,
         snapshot_storages.as_slice(),
-        false, // we do not intend to fastboot, so skip flushing and hard linking the storages
+        None, // we do not intend to fastboot, so skip flushing and hard linking the storages
         &io_setup,
     )?;
 
@@ -844,7 +846,8 @@ mod tests {
             status_cache::{Status, StatusCache},
         },
         agave_snapshots::{
-            SnapshotVersion, error::VerifySlotDeltasError, paths::get_bank_snapshot_dir,
+            SnapshotVersion, UnpackProgress, error::VerifySlotDeltasError,
+            paths::get_bank_snapshot_dir,
         },
         semver::Version,
         solana_accounts_db::{
@@ -862,7 +865,10 @@ mod tests {
             fs,
             path::Path,
             slice,
-            sync::{Arc, atomic::Ordering},
+            sync::{
+                Arc,
+                atomic::{AtomicU64, Ordering},
+            },
         },
         test_case::test_case,
     };
@@ -942,13 +948,17 @@ mod tests {
         };
 
         let snapshot_storages = bank.get_snapshot_storages(None);
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
 
         snapshot_utils::serialize_snapshot(
             bank_snapshots_dir.as_ref(),
             snapshot_version,
             bank_snapshot_package,
             snapshot_storages.as_slice(),
-            should_finalize,
+            should_finalize.then_some(&thread_pool),
             &IoSetupState::default(),
         )?;
 
@@ -999,6 +1009,69 @@ mod tests {
         assert_eq!(original_bank, roundtrip_bank);
     }
 
+    /// Test that loading a bank from a full snapshot archive reports how much of the archive has
+    /// been unpacked and how many accounts have been indexed
+    #[test]
+    fn test_bank_from_snapshot_archives_reports_progress() {
+        let genesis_config = GenesisConfig::default();
+        let original_bank = Bank::new_for_tests(&genesis_config);
+
+        original_bank.fill_bank_with_ticks_for_tests();
+        original_bank.set_block_id(Some(Hash::default()));
+
+        let (_tmp_dir, accounts_dir) = create_tmp_accounts_dir_for_tests();
+        let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+        let full_snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        let incremental_snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+
+        let unpack_progress = Arc::new(UnpackProgress::default());
+        let snapshot_config = SnapshotConfig {
+            unpack_progress: Some(unpack_progress.clone()),
+            ..snapshot_config_for_tests(
+                &bank_snapshots_dir,
+                &full_snapshot_archives_dir,
+                &incremental_snapshot_archives_dir,
+            )
+        };
+        let snapshot_archive_info =
+            bank_to_full_snapshot_archive(&snapshot_config, &original_bank).unwrap();
+
+        let num_accounts_indexed = Arc::new(AtomicU64::new(0));
+        let accounts_db_config = AccountsDbConfig {
+            num_accounts_indexed: Some(num_accounts_indexed.clone()),
+            ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+        };
+        let roundtrip_bank = bank_from_snapshot_archives(
+            &[accounts_dir],
+            &snapshot_archive_info,
+            None,
+            &snapshot_config,
+            &genesis_config,
+            &RuntimeConfig::default(),
+            None,
+            Some(*original_bank.leader()), // genesis doesn't have a staked node
+            None,
+            false,
+            false,
+            false,
+            accounts_db_config,
+            None,
+            Arc::default(),
+        )
+        .unwrap();
+        assert_eq!(original_bank, roundtrip_bank);
+
+        let archive_size = fs::metadata(snapshot_archive_info.path()).unwrap().len();
+        assert_eq!(
+            unpack_progress.total_bytes.load(Ordering::Relaxed),
+            archive_size
+        );
+        let bytes_unpacked = unpack_progress.bytes_unpacked.load(Ordering::Relaxed);
+        assert!(bytes_unpacked > 0);
+        assert!(bytes_unpacked <= archive_size);
+        assert!(num_accounts_indexed.load(Ordering::Relaxed) > 0);
+    }
+
     /// This tests handling of obsolete accounts during a full snapshot with obsolete accounts
     /// marked in the accounts database. This test injects them directly
     #[test]
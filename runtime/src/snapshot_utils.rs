@@ -20,7 +20,8 @@ use {
         io_setup::IoSetupState,
     },
     agave_snapshots::{
-        ArchiveFormat, Result, SnapshotArchiveKind, SnapshotVersion, archive_snapshot,
+        ArchiveFormat, Result, SnapshotArchiveKind, SnapshotVersion, UnpackProgress,
+        archive_snapshot,
         error::{
             AddBankSnapshotError, SnapshotError, SnapshotFastbootError, SnapshotNewFromDirError,
         },
@@ -35,6 +36,10 @@ use {
     },
     crossbeam_channel::Receiver,
     log::*,
+    rayon::{
+        ThreadPool,
+        iter::{IntoParallelRefIterator, ParallelIterator},
+    },
     regex::Regex,
     semver::Version,
     solana_accounts_db::{
@@ -57,7 +62,7 @@ use {
         num::NonZeroUsize,
         path::{Path, PathBuf},
         str::FromStr,
-        sync::{Arc, LazyLock},
+        sync::{Arc, LazyLock, atomic},
         thread,
     },
     tempfile::TempDir,
@@ -408,7 +413,9 @@ pub fn get_highest_loadable_bank_snapshot(
 }
 
 /// If the validator halts in the middle of `archive_snapshot_package()`, the temporary staging
-/// directory won't be cleaned up.  Call this function to clean them up.
+/// directory won't be cleaned up.  Call this function to clean them up from
+/// `snapshot_archives_dir`, for each of the archives dirs as well as the bank snapshots dir,
+/// which holds the staging directories of archives written to another filesystem.
 pub fn remove_tmp_snapshot_archives(snapshot_archives_dir: impl AsRef<Path>) {
     if let Ok(entries) = std::fs::read_dir(snapshot_archives_dir) {
         for entry in entries.flatten() {
@@ -481,12 +488,15 @@ pub fn archive_snapshot_package(
 }
 
 /// Serializes a snapshot into `bank_snapshots_dir`
+///
+/// If `finalize_thread_pool` is set, the snapshot is also made fastboot-loadable, and its
+/// storages are flushed in parallel on that pool.
 pub fn serialize_snapshot(
     bank_snapshots_dir: impl AsRef<Path>,
     snapshot_version: SnapshotVersion,
     bank_snapshot_package: BankSnapshotPackage,
     snapshot_storages: &[Arc<AccountStorageEntry>],
-    should_finalize: bool,
+    finalize_thread_pool: Option<&ThreadPool>,
     io_setup: &IoSetupState,
 ) -> Result<BankSnapshotInfo> {
     let BankSnapshotPackage {
@@ -562,26 +572,32 @@ pub fn serialize_snapshot(
         );
 
         let (flush_storages_us, serialize_obsolete_accounts_us, write_storages_list_us) =
-            if should_finalize {
+            if let Some(thread_pool) = finalize_thread_pool {
                 let flush_measure = Measure::start("");
-                for storage in snapshot_storages {
+                try_for_each_storage(thread_pool, snapshot_storages, |storage| {
                     storage.flush().map_err(|err| {
                         AddBankSnapshotError::FlushStorage(err, storage.path().to_path_buf())
                     })?;
                     // We're about to mark this snapshot fastboot-loadable. Pin the storage
                     // file so it outlives the validator-exit Drop chain.
                     storage.disable_remove_on_drop();
-                }
+                    Ok(())
+                })?;
                 let flush_us = flush_measure.end_as_us();
 
                 let (_, serialize_obsolete_accounts_us) = measure_us!({
-                    write_obsolete_accounts_to_snapshot(
-                        &bank_snapshot_dir,
-                        snapshot_storages,
-                        slot,
-                        io_setup,
-                    )
-                    .map_err(|err| AddBankSnapshotError::SerializeObsoleteAccounts(Box::new(err)))?
+                    thread_pool
+                        .install(|| {
+                            write_obsolete_accounts_to_snapshot(
+                                &bank_snapshot_dir,
+                                snapshot_storages,
+                                slot,
+                                io_setup,
+                            )
+                        })
+                        .map_err(|err| {
+                            AddBankSnapshotError::SerializeObsoleteAccounts(Box::new(err))
+                        })?
                 });
 
                 let (_, write_storages_list_us) = measure_us!(
@@ -640,6 +656,15 @@ pub fn serialize_snapshot(
     do_serialize_snapshot().map_err(|err| SnapshotError::AddBankSnapshot(err, slot))
 }
 
+// Runs `f` on every storage in parallel on `thread_pool`, rather than on the global pool
+fn try_for_each_storage<S: Sync>(
+    thread_pool: &ThreadPool,
+    snapshot_storages: &[S],
+    f: impl Fn(&S) -> std::result::Result<(), AddBankSnapshotError> + Send + Sync,
+) -> std::result::Result<(), AddBankSnapshotError> {
+    thread_pool.install(|| snapshot_storages.par_iter().try_for_each(f))
+}
+
 /// Get the bank snapshots in a directory
 pub fn get_bank_snapshots(bank_snapshots_dir: impl AsRef<Path>) -> Vec<BankSnapshotInfo> {
     let mut bank_snapshots = Vec::default();
@@ -989,12 +1014,26 @@ pub fn verify_and_unarchive_snapshots(
     incremental_snapshot_archive_info: Option<&IncrementalSnapshotArchiveInfo>,
     account_paths: &[PathBuf],
     io_setup: &IoSetupState,
+    unpack_progress: Option<&Arc<UnpackProgress>>,
 ) -> Result<(UnarchivedSnapshots, UnarchivedSnapshotsGuard)> {
     check_are_snapshots_compatible(
         full_snapshot_archive_info,
         incremental_snapshot_archive_info,
     )?;
 
+    if let Some(unpack_progress) = unpack_progress {
+        let mut total_bytes = fs::metadata(full_snapshot_archive_info.path())?.len();
+        if let Some(incremental_snapshot_archive_info) = incremental_snapshot_archive_info {
+            total_bytes += fs::metadata(incremental_snapshot_archive_info.path())?.len();
+        }
+        unpack_progress
+            .bytes_unpacked
+            .store(0, atomic::Ordering::Relaxed);
+        unpack_progress
+            .total_bytes
+            .store(total_bytes, atomic::Ordering::Relaxed);
+    }
+
     let next_append_vec_id = Arc::new(AtomicAccountsFileId::new(0));
     let UnarchivedSnapshot {
         unpack_dir: full_unpack_dir,
@@ -1012,6 +1051,7 @@ pub fn verify_and_unarchive_snapshots(
         full_snapshot_archive_info.archive_format(),
         next_append_vec_id.clone(),
         io_setup,
+        unpack_progress,
     )?;
 
     let (
@@ -1038,6 +1078,7 @@ pub fn verify_and_unarchive_snapshots(
             incremental_snapshot_archive_info.archive_format(),
             next_append_vec_id.clone(),
             io_setup,
+            unpack_progress,
         )?;
         (
             Some(unpack_dir),
@@ -1181,6 +1222,17 @@ fn snapshot_fields_from_files(file_receiver: &Receiver<FileInfo>) -> Result<Snap
     })
 }
 
+/// Reads the snapshot hash a bank snapshot file is advertised with in gossip.
+///
+/// The snapshot hash is the checksum of the accounts lt hash of the bank, so
+/// it is checked without loading the accounts of the snapshot.
+pub fn snapshot_hash_from_bank_snapshot(bank_snapshot: impl Read) -> io::Result<SnapshotHash> {
+    let (bank_fields, _accounts_db_fields) =
+        serde_snapshot::fields_from_stream(&mut BufReader::new(bank_snapshot))
+            .map_err(IoError::other)?;
+    Ok(SnapshotHash::new(bank_fields.accounts_lt_hash.0.checksum()))
+}
+
 /// BankSnapshotInfo::new_from_dir() requires a few meta files to accept a snapshot dir
 /// as a valid one.  A dir unpacked from an archive lacks these files.  Fill them here to
 /// allow new_from_dir() checks to pass.  These checks are not needed for unpacked dirs,
@@ -1229,6 +1281,7 @@ fn unarchive_snapshot(
     archive_format: ArchiveFormat,
     next_append_vec_id: Arc<AtomicAccountsFileId>,
     io_setup: &IoSetupState,
+    unpack_progress: Option<&Arc<UnpackProgress>>,
 ) -> Result<UnarchivedSnapshot> {
     let unpack_dir = tempfile::Builder::new()
         .prefix(unpacked_snapshots_dir_prefix)
@@ -1245,6 +1298,7 @@ fn unarchive_snapshot(
             snapshot_archive_path.as_ref().to_path_buf(),
             archive_format,
             io_setup,
+            unpack_progress.cloned(),
         );
 
         let snapshot_result = snapshot_fields_from_files(&file_receiver).and_then(
@@ -1890,7 +1944,7 @@ mod tests {
         bincode::{deserialize_from, serialize_into},
         solana_accounts_db::accounts_file::{AccountsFile, AccountsFileProvider},
         solana_hash::Hash,
-        std::{convert::TryFrom, mem::size_of},
+        std::{collections::HashSet, convert::TryFrom, mem::size_of, sync::Mutex, thread},
         tempfile::NamedTempFile,
         test_case::test_case,
     };
@@ -1928,6 +1982,30 @@ mod tests {
         assert_matches!(result, Err(SnapshotError::Io(ref message)) if message.to_string().contains("bytes would exceed limit of"));
     }
 
+    #[test]
+    fn test_try_for_each_storage_runs_on_thread_pool() {
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .thread_name(|i| format!("solTestPool{i:02}"))
+            .build()
+            .unwrap();
+        let thread_names = Mutex::new(HashSet::new());
+        try_for_each_storage(&thread_pool, &[0; 64], |_| {
+            let thread_name = thread::current().name().map(str::to_string);
+            thread_names.lock().unwrap().insert(thread_name);
+            Ok(())
+        })
+        .unwrap();
+        let thread_names = thread_names.into_inner().unwrap();
+        assert!(!thread_names.is_empty());
+        assert!(
+            thread_names.iter().all(|thread_name| thread_name
+                .as_deref()
+                .is_some_and(|thread_name| thread_name.starts_with("solTestPool"))),
+            "{thread_names:?}"
+        );
+    }
+
     #[test]
     fn test_deserialize_snapshot_data_file_under_limit() {
         let expected_data = 2323_u32;
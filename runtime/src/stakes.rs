@@ -412,6 +412,27 @@ impl Stakes<StakeAccount> {
         stake_delegations: &[(&Pubkey, &StakeAccount)],
         use_fixed_point_stake_math: bool,
     ) -> (StakeHistory, VoteAccounts) {
+        let activated_stake = self.calculate_activated_stake_delegations(
+            next_epoch,
+            thread_pool,
+            new_rate_activation_epoch,
+            stake_delegations,
+            use_fixed_point_stake_math,
+        );
+        self.activated_stake_history_and_vote_accounts(&activated_stake)
+    }
+
+    /// Calculates the part of `calculate_activated_stake()` which only depends
+    /// on the stake delegations and the stake history, and not on the state of
+    /// the vote accounts.
+    pub(crate) fn calculate_activated_stake_delegations(
+        &self,
+        next_epoch: Epoch,
+        thread_pool: &ThreadPool,
+        new_rate_activation_epoch: Option<Epoch>,
+        stake_delegations: &[(&Pubkey, &StakeAccount)],
+        use_fixed_point_stake_math: bool,
+    ) -> ActivatedStake {
         // Wrap up the prev epoch by adding new stake history entry for the
         // prev epoch.
         let stake_history_entry = thread_pool.install(|| {
@@ -437,16 +458,30 @@ impl Stakes<StakeAccount> {
         stake_history.add(self.epoch, stake_history_entry);
         // Refresh the stake distribution of vote accounts for the next epoch,
         // using new stake history.
-        let vote_accounts = refresh_vote_accounts(
+        let delegated_stakes = delegated_stakes(
             thread_pool,
             next_epoch,
-            &self.vote_accounts,
             stake_delegations,
             &stake_history,
             new_rate_activation_epoch,
             use_fixed_point_stake_math,
         );
-        (stake_history, vote_accounts)
+        ActivatedStake {
+            stake_history,
+            delegated_stakes,
+        }
+    }
+
+    /// Returns the stake history and vote accounts `calculate_activated_stake()`
+    /// returns, from the result of `calculate_activated_stake_delegations()`
+    pub(crate) fn activated_stake_history_and_vote_accounts(
+        &self,
+        activated_stake: &ActivatedStake,
+    ) -> (StakeHistory, VoteAccounts) {
+        (
+            activated_stake.stake_history.clone(),
+            activated_stake.vote_accounts(&self.vote_accounts),
+        )
     }
 
     pub(crate) fn activate_epoch(
@@ -668,15 +703,38 @@ impl From<Stakes<Stake>> for Stakes<Delegation> {
     }
 }
 
-fn refresh_vote_accounts(
+/// The stake history and the stake delegated to each vote account once a new
+/// epoch is activated, as calculated from the stake delegations
+#[derive(Debug)]
+pub(crate) struct ActivatedStake {
+    stake_history: StakeHistory,
+    delegated_stakes: HashMap</*voter:*/ Pubkey, /*stake:*/ u64>,
+}
+
+impl ActivatedStake {
+    fn vote_accounts(&self, vote_accounts: &VoteAccounts) -> VoteAccounts {
+        vote_accounts
+            .iter()
+            .map(|(&vote_pubkey, vote_account)| {
+                let delegated_stake = self
+                    .delegated_stakes
+                    .get(&vote_pubkey)
+                    .copied()
+                    .unwrap_or_default();
+                (vote_pubkey, (delegated_stake, vote_account.clone()))
+            })
+            .collect()
+    }
+}
+
+fn delegated_stakes(
     thread_pool: &ThreadPool,
     epoch: Epoch,
-    vote_accounts: &VoteAccounts,
     stake_delegations: &[(&Pubkey, &StakeAccount)],
     stake_history: &StakeHistory,
     new_rate_activation_epoch: Option<Epoch>,
     use_fixed_point_stake_math: bool,
-) -> VoteAccounts {
+) -> HashMap</*voter:*/ Pubkey, /*stake:*/ u64> {
     type StakesHashMap = HashMap</*voter:*/ Pubkey, /*stake:*/ u64>;
     fn merge(mut stakes: StakesHashMap, other: StakesHashMap) -> StakesHashMap {
         if stakes.len() < other.len() {
@@ -687,7 +745,7 @@ fn refresh_vote_accounts(
         }
         stakes
     }
-    let delegated_stakes = thread_pool.install(|| {
+    thread_pool.install(|| {
         stake_delegations
             .par_iter()
             .fold(
@@ -707,17 +765,7 @@ fn refresh_vote_accounts(
                 },
             )
             .reduce(HashMap::default, merge)
-    });
-    vote_accounts
-        .iter()
-        .map(|(&vote_pubkey, vote_account)| {
-            let delegated_stake = delegated_stakes
-                .get(&vote_pubkey)
-                .copied()
-                .unwrap_or_default();
-            (vote_pubkey, (delegated_stake, vote_account.clone()))
-        })
-        .collect()
+    })
 }
 
 #[cfg(test)]
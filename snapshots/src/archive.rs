@@ -20,7 +20,13 @@ use {
     solana_clock::Slot,
     solana_measure::measure::Measure,
     solana_metrics::datapoint_info,
-    std::{fs, io::Write, path::Path, sync::Arc},
+    std::{
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::Instant,
+    },
 };
 
 // Balance large and small files order in snapshot tar with bias towards small (4 small + 1 large),
@@ -35,6 +41,55 @@ const STORAGE_FILE_OPEN_CHUNK_SIZE: usize = 25
     * (INTERLEAVE_TAR_ENTRIES_SMALL_TO_LARGE_RATIO.0
         + INTERLEAVE_TAR_ENTRIES_SMALL_TO_LARGE_RATIO.1);
 
+/// How an archive gets from the bank snapshot to the archives dir
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchivePublishStrategy {
+    /// The bank snapshot and the archives dir share a filesystem
+    SameFilesystem,
+    /// The archives dir is on another filesystem, e.g. a network volume. The
+    /// staging dir stays with the bank snapshot so only the archive itself is
+    /// written to the archives dir.
+    CrossFilesystem,
+}
+
+impl ArchivePublishStrategy {
+    /// Picks the strategy given whether `bank_snapshot_dir` and `archives_dir`
+    /// are on the same filesystem
+    pub fn detect(
+        bank_snapshot_dir: impl AsRef<Path>,
+        archives_dir: impl AsRef<Path>,
+    ) -> std::result::Result<Self, ArchiveSnapshotPackageError> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+            let device = |path: &Path| {
+                fs::metadata(path)
+                    .map(|metadata| metadata.dev())
+                    .map_err(|err| {
+                        ArchiveSnapshotPackageError::QueryFilesystem(err, path.to_path_buf())
+                    })
+            };
+            if device(bank_snapshot_dir.as_ref())? == device(archives_dir.as_ref())? {
+                Ok(Self::SameFilesystem)
+            } else {
+                Ok(Self::CrossFilesystem)
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (bank_snapshot_dir, archives_dir);
+            Ok(Self::SameFilesystem)
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::SameFilesystem => "same-filesystem",
+            Self::CrossFilesystem => "cross-filesystem",
+        }
+    }
+}
+
 /// Archives a snapshot into `archive_path`
 pub fn archive_snapshot(
     snapshot_archive_kind: SnapshotArchiveKind,
@@ -45,6 +100,33 @@ pub fn archive_snapshot(
     archive_path: impl AsRef<Path>,
     archive_format: ArchiveFormat,
     io_setup: &IoSetupState,
+) -> Result<SnapshotArchiveInfo> {
+    archive_snapshot_with_strategy(
+        snapshot_archive_kind,
+        snapshot_slot,
+        snapshot_hash,
+        snapshot_storages,
+        bank_snapshot_dir,
+        archive_path,
+        archive_format,
+        io_setup,
+        None,
+    )
+}
+
+/// Archives a snapshot into `archive_path`, detecting the publish strategy
+/// unless one is given
+#[allow(clippy::too_many_arguments)]
+fn archive_snapshot_with_strategy(
+    snapshot_archive_kind: SnapshotArchiveKind,
+    snapshot_slot: Slot,
+    snapshot_hash: SnapshotHash,
+    snapshot_storages: &[Arc<AccountStorageEntry>],
+    bank_snapshot_dir: impl AsRef<Path>,
+    archive_path: impl AsRef<Path>,
+    archive_format: ArchiveFormat,
+    io_setup: &IoSetupState,
+    publish_strategy: Option<ArchivePublishStrategy>,
 ) -> Result<SnapshotArchiveInfo> {
     use ArchiveSnapshotPackageError as E;
     const ACCOUNTS_DIR: &str = "accounts";
@@ -58,12 +140,26 @@ pub fn archive_snapshot(
 
     fs::create_dir_all(tar_dir).map_err(|err| E::CreateArchiveDir(err, tar_dir.to_path_buf()))?;
 
+    let publish_strategy = match publish_strategy {
+        Some(publish_strategy) => publish_strategy,
+        None => ArchivePublishStrategy::detect(&bank_snapshot_dir, tar_dir)?,
+    };
+    // The staging dir only holds symlinks to the bank snapshot files, which
+    // are better kept off a remote archives dir
+    let staging_parent_dir = match publish_strategy {
+        ArchivePublishStrategy::SameFilesystem => tar_dir,
+        ArchivePublishStrategy::CrossFilesystem => bank_snapshot_dir
+            .as_ref()
+            .parent()
+            .expect("Bank snapshot dir is invalid"),
+    };
+
     // Create the staging directories
     let staging_dir_prefix = paths::TMP_SNAPSHOT_ARCHIVE_PREFIX;
     let staging_dir = tempfile::Builder::new()
         .prefix(&format!("{staging_dir_prefix}{snapshot_slot}-"))
-        .tempdir_in(tar_dir)
-        .map_err(|err| E::CreateStagingDir(err, tar_dir.to_path_buf()))?;
+        .tempdir_in(staging_parent_dir)
+        .map_err(|err| E::CreateStagingDir(err, staging_parent_dir.to_path_buf()))?;
     let staging_snapshots_dir = staging_dir.path().join(paths::BANK_SNAPSHOTS_DIR);
 
     let slot_str = snapshot_slot.to_string();
@@ -95,7 +191,9 @@ pub fn archive_snapshot(
         E::SymlinkVersionFile(err, src_version_file, staging_version_file.clone())
     })?;
 
-    // Tar the staging directory into the archive at `staging_archive_path`
+    // Tar the staging directory into the archive at `staging_archive_path`,
+    // which is on the filesystem of the archives dir so that the archive can
+    // be renamed into position
     let staging_archive_path = tar_dir.join(format!(
         "{}{}.{}",
         staging_dir_prefix,
@@ -103,6 +201,7 @@ pub fn archive_snapshot(
         archive_format.extension(),
     ));
 
+    let write_start = Instant::now();
     {
         let archive_writer = large_file_buf_writer(&staging_archive_path, io_setup)
             .map_err(|err| E::CreateArchiveFile(err, staging_archive_path.clone()))?;
@@ -221,20 +320,21 @@ pub fn archive_snapshot(
         };
     }
 
-    // Atomically move the archive into position for other validators to find
-    let metadata = fs::metadata(&staging_archive_path)
-        .map_err(|err| E::QueryArchiveMetadata(err, staging_archive_path.clone()))?;
+    let write_duration = write_start.elapsed();
+
+    let publish_start = Instant::now();
     let archive_path = archive_path.as_ref().to_path_buf();
-    fs::rename(&staging_archive_path, &archive_path)
-        .map_err(|err| E::MoveArchive(err, staging_archive_path, archive_path.clone()))?;
+    let archive_size = publish_archive(staging_archive_path, &archive_path)?;
+    let publish_duration = publish_start.elapsed();
 
     timer.stop();
     info!(
-        "Successfully created {}. slot: {}, elapsed ms: {}, size: {}",
+        "Successfully created {}. slot: {}, elapsed ms: {}, size: {}, strategy: {}",
         archive_path.display(),
         snapshot_slot,
         timer.as_ms(),
-        metadata.len()
+        archive_size,
+        publish_strategy.as_str(),
     );
 
     datapoint_info!(
@@ -248,7 +348,16 @@ pub fn archive_snapshot(
             } else {
                 "incremental-snapshot-archive-size"
             },
-            metadata.len(),
+            archive_size,
+            i64
+        ),
+        ("publish_strategy", publish_strategy.as_str(), String),
+        ("write_duration_ms", write_duration.as_millis(), i64),
+        ("publish_duration_ms", publish_duration.as_millis(), i64),
+        // Archive bytes written per second, tarring and compressing included
+        (
+            "archive_bytes_per_second",
+            (archive_size as f64 / write_duration.as_secs_f64().max(f64::EPSILON)) as u64,
             i64
         ),
     );
@@ -259,3 +368,210 @@ pub fn archive_snapshot(
         archive_format,
     })
 }
+
+/// Publishes the archive written to `staging_archive_path` under
+/// `archive_path`, both in the same dir, once it is durable. The archive is
+/// never visible under `archive_path` before it is complete.
+///
+/// Returns the size of the archive.
+fn publish_archive(
+    staging_archive_path: PathBuf,
+    archive_path: &Path,
+) -> std::result::Result<u64, ArchiveSnapshotPackageError> {
+    use ArchiveSnapshotPackageError as E;
+    let staging_archive = fs::File::open(&staging_archive_path)
+        .map_err(|err| E::SyncArchive(err, staging_archive_path.clone()))?;
+    staging_archive
+        .sync_all()
+        .map_err(|err| E::SyncArchive(err, staging_archive_path.clone()))?;
+    let metadata = staging_archive
+        .metadata()
+        .map_err(|err| E::QueryArchiveMetadata(err, staging_archive_path.clone()))?;
+    drop(staging_archive);
+
+    // Atomically move the archive into position for other validators to find
+    fs::rename(&staging_archive_path, archive_path)
+        .map_err(|err| E::MoveArchive(err, staging_archive_path, archive_path.to_path_buf()))?;
+    // Persist the rename, directories cannot be opened to be synced on windows
+    #[cfg(unix)]
+    {
+        let archive_dir = archive_path.parent().expect("Tar output path is invalid");
+        fs::File::open(archive_dir)
+            .and_then(|archive_dir| archive_dir.sync_all())
+            .map_err(|err| E::SyncArchiveDir(err, archive_dir.to_path_buf()))?;
+    }
+    Ok(metadata.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::ZstdConfig, assert_matches::assert_matches, solana_hash::Hash,
+        std::io::Read,
+    };
+
+    fn tmp_entries(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .starts_with(paths::TMP_SNAPSHOT_ARCHIVE_PREFIX)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_archive_publish_strategy_detect() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let bank_snapshots_dir = dir.path().join(paths::BANK_SNAPSHOTS_DIR);
+        let archives_dir = dir.path().join("archives");
+        fs::create_dir_all(&bank_snapshots_dir).unwrap();
+        fs::create_dir_all(&archives_dir).unwrap();
+        assert_eq!(
+            ArchivePublishStrategy::detect(&bank_snapshots_dir, &archives_dir).unwrap(),
+            ArchivePublishStrategy::SameFilesystem
+        );
+        assert_matches!(
+            ArchivePublishStrategy::detect(&bank_snapshots_dir, dir.path().join("missing")),
+            Err(ArchiveSnapshotPackageError::QueryFilesystem(..))
+        );
+
+        // tmpfs is another filesystem, where available
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+            let shm_dir = Path::new("/dev/shm");
+            if shm_dir.is_dir()
+                && fs::metadata(shm_dir).unwrap().dev() != fs::metadata(dir.path()).unwrap().dev()
+            {
+                assert_eq!(
+                    ArchivePublishStrategy::detect(&bank_snapshots_dir, shm_dir).unwrap(),
+                    ArchivePublishStrategy::CrossFilesystem
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_archive_snapshot_publish_strategies() {
+        const SLOT: Slot = 42;
+        for publish_strategy in [
+            ArchivePublishStrategy::SameFilesystem,
+            ArchivePublishStrategy::CrossFilesystem,
+        ] {
+            let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+            let archives_dir = tempfile::TempDir::new().unwrap();
+            let bank_snapshot_dir = bank_snapshots_dir.path().join(SLOT.to_string());
+            fs::create_dir_all(&bank_snapshot_dir).unwrap();
+            fs::write(bank_snapshot_dir.join(SLOT.to_string()), b"bank").unwrap();
+            fs::write(
+                bank_snapshot_dir.join(paths::SNAPSHOT_STATUS_CACHE_FILENAME),
+                b"status cache",
+            )
+            .unwrap();
+            fs::write(
+                bank_snapshot_dir.join(paths::SNAPSHOT_VERSION_FILENAME),
+                b"1.2.0",
+            )
+            .unwrap();
+
+            let archive_format = ArchiveFormat::TarZstd {
+                config: ZstdConfig::default(),
+            };
+            let snapshot_hash = SnapshotHash(Hash::new_unique());
+            let archive_path = paths::build_full_snapshot_archive_path(
+                archives_dir.path(),
+                SLOT,
+                &snapshot_hash,
+                archive_format,
+            );
+            let archive_info = archive_snapshot_with_strategy(
+                SnapshotArchiveKind::Full,
+                SLOT,
+                snapshot_hash,
+                &[],
+                &bank_snapshot_dir,
+                &archive_path,
+                archive_format,
+                &IoSetupState::default(),
+                Some(publish_strategy),
+            )
+            .unwrap();
+            assert_eq!(archive_info.path, archive_path);
+
+            // Only the published archive is left behind
+            assert!(tmp_entries(archives_dir.path()).is_empty());
+            assert!(tmp_entries(bank_snapshots_dir.path()).is_empty());
+            assert_eq!(fs::read_dir(archives_dir.path()).unwrap().count(), 1);
+
+            let decoder =
+                zstd::stream::read::Decoder::new(fs::File::open(&archive_path).unwrap()).unwrap();
+            let mut archive = tar::Archive::new(decoder);
+            let mut entries: Vec<_> = archive
+                .entries()
+                .unwrap()
+                .map(|entry| {
+                    let mut entry = entry.unwrap();
+                    let path = entry.path().unwrap().to_path_buf();
+                    let mut data = Vec::new();
+                    entry.read_to_end(&mut data).unwrap();
+                    (path, data)
+                })
+                .filter(|(_, data)| !data.is_empty())
+                .collect();
+            entries.sort();
+            assert_eq!(
+                entries,
+                vec![
+                    (
+                        Path::new(paths::BANK_SNAPSHOTS_DIR).join("42").join("42"),
+                        b"bank".to_vec()
+                    ),
+                    (
+                        Path::new(paths::BANK_SNAPSHOTS_DIR)
+                            .join(paths::SNAPSHOT_STATUS_CACHE_FILENAME),
+                        b"status cache".to_vec()
+                    ),
+                    (
+                        PathBuf::from(paths::SNAPSHOT_VERSION_FILENAME),
+                        b"1.2.0".to_vec()
+                    ),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_publish_archive() {
+        let archives_dir = tempfile::TempDir::new().unwrap();
+        let staging_archive_path = archives_dir
+            .path()
+            .join(format!("{}42.tar.zst", paths::TMP_SNAPSHOT_ARCHIVE_PREFIX));
+        let archive_path = archives_dir.path().join("snapshot-42-hash.tar.zst");
+
+        // A missing archive is not published
+        assert_matches!(
+            publish_archive(staging_archive_path.clone(), &archive_path),
+            Err(ArchiveSnapshotPackageError::SyncArchive(..))
+        );
+        assert!(!archive_path.exists());
+
+        // The archive being written is only visible under its staging name
+        let mut staging_archive = fs::File::create(&staging_archive_path).unwrap();
+        staging_archive.write_all(b"partial").unwrap();
+        assert!(!archive_path.exists());
+        staging_archive.write_all(b" archive").unwrap();
+        drop(staging_archive);
+
+        assert_eq!(
+            publish_archive(staging_archive_path.clone(), &archive_path).unwrap(),
+            15
+        );
+        assert!(!staging_archive_path.exists());
+        assert_eq!(fs::read(&archive_path).unwrap(), b"partial archive");
+    }
+}
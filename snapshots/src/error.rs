@@ -142,6 +142,28 @@ pub enum SnapshotNewFromDirError {
     MissingSnapshotFile(PathBuf),
 }
 
+/// Errors that can happen in `verify_snapshot_archive()`
+#[derive(Error, Debug)]
+pub enum VerifySnapshotArchiveError {
+    #[error("failed to read snapshot archive: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("missing version file")]
+    MissingVersionFile,
+
+    #[error("invalid snapshot version '{0}'")]
+    InvalidVersion(String),
+
+    #[error("missing bank snapshot for slot {0}")]
+    MissingBankSnapshot(Slot),
+
+    #[error("mismatched snapshot hash: expected {}, got {}", .expected.0, .actual.0)]
+    MismatchedHash {
+        expected: SnapshotHash,
+        actual: SnapshotHash,
+    },
+}
+
 /// Errors that can happen in `verify_slot_deltas()`
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum VerifySlotDeltasError {
@@ -224,6 +246,9 @@ pub enum ArchiveSnapshotPackageError {
     #[error("failed to create archive path '{1}': {0}")]
     CreateArchiveDir(#[source] io::Error, PathBuf),
 
+    #[error("failed to query the filesystem of '{1}': {0}")]
+    QueryFilesystem(#[source] io::Error, PathBuf),
+
     #[error("failed to create staging dir inside '{1}': {0}")]
     CreateStagingDir(#[source] io::Error, PathBuf),
 
@@ -266,9 +291,15 @@ pub enum ArchiveSnapshotPackageError {
     #[error("failed to query archive metadata '{1}': {0}")]
     QueryArchiveMetadata(#[source] io::Error, PathBuf),
 
+    #[error("failed to sync archive '{1}': {0}")]
+    SyncArchive(#[source] io::Error, PathBuf),
+
     #[error("failed to move archive from '{1}' to '{2}': {0}")]
     MoveArchive(#[source] io::Error, PathBuf, PathBuf),
 
+    #[error("failed to sync archive dir '{1}': {0}")]
+    SyncArchiveDir(#[source] io::Error, PathBuf),
+
     #[error("failed to create account storage reader '{1}': {0}")]
     AccountStorageReaderError(#[source] io::Error, PathBuf),
 
@@ -16,10 +16,13 @@ mod unarchive;
 pub type Result<T> = std::result::Result<T, error::SnapshotError>;
 
 pub use {
-    archive::archive_snapshot,
+    archive::{ArchivePublishStrategy, archive_snapshot},
     archive_format::*,
     kind::{SnapshotArchiveKind, SnapshotKind},
     snapshot_interval::SnapshotInterval,
     snapshot_version::SnapshotVersion,
-    unarchive::{streaming_unarchive_snapshot, unpack_genesis_archive},
+    unarchive::{
+        UnpackProgress, streaming_unarchive_snapshot, unpack_genesis_archive,
+        verify_snapshot_archive,
+    },
 };
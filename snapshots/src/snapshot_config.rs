@@ -1,8 +1,9 @@
 use {
-    super::{ArchiveFormat, SnapshotInterval, SnapshotVersion, ZstdConfig},
+    super::{ArchiveFormat, SnapshotInterval, SnapshotVersion, UnpackProgress, ZstdConfig},
     std::{
         num::{NonZeroU64, NonZeroUsize},
         path::PathBuf,
+        sync::Arc,
     },
 };
 
@@ -56,6 +57,9 @@ pub struct SnapshotConfig {
     ///
     /// Requires memlock ulimit higher than sum of buffer sizes registered at the same time.
     pub use_registered_io_uring_buffers: bool,
+
+    /// Reports the progress of unpacking snapshot archives at startup
+    pub unpack_progress: Option<Arc<UnpackProgress>>,
 }
 
 impl Default for SnapshotConfig {
@@ -80,6 +84,7 @@ impl Default for SnapshotConfig {
                 DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
             use_direct_io: true,
             use_registered_io_uring_buffers: true,
+            unpack_progress: None,
         }
     }
 }
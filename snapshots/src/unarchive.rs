@@ -1,17 +1,23 @@
 use {
     crate::{
-        ArchiveFormat, ArchiveFormatDecompressor,
-        error::SnapshotError,
+        ArchiveFormat, ArchiveFormatDecompressor, SnapshotVersion,
+        error::{SnapshotError, VerifySnapshotArchiveError},
         hardened_unpack::{self, UnpackError},
+        paths,
+        snapshot_hash::SnapshotHash,
     },
     agave_fs::{FileInfo, buffered_reader, file_io::file_creator, io_setup::IoSetupState},
     bzip2::bufread::BzDecoder,
     crossbeam_channel::{SendError, Sender},
+    solana_clock::Slot,
     std::{
         fs,
-        io::{self, BufRead, BufReader},
+        io::{self, BufRead, BufReader, Read},
         path::{Path, PathBuf},
-        sync::OnceLock,
+        sync::{
+            Arc, OnceLock,
+            atomic::{AtomicU64, Ordering},
+        },
         thread::{self, Scope, ScopedJoinHandle},
         time::Instant,
     },
@@ -26,7 +32,51 @@ const MAX_SNAPSHOT_READER_BUF_SIZE: usize = 128 * 1024 * 1024;
 //   operations to complete.
 const MAX_UNPACK_WRITE_BUF_SIZE: usize = 512 * 1024 * 1024;
 
-/// Streams unpacked files across channel
+/// Progress of unpacking snapshot archives, in bytes read from the archives
+#[derive(Debug, Default)]
+pub struct UnpackProgress {
+    /// Bytes read from the archives so far
+    pub bytes_unpacked: AtomicU64,
+    /// Total size of the archives being unpacked
+    pub total_bytes: AtomicU64,
+}
+
+/// Counts the bytes consumed from `inner` into `UnpackProgress::bytes_unpacked`
+struct ProgressReader<R> {
+    inner: R,
+    unpack_progress: Option<Arc<UnpackProgress>>,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        if let Some(unpack_progress) = &self.unpack_progress {
+            unpack_progress
+                .bytes_unpacked
+                .fetch_add(len as u64, Ordering::Relaxed);
+        }
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for ProgressReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        if let Some(unpack_progress) = &self.unpack_progress {
+            unpack_progress
+                .bytes_unpacked
+                .fetch_add(amt as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Streams unpacked files across channel, counting the bytes read from the
+/// archive into `unpack_progress`
+#[allow(clippy::too_many_arguments)]
 pub fn streaming_unarchive_snapshot<'scope, 'env: 'scope>(
     scope: &'scope Scope<'scope, 'env>,
     file_sender: Sender<FileInfo>,
@@ -35,6 +85,7 @@ pub fn streaming_unarchive_snapshot<'scope, 'env: 'scope>(
     snapshot_archive_path: PathBuf,
     archive_format: ArchiveFormat,
     io_setup: &'env IoSetupState,
+    unpack_progress: Option<Arc<UnpackProgress>>,
 ) -> ScopedJoinHandle<'scope, Result<(), SnapshotError>> {
     let do_unpack = move |archive_path: &Path| {
         let first_failed_send = OnceLock::<PathBuf>::new();
@@ -46,8 +97,13 @@ pub fn streaming_unarchive_snapshot<'scope, 'env: 'scope>(
             let read_buf_size = MAX_SNAPSHOT_READER_BUF_SIZE.min(archive_size);
             let write_buf_size = MAX_UNPACK_WRITE_BUF_SIZE.min(archive_size);
 
-            let decompressor =
-                decompressed_tar_reader(archive_format, archive_path, read_buf_size, io_setup)?;
+            let decompressor = decompressed_tar_reader(
+                archive_format,
+                archive_path,
+                read_buf_size,
+                io_setup,
+                unpack_progress,
+            )?;
             (
                 decompressor,
                 file_creator(write_buf_size, io_setup, move |file_info| {
@@ -114,12 +170,165 @@ pub fn unpack_genesis_archive(
     Ok(())
 }
 
+/// Checks that a snapshot archive holds a supported snapshot version and the
+/// bank snapshot for `slot` with snapshot hash `hash`, without unpacking it.
+///
+/// Archives store the version and bank snapshot files ahead of the account
+/// storages, so only the leading entries of the archive are read.
+/// `read_snapshot_hash` reads the snapshot hash from the bank snapshot file.
+pub fn verify_snapshot_archive(
+    archive_path: &Path,
+    archive_format: ArchiveFormat,
+    slot: Slot,
+    hash: SnapshotHash,
+    read_snapshot_hash: impl FnOnce(&mut dyn Read) -> io::Result<SnapshotHash>,
+) -> Result<(), VerifySnapshotArchiveError> {
+    // The version file only holds a short version string
+    const MAX_VERSION_FILE_SIZE: u64 = 8;
+
+    let archive_file = BufReader::new(fs::File::open(archive_path)?);
+    let mut archive = tar::Archive::new(ArchiveFormatDecompressor::new(
+        archive_format,
+        archive_file,
+    )?);
+    let bank_snapshot_path = Path::new(paths::BANK_SNAPSHOTS_DIR)
+        .join(slot.to_string())
+        .join(slot.to_string());
+    let mut has_version = false;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if path == Path::new(paths::SNAPSHOT_VERSION_FILENAME) {
+            let mut version = String::new();
+            (&mut entry)
+                .take(MAX_VERSION_FILE_SIZE)
+                .read_to_string(&mut version)?;
+            let version = version.trim();
+            version
+                .parse::<SnapshotVersion>()
+                .map_err(|_| VerifySnapshotArchiveError::InvalidVersion(version.to_string()))?;
+            has_version = true;
+        } else if path == bank_snapshot_path {
+            if !has_version {
+                return Err(VerifySnapshotArchiveError::MissingVersionFile);
+            }
+            let actual = read_snapshot_hash(&mut entry)?;
+            return if actual == hash {
+                Ok(())
+            } else {
+                Err(VerifySnapshotArchiveError::MismatchedHash {
+                    expected: hash,
+                    actual,
+                })
+            };
+        } else if !path.starts_with(paths::BANK_SNAPSHOTS_DIR) {
+            // The account storages follow the bank snapshots
+            break;
+        }
+    }
+    Err(if has_version {
+        VerifySnapshotArchiveError::MissingBankSnapshot(slot)
+    } else {
+        VerifySnapshotArchiveError::MissingVersionFile
+    })
+}
+
 fn decompressed_tar_reader(
     archive_format: ArchiveFormat,
     archive_path: &Path,
     buf_size: usize,
     io_setup: &IoSetupState,
+    unpack_progress: Option<Arc<UnpackProgress>>,
 ) -> io::Result<ArchiveFormatDecompressor<impl BufRead + use<>>> {
     let buf_reader = buffered_reader::large_file_buf_reader(archive_path, buf_size, io_setup)?;
-    ArchiveFormatDecompressor::new(archive_format, buf_reader)
+    ArchiveFormatDecompressor::new(
+        archive_format,
+        ProgressReader {
+            inner: buf_reader,
+            unpack_progress,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::ZstdConfig, assert_matches::assert_matches, solana_hash::Hash,
+        zstd::stream::write::Encoder as ZstdEncoder,
+    };
+
+    fn write_archive(archive_path: &Path, entries: &[(&str, &[u8])]) {
+        let encoder = ZstdEncoder::new(fs::File::create(archive_path).unwrap(), 0).unwrap();
+        let mut archive = tar::Builder::new(encoder);
+        for (path, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            archive.append_data(&mut header, path, *data).unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_verify_snapshot_archive() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("snapshot.tar.zst");
+        let archive_format = ArchiveFormat::TarZstd {
+            config: ZstdConfig::default(),
+        };
+        let hash = SnapshotHash(Hash::new_from_array([1; 32]));
+        // Takes the bank snapshot file for the snapshot hash, as the bank
+        // snapshots in these archives are not serialized banks
+        let read_snapshot_hash = |bank_snapshot: &mut dyn Read| {
+            let mut data = Vec::new();
+            bank_snapshot.read_to_end(&mut data)?;
+            Ok(SnapshotHash(Hash::new_from_array(
+                data.try_into().unwrap_or_default(),
+            )))
+        };
+        let verify = |entries: &[(&str, &[u8])]| {
+            write_archive(&archive_path, entries);
+            verify_snapshot_archive(&archive_path, archive_format, 42, hash, read_snapshot_hash)
+        };
+
+        let bank = hash.0.as_ref();
+
+        assert_matches!(
+            verify(&[
+                ("version", b"1.2.0"),
+                ("snapshots/42/42", bank),
+                ("snapshots/status_cache", b"status cache"),
+                ("accounts/42.0", b"accounts"),
+            ]),
+            Ok(())
+        );
+        assert_matches!(
+            verify(&[("snapshots/42/42", bank)]),
+            Err(VerifySnapshotArchiveError::MissingVersionFile)
+        );
+        assert_matches!(
+            verify(&[("version", b"0.1.0"), ("snapshots/42/42", bank)]),
+            Err(VerifySnapshotArchiveError::InvalidVersion(version)) if version == "0.1.0"
+        );
+        assert_matches!(
+            verify(&[
+                ("version", b"1.2.0"),
+                ("snapshots/41/41", bank),
+                ("accounts/42.0", b"accounts"),
+                ("snapshots/42/42", bank),
+            ]),
+            Err(VerifySnapshotArchiveError::MissingBankSnapshot(42))
+        );
+        assert_matches!(
+            verify(&[("version", b"1.2.0"), ("snapshots/42/42", b"other bank")]),
+            Err(VerifySnapshotArchiveError::MismatchedHash { expected, actual })
+                if expected == hash && actual == SnapshotHash(Hash::default())
+        );
+
+        fs::write(&archive_path, b"corrupted").unwrap();
+        assert_matches!(
+            verify_snapshot_archive(&archive_path, archive_format, 42, hash, read_snapshot_hash),
+            Err(VerifySnapshotArchiveError::Io(_))
+        );
+    }
 }
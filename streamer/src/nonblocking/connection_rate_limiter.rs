@@ -1,6 +1,7 @@
 use {
+    crate::quic::StreamerStats,
     solana_net_utils::token_bucket::{KeyedRateLimiter, TokenBucket},
-    std::net::IpAddr,
+    std::{net::IpAddr, sync::atomic::Ordering},
 };
 
 /// Limits the rate of connections per IP address.
@@ -8,24 +9,27 @@ pub struct ConnectionRateLimiter {
     limiter: KeyedRateLimiter<IpAddr>,
 }
 
-/// The threshold of the size of the connection rate limiter map. When
-/// the map size is above this, we will trigger a cleanup of older
-/// entries used by past requests.
-const CONNECTION_RATE_LIMITER_CLEANUP_SIZE_THRESHOLD: usize = 100_000;
-
 impl ConnectionRateLimiter {
     /// Create a new rate limiter per IpAddr. The rate is specified as the count per minute to allow for
     /// less frequent connections. Higher limit also allows higher bursts.
+    /// Registering connections never cleans up the map, `retain_recent` has to
+    /// be called periodically instead. It keeps up to cleanup_threshold of the
+    /// most recently seen IPs.
     /// num_shards controls how many shards are used in the underlying dashmap,
     /// should be set >= number of contending threads.
-    pub fn new(limit_per_minute: u64, max_burst: u64, num_shards: usize) -> Self {
-        Self {
-            limiter: KeyedRateLimiter::new(
-                CONNECTION_RATE_LIMITER_CLEANUP_SIZE_THRESHOLD,
-                TokenBucket::new(limit_per_minute, max_burst, limit_per_minute as f64 / 60.0),
-                num_shards,
-            ),
-        }
+    pub fn new(
+        limit_per_minute: u64,
+        max_burst: u64,
+        cleanup_threshold: usize,
+        num_shards: usize,
+    ) -> Self {
+        let mut limiter = KeyedRateLimiter::new(
+            cleanup_threshold,
+            TokenBucket::new(limit_per_minute, max_burst, limit_per_minute as f64 / 60.0),
+            num_shards,
+        );
+        limiter.set_shrink_interval(0);
+        Self { limiter }
     }
 
     /// Check if the connection from the said `ip` is allowed.
@@ -48,15 +52,40 @@ impl ConnectionRateLimiter {
             false // Request blocked
         }
     }
+
+    /// Cleans up the entries of the least recently seen IPs if more than
+    /// cleanup_threshold IPs are tracked. Scans the whole map, so it is kept
+    /// off the accept path.
+    pub fn retain_recent(&self) {
+        self.limiter.shrink();
+    }
+
+    /// Returns approximate amount of IP addresses tracked.
+    pub fn len_approx(&self) -> usize {
+        self.limiter.len_approx()
+    }
+
+    /// Samples the size and eviction counters of the limiter into `stats`.
+    pub(crate) fn report_stats(&self, stats: &StreamerStats) {
+        stats
+            .connection_rate_limiter_entries
+            .store(self.limiter.len_approx(), Ordering::Relaxed);
+        stats
+            .connection_rate_limiter_evictions
+            .store(self.limiter.num_evicted() as usize, Ordering::Relaxed);
+        stats
+            .connection_rate_limiter_shrink_time_us
+            .store(self.limiter.shrink_time_us() as usize, Ordering::Relaxed);
+    }
 }
 
 #[cfg(test)]
 pub mod test {
-    use {super::*, std::net::Ipv4Addr};
+    use {super::*, crate::quic::DEFAULT_RATE_LIMITER_CLEANUP_THRESHOLD, std::net::Ipv4Addr};
 
     #[tokio::test]
     async fn test_connection_rate_limiter() {
-        let limiter = ConnectionRateLimiter::new(3, 3, 4);
+        let limiter = ConnectionRateLimiter::new(3, 3, DEFAULT_RATE_LIMITER_CLEANUP_THRESHOLD, 4);
         let ip1 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
         assert!(limiter.is_allowed(&ip1));
         assert!(limiter.register_connection(&ip1));
@@ -79,4 +108,81 @@ pub mod test {
         assert!(limiter.register_connection(&ip2));
         assert!(!limiter.is_allowed(&ip2));
     }
+
+    #[test]
+    fn test_connection_rate_limiter_bounded_memory() {
+        const NUM_IPS: u32 = 1_000_000;
+        // Connections registered between two cleanups
+        const CLEANUP_EVERY: u32 = 10_000;
+        let limiter = ConnectionRateLimiter::new(3, 3, DEFAULT_RATE_LIMITER_CLEANUP_THRESHOLD, 4);
+        let stats = StreamerStats::default();
+        for i in 0..NUM_IPS {
+            let ip = IpAddr::V4(Ipv4Addr::from_bits(i));
+            assert!(limiter.register_connection(&ip));
+            if (i + 1) % CLEANUP_EVERY == 0 {
+                // Registering connections never pays for a cleanup
+                limiter.report_stats(&stats);
+                let num_evicted = stats
+                    .connection_rate_limiter_evictions
+                    .load(Ordering::Relaxed);
+                assert_eq!(limiter.len_approx() + num_evicted, i as usize + 1);
+
+                limiter.retain_recent();
+                assert!(limiter.len_approx() <= 3 * DEFAULT_RATE_LIMITER_CLEANUP_THRESHOLD / 2);
+            }
+        }
+
+        limiter.report_stats(&stats);
+        let entries = stats
+            .connection_rate_limiter_entries
+            .load(Ordering::Relaxed);
+        assert!(entries <= 3 * DEFAULT_RATE_LIMITER_CLEANUP_THRESHOLD / 2);
+        assert_eq!(
+            stats
+                .connection_rate_limiter_evictions
+                .load(Ordering::Relaxed),
+            NUM_IPS as usize - entries
+        );
+
+        // The most recently seen IPs keep their state.
+        let ip = IpAddr::V4(Ipv4Addr::from_bits(NUM_IPS - 1));
+        assert!(limiter.register_connection(&ip));
+        assert!(limiter.register_connection(&ip));
+        assert!(!limiter.is_allowed(&ip));
+    }
+
+    #[test]
+    fn test_connection_rate_limiter_cleanup_threshold() {
+        const CLEANUP_THRESHOLD: usize = 100;
+        // A single shard makes the cleanup deterministic
+        let limiter = ConnectionRateLimiter::new(3, 3, CLEANUP_THRESHOLD, 1);
+        let stats = StreamerStats::default();
+        for i in 0..CLEANUP_THRESHOLD as u32 {
+            assert!(limiter.register_connection(&IpAddr::V4(Ipv4Addr::from_bits(i))));
+        }
+        limiter.retain_recent();
+        limiter.report_stats(&stats);
+        assert_eq!(limiter.len_approx(), CLEANUP_THRESHOLD);
+        assert_eq!(
+            stats
+                .connection_rate_limiter_evictions
+                .load(Ordering::Relaxed),
+            0
+        );
+
+        // Growing past the configured threshold cleans up older entries
+        for i in CLEANUP_THRESHOLD as u32..10 * CLEANUP_THRESHOLD as u32 {
+            assert!(limiter.register_connection(&IpAddr::V4(Ipv4Addr::from_bits(i))));
+        }
+        assert_eq!(limiter.len_approx(), 10 * CLEANUP_THRESHOLD);
+        limiter.retain_recent();
+        limiter.report_stats(&stats);
+        assert_eq!(limiter.len_approx(), CLEANUP_THRESHOLD);
+        assert_eq!(
+            stats
+                .connection_rate_limiter_evictions
+                .load(Ordering::Relaxed),
+            9 * CLEANUP_THRESHOLD
+        );
+    }
 }
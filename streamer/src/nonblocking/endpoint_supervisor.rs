@@ -0,0 +1,307 @@
+//! Supervises the endpoints of a QUIC server, giving the endpoints whose
+//! socket stopped working a fresh one.
+//!
+//! The socket under an endpoint may fail for good, e.g. once a firewall reload
+//! starts rejecting its traffic or its virtual network device goes away. The
+//! endpoint then stops serving without the server noticing. An endpoint is
+//! considered dead once its driver is lost, which happens when receiving on
+//! the socket fails, or once it went without incoming connections for
+//! `endpoint_dead_timeout` while sending a probe to itself over the socket
+//! kept failing.
+//!
+//! Dead endpoints are handed a socket bound by the rebinder of the server. An
+//! endpoint whose driver is still running is rebound in place, while one
+//! whose driver is lost is replaced with a new endpoint. The connections open
+//! on a dead endpoint are lost either way. Only endpoints backed by a kernel
+//! socket are supervised.
+
+use {
+    crate::quic::{EndpointRebinder, QuicStreamerConfig, StreamerStats},
+    quinn::{Endpoint, EndpointConfig, ServerConfig, TokioRuntime},
+    std::{
+        io,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+        sync::{Arc, RwLock, atomic::Ordering},
+        time::{Duration, Instant},
+    },
+};
+
+/// How often the endpoints are checked
+const SUPERVISION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Consecutive failed rebinds after which an endpoint is reported degraded
+const MAX_FAILED_REBINDS: usize = 5;
+
+/// The endpoints of a server along with the server config they serve, shared
+/// with the key updater of the server so that the endpoints replaced after
+/// their socket died serve the current identity
+pub(crate) struct ServerEndpoints {
+    server_config: ServerConfig,
+    endpoints: Vec<Endpoint>,
+}
+
+impl ServerEndpoints {
+    pub(crate) fn new(server_config: ServerConfig, endpoints: Vec<Endpoint>) -> Self {
+        Self {
+            server_config,
+            endpoints,
+        }
+    }
+
+    pub(crate) fn endpoints(&self) -> Vec<Endpoint> {
+        self.endpoints.clone()
+    }
+
+    pub(crate) fn set_server_config(&mut self, server_config: ServerConfig) {
+        for endpoint in &self.endpoints {
+            endpoint.set_server_config(Some(server_config.clone()));
+        }
+        self.server_config = server_config;
+    }
+}
+
+struct EndpointHealth {
+    /// Clone of the kernel socket of the endpoint to probe it with, None if
+    /// the endpoint is not supervised
+    probe_socket: Option<UdpSocket>,
+    /// Address the socket of the endpoint is bound to, and rebound to
+    local_addr: Option<SocketAddr>,
+    last_accept: Instant,
+    /// Since when probing the socket fails, if it does
+    failing_since: Option<Instant>,
+    driver_lost: bool,
+    num_failed_rebinds: usize,
+}
+
+impl EndpointHealth {
+    fn new(probe_socket: Option<UdpSocket>) -> Self {
+        let local_addr = probe_socket
+            .as_ref()
+            .and_then(|socket| socket.local_addr().ok());
+        Self {
+            probe_socket,
+            local_addr,
+            last_accept: Instant::now(),
+            failing_since: None,
+            driver_lost: false,
+            num_failed_rebinds: 0,
+        }
+    }
+
+    /// Sends an empty datagram to the socket from itself, which the endpoint
+    /// drops on receipt
+    fn probe(&self) -> io::Result<()> {
+        let (Some(socket), Some(local_addr)) = (&self.probe_socket, self.local_addr) else {
+            return Ok(());
+        };
+        let ip = match local_addr.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+        socket
+            .send_to(&[], SocketAddr::new(ip, local_addr.port()))
+            .map(|_| ())
+    }
+
+    fn is_dead(&self, dead_timeout: Duration) -> bool {
+        self.driver_lost
+            || (self.last_accept.elapsed() >= dead_timeout
+                && self
+                    .failing_since
+                    .is_some_and(|failing_since| failing_since.elapsed() >= dead_timeout))
+    }
+}
+
+pub(crate) struct EndpointSupervisor {
+    server_endpoints: Arc<RwLock<ServerEndpoints>>,
+    health: Vec<EndpointHealth>,
+    rebinder: Option<Arc<EndpointRebinder>>,
+    dead_timeout: Duration,
+    last_check: Instant,
+    stats: Arc<StreamerStats>,
+}
+
+impl EndpointSupervisor {
+    /// `probe_sockets` holds a clone of the kernel socket of each endpoint,
+    /// or None for the endpoints not to supervise
+    pub(crate) fn new(
+        server_endpoints: Arc<RwLock<ServerEndpoints>>,
+        probe_sockets: Vec<Option<UdpSocket>>,
+        quic_server_params: &QuicStreamerConfig,
+        stats: Arc<StreamerStats>,
+    ) -> Self {
+        Self {
+            server_endpoints,
+            health: probe_sockets.into_iter().map(EndpointHealth::new).collect(),
+            rebinder: quic_server_params.endpoint_rebinder.clone(),
+            dead_timeout: quic_server_params.endpoint_dead_timeout,
+            last_check: Instant::now(),
+            stats,
+        }
+    }
+
+    pub(crate) fn endpoints(&self) -> Vec<Endpoint> {
+        self.server_endpoints.read().unwrap().endpoints()
+    }
+
+    pub(crate) fn endpoint(&self, i: usize) -> Endpoint {
+        self.server_endpoints.read().unwrap().endpoints[i].clone()
+    }
+
+    pub(crate) fn on_accept(&mut self, i: usize) {
+        let health = &mut self.health[i];
+        health.last_accept = Instant::now();
+        health.failing_since = None;
+    }
+
+    /// The endpoint no longer accepts connections, as its driver is lost or
+    /// it was closed
+    pub(crate) fn on_accept_ended(&mut self, i: usize) {
+        self.health[i].driver_lost = true;
+    }
+
+    /// Checks the endpoints if due, giving the dead ones a fresh socket, and
+    /// returns the endpoints replaced with a new one
+    pub(crate) fn check(&mut self) -> Vec<usize> {
+        if self.last_check.elapsed() < SUPERVISION_INTERVAL {
+            return vec![];
+        }
+        self.last_check = Instant::now();
+        let mut replaced = vec![];
+        for i in 0..self.health.len() {
+            let health = &mut self.health[i];
+            if health.probe_socket.is_none() {
+                continue;
+            }
+            if health.last_accept.elapsed() >= SUPERVISION_INTERVAL {
+                match health.probe() {
+                    Ok(()) => health.failing_since = None,
+                    Err(err) => {
+                        debug!(
+                            "Probing QUIC endpoint {:?} failed: {err}",
+                            health.local_addr
+                        );
+                        health.failing_since.get_or_insert_with(Instant::now);
+                    }
+                }
+            }
+            if !health.is_dead(self.dead_timeout) {
+                continue;
+            }
+            match self.rebind(i) {
+                Ok(was_replaced) => {
+                    if was_replaced {
+                        replaced.push(i);
+                    }
+                }
+                Err(err) => self.on_rebind_failed(i, err),
+            }
+        }
+        replaced
+    }
+
+    /// Hands the endpoint a fresh socket, returning true if the endpoint was
+    /// replaced with a new one
+    fn rebind(&mut self, i: usize) -> io::Result<bool> {
+        let health = &mut self.health[i];
+        let local_addr = health
+            .local_addr
+            .ok_or_else(|| io::Error::other("unknown endpoint address"))?;
+        let rebinder = self
+            .rebinder
+            .as_ref()
+            .ok_or_else(|| io::Error::other("no endpoint rebinder"))?;
+        let socket = rebinder(local_addr)?;
+        let probe_socket = socket.try_clone()?;
+
+        let mut server_endpoints = self.server_endpoints.write().unwrap();
+        let dead_endpoint = server_endpoints.endpoints[i].clone();
+        let lost_connections = dead_endpoint.open_connections();
+        let was_replaced = health.driver_lost;
+        if was_replaced {
+            let endpoint = Endpoint::new(
+                EndpointConfig::default(),
+                Some(server_endpoints.server_config.clone()),
+                socket,
+                Arc::new(TokioRuntime),
+            )?;
+            server_endpoints.endpoints[i] = endpoint;
+        } else {
+            dead_endpoint.rebind(socket)?;
+        }
+        drop(server_endpoints);
+
+        if health.num_failed_rebinds >= MAX_FAILED_REBINDS {
+            self.stats
+                .quic_endpoints_degraded
+                .fetch_sub(1, Ordering::Relaxed);
+        }
+        info!(
+            "Rebound dead QUIC endpoint {local_addr} to {:?}, losing {lost_connections} \
+             connections",
+            probe_socket.local_addr()
+        );
+        *health = EndpointHealth::new(Some(probe_socket));
+        self.stats
+            .quic_endpoint_rebinds
+            .fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .quic_endpoint_lost_connections
+            .fetch_add(lost_connections, Ordering::Relaxed);
+        Ok(was_replaced)
+    }
+
+    fn on_rebind_failed(&mut self, i: usize, err: io::Error) {
+        let health = &mut self.health[i];
+        health.num_failed_rebinds = health.num_failed_rebinds.saturating_add(1);
+        self.stats
+            .quic_endpoint_rebind_failures
+            .fetch_add(1, Ordering::Relaxed);
+        if health.num_failed_rebinds < MAX_FAILED_REBINDS {
+            warn!(
+                "Failed to rebind dead QUIC endpoint {:?}: {err}",
+                health.local_addr
+            );
+        } else if health.num_failed_rebinds == MAX_FAILED_REBINDS {
+            error!(
+                "QUIC endpoint {:?} is dead and failed to rebind {MAX_FAILED_REBINDS} times, the \
+                 server is degraded: {err}",
+                health.local_addr
+            );
+            self.stats
+                .quic_endpoints_degraded
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_net_utils::sockets::bind_to_localhost_unique};
+
+    #[test]
+    fn test_endpoint_health_probe() {
+        let socket = bind_to_localhost_unique().unwrap();
+        let mut health = EndpointHealth::new(Some(socket.try_clone().unwrap()));
+        assert!(health.probe().is_ok());
+        let mut buf = [0u8; 1];
+        assert_eq!(socket.recv(&mut buf).unwrap(), 0);
+
+        let dead_timeout = Duration::from_millis(10);
+        assert!(!health.is_dead(dead_timeout));
+        health.failing_since = Some(Instant::now());
+        // Failing probes only kill an endpoint that has not accepted for as
+        // long
+        health.last_accept = Instant::now();
+        std::thread::sleep(dead_timeout);
+        assert!(health.is_dead(dead_timeout));
+        health.last_accept = Instant::now();
+        assert!(!health.is_dead(dead_timeout));
+        health.driver_lost = true;
+        assert!(health.is_dead(dead_timeout));
+
+        // Endpoints not backed by a kernel socket are never probed
+        assert!(EndpointHealth::new(None).probe().is_ok());
+    }
+}
@@ -0,0 +1,168 @@
+//! A QUIC client for the self-tests of the QUIC servers of this process.
+//!
+//! The client presents the certificate of the keypair it is given, so that the
+//! server admits it as a peer with that identity and stake, and sends each
+//! payload over its own uni stream the way the TPU clients do. Closing the
+//! client waits for the server to acknowledge the close, so that the
+//! connection slot is freed once `close()` returns.
+
+use {
+    crate::{nonblocking::quic::ALPN_TPU_PROTOCOL_ID, quic::QUIC_MAX_TIMEOUT},
+    futures::future::join_all,
+    quinn::{
+        ClientConfig, ConnectError, Connection, ConnectionError, Endpoint, IdleTimeout,
+        TransportConfig, crypto::rustls::QuicClientConfig,
+    },
+    solana_keypair::Keypair,
+    solana_tls_utils::{new_dummy_x509_certificate, tls_client_config_builder},
+    std::{
+        io,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+        sync::Arc,
+    },
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum LoopbackClientError {
+    #[error("failed to bind the client endpoint: {0}")]
+    Bind(#[from] io::Error),
+    #[error("failed to configure the client: {0}")]
+    Config(String),
+    #[error("failed to connect: {0}")]
+    Connect(#[from] ConnectError),
+    #[error("connection failed: {0}")]
+    Connection(#[from] ConnectionError),
+}
+
+pub struct LoopbackClient {
+    endpoint: Endpoint,
+    connection: Connection,
+}
+
+impl LoopbackClient {
+    /// Connects to the server at `server_addr` with the identity of `keypair`
+    pub async fn connect(
+        server_addr: SocketAddr,
+        keypair: &Keypair,
+    ) -> Result<Self, LoopbackClientError> {
+        let bind_ip = if server_addr.is_ipv4() {
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        } else {
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+        };
+        let mut endpoint = Endpoint::client(SocketAddr::new(bind_ip, 0))?;
+        endpoint.set_default_client_config(client_config(keypair)?);
+        let connection = match endpoint.connect(server_addr, "connect") {
+            Ok(connecting) => connecting.await,
+            Err(err) => {
+                endpoint.close(0u32.into(), b"");
+                return Err(err.into());
+            }
+        };
+        match connection {
+            Ok(connection) => Ok(Self {
+                endpoint,
+                connection,
+            }),
+            Err(err) => {
+                endpoint.close(0u32.into(), b"");
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Sends each payload over its own uni stream, all at once, and returns
+    /// the number of payloads sent in full.
+    ///
+    /// Streams the server has no credit for wait for it, so callers wanting a
+    /// bounded send should put a timeout on the returned future.
+    pub async fn send_all(&self, payloads: &[Vec<u8>]) -> usize {
+        let sends = payloads.iter().map(|payload| async move {
+            let mut stream = self.connection.open_uni().await.ok()?;
+            stream.write_all(payload).await.ok()?;
+            stream.finish().ok()
+        });
+        join_all(sends).await.into_iter().flatten().count()
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.endpoint.local_addr()
+    }
+
+    /// Closes the connection and waits for the server to acknowledge it
+    pub async fn close(self) {
+        self.connection.close(0u32.into(), b"done");
+        self.endpoint.wait_idle().await;
+    }
+}
+
+fn client_config(keypair: &Keypair) -> Result<ClientConfig, LoopbackClientError> {
+    let (cert, key) = new_dummy_x509_certificate(keypair);
+    let mut crypto = tls_client_config_builder()
+        .with_client_auth_cert(vec![cert], key)
+        .map_err(|err| LoopbackClientError::Config(err.to_string()))?;
+    crypto.enable_early_data = true;
+    crypto.alpn_protocols = vec![ALPN_TPU_PROTOCOL_ID.to_vec()];
+    let crypto = QuicClientConfig::try_from(crypto)
+        .map_err(|err| LoopbackClientError::Config(err.to_string()))?;
+
+    let mut config = ClientConfig::new(Arc::new(crypto));
+    let mut transport_config = TransportConfig::default();
+    let timeout = IdleTimeout::try_from(QUIC_MAX_TIMEOUT).unwrap();
+    transport_config.max_idle_timeout(Some(timeout));
+    transport_config.send_fairness(false);
+    config.transport_config(Arc::new(transport_config));
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            nonblocking::{swqos::SwQosConfig, testing_utilities::setup_quic_server},
+            quic::QuicStreamerConfig,
+        },
+        std::{
+            sync::atomic::Ordering,
+            time::{Duration, Instant},
+        },
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_loopback_client() {
+        let server = setup_quic_server(
+            None,
+            QuicStreamerConfig::default_for_tests(),
+            SwQosConfig::default(),
+        );
+        let client = LoopbackClient::connect(server.server_address, &Keypair::new())
+            .await
+            .unwrap();
+        let payloads: Vec<_> = (0..10u8).map(|i| vec![i; 64]).collect();
+        assert_eq!(client.send_all(&payloads).await, payloads.len());
+
+        let mut num_received = 0;
+        while num_received < payloads.len() {
+            let batch = server
+                .receiver
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap();
+            num_received += batch.len();
+        }
+        assert_eq!(num_received, payloads.len());
+        assert_eq!(server.stats.open_connections.load(Ordering::Relaxed), 1);
+
+        // The server drops the connection once the close is acknowledged
+        client.close().await;
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.stats.open_connections.load(Ordering::Relaxed) != 0 {
+            assert!(Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        server.cancel.cancel();
+        server.join_handle.await.unwrap();
+    }
+}
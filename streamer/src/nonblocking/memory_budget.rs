@@ -0,0 +1,189 @@
+//! A memory budget shared by QUIC servers, bounding the memory used by the
+//! packets they ingest.
+//!
+//! Usage is approximate. Each server accounts for the packets queued in its
+//! packet channel, the bytes of streams being reassembled and a fixed cost per
+//! connection. Servers consult the budget before accepting new connections
+//! and streams, shedding those of unstaked peers first.
+
+use {
+    crate::nonblocking::quic::ConnectionPeerType,
+    solana_packet::PACKET_DATA_SIZE,
+    std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// Approximate memory held by an open connection, e.g. by quinn's
+/// connection state and receive buffers
+pub(crate) const APPROX_CONNECTION_BYTES: usize = 16 * 1024;
+// Unstaked peers are shed once usage reaches this percentage of the budget
+const UNSTAKED_SHED_PERCENT: usize = 75;
+
+#[derive(Debug)]
+pub struct TpuIngestMemoryBudget {
+    max_bytes: usize,
+    used_bytes: AtomicUsize,
+}
+
+impl TpuIngestMemoryBudget {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            used_bytes: AtomicUsize::default(),
+        }
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Returns the approximate memory used by all the servers sharing the
+    /// budget
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if a new connection or stream of `peer_type` fits into
+    /// the budget
+    pub(crate) fn admits(&self, peer_type: ConnectionPeerType) -> bool {
+        let used_bytes = self.used_bytes();
+        if peer_type.is_staked() {
+            used_bytes < self.max_bytes
+        } else {
+            used_bytes < self.max_bytes / 100 * UNSTAKED_SHED_PERCENT
+        }
+    }
+}
+
+/// The share of a `TpuIngestMemoryBudget` used by a single server
+#[derive(Debug)]
+pub(crate) struct MemoryBudgetAccount {
+    budget: Arc<TpuIngestMemoryBudget>,
+    used_bytes: AtomicUsize,
+    channel_bytes: AtomicUsize,
+}
+
+impl MemoryBudgetAccount {
+    pub(crate) fn new(budget: Arc<TpuIngestMemoryBudget>) -> Self {
+        Self {
+            budget,
+            used_bytes: AtomicUsize::default(),
+            channel_bytes: AtomicUsize::default(),
+        }
+    }
+
+    pub(crate) fn budget(&self) -> &TpuIngestMemoryBudget {
+        &self.budget
+    }
+
+    pub(crate) fn used_bytes(&self) -> usize {
+        self.used_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Accounts for `num_packets` packets queued in the server's packet
+    /// channel, replacing the previous count
+    pub(crate) fn set_channel_len(&self, num_packets: usize) {
+        let bytes = num_packets * PACKET_DATA_SIZE;
+        // bytes are added before they can be swapped out and subtracted by a
+        // concurrent update, so that usage never underflows
+        self.add(bytes);
+        let prev_bytes = self.channel_bytes.swap(bytes, Ordering::Relaxed);
+        self.sub(prev_bytes);
+    }
+
+    /// Charges `bytes` to the budget until the returned charge is dropped
+    pub(crate) fn charge(self: &Arc<Self>, bytes: usize) -> MemoryCharge {
+        self.add(bytes);
+        MemoryCharge {
+            account: self.clone(),
+            bytes,
+        }
+    }
+
+    fn add(&self, bytes: usize) {
+        self.used_bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.budget.used_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn sub(&self, bytes: usize) {
+        self.used_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        self.budget.used_bytes.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Memory charged to a `MemoryBudgetAccount`, released on drop
+#[derive(Debug)]
+pub(crate) struct MemoryCharge {
+    account: Arc<MemoryBudgetAccount>,
+    bytes: usize,
+}
+
+impl MemoryCharge {
+    pub(crate) fn set(&mut self, bytes: usize) {
+        if bytes > self.bytes {
+            self.account.add(bytes - self.bytes);
+        } else {
+            self.account.sub(self.bytes - bytes);
+        }
+        self.bytes = bytes;
+    }
+}
+
+impl Drop for MemoryCharge {
+    fn drop(&mut self) {
+        self.account.sub(self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_budget_accounting() {
+        let budget = Arc::new(TpuIngestMemoryBudget::new(100 * PACKET_DATA_SIZE));
+        let account1 = Arc::new(MemoryBudgetAccount::new(budget.clone()));
+        let account2 = Arc::new(MemoryBudgetAccount::new(budget.clone()));
+
+        account1.set_channel_len(10);
+        let mut charge = account2.charge(1_000);
+        assert_eq!(account1.used_bytes(), 10 * PACKET_DATA_SIZE);
+        assert_eq!(account2.used_bytes(), 1_000);
+        assert_eq!(budget.used_bytes(), 10 * PACKET_DATA_SIZE + 1_000);
+
+        charge.set(3_000);
+        account1.set_channel_len(2);
+        assert_eq!(budget.used_bytes(), 2 * PACKET_DATA_SIZE + 3_000);
+        charge.set(500);
+        assert_eq!(account2.used_bytes(), 500);
+        drop(charge);
+        account1.set_channel_len(0);
+        assert_eq!(account1.used_bytes(), 0);
+        assert_eq!(account2.used_bytes(), 0);
+        assert_eq!(budget.used_bytes(), 0);
+    }
+
+    #[test]
+    fn test_memory_budget_sheds_unstaked_first() {
+        let budget = Arc::new(TpuIngestMemoryBudget::new(100 * PACKET_DATA_SIZE));
+        let account = Arc::new(MemoryBudgetAccount::new(budget.clone()));
+        let staked = ConnectionPeerType::Staked(1);
+        let unstaked = ConnectionPeerType::Unstaked;
+        assert!(budget.admits(staked));
+        assert!(budget.admits(unstaked));
+
+        account.set_channel_len(UNSTAKED_SHED_PERCENT);
+        assert!(budget.admits(staked));
+        assert!(!budget.admits(unstaked));
+
+        account.set_channel_len(100);
+        assert!(!budget.admits(staked));
+        assert!(!budget.admits(unstaked));
+
+        account.set_channel_len(0);
+        assert!(budget.admits(staked));
+        assert!(budget.admits(unstaked));
+    }
+}
@@ -1,4 +1,7 @@
 pub mod connection_rate_limiter;
+pub(crate) mod endpoint_supervisor;
+pub mod loopback_client;
+pub mod memory_budget;
 pub mod qos;
 pub mod quic;
 pub mod simple_qos;
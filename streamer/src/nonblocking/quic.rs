@@ -2,9 +2,14 @@ use {
     crate::{
         nonblocking::{
             connection_rate_limiter::ConnectionRateLimiter,
+            endpoint_supervisor::{EndpointSupervisor, ServerEndpoints},
+            memory_budget::{APPROX_CONNECTION_BYTES, MemoryBudgetAccount},
             qos::{ConnectionContext, OpaqueStreamerCounter, QosController},
         },
-        quic::{QuicServerError, QuicStreamerConfig, StreamerStats, configure_server},
+        quic::{
+            EndpointKeyUpdater, QuicServerEndpoints, QuicServerError, QuicStreamerConfig,
+            StreamerStats, configure_server,
+        },
         quic_socket::{QuicSocket, QuicXdpSocketParts, QuicXdpTxSocket},
         streamer::StakedNodes,
     },
@@ -13,26 +18,27 @@ use {
     futures::{Future, StreamExt as _, stream::FuturesUnordered},
     indexmap::map::{Entry, IndexMap},
     quinn::{
-        Accept, AsyncUdpSocket, Connecting, Connection, Endpoint, EndpointConfig, TokioRuntime,
+        AsyncUdpSocket, Connecting, Connection, Endpoint, EndpointConfig, ServerConfig,
+        TokioRuntime,
     },
-    rand::{Rng, rng},
+    rand::{Rng, SeedableRng, rng, rngs::SmallRng},
     smallvec::SmallVec,
     solana_keypair::Keypair,
     solana_net_utils::token_bucket::TokenBucket,
     solana_packet::Meta,
     solana_perf::packet::{BytesPacket, PacketBatch},
     solana_pubkey::Pubkey,
+    solana_time_utils as timing,
     solana_tls_utils::get_remote_pubkey,
     std::{
-        array, fmt,
+        array, fmt, io,
         iter::repeat_with,
-        net::{IpAddr, SocketAddr},
+        net::{IpAddr, SocketAddr, UdpSocket},
         pin::Pin,
         sync::{
-            Arc, RwLock,
+            Arc, Mutex, RwLock,
             atomic::{AtomicU64, Ordering},
         },
-        task::Poll,
         time::{Duration, Instant},
     },
     tokio::{
@@ -45,8 +51,9 @@ use {
         // (i.e. lock order is always async Mutex -> RwLock). Also, be careful not to
         // introduce any other awaits while holding the RwLock.
         select,
+        sync::{OwnedSemaphorePermit, Semaphore},
         task::JoinHandle,
-        time::timeout,
+        time::{MissedTickBehavior, interval, sleep, timeout},
     },
     tokio_util::{sync::CancellationToken, task::TaskTracker},
 };
@@ -67,6 +74,11 @@ const CONNECTION_CLOSE_REASON_TOO_MANY: &[u8] = b"too_many";
 const CONNECTION_CLOSE_CODE_INVALID_STREAM: u32 = 5;
 const CONNECTION_CLOSE_REASON_INVALID_STREAM: &[u8] = b"invalid_stream";
 
+const STREAM_STOP_CODE_MEMORY_BUDGET: u32 = 6;
+
+const CONNECTION_CLOSE_CODE_NOT_READY: u32 = 7;
+const CONNECTION_CLOSE_REASON_NOT_READY: &[u8] = b"not_ready";
+
 /// Total new connection counts per second. Heuristically taken from
 /// the default staked and unstaked connection limits. Might be adjusted
 /// later.
@@ -75,6 +87,11 @@ const TOTAL_CONNECTIONS_PER_SECOND: f64 = 2500.0;
 /// Max burst of connections above sustained rate to pass through
 const MAX_CONNECTION_BURST: u64 = 1000;
 
+/// How often the per-IP connection rate limiter cleans up the entries of
+/// the least recently seen IPs. Between two cleanups the limiter only grows
+/// by the connections accepted meanwhile.
+const RATE_LIMITER_CLEANUP_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Timeout for connection handshake. Timer starts once we get Initial from the
 /// peer, and is canceled when we get a Handshake packet from them.
 const QUIC_CONNECTION_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
@@ -130,13 +147,14 @@ impl ConnectionPeerType {
 }
 
 pub struct SpawnNonBlockingServerResult {
-    pub endpoints: Vec<Endpoint>,
+    pub endpoints: QuicServerEndpoints,
     pub stats: Arc<StreamerStats>,
     pub thread: JoinHandle<()>,
     pub max_concurrent_connections: usize,
 }
 
-/// Spawn a streamer instance in the current tokio runtime.
+/// Spawn a streamer instance in the current tokio runtime, along with the
+/// key updater of its endpoints.
 pub(crate) fn spawn_server<Q, C>(
     name: &'static str,
     stats: Arc<StreamerStats>,
@@ -146,7 +164,7 @@ pub(crate) fn spawn_server<Q, C>(
     quic_server_params: QuicStreamerConfig,
     qos: Q,
     cancel: CancellationToken,
-) -> Result<SpawnNonBlockingServerResult, QuicServerError>
+) -> Result<(SpawnNonBlockingServerResult, EndpointKeyUpdater), QuicServerError>
 where
     Q: QosController<C> + Send + Sync + 'static,
     C: ConnectionContext + Send + Sync + 'static,
@@ -154,42 +172,171 @@ where
     let sockets: Vec<_> = sockets.into_iter().collect();
     info!("Start {name} quic server on {sockets:?}");
     let (config, _) = configure_server(keypair, &quic_server_params)?;
-
     let endpoints = sockets
         .into_iter()
-        .map(|sock| match sock {
-            QuicSocket::Kernel(socket) => Endpoint::new(
+        .map(|socket| create_endpoint(socket, &config))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(QuicServerError::EndpointFailed)?;
+
+    Ok(spawn_server_on_endpoints(
+        name,
+        stats,
+        config,
+        endpoints,
+        packet_sender,
+        quic_server_params,
+        qos,
+        cancel,
+    ))
+}
+
+/// Same as `spawn_server`, but when creating the endpoint of a kernel socket
+/// fails, binds a fresh socket on its address with the `endpoint_rebinder`
+/// and retries, e.g. as the address is still held by the process being
+/// restarted.
+pub(crate) async fn spawn_server_with_bind_retries<Q, C>(
+    name: &'static str,
+    stats: Arc<StreamerStats>,
+    sockets: impl IntoIterator<Item = QuicSocket>,
+    keypair: &Keypair,
+    packet_sender: Sender<PacketBatch>,
+    quic_server_params: QuicStreamerConfig,
+    qos: Q,
+    cancel: CancellationToken,
+) -> Result<(SpawnNonBlockingServerResult, EndpointKeyUpdater), QuicServerError>
+where
+    Q: QosController<C> + Send + Sync + 'static,
+    C: ConnectionContext + Send + Sync + 'static,
+{
+    let sockets: Vec<_> = sockets.into_iter().collect();
+    info!("Start {name} quic server on {sockets:?}");
+    let (config, _) = configure_server(keypair, &quic_server_params)?;
+    let mut endpoints = Vec::with_capacity(sockets.len());
+    for socket in sockets {
+        let endpoint = match socket {
+            QuicSocket::Kernel(socket) => {
+                bind_with_retries(socket, &quic_server_params, &stats, |socket| {
+                    create_endpoint(QuicSocket::Kernel(socket), &config)
+                })
+                .await
+            }
+            socket => create_endpoint(socket, &config),
+        };
+        endpoints.push(endpoint.map_err(QuicServerError::EndpointFailed)?);
+    }
+
+    Ok(spawn_server_on_endpoints(
+        name,
+        stats,
+        config,
+        endpoints,
+        packet_sender,
+        quic_server_params,
+        qos,
+        cancel,
+    ))
+}
+
+/// Creates the endpoint of `socket`, along with a clone of the socket to
+/// probe the endpoint with if it is a kernel socket.
+fn create_endpoint(
+    socket: QuicSocket,
+    config: &ServerConfig,
+) -> io::Result<(Endpoint, Option<UdpSocket>)> {
+    match socket {
+        QuicSocket::Kernel(socket) => {
+            let probe_socket = socket.try_clone().ok();
+            let endpoint = Endpoint::new(
                 EndpointConfig::default(),
                 Some(config.clone()),
                 socket,
                 Arc::new(TokioRuntime),
-            )
-            .map_err(QuicServerError::EndpointFailed),
-            QuicSocket::Xdp(QuicXdpSocketParts {
+            )?;
+            Ok((endpoint, probe_socket))
+        }
+        QuicSocket::Xdp(QuicXdpSocketParts {
+            socket,
+            fallback_src_ip,
+            xdp_sender,
+        }) => {
+            let socket = Arc::new(QuicXdpTxSocket::new(socket, fallback_src_ip, xdp_sender)?)
+                as Arc<dyn AsyncUdpSocket>;
+            let endpoint = Endpoint::new_with_abstract_socket(
+                EndpointConfig::default(),
+                Some(config.clone()),
                 socket,
-                fallback_src_ip,
-                xdp_sender,
-            }) => {
-                let socket = Arc::new(
-                    QuicXdpTxSocket::new(socket, fallback_src_ip, xdp_sender)
-                        .map_err(QuicServerError::EndpointFailed)?,
-                ) as Arc<dyn AsyncUdpSocket>;
-                Endpoint::new_with_abstract_socket(
-                    EndpointConfig::default(),
-                    Some(config.clone()),
-                    socket,
-                    Arc::new(TokioRuntime),
-                )
-                .map_err(QuicServerError::EndpointFailed)
-            }
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+                Arc::new(TokioRuntime),
+            )?;
+            Ok((endpoint, None))
+        }
+    }
+}
+
+/// Runs `create_endpoint` on `socket`. After each failure, binds a fresh
+/// socket on the same address with the `endpoint_rebinder` and runs
+/// `create_endpoint` on it, up to `endpoint_bind_retries` times with a delay
+/// doubling after each failure. A failure to bind counts as a failed attempt.
+async fn bind_with_retries<T>(
+    socket: UdpSocket,
+    quic_server_params: &QuicStreamerConfig,
+    stats: &StreamerStats,
+    mut create_endpoint: impl FnMut(UdpSocket) -> io::Result<T>,
+) -> io::Result<T> {
+    let address = socket.local_addr()?;
+    let mut result = create_endpoint(socket);
+    let Some(rebinder) = quic_server_params.endpoint_rebinder.as_ref() else {
+        return result;
+    };
+    let mut retry_delay = quic_server_params.endpoint_bind_retry_delay;
+    for _ in 0..quic_server_params.endpoint_bind_retries {
+        let err = match result {
+            Ok(endpoint) => return Ok(endpoint),
+            Err(err) => err,
+        };
+        warn!("Failed to create QUIC endpoint on {address}, rebinding in {retry_delay:?}: {err}");
+        stats
+            .quic_endpoint_bind_retries
+            .fetch_add(1, Ordering::Relaxed);
+        sleep(retry_delay).await;
+        retry_delay = retry_delay.saturating_mul(2);
+        result = rebinder(address).and_then(&mut create_endpoint);
+    }
+    result
+}
+
+fn spawn_server_on_endpoints<Q, C>(
+    name: &'static str,
+    stats: Arc<StreamerStats>,
+    config: ServerConfig,
+    endpoints: Vec<(Endpoint, Option<UdpSocket>)>,
+    packet_sender: Sender<PacketBatch>,
+    quic_server_params: QuicStreamerConfig,
+    qos: Q,
+    cancel: CancellationToken,
+) -> (SpawnNonBlockingServerResult, EndpointKeyUpdater)
+where
+    Q: QosController<C> + Send + Sync + 'static,
+    C: ConnectionContext + Send + Sync + 'static,
+{
+    // Clones of the kernel sockets, to probe them with
+    let (endpoints, probe_sockets): (Vec<_>, Vec<_>) = endpoints.into_iter().unzip();
+    let server_endpoints = Arc::new(RwLock::new(ServerEndpoints::new(config, endpoints)));
+    let endpoint_supervisor = EndpointSupervisor::new(
+        server_endpoints.clone(),
+        probe_sockets,
+        &quic_server_params,
+        stats.clone(),
+    );
+    let key_updater = EndpointKeyUpdater {
+        endpoints: server_endpoints.clone(),
+        quic_server_params: quic_server_params.clone(),
+    };
 
     let max_concurrent_connections = qos.max_concurrent_connections();
     let handle = tokio::spawn({
         run_server(
             name,
-            endpoints.clone(),
+            endpoint_supervisor,
             packet_sender,
             stats.clone(),
             quic_server_params,
@@ -198,12 +345,15 @@ where
         )
     });
 
-    Ok(SpawnNonBlockingServerResult {
-        endpoints,
-        stats,
-        thread: handle,
-        max_concurrent_connections,
-    })
+    (
+        SpawnNonBlockingServerResult {
+            endpoints: QuicServerEndpoints(server_endpoints),
+            stats,
+            thread: handle,
+            max_concurrent_connections,
+        },
+        key_updater,
+    )
 }
 
 /// struct ease tracking connections of all stages, so that we do not have to
@@ -212,6 +362,45 @@ where
 /// reduced when it is dropped.
 pub struct ClientConnectionTracker {
     pub(crate) stats: Arc<StreamerStats>,
+    /// Traffic served over the lifetime of the connection.
+    pub(crate) traffic: Arc<ConnectionTraffic>,
+}
+
+/// Cumulative counters of the traffic served over a single connection.
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionTraffic {
+    streams: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl ConnectionTraffic {
+    fn record_stream(&self, bytes: usize) {
+        self.streams.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// A snapshot of a connection in the connection table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionSummary {
+    pub remote_pubkey: Option<Pubkey>,
+    /// None for entries without a live connection.
+    pub remote_address: Option<SocketAddr>,
+    pub stake: u64,
+    /// Timestamp in milliseconds of the last finished stream.
+    pub last_update: u64,
+    /// Milliseconds since `last_update`.
+    pub idle_ms: u64,
+    /// Time since the connection was added to the connection table.
+    pub age: Duration,
+    /// Number of streams received over the lifetime of the connection.
+    pub lifetime_streams: u64,
+    /// Number of bytes received over the lifetime of the connection.
+    pub lifetime_bytes: u64,
+    /// Number of streams the connection may open per throttling interval
+    /// under the current load. None if the QoS controller does not throttle
+    /// the streams of the connection this way.
+    pub current_stream_cap: Option<u64>,
 }
 
 /// This is required by ConnectionEntry for supporting debug format.
@@ -247,14 +436,17 @@ impl ClientConnectionTracker {
             return Err(());
         }
 
-        Ok(Self { stats })
+        Ok(Self {
+            stats,
+            traffic: Arc::default(),
+        })
     }
 }
 
 #[allow(clippy::too_many_arguments)]
 async fn run_server<Q, C>(
     name: &'static str,
-    endpoints: Vec<Endpoint>,
+    mut endpoint_supervisor: EndpointSupervisor,
     packet_batch_sender: Sender<PacketBatch>,
     stats: Arc<StreamerStats>,
     quic_server_params: QuicStreamerConfig,
@@ -272,6 +464,10 @@ where
         // allow for 10x burst to make sure we can accommodate legitimate
         // bursts from container environments running multiple pods on same IP
         quic_server_params.max_connections_per_ipaddr_per_min * 10,
+        // the limiter needs room for at least one entry per shard
+        quic_server_params
+            .rate_limiter_cleanup_threshold
+            .max(num_shards),
         num_shards,
     ));
     let overall_connection_rate_limiter = Arc::new(TokenBucket::new(
@@ -283,34 +479,47 @@ where
     const WAIT_FOR_CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
     debug!("spawn quic server");
     let mut last_datapoint = Instant::now();
+    let endpoints = endpoint_supervisor.endpoints();
     stats
         .quic_endpoints_count
         .store(endpoints.len(), Ordering::Relaxed);
 
     let mut accepts = endpoints
-        .iter()
+        .into_iter()
         .enumerate()
-        .map(|(i, incoming)| {
-            Box::pin(EndpointAccept {
-                accept: incoming.accept(),
-                endpoint: i,
-            })
-        })
+        .map(|(i, endpoint)| endpoint_accept(endpoint, i))
         .collect::<FuturesUnordered<_>>();
     let mut qos = qos;
     qos.spawn_background_tasks();
     let qos = Arc::new(qos);
+    let handshake_semaphore = Arc::new(Semaphore::new(
+        quic_server_params
+            .max_concurrent_handshakes
+            .unwrap_or(qos.max_concurrent_connections() / 4)
+            .max(1),
+    ));
+    let memory_budget = quic_server_params
+        .memory_budget
+        .clone()
+        .map(|budget| Arc::new(MemoryBudgetAccount::new(budget)));
     let tasks = TaskTracker::new();
+    tasks.spawn(retain_recent_connections(
+        rate_limiter.clone(),
+        cancel.clone(),
+    ));
     loop {
         let timeout_connection = select! {
-            ready = accepts.next() => {
+            // the accepts run out if all the endpoints died
+            ready = accepts.next(), if !accepts.is_empty() => {
                 if let Some((connecting, i)) = ready {
-                    accepts.push(
-                        Box::pin(EndpointAccept {
-                            accept: endpoints[i].accept(),
-                            endpoint: i,
-                        }
-                    ));
+                    if connecting.is_some() {
+                        endpoint_supervisor.on_accept(i);
+                        accepts.push(endpoint_accept(endpoint_supervisor.endpoint(i), i));
+                    } else {
+                        // the endpoint is dead, its replacement is accepted
+                        // from once rebound
+                        endpoint_supervisor.on_accept_ended(i);
+                    }
                     Ok(connecting)
                 } else {
                     // we can't really get here - we never poll an empty FuturesUnordered
@@ -323,7 +532,21 @@ where
             _ = cancel.cancelled() => break,
         };
 
+        for i in endpoint_supervisor.check() {
+            accepts.push(endpoint_accept(endpoint_supervisor.endpoint(i), i));
+        }
+
+        if let Some(memory_budget) = &memory_budget {
+            memory_budget.set_channel_len(packet_batch_sender.len());
+        }
+
         if last_datapoint.elapsed().as_secs() >= 5 {
+            rate_limiter.report_stats(&stats);
+            if let Some(memory_budget) = &memory_budget {
+                stats
+                    .memory_budget_used_bytes
+                    .store(memory_budget.used_bytes(), Ordering::Relaxed);
+            }
             stats.report(name);
             last_datapoint = Instant::now();
         }
@@ -368,6 +591,20 @@ where
                 continue;
             }
 
+            // bound the work spent on connections which have not completed
+            // the handshake yet, without waiting for any to time out
+            let Ok(handshake_permit) = handshake_semaphore.clone().try_acquire_owned() else {
+                stats
+                    .refused_handshake_overload
+                    .fetch_add(1, Ordering::Relaxed);
+                debug!(
+                    "Refusing incoming connection from {}, too many handshakes in progress.",
+                    incoming.remote_address()
+                );
+                incoming.refuse();
+                continue;
+            };
+
             let Ok(client_connection_tracker) =
                 ClientConnectionTracker::new(stats.clone(), qos.max_concurrent_connections())
             else {
@@ -391,6 +628,8 @@ where
                         rate_limiter,
                         overall_connection_rate_limiter,
                         client_connection_tracker,
+                        handshake_permit,
+                        memory_budget.clone(),
                         packet_batch_sender.clone(),
                         stats.clone(),
                         quic_server_params.clone(),
@@ -413,6 +652,22 @@ where
     tasks.wait().await;
 }
 
+// Keeps the per-IP connection rate limiter bounded, so that the accept path
+// never has to scan it.
+async fn retain_recent_connections(
+    rate_limiter: Arc<ConnectionRateLimiter>,
+    cancel: CancellationToken,
+) {
+    let mut cleanup_interval = interval(RATE_LIMITER_CLEANUP_INTERVAL);
+    cleanup_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        select! {
+            _ = cleanup_interval.tick() => rate_limiter.retain_recent(),
+            _ = cancel.cancelled() => break,
+        }
+    }
+}
+
 pub fn get_connection_stake(
     connection: &Connection,
     staked_nodes: &RwLock<StakedNodes>,
@@ -459,6 +714,8 @@ async fn setup_connection<Q, C>(
     rate_limiter: Arc<ConnectionRateLimiter>,
     overall_connection_rate_limiter: Arc<TokenBucket>,
     client_connection_tracker: ClientConnectionTracker,
+    handshake_permit: OwnedSemaphorePermit,
+    memory_budget: Option<Arc<MemoryBudgetAccount>>,
     packet_sender: Sender<PacketBatch>,
     stats: Arc<StreamerStats>,
     server_params: Arc<QuicStreamerConfig>,
@@ -470,6 +727,7 @@ async fn setup_connection<Q, C>(
 {
     let from = connecting.remote_address();
     let res = timeout(QUIC_CONNECTION_HANDSHAKE_TIMEOUT, connecting).await;
+    drop(handshake_permit);
     stats
         .outstanding_incoming_connection_attempts
         .fetch_sub(1, Ordering::Relaxed);
@@ -477,6 +735,22 @@ async fn setup_connection<Q, C>(
         match connecting_result {
             Ok(new_connection) => {
                 debug!("Got a connection {from:?}");
+                if server_params
+                    .ready
+                    .as_ref()
+                    .is_some_and(|ready| !ready.load(Ordering::Relaxed))
+                {
+                    debug!("Reject connection from {from:?} -- server not ready");
+                    stats
+                        .refused_connections_not_ready
+                        .fetch_add(1, Ordering::Relaxed);
+                    new_connection.close(
+                        CONNECTION_CLOSE_CODE_NOT_READY.into(),
+                        CONNECTION_CLOSE_REASON_NOT_READY,
+                    );
+                    return;
+                }
+
                 // now that we have observed the handshake we can be certain
                 // that the initiator owns an IP address, we can update rate
                 // limiters on the server
@@ -510,6 +784,29 @@ async fn setup_connection<Q, C>(
                 stats.total_new_connections.fetch_add(1, Ordering::Relaxed);
 
                 let mut conn_context = qos.build_connection_context(&new_connection);
+                if let Some(memory_budget) = &memory_budget {
+                    if !memory_budget.budget().admits(conn_context.peer_type()) {
+                        debug!("Reject connection from {from:?} -- memory budget exceeded");
+                        stats
+                            .memory_budget_shed_connections
+                            .fetch_add(1, Ordering::Relaxed);
+                        new_connection.close(
+                            CONNECTION_CLOSE_CODE_DISALLOWED.into(),
+                            CONNECTION_CLOSE_REASON_DISALLOWED,
+                        );
+                        return;
+                    }
+                }
+                // Only looks the remote pubkey up if some peers are tracked
+                let track_performance = server_params
+                    .track_pubkeys
+                    .as_ref()
+                    .and_then(|track_pubkeys| {
+                        get_remote_pubkey(&new_connection)
+                            .map(|pubkey| track_pubkeys.contains(&pubkey))
+                    })
+                    .unwrap_or_default();
+                let traffic = client_connection_tracker.traffic.clone();
                 if let Some(cancel_connection) = qos
                     .try_add_connection(
                         client_connection_tracker,
@@ -528,6 +825,9 @@ async fn setup_connection<Q, C>(
                         conn_context.clone(),
                         qos,
                         cancel_connection,
+                        traffic,
+                        memory_budget,
+                        track_performance,
                     ));
                 }
             }
@@ -580,6 +880,7 @@ fn handle_connection_error(e: quinn::ConnectionError, stats: &StreamerStats, fro
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_connection<Q, C>(
     packet_sender: Sender<PacketBatch>,
     remote_address: SocketAddr,
@@ -590,6 +891,9 @@ async fn handle_connection<Q, C>(
     context: C,
     qos: Arc<Q>,
     cancel: CancellationToken,
+    traffic: Arc<ConnectionTraffic>,
+    memory_budget: Option<Arc<MemoryBudgetAccount>>,
+    track_performance: bool,
 ) where
     Q: QosController<C> + Send + Sync + 'static,
     C: ConnectionContext + Send + Sync + 'static,
@@ -602,6 +906,9 @@ async fn handle_connection<Q, C>(
         stats.total_connections.load(Ordering::Relaxed),
     );
     stats.total_connections.fetch_add(1, Ordering::Relaxed);
+    let _connection_charge = memory_budget
+        .as_ref()
+        .map(|memory_budget| memory_budget.charge(APPROX_CONNECTION_BYTES));
 
     // cache the RTT to avoid grabbing lock for every stream.
     // we only use that for some stats here, so if it gets stale during connection lifetime
@@ -621,6 +928,17 @@ async fn handle_connection<Q, C>(
             _ = cancel.cancelled() => break,
         };
 
+        if memory_budget
+            .as_ref()
+            .is_some_and(|memory_budget| !memory_budget.budget().admits(peer_type))
+        {
+            stats
+                .memory_budget_shed_streams
+                .fetch_add(1, Ordering::Relaxed);
+            let _ = stream.stop(STREAM_STOP_CODE_MEMORY_BUDGET.into());
+            continue;
+        }
+
         qos.on_new_stream(&context).await;
         qos.on_stream_accepted(&context);
         stats.active_streams.fetch_add(1, Ordering::Relaxed);
@@ -632,6 +950,7 @@ async fn handle_connection<Q, C>(
         if let Some(pubkey) = context.remote_pubkey() {
             meta.set_remote_pubkey(pubkey);
         }
+        meta.set_track_performance(track_performance);
 
         let mut accum = PacketAccumulator::new(meta);
         // Virtually all small transactions will fit in 1 chunk. Larger transactions will fit in 1
@@ -643,6 +962,10 @@ async fn handle_connection<Q, C>(
         // overallocating a few bytes is negligible compared to the cost of having to do multiple
         // read_chunks() calls.
         let mut chunks: [Bytes; 4] = array::from_fn(|_| Bytes::new());
+        // the chunks accumulated so far, released once the stream is done
+        let mut stream_charge = memory_budget
+            .as_ref()
+            .map(|memory_budget| memory_budget.charge(0));
 
         loop {
             // Read the next chunks, waiting up to `wait_for_chunk_timeout`. If we don't get chunks
@@ -688,11 +1011,19 @@ async fn handle_connection<Q, C>(
             ) {
                 // The stream is finished, break out of the loop and close the stream.
                 Ok(StreamState::Finished) => {
-                    qos.on_stream_finished(&context);
+                    if let Some(memory_budget) = &memory_budget {
+                        memory_budget.set_channel_len(packet_sender.len());
+                    }
+                    traffic.record_stream(accum.meta.size);
+                    qos.on_stream_finished(&context, accum.meta.size);
                     break;
                 }
                 // The stream is still active, continue reading.
-                Ok(StreamState::Receiving) => {}
+                Ok(StreamState::Receiving) => {
+                    if let Some(stream_charge) = &mut stream_charge {
+                        stream_charge.set(accum.meta.size);
+                    }
+                }
                 Err(_) => {
                     // Disconnect peers that send invalid streams.
                     connection.close(
@@ -723,6 +1054,7 @@ async fn handle_connection<Q, C>(
     stats.total_connections.fetch_sub(1, Ordering::Relaxed);
 }
 
+#[derive(Debug)]
 enum StreamState {
     // Stream is not finished, keep receiving chunks
     Receiving,
@@ -749,7 +1081,7 @@ fn handle_chunks(
         if accum.meta.size > max_stream_data_bytes as usize {
             // A peer can send multiple chunks that together exceed the
             // configured maximum data bytes receivable over one stream; reject the stream in that case.
-            stats.invalid_stream_size.fetch_add(1, Ordering::Relaxed);
+            stats.record_invalid_stream_size(accum.meta.addr, accum.meta.size);
             debug!("invalid stream size {}", accum.meta.size);
             return Err(());
         }
@@ -788,11 +1120,17 @@ fn handle_chunks(
     // them into one `Bytes` buffer. We make a copy once, with
     // intention to not do it again.
     let packet = if accum.chunks.len() == 1 {
+        stats
+            .total_single_chunk_packets
+            .fetch_add(1, Ordering::Relaxed);
         BytesPacket::new(
             accum.chunks.pop().expect("expected one chunk"),
             accum.meta.clone(),
         )
     } else {
+        stats
+            .total_multi_chunk_packets
+            .fetch_add(1, Ordering::Relaxed);
         let mut buf = BytesMut::with_capacity(bytes_sent);
         for chunk in &accum.chunks {
             buf.put_slice(chunk);
@@ -811,6 +1149,16 @@ fn handle_chunks(
             .reassembly_delayed_streams_cumulative_delay_us
             .fetch_add(total_latency.as_micros() as usize, Ordering::Relaxed);
     }
+    if packet.meta().is_perf_track_packet() {
+        debug!(
+            "Tracked stream reassembled in {}us",
+            total_latency.as_micros()
+        );
+        stats.tracked_packets.fetch_add(1, Ordering::Relaxed);
+        stats
+            .tracked_packets_cumulative_reassembly_us
+            .fetch_add(total_latency.as_micros() as usize, Ordering::Relaxed);
+    }
     let packet_batch = PacketBatch::Single(packet);
 
     if let Err(err) = packet_sender.try_send(packet_batch) {
@@ -862,10 +1210,11 @@ struct ConnectionEntry<S: OpaqueStreamerCounter> {
     peer_type: ConnectionPeerType,
     last_update: Arc<AtomicU64>,
     port: u16,
-    // We do not explicitly use it, but its drop is triggered when ConnectionEntry is dropped.
-    _client_connection_tracker: ClientConnectionTracker,
+    // Its drop is triggered when ConnectionEntry is dropped.
+    client_connection_tracker: ClientConnectionTracker,
     connection: Option<Connection>,
     stream_counter: Arc<S>,
+    established: Instant,
 }
 
 impl<S: OpaqueStreamerCounter> ConnectionEntry<S> {
@@ -883,9 +1232,10 @@ impl<S: OpaqueStreamerCounter> ConnectionEntry<S> {
             peer_type,
             last_update,
             port,
-            _client_connection_tracker: client_connection_tracker,
+            client_connection_tracker,
             connection,
             stream_counter,
+            established: Instant::now(),
         }
     }
 
@@ -893,6 +1243,26 @@ impl<S: OpaqueStreamerCounter> ConnectionEntry<S> {
         self.last_update.load(Ordering::Relaxed)
     }
 
+    fn summary(&self, key: &ConnectionTableKey) -> ConnectionSummary {
+        let remote_pubkey = match key {
+            ConnectionTableKey::IP(_) => None,
+            ConnectionTableKey::Pubkey(pubkey) => Some(*pubkey),
+        };
+        let traffic = &self.client_connection_tracker.traffic;
+        let last_update = self.last_update();
+        ConnectionSummary {
+            remote_pubkey,
+            remote_address: self.connection.as_ref().map(Connection::remote_address),
+            stake: self.stake(),
+            last_update,
+            idle_ms: timing::timestamp().saturating_sub(last_update),
+            age: self.established.elapsed(),
+            lifetime_streams: traffic.streams.load(Ordering::Relaxed),
+            lifetime_bytes: traffic.bytes.load(Ordering::Relaxed),
+            current_stream_cap: None,
+        }
+    }
+
     fn stake(&self) -> u64 {
         match self.peer_type {
             ConnectionPeerType::Unstaked => 0,
@@ -932,6 +1302,32 @@ pub(crate) enum ConnectionTableType {
     Unstaked,
 }
 
+/// Randomness of the connection pruning of a QoS, seeded for reproducible runs
+/// and drawn from the thread local RNG otherwise
+pub(crate) struct PruneRng(Option<Mutex<SmallRng>>);
+
+impl PruneRng {
+    pub(crate) fn new(seed: Option<u64>) -> Self {
+        Self(seed.map(|seed| Mutex::new(SmallRng::seed_from_u64(seed))))
+    }
+
+    pub(crate) fn prune_random<S: OpaqueStreamerCounter>(
+        &self,
+        connection_table: &mut ConnectionTable<S>,
+        sample_size: usize,
+        threshold_stake: u64,
+    ) -> usize {
+        match &self.0 {
+            Some(rng) => connection_table.prune_random(
+                &mut *rng.lock().unwrap(),
+                sample_size,
+                threshold_stake,
+            ),
+            None => connection_table.prune_random(&mut rng(), sample_size, threshold_stake),
+        }
+    }
+}
+
 // Map of IP to list of connection entries
 pub(crate) struct ConnectionTable<S: OpaqueStreamerCounter> {
     table: IndexMap<ConnectionTableKey, Vec<ConnectionEntry<S>>>,
@@ -983,13 +1379,17 @@ impl<S: OpaqueStreamerCounter> ConnectionTable<S> {
     // lowest stake, and returns the number of pruned connections.
     // If the stakes of all the sampled connections are higher than the
     // threshold_stake, rejects the pruning attempt, and returns 0.
-    pub(crate) fn prune_random(&mut self, sample_size: usize, threshold_stake: u64) -> usize {
-        let num_pruned = std::iter::once(self.table.len())
-            .filter(|&size| size > 0)
-            .flat_map(|size| {
-                let mut rng = rng();
-                repeat_with(move || rng.random_range(0..size))
-            })
+    pub(crate) fn prune_random(
+        &mut self,
+        rng: &mut impl Rng,
+        sample_size: usize,
+        threshold_stake: u64,
+    ) -> usize {
+        let size = self.table.len();
+        if size == 0 {
+            return 0;
+        }
+        let num_pruned = repeat_with(|| rng.random_range(0..size))
             .map(|index| {
                 let connection = self.table[index].first();
                 let stake = connection.map(|connection: &ConnectionEntry<S>| connection.stake());
@@ -1086,6 +1486,26 @@ impl<S: OpaqueStreamerCounter> ConnectionTable<S> {
         }
     }
 
+    /// Returns a summary of every connection in the table.
+    pub(crate) fn list_connections(&self) -> Vec<ConnectionSummary> {
+        self.table
+            .iter()
+            .flat_map(|(key, connections)| {
+                connections.iter().map(|connection| connection.summary(key))
+            })
+            .collect()
+    }
+
+    /// Returns a summary of every connection without a finished stream for at
+    /// least `threshold`.
+    pub(crate) fn idle_connections(&self, threshold: Duration) -> Vec<ConnectionSummary> {
+        let threshold_ms = threshold.as_millis() as u64;
+        self.list_connections()
+            .into_iter()
+            .filter(|connection| connection.idle_ms >= threshold_ms)
+            .collect()
+    }
+
     /// Removes all connections associated with `key`.
     ///
     /// Returns the number of removed connections.
@@ -1108,23 +1528,12 @@ impl<S: OpaqueStreamerCounter> ConnectionTable<S> {
     }
 }
 
-struct EndpointAccept<'a> {
-    endpoint: usize,
-    accept: Accept<'a>,
-}
-
-impl Future for EndpointAccept<'_> {
-    type Output = (Option<quinn::Incoming>, usize);
+type EndpointAccept = Pin<Box<dyn Future<Output = (Option<quinn::Incoming>, usize)> + Send>>;
 
-    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context) -> Poll<Self::Output> {
-        let i = self.endpoint;
-        // Safety:
-        // self is pinned and accept is a field so it can't get moved out. See safety docs of
-        // map_unchecked_mut.
-        unsafe { self.map_unchecked_mut(|this| &mut this.accept) }
-            .poll(cx)
-            .map(|r| (r, i))
-    }
+/// Accepts the next incoming connection of `endpoint`, the `i`th endpoint of
+/// the server. The future owns the endpoint, as the server may replace it.
+fn endpoint_accept(endpoint: Endpoint, i: usize) -> EndpointAccept {
+    Box::pin(async move { (endpoint.accept().await, i) })
 }
 
 #[cfg(test)]
@@ -1132,24 +1541,85 @@ pub mod test {
     use {
         super::*,
         crate::nonblocking::{
+            memory_budget::TpuIngestMemoryBudget,
             qos::NullStreamerCounter,
             swqos::SwQosConfig,
             testing_utilities::{
                 SpawnTestServerResult, check_multiple_streams, get_client_config,
-                make_client_endpoint, setup_quic_server, spawn_stake_weighted_qos_server,
+                get_client_config_with_alpn, make_client_endpoint, setup_quic_server,
+                spawn_stake_weighted_qos_server,
             },
         },
         assert_matches::assert_matches,
         crossbeam_channel::{Receiver, unbounded},
-        quinn::{ApplicationClose, ConnectionError},
+        quinn::{
+            ApplicationClose, ConnectionError, Runtime, UdpPoller,
+            udp::{RecvMeta, Transmit},
+        },
         solana_keypair::Keypair,
         solana_net_utils::sockets::bind_to_localhost_unique,
         solana_packet::PACKET_DATA_SIZE,
         solana_signer::Signer,
-        std::collections::HashMap,
+        std::{
+            collections::{HashMap, HashSet},
+            io::{self, IoSliceMut},
+            sync::atomic::{AtomicBool, AtomicUsize},
+            task::{Context, Poll},
+        },
         tokio::time::sleep,
     };
 
+    /// A client socket which drops every datagram it receives, so that the
+    /// handshakes of its connections never complete
+    #[derive(Debug)]
+    struct DeafUdpSocket(Arc<dyn AsyncUdpSocket>);
+
+    impl AsyncUdpSocket for DeafUdpSocket {
+        fn create_io_poller(self: Arc<Self>) -> Pin<Box<dyn UdpPoller>> {
+            self.0.clone().create_io_poller()
+        }
+
+        fn try_send(&self, transmit: &Transmit<'_>) -> io::Result<()> {
+            self.0.try_send(transmit)
+        }
+
+        fn poll_recv(
+            &self,
+            _cx: &mut Context,
+            _bufs: &mut [IoSliceMut<'_>],
+            _meta: &mut [RecvMeta],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+
+        fn local_addr(&self) -> io::Result<SocketAddr> {
+            self.0.local_addr()
+        }
+    }
+
+    /// Starts a handshake with `server_address` which the server never sees
+    /// completing
+    fn start_stalled_handshake(server_address: SocketAddr) -> (Endpoint, Connecting) {
+        let socket = TokioRuntime
+            .wrap_udp_socket(bind_to_localhost_unique().expect("should bind - client"))
+            .unwrap();
+        let endpoint = Endpoint::new_with_abstract_socket(
+            EndpointConfig::default(),
+            None,
+            Arc::new(DeafUdpSocket(socket)),
+            Arc::new(TokioRuntime),
+        )
+        .unwrap();
+        let connecting = endpoint
+            .connect_with(
+                get_client_config(&Keypair::new()),
+                server_address,
+                "localhost",
+            )
+            .unwrap();
+        (endpoint, connecting)
+    }
+
     pub async fn check_timeout(receiver: Receiver<PacketBatch>, server_address: SocketAddr) {
         let conn1 = make_client_endpoint(&server_address, None).await;
         let total = 30;
@@ -1299,6 +1769,295 @@ pub mod test {
         drop(receiver);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quic_server_custom_alpn() {
+        agave_logger::setup();
+        const CUSTOM_ALPN_PROTOCOL_ID: &[u8] = b"custom-tpu";
+        let SpawnTestServerResult {
+            join_handle,
+            receiver,
+            server_address,
+            stats: _,
+            cancel,
+        } = setup_quic_server(
+            None,
+            QuicStreamerConfig {
+                alpn_protocol_id: Some(CUSTOM_ALPN_PROTOCOL_ID.to_vec()),
+                ..QuicStreamerConfig::default_for_tests()
+            },
+            SwQosConfig::default(),
+        );
+
+        let client_socket = bind_to_localhost_unique().expect("should bind - client");
+        let endpoint = quinn::Endpoint::new(
+            EndpointConfig::default(),
+            None,
+            client_socket,
+            Arc::new(TokioRuntime),
+        )
+        .unwrap();
+        let keypair = Keypair::new();
+
+        // A client negotiating the default protocol id is rejected
+        let result = endpoint
+            .connect_with(get_client_config(&keypair), server_address, "localhost")
+            .expect("Failed in connecting")
+            .await;
+        assert_matches!(result, Err(ConnectionError::ConnectionClosed(_)));
+
+        // A client negotiating the custom protocol id can send transactions
+        let connection = endpoint
+            .connect_with(
+                get_client_config_with_alpn(&keypair, CUSTOM_ALPN_PROTOCOL_ID),
+                server_address,
+                "localhost",
+            )
+            .expect("Failed in connecting")
+            .await
+            .expect("Failed in waiting");
+        let mut stream = connection.open_uni().await.unwrap();
+        stream.write_all(&[42u8]).await.unwrap();
+        stream.finish().unwrap();
+        let packet_batch = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(packet_batch.get(0).unwrap().data(..).unwrap(), &[42u8]);
+
+        cancel.cancel();
+        join_handle.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quic_server_refuses_handshake_overload() {
+        agave_logger::setup();
+        const MAX_CONCURRENT_HANDSHAKES: usize = 2;
+        const NUM_STALLED_HANDSHAKES: usize = 8;
+        let client_keypair = Keypair::new();
+        let stakes = HashMap::from([(client_keypair.pubkey(), 100_000)]);
+        let staked_nodes = StakedNodes::new(
+            Arc::new(stakes),
+            HashMap::<Pubkey, u64>::default(), // overrides
+        );
+        let SpawnTestServerResult {
+            join_handle,
+            receiver,
+            server_address,
+            stats,
+            cancel,
+        } = setup_quic_server(
+            Some(staked_nodes),
+            QuicStreamerConfig {
+                max_concurrent_handshakes: Some(MAX_CONCURRENT_HANDSHAKES),
+                ..QuicStreamerConfig::default_for_tests()
+            },
+            SwQosConfig::default(),
+        );
+        let staked_connection = make_client_endpoint(&server_address, Some(&client_keypair)).await;
+
+        // Handshakes beyond the cap are refused well before the stalled ones
+        // time out
+        let start = Instant::now();
+        let stalled_handshakes: Vec<_> = (0..NUM_STALLED_HANDSHAKES)
+            .map(|_| start_stalled_handshake(server_address))
+            .collect();
+        while stats.refused_handshake_overload.load(Ordering::Relaxed)
+            < NUM_STALLED_HANDSHAKES - MAX_CONCURRENT_HANDSHAKES
+        {
+            assert!(start.elapsed() < QUIC_CONNECTION_HANDSHAKE_TIMEOUT);
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            stats
+                .outstanding_incoming_connection_attempts
+                .load(Ordering::Relaxed),
+            MAX_CONCURRENT_HANDSHAKES
+        );
+
+        // The established staked connection is still served
+        let mut stream = staked_connection.open_uni().await.unwrap();
+        stream.write_all(&[0u8; 8]).await.unwrap();
+        stream.finish().unwrap();
+        let packets = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets.iter().next().unwrap().meta().size, 8);
+
+        drop(stalled_handshakes);
+        cancel.cancel();
+        join_handle.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quic_servers_share_memory_budget() {
+        agave_logger::setup();
+        // Unstaked peers are shed past 75 packets worth of memory, staked
+        // peers past 100
+        const NUM_FLOOD_PACKETS: usize = 64;
+        let budget = Arc::new(TpuIngestMemoryBudget::new(100 * PACKET_DATA_SIZE));
+        let client_keypair = Keypair::new();
+        let staked_nodes = || {
+            StakedNodes::new(
+                Arc::new(HashMap::from([(client_keypair.pubkey(), 100_000)])),
+                HashMap::<Pubkey, u64>::default(), // overrides
+            )
+        };
+        let server_params = QuicStreamerConfig {
+            memory_budget: Some(budget.clone()),
+            ..QuicStreamerConfig::default_for_tests()
+        };
+        let SpawnTestServerResult {
+            join_handle: flooded_join_handle,
+            receiver: flooded_receiver,
+            server_address: flooded_server_address,
+            stats: _,
+            cancel: flooded_cancel,
+        } = setup_quic_server(
+            Some(staked_nodes()),
+            server_params.clone(),
+            SwQosConfig::default(),
+        );
+        let SpawnTestServerResult {
+            join_handle,
+            receiver,
+            server_address,
+            stats,
+            cancel,
+        } = setup_quic_server(Some(staked_nodes()), server_params, SwQosConfig::default());
+
+        // Nothing drains the packet channel of the flooded server
+        let flooding_connection =
+            make_client_endpoint(&flooded_server_address, Some(&client_keypair)).await;
+        for _ in 0..NUM_FLOOD_PACKETS {
+            let mut stream = flooding_connection.open_uni().await.unwrap();
+            stream.write_all(&[0u8; 8]).await.unwrap();
+            stream.finish().unwrap();
+        }
+        let start = Instant::now();
+        while flooded_receiver.len() < NUM_FLOOD_PACKETS
+            || budget.admits(ConnectionPeerType::Unstaked)
+        {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert!(budget.admits(ConnectionPeerType::Staked(1)));
+
+        // The other server sheds unstaked connections
+        let unstaked_connection = make_client_endpoint(&server_address, None).await;
+        assert_matches!(
+            unstaked_connection.closed().await,
+            ConnectionError::ApplicationClosed(ApplicationClose { error_code, .. })
+                if error_code == CONNECTION_CLOSE_CODE_DISALLOWED.into()
+        );
+        assert_eq!(
+            stats.memory_budget_shed_connections.load(Ordering::Relaxed),
+            1
+        );
+
+        // but still accepts staked traffic
+        let staked_connection = make_client_endpoint(&server_address, Some(&client_keypair)).await;
+        let mut stream = staked_connection.open_uni().await.unwrap();
+        stream.write_all(&[0u8; 8]).await.unwrap();
+        stream.finish().unwrap();
+        let packets = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(stats.memory_budget_shed_streams.load(Ordering::Relaxed), 0);
+
+        // Draining the flooded server releases its share of the budget
+        flooded_receiver.try_iter().for_each(drop);
+        let mut stream = flooding_connection.open_uni().await.unwrap();
+        stream.write_all(&[0u8; 8]).await.unwrap();
+        stream.finish().unwrap();
+        flooded_receiver
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap();
+        let start = Instant::now();
+        while !budget.admits(ConnectionPeerType::Unstaked) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        drop(flooding_connection);
+        drop(staked_connection);
+        cancel.cancel();
+        flooded_cancel.cancel();
+        join_handle.await.unwrap();
+        flooded_join_handle.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quic_server_refuses_connections_until_ready() {
+        agave_logger::setup();
+        let ready = Arc::new(AtomicBool::new(false));
+        let server_params = QuicStreamerConfig {
+            ready: Some(ready.clone()),
+            ..QuicStreamerConfig::default_for_tests()
+        };
+        let SpawnTestServerResult {
+            join_handle,
+            receiver,
+            server_address,
+            stats,
+            cancel,
+        } = setup_quic_server(None, server_params, SwQosConfig::default());
+
+        let connection = make_client_endpoint(&server_address, None).await;
+        assert_matches!(
+            connection.closed().await,
+            ConnectionError::ApplicationClosed(ApplicationClose { error_code, reason })
+                if error_code == CONNECTION_CLOSE_CODE_NOT_READY.into()
+                    && reason.as_ref() == CONNECTION_CLOSE_REASON_NOT_READY
+        );
+        assert_eq!(
+            stats.refused_connections_not_ready.load(Ordering::Relaxed),
+            1
+        );
+
+        ready.store(true, Ordering::Relaxed);
+        let connection = make_client_endpoint(&server_address, None).await;
+        let mut stream = connection.open_uni().await.unwrap();
+        stream.write_all(&[0u8; 8]).await.unwrap();
+        stream.finish().unwrap();
+        let packets = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(
+            stats.refused_connections_not_ready.load(Ordering::Relaxed),
+            1
+        );
+
+        drop(connection);
+        cancel.cancel();
+        join_handle.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quic_server_tracks_packets_of_track_pubkeys() {
+        agave_logger::setup();
+        let tracked_keypair = Keypair::new();
+        let server_params = QuicStreamerConfig {
+            track_pubkeys: Some(Arc::new(HashSet::from([tracked_keypair.pubkey()]))),
+            ..QuicStreamerConfig::default_for_tests()
+        };
+        let SpawnTestServerResult {
+            join_handle,
+            receiver,
+            server_address,
+            stats,
+            cancel,
+        } = setup_quic_server(None, server_params, SwQosConfig::default());
+
+        for (client_keypair, is_tracked) in [(Some(&tracked_keypair), true), (None, false)] {
+            let connection = make_client_endpoint(&server_address, client_keypair).await;
+            let mut stream = connection.open_uni().await.unwrap();
+            stream.write_all(&[0u8; 8]).await.unwrap();
+            stream.finish().unwrap();
+            let packets = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+            assert_eq!(packets.len(), 1);
+            let packet = packets.iter().next().unwrap();
+            assert_eq!(packet.meta().is_perf_track_packet(), is_tracked);
+        }
+        assert_eq!(stats.tracked_packets.load(Ordering::Relaxed), 1);
+
+        cancel.cancel();
+        join_handle.await.unwrap();
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_quic_timeout() {
         agave_logger::setup();
@@ -1465,6 +2224,59 @@ pub mod test {
         join_handle.await.unwrap();
     }
 
+    #[test]
+    fn test_handle_chunks_counts_single_and_multi_chunk_packets() {
+        let (sender, receiver) = unbounded();
+        let stats = StreamerStats::default();
+        let receive_packet = |chunks: &[&'static [u8]]| {
+            let mut accum = PacketAccumulator::new(Meta::default());
+            for chunk in chunks {
+                assert_matches!(
+                    handle_chunks(
+                        [Bytes::from_static(chunk)].into_iter(),
+                        &mut accum,
+                        Duration::from_millis(10),
+                        &sender,
+                        &stats,
+                        ConnectionPeerType::Unstaked,
+                        PACKET_DATA_SIZE as u32,
+                    ),
+                    Ok(StreamState::Receiving)
+                );
+            }
+            // No chunks mark the end of the stream
+            assert_matches!(
+                handle_chunks(
+                    std::iter::empty(),
+                    &mut accum,
+                    Duration::from_millis(10),
+                    &sender,
+                    &stats,
+                    ConnectionPeerType::Unstaked,
+                    PACKET_DATA_SIZE as u32,
+                ),
+                Ok(StreamState::Finished)
+            );
+            let packets = receiver.try_recv().unwrap();
+            assert_eq!(
+                packets.iter().next().unwrap().data(..).unwrap(),
+                chunks.concat()
+            );
+        };
+
+        receive_packet(&[b"single"]);
+        receive_packet(&[b"single"]);
+        receive_packet(&[b"multi", b"ple"]);
+        receive_packet(&[b"mu", b"lt", b"iple"]);
+        receive_packet(&[b"single"]);
+        assert_eq!(stats.total_single_chunk_packets.load(Ordering::Relaxed), 3);
+        assert_eq!(stats.total_multi_chunk_packets.load(Ordering::Relaxed), 2);
+
+        stats.report("test");
+        assert_eq!(stats.total_single_chunk_packets.load(Ordering::Relaxed), 0);
+        assert_eq!(stats.total_multi_chunk_packets.load(Ordering::Relaxed), 0);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_quic_server_multiple_writes() {
         agave_logger::setup();
@@ -1519,6 +2331,46 @@ pub mod test {
         assert_eq!(stats.connection_remove_failed.load(Ordering::Relaxed), 0);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quic_server_staked_connection_demoted() {
+        agave_logger::setup();
+
+        let client_keypair = Keypair::new();
+        let stakes = HashMap::from([(client_keypair.pubkey(), 100_000)]);
+        let staked_nodes = StakedNodes::new(
+            Arc::new(stakes),
+            HashMap::<Pubkey, u64>::default(), // overrides
+        );
+        // No room in the staked table, the connection is demoted to the
+        // unstaked one
+        let SpawnTestServerResult {
+            join_handle,
+            receiver,
+            server_address,
+            stats,
+            cancel,
+        } = setup_quic_server(
+            Some(staked_nodes),
+            QuicStreamerConfig::default_for_tests(),
+            SwQosConfig {
+                max_staked_connections: 0,
+                report_staked_connection_demotions: true,
+                ..SwQosConfig::default()
+            },
+        );
+        check_multiple_writes(receiver, server_address, Some(&client_keypair)).await;
+        cancel.cancel();
+        join_handle.await.unwrap();
+
+        assert_eq!(stats.staked_connection_demoted.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            stats
+                .connection_added_from_staked_peer
+                .load(Ordering::Relaxed),
+            1
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_quic_server_zero_staked_connection_removal() {
         // In this test, the client has a pubkey, but is not in stake table.
@@ -1662,6 +2514,75 @@ pub mod test {
         assert_eq!(stats.total_new_connections.load(Ordering::Relaxed), 2);
     }
 
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quic_server_rebinds_dead_endpoint() {
+        use std::os::fd::AsRawFd;
+
+        agave_logger::setup();
+        let s = bind_to_localhost_unique().expect("should bind");
+        let dead_socket = s.try_clone().unwrap();
+        let (sender, receiver) = unbounded();
+        let num_rebinds = Arc::new(AtomicUsize::new(0));
+        // The dead socket still holds its address, so rebind to another one
+        let endpoint_rebinder = Arc::new({
+            let num_rebinds = num_rebinds.clone();
+            move |_dead_address| {
+                num_rebinds.fetch_add(1, Ordering::Relaxed);
+                bind_to_localhost_unique()
+            }
+        });
+        let cancel = CancellationToken::new();
+        let SpawnNonBlockingServerResult {
+            endpoints,
+            stats,
+            thread: t,
+            max_concurrent_connections: _,
+        } = spawn_stake_weighted_qos_server(
+            "quic_streamer_test",
+            [s.into()],
+            &Keypair::new(),
+            sender,
+            Arc::default(),
+            QuicStreamerConfig {
+                endpoint_rebinder: Some(endpoint_rebinder),
+                endpoint_dead_timeout: Duration::from_millis(100),
+                ..QuicStreamerConfig::default_for_tests()
+            },
+            SwQosConfig {
+                max_connections_per_unstaked_peer: 2,
+                ..Default::default()
+            },
+            cancel.clone(),
+        )
+        .unwrap();
+        let endpoint = endpoints.get()[0].clone();
+        let dead_address = endpoint.local_addr().unwrap();
+
+        // Sending over the socket fails from now on. The socket is not
+        // connected, which shutdown reports while shutting it down anyway.
+        let _ =
+            nix::sys::socket::shutdown(dead_socket.as_raw_fd(), nix::sys::socket::Shutdown::Write);
+        let start = Instant::now();
+        while endpoint.local_addr().unwrap() == dead_address {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "dead endpoint not rebound"
+            );
+            sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(num_rebinds.load(Ordering::Relaxed), 1);
+        assert!(!stats.is_degraded());
+
+        // The rebound endpoint serves new connections
+        let server_address = endpoint.local_addr().unwrap();
+        assert_eq!(endpoints.get()[0].local_addr().unwrap(), server_address);
+        check_multiple_streams(receiver, server_address, None).await;
+        assert_eq!(num_rebinds.load(Ordering::Relaxed), 1);
+        cancel.cancel();
+        t.await.unwrap();
+    }
+
     #[test]
     fn test_prune_table_with_ip() {
         use std::net::Ipv4Addr;
@@ -1808,6 +2729,47 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn test_idle_connections() {
+        let cancel = CancellationToken::new();
+        let mut table = ConnectionTable::new(ConnectionTableType::Unstaked, cancel);
+        let stats = Arc::new(StreamerStats::default());
+        let now = timing::timestamp();
+        let pubkeys: Vec<_> = repeat_with(Pubkey::new_unique).take(3).collect();
+        // Last finished a stream 1 minute ago, 5 seconds ago, and just now
+        for (pubkey, idle_ms) in pubkeys.iter().zip([60_000, 5_000, 0]) {
+            table
+                .try_add_connection(
+                    ConnectionTableKey::Pubkey(*pubkey),
+                    0,
+                    ClientConnectionTracker::new(stats.clone(), 1000).unwrap(),
+                    None,
+                    ConnectionPeerType::Unstaked,
+                    Arc::new(AtomicU64::new(now - idle_ms)),
+                    10,
+                    || Arc::new(NullStreamerCounter {}),
+                )
+                .unwrap();
+        }
+
+        let idle_pubkeys = |threshold| {
+            let mut idle_pubkeys: Vec<_> = table
+                .idle_connections(threshold)
+                .into_iter()
+                .map(|connection| {
+                    assert!(connection.idle_ms >= threshold.as_millis() as u64);
+                    connection.remote_pubkey.unwrap()
+                })
+                .collect();
+            idle_pubkeys.sort_by_key(|pubkey| pubkeys.iter().position(|p| p == pubkey));
+            idle_pubkeys
+        };
+        assert_eq!(idle_pubkeys(Duration::from_secs(30)), pubkeys[..1]);
+        assert_eq!(idle_pubkeys(Duration::from_secs(1)), pubkeys[..2]);
+        assert_eq!(idle_pubkeys(Duration::ZERO), pubkeys);
+        assert!(idle_pubkeys(Duration::from_secs(3600)).is_empty());
+    }
+
     #[test]
     fn test_prune_table_with_non_unique_pubkeys() {
         agave_logger::setup();
@@ -1912,12 +2874,17 @@ pub mod test {
 
         // Try pruninng with threshold stake less than all the entries in the table
         // It should fail to prune (i.e. return 0 number of pruned entries)
-        let pruned = table.prune_random(/*sample_size:*/ 2, /*threshold_stake:*/ 0);
+        let pruned = table.prune_random(
+            &mut rng(),
+            /*sample_size:*/ 2,
+            /*threshold_stake:*/ 0,
+        );
         assert_eq!(pruned, 0);
 
         // Try pruninng with threshold stake higher than all the entries in the table
         // It should succeed to prune (i.e. return 1 number of pruned entries)
         let pruned = table.prune_random(
+            &mut rng(),
             2,                      // sample_size
             num_entries as u64 + 1, // threshold_stake
         );
@@ -1926,6 +2893,98 @@ pub mod test {
         assert_eq!(stats.open_connections.load(Ordering::Relaxed), 4);
     }
 
+    #[test]
+    fn test_prune_table_random_seeded() {
+        use std::net::Ipv4Addr;
+        agave_logger::setup();
+        let stats: Arc<StreamerStats> = Arc::new(StreamerStats::default());
+        let new_table = || {
+            let mut table =
+                ConnectionTable::new(ConnectionTableType::Staked, CancellationToken::new());
+            for i in 0..64u8 {
+                let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(i, 0, 0, 0)), 0);
+                table
+                    .try_add_connection(
+                        ConnectionTableKey::IP(socket.ip()),
+                        socket.port(),
+                        ClientConnectionTracker::new(stats.clone(), 1000).unwrap(),
+                        None,
+                        ConnectionPeerType::Staked(u64::from(i % 8) + 1),
+                        Arc::new(AtomicU64::new(0)),
+                        /*max_connections_per_peer:*/ 1,
+                        || Arc::new(NullStreamerCounter {}),
+                    )
+                    .unwrap();
+            }
+            table
+        };
+        let prune = |seed: u64| {
+            let prune_rng = PruneRng::new(Some(seed));
+            let mut table = new_table();
+            for _ in 0..32 {
+                prune_rng.prune_random(
+                    &mut table, /*sample_size:*/ 2, /*threshold_stake:*/ 8,
+                );
+            }
+            let remaining: Vec<_> = table.table.keys().copied().collect();
+            remaining
+        };
+
+        // Runs with the same seed prune the same connections
+        let remaining = prune(42);
+        assert!(remaining.len() < 64);
+        assert_eq!(prune(42), remaining);
+        assert_ne!(prune(43), remaining);
+    }
+
+    #[tokio::test]
+    async fn test_bind_with_retries() {
+        let stats = StreamerStats::default();
+        let quic_server_params = QuicStreamerConfig {
+            endpoint_rebinder: Some(Arc::new(UdpSocket::bind::<SocketAddr>)),
+            endpoint_bind_retries: 8,
+            endpoint_bind_retry_delay: Duration::from_millis(5),
+            ..QuicStreamerConfig::default()
+        };
+        // The socket handed to the server fails, and the fresh sockets can't
+        // be bound as long as the address is held by another socket
+        let bind = |holder: UdpSocket| {
+            let address = holder.local_addr().unwrap();
+            let socket = holder.try_clone().unwrap();
+            let mut num_attempts = 0;
+            let result = bind_with_retries(socket, &quic_server_params, &stats, move |socket| {
+                num_attempts += 1;
+                if num_attempts == 1 {
+                    return Err(io::Error::other("broken socket"));
+                }
+                socket.local_addr()
+            });
+            async move { (result.await.map_err(|err| err.kind()), address) }
+        };
+
+        // The address is bound again once it is released
+        let holder = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let release = {
+            let holder = holder.try_clone().unwrap();
+            tokio::spawn(async move {
+                sleep(Duration::from_millis(30)).await;
+                drop(holder);
+            })
+        };
+        let (result, address) = bind(holder).await;
+        release.await.unwrap();
+        assert_eq!(result, Ok(address));
+        let num_retries = stats.quic_endpoint_bind_retries.swap(0, Ordering::Relaxed);
+        assert!((2..=8).contains(&num_retries), "{num_retries}");
+
+        // The failure to bind is returned once the retries are exhausted
+        let holder = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let _held = holder.try_clone().unwrap();
+        let (result, _address) = bind(holder).await;
+        assert_eq!(result, Err(io::ErrorKind::AddrInUse));
+        assert_eq!(stats.quic_endpoint_bind_retries.load(Ordering::Relaxed), 8);
+    }
+
     #[test]
     fn test_remove_connections() {
         use std::net::Ipv4Addr;
@@ -2048,6 +3107,65 @@ pub mod test {
         assert!(stats.throttled_unstaked_streams.load(Ordering::Relaxed) > 0);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_throughput_cap_no_packet_drop() {
+        agave_logger::setup_with_default_filter();
+
+        let SpawnTestServerResult {
+            join_handle,
+            receiver,
+            server_address,
+            stats,
+            cancel,
+        } = setup_quic_server(
+            None,
+            QuicStreamerConfig::default_for_tests(),
+            SwQosConfig {
+                max_bytes_per_second_per_peer: Some(u64::MAX),
+                // 1000 bytes per throttling interval
+                max_bytes_per_second_per_unstaked_peer: 10_000,
+                ..SwQosConfig::default()
+            },
+        );
+
+        // Both connections come from the same IP address, so they share one budget
+        let client_connections = [
+            make_client_endpoint(&server_address, None).await,
+            make_client_endpoint(&server_address, None).await,
+        ];
+
+        // 10_000 bytes should take about a second to go through
+        let expected_num_txs = 20;
+        let start_time = tokio::time::Instant::now();
+        for i in 0..expected_num_txs {
+            let mut send_stream = client_connections[i % 2].open_uni().await.unwrap();
+            send_stream.write_all(&[i as u8; 500]).await.unwrap();
+            send_stream.finish().unwrap();
+        }
+
+        let mut num_txs_received = 0;
+        while num_txs_received < expected_num_txs && start_time.elapsed() < Duration::from_secs(5) {
+            if let Ok(packets) = receiver.try_recv() {
+                num_txs_received += packets.len();
+            } else {
+                sleep(Duration::from_millis(10)).await;
+            }
+        }
+        let elapsed = start_time.elapsed();
+        info!("Elapsed receiving: {elapsed:?}");
+        assert_eq!(expected_num_txs, num_txs_received);
+        assert!(elapsed >= Duration::from_millis(700));
+
+        cancel.cancel();
+        join_handle.await.unwrap();
+
+        assert_eq!(
+            stats.total_new_streams.load(Ordering::Relaxed),
+            expected_num_txs
+        );
+        assert!(stats.throttled_bytes_peers.load(Ordering::Relaxed) > 0);
+    }
+
     #[test]
     fn test_client_connection_tracker() {
         let stats = Arc::new(StreamerStats::default());
@@ -2089,6 +3207,10 @@ pub mod test {
             _ => panic!("unexpected close"),
         }
         assert_eq!(stats.invalid_stream_size.load(Ordering::Relaxed), 1);
+        let recent = stats.recent_invalid_stream_sizes();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].ip, server_address.ip());
+        assert!(recent[0].size > PACKET_DATA_SIZE);
         cancel.cancel();
         join_handle.await.unwrap();
     }
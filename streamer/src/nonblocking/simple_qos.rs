@@ -5,8 +5,8 @@ use {
             quic::{
                 CONNECTION_CLOSE_CODE_DISALLOWED, CONNECTION_CLOSE_REASON_DISALLOWED,
                 ClientConnectionTracker, ConnectionHandlerError, ConnectionPeerType,
-                ConnectionTable, ConnectionTableKey, ConnectionTableType, MAX_RTT, MIN_RTT,
-                get_connection_stake, update_open_connections_stat,
+                ConnectionSummary, ConnectionTable, ConnectionTableKey, ConnectionTableType,
+                MAX_RTT, MIN_RTT, PruneRng, get_connection_stake, update_open_connections_stat,
             },
         },
         quic::{
@@ -134,6 +134,9 @@ pub struct SimpleQosConfig {
     pub max_streams_per_second: u64,
     pub max_staked_connections: usize,
     pub max_connections_per_peer: usize,
+    /// Seed of the RNG picking the connections to prune, None to draw from
+    /// the thread local RNG
+    pub prune_rng_seed: Option<u64>,
 }
 
 impl Default for SimpleQosConfig {
@@ -142,6 +145,7 @@ impl Default for SimpleQosConfig {
             max_streams_per_second: DEFAULT_MAX_STREAMS_PER_MS * 1000,
             max_staked_connections: DEFAULT_MAX_STAKED_CONNECTIONS,
             max_connections_per_peer: DEFAULT_MAX_QUIC_CONNECTIONS_PER_STAKED_PEER,
+            prune_rng_seed: None,
         }
     }
 }
@@ -155,6 +159,7 @@ pub struct SimpleQos {
     staked_nodes: Arc<RwLock<StakedNodes>>,
     pub(crate) banlist: Arc<SimpleQosBanlist>,
     banlist_eviction_receiver: Option<Receiver<Pubkey>>,
+    prune_rng: PruneRng,
 }
 
 impl SimpleQos {
@@ -166,6 +171,7 @@ impl SimpleQos {
     ) -> Self {
         let (banlist, banlist_eviction_receiver) = SimpleQosBanlist::new();
         let banlist = Arc::new(banlist);
+        let prune_rng = PruneRng::new(config.prune_rng_seed);
         Self {
             config,
             stats,
@@ -176,9 +182,21 @@ impl SimpleQos {
                 ConnectionTableType::Staked,
                 cancel,
             ))),
+            prune_rng,
         }
     }
 
+    /// Returns a summary of every connection.
+    pub async fn list_connections(&self) -> Vec<ConnectionSummary> {
+        self.staked_connection_table.lock().await.list_connections()
+    }
+
+    /// Returns the connection table, to list the connections once the QoS is
+    /// moved into its server
+    pub(crate) fn connection_table(&self) -> Arc<Mutex<ConnectionTable<TokenBucket>>> {
+        self.staked_connection_table.clone()
+    }
+
     fn cache_new_connection(
         &self,
         client_connection_tracker: ClientConnectionTracker,
@@ -312,8 +330,11 @@ impl QosController<SimpleQosConnectionContext> for SimpleQos {
                     let mut connection_table_l = self.staked_connection_table.lock().await;
 
                     if connection_table_l.total_size >= self.config.max_staked_connections {
-                        let num_pruned =
-                            connection_table_l.prune_random(PRUNE_RANDOM_SAMPLE_SIZE, stake);
+                        let num_pruned = self.prune_rng.prune_random(
+                            &mut connection_table_l,
+                            PRUNE_RANDOM_SAMPLE_SIZE,
+                            stake,
+                        );
 
                         debug!(
                             "Pruned {} staked connections to make room for new staked connection \
@@ -377,7 +398,7 @@ impl QosController<SimpleQosConnectionContext> for SimpleQos {
         }
     }
 
-    fn on_stream_finished(&self, context: &SimpleQosConnectionContext) {
+    fn on_stream_finished(&self, context: &SimpleQosConnectionContext, _stream_bytes: usize) {
         context
             .last_update
             .store(timing::timestamp(), Ordering::Relaxed);
@@ -539,6 +560,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         // Create server-side accepted connection
@@ -597,6 +619,7 @@ mod tests {
 
         let client_tracker1 = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         // Add first connection to reach the limit
@@ -619,6 +642,7 @@ mod tests {
             create_server_side_connection().await;
         let client_tracker2 = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let conn_context = SimpleQosConnectionContext {
@@ -664,6 +688,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         // Create server-side accepted connection
@@ -791,6 +816,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         // Create connection using the staked keypairs
@@ -838,6 +864,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         // Create unstaked connection
@@ -889,6 +916,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
         let (server_connection, _client_endpoint, _server_endpoint) =
             create_connection_with_keypairs(&server_keypair, &client_keypair).await;
@@ -940,6 +968,7 @@ mod tests {
         // Add first connection to fill the table
         let client_tracker1 = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection1, _client_endpoint1, _server_endpoint1) =
@@ -956,6 +985,7 @@ mod tests {
         // Try to add second connection (should trigger pruning)
         let client_tracker2 = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection2, _client_endpoint2, _server_endpoint2) =
@@ -1010,6 +1040,7 @@ mod tests {
         // Add high-stake connection first
         let client_tracker1 = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection1, _client_endpoint1, _server_endpoint1) =
@@ -1026,6 +1057,7 @@ mod tests {
         // Try to add low-stake connection (should fail as it can't prune the high-stake one)
         let client_tracker2 = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection2, _client_endpoint2, _server_endpoint2) =
@@ -1067,6 +1099,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection, _client_endpoint, _server_endpoint) =
@@ -1117,6 +1150,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection, _client_endpoint, _server_endpoint) =
@@ -1156,6 +1190,7 @@ mod tests {
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection, _client_endpoint, _server_endpoint) =
@@ -1208,12 +1243,14 @@ mod tests {
             max_streams_per_second,
             max_staked_connections: 100,
             max_connections_per_peer: 10,
+            prune_rng_seed: None,
         };
 
         let simple_qos = SimpleQos::new(qos_config, stats.clone(), staked_nodes, cancel.clone());
 
         let client_tracker = ClientConnectionTracker {
             stats: stats.clone(),
+            traffic: Arc::default(),
         };
 
         let (server_connection, _client_endpoint, _server_endpoint) =
@@ -31,6 +31,20 @@ const STREAM_LOAD_EMA_INTERVAL_COUNT: u64 = 40;
 
 const STAKED_THROTTLING_ON_LOAD_THRESHOLD_RATIO: f64 = 0.95;
 
+/// Max streams per ms shared by all staked connections
+pub(crate) fn max_staked_load_in_ms(
+    max_unstaked_connections: usize,
+    max_streams_per_ms: u64,
+) -> u64 {
+    let allow_unstaked_streams = max_unstaked_connections > 0;
+    if allow_unstaked_streams {
+        max_streams_per_ms
+            - ((EXPECTED_UNSTAKED_STREAMS_RATIO * (max_streams_per_ms as f64)) as u64)
+    } else {
+        max_streams_per_ms
+    }
+}
+
 pub(crate) struct StakedStreamLoadEMA {
     current_load_ema: AtomicU64,
     load_in_recent_interval: AtomicU64,
@@ -50,12 +64,8 @@ impl StakedStreamLoadEMA {
         max_streams_per_ms: u64,
     ) -> Self {
         let allow_unstaked_streams = max_unstaked_connections > 0;
-        let max_staked_load_in_ms = if allow_unstaked_streams {
-            max_streams_per_ms
-                - ((EXPECTED_UNSTAKED_STREAMS_RATIO * (max_streams_per_ms as f64)) as u64)
-        } else {
-            max_streams_per_ms
-        };
+        let max_staked_load_in_ms =
+            max_staked_load_in_ms(max_unstaked_connections, max_streams_per_ms);
 
         let max_staked_load_in_ema_interval = max_staked_load_in_ms * STREAM_LOAD_EMA_INTERVAL_MS;
         let max_staked_load_in_throttling_window =
@@ -195,6 +205,8 @@ impl StakedStreamLoadEMA {
 #[derive(Debug)]
 pub struct ConnectionStreamCounter {
     pub(crate) stream_count: AtomicU64,
+    /// Bytes received in the finished streams of the current throttling interval
+    pub(crate) bytes_count: AtomicU64,
     last_throttling_instant: RwLock<tokio::time::Instant>,
 }
 
@@ -204,11 +216,12 @@ impl ConnectionStreamCounter {
     pub fn new() -> Self {
         Self {
             stream_count: AtomicU64::default(),
+            bytes_count: AtomicU64::default(),
             last_throttling_instant: RwLock::new(tokio::time::Instant::now()),
         }
     }
 
-    /// Reset the counter and last throttling instant and
+    /// Reset the counters and last throttling instant and
     /// return last_throttling_instant regardless it is reset or not.
     pub(crate) fn reset_throttling_params_if_needed(&self) -> tokio::time::Instant {
         let last_throttling_instant = *self.last_throttling_instant.read().unwrap();
@@ -222,6 +235,7 @@ impl ConnectionStreamCounter {
             {
                 *last_throttling_instant = tokio::time::Instant::now();
                 self.stream_count.store(0, Ordering::Relaxed);
+                self.bytes_count.store(0, Ordering::Relaxed);
             }
             *last_throttling_instant
         } else {
@@ -270,12 +284,55 @@ pub(crate) async fn throttle_stream(
     }
 }
 
+/// Converts a cap in bytes per second into the bytes allowed per throttling
+/// interval, allowing at least one byte so that the peer is never stalled
+pub(crate) fn max_bytes_per_throttling_interval(max_bytes_per_second: u64) -> u64 {
+    max_bytes_per_second
+        .saturating_mul(STREAM_THROTTLING_INTERVAL_MS)
+        .checked_div(1000)
+        .unwrap_or_default()
+        .max(1)
+}
+
+/// Delays the next stream of a peer that already sent
+/// `max_bytes_per_throttling_interval` bytes, over all its connections, in the
+/// current throttling interval.
+///
+/// Bytes are accounted once a stream finishes, so a peer may overshoot its
+/// budget by the size of the stream that crosses it.
+pub(crate) async fn throttle_stream_bytes(
+    stats: &StreamerStats,
+    peer_type: ConnectionPeerType,
+    remote_addr: std::net::SocketAddr,
+    stream_counter: &Arc<ConnectionStreamCounter>,
+    max_bytes_per_throttling_interval: u64,
+) {
+    let throttle_interval_start = stream_counter.reset_throttling_params_if_needed();
+    let bytes_read_in_throttle_interval = stream_counter.bytes_count.load(Ordering::Relaxed);
+    if bytes_read_in_throttle_interval >= max_bytes_per_throttling_interval {
+        let throttle_duration =
+            STREAM_THROTTLING_INTERVAL.saturating_sub(throttle_interval_start.elapsed());
+
+        if !throttle_duration.is_zero() {
+            debug!(
+                "Throttling stream from {remote_addr:?}, peer type: {peer_type:?}, \
+                 max_bytes_per_interval: {max_bytes_per_throttling_interval}, \
+                 read_interval_bytes: {bytes_read_in_throttle_interval} throttle_duration: \
+                 {throttle_duration:?}"
+            );
+            stats.throttled_bytes_peers.fetch_add(1, Ordering::Relaxed);
+            sleep(throttle_duration).await;
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use {
         super::*,
-        crate::quic::{
-            DEFAULT_MAX_STREAMS_PER_MS, DEFAULT_MAX_UNSTAKED_CONNECTIONS, StreamerStats,
+        crate::{
+            nonblocking::swqos::expected_stream_capacity_per_interval,
+            quic::{DEFAULT_MAX_STREAMS_PER_MS, DEFAULT_MAX_UNSTAKED_CONNECTIONS, StreamerStats},
         },
         std::sync::{Arc, atomic::Ordering},
     };
@@ -296,6 +353,54 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn test_max_bytes_per_throttling_interval() {
+        assert_eq!(max_bytes_per_throttling_interval(1_000_000), 100_000);
+        assert_eq!(max_bytes_per_throttling_interval(15), 1);
+        assert_eq!(max_bytes_per_throttling_interval(0), 1);
+        assert_eq!(max_bytes_per_throttling_interval(u64::MAX), u64::MAX / 1000);
+    }
+
+    #[tokio::test]
+    async fn test_throttle_stream_bytes() {
+        let stats = StreamerStats::default();
+        let stream_counter = Arc::new(ConnectionStreamCounter::new());
+        let remote_addr = "127.0.0.1:8000".parse().unwrap();
+
+        // Under the budget the stream goes through right away
+        stream_counter.bytes_count.store(999, Ordering::Relaxed);
+        let start = tokio::time::Instant::now();
+        throttle_stream_bytes(
+            &stats,
+            ConnectionPeerType::Unstaked,
+            remote_addr,
+            &stream_counter,
+            1_000,
+        )
+        .await;
+        assert!(start.elapsed() < STREAM_THROTTLING_INTERVAL);
+        assert_eq!(stats.throttled_bytes_peers.load(Ordering::Relaxed), 0);
+
+        // Once the budget is used up the stream waits for the next interval
+        stream_counter.bytes_count.store(1_000, Ordering::Relaxed);
+        let start = tokio::time::Instant::now();
+        throttle_stream_bytes(
+            &stats,
+            ConnectionPeerType::Unstaked,
+            remote_addr,
+            &stream_counter,
+            1_000,
+        )
+        .await;
+        assert!(start.elapsed() > Duration::ZERO);
+        assert_eq!(stats.throttled_bytes_peers.load(Ordering::Relaxed), 1);
+
+        // and the next interval starts with the budget available again
+        sleep(Duration::from_millis(1)).await;
+        stream_counter.reset_throttling_params_if_needed();
+        assert_eq!(stream_counter.bytes_count.load(Ordering::Relaxed), 0);
+    }
+
     #[test]
     fn test_staked_throttling_on_off() {
         let mut load_ema = StakedStreamLoadEMA::new(
@@ -414,4 +519,32 @@ pub mod test {
             load_ema.max_unstaked_load_in_throttling_window + 1
         );
     }
+
+    #[test]
+    fn test_staked_shares_add_up_to_expected_capacity() {
+        let load_ema = StakedStreamLoadEMA::new(
+            Arc::new(StreamerStats::default()),
+            DEFAULT_MAX_UNSTAKED_CONNECTIONS,
+            DEFAULT_MAX_STREAMS_PER_MS,
+        );
+        load_ema
+            .staked_throttling_enabled
+            .store(true, Ordering::Relaxed);
+
+        let stakes = [1_000, 3_000, 6_000];
+        let total_stake = stakes.iter().sum();
+        let total_capacity: u64 = stakes
+            .into_iter()
+            .map(|stake| {
+                load_ema.available_load_capacity_in_throttling_duration(
+                    ConnectionPeerType::Staked(stake),
+                    total_stake,
+                )
+            })
+            .sum();
+        assert_eq!(
+            total_capacity,
+            expected_stream_capacity_per_interval(total_stake, DEFAULT_MAX_STREAMS_PER_MS)
+        );
+    }
 }
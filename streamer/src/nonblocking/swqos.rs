@@ -5,15 +5,17 @@ use {
             quic::{
                 CONNECTION_CLOSE_CODE_DISALLOWED, CONNECTION_CLOSE_REASON_DISALLOWED,
                 ClientConnectionTracker, ConnectionHandlerError, ConnectionPeerType,
-                ConnectionTable, ConnectionTableKey, ConnectionTableType, get_connection_stake,
-                update_open_connections_stat,
+                ConnectionSummary, ConnectionTable, ConnectionTableKey, ConnectionTableType,
+                PruneRng, get_connection_stake, update_open_connections_stat,
             },
             stream_throttle::{
                 ConnectionStreamCounter, STREAM_THROTTLING_INTERVAL_MS, StakedStreamLoadEMA,
-                throttle_stream,
+                max_bytes_per_throttling_interval, max_staked_load_in_ms, throttle_stream,
+                throttle_stream_bytes,
             },
         },
         quic::{
+            DEFAULT_MAX_BYTES_PER_SECOND_PER_UNSTAKED_PEER,
             DEFAULT_MAX_QUIC_CONNECTIONS_PER_STAKED_PEER,
             DEFAULT_MAX_QUIC_CONNECTIONS_PER_UNSTAKED_PEER, DEFAULT_MAX_STAKED_CONNECTIONS,
             DEFAULT_MAX_STREAMS_PER_MS, DEFAULT_MAX_UNSTAKED_CONNECTIONS, StreamerStats,
@@ -21,15 +23,21 @@ use {
         streamer::StakedNodes,
     },
     quinn::{Connection, VarInt},
-    solana_time_utils as timing,
+    solana_pubkey::Pubkey,
+    solana_time_utils::{self as timing, AtomicInterval},
     std::{
+        collections::{BTreeSet, HashMap},
         future::Future,
         sync::{
             Arc, RwLock,
             atomic::{AtomicU64, Ordering},
         },
+        time::Duration,
+    },
+    tokio::{
+        sync::{Mutex, MutexGuard},
+        time::{MissedTickBehavior, interval},
     },
-    tokio::sync::{Mutex, MutexGuard},
     tokio_util::sync::CancellationToken,
 };
 
@@ -53,6 +61,14 @@ const REFERENCE_RTT_MS: u32 = 50;
 /// Above this RTT we stop scaling for BDP
 const MAX_RTT_MS: u32 = 350;
 
+/// How often the stakes of the connected peers exempt from throttling, or
+/// competing to be, are refreshed
+const TOP_STAKE_PEERS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Minimum time between two datapoints reporting a staked connection demoted
+/// to the unstaked table
+const STAKED_CONNECTION_DEMOTION_REPORT_INTERVAL_MS: u64 = 1_000;
+
 #[derive(Clone)]
 pub struct SwQosConfig {
     pub max_streams_per_ms: u64,
@@ -60,6 +76,24 @@ pub struct SwQosConfig {
     pub max_unstaked_connections: usize,
     pub max_connections_per_staked_peer: usize,
     pub max_connections_per_unstaked_peer: usize,
+    /// Whether to emit a datapoint, with the stake of the peer, for the
+    /// connections from staked peers demoted to the unstaked table as the
+    /// staked table is full, at most one every
+    /// STAKED_CONNECTION_DEMOTION_REPORT_INTERVAL_MS
+    pub report_staked_connection_demotions: bool,
+    /// Seed of the RNG picking the staked connections to prune, None to
+    /// draw from the thread local RNG
+    pub prune_rng_seed: Option<u64>,
+    /// Number of connected staked peers, the highest stakes first, whose
+    /// streams are never throttled
+    pub unthrottled_top_stake_peers: usize,
+    /// Bytes per second a peer may send over all its connections before its
+    /// streams are delayed, None for no cap. Applies to the peers exempt from
+    /// stream throttling too
+    pub max_bytes_per_second_per_peer: Option<u64>,
+    /// Bytes per second an unstaked peer may send when the cap above is set,
+    /// at most the cap itself
+    pub max_bytes_per_second_per_unstaked_peer: u64,
 }
 
 impl Default for SwQosConfig {
@@ -70,11 +104,23 @@ impl Default for SwQosConfig {
             max_unstaked_connections: DEFAULT_MAX_UNSTAKED_CONNECTIONS,
             max_connections_per_staked_peer: DEFAULT_MAX_QUIC_CONNECTIONS_PER_STAKED_PEER,
             max_connections_per_unstaked_peer: DEFAULT_MAX_QUIC_CONNECTIONS_PER_UNSTAKED_PEER,
+            report_staked_connection_demotions: false,
+            prune_rng_seed: None,
+            unthrottled_top_stake_peers: 0,
+            max_bytes_per_second_per_peer: None,
+            max_bytes_per_second_per_unstaked_peer: DEFAULT_MAX_BYTES_PER_SECOND_PER_UNSTAKED_PEER,
         }
     }
 }
 
 impl SwQosConfig {
+    /// Number of connections the staked and unstaked tables hold together,
+    /// without the headroom allowed for the handshakes
+    pub fn total_connection_capacity(&self) -> usize {
+        self.max_staked_connections
+            .saturating_add(self.max_unstaked_connections)
+    }
+
     #[cfg(feature = "dev-context-only-utils")]
     pub fn default_for_tests() -> Self {
         Self {
@@ -85,6 +131,146 @@ impl SwQosConfig {
     }
 }
 
+/// Returns the total number of streams the staked peers, whose stakes add up
+/// to `total_stake`, are expected to be allowed per throttling interval once
+/// the staked load saturates, with unstaked connections allowed.
+///
+/// Each staked peer is then allowed its stake weighted share of the staked
+/// load, so the total does not depend on how the stake is distributed. The
+/// rounding of the individual shares, and the minimum each peer is allowed,
+/// are not accounted for.
+pub fn expected_stream_capacity_per_interval(total_stake: u64, max_streams_per_ms: u64) -> u64 {
+    if total_stake == 0 {
+        return 0;
+    }
+    max_staked_load_in_ms(DEFAULT_MAX_UNSTAKED_CONNECTIONS, max_streams_per_ms)
+        .saturating_mul(STREAM_THROTTLING_INTERVAL_MS)
+}
+
+/// Tracks the staked peers with a connection open, to find the ones with the
+/// most stake whose streams are exempt from throttling.
+struct TopStakePeers {
+    max_peers: usize,
+    state: RwLock<TopStakePeersState>,
+}
+
+/// The connected staked peers, ranked by stake. The ties on stake are broken
+/// by pubkey so that exactly `max_peers` peers are picked, deterministically.
+#[derive(Default)]
+struct TopStakePeersState {
+    /// Stake and number of open connections of each connected staked peer
+    connected: HashMap<Pubkey, (u64, usize)>,
+    /// The `max_peers` connected peers with the most stake
+    top: BTreeSet<(u64, Pubkey)>,
+    /// The other connected peers with stake
+    rest: BTreeSet<(u64, Pubkey)>,
+}
+
+impl TopStakePeersState {
+    fn rank(&mut self, max_peers: usize, stake: u64, pubkey: Pubkey) {
+        if stake == 0 {
+            return;
+        }
+        self.top.insert((stake, pubkey));
+        if self.top.len() > max_peers {
+            let lowest = self.top.pop_first().unwrap();
+            self.rest.insert(lowest);
+        }
+    }
+
+    fn unrank(&mut self, stake: u64, pubkey: Pubkey) {
+        if self.top.remove(&(stake, pubkey)) {
+            if let Some(highest) = self.rest.pop_last() {
+                self.top.insert(highest);
+            }
+        } else {
+            self.rest.remove(&(stake, pubkey));
+        }
+    }
+
+    fn update_stake(&mut self, max_peers: usize, pubkey: Pubkey, stake: u64) {
+        let Some((peer_stake, _)) = self.connected.get_mut(&pubkey) else {
+            return;
+        };
+        let prev_stake = std::mem::replace(peer_stake, stake);
+        if prev_stake != stake {
+            self.unrank(prev_stake, pubkey);
+            self.rank(max_peers, stake, pubkey);
+        }
+    }
+}
+
+impl TopStakePeers {
+    fn new(max_peers: usize) -> Self {
+        Self {
+            max_peers,
+            state: RwLock::default(),
+        }
+    }
+
+    fn add_connection(&self, pubkey: Pubkey, stake: u64) {
+        if self.max_peers == 0 {
+            return;
+        }
+        let mut state = self.state.write().unwrap();
+        match state.connected.get_mut(&pubkey) {
+            Some((_, num_connections)) => {
+                *num_connections += 1;
+                state.update_stake(self.max_peers, pubkey, stake);
+            }
+            None => {
+                state.connected.insert(pubkey, (stake, 1));
+                state.rank(self.max_peers, stake, pubkey);
+            }
+        }
+    }
+
+    fn remove_connection(&self, pubkey: &Pubkey) {
+        if self.max_peers == 0 {
+            return;
+        }
+        let mut state = self.state.write().unwrap();
+        let Some((stake, num_connections)) = state.connected.get_mut(pubkey) else {
+            return;
+        };
+        *num_connections = num_connections.saturating_sub(1);
+        if *num_connections == 0 {
+            let stake = *stake;
+            state.connected.remove(pubkey);
+            state.unrank(stake, *pubkey);
+        }
+    }
+
+    /// Updates the stakes of the connected peers, which change at epoch
+    /// boundaries
+    fn update_stakes(&self, staked_nodes: &StakedNodes) {
+        let mut state = self.state.write().unwrap();
+        let updated_stakes: Vec<_> = state
+            .connected
+            .iter()
+            .filter_map(|(pubkey, (stake, _))| {
+                let updated_stake = staked_nodes.get_node_stake(pubkey).unwrap_or_default();
+                (updated_stake != *stake).then_some((*pubkey, updated_stake))
+            })
+            .collect();
+        for (pubkey, stake) in updated_stakes {
+            state.update_stake(self.max_peers, pubkey, stake);
+        }
+    }
+
+    fn contains(&self, pubkey: &Pubkey) -> bool {
+        if self.max_peers == 0 {
+            return false;
+        }
+        let state = self.state.read().unwrap();
+        state
+            .connected
+            .get(pubkey)
+            .is_some_and(|(stake, _)| state.top.contains(&(*stake, *pubkey)))
+    }
+}
+
+#[derive(Clone)]
 pub struct SwQos {
     config: SwQosConfig,
     staked_stream_load_ema: Arc<StakedStreamLoadEMA>,
@@ -92,6 +278,10 @@ pub struct SwQos {
     staked_nodes: Arc<RwLock<StakedNodes>>,
     unstaked_connection_table: Arc<Mutex<ConnectionTable<ConnectionStreamCounter>>>,
     staked_connection_table: Arc<Mutex<ConnectionTable<ConnectionStreamCounter>>>,
+    prune_rng: Arc<PruneRng>,
+    top_stake_peers: Arc<TopStakePeers>,
+    last_demotion_report: Arc<AtomicInterval>,
+    cancel: CancellationToken,
 }
 
 // QoS Params for Stake weighted QoS
@@ -138,10 +328,125 @@ impl SwQos {
             ))),
             staked_connection_table: Arc::new(Mutex::new(ConnectionTable::new(
                 ConnectionTableType::Staked,
-                cancel,
+                cancel.clone(),
             ))),
+            prune_rng: Arc::new(PruneRng::new(config.prune_rng_seed)),
+            top_stake_peers: Arc::new(TopStakePeers::new(config.unthrottled_top_stake_peers)),
+            last_demotion_report: Arc::default(),
+            cancel,
+        }
+    }
+
+    /// Whether to emit the datapoint of a demoted staked connection, rate
+    /// limited as the staked table stays full under a connection flood
+    fn should_report_staked_connection_demotion(&self) -> bool {
+        self.config.report_staked_connection_demotions
+            && self
+                .last_demotion_report
+                .should_update(STAKED_CONNECTION_DEMOTION_REPORT_INTERVAL_MS)
+    }
+
+    /// Returns a summary of every staked and unstaked connection.
+    pub async fn list_connections(&self) -> Vec<ConnectionSummary> {
+        let mut connections = self.staked_connection_table.lock().await.list_connections();
+        connections.extend(
+            self.unstaked_connection_table
+                .lock()
+                .await
+                .list_connections(),
+        );
+        self.set_current_stream_caps(&mut connections);
+        connections
+    }
+
+    /// Returns a summary of every staked and unstaked connection without a
+    /// finished stream for at least `threshold`.
+    pub async fn idle_connections(&self, threshold: Duration) -> Vec<ConnectionSummary> {
+        let mut connections = self
+            .staked_connection_table
+            .lock()
+            .await
+            .idle_connections(threshold);
+        connections.extend(
+            self.unstaked_connection_table
+                .lock()
+                .await
+                .idle_connections(threshold),
+        );
+        self.set_current_stream_caps(&mut connections);
+        connections
+    }
+
+    fn set_current_stream_caps(&self, connections: &mut [ConnectionSummary]) {
+        let total_stake = self.staked_nodes.read().unwrap().total_stake();
+        for connection in connections {
+            // Entries without a live connection open no streams
+            let Some(remote_address) = connection.remote_address else {
+                continue;
+            };
+            let stake = connection
+                .remote_pubkey
+                .filter(|_| connection.stake > 0)
+                .map(|pubkey| (pubkey, connection.stake, total_stake));
+            let conn_context = self.connection_context(remote_address, stake);
+            connection.current_stream_cap = self.current_stream_cap(&conn_context);
         }
     }
+
+    /// Returns the number of streams the connection may currently open per
+    /// throttling interval given the load EMA, or None if its streams are
+    /// exempt from throttling.
+    fn current_stream_cap(&self, conn_context: &SwQosConnectionContext) -> Option<u64> {
+        (!self.is_unthrottled(conn_context))
+            .then(|| self.max_streams_per_throttling_interval(conn_context))
+    }
+
+    /// Builds the context of a connection from `remote_address`, given the
+    /// pubkey, stake and total stake of a staked peer.
+    fn connection_context(
+        &self,
+        remote_address: std::net::SocketAddr,
+        stake: Option<(Pubkey, u64, u64)>,
+    ) -> SwQosConnectionContext {
+        stake.map_or(
+            SwQosConnectionContext {
+                peer_type: ConnectionPeerType::Unstaked,
+                total_stake: 0,
+                remote_pubkey: None,
+                in_staked_table: false,
+                remote_address,
+                stream_counter: None,
+                last_update: Arc::new(AtomicU64::new(timing::timestamp())),
+            },
+            |(pubkey, stake, total_stake)| {
+                // The heuristic is that the stake should be large enough to have 1 stream pass through within one throttle
+                // interval during which we allow max (MAX_STREAMS_PER_MS * STREAM_THROTTLING_INTERVAL_MS) streams.
+
+                let peer_type = {
+                    let max_streams_per_ms = self.staked_stream_load_ema.max_streams_per_ms();
+                    let min_stake_ratio =
+                        1_f64 / (max_streams_per_ms * STREAM_THROTTLING_INTERVAL_MS) as f64;
+                    let stake_ratio = stake as f64 / total_stake as f64;
+                    if stake_ratio < min_stake_ratio {
+                        // If it is a staked connection with ultra low stake ratio, treat it as unstaked.
+                        ConnectionPeerType::Unstaked
+                    } else {
+                        ConnectionPeerType::Staked(stake)
+                    }
+                };
+
+                SwQosConnectionContext {
+                    peer_type,
+                    total_stake,
+                    remote_pubkey: Some(pubkey),
+                    in_staked_table: false,
+                    remote_address,
+                    last_update: Arc::new(AtomicU64::new(timing::timestamp())),
+                    stream_counter: None,
+                }
+            },
+        )
+    }
 }
 
 fn compute_max_allowed_uni_streams_with_rtt(
@@ -178,6 +483,16 @@ fn compute_max_allowed_uni_streams_with_rtt(
     (streams.saturating_mul(rtt_millis.clamp(REFERENCE_RTT_MS, MAX_RTT_MS))) / REFERENCE_RTT_MS
 }
 
+/// Returns the most streams a connection of `peer_type` is ever allowed to
+/// have open at once
+pub(crate) fn max_allowed_uni_streams(peer_type: ConnectionPeerType) -> u32 {
+    let total_stake = match peer_type {
+        ConnectionPeerType::Staked(peer_stake) => peer_stake,
+        ConnectionPeerType::Unstaked => 0,
+    };
+    compute_max_allowed_uni_streams_with_rtt(MAX_RTT_MS, peer_type, total_stake)
+}
+
 impl SwQos {
     fn cache_new_connection(
         &self,
@@ -289,6 +604,34 @@ impl SwQos {
         }
     }
 
+    fn add_top_stake_peer_connection(&self, conn_context: &SwQosConnectionContext, stake: u64) {
+        if let Some(pubkey) = conn_context.remote_pubkey {
+            self.top_stake_peers.add_connection(pubkey, stake);
+        }
+    }
+
+    /// Returns whether the streams of the connection are exempt from
+    /// throttling, as the peer is among the connected peers with the most stake
+    fn is_unthrottled(&self, conn_context: &SwQosConnectionContext) -> bool {
+        matches!(conn_context.peer_type, ConnectionPeerType::Staked(_))
+            && conn_context
+                .remote_pubkey
+                .is_some_and(|pubkey| self.top_stake_peers.contains(&pubkey))
+    }
+
+    /// Returns the bytes per second the peer may send over all its
+    /// connections, or None if throughput is not capped
+    fn max_bytes_per_second(&self, peer_type: ConnectionPeerType) -> Option<u64> {
+        let max_bytes_per_second = self.config.max_bytes_per_second_per_peer?;
+        Some(match peer_type {
+            ConnectionPeerType::Unstaked => self
+                .config
+                .max_bytes_per_second_per_unstaked_peer
+                .min(max_bytes_per_second),
+            ConnectionPeerType::Staked(_) => max_bytes_per_second,
+        })
+    }
+
     fn max_streams_per_throttling_interval(&self, conn_context: &SwQosConnectionContext) -> u64 {
         self.staked_stream_load_ema
             .available_load_capacity_in_throttling_duration(
@@ -300,44 +643,9 @@ impl SwQos {
 
 impl QosController<SwQosConnectionContext> for SwQos {
     fn build_connection_context(&self, connection: &Connection) -> SwQosConnectionContext {
-        let remote_address = connection.remote_address();
-        get_connection_stake(connection, &self.staked_nodes).map_or(
-            SwQosConnectionContext {
-                peer_type: ConnectionPeerType::Unstaked,
-                total_stake: 0,
-                remote_pubkey: None,
-                in_staked_table: false,
-                remote_address,
-                stream_counter: None,
-                last_update: Arc::new(AtomicU64::new(timing::timestamp())),
-            },
-            |(pubkey, stake, total_stake)| {
-                // The heuristic is that the stake should be large enough to have 1 stream pass through within one throttle
-                // interval during which we allow max (MAX_STREAMS_PER_MS * STREAM_THROTTLING_INTERVAL_MS) streams.
-
-                let peer_type = {
-                    let max_streams_per_ms = self.staked_stream_load_ema.max_streams_per_ms();
-                    let min_stake_ratio =
-                        1_f64 / (max_streams_per_ms * STREAM_THROTTLING_INTERVAL_MS) as f64;
-                    let stake_ratio = stake as f64 / total_stake as f64;
-                    if stake_ratio < min_stake_ratio {
-                        // If it is a staked connection with ultra low stake ratio, treat it as unstaked.
-                        ConnectionPeerType::Unstaked
-                    } else {
-                        ConnectionPeerType::Staked(stake)
-                    }
-                };
-
-                SwQosConnectionContext {
-                    peer_type,
-                    total_stake,
-                    remote_pubkey: Some(pubkey),
-                    in_staked_table: false,
-                    remote_address,
-                    last_update: Arc::new(AtomicU64::new(timing::timestamp())),
-                    stream_counter: None,
-                }
-            },
+        self.connection_context(
+            connection.remote_address(),
+            get_connection_stake(connection, &self.staked_nodes),
         )
     }
 
@@ -356,8 +664,11 @@ impl QosController<SwQosConnectionContext> for SwQos {
                     let mut connection_table_l = self.staked_connection_table.lock().await;
 
                     if connection_table_l.total_size >= self.config.max_staked_connections {
-                        let num_pruned =
-                            connection_table_l.prune_random(PRUNE_RANDOM_SAMPLE_SIZE, stake);
+                        let num_pruned = self.prune_rng.prune_random(
+                            &mut connection_table_l,
+                            PRUNE_RANDOM_SAMPLE_SIZE,
+                            stake,
+                        );
                         self.stats
                             .num_evictions_staked
                             .fetch_add(num_pruned, Ordering::Relaxed);
@@ -379,6 +690,7 @@ impl QosController<SwQosConnectionContext> for SwQos {
                             conn_context.in_staked_table = true;
                             conn_context.last_update = last_update;
                             conn_context.stream_counter = Some(stream_counter);
+                            self.add_top_stake_peer_connection(conn_context, stake);
                             return Some(cancel_connection);
                         }
                     } else {
@@ -398,9 +710,27 @@ impl QosController<SwQosConnectionContext> for SwQos {
                             self.stats
                                 .connection_added_from_staked_peer
                                 .fetch_add(1, Ordering::Relaxed);
+                            self.stats
+                                .staked_connection_demoted
+                                .fetch_add(1, Ordering::Relaxed);
+                            if self.should_report_staked_connection_demotion() {
+                                datapoint_info!(
+                                    "quic_staked_connection_demoted",
+                                    (
+                                        "pubkey",
+                                        conn_context
+                                            .remote_pubkey()
+                                            .map(|pubkey| pubkey.to_string()),
+                                        Option<String>
+                                    ),
+                                    ("stake", stake, i64),
+                                    ("total_stake", conn_context.total_stake, i64),
+                                );
+                            }
                             conn_context.in_staked_table = false;
                             conn_context.last_update = last_update;
                             conn_context.stream_counter = Some(stream_counter);
+                            self.add_top_stake_peer_connection(conn_context, stake);
                             return Some(cancel_connection);
                         } else {
                             self.stats
@@ -483,14 +813,29 @@ impl QosController<SwQosConnectionContext> for SwQos {
                 stable_id,
             );
             update_open_connections_stat(&self.stats, &lock);
+            drop(lock);
+
+            if let (ConnectionPeerType::Staked(_), Some(pubkey)) =
+                (conn_context.peer_type, conn_context.remote_pubkey)
+            {
+                self.top_stake_peers.remove_connection(&pubkey);
+            }
             removed_count
         }
     }
 
-    fn on_stream_finished(&self, context: &SwQosConnectionContext) {
+    fn on_stream_finished(&self, context: &SwQosConnectionContext, stream_bytes: usize) {
         context
             .last_update
             .store(timing::timestamp(), Ordering::Relaxed);
+        if self.config.max_bytes_per_second_per_peer.is_some() {
+            context
+                .stream_counter
+                .as_ref()
+                .unwrap()
+                .bytes_count
+                .fetch_add(stream_bytes as u64, Ordering::Relaxed);
+        }
     }
 
     #[allow(clippy::manual_async_fn)]
@@ -500,9 +845,19 @@ impl QosController<SwQosConnectionContext> for SwQos {
             let remote_addr = context.remote_address;
             let stream_counter: &Arc<ConnectionStreamCounter> =
                 context.stream_counter.as_ref().unwrap();
-
-            let max_streams_per_throttling_interval =
-                self.max_streams_per_throttling_interval(context);
+            if let Some(max_bytes_per_second) = self.max_bytes_per_second(peer_type) {
+                throttle_stream_bytes(
+                    &self.stats,
+                    peer_type,
+                    remote_addr,
+                    stream_counter,
+                    max_bytes_per_throttling_interval(max_bytes_per_second),
+                )
+                .await;
+            }
+            let Some(max_streams_per_throttling_interval) = self.current_stream_cap(context) else {
+                return;
+            };
 
             throttle_stream(
                 &self.stats,
@@ -515,21 +870,79 @@ impl QosController<SwQosConnectionContext> for SwQos {
         }
     }
 
+    fn spawn_background_tasks(&mut self) {
+        if self.config.unthrottled_top_stake_peers == 0 {
+            return;
+        }
+        let top_stake_peers = self.top_stake_peers.clone();
+        let staked_nodes = self.staked_nodes.clone();
+        let cancel = self.cancel.clone();
+        let _refresh_task = tokio::spawn(async move {
+            let mut refresh_interval = interval(TOP_STAKE_PEERS_REFRESH_INTERVAL);
+            refresh_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = refresh_interval.tick() => {
+                        top_stake_peers.update_stakes(&staked_nodes.read().unwrap());
+                    }
+                }
+            }
+        });
+    }
+
     fn max_concurrent_connections(&self) -> usize {
         // Allow 25% more connections than required to allow for handshake
-
-        (self.config.max_staked_connections + self.config.max_unstaked_connections) * 5 / 4
+        self.config.total_connection_capacity() * 5 / 4
     }
 }
 
 #[cfg(test)]
 pub mod test {
-    use super::*;
+    use {
+        super::*,
+        crate::{
+            nonblocking::{
+                quic::spawn_server,
+                testing_utilities::{create_quic_server_sockets, make_client_endpoint},
+            },
+            quic::QuicStreamerConfig,
+        },
+        crossbeam_channel::unbounded,
+        solana_keypair::Keypair,
+        std::{
+            net::SocketAddr,
+            time::{Duration, Instant},
+        },
+    };
 
     fn compute_max_allowed_uni_streams(peer_type: ConnectionPeerType, total_stake: u64) -> u32 {
         compute_max_allowed_uni_streams_with_rtt(REFERENCE_RTT_MS, peer_type, total_stake)
     }
 
+    #[test]
+    fn test_total_connection_capacity() {
+        let config = SwQosConfig::default();
+        assert_eq!(
+            config.total_connection_capacity(),
+            DEFAULT_MAX_STAKED_CONNECTIONS + DEFAULT_MAX_UNSTAKED_CONNECTIONS
+        );
+        let config = SwQosConfig {
+            max_staked_connections: 10,
+            max_unstaked_connections: 0,
+            ..SwQosConfig::default()
+        };
+        assert_eq!(config.total_connection_capacity(), 10);
+        let qos = SwQos::new(
+            config,
+            Arc::new(StreamerStats::default()),
+            Arc::new(RwLock::new(StakedNodes::default())),
+            CancellationToken::new(),
+        );
+        // Headroom is allowed on top for the handshakes
+        assert_eq!(qos.max_concurrent_connections(), 12);
+    }
+
     #[test]
     fn test_max_allowed_uni_streams() {
         assert_eq!(
@@ -557,6 +970,31 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn test_expected_stream_capacity_per_interval() {
+        // No staked peers to allow any streams to
+        assert_eq!(
+            expected_stream_capacity_per_interval(0, DEFAULT_MAX_STREAMS_PER_MS),
+            0
+        );
+        // 80% of the streams are left to the staked peers
+        assert_eq!(
+            expected_stream_capacity_per_interval(10_000, DEFAULT_MAX_STREAMS_PER_MS),
+            40_000
+        );
+        assert_eq!(expected_stream_capacity_per_interval(10_000, 10), 800);
+        // The share of unstaked peers rounds down to nothing
+        assert_eq!(expected_stream_capacity_per_interval(10_000, 1), 100);
+        // The total does not depend on the amount of stake
+        assert_eq!(
+            expected_stream_capacity_per_interval(u64::MAX, DEFAULT_MAX_STREAMS_PER_MS),
+            40_000
+        );
+        assert_eq!(expected_stream_capacity_per_interval(1, 0), 0);
+        // No overflow at the extreme
+        assert_eq!(expected_stream_capacity_per_interval(1, u64::MAX), u64::MAX);
+    }
+
     #[test]
     fn test_max_allowed_uni_streams_with_rtt() {
         assert_eq!(
@@ -578,4 +1016,258 @@ pub mod test {
             "Max streams should scale with BDP in high-RTT connections"
         );
     }
+
+    #[tokio::test]
+    async fn test_unthrottled_top_stake_peers() {
+        let stats = Arc::<StreamerStats>::default();
+        let qos = SwQos::new(
+            SwQosConfig {
+                unthrottled_top_stake_peers: 1,
+                ..SwQosConfig::default()
+            },
+            stats.clone(),
+            Arc::default(),
+            CancellationToken::new(),
+        );
+        let new_context = |stake| {
+            let context = SwQosConnectionContext {
+                peer_type: ConnectionPeerType::Staked(stake),
+                remote_pubkey: Some(Pubkey::new_unique()),
+                total_stake: 1_000,
+                in_staked_table: true,
+                last_update: Arc::default(),
+                remote_address: "127.0.0.1:8000".parse().unwrap(),
+                stream_counter: Some(Arc::new(ConnectionStreamCounter::new())),
+            };
+            qos.add_top_stake_peer_connection(&context, stake);
+            // Over the allowance of the throttling interval already
+            context
+                .stream_counter
+                .as_ref()
+                .unwrap()
+                .stream_count
+                .store(u64::MAX, Ordering::Relaxed);
+            context
+        };
+        let top = new_context(900);
+        let other = new_context(100);
+
+        qos.on_new_stream(&top).await;
+        assert_eq!(stats.throttled_staked_streams.load(Ordering::Relaxed), 0);
+        qos.on_new_stream(&other).await;
+        assert_eq!(stats.throttled_staked_streams.load(Ordering::Relaxed), 1);
+
+        // Once the top peer disconnects, the other peer has the most stake
+        qos.top_stake_peers
+            .remove_connection(&top.remote_pubkey.unwrap());
+        assert!(qos.is_unthrottled(&other));
+        qos.on_new_stream(&other).await;
+        assert_eq!(stats.throttled_staked_streams.load(Ordering::Relaxed), 1);
+
+        // Unstaked peers are always subject to throttling
+        let unstaked = SwQosConnectionContext {
+            peer_type: ConnectionPeerType::Unstaked,
+            ..other.clone()
+        };
+        assert!(!qos.is_unthrottled(&unstaked));
+    }
+
+    #[tokio::test]
+    async fn test_should_report_staked_connection_demotion() {
+        let new_qos = |report_staked_connection_demotions| {
+            SwQos::new(
+                SwQosConfig {
+                    report_staked_connection_demotions,
+                    ..SwQosConfig::default()
+                },
+                Arc::default(),
+                Arc::default(),
+                CancellationToken::new(),
+            )
+        };
+        assert!(!new_qos(false).should_report_staked_connection_demotion());
+        let qos = new_qos(true);
+        assert!(qos.should_report_staked_connection_demotion());
+        // Throttled until the report interval elapses
+        assert!(!qos.should_report_staked_connection_demotion());
+        assert!(!qos.clone().should_report_staked_connection_demotion());
+    }
+
+    #[test]
+    fn test_top_stake_peers() {
+        let top_stake_peers = TopStakePeers::new(2);
+        let [a, b, c] = std::array::from_fn(|_| Pubkey::new_unique());
+        top_stake_peers.add_connection(a, 300);
+        top_stake_peers.add_connection(b, 200);
+        top_stake_peers.add_connection(c, 100);
+        assert!(top_stake_peers.contains(&a));
+        assert!(top_stake_peers.contains(&b));
+        assert!(!top_stake_peers.contains(&c));
+
+        // Still ranked while any connection of the peer is open
+        top_stake_peers.add_connection(a, 300);
+        top_stake_peers.remove_connection(&a);
+        assert!(top_stake_peers.contains(&a));
+        top_stake_peers.remove_connection(&a);
+        assert!(!top_stake_peers.contains(&a));
+        assert!(top_stake_peers.contains(&b));
+        assert!(top_stake_peers.contains(&c));
+
+        // Peers without stake are never ranked
+        top_stake_peers.add_connection(a, 0);
+        assert!(!top_stake_peers.contains(&a));
+
+        // The stakes of the connected peers are refreshed
+        let staked_nodes = StakedNodes::new(
+            Arc::new(HashMap::from([(a, 300), (b, 200)])),
+            HashMap::new(),
+        );
+        top_stake_peers.update_stakes(&staked_nodes);
+        assert!(top_stake_peers.contains(&a));
+        assert!(top_stake_peers.contains(&b));
+        assert!(!top_stake_peers.contains(&c));
+    }
+
+    #[test]
+    fn test_max_bytes_per_second() {
+        let new_qos = |config| {
+            SwQos::new(
+                config,
+                Arc::<StreamerStats>::default(),
+                Arc::default(),
+                CancellationToken::new(),
+            )
+        };
+
+        // Not capped by default
+        let qos = new_qos(SwQosConfig::default());
+        assert_eq!(
+            qos.max_bytes_per_second(ConnectionPeerType::Staked(10)),
+            None
+        );
+        assert_eq!(qos.max_bytes_per_second(ConnectionPeerType::Unstaked), None);
+
+        // Unstaked peers get the lower cap
+        let qos = new_qos(SwQosConfig {
+            max_bytes_per_second_per_peer: Some(1_000_000),
+            ..SwQosConfig::default()
+        });
+        assert_eq!(
+            qos.max_bytes_per_second(ConnectionPeerType::Staked(10)),
+            Some(1_000_000)
+        );
+        assert_eq!(
+            qos.max_bytes_per_second(ConnectionPeerType::Unstaked),
+            Some(DEFAULT_MAX_BYTES_PER_SECOND_PER_UNSTAKED_PEER)
+        );
+
+        // but never more than staked peers
+        let qos = new_qos(SwQosConfig {
+            max_bytes_per_second_per_peer: Some(1_000),
+            ..SwQosConfig::default()
+        });
+        assert_eq!(
+            qos.max_bytes_per_second(ConnectionPeerType::Unstaked),
+            Some(1_000)
+        );
+    }
+
+    #[test]
+    fn test_current_stream_cap() {
+        let qos = SwQos::new(
+            SwQosConfig {
+                // 100 streams per throttling interval shared by staked peers,
+                // 20 for each unstaked one
+                max_streams_per_ms: 1,
+                unthrottled_top_stake_peers: 1,
+                ..SwQosConfig::default()
+            },
+            Arc::<StreamerStats>::default(),
+            Arc::default(),
+            CancellationToken::new(),
+        );
+        let peer = Pubkey::new_unique();
+        let remote_address = SocketAddr::from(([127, 0, 0, 1], 8000));
+        let stream_cap = |stake| {
+            let stake = (stake > 0).then_some((peer, stake, 100));
+            qos.current_stream_cap(&qos.connection_context(remote_address, stake))
+        };
+
+        // Below the load threshold staked peers get the whole staked capacity
+        assert_eq!(stream_cap(10), Some(100));
+        assert_eq!(stream_cap(0), Some(20));
+
+        // Sustained load turns throttling on, sharing the capacity by stake
+        for _ in 0..10_000 {
+            qos.staked_stream_load_ema
+                .increment_load(ConnectionPeerType::Staked(10));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        qos.staked_stream_load_ema.update_ema_if_needed();
+        assert_eq!(stream_cap(50), Some(50));
+        assert_eq!(stream_cap(10), Some(21));
+        assert_eq!(stream_cap(0), Some(20));
+
+        // Peers exempt from throttling have no cap
+        qos.top_stake_peers.add_connection(peer, 50);
+        assert_eq!(stream_cap(50), None);
+        assert_eq!(stream_cap(0), Some(20));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_list_connections_lifetime_traffic() {
+        agave_logger::setup();
+        let sockets = create_quic_server_sockets();
+        let server_address = sockets[0].local_addr().unwrap();
+        let (sender, receiver) = unbounded();
+        let stats = Arc::<StreamerStats>::default();
+        let cancel = CancellationToken::new();
+        let swqos = SwQos::new(
+            SwQosConfig::default_for_tests(),
+            stats.clone(),
+            Arc::default(),
+            cancel.clone(),
+        );
+        let (server, _key_updater) = spawn_server(
+            "quic_streamer_test",
+            stats,
+            sockets,
+            &Keypair::new(),
+            sender,
+            QuicStreamerConfig::default_for_tests(),
+            swqos.clone(),
+            cancel.clone(),
+        )
+        .unwrap();
+        assert!(swqos.list_connections().await.is_empty());
+
+        let connection = make_client_endpoint(&server_address, None).await;
+        let payload_sizes = [10, 100, 1000];
+        for size in payload_sizes {
+            let mut stream = connection.open_uni().await.unwrap();
+            stream.write_all(&vec![42u8; size]).await.unwrap();
+            stream.finish().unwrap();
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+
+        // The counters are bumped right after the packet is handed off
+        let start = Instant::now();
+        let summary = loop {
+            let connections = swqos.list_connections().await;
+            assert_eq!(connections.len(), 1);
+            if connections[0].lifetime_streams == payload_sizes.len() as u64
+                || start.elapsed() > Duration::from_secs(5)
+            {
+                break connections[0].clone();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(summary.stake, 0);
+        assert!(summary.remote_address.is_some());
+        assert_eq!(summary.lifetime_streams, 3);
+        assert_eq!(summary.lifetime_bytes, 1110);
+
+        cancel.cancel();
+        server.thread.await.unwrap();
+    }
 }
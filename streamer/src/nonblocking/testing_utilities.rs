@@ -63,9 +63,14 @@ where
         swqos,
         cancel,
     )
+    .map(|(result, _key_updater)| result)
 }
 
 pub fn get_client_config(keypair: &Keypair) -> ClientConfig {
+    get_client_config_with_alpn(keypair, ALPN_TPU_PROTOCOL_ID)
+}
+
+pub fn get_client_config_with_alpn(keypair: &Keypair, alpn_protocol_id: &[u8]) -> ClientConfig {
     let (cert, key) = new_dummy_x509_certificate(keypair);
 
     let mut crypto = tls_client_config_builder()
@@ -73,7 +78,7 @@ pub fn get_client_config(keypair: &Keypair) -> ClientConfig {
         .expect("Failed to use client certificate");
 
     crypto.enable_early_data = true;
-    crypto.alpn_protocols = vec![ALPN_TPU_PROTOCOL_ID.to_vec()];
+    crypto.alpn_protocols = vec![alpn_protocol_id.to_vec()];
 
     let mut config = ClientConfig::new(Arc::new(QuicClientConfig::try_from(crypto).unwrap()));
 
@@ -1,10 +1,15 @@
 use {
     crate::{
         nonblocking::{
+            endpoint_supervisor::ServerEndpoints,
+            memory_budget::TpuIngestMemoryBudget,
             qos::{ConnectionContext, QosController},
-            quic::{ALPN_TPU_PROTOCOL_ID, DEFAULT_WAIT_FOR_CHUNK_TIMEOUT},
+            quic::{
+                ALPN_TPU_PROTOCOL_ID, ConnectionPeerType, ConnectionSummary, ConnectionTable,
+                DEFAULT_WAIT_FOR_CHUNK_TIMEOUT,
+            },
             simple_qos::{SimpleQos, SimpleQosBanlist, SimpleQosConfig},
-            swqos::{SwQos, SwQosConfig},
+            swqos::{SwQos, SwQosConfig, max_allowed_uni_streams},
         },
         quic_socket::QuicSocket,
         streamer::StakedNodes,
@@ -17,19 +22,27 @@ use {
     },
     rustls::KeyLogFile,
     solana_keypair::Keypair,
+    solana_net_utils::{
+        sockets::{SocketConfiguration, bind_one_more_with_config},
+        token_bucket::TokenBucket,
+    },
     solana_packet::PACKET_DATA_SIZE,
     solana_perf::packet::PacketBatch,
+    solana_pubkey::Pubkey,
     solana_tls_utils::{NotifyKeyUpdate, new_dummy_x509_certificate, tls_server_config_builder},
     std::{
+        collections::{HashSet, VecDeque},
+        io,
+        net::{IpAddr, SocketAddr, UdpSocket},
         num::NonZeroUsize,
         sync::{
-            Arc, RwLock,
-            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex, RwLock,
+            atomic::{AtomicBool, AtomicUsize, Ordering},
         },
         thread::{self},
         time::Duration,
     },
-    tokio::runtime::Runtime,
+    tokio::runtime::{Handle, Runtime},
     tokio_util::sync::CancellationToken,
 };
 
@@ -50,14 +63,47 @@ pub const DEFAULT_MAX_UNSTAKED_CONNECTIONS: usize = 2000;
 /// Limit to 500K PPS
 pub const DEFAULT_MAX_STREAMS_PER_MS: u64 = 500;
 
+/// Bytes per second an unstaked peer may send once the per peer throughput
+/// cap is enabled, about 200 full sized packets per second
+pub const DEFAULT_MAX_BYTES_PER_SECOND_PER_UNSTAKED_PEER: u64 = 250_000;
+
 /// The new connections per minute from a particular IP address.
 /// Heuristically set to the default maximum concurrent connections
 /// per IP address. Might be adjusted later.
 pub const DEFAULT_MAX_CONNECTIONS_PER_IPADDR_PER_MINUTE: u64 = 8;
 
+/// The number of IP addresses tracked by the connection rate limiter above
+/// which the entries used by past connections are cleaned up.
+pub const DEFAULT_RATE_LIMITER_CLEANUP_THRESHOLD: usize = 100_000;
+
 // This will be adjusted and parameterized in follow-on PRs.
 pub const DEFAULT_QUIC_ENDPOINTS: usize = 1;
 
+/// Time an endpoint goes without incoming connections while its socket fails
+/// to send before it is considered dead.
+pub const DEFAULT_ENDPOINT_DEAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts to bind and create an endpoint made after the first one failed,
+/// e.g. as the address is still held by the process being restarted.
+pub const DEFAULT_ENDPOINT_BIND_RETRIES: usize = 3;
+
+/// Delay before the first retry to create an endpoint, doubled after each
+/// retry.
+pub const DEFAULT_ENDPOINT_BIND_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Binds a fresh socket on the given address for an endpoint whose socket
+/// died. The socket it replaces may still hold the address.
+pub type EndpointRebinder = dyn Fn(SocketAddr) -> io::Result<UdpSocket> + Send + Sync;
+
+/// Rebinder for endpoints whose sockets were bound with
+/// `bind_more_with_config`, binding alongside the sockets still bound to the
+/// address.
+pub fn reuseport_endpoint_rebinder(config: SocketConfiguration) -> Arc<EndpointRebinder> {
+    Arc::new(move |address: SocketAddr| {
+        bind_one_more_with_config(address.ip(), address.port(), config)
+    })
+}
+
 /// Allow for 8 MB QUIC connection receive window (MAX_DATA). This is sufficient to
 /// support 200 Mbps upload rate at 320 ms RTT. It is unreasonable to expect a single
 /// connection to require more bandwidth. This prevents MAX_DATA from affecting
@@ -79,7 +125,7 @@ pub fn default_num_tpu_vote_transaction_receive_threads() -> usize {
 }
 
 pub struct SpawnServerResult {
-    pub endpoints: Vec<Endpoint>,
+    pub endpoints: QuicServerEndpoints,
     pub thread: thread::JoinHandle<()>,
     pub key_updater: Arc<EndpointKeyUpdater>,
 }
@@ -99,7 +145,12 @@ pub(crate) fn configure_server(
 
     let mut server_tls_config =
         tls_server_config_builder().with_single_cert(vec![cert], priv_key)?;
-    server_tls_config.alpn_protocols = vec![ALPN_TPU_PROTOCOL_ID.to_vec()];
+    server_tls_config.alpn_protocols = vec![
+        quic_server_params
+            .alpn_protocol_id
+            .clone()
+            .unwrap_or_else(|| ALPN_TPU_PROTOCOL_ID.to_vec()),
+    ];
     server_tls_config.key_log = Arc::new(KeyLogFile::new());
     let quic_server_config = QuicServerConfig::try_from(server_tls_config)?;
 
@@ -151,21 +202,68 @@ pub enum QuicServerError {
     NoInitialCipherSuite(#[from] NoInitialCipherSuite),
 }
 
+/// The endpoints of a server, reflecting the endpoints the server replaced
+/// after their socket died
+#[derive(Clone)]
+pub struct QuicServerEndpoints(pub(crate) Arc<RwLock<ServerEndpoints>>);
+
+impl QuicServerEndpoints {
+    /// The current endpoints of the server
+    pub fn get(&self) -> Vec<Endpoint> {
+        self.0.read().unwrap().endpoints()
+    }
+}
+
 pub struct EndpointKeyUpdater {
-    endpoints: Vec<Endpoint>,
-    quic_server_params: QuicStreamerConfig,
+    pub(crate) endpoints: Arc<RwLock<ServerEndpoints>>,
+    pub(crate) quic_server_params: QuicStreamerConfig,
 }
 
 impl NotifyKeyUpdate for EndpointKeyUpdater {
     fn update_key(&self, key: &Keypair) -> Result<(), Box<dyn std::error::Error>> {
         let (config, _) = configure_server(key, &self.quic_server_params)?;
-        for endpoint in &self.endpoints {
-            endpoint.set_server_config(Some(config.clone()));
-        }
+        self.endpoints.write().unwrap().set_server_config(config);
         Ok(())
     }
 }
 
+/// Chunks received from staked and unstaked peers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChunkCounts {
+    pub staked: usize,
+    pub unstaked: usize,
+    pub total: usize,
+}
+
+impl ChunkCounts {
+    fn new(staked: usize, unstaked: usize) -> Self {
+        Self {
+            staked,
+            unstaked,
+            total: staked.saturating_add(unstaked),
+        }
+    }
+
+    /// Fraction of the chunks received from staked peers, zero if no chunks
+    /// were received
+    pub fn staked_ratio(&self) -> f64 {
+        self.staked as f64 / self.total.max(1) as f64
+    }
+}
+
+/// Number of the most recent streams rejected for their size kept around
+const MAX_RECENT_INVALID_STREAM_SIZES: usize = 64;
+
+/// A stream rejected as it exceeded the maximum stream data bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidStreamSize {
+    /// Milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    pub ip: IpAddr,
+    /// Bytes received on the stream when it was rejected
+    pub size: usize,
+}
+
 #[derive(Default)]
 pub struct StreamerStats {
     pub(crate) total_connections: AtomicUsize,
@@ -173,6 +271,8 @@ pub struct StreamerStats {
     pub(crate) active_streams: AtomicUsize,
     pub(crate) total_new_streams: AtomicUsize,
     pub(crate) invalid_stream_size: AtomicUsize,
+    // The most recent streams rejected for their size, oldest first
+    recent_invalid_stream_sizes: Mutex<VecDeque<InvalidStreamSize>>,
     pub(crate) total_staked_chunks_received: AtomicUsize,
     pub(crate) total_unstaked_chunks_received: AtomicUsize,
     pub(crate) total_handle_chunk_to_packet_send_err: AtomicUsize,
@@ -181,6 +281,10 @@ pub struct StreamerStats {
     pub(crate) total_packet_batches_none: AtomicUsize,
     pub(crate) total_packets_sent_to_consumer: AtomicUsize,
     pub(crate) total_bytes_sent_to_consumer: AtomicUsize,
+    // Packets reassembled from a single chunk, which need no copy, or from
+    // multiple chunks
+    pub(crate) total_single_chunk_packets: AtomicUsize,
+    pub(crate) total_multi_chunk_packets: AtomicUsize,
     pub(crate) total_chunks_processed_by_batcher: AtomicUsize,
     pub(crate) total_stream_read_errors: AtomicUsize,
     pub(crate) total_stream_read_timeouts: AtomicUsize,
@@ -188,6 +292,9 @@ pub struct StreamerStats {
     pub(crate) num_evictions_unstaked: AtomicUsize,
     pub(crate) connection_added_from_staked_peer: AtomicUsize,
     pub(crate) connection_added_from_unstaked_peer: AtomicUsize,
+    // Connections from staked peers added to the unstaked table as the staked
+    // table was full
+    pub(crate) staked_connection_demoted: AtomicUsize,
     pub(crate) connection_add_failed: AtomicUsize,
     pub(crate) connection_add_failed_staked_node: AtomicUsize,
     pub(crate) connection_add_failed_unstaked_node: AtomicUsize,
@@ -218,10 +325,17 @@ pub struct StreamerStats {
     pub(crate) total_unstaked_packets_sent_for_batching: AtomicUsize,
     pub(crate) throttled_staked_streams: AtomicUsize,
     pub(crate) throttled_unstaked_streams: AtomicUsize,
+    /// Number of streams delayed as the peer went over its cap of bytes per
+    /// second
+    pub(crate) throttled_bytes_peers: AtomicUsize,
     /// number of streams that got delayed beyond reasonable fragmentation delays
     pub(crate) reassembly_delayed_streams: AtomicUsize,
     /// total delay accumulated by delayed streams, in microseconds
     pub(crate) reassembly_delayed_streams_cumulative_delay_us: AtomicUsize,
+    /// Number of packets marked for performance tracking reassembled
+    pub(crate) tracked_packets: AtomicUsize,
+    /// Total time the tracked packets took to reassemble, in microseconds
+    pub(crate) tracked_packets_cumulative_reassembly_us: AtomicUsize,
     // All connections in various states such as Incoming, Connecting, Connection
     pub(crate) open_connections: AtomicUsize,
     pub(crate) open_staked_connections: AtomicUsize,
@@ -229,13 +343,85 @@ pub struct StreamerStats {
     pub(crate) peak_open_staked_connections: AtomicUsize,
     pub(crate) peak_open_unstaked_connections: AtomicUsize,
     pub(crate) refused_connections_too_many_open_connections: AtomicUsize,
+    pub(crate) refused_handshake_overload: AtomicUsize,
+    pub(crate) refused_connections_not_ready: AtomicUsize,
+    // Connections and streams shed to stay within the shared memory budget
+    pub(crate) memory_budget_shed_connections: AtomicUsize,
+    pub(crate) memory_budget_shed_streams: AtomicUsize,
+    // Gauge of the memory this server charged to the shared memory budget
+    pub(crate) memory_budget_used_bytes: AtomicUsize,
     pub(crate) outstanding_incoming_connection_attempts: AtomicUsize,
     pub(crate) total_incoming_connection_attempts: AtomicUsize,
     pub(crate) quic_endpoints_count: AtomicUsize,
+    // Dead endpoints given a fresh socket, the attempts that failed and the
+    // connections open on the dead endpoints
+    pub(crate) quic_endpoint_rebinds: AtomicUsize,
+    pub(crate) quic_endpoint_rebind_failures: AtomicUsize,
+    pub(crate) quic_endpoint_lost_connections: AtomicUsize,
+    // Gauge of the dead endpoints that repeatedly failed to rebind
+    pub(crate) quic_endpoints_degraded: AtomicUsize,
+    // Failed attempts to create an endpoint when starting the server, retried
+    pub(crate) quic_endpoint_bind_retries: AtomicUsize,
+    // Gauges sampled from the per-IP connection rate limiter.
+    pub(crate) connection_rate_limiter_entries: AtomicUsize,
+    pub(crate) connection_rate_limiter_evictions: AtomicUsize,
+    pub(crate) connection_rate_limiter_shrink_time_us: AtomicUsize,
 }
 
 impl StreamerStats {
+    /// Returns the chunks received since the last report
+    pub fn chunk_counts(&self) -> ChunkCounts {
+        ChunkCounts::new(
+            self.total_staked_chunks_received.load(Ordering::Relaxed),
+            self.total_unstaked_chunks_received.load(Ordering::Relaxed),
+        )
+    }
+
+    pub(crate) fn record_invalid_stream_size(&self, ip: IpAddr, size: usize) {
+        self.invalid_stream_size.fetch_add(1, Ordering::Relaxed);
+        let mut recent_invalid_stream_sizes = self.recent_invalid_stream_sizes.lock().unwrap();
+        if recent_invalid_stream_sizes.len() == MAX_RECENT_INVALID_STREAM_SIZES {
+            recent_invalid_stream_sizes.pop_front();
+        }
+        recent_invalid_stream_sizes.push_back(InvalidStreamSize {
+            timestamp: solana_time_utils::timestamp(),
+            ip,
+            size,
+        });
+    }
+
+    /// Returns the number of streams rejected for their size since the last
+    /// report
+    pub fn invalid_stream_size_count(&self) -> usize {
+        self.invalid_stream_size.load(Ordering::Relaxed)
+    }
+
+    /// Returns the most recent streams rejected for their size, oldest first
+    pub fn recent_invalid_stream_sizes(&self) -> Vec<InvalidStreamSize> {
+        self.recent_invalid_stream_sizes
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Returns true if some endpoint of the server is dead and repeatedly
+    /// failed to rebind
+    pub fn is_degraded(&self) -> bool {
+        self.quic_endpoints_degraded.load(Ordering::Relaxed) > 0
+    }
+
     pub fn report(&self, name: &'static str) {
+        let chunk_counts = ChunkCounts::new(
+            self.total_staked_chunks_received.swap(0, Ordering::Relaxed),
+            self.total_unstaked_chunks_received
+                .swap(0, Ordering::Relaxed),
+        );
+        let single_chunk_packets = self.total_single_chunk_packets.swap(0, Ordering::Relaxed);
+        let multi_chunk_packets = self.total_multi_chunk_packets.swap(0, Ordering::Relaxed);
+        let single_chunk_packets_ratio = single_chunk_packets as f64
+            / (single_chunk_packets + multi_chunk_packets).max(1) as f64;
         datapoint_info!(
             name,
             (
@@ -280,6 +466,11 @@ impl StreamerStats {
                     .swap(0, Ordering::Relaxed),
                 i64
             ),
+            (
+                "staked_connection_demoted",
+                self.staked_connection_demoted.swap(0, Ordering::Relaxed),
+                i64
+            ),
             (
                 "connection_add_failed",
                 self.connection_add_failed.swap(0, Ordering::Relaxed),
@@ -408,22 +599,26 @@ impl StreamerStats {
                 self.total_bytes_sent_to_consumer.swap(0, Ordering::Relaxed),
                 i64
             ),
+            ("single_chunk_packets", single_chunk_packets, i64),
+            ("multi_chunk_packets", multi_chunk_packets, i64),
+            (
+                "single_chunk_packets_ratio",
+                single_chunk_packets_ratio,
+                f64
+            ),
             (
                 "chunks_processed_by_batcher",
                 self.total_chunks_processed_by_batcher
                     .swap(0, Ordering::Relaxed),
                 i64
             ),
+            ("staked_chunks_received", chunk_counts.staked, i64),
+            ("unstaked_chunks_received", chunk_counts.unstaked, i64),
+            ("chunks_received", chunk_counts.total, i64),
             (
-                "staked_chunks_received",
-                self.total_staked_chunks_received.swap(0, Ordering::Relaxed),
-                i64
-            ),
-            (
-                "unstaked_chunks_received",
-                self.total_unstaked_chunks_received
-                    .swap(0, Ordering::Relaxed),
-                i64
+                "staked_chunks_received_ratio",
+                chunk_counts.staked_ratio(),
+                f64
             ),
             (
                 "total_handle_chunk_to_packet_send_err",
@@ -489,6 +684,17 @@ impl StreamerStats {
                     .swap(0, Ordering::Relaxed),
                 i64
             ),
+            (
+                "tracked_packets",
+                self.tracked_packets.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "tracked_packets_cumulative_reassembly_us",
+                self.tracked_packets_cumulative_reassembly_us
+                    .swap(0, Ordering::Relaxed),
+                i64
+            ),
             (
                 "throttled_unstaked_streams",
                 self.throttled_unstaked_streams.swap(0, Ordering::Relaxed),
@@ -499,6 +705,11 @@ impl StreamerStats {
                 self.throttled_staked_streams.swap(0, Ordering::Relaxed),
                 i64
             ),
+            (
+                "throttled_bytes_peers",
+                self.throttled_bytes_peers.swap(0, Ordering::Relaxed),
+                i64
+            ),
             (
                 "outstanding_incoming_connection_attempts",
                 self.outstanding_incoming_connection_attempts
@@ -516,11 +727,55 @@ impl StreamerStats {
                 self.quic_endpoints_count.load(Ordering::Relaxed),
                 i64
             ),
+            (
+                "quic_endpoint_rebinds",
+                self.quic_endpoint_rebinds.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "quic_endpoint_rebind_failures",
+                self.quic_endpoint_rebind_failures
+                    .swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "quic_endpoint_lost_connections",
+                self.quic_endpoint_lost_connections
+                    .swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "quic_endpoints_degraded",
+                self.quic_endpoints_degraded.load(Ordering::Relaxed),
+                i64
+            ),
+            (
+                "quic_endpoint_bind_retries",
+                self.quic_endpoint_bind_retries.swap(0, Ordering::Relaxed),
+                i64
+            ),
             (
                 "open_connections",
                 self.open_connections.load(Ordering::Relaxed),
                 i64
             ),
+            (
+                "connection_rate_limiter_entries",
+                self.connection_rate_limiter_entries.load(Ordering::Relaxed),
+                i64
+            ),
+            (
+                "connection_rate_limiter_evictions",
+                self.connection_rate_limiter_evictions
+                    .load(Ordering::Relaxed),
+                i64
+            ),
+            (
+                "connection_rate_limiter_shrink_time_us",
+                self.connection_rate_limiter_shrink_time_us
+                    .load(Ordering::Relaxed),
+                i64
+            ),
             (
                 "peak_open_staked_connections",
                 self.peak_open_staked_connections.swap(
@@ -543,6 +798,33 @@ impl StreamerStats {
                     .swap(0, Ordering::Relaxed),
                 i64
             ),
+            (
+                "refused_handshake_overload",
+                self.refused_handshake_overload.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "refused_connections_not_ready",
+                self.refused_connections_not_ready
+                    .swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "memory_budget_shed_connections",
+                self.memory_budget_shed_connections
+                    .swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "memory_budget_shed_streams",
+                self.memory_budget_shed_streams.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "memory_budget_used_bytes",
+                self.memory_budget_used_bytes.load(Ordering::Relaxed),
+                i64
+            ),
         );
     }
 }
@@ -550,12 +832,44 @@ impl StreamerStats {
 #[derive(Clone)]
 pub struct QuicStreamerConfig {
     pub max_connections_per_ipaddr_per_min: u64,
+    /// Number of IP addresses tracked by the connection rate limiter above
+    /// which older entries are cleaned up. The cleanup runs every
+    /// second, off the accept path.
+    pub rate_limiter_cleanup_threshold: usize,
     pub wait_for_chunk_timeout: Duration,
     pub num_threads: NonZeroUsize,
     /// Per-stream QUIC receive window (flow control limit).
     pub stream_receive_window_size: u32,
     /// Maximum total bytes allowed per stream (hard cap).
     pub max_stream_data_bytes: u32,
+    /// ALPN protocol id negotiated with clients, `ALPN_TPU_PROTOCOL_ID` if None.
+    pub alpn_protocol_id: Option<Vec<u8>>,
+    /// Maximum number of connections handshaking at once, beyond which new
+    /// connection attempts are refused. A quarter of the maximum concurrent
+    /// connections of the QoS controller if None.
+    pub max_concurrent_handshakes: Option<usize>,
+    /// Memory budget shared with other servers, shedding connections and
+    /// streams of unstaked peers first once it fills up. Unbounded if None.
+    pub memory_budget: Option<Arc<TpuIngestMemoryBudget>>,
+    /// Binds a fresh socket for the endpoints found dead. Dead endpoints are
+    /// reported but not recovered if None.
+    pub endpoint_rebinder: Option<Arc<EndpointRebinder>>,
+    /// Time an endpoint goes without incoming connections while its socket
+    /// fails to send before it is considered dead.
+    pub endpoint_dead_timeout: Duration,
+    /// Times creating an endpoint on a fresh socket bound by the
+    /// `endpoint_rebinder` is retried when starting the server in its own
+    /// runtime, before giving up with `QuicServerError::EndpointFailed`.
+    pub endpoint_bind_retries: usize,
+    /// Delay before the first retry to create an endpoint, doubled after each
+    /// retry.
+    pub endpoint_bind_retry_delay: Duration,
+    /// Connections are closed with a `not_ready` reason once their handshake
+    /// completes for as long as this is false. Always ready if None.
+    pub ready: Option<Arc<AtomicBool>>,
+    /// The packets received from these peers are all marked for performance
+    /// tracking, and their reassembly time is reported.
+    pub track_pubkeys: Option<Arc<HashSet<Pubkey>>>,
 }
 
 #[derive(Clone)]
@@ -574,14 +888,68 @@ impl Default for QuicStreamerConfig {
     fn default() -> Self {
         Self {
             max_connections_per_ipaddr_per_min: DEFAULT_MAX_CONNECTIONS_PER_IPADDR_PER_MINUTE,
+            rate_limiter_cleanup_threshold: DEFAULT_RATE_LIMITER_CLEANUP_THRESHOLD,
             wait_for_chunk_timeout: DEFAULT_WAIT_FOR_CHUNK_TIMEOUT,
             num_threads: NonZeroUsize::new(num_cpus::get().min(1)).expect("1 is non-zero"),
             stream_receive_window_size: PACKET_DATA_SIZE as u32,
             max_stream_data_bytes: PACKET_DATA_SIZE as u32,
+            alpn_protocol_id: None,
+            max_concurrent_handshakes: None,
+            memory_budget: None,
+            endpoint_rebinder: None,
+            endpoint_dead_timeout: DEFAULT_ENDPOINT_DEAD_TIMEOUT,
+            endpoint_bind_retries: DEFAULT_ENDPOINT_BIND_RETRIES,
+            endpoint_bind_retry_delay: DEFAULT_ENDPOINT_BIND_RETRY_DELAY,
+            ready: None,
+            track_pubkeys: None,
+        }
+    }
+}
+
+impl SwQosQuicStreamerConfig {
+    /// Derives the maximum number of staked and unstaked connections from the
+    /// memory their receive windows may take up together, keeping the ratio
+    /// of the default connection limits
+    pub fn with_max_memory_bytes(
+        quic_streamer_config: QuicStreamerConfig,
+        max_memory_bytes: NonZeroUsize,
+    ) -> Self {
+        let staked_connection_bytes =
+            max_connection_receive_bytes(&quic_streamer_config, ConnectionPeerType::Staked(1));
+        let unstaked_connection_bytes =
+            max_connection_receive_bytes(&quic_streamer_config, ConnectionPeerType::Unstaked);
+        // max_memory_bytes / (staked * staked_bytes + unstaked * unstaked_bytes)
+        // scales the default limits to fit the budget
+        let default_memory_bytes = DEFAULT_MAX_STAKED_CONNECTIONS as u128
+            * staked_connection_bytes as u128
+            + DEFAULT_MAX_UNSTAKED_CONNECTIONS as u128 * unstaked_connection_bytes as u128;
+        let scale = |default_max_connections: usize| {
+            (default_max_connections as u128 * max_memory_bytes.get() as u128
+                / default_memory_bytes) as usize
+        };
+        Self {
+            quic_streamer_config,
+            qos_config: SwQosConfig {
+                max_staked_connections: scale(DEFAULT_MAX_STAKED_CONNECTIONS),
+                max_unstaked_connections: scale(DEFAULT_MAX_UNSTAKED_CONNECTIONS),
+                ..SwQosConfig::default()
+            },
         }
     }
 }
 
+/// Returns the most data a connection of `peer_type` can have buffered on the
+/// server, i.e. the receive windows of all the streams it may have open,
+/// bounded by the connection receive window
+fn max_connection_receive_bytes(
+    quic_streamer_config: &QuicStreamerConfig,
+    peer_type: ConnectionPeerType,
+) -> usize {
+    let max_streams = max_allowed_uni_streams(peer_type) as u64;
+    (max_streams * quic_streamer_config.stream_receive_window_size as u64)
+        .min(CONNECTION_RECEIVE_WINDOW_BYTES.into_inner()) as usize
+}
+
 impl QuicStreamerConfig {
     #[cfg(feature = "dev-context-only-utils")]
     pub const DEFAULT_NUM_SERVER_THREADS_FOR_TEST: NonZeroUsize = NonZeroUsize::new(8).unwrap();
@@ -595,6 +963,66 @@ impl QuicStreamerConfig {
     }
 }
 
+/// Lists the connections of a running stake weighted QoS server on demand.
+#[derive(Clone)]
+pub struct SwQosConnections {
+    runtime: Handle,
+    swqos: SwQos,
+}
+
+impl SwQosConnections {
+    /// Returns a summary of every staked and unstaked connection, or nothing
+    /// if the server has exited.
+    ///
+    /// Blocks until the connection tables are read on the server runtime, so
+    /// this must not be called from within that runtime.
+    pub fn dump(&self) -> Vec<ConnectionSummary> {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let swqos = self.swqos.clone();
+        self.runtime.spawn(async move {
+            let _ = sender.send(swqos.list_connections().await);
+        });
+        receiver.recv().unwrap_or_default()
+    }
+
+    /// Returns a summary of every connection without a finished stream for at
+    /// least `threshold`, or nothing if the server has exited.
+    ///
+    /// Same as `dump()`, this must not be called from within the server
+    /// runtime.
+    pub fn dump_idle(&self, threshold: Duration) -> Vec<ConnectionSummary> {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let swqos = self.swqos.clone();
+        self.runtime.spawn(async move {
+            let _ = sender.send(swqos.idle_connections(threshold).await);
+        });
+        receiver.recv().unwrap_or_default()
+    }
+}
+
+/// Lists the connections of a running simple QoS server on demand.
+#[derive(Clone)]
+pub struct SimpleQosConnections {
+    runtime: Handle,
+    connection_table: Arc<tokio::sync::Mutex<ConnectionTable<TokenBucket>>>,
+}
+
+impl SimpleQosConnections {
+    /// Returns a summary of every connection, or nothing if the server has
+    /// exited.
+    ///
+    /// Same as `SwQosConnections::dump()`, this must not be called from within
+    /// the server runtime.
+    pub fn dump(&self) -> Vec<ConnectionSummary> {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let connection_table = self.connection_table.clone();
+        self.runtime.spawn(async move {
+            let _ = sender.send(connection_table.lock().await.list_connections());
+        });
+        receiver.recv().unwrap_or_default()
+    }
+}
+
 /// Generic function to spawn a tokio runtime with a QUIC server
 /// Generic over QoS implementation
 fn spawn_runtime_and_server<Q, C>(
@@ -607,15 +1035,15 @@ fn spawn_runtime_and_server<Q, C>(
     quic_server_params: QuicStreamerConfig,
     qos: Q,
     cancel: CancellationToken,
-) -> Result<SpawnServerResult, QuicServerError>
+) -> Result<(SpawnServerResult, Handle), QuicServerError>
 where
     Q: QosController<C> + Send + Sync + 'static,
     C: ConnectionContext + Send + Sync + 'static,
 {
     let runtime = rt(format!("{thread_name}Rt"), quic_server_params.num_threads);
-    let result = {
-        let _guard = runtime.enter();
-        crate::nonblocking::quic::spawn_server(
+    let runtime_handle = runtime.handle().clone();
+    let (result, updater) =
+        runtime.block_on(crate::nonblocking::quic::spawn_server_with_bind_retries(
             metrics_name,
             stats,
             sockets,
@@ -624,8 +1052,7 @@ where
             quic_server_params.clone(),
             qos,
             cancel,
-        )
-    }?;
+        ))?;
     let handle = thread::Builder::new()
         .name(thread_name.into())
         .spawn(move || {
@@ -634,15 +1061,14 @@ where
             }
         })
         .unwrap();
-    let updater = EndpointKeyUpdater {
-        endpoints: result.endpoints.clone(),
-        quic_server_params,
-    };
-    Ok(SpawnServerResult {
-        endpoints: result.endpoints,
-        thread: handle,
-        key_updater: Arc::new(updater),
-    })
+    Ok((
+        SpawnServerResult {
+            endpoints: result.endpoints,
+            thread: handle,
+            key_updater: Arc::new(updater),
+        },
+        runtime_handle,
+    ))
 }
 
 /// Spawns a tokio runtime and a streamer instance inside it.
@@ -658,9 +1084,38 @@ pub fn spawn_stake_weighted_qos_server(
     qos_config: SwQosConfig,
     cancel: CancellationToken,
 ) -> Result<SpawnServerResult, QuicServerError> {
+    spawn_stake_weighted_qos_server_with_connections(
+        thread_name,
+        metrics_name,
+        sockets,
+        keypair,
+        packet_sender,
+        staked_nodes,
+        quic_server_params,
+        qos_config,
+        cancel,
+    )
+    .map(|(result, _connections)| result)
+}
+
+/// Spawns a tokio runtime and a streamer instance inside it.
+/// Uses Stake Weighted QoS
+///
+/// Additionally returns a handle to list the server's connections on demand
+pub fn spawn_stake_weighted_qos_server_with_connections(
+    thread_name: &'static str,
+    metrics_name: &'static str,
+    sockets: impl IntoIterator<Item = QuicSocket>,
+    keypair: &Keypair,
+    packet_sender: Sender<PacketBatch>,
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+    quic_server_params: QuicStreamerConfig,
+    qos_config: SwQosConfig,
+    cancel: CancellationToken,
+) -> Result<(SpawnServerResult, SwQosConnections), QuicServerError> {
     let stats = Arc::<StreamerStats>::default();
     let swqos = SwQos::new(qos_config, stats.clone(), staked_nodes, cancel.clone());
-    spawn_runtime_and_server(
+    let (result, runtime) = spawn_runtime_and_server(
         thread_name,
         metrics_name,
         stats,
@@ -668,9 +1123,10 @@ pub fn spawn_stake_weighted_qos_server(
         keypair,
         packet_sender,
         quic_server_params,
-        swqos,
+        swqos.clone(),
         cancel,
-    )
+    )?;
+    Ok((result, SwQosConnections { runtime, swqos }))
 }
 
 /// Spawns a tokio runtime and a streamer instance inside it.
@@ -687,6 +1143,42 @@ pub fn spawn_simple_qos_server(
     qos_config: SimpleQosConfig,
     cancel: CancellationToken,
 ) -> Result<(SpawnServerResult, Arc<SimpleQosBanlist>), QuicServerError> {
+    spawn_simple_qos_server_with_connections(
+        thread_name,
+        metrics_name,
+        sockets,
+        keypair,
+        packet_sender,
+        staked_nodes,
+        quic_server_params,
+        qos_config,
+        cancel,
+    )
+    .map(|(result, banlist, _connections)| (result, banlist))
+}
+
+/// Spawns a tokio runtime and a streamer instance inside it.
+///
+/// Additionally returns a banlist for control over connection admission, and
+/// a handle to list the server's connections on demand
+pub fn spawn_simple_qos_server_with_connections(
+    thread_name: &'static str,
+    metrics_name: &'static str,
+    sockets: impl IntoIterator<Item = QuicSocket>,
+    keypair: &Keypair,
+    packet_sender: Sender<PacketBatch>,
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+    quic_server_params: QuicStreamerConfig,
+    qos_config: SimpleQosConfig,
+    cancel: CancellationToken,
+) -> Result<
+    (
+        SpawnServerResult,
+        Arc<SimpleQosBanlist>,
+        SimpleQosConnections,
+    ),
+    QuicServerError,
+> {
     let server_params = SimpleQosQuicStreamerConfig {
         quic_streamer_config: quic_server_params,
         qos_config,
@@ -699,6 +1191,7 @@ pub fn spawn_simple_qos_server(
         cancel.clone(),
     );
     let banlist = simple_qos.banlist.clone();
+    let connection_table = simple_qos.connection_table();
 
     spawn_runtime_and_server(
         thread_name,
@@ -711,7 +1204,13 @@ pub fn spawn_simple_qos_server(
         simple_qos,
         cancel,
     )
-    .map(|ssr| (ssr, banlist))
+    .map(|(ssr, runtime)| {
+        let connections = SimpleQosConnections {
+            runtime,
+            connection_table,
+        };
+        (ssr, banlist, connections)
+    })
 }
 
 #[cfg(test)]
@@ -814,6 +1313,178 @@ mod test {
         (t, receiver, server_address, cancel)
     }
 
+    #[test]
+    fn test_quic_server_dump_connections() {
+        agave_logger::setup();
+        let s = bind_to_localhost_unique().expect("should bind");
+        let (sender, _receiver) = unbounded();
+        let server_address = s.local_addr().unwrap();
+        let cancel = CancellationToken::new();
+        let (
+            SpawnServerResult {
+                endpoints: _,
+                thread: t,
+                key_updater: _,
+            },
+            connections,
+        ) = spawn_stake_weighted_qos_server_with_connections(
+            "solQuicTest",
+            "quic_streamer_test",
+            [s.into()],
+            &Keypair::new(),
+            sender,
+            Arc::new(RwLock::new(StakedNodes::default())),
+            QuicStreamerConfig::default_for_tests(),
+            SwQosConfig {
+                max_connections_per_unstaked_peer: 3,
+                ..SwQosConfig::default_for_tests()
+            },
+            cancel.clone(),
+        )
+        .unwrap();
+        assert!(connections.dump().is_empty());
+
+        let runtime = rt_for_test();
+        let clients: Vec<_> = (0..3)
+            .map(|_| runtime.block_on(make_client_endpoint(&server_address, None)))
+            .collect();
+        // Connections are added to the table asynchronously after the handshake
+        let start = Instant::now();
+        let dump = loop {
+            let dump = connections.dump();
+            if dump.len() == clients.len() || start.elapsed() > Duration::from_secs(5) {
+                break dump;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(dump.len(), clients.len());
+        for summary in &dump {
+            assert_eq!(summary.remote_pubkey, None);
+            assert_eq!(summary.stake, 0);
+        }
+        // Every client connection is listed
+        let mut client_addresses: Vec<_> = dump
+            .iter()
+            .map(|summary| summary.remote_address.unwrap())
+            .collect();
+        client_addresses.sort_unstable();
+        client_addresses.dedup();
+        assert_eq!(client_addresses.len(), clients.len());
+
+        cancel.cancel();
+        t.join().unwrap();
+        // The server runtime is gone, so there is nothing to list
+        assert!(connections.dump().is_empty());
+    }
+
+    #[test]
+    fn test_streamer_stats_chunk_counts() {
+        let stats = StreamerStats::default();
+        assert_eq!(stats.chunk_counts(), ChunkCounts::default());
+        assert_eq!(stats.chunk_counts().staked_ratio(), 0.0);
+
+        stats
+            .total_staked_chunks_received
+            .fetch_add(3, Ordering::Relaxed);
+        stats
+            .total_unstaked_chunks_received
+            .fetch_add(1, Ordering::Relaxed);
+        let chunk_counts = stats.chunk_counts();
+        assert_eq!(
+            chunk_counts,
+            ChunkCounts {
+                staked: 3,
+                unstaked: 1,
+                total: 4,
+            }
+        );
+        assert_eq!(chunk_counts.staked_ratio(), 0.75);
+
+        // Reporting starts the counts over
+        stats.report("test_streamer_stats_chunk_counts");
+        assert_eq!(stats.chunk_counts(), ChunkCounts::default());
+    }
+
+    #[test]
+    fn test_streamer_stats_recent_invalid_stream_sizes() {
+        let stats = StreamerStats::default();
+        assert!(stats.recent_invalid_stream_sizes().is_empty());
+        for size in 0..MAX_RECENT_INVALID_STREAM_SIZES + 2 {
+            let ip = IpAddr::from([10, 0, 0, size as u8]);
+            stats.record_invalid_stream_size(ip, PACKET_DATA_SIZE + size);
+        }
+        assert_eq!(
+            stats.invalid_stream_size_count(),
+            MAX_RECENT_INVALID_STREAM_SIZES + 2
+        );
+        // Only the most recent ones are kept
+        let recent = stats.recent_invalid_stream_sizes();
+        assert_eq!(recent.len(), MAX_RECENT_INVALID_STREAM_SIZES);
+        assert_eq!(recent[0].ip, IpAddr::from([10, 0, 0, 2]));
+        assert_eq!(recent[0].size, PACKET_DATA_SIZE + 2);
+        assert_eq!(
+            recent.last().unwrap().size,
+            PACKET_DATA_SIZE + MAX_RECENT_INVALID_STREAM_SIZES + 1
+        );
+
+        // Reporting starts the count over, but keeps the examples
+        stats.report("test_streamer_stats_recent_invalid_stream_sizes");
+        assert_eq!(stats.invalid_stream_size_count(), 0);
+        assert_eq!(
+            stats.recent_invalid_stream_sizes().len(),
+            MAX_RECENT_INVALID_STREAM_SIZES
+        );
+    }
+
+    #[test]
+    fn test_swqos_config_with_max_memory_bytes() {
+        let quic_streamer_config = QuicStreamerConfig::default();
+        let staked_connection_bytes =
+            max_connection_receive_bytes(&quic_streamer_config, ConnectionPeerType::Staked(1));
+        let unstaked_connection_bytes =
+            max_connection_receive_bytes(&quic_streamer_config, ConnectionPeerType::Unstaked);
+        assert!(staked_connection_bytes > unstaked_connection_bytes);
+
+        for max_memory_bytes in [1, 1 << 20, 1 << 30, 16 << 30, 256 << 30] {
+            let SwQosQuicStreamerConfig { qos_config, .. } =
+                SwQosQuicStreamerConfig::with_max_memory_bytes(
+                    quic_streamer_config.clone(),
+                    NonZeroUsize::new(max_memory_bytes).unwrap(),
+                );
+            let memory_bytes = qos_config.max_staked_connections * staked_connection_bytes
+                + qos_config.max_unstaked_connections * unstaked_connection_bytes;
+            assert!(memory_bytes <= max_memory_bytes);
+            // One more of each connection would not fit
+            assert!(
+                memory_bytes + staked_connection_bytes + unstaked_connection_bytes
+                    > max_memory_bytes
+            );
+            assert_eq!(
+                qos_config.max_staked_connections,
+                qos_config.max_unstaked_connections
+            );
+        }
+
+        // Larger stream receive windows leave room for fewer connections
+        let max_memory_bytes = NonZeroUsize::new(16 << 30).unwrap();
+        let SwQosQuicStreamerConfig { qos_config, .. } =
+            SwQosQuicStreamerConfig::with_max_memory_bytes(
+                quic_streamer_config.clone(),
+                max_memory_bytes,
+            );
+        let SwQosQuicStreamerConfig {
+            qos_config: large_window_qos_config,
+            ..
+        } = SwQosQuicStreamerConfig::with_max_memory_bytes(
+            QuicStreamerConfig {
+                stream_receive_window_size: 4 * PACKET_DATA_SIZE as u32,
+                ..quic_streamer_config
+            },
+            max_memory_bytes,
+        );
+        assert!(large_window_qos_config.max_staked_connections < qos_config.max_staked_connections);
+    }
+
     #[test]
     fn test_quic_server_exit() {
         let (t, _receiver, _server_address, cancel) = setup_swqos_quic_server();
@@ -444,6 +444,16 @@ impl StakedNodes {
         self.total_stake
     }
 
+    /// Returns the nodes with non-zero stake, with the overrides applied
+    pub fn staked_nodes(&self) -> impl Iterator<Item = (&Pubkey, u64)> {
+        self.stakes
+            .iter()
+            .filter(|(pubkey, _)| !self.overrides.contains_key(pubkey))
+            .chain(self.overrides.iter())
+            .map(|(pubkey, &stake)| (pubkey, stake))
+            .filter(|(_, stake)| *stake > 0)
+    }
+
     // Update the stake map given a new stakes map
     pub fn update_stake_map(&mut self, stakes: Arc<HashMap<Pubkey, u64>>) {
         let total_stake = Self::calculate_total_stake(&stakes, &self.overrides);
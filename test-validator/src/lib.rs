@@ -1151,7 +1151,9 @@ impl TestValidator {
                     .unwrap()
                     .0,
             ],
-            run_verification: false, // Skip PoH verification of ledger on startup for speed
+            // Skip verification of ledger on startup for speed
+            run_poh_verification: false,
+            run_sig_verification: false,
             snapshot_config: SnapshotConfig {
                 full_snapshot_archive_interval: SnapshotInterval::Slots(
                     NonZeroU64::new(100).unwrap(),
@@ -32,7 +32,10 @@ use {
         iter::repeat_with,
         marker::PhantomData,
         net::SocketAddr,
-        sync::{Arc, OnceLock, RwLock},
+        sync::{
+            Arc, OnceLock, RwLock,
+            atomic::{self, AtomicU64},
+        },
         time::{Duration, Instant},
     },
     thiserror::Error,
@@ -47,6 +50,9 @@ thread_local! {
 pub(crate) const DATA_PLANE_FANOUT: usize = 200;
 pub(crate) const MAX_NUM_TURBINE_HOPS: usize = 4;
 
+// Version assigned to the next ClusterNodes instance.
+static NEXT_CLUSTER_NODES_VERSION: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Loopback from slot leader: {leader}, shred: {shred:?}")]
@@ -62,6 +68,24 @@ enum NodeId {
     Pubkey(Pubkey),
 }
 
+/// A node in the turbine tree, as seen by the weighted shuffle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetransmitPeer {
+    pub pubkey: Pubkey,
+    pub stake: u64,
+    // None if the node has no contact-info in gossip.
+    pub tvu: Option<SocketAddr>,
+}
+
+/// The local node's position in the turbine tree of a shred.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetransmitTree {
+    pub root_distance: u8,
+    // None if the node is the root of the tree or if it is not staked.
+    pub parent: Option<RetransmitPeer>,
+    pub children: Vec<RetransmitPeer>,
+}
+
 // A lite version of gossip ContactInfo local to turbine where we only hold on
 // to a few necessary fields from gossip ContactInfo.
 #[derive(Clone, Debug)]
@@ -78,6 +102,8 @@ pub struct Node {
 
 pub struct ClusterNodes<T> {
     pubkey: Pubkey, // The local node itself.
+    // Uniquely identifies the snapshot of stakes and contact-infos.
+    version: u64,
     // All staked nodes + other known tvu-peers + the node itself;
     // sorted by (stake, pubkey) in descending order.
     nodes: Vec<Node>,
@@ -123,6 +149,14 @@ impl Node {
             NodeId::ContactInfo(node) => Some(node),
         }
     }
+
+    fn retransmit_peer(&self, protocol: Protocol) -> RetransmitPeer {
+        RetransmitPeer {
+            pubkey: *self.pubkey(),
+            stake: self.stake,
+            tvu: self.contact_info().and_then(|node| node.tvu(protocol)),
+        }
+    }
 }
 
 impl ContactInfo {
@@ -258,6 +292,13 @@ impl ClusterNodes<BroadcastStage> {
     }
 }
 
+impl<T> ClusterNodes<T> {
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
 impl ClusterNodes<RetransmitStage> {
     pub fn get_retransmit_addrs(
         &self,
@@ -266,6 +307,51 @@ impl ClusterNodes<RetransmitStage> {
         fanout: usize,
         socket_addr_space: &SocketAddrSpace,
     ) -> Result<(/*root_distance:*/ u8, Vec<SocketAddr>), Error> {
+        let protocol = get_broadcast_protocol(shred);
+        self.with_retransmit_peers(slot_leader, shred, fanout, |root_distance, peers| {
+            let peers = peers
+                .filter_map(|node| node.contact_info()?.tvu(protocol))
+                .filter(|addr| !addr.is_ipv6() && socket_addr_space.check(addr))
+                .collect();
+            (root_distance, peers)
+        })
+    }
+
+    /// Returns the retransmit children and parent of this node for the shred,
+    /// computed the same way as get_retransmit_addrs and
+    /// get_retransmit_parent. Unlike get_retransmit_addrs, children are not
+    /// filtered by their socket addresses.
+    pub fn get_retransmit_tree(
+        &self,
+        slot_leader: &Pubkey,
+        shred: &ShredId,
+        fanout: usize,
+    ) -> Result<RetransmitTree, Error> {
+        let protocol = get_broadcast_protocol(shred);
+        let (root_distance, children) =
+            self.with_retransmit_peers(slot_leader, shred, fanout, |root_distance, peers| {
+                let children: Vec<_> = peers.map(|node| node.retransmit_peer(protocol)).collect();
+                (root_distance, children)
+            })?;
+        let parent = self
+            .get_retransmit_parent(slot_leader, shred, fanout)?
+            .map(|parent| self.nodes[self.index[&parent]].retransmit_peer(protocol));
+        Ok(RetransmitTree {
+            root_distance,
+            parent,
+            children,
+        })
+    }
+
+    // Shuffles the nodes for the shred and invokes f with this node's distance
+    // from the root of the turbine tree and its retransmit peers.
+    fn with_retransmit_peers<'a, R>(
+        &'a self,
+        slot_leader: &Pubkey,
+        shred: &ShredId,
+        fanout: usize,
+        f: impl FnOnce(/*root_distance:*/ u8, &mut dyn Iterator<Item = &'a Node>) -> R,
+    ) -> Result<R, Error> {
         // Exclude slot leader from list of nodes.
         if slot_leader == &self.pubkey {
             return Err(Error::Loopback {
@@ -284,13 +370,8 @@ impl ClusterNodes<RetransmitStage> {
                 |k| self.nodes[k].pubkey() == &self.pubkey,
                 weighted_shuffle.shuffle(&mut rng),
             );
-            let protocol = get_broadcast_protocol(shred);
-            let peers = peers
-                .filter_map(|k| self.nodes[k].contact_info()?.tvu(protocol))
-                .filter(|addr| !addr.is_ipv6() && socket_addr_space.check(addr))
-                .collect();
             let root_distance = get_root_distance(index, fanout);
-            Ok((root_distance, peers))
+            Ok(f(root_distance, &mut peers.map(|k| &self.nodes[k])))
         })
     }
 
@@ -360,6 +441,7 @@ pub fn new_cluster_nodes<T: 'static>(
     }
     ClusterNodes {
         pubkey: self_pubkey,
+        version: NEXT_CLUSTER_NODES_VERSION.fetch_add(1, atomic::Ordering::Relaxed),
         nodes,
         index,
         weighted_shuffle,
@@ -724,7 +806,7 @@ mod tests {
         itertools::Itertools,
         rand::prelude::IndexedRandom as _,
         solana_hash::Hash as SolanaHash,
-        solana_ledger::shred::{ProcessShredsStats, ReedSolomonCache, Shredder},
+        solana_ledger::shred::{ProcessShredsStats, ReedSolomonCache, ShredType, Shredder},
         std::{collections::VecDeque, fmt::Debug, hash::Hash},
         test_case::test_case,
     };
@@ -865,6 +947,65 @@ mod tests {
         }
     }
 
+    #[test_case(true)/*ChaCha8 */]
+    #[test_case(false)/*ChaCha20 */]
+    fn test_get_retransmit_tree(use_cha_cha_8: bool) {
+        const FANOUT: usize = 10;
+        let mut rng = rand::rng();
+        let (nodes, mut stakes, cluster_info) = make_test_cluster(&mut rng, 1_000, None);
+        // Make sure this node is staked so that it has a retransmit parent.
+        stakes.insert(cluster_info.id(), 10);
+        let cluster_nodes = new_cluster_nodes::<RetransmitStage>(
+            &cluster_info,
+            ClusterType::Development,
+            &stakes,
+            use_cha_cha_8,
+        );
+        let socket_addr_space = SocketAddrSpace::Unspecified;
+        let mut num_children = 0;
+        for _ in 0..100 {
+            let slot_leader = nodes[rng.random_range(1..nodes.len())].pubkey();
+            let shred = ShredId::new(
+                rng.random_range(0..1_000),
+                rng.random_range(0..32_768),
+                ShredType::Data,
+            );
+            let tree = cluster_nodes
+                .get_retransmit_tree(slot_leader, &shred, FANOUT)
+                .unwrap();
+            let (root_distance, addrs) = cluster_nodes
+                .get_retransmit_addrs(slot_leader, &shred, FANOUT, &socket_addr_space)
+                .unwrap();
+            assert_eq!(tree.root_distance, root_distance);
+            assert_eq!(
+                tree.children
+                    .iter()
+                    .filter_map(|child| child.tvu)
+                    .collect::<Vec<_>>(),
+                addrs
+            );
+            for child in &tree.children {
+                assert_eq!(child.stake, stakes.get(&child.pubkey).copied().unwrap_or(0));
+            }
+            num_children += tree.children.len();
+            let parent = cluster_nodes
+                .get_retransmit_parent(slot_leader, &shred, FANOUT)
+                .unwrap();
+            assert_eq!(tree.parent.as_ref().map(|parent| parent.pubkey), parent);
+            assert_eq!(tree.parent.is_none(), root_distance == 0);
+        }
+        assert!(num_children > 0);
+        // The slot leader does not retransmit its own shreds.
+        assert_matches!(
+            cluster_nodes.get_retransmit_tree(
+                &cluster_info.id(),
+                &ShredId::new(1, 0, ShredType::Data),
+                FANOUT
+            ),
+            Err(Error::Loopback { .. })
+        );
+    }
+
     #[test_case(true)/*ChaCha8 */]
     #[test_case(false)/*ChaCha20 */]
     fn test_cluster_nodes_broadcast(use_cha_cha_8: bool) {
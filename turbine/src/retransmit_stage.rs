@@ -6,15 +6,16 @@ use {
         addr_cache::AddrCache,
         cluster_nodes::{
             ClusterNodes, ClusterNodesCache, DATA_PLANE_FANOUT, Error, MAX_NUM_TURBINE_HOPS,
+            RetransmitTree,
         },
     },
     agave_votor::event::VotorEvent,
     agave_votor_messages::migration::MigrationStatus,
     crossbeam_channel::{Receiver, Sender, TryRecvError, TrySendError},
     lru::LruCache,
-    rand::Rng,
+    rand::{Rng, RngCore, SeedableRng, rngs::SmallRng},
     rayon::{ThreadPool, ThreadPoolBuilder, prelude::*},
-    solana_clock::Slot,
+    solana_clock::{Epoch, Slot},
     solana_gossip::cluster_info::ClusterInfo,
     solana_ledger::{
         leader_schedule_cache::LeaderScheduleCache,
@@ -66,6 +67,9 @@ const _: () = const {
 };
 const CLUSTER_NODES_CACHE_NUM_EPOCH_CAP: usize = MAX_LEADER_SCHEDULE_STAKES as usize;
 const CLUSTER_NODES_CACHE_TTL: Duration = Duration::from_secs(5);
+// Number of recent slots for which the turbine tree inputs are retained for
+// diagnostics.
+const TURBINE_DIAGNOSTICS_NUM_SLOTS: usize = 1024;
 
 // Output of fn retransmit_shred(...).
 struct RetransmitShredOutput {
@@ -123,6 +127,7 @@ struct RetransmitState {
     stats: RetransmitStats,
     addr_cache: AddrCache,
     shred_buf: Vec<Vec<shred::Payload>>,
+    rng: Box<dyn RngCore>,
 }
 
 struct RetransmitNotifiers {
@@ -140,18 +145,24 @@ struct RetransmitContext {
     retransmit_receiver: Receiver<Vec<shred::Payload>>,
     retransmit_sockets: Arc<Vec<UdpSocket>>,
     xdp_sender: Option<XdpSender>,
-    cluster_nodes_cache: ClusterNodesCache<RetransmitStage>,
+    cluster_nodes_cache: Arc<ClusterNodesCache<RetransmitStage>>,
+    diagnostics: Arc<RetransmitDiagnostics>,
     shred_deduper: ShredDeduper,
     max_slots: Arc<MaxSlots>,
     notifiers: RetransmitNotifiers,
 }
 
 impl RetransmitState {
-    fn new(now: Instant) -> Self {
+    fn new(now: Instant, seeded_rng: Option<SmallRng>) -> Self {
+        let rng: Box<dyn RngCore> = match seeded_rng {
+            Some(rng) => Box::new(rng),
+            None => Box::new(rand::rng()),
+        };
         Self {
             stats: RetransmitStats::new(now),
             addr_cache: AddrCache::with_capacity(/*capacity:*/ 4),
             shred_buf: Vec::with_capacity(RETRANSMIT_BATCH_SIZE),
+            rng,
         }
     }
 }
@@ -331,6 +342,7 @@ fn retransmit(context: &RetransmitContext, state: &mut RetransmitState) -> Resul
         stats,
         addr_cache,
         shred_buf,
+        rng,
     } = state;
 
     // Try to receive shreds from the channel without blocking. If the channel
@@ -380,11 +392,7 @@ fn retransmit(context: &RetransmitContext, state: &mut RetransmitState) -> Resul
     stats.epoch_fetch += epoch_fetch.as_us();
 
     let mut epoch_cache_update = Measure::start("retransmit_epoch_cache_update");
-    shred_deduper.maybe_reset(
-        &mut rand::rng(),
-        DEDUPER_FALSE_POSITIVE_RATE,
-        DEDUPER_RESET_CYCLE,
-    );
+    shred_deduper.maybe_reset(rng, DEDUPER_FALSE_POSITIVE_RATE, DEDUPER_RESET_CYCLE);
     epoch_cache_update.stop();
     stats.epoch_cache_update += epoch_cache_update.as_us();
     // Lookup slot leader and cluster nodes for each slot.
@@ -411,6 +419,7 @@ fn retransmit(context: &RetransmitContext, state: &mut RetransmitState) -> Resul
             Some((slot, (slot_leader.id, cluster_nodes)))
         })
         .collect();
+    context.diagnostics.record(&cache);
     let socket_addr_space = cluster_info.socket_addr_space();
     let record = |mut stats: HashMap<Slot, RetransmitSlotStats>, out: RetransmitShredOutput| {
         let now = timestamp();
@@ -637,9 +646,140 @@ fn cache_retransmit_addrs(
     out
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum TurbineDiagnosticsError {
+    #[error("Unknown slot leader for slot: {0}")]
+    UnknownSlotLeader(Slot),
+    #[error(transparent)]
+    ClusterNodes(#[from] Error),
+}
+
+/// Turbine tree of a shred as computed by this node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TurbineTreeDiagnostics {
+    pub shred: ShredId,
+    pub slot_leader: Pubkey,
+    // Epoch of the staked nodes the tree was computed from.
+    pub epoch: Epoch,
+    // Version of the snapshot of staked nodes and contact-infos.
+    pub cluster_nodes_version: u64,
+    // True if these are the inputs retransmit used for the slot, false if
+    // the slot was not observed recently and the current inputs were used.
+    pub observed: bool,
+    pub tree: RetransmitTree,
+}
+
+// Inputs retransmit used for the turbine tree of a slot.
+struct RecentSlot {
+    slot: Slot,
+    slot_leader: Pubkey,
+    cluster_nodes: Arc<ClusterNodes<RetransmitStage>>,
+}
+
+/// Retains the slot leader and cluster nodes retransmit used for recent
+/// slots, so that the turbine tree of a shred can be recomputed after the
+/// fact.
+pub struct RetransmitDiagnostics {
+    bank_forks: Arc<RwLock<BankForks>>,
+    leader_schedule_cache: Arc<LeaderScheduleCache>,
+    cluster_info: Arc<ClusterInfo>,
+    cluster_nodes_cache: Arc<ClusterNodesCache<RetransmitStage>>,
+    // Indexed by slot modulo the number of entries. Each slot has its own
+    // lock, which retransmit only takes for writing once per slot and
+    // cluster nodes snapshot, so it does not contend on the hot path.
+    recent_slots: Box<[RwLock<Option<RecentSlot>>]>,
+}
+
+impl RetransmitDiagnostics {
+    fn new(
+        bank_forks: Arc<RwLock<BankForks>>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
+        cluster_info: Arc<ClusterInfo>,
+        cluster_nodes_cache: Arc<ClusterNodesCache<RetransmitStage>>,
+    ) -> Self {
+        Self {
+            bank_forks,
+            leader_schedule_cache,
+            cluster_info,
+            cluster_nodes_cache,
+            recent_slots: (0..TURBINE_DIAGNOSTICS_NUM_SLOTS)
+                .map(|_| RwLock::default())
+                .collect(),
+        }
+    }
+
+    fn recent_slot(&self, slot: Slot) -> &RwLock<Option<RecentSlot>> {
+        &self.recent_slots[slot as usize % self.recent_slots.len()]
+    }
+
+    fn record(&self, cache: &HashMap<Slot, (Pubkey, Arc<ClusterNodes<RetransmitStage>>)>) {
+        for (&slot, (slot_leader, cluster_nodes)) in cache {
+            let recent_slot = self.recent_slot(slot);
+            let is_recorded = recent_slot.read().unwrap().as_ref().is_some_and(|recent| {
+                recent.slot == slot && Arc::ptr_eq(&recent.cluster_nodes, cluster_nodes)
+            });
+            if !is_recorded {
+                *recent_slot.write().unwrap() = Some(RecentSlot {
+                    slot,
+                    slot_leader: *slot_leader,
+                    cluster_nodes: Arc::clone(cluster_nodes),
+                });
+            }
+        }
+    }
+
+    /// Returns the retransmit children and parent of this node for the
+    /// shred. For recently retransmitted slots the same inputs as retransmit
+    /// are used; otherwise the tree is computed from the current inputs.
+    pub fn get_turbine_tree(
+        &self,
+        shred: ShredId,
+    ) -> Result<TurbineTreeDiagnostics, TurbineDiagnosticsError> {
+        inc_new_counter_info!("retransmit-turbine_tree_diagnostics_queries", 1);
+        let slot = shred.slot();
+        let (working_bank, root_bank) = {
+            let bank_forks = self.bank_forks.read().unwrap();
+            (bank_forks.working_bank(), bank_forks.root_bank())
+        };
+        let observed = self
+            .recent_slot(slot)
+            .read()
+            .unwrap()
+            .as_ref()
+            .filter(|recent| recent.slot == slot)
+            .map(|recent| (recent.slot_leader, Arc::clone(&recent.cluster_nodes)));
+        let (slot_leader, cluster_nodes, observed) = match observed {
+            Some((slot_leader, cluster_nodes)) => (slot_leader, cluster_nodes, true),
+            None => {
+                let slot_leader = self
+                    .leader_schedule_cache
+                    .slot_leader_at(slot, Some(&working_bank))
+                    .ok_or(TurbineDiagnosticsError::UnknownSlotLeader(slot))?;
+                let cluster_nodes = self.cluster_nodes_cache.get(
+                    slot,
+                    &root_bank,
+                    &working_bank,
+                    &self.cluster_info,
+                );
+                (slot_leader.id, cluster_nodes, false)
+            }
+        };
+        let tree = cluster_nodes.get_retransmit_tree(&slot_leader, &shred, DATA_PLANE_FANOUT)?;
+        Ok(TurbineTreeDiagnostics {
+            shred,
+            slot_leader,
+            epoch: root_bank.epoch_schedule().get_epoch(slot),
+            cluster_nodes_version: cluster_nodes.version(),
+            observed,
+            tree,
+        })
+    }
+}
+
 /// Service to retransmit messages received from other peers in turbine.
 pub struct RetransmitStage {
     retransmit_thread_handle: JoinHandle<()>,
+    diagnostics: Arc<RetransmitDiagnostics>,
 }
 
 impl RetransmitStage {
@@ -652,6 +792,7 @@ impl RetransmitStage {
     /// * `leader_schedule_cache` - The leader schedule to verify shreds
     /// * `cluster_info` - This structure needs to be updated and populated by the bank and via gossip.
     /// * `retransmit_receiver` - Receive channel for batches of shreds to be retransmitted.
+    /// * `rng_seed` - Seed of the RNG of the shred deduper, for reproducible runs.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         bank_forks: Arc<RwLock<BankForks>>,
@@ -664,14 +805,24 @@ impl RetransmitStage {
         slot_status_notifier: Option<SlotStatusNotifier>,
         xdp_sender: Option<XdpSender>,
         votor_event_sender: Sender<VotorEvent>,
+        rng_seed: Option<u64>,
     ) -> Self {
         let migration_status = bank_forks.read().unwrap().migration_status();
-        let cluster_nodes_cache = ClusterNodesCache::<RetransmitStage>::new(
+        let cluster_nodes_cache = Arc::new(ClusterNodesCache::<RetransmitStage>::new(
             CLUSTER_NODES_CACHE_NUM_EPOCH_CAP,
             CLUSTER_NODES_CACHE_TTL,
-        );
-        let mut rng = rand::rng();
-        let shred_deduper = ShredDeduper::new(&mut rng, DEDUPER_NUM_BITS);
+        ));
+        let diagnostics = Arc::new(RetransmitDiagnostics::new(
+            bank_forks.clone(),
+            leader_schedule_cache.clone(),
+            cluster_info.clone(),
+            cluster_nodes_cache.clone(),
+        ));
+        let mut seeded_rng = rng_seed.map(SmallRng::seed_from_u64);
+        let shred_deduper = match seeded_rng.as_mut() {
+            Some(rng) => ShredDeduper::new(rng, DEDUPER_NUM_BITS),
+            None => ShredDeduper::new(&mut rand::rng(), DEDUPER_NUM_BITS),
+        };
 
         let thread_pool = {
             let num_threads = retransmit_sockets.len();
@@ -691,6 +842,7 @@ impl RetransmitStage {
             retransmit_sockets,
             xdp_sender,
             cluster_nodes_cache,
+            diagnostics: diagnostics.clone(),
             shred_deduper,
             max_slots,
             notifiers: RetransmitNotifiers {
@@ -705,7 +857,7 @@ impl RetransmitStage {
             .name("solRetransmittr".to_string())
             .spawn({
                 move || {
-                    let mut retransmit_state = RetransmitState::new(Instant::now());
+                    let mut retransmit_state = RetransmitState::new(Instant::now(), seeded_rng);
                     while retransmit(&retransmit_context, &mut retransmit_state).is_ok() {}
                 }
             })
@@ -713,9 +865,14 @@ impl RetransmitStage {
 
         Self {
             retransmit_thread_handle,
+            diagnostics,
         }
     }
 
+    pub fn diagnostics(&self) -> &Arc<RetransmitDiagnostics> {
+        &self.diagnostics
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.retransmit_thread_handle.join()
     }
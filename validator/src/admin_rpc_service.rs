@@ -11,7 +11,7 @@ use {
     },
     log::*,
     serde::{Deserialize, Serialize, de::Deserializer},
-    solana_clock::Slot,
+    solana_clock::{Epoch, Slot},
     solana_core::{
         admin_rpc_post_init::AdminRpcRequestMetadataPostInit,
         banking_stage::{
@@ -19,7 +19,8 @@ use {
             transaction_scheduler::scheduler_controller::SchedulerConfig,
         },
         consensus::{Tower, tower_storage::TowerStorage},
-        repair::repair_service,
+        repair::{repair_service, repair_state_snapshot::RepairStateSnapshot},
+        tpu_ingest_self_test::{IngestSelfTestConfig, IngestSelfTestResult},
         validator::{
             BlockProductionMethod, SchedulerPacing, TransactionStructure, ValidatorStartProgress,
             should_require_vote_history_file,
@@ -28,10 +29,13 @@ use {
     solana_geyser_plugin_manager::GeyserPluginManagerRequest,
     solana_gossip::contact_info::{ContactInfo, Protocol, SOCKET_ADDR_UNSPECIFIED},
     solana_keypair::{Keypair, read_keypair_file},
+    solana_ledger::shred::{ShredId, ShredType},
     solana_metrics::{datapoint_info, datapoint_warn},
     solana_pubkey::Pubkey,
     solana_runtime::snapshot_controller::SnapshotController,
     solana_signer::Signer,
+    solana_streamer::{nonblocking::quic::ConnectionSummary, quic::SwQosConnections},
+    solana_turbine::{cluster_nodes::RetransmitPeer, retransmit_stage::TurbineTreeDiagnostics},
     solana_validator_exit::Exit,
     std::{
         collections::{HashMap, HashSet},
@@ -165,6 +169,107 @@ impl Display for AdminRpcRepairWhitelist {
 impl solana_cli_output::VerboseDisplay for AdminRpcRepairWhitelist {}
 impl solana_cli_output::QuietDisplay for AdminRpcRepairWhitelist {}
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcConnectionSummary {
+    pub pubkey: Option<String>,
+    pub address: Option<SocketAddr>,
+    pub stake: u64,
+    pub age_ms: u64,
+    pub last_update: u64,
+    #[serde(default)]
+    pub idle_ms: u64,
+    pub lifetime_streams: u64,
+    pub lifetime_bytes: u64,
+    #[serde(default)]
+    pub current_stream_cap: Option<u64>,
+}
+
+impl From<ConnectionSummary> for AdminRpcConnectionSummary {
+    fn from(summary: ConnectionSummary) -> Self {
+        Self {
+            pubkey: summary.remote_pubkey.as_ref().map(Pubkey::to_string),
+            address: summary.remote_address,
+            stake: summary.stake,
+            age_ms: summary.age.as_millis() as u64,
+            last_update: summary.last_update,
+            idle_ms: summary.idle_ms,
+            lifetime_streams: summary.lifetime_streams,
+            lifetime_bytes: summary.lifetime_bytes,
+            current_stream_cap: summary.current_stream_cap,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRpcShredType {
+    Data,
+    Code,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcTurbinePeer {
+    pub pubkey: String,
+    pub stake: u64,
+    pub tvu: Option<SocketAddr>,
+}
+
+impl From<RetransmitPeer> for AdminRpcTurbinePeer {
+    fn from(peer: RetransmitPeer) -> Self {
+        Self {
+            pubkey: peer.pubkey.to_string(),
+            stake: peer.stake,
+            tvu: peer.tvu,
+        }
+    }
+}
+
+/// This node's position in the turbine tree of a shred.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcTurbineTree {
+    pub slot_leader: String,
+    pub epoch: Epoch,
+    pub cluster_nodes_version: u64,
+    /// False if the slot was not retransmitted recently, and the tree was
+    /// computed from the current inputs instead.
+    pub observed: bool,
+    pub root_distance: u8,
+    pub parent: Option<AdminRpcTurbinePeer>,
+    pub children: Vec<AdminRpcTurbinePeer>,
+}
+
+impl From<TurbineTreeDiagnostics> for AdminRpcTurbineTree {
+    fn from(diagnostics: TurbineTreeDiagnostics) -> Self {
+        let TurbineTreeDiagnostics {
+            shred: _,
+            slot_leader,
+            epoch,
+            cluster_nodes_version,
+            observed,
+            tree,
+        } = diagnostics;
+        Self {
+            slot_leader: slot_leader.to_string(),
+            epoch,
+            cluster_nodes_version,
+            observed,
+            root_distance: tree.root_distance,
+            parent: tree.parent.map(AdminRpcTurbinePeer::from),
+            children: tree
+                .children
+                .into_iter()
+                .map(AdminRpcTurbinePeer::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct AdminRpcTpuConnections {
+    pub tpu: Vec<AdminRpcConnectionSummary>,
+    pub tpu_forwards: Vec<AdminRpcConnectionSummary>,
+}
+
 #[rpc]
 pub trait AdminRpc {
     type Metadata;
@@ -259,6 +364,12 @@ pub trait AdminRpc {
     #[rpc(meta, name = "setRepairWhitelist")]
     fn set_repair_whitelist(&self, meta: Self::Metadata, whitelist: Vec<Pubkey>) -> Result<()>;
 
+    #[rpc(meta, name = "setRepairValidators")]
+    fn set_repair_validators(&self, meta: Self::Metadata, validators: Vec<Pubkey>) -> Result<()>;
+
+    #[rpc(meta, name = "repairStateSnapshot")]
+    fn repair_state_snapshot(&self, meta: Self::Metadata) -> Result<RepairStateSnapshot>;
+
     #[rpc(meta, name = "setPublicTpuAddress")]
     fn set_public_tpu_address(
         &self,
@@ -280,6 +391,13 @@ pub trait AdminRpc {
         public_tvu_addr: SocketAddr,
     ) -> Result<()>;
 
+    #[rpc(meta, name = "refreshAdvertisedAddresses")]
+    fn refresh_advertised_addresses(
+        &self,
+        meta: Self::Metadata,
+        new_ip: Option<IpAddr>,
+    ) -> Result<IpAddr>;
+
     #[rpc(meta, name = "manageBlockProduction")]
     fn manage_block_production(
         &self,
@@ -295,6 +413,25 @@ pub trait AdminRpc {
 
     #[rpc(meta, name = "blockstorePurge")]
     fn blockstore_purge(&self, meta: Self::Metadata, maximum_purge_slot: Slot) -> Result<()>;
+
+    #[rpc(meta, name = "tpuConnections")]
+    fn tpu_connections(&self, meta: Self::Metadata) -> Result<AdminRpcTpuConnections>;
+
+    #[rpc(meta, name = "runIngestSelfTest")]
+    fn run_ingest_self_test(
+        &self,
+        meta: Self::Metadata,
+        config: Option<IngestSelfTestConfig>,
+    ) -> Result<IngestSelfTestResult>;
+
+    #[rpc(meta, name = "turbineTree")]
+    fn turbine_tree(
+        &self,
+        meta: Self::Metadata,
+        slot: Slot,
+        index: u32,
+        shred_type: AdminRpcShredType,
+    ) -> Result<AdminRpcTurbineTree>;
 }
 
 pub struct AdminRpcImpl;
@@ -698,6 +835,28 @@ impl AdminRpc for AdminRpcImpl {
         })
     }
 
+    fn set_repair_validators(&self, meta: Self::Metadata, validators: Vec<Pubkey>) -> Result<()> {
+        debug!("set_repair_validators request received");
+
+        // An empty set requests repairs from all validators
+        let validators: Option<HashSet<Pubkey>> =
+            (!validators.is_empty()).then(|| validators.into_iter().collect());
+        meta.with_post_init(|post_init| {
+            *post_init.repair_validators.write().unwrap() = validators;
+            warn!(
+                "Repair validators set to {:?}",
+                &post_init.repair_validators.read().unwrap()
+            );
+            Ok(())
+        })
+    }
+
+    fn repair_state_snapshot(&self, meta: Self::Metadata) -> Result<RepairStateSnapshot> {
+        debug!("repair_state_snapshot request received");
+
+        meta.with_post_init(|post_init| Ok((post_init.repair_state_snapshot)()))
+    }
+
     fn set_public_tpu_address(
         &self,
         meta: Self::Metadata,
@@ -809,6 +968,26 @@ impl AdminRpc for AdminRpcImpl {
         })
     }
 
+    fn refresh_advertised_addresses(
+        &self,
+        meta: Self::Metadata,
+        new_ip: Option<IpAddr>,
+    ) -> Result<IpAddr> {
+        debug!("refresh_advertised_addresses rpc request received: {new_ip:?}");
+
+        // Rediscovering the public IP address may take a while, so the post
+        // init is not held throughout
+        let advertised_addresses =
+            meta.with_post_init(|post_init| Ok(post_init.advertised_addresses.clone()))?;
+        advertised_addresses
+            .refresh(new_ip)
+            .map_err(|err| jsonrpc_core::Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!("{err}"),
+                data: None,
+            })
+    }
+
     fn manage_block_production(
         &self,
         meta: Self::Metadata,
@@ -872,6 +1051,80 @@ impl AdminRpc for AdminRpcImpl {
                 })
         })
     }
+
+    fn tpu_connections(&self, meta: Self::Metadata) -> Result<AdminRpcTpuConnections> {
+        meta.with_post_init(|post_init| {
+            let dump = |connections: Option<&SwQosConnections>| {
+                connections
+                    .map(|connections| connections.dump().into_iter().map(Into::into).collect())
+                    .unwrap_or_default()
+            };
+            Ok(AdminRpcTpuConnections {
+                tpu: dump(post_init.tpu_connections.as_ref()),
+                tpu_forwards: dump(post_init.tpu_forwards_connections.as_ref()),
+            })
+        })
+    }
+
+    fn run_ingest_self_test(
+        &self,
+        meta: Self::Metadata,
+        config: Option<IngestSelfTestConfig>,
+    ) -> Result<IngestSelfTestResult> {
+        debug!("run_ingest_self_test request received");
+
+        // The self-test runs for seconds, so it must not hold the post init lock
+        let ingest_self_test = meta.with_post_init(|post_init| {
+            post_init
+                .ingest_self_test
+                .clone()
+                .ok_or_else(|| jsonrpc_core::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: "The TPU ingest self-test is not available".to_string(),
+                    data: None,
+                })
+        })?;
+        ingest_self_test
+            .run(&config.unwrap_or_default())
+            .map_err(|err| jsonrpc_core::Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!("{err}"),
+                data: None,
+            })
+    }
+
+    fn turbine_tree(
+        &self,
+        meta: Self::Metadata,
+        slot: Slot,
+        index: u32,
+        shred_type: AdminRpcShredType,
+    ) -> Result<AdminRpcTurbineTree> {
+        debug!("turbine_tree request received: {slot} {index} {shred_type:?}");
+
+        let retransmit_diagnostics = meta.with_post_init(|post_init| {
+            post_init
+                .retransmit_diagnostics
+                .clone()
+                .ok_or_else(|| jsonrpc_core::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: "Turbine diagnostics are not available".to_string(),
+                    data: None,
+                })
+        })?;
+        let shred_type = match shred_type {
+            AdminRpcShredType::Data => ShredType::Data,
+            AdminRpcShredType::Code => ShredType::Code,
+        };
+        retransmit_diagnostics
+            .get_turbine_tree(ShredId::new(slot, index, shred_type))
+            .map(AdminRpcTurbineTree::from)
+            .map_err(|err| jsonrpc_core::Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!("{err}"),
+                data: None,
+            })
+    }
 }
 
 impl AdminRpcImpl {
@@ -1111,6 +1364,7 @@ mod tests {
         },
         solana_core::{
             admin_rpc_post_init::{KeyUpdaterType, KeyUpdaters},
+            advertised_addresses::AdvertisedAddresses,
             consensus::tower_storage::NullTowerStorage,
             validator::{Validator, ValidatorConfig, ValidatorTpuConfig},
         },
@@ -1182,6 +1436,20 @@ mod tests {
             let vote_account = vote_keypair.pubkey();
             let start_progress = Arc::new(RwLock::new(ValidatorStartProgress::default()));
             let repair_whitelist = Arc::new(RwLock::new(HashSet::new()));
+            let outstanding_repair_requests =
+                Arc::<RwLock<repair_service::OutstandingShredRepairs>>::default();
+            let cluster_slots = Arc::new(
+                solana_core::cluster_slots_service::cluster_slots::ClusterSlots::default_for_tests(
+                ),
+            );
+            let repair_state_snapshot =
+                solana_core::repair::repair_state_snapshot::repair_state_snapshot_fn(
+                    repair_whitelist.clone(),
+                    outstanding_repair_requests.clone(),
+                    cluster_slots.clone(),
+                );
+            let advertised_addresses =
+                Arc::new(AdvertisedAddresses::new(cluster_info.clone(), vec![]));
             let votor_event_sender = config.votor_event_sender.unwrap_or_else(|| {
                 let (votor_event_sender, _) = bounded(1024);
                 votor_event_sender
@@ -1202,19 +1470,22 @@ mod tests {
                     bank_forks,
                     vote_account,
                     repair_whitelist,
+                    repair_validators: Arc::default(),
                     notifies: Arc::new(RwLock::new(KeyUpdaters::default())),
                     repair_socket: Arc::new(bind_to_localhost_unique().expect("should bind")),
-                    outstanding_repair_requests: Arc::<
-                        RwLock<repair_service::OutstandingShredRepairs>,
-                    >::default(),
-                    cluster_slots: Arc::new(
-                        solana_core::cluster_slots_service::cluster_slots::ClusterSlots::default_for_tests(),
-                    ),
+                    outstanding_repair_requests,
+                    cluster_slots,
+                    repair_state_snapshot,
                     node: None,
                     banking_control_sender: mpsc::channel(1).0,
                     snapshot_controller,
                     blockstore,
                     votor_event_sender,
+                    tpu_connections: None,
+                    tpu_forwards_connections: None,
+                    ingest_self_test: None,
+                    advertised_addresses,
+                    retransmit_diagnostics: None,
                 }))),
                 staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
                 rpc_to_plugin_manager_sender: None,
@@ -1471,6 +1742,135 @@ mod tests {
         assert_eq!(actual_parsed_response, expected_parsed_response);
     }
 
+    #[test]
+    fn test_tpu_connections() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let RpcHandler { io, meta, .. } = rpc;
+
+        // The test post init has no TPU, so both tables are empty
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"tpuConnections","params":[]}"#;
+        let response = io.handle_request_sync(request, meta);
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        let connections: AdminRpcTpuConnections =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        assert!(connections.tpu.is_empty());
+        assert!(connections.tpu_forwards.is_empty());
+    }
+
+    #[test]
+    fn test_run_ingest_self_test_unavailable() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let RpcHandler { io, meta, .. } = rpc;
+
+        // The test post init has no TPU to run the self-test against
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"runIngestSelfTest","params":[{"max_duration_ms":1000}]}"#;
+        let response = io.handle_request_sync(request, meta);
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["message"],
+            "The TPU ingest self-test is not available"
+        );
+    }
+
+    #[test]
+    fn test_turbine_tree_unavailable() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let RpcHandler { io, meta, .. } = rpc;
+
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"turbineTree","params":[1, 0, "data"]}"#;
+        let response = io.handle_request_sync(request, meta);
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["message"],
+            "Turbine diagnostics are not available"
+        );
+    }
+
+    #[test]
+    fn test_refresh_advertised_addresses() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let RpcHandler { io, meta, .. } = rpc;
+
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"refreshAdvertisedAddresses","params":["127.0.0.2"]}"#;
+        let response = io.handle_request_sync(request, meta.clone());
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"], "127.0.0.2");
+
+        // Refreshing again right away is rejected
+        let response = io.handle_request_sync(request, meta);
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert!(
+            result["error"]["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("advertised addresses were refreshed")
+        );
+    }
+
+    #[test]
+    fn test_repair_state_snapshot() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let RpcHandler { io, meta, .. } = rpc;
+
+        let whitelisted = Pubkey::new_unique();
+        let params = serde_json::to_string(&[vec![whitelisted]]).unwrap();
+        let request = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"setRepairWhitelist","params":{params}}}"#
+        );
+        io.handle_request_sync(&request, meta.clone())
+            .expect("actual response");
+
+        // The snapshot reflects the whitelist set through the admin RPC
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"repairStateSnapshot","params":[]}"#;
+        let response = io.handle_request_sync(request, meta);
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        let snapshot: RepairStateSnapshot =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        assert_eq!(snapshot.repair_whitelist, vec![whitelisted]);
+        assert_eq!(snapshot.num_outstanding_requests, 0);
+        assert!(snapshot.outstanding_requests.is_empty());
+        assert!(snapshot.slot_claims.is_empty());
+    }
+
+    #[test]
+    fn test_set_repair_validators() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let RpcHandler { io, meta, .. } = rpc;
+        let repair_validators = meta
+            .post_init
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .repair_validators
+            .clone();
+        assert_eq!(*repair_validators.read().unwrap(), None);
+
+        let validator = Pubkey::new_unique();
+        let params = serde_json::to_string(&[vec![validator]]).unwrap();
+        let request = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"setRepairValidators","params":{params}}}"#
+        );
+        io.handle_request_sync(&request, meta.clone())
+            .expect("actual response");
+        assert_eq!(
+            *repair_validators.read().unwrap(),
+            Some(HashSet::from([validator]))
+        );
+
+        // An empty set repairs from all validators
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"setRepairValidators","params":[[]]}"#;
+        io.handle_request_sync(request, meta)
+            .expect("actual response");
+        assert_eq!(*repair_validators.read().unwrap(), None);
+    }
+
     #[test]
     fn test_is_generating_snapshots() {
         // Test with snapshots enabled
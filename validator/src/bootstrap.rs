@@ -1,9 +1,10 @@
 use {
+    self::snapshot_sources::{SnapshotSourceRank, SnapshotSources},
     agave_snapshots::{
-        SnapshotArchiveKind, paths as snapshot_paths,
-        snapshot_archive_info::SnapshotArchiveInfoGetter as _,
+        ArchiveFormat, SnapshotArchiveKind, ZstdConfig, error::VerifySnapshotArchiveError,
+        paths as snapshot_paths, snapshot_archive_info::SnapshotArchiveInfoGetter as _,
+        verify_snapshot_archive,
     },
-    itertools::Itertools,
     log::*,
     rand::{Rng, rng, seq::SliceRandom},
     rayon::prelude::*,
@@ -12,6 +13,7 @@ use {
     solana_commitment_config::CommitmentConfig,
     solana_core::validator::{ValidatorConfig, ValidatorStartProgress},
     solana_download_utils::{DownloadProgressRecord, download_snapshot_archive},
+    solana_genesis_config::DEFAULT_GENESIS_ARCHIVE,
     solana_genesis_utils::download_then_check_genesis_hash,
     solana_gossip::{
         cluster_info::ClusterInfo,
@@ -26,10 +28,13 @@ use {
     solana_net_utils::SocketAddrSpace,
     solana_pubkey::Pubkey,
     solana_rpc_client::rpc_client::RpcClient,
+    solana_runtime::snapshot_utils,
     solana_signer::Signer,
     solana_vote_program::vote_state::VoteStateV4,
     std::{
         collections::{HashMap, HashSet, hash_map::RandomState},
+        fs,
+        io::{self, BufRead, BufReader, Read, Write},
         net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
         path::Path,
         process::exit,
@@ -42,6 +47,8 @@ use {
     thiserror::Error,
 };
 
+mod snapshot_sources;
+
 /// When downloading snapshots, wait at most this long for snapshot hashes from
 /// _all_ known validators.  Afterwards, wait for snapshot hashes from _any_
 /// known validator.
@@ -57,7 +64,14 @@ const GET_RPC_PEERS_TIMEOUT: Duration = Duration::from_secs(300);
 
 pub const MAX_RPC_CONNECTIONS_EVALUATED_PER_ITERATION: usize = 32;
 
-pub const PING_TIMEOUT: Duration = Duration::from_secs(2);
+/// How much of an archive to download from each RPC node to measure its
+/// download throughput
+const THROUGHPUT_PROBE_SIZE: u64 = 1024 * 1024;
+/// Maximum time to measure the download throughput of an RPC node for
+const THROUGHPUT_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Number of times the download throughput of an RPC node is measured before
+/// it is ranked as the slowest
+const THROUGHPUT_PROBE_ATTEMPTS: usize = 3;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct RpcBootstrapConfig {
@@ -299,6 +313,26 @@ fn check_vote_account(
     Ok(())
 }
 
+#[derive(Error, Debug)]
+pub enum DownloadSnapshotError {
+    #[error("{0}")]
+    Other(String),
+
+    #[error("snapshot archive for slot {slot} from {rpc_addr} failed verification: {err}")]
+    InvalidArchive {
+        slot: Slot,
+        rpc_addr: SocketAddr,
+        #[source]
+        err: VerifySnapshotArchiveError,
+    },
+}
+
+impl From<String> for DownloadSnapshotError {
+    fn from(err: String) -> Self {
+        Self::Other(err)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum GetRpcNodeError {
     #[error("Unable to find any RPC peers")]
@@ -371,10 +405,11 @@ pub fn attempt_download_genesis_and_snapshot(
     maximum_snapshot_download_abort: u64,
     download_abort_count: &mut u64,
     snapshot_hash: Option<SnapshotHash>,
+    attempt: usize,
     identity_keypair: &Arc<Keypair>,
     vote_account: &Pubkey,
     authorized_voter_keypairs: Arc<RwLock<Vec<Arc<Keypair>>>>,
-) -> Result<(), String> {
+) -> Result<(), DownloadSnapshotError> {
     download_then_check_genesis_hash(
         &rpc_contact_info
             .rpc()
@@ -407,6 +442,7 @@ pub fn attempt_download_genesis_and_snapshot(
         download_abort_count,
         snapshot_hash,
         rpc_contact_info,
+        attempt,
     )?;
 
     if let Some(url) = bootstrap_config.check_vote_account.as_ref() {
@@ -436,20 +472,103 @@ pub fn attempt_download_genesis_and_snapshot(
     Ok(())
 }
 
-/// simple ping helper function which returns the time to connect
-fn ping(addr: &SocketAddr) -> Option<Duration> {
+/// Measures the download throughput of the RPC node at `rpc_addr`, in bytes
+/// per second, by downloading the start of the archive bootstrap would download
+/// from it: the full snapshot archive it advertises, or the genesis archive if
+/// it does not advertise any snapshots. Retries up to
+/// `THROUGHPUT_PROBE_ATTEMPTS` times.
+fn probe_download_throughput(
+    rpc_addr: &SocketAddr,
+    snapshot_hash: Option<&SnapshotHash>,
+) -> Option<u64> {
+    let archive_names = match snapshot_hash {
+        Some(snapshot_hash) => {
+            let (slot, hash) = snapshot_hash.full;
+            let hash = agave_snapshots::snapshot_hash::SnapshotHash(hash);
+            [
+                ArchiveFormat::TarZstd {
+                    config: ZstdConfig::default(),
+                },
+                ArchiveFormat::TarLz4,
+            ]
+            .map(|archive_format| {
+                snapshot_paths::build_full_snapshot_archive_path("", slot, &hash, archive_format)
+                    .display()
+                    .to_string()
+            })
+            .to_vec()
+        }
+        None => vec![DEFAULT_GENESIS_ARCHIVE.to_string()],
+    };
+    (0..THROUGHPUT_PROBE_ATTEMPTS).find_map(|_| {
+        archive_names.iter().find_map(|archive_name| {
+            download_throughput(rpc_addr, archive_name)
+                .inspect_err(|err| {
+                    debug!("Failed to download {archive_name} from {rpc_addr}: {err}")
+                })
+                .ok()
+        })
+    })
+}
+
+/// Downloads up to `THROUGHPUT_PROBE_SIZE` bytes of `archive_name` from
+/// `rpc_addr` within `THROUGHPUT_PROBE_TIMEOUT`, and returns the throughput of
+/// the download in bytes per second
+fn download_throughput(rpc_addr: &SocketAddr, archive_name: &str) -> io::Result<u64> {
     let start = Instant::now();
-    match TcpStream::connect_timeout(addr, PING_TIMEOUT) {
-        Ok(_) => Some(start.elapsed()),
-        Err(_) => None,
+    let mut stream = TcpStream::connect_timeout(rpc_addr, THROUGHPUT_PROBE_TIMEOUT)?;
+    stream.set_read_timeout(Some(THROUGHPUT_PROBE_TIMEOUT))?;
+    write!(
+        stream,
+        "GET /{archive_name} HTTP/1.1\r\nHost: {rpc_addr}\r\nConnection: close\r\n\r\n"
+    )?;
+
+    let mut response = BufReader::new(stream);
+    let mut line = String::new();
+    response.read_line(&mut line)?;
+    if line.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::other(format!(
+            "unexpected response: {}",
+            line.trim_end()
+        )));
+    }
+    // Skip the headers up to the blank line ending them
+    line.clear();
+    while response.read_line(&mut line)? > "\r\n".len() {
+        line.clear();
     }
+
+    let mut body = response.take(THROUGHPUT_PROBE_SIZE);
+    let mut buf = vec![0; 64 * 1024];
+    let mut size = 0;
+    while start.elapsed() < THROUGHPUT_PROBE_TIMEOUT {
+        match body.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => size += len,
+            // A stalled download ends the measurement
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                break;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    if size == 0 {
+        return Err(io::Error::other("empty archive"));
+    }
+    Ok((size as f64 / start.elapsed().as_secs_f64()) as u64)
 }
 
-// Populates `vetted_rpc_nodes` with a list of RPC nodes that are ready to be
-// used for downloading latest snapshots and/or the genesis block. Guaranteed to
-// find at least one viable node or terminate the process.
+// Populates `vetted_rpc_nodes` with the RPC nodes that are ready to be used for
+// downloading latest snapshots and/or the genesis block, ranked by how good a
+// snapshot source they are. Guaranteed to find at least one viable node or
+// terminate the process.
 fn get_vetted_rpc_nodes(
-    vetted_rpc_nodes: &mut Vec<(ContactInfo, Option<SnapshotHash>, RpcClient)>,
+    vetted_rpc_nodes: &mut SnapshotSources<(ContactInfo, Option<SnapshotHash>, RpcClient)>,
     cluster_info: &Arc<ClusterInfo>,
     validator_config: &ValidatorConfig,
     blacklisted_rpc_nodes: &mut HashSet<Pubkey>,
@@ -490,28 +609,41 @@ fn get_vetted_rpc_nodes(
                     );
 
                     let rpc_addr = rpc_contact_info.rpc()?;
-                    let ping_time = ping(&rpc_addr);
+                    // A node whose throughput cannot be measured may still
+                    // serve the download, so it is only ranked last
+                    let download_throughput =
+                        probe_download_throughput(&rpc_addr, snapshot_hash.as_ref())
+                            .unwrap_or_else(|| {
+                                warn!(
+                                    "Failed to measure the download throughput of RPC node \
+                                     {rpc_addr}"
+                                );
+                                0
+                            });
 
                     let rpc_client =
                         RpcClient::new_socket_with_timeout(rpc_addr, Duration::from_secs(5));
 
-                    Some((rpc_contact_info, snapshot_hash, rpc_client, ping_time))
+                    Some((
+                        rpc_contact_info,
+                        snapshot_hash,
+                        rpc_client,
+                        download_throughput,
+                    ))
                 })
                 .filter(
-                    |(rpc_contact_info, _snapshot_hash, rpc_client, ping_time)| match rpc_client
-                        .get_version()
-                    {
-                        Ok(rpc_version) => {
-                            if let Some(ping_time) = ping_time {
+                    |(rpc_contact_info, _snapshot_hash, rpc_client, download_throughput)| {
+                        match rpc_client.get_version() {
+                            Ok(rpc_version) => {
                                 info!(
-                                    "RPC node version: {} Ping: {}ms",
-                                    rpc_version.solana_core,
-                                    ping_time.as_millis()
+                                    "RPC node version: {} Download throughput: {} bytes/sec",
+                                    rpc_version.solana_core, download_throughput
                                 );
                                 true
-                            } else {
+                            }
+                            Err(err) => {
                                 fail_rpc_node(
-                                    "Failed to ping RPC".to_string(),
+                                    format!("Failed to get RPC node version: {err}"),
                                     &validator_config.known_validators,
                                     rpc_contact_info.pubkey(),
                                     &mut newly_blacklisted_rpc_nodes.write().unwrap(),
@@ -519,29 +651,32 @@ fn get_vetted_rpc_nodes(
                                 false
                             }
                         }
-                        Err(err) => {
-                            fail_rpc_node(
-                                format!("Failed to get RPC node version: {err}"),
-                                &validator_config.known_validators,
-                                rpc_contact_info.pubkey(),
-                                &mut newly_blacklisted_rpc_nodes.write().unwrap(),
-                            );
-                            false
-                        }
                     },
                 )
-                .collect::<Vec<(
-                    ContactInfo,
-                    Option<SnapshotHash>,
-                    RpcClient,
-                    Option<Duration>,
-                )>>()
+                .collect::<Vec<(ContactInfo, Option<SnapshotHash>, RpcClient, u64)>>()
                 .into_iter()
-                .sorted_by_key(|(_, _, _, ping_time)| ping_time.unwrap())
-                .map(|(rpc_contact_info, snapshot_hash, rpc_client, _)| {
-                    (rpc_contact_info, snapshot_hash, rpc_client)
-                })
-                .collect::<Vec<(ContactInfo, Option<SnapshotHash>, RpcClient)>>(),
+                .map(
+                    |(rpc_contact_info, snapshot_hash, rpc_client, download_throughput)| {
+                        let rank = SnapshotSourceRank {
+                            is_known_validator: is_known_validator(
+                                rpc_contact_info.pubkey(),
+                                &validator_config.known_validators,
+                            ),
+                            snapshot_slot: snapshot_hash.map(|snapshot_hash| {
+                                snapshot_hash
+                                    .incr
+                                    .map(|(slot, _hash)| slot)
+                                    .unwrap_or(snapshot_hash.full.0)
+                            }),
+                            download_throughput,
+                        };
+                        (
+                            *rpc_contact_info.pubkey(),
+                            rank,
+                            (rpc_contact_info, snapshot_hash, rpc_client),
+                        )
+                    },
+                ),
         );
         blacklisted_rpc_nodes.extend(newly_blacklisted_rpc_nodes.into_inner().unwrap());
     }
@@ -589,7 +724,7 @@ pub fn rpc_bootstrap(
     let mut snapshot_download_time = Duration::new(0, 0);
     let mut blacklisted_rpc_nodes = HashSet::new();
     let mut gossip = None;
-    let mut vetted_rpc_nodes = vec![];
+    let mut vetted_rpc_nodes = SnapshotSources::default();
     let mut download_abort_count = 0;
     loop {
         if gossip.is_none() {
@@ -622,7 +757,8 @@ pub fn rpc_bootstrap(
             &mut blacklisted_rpc_nodes,
             &bootstrap_config,
         );
-        let (rpc_contact_info, snapshot_hash, rpc_client) = vetted_rpc_nodes.pop().unwrap();
+        let ((rpc_contact_info, snapshot_hash, rpc_client), attempt) =
+            vetted_rpc_nodes.next().unwrap();
         get_rpc_nodes_time += get_rpc_nodes_start.elapsed();
 
         let snapshot_download_start = Instant::now();
@@ -640,6 +776,7 @@ pub fn rpc_bootstrap(
             maximum_snapshot_download_abort,
             &mut download_abort_count,
             snapshot_hash,
+            attempt,
             identity_keypair,
             vote_account,
             authorized_voter_keypairs.clone(),
@@ -647,9 +784,16 @@ pub fn rpc_bootstrap(
         snapshot_download_time += snapshot_download_start.elapsed();
         match download_result {
             Ok(()) => break,
+            Err(err @ DownloadSnapshotError::InvalidArchive { .. }) => {
+                // Even known validators are excluded, as they would serve the
+                // same archive again
+                warn!("{err}");
+                vetted_rpc_nodes.blacklist(*rpc_contact_info.pubkey());
+                blacklisted_rpc_nodes.insert(*rpc_contact_info.pubkey());
+            }
             Err(err) => {
                 fail_rpc_node(
-                    err,
+                    err.to_string(),
                     &validator_config.known_validators,
                     rpc_contact_info.pubkey(),
                     &mut blacklisted_rpc_nodes,
@@ -1103,7 +1247,8 @@ fn download_snapshots(
     download_abort_count: &mut u64,
     snapshot_hash: Option<SnapshotHash>,
     rpc_contact_info: &ContactInfo,
-) -> Result<(), String> {
+    attempt: usize,
+) -> Result<(), DownloadSnapshotError> {
     if snapshot_hash.is_none() {
         return Ok(());
     }
@@ -1149,6 +1294,7 @@ fn download_snapshots(
             maximum_snapshot_download_abort,
             download_abort_count,
             rpc_contact_info,
+            attempt,
             full_snapshot_hash,
             SnapshotArchiveKind::Full,
         )?;
@@ -1179,6 +1325,7 @@ fn download_snapshots(
                     maximum_snapshot_download_abort,
                     download_abort_count,
                     rpc_contact_info,
+                    attempt,
                     incremental_snapshot_hash,
                     SnapshotArchiveKind::Incremental(full_snapshot_hash.0),
                 )?;
@@ -1189,7 +1336,7 @@ fn download_snapshots(
     Ok(())
 }
 
-/// Download a snapshot
+/// Download a snapshot, and verify the downloaded archive before it is unpacked
 #[allow(clippy::too_many_arguments)]
 fn download_snapshot(
     validator_config: &ValidatorConfig,
@@ -1200,9 +1347,10 @@ fn download_snapshot(
     maximum_snapshot_download_abort: u64,
     download_abort_count: &mut u64,
     rpc_contact_info: &ContactInfo,
+    attempt: usize,
     desired_snapshot_hash: (Slot, Hash),
     snapshot_kind: SnapshotArchiveKind,
-) -> Result<(), String> {
+) -> Result<(), DownloadSnapshotError> {
     let maximum_full_snapshot_archives_to_retain = validator_config
         .snapshot_config
         .maximum_full_snapshot_archives_to_retain;
@@ -1214,20 +1362,20 @@ fn download_snapshot(
         .snapshot_config
         .incremental_snapshot_archives_dir;
 
+    let rpc_addr = rpc_contact_info
+        .rpc()
+        .ok_or_else(|| String::from("Invalid RPC address"))?;
     *start_progress.write().unwrap() = ValidatorStartProgress::DownloadingSnapshot {
         slot: desired_snapshot_hash.0,
-        rpc_addr: rpc_contact_info
-            .rpc()
-            .ok_or_else(|| String::from("Invalid RPC address"))?,
+        rpc_addr,
+        attempt,
     };
     let desired_snapshot_hash = (
         desired_snapshot_hash.0,
         agave_snapshots::snapshot_hash::SnapshotHash(desired_snapshot_hash.1),
     );
     download_snapshot_archive(
-        &rpc_contact_info
-            .rpc()
-            .ok_or_else(|| String::from("Invalid RPC address"))?,
+        &rpc_addr,
         full_snapshot_archives_dir,
         incremental_snapshot_archives_dir,
         desired_snapshot_hash,
@@ -1275,7 +1423,49 @@ fn download_snapshot(
                 true
             }
         })),
-    )
+    )?;
+
+    // The archive is named after the snapshot hash advertised in gossip, so
+    // make sure it is an archive of the advertised snapshot, with that hash
+    let (slot, hash) = desired_snapshot_hash;
+    let archive = match snapshot_kind {
+        SnapshotArchiveKind::Full => {
+            snapshot_paths::full_snapshot_archives_iter(full_snapshot_archives_dir)
+                .find(|archive| archive.slot() == slot && *archive.hash() == hash)
+                .map(|archive| archive.snapshot_archive_info().clone())
+        }
+        SnapshotArchiveKind::Incremental(base_slot) => {
+            snapshot_paths::incremental_snapshot_archives_iter(incremental_snapshot_archives_dir)
+                .find(|archive| {
+                    archive.base_slot() == base_slot
+                        && archive.slot() == slot
+                        && *archive.hash() == hash
+                })
+                .map(|archive| archive.snapshot_archive_info().clone())
+        }
+    }
+    .ok_or_else(|| format!("Downloaded snapshot archive for slot {slot} not found"))?;
+    if let Err(err) = verify_snapshot_archive(
+        &archive.path,
+        archive.archive_format,
+        slot,
+        hash,
+        |bank_snapshot| snapshot_utils::snapshot_hash_from_bank_snapshot(bank_snapshot),
+    ) {
+        // Remove the archive, so that it is downloaded again from another peer
+        if let Err(err) = fs::remove_file(&archive.path) {
+            warn!(
+                "Failed to remove snapshot archive {}: {err}",
+                archive.path.display()
+            );
+        }
+        return Err(DownloadSnapshotError::InvalidArchive {
+            slot,
+            rpc_addr,
+            err,
+        });
+    }
+    Ok(())
 }
 
 /// Check to see if bootstrap should load from its local snapshots or not.  If not, then snapshots
@@ -1341,7 +1531,17 @@ fn get_snapshot_hashes_for_node(cluster_info: &ClusterInfo, node: &Pubkey) -> Op
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        agave_snapshots::snapshot_config::SnapshotConfig,
+        assert_matches::assert_matches,
+        solana_runtime::{bank::Bank, genesis_utils::create_genesis_config, snapshot_bank_utils},
+        std::{
+            net::Ipv4Addr,
+            thread::{self, JoinHandle},
+        },
+        tempfile::TempDir,
+    };
 
     impl PeerSnapshotHash {
         fn new(
@@ -1363,6 +1563,160 @@ mod tests {
         ContactInfo::new_localhost(&Pubkey::default(), /*now:*/ 1_681_834_947_321)
     }
 
+    /// Responds to a single HTTP request with `body`
+    fn serve_once(body: Vec<u8>) -> (SocketAddr, JoinHandle<()>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // Skip the request up to the blank line ending its headers
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > "\r\n".len() {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        (addr, server)
+    }
+
+    /// Builds a full snapshot archive of a new bank, returning the snapshot
+    /// hash of the archive and its contents
+    fn snapshot_archive_for_tests(mint_lamports: u64) -> ((Slot, Hash), Vec<u8>) {
+        let genesis_config = create_genesis_config(mint_lamports).genesis_config;
+        let bank = Bank::new_for_tests(&genesis_config);
+        bank.fill_bank_with_ticks_for_tests();
+        bank.set_block_id(Some(Hash::default()));
+        let snapshot_dir = TempDir::new().unwrap();
+        let snapshot_config = SnapshotConfig {
+            full_snapshot_archives_dir: snapshot_dir.path().to_path_buf(),
+            incremental_snapshot_archives_dir: snapshot_dir.path().to_path_buf(),
+            bank_snapshots_dir: snapshot_dir.path().to_path_buf(),
+            ..SnapshotConfig::default()
+        };
+        let archive =
+            snapshot_bank_utils::bank_to_full_snapshot_archive(&snapshot_config, &bank).unwrap();
+        (
+            (archive.slot(), archive.hash().0),
+            fs::read(archive.path()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_download_snapshot_falls_back_from_invalid_archive() {
+        agave_logger::setup();
+        let snapshot_archives_dir = TempDir::new().unwrap();
+        let mut validator_config = ValidatorConfig::default_for_test();
+        validator_config.snapshot_config.full_snapshot_archives_dir =
+            snapshot_archives_dir.path().to_path_buf();
+        validator_config
+            .snapshot_config
+            .incremental_snapshot_archives_dir = snapshot_archives_dir.path().to_path_buf();
+        let bootstrap_config = RpcBootstrapConfig {
+            no_genesis_fetch: true,
+            no_snapshot_fetch: false,
+            only_known_rpc: false,
+            max_genesis_archive_unpacked_size: 0,
+            check_vote_account: None,
+            incremental_snapshot_fetch: false,
+        };
+        let start_progress = Arc::default();
+        let mut download_abort_count = 0;
+        let (full_snapshot_hash, archive) = snapshot_archive_for_tests(1_000_000);
+        // An archive of another bank, served under the name of the advertised
+        // snapshot
+        let (_other_snapshot_hash, other_archive) = snapshot_archive_for_tests(2_000_000);
+
+        let (corrupt_rpc_addr, corrupt_server) = serve_once(other_archive);
+        let (rpc_addr, server) = serve_once(archive);
+        let contact_info = |rpc_addr| {
+            let mut contact_info = ContactInfo::new_localhost(&Pubkey::new_unique(), 0);
+            contact_info.set_rpc(rpc_addr).unwrap();
+            contact_info
+        };
+        let corrupt_peer = contact_info(corrupt_rpc_addr);
+        let peer = contact_info(rpc_addr);
+        let mut sources = SnapshotSources::default();
+        sources.extend([
+            // The peer serving the archive of another bank is ranked first
+            (
+                *corrupt_peer.pubkey(),
+                SnapshotSourceRank {
+                    is_known_validator: true,
+                    snapshot_slot: Some(full_snapshot_hash.0),
+                    download_throughput: 0,
+                },
+                corrupt_peer,
+            ),
+            (
+                *peer.pubkey(),
+                SnapshotSourceRank {
+                    is_known_validator: false,
+                    snapshot_slot: Some(full_snapshot_hash.0),
+                    download_throughput: 0,
+                },
+                peer,
+            ),
+        ]);
+        let mut download = |rpc_contact_info: &ContactInfo, attempt| {
+            download_snapshot(
+                &validator_config,
+                &bootstrap_config,
+                false,
+                &start_progress,
+                0.0,
+                0,
+                &mut download_abort_count,
+                rpc_contact_info,
+                attempt,
+                full_snapshot_hash,
+                SnapshotArchiveKind::Full,
+            )
+        };
+
+        let (rpc_contact_info, attempt) = sources.next().unwrap();
+        assert_matches!(
+            download(&rpc_contact_info, attempt),
+            Err(DownloadSnapshotError::InvalidArchive {
+                slot: 0,
+                rpc_addr,
+                err: VerifySnapshotArchiveError::MismatchedHash { expected, .. },
+            }) if rpc_addr == corrupt_rpc_addr && expected.0 == full_snapshot_hash.1
+        );
+        sources.blacklist(*rpc_contact_info.pubkey());
+        // The invalid archive is not left behind
+        assert_eq!(
+            snapshot_paths::full_snapshot_archives_iter(snapshot_archives_dir.path()).count(),
+            0
+        );
+
+        let (rpc_contact_info, attempt) = sources.next().unwrap();
+        download(&rpc_contact_info, attempt).unwrap();
+        assert_eq!(
+            *start_progress.read().unwrap(),
+            ValidatorStartProgress::DownloadingSnapshot {
+                slot: 0,
+                rpc_addr,
+                attempt: 2,
+            }
+        );
+        let archive =
+            snapshot_paths::get_highest_full_snapshot_archive_info(snapshot_archives_dir.path())
+                .unwrap();
+        assert_eq!(archive.slot(), full_snapshot_hash.0);
+        assert_eq!(archive.hash().0, full_snapshot_hash.1);
+        assert!(sources.next().is_none());
+
+        corrupt_server.join().unwrap();
+        server.join().unwrap();
+    }
+
     #[test]
     fn test_build_known_snapshot_hashes() {
         agave_logger::setup();
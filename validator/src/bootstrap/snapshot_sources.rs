@@ -0,0 +1,176 @@
+//! Picks the peers to download snapshots from during bootstrap.
+//!
+//! Candidates are ranked by whether they are known validators, then by the
+//! slot of the snapshots they advertise in gossip, then by the throughput of a
+//! short download from their RPC port. A peer that serves a snapshot archive
+//! which fails verification is excluded for the rest of the boot.
+
+use {
+    log::*,
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    std::{cmp::Reverse, collections::HashSet},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct SnapshotSourceRank {
+    pub(super) is_known_validator: bool,
+    /// Highest slot of the snapshots the peer advertises, if any
+    pub(super) snapshot_slot: Option<Slot>,
+    /// Throughput of downloading from the RPC port of the peer, in bytes per
+    /// second
+    pub(super) download_throughput: u64,
+}
+
+impl SnapshotSourceRank {
+    /// Orders the better ranked sources first
+    fn sort_key(&self) -> (Reverse<bool>, Reverse<Option<Slot>>, Reverse<u64>) {
+        (
+            Reverse(self.is_known_validator),
+            Reverse(self.snapshot_slot),
+            Reverse(self.download_throughput),
+        )
+    }
+}
+
+pub(super) struct SnapshotSources<T> {
+    /// The candidates, the best ranked last
+    candidates: Vec<(Pubkey, SnapshotSourceRank, T)>,
+    blacklist: HashSet<Pubkey>,
+    num_attempts: usize,
+}
+
+impl<T> Default for SnapshotSources<T> {
+    fn default() -> Self {
+        Self {
+            candidates: Vec::default(),
+            blacklist: HashSet::default(),
+            num_attempts: 0,
+        }
+    }
+}
+
+impl<T> SnapshotSources<T> {
+    pub(super) fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Adds the candidates that are not blacklisted
+    pub(super) fn extend(
+        &mut self,
+        candidates: impl IntoIterator<Item = (Pubkey, SnapshotSourceRank, T)>,
+    ) {
+        let blacklist = &self.blacklist;
+        self.candidates.extend(
+            candidates
+                .into_iter()
+                .filter(|(pubkey, _rank, _candidate)| !blacklist.contains(pubkey)),
+        );
+        self.candidates
+            .sort_by_key(|(_pubkey, rank, _candidate)| Reverse(rank.sort_key()));
+    }
+
+    /// Takes the best ranked candidate, along with the number of candidates
+    /// taken so far, counting this one
+    pub(super) fn next(&mut self) -> Option<(T, usize)> {
+        let (_pubkey, _rank, candidate) = self.candidates.pop()?;
+        self.num_attempts += 1;
+        Some((candidate, self.num_attempts))
+    }
+
+    /// Excludes the peer for the rest of the boot
+    pub(super) fn blacklist(&mut self, pubkey: Pubkey) {
+        info!("Excluding {pubkey} as a snapshot source for the rest of the boot");
+        self.blacklist.insert(pubkey);
+        self.candidates
+            .retain(|(candidate_pubkey, _rank, _candidate)| *candidate_pubkey != pubkey);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank(
+        is_known_validator: bool,
+        snapshot_slot: Option<Slot>,
+        download_throughput: u64,
+    ) -> SnapshotSourceRank {
+        SnapshotSourceRank {
+            is_known_validator,
+            snapshot_slot,
+            download_throughput,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_sources_ranking() {
+        let mut sources = SnapshotSources::default();
+        sources.extend([
+            (Pubkey::new_unique(), rank(false, Some(200), 50), "unknown"),
+            (Pubkey::new_unique(), rank(true, Some(100), 20), "known old"),
+            (
+                Pubkey::new_unique(),
+                rank(true, Some(200), 20),
+                "known slow",
+            ),
+            (
+                Pubkey::new_unique(),
+                rank(true, None, 100),
+                "known no snapshot",
+            ),
+            (
+                Pubkey::new_unique(),
+                rank(true, Some(200), 50),
+                "known fast",
+            ),
+        ]);
+        // Candidates added later are ranked along with the others
+        sources.extend([(
+            Pubkey::new_unique(),
+            rank(false, Some(300), 100),
+            "unknown new",
+        )]);
+
+        let ranked: Vec<_> = std::iter::from_fn(|| sources.next()).collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("known fast", 1),
+                ("known slow", 2),
+                ("known old", 3),
+                ("known no snapshot", 4),
+                ("unknown new", 5),
+                ("unknown", 6),
+            ]
+        );
+        assert!(sources.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_sources_blacklist() {
+        let corrupt = Pubkey::new_unique();
+        let mut sources = SnapshotSources::default();
+        sources.extend([
+            (corrupt, rank(true, Some(200), 10), "corrupt"),
+            (Pubkey::new_unique(), rank(false, Some(200), 10), "fallback"),
+        ]);
+        assert_eq!(sources.next(), Some(("corrupt", 1)));
+        sources.blacklist(corrupt);
+
+        // The blacklisted peer is not taken again, even if it is found again
+        sources.extend([(corrupt, rank(true, Some(300), 1), "corrupt")]);
+        assert_eq!(sources.next(), Some(("fallback", 2)));
+        assert_eq!(sources.next(), None);
+
+        // Blacklisting drops the peer from the remaining candidates
+        let other = Pubkey::new_unique();
+        sources.extend([
+            (other, rank(true, Some(200), 10), "other"),
+            (Pubkey::new_unique(), rank(false, Some(200), 10), "last"),
+        ]);
+        sources.blacklist(other);
+        assert_eq!(sources.next(), Some(("last", 3)));
+        assert!(sources.is_empty());
+    }
+}
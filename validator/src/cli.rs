@@ -22,7 +22,13 @@ use {
         },
     },
     solana_clock::Slot,
-    solana_core::banking_trace::BANKING_TRACE_DIR_DEFAULT_BYTE_LIMIT,
+    solana_core::{
+        banking_trace::BANKING_TRACE_DIR_DEFAULT_BYTE_LIMIT,
+        cluster_info_vote_listener::DEFAULT_DUPLICATE_CONFIRMED_SLOTS_CHANNEL_CAPACITY,
+        repair::repair_handler::DEFAULT_REPAIR_SERVE_BLOCKSTORE_CATCH_UP_INTERVAL,
+        tpu::{TPU_CHANNEL_SIZE, TPU_FORWARD_CHANNEL_SIZE, TPU_VOTE_CHANNEL_SIZE},
+        vote_tracker_archive::DEFAULT_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES,
+    },
     solana_epoch_schedule::MINIMUM_SLOTS_PER_EPOCH,
     solana_faucet::faucet::{self, FAUCET_PORT},
     solana_hash::Hash,
@@ -277,6 +283,10 @@ pub struct DefaultArgs {
     pub tpu_max_fwd_staked_connections: String,
     pub tpu_max_fwd_unstaked_connections: String,
     pub tpu_max_streams_per_ms: String,
+    pub tpu_channel_size: String,
+    pub tpu_fwd_channel_size: String,
+    pub tpu_vote_channel_size: String,
+    pub tpu_unthrottled_top_stake_peers: String,
 
     pub num_quic_endpoints: String,
     pub vote_use_quic: String,
@@ -284,6 +294,15 @@ pub struct DefaultArgs {
     pub banking_trace_dir_byte_limit: String,
     pub block_production_pacing_fill_time_millis: String,
 
+    pub duplicate_confirmed_slots_channel_capacity: String,
+
+    pub gossip_export_interval_secs: String,
+    pub gossip_export_max_files: String,
+
+    pub vote_tracker_archive_max_file_bytes: String,
+
+    pub repair_serve_blockstore_catch_up_interval_ms: String,
+
     pub thread_args: DefaultThreadArgs,
 }
 
@@ -331,10 +350,24 @@ impl DefaultArgs {
                 .to_string(),
             tpu_max_fwd_unstaked_connections: 0.to_string(),
             tpu_max_streams_per_ms: DEFAULT_MAX_STREAMS_PER_MS.to_string(),
+            tpu_channel_size: TPU_CHANNEL_SIZE.to_string(),
+            tpu_fwd_channel_size: TPU_FORWARD_CHANNEL_SIZE.to_string(),
+            tpu_vote_channel_size: TPU_VOTE_CHANNEL_SIZE.to_string(),
+            tpu_unthrottled_top_stake_peers: 0.to_string(),
             num_quic_endpoints: DEFAULT_QUIC_ENDPOINTS.to_string(),
             banking_trace_dir_byte_limit: BANKING_TRACE_DIR_DEFAULT_BYTE_LIMIT.to_string(),
             block_production_pacing_fill_time_millis: BankingStage::default_fill_time_millis()
                 .to_string(),
+            duplicate_confirmed_slots_channel_capacity:
+                DEFAULT_DUPLICATE_CONFIRMED_SLOTS_CHANNEL_CAPACITY.to_string(),
+            gossip_export_interval_secs: "600".to_string(),
+            gossip_export_max_files: "144".to_string(),
+            vote_tracker_archive_max_file_bytes: DEFAULT_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES
+                .to_string(),
+            repair_serve_blockstore_catch_up_interval_ms:
+                DEFAULT_REPAIR_SERVE_BLOCKSTORE_CATCH_UP_INTERVAL
+                    .as_millis()
+                    .to_string(),
             thread_args: DefaultThreadArgs::default(),
         }
     }
@@ -4,7 +4,9 @@ use {
     clap::{Arg, ArgMatches, value_t_or_exit},
     solana_accounts_db::{accounts_db, accounts_index},
     solana_clap_utils::{hidden_unless_forced, input_validators::is_within_range},
-    solana_core::banking_stage::BankingStage,
+    solana_core::{
+        banking_stage::BankingStage, snapshot_packager_service::SnapshotPackagerService,
+    },
     solana_rayon_threadlimit::get_thread_count,
     std::{num::NonZeroUsize, ops::RangeInclusive},
 };
@@ -13,12 +15,15 @@ use {
 pub struct DefaultThreadArgs {
     pub accounts_db_background_threads: String,
     pub accounts_db_foreground_threads: String,
+    pub accounts_db_startup_hash_threads: String,
+    pub accounts_db_steady_state_hash_threads: String,
     pub accounts_index_flush_threads: String,
     pub block_production_num_workers: String,
     pub ip_echo_server_threads: String,
     pub rayon_global_threads: String,
     pub replay_forks_threads: String,
     pub replay_transactions_threads: String,
+    pub snapshot_packaging_threads: String,
     pub tpu_sigverify_threads: String,
     pub tpu_transaction_forward_receive_threads: String,
     pub tpu_transaction_receive_threads: String,
@@ -36,6 +41,10 @@ impl Default for DefaultThreadArgs {
                 .to_string(),
             accounts_db_foreground_threads: AccountsDbForegroundThreadsArg::bounded_default()
                 .to_string(),
+            accounts_db_startup_hash_threads: AccountsDbStartupHashThreadsArg::bounded_default()
+                .to_string(),
+            accounts_db_steady_state_hash_threads:
+                AccountsDbSteadyStateHashThreadsArg::bounded_default().to_string(),
             accounts_index_flush_threads: AccountsIndexFlushThreadsArg::bounded_default()
                 .to_string(),
             block_production_num_workers: BankingStage::default_num_workers().to_string(),
@@ -44,6 +53,7 @@ impl Default for DefaultThreadArgs {
             replay_forks_threads: ReplayForksThreadsArg::bounded_default().to_string(),
             replay_transactions_threads: ReplayTransactionsThreadsArg::bounded_default()
                 .to_string(),
+            snapshot_packaging_threads: SnapshotPackagingThreadsArg::bounded_default().to_string(),
             tpu_sigverify_threads: TpuSigverifyThreadsArg::bounded_default().to_string(),
             tpu_transaction_forward_receive_threads:
                 TpuTransactionForwardReceiveThreadArgs::bounded_default().to_string(),
@@ -64,12 +74,19 @@ pub fn thread_args<'a>(defaults: &DefaultThreadArgs) -> Vec<Arg<'_, 'a>> {
     vec![
         new_thread_arg::<AccountsDbBackgroundThreadsArg>(&defaults.accounts_db_background_threads),
         new_thread_arg::<AccountsDbForegroundThreadsArg>(&defaults.accounts_db_foreground_threads),
+        new_thread_arg::<AccountsDbStartupHashThreadsArg>(
+            &defaults.accounts_db_startup_hash_threads,
+        ),
+        new_thread_arg::<AccountsDbSteadyStateHashThreadsArg>(
+            &defaults.accounts_db_steady_state_hash_threads,
+        ),
         new_thread_arg::<AccountsIndexFlushThreadsArg>(&defaults.accounts_index_flush_threads),
         new_thread_arg::<BlockProductionNumWorkersArg>(&defaults.block_production_num_workers),
         new_thread_arg::<IpEchoServerThreadsArg>(&defaults.ip_echo_server_threads),
         new_thread_arg::<RayonGlobalThreadsArg>(&defaults.rayon_global_threads),
         new_thread_arg::<ReplayForksThreadsArg>(&defaults.replay_forks_threads),
         new_thread_arg::<ReplayTransactionsThreadsArg>(&defaults.replay_transactions_threads),
+        new_thread_arg::<SnapshotPackagingThreadsArg>(&defaults.snapshot_packaging_threads),
         new_thread_arg::<TpuSigverifyThreadsArg>(&defaults.tpu_sigverify_threads),
         new_thread_arg::<TpuTransactionForwardReceiveThreadArgs>(
             &defaults.tpu_transaction_forward_receive_threads,
@@ -99,12 +116,15 @@ pub(crate) fn new_thread_arg<'a, T: ThreadArg>(default: &str) -> Arg<'_, 'a> {
 pub struct NumThreadConfig {
     pub accounts_db_background_threads: NonZeroUsize,
     pub accounts_db_foreground_threads: NonZeroUsize,
+    pub accounts_db_startup_hash_threads: NonZeroUsize,
+    pub accounts_db_steady_state_hash_threads: NonZeroUsize,
     pub accounts_index_flush_threads: NonZeroUsize,
     pub block_production_num_workers: NonZeroUsize,
     pub ip_echo_server_threads: NonZeroUsize,
     pub rayon_global_threads: NonZeroUsize,
     pub replay_forks_threads: NonZeroUsize,
     pub replay_transactions_threads: NonZeroUsize,
+    pub snapshot_packaging_threads: NonZeroUsize,
     pub tpu_sigverify_threads: NonZeroUsize,
     pub tpu_transaction_forward_receive_threads: NonZeroUsize,
     pub tpu_transaction_receive_threads: NonZeroUsize,
@@ -127,6 +147,16 @@ pub fn parse_num_threads_args(matches: &ArgMatches) -> NumThreadConfig {
             AccountsDbForegroundThreadsArg::NAME,
             NonZeroUsize
         ),
+        accounts_db_startup_hash_threads: value_t_or_exit!(
+            matches,
+            AccountsDbStartupHashThreadsArg::NAME,
+            NonZeroUsize
+        ),
+        accounts_db_steady_state_hash_threads: value_t_or_exit!(
+            matches,
+            AccountsDbSteadyStateHashThreadsArg::NAME,
+            NonZeroUsize
+        ),
         accounts_index_flush_threads: value_t_or_exit!(
             matches,
             AccountsIndexFlushThreadsArg::NAME,
@@ -149,6 +179,11 @@ pub fn parse_num_threads_args(matches: &ArgMatches) -> NumThreadConfig {
             ReplayTransactionsThreadsArg::NAME,
             NonZeroUsize
         ),
+        snapshot_packaging_threads: value_t_or_exit!(
+            matches,
+            SnapshotPackagingThreadsArg::NAME,
+            NonZeroUsize
+        ),
         tpu_sigverify_threads: value_t_or_exit!(
             matches,
             TpuSigverifyThreadsArg::NAME,
@@ -243,6 +278,30 @@ impl ThreadArg for AccountsDbForegroundThreadsArg {
     }
 }
 
+struct AccountsDbStartupHashThreadsArg;
+impl ThreadArg for AccountsDbStartupHashThreadsArg {
+    const NAME: &'static str = "accounts_db_startup_hash_threads";
+    const LONG_NAME: &'static str = "accounts-db-startup-hash-threads";
+    const HELP: &'static str =
+        "Number of threads to use for calculating the accounts hash while the validator starts up";
+
+    fn default() -> usize {
+        accounts_db::default_num_foreground_threads()
+    }
+}
+
+struct AccountsDbSteadyStateHashThreadsArg;
+impl ThreadArg for AccountsDbSteadyStateHashThreadsArg {
+    const NAME: &'static str = "accounts_db_steady_state_hash_threads";
+    const LONG_NAME: &'static str = "accounts-db-steady-state-hash-threads";
+    const HELP: &'static str =
+        "Number of threads to use for calculating the accounts hash once the validator is running";
+
+    fn default() -> usize {
+        accounts_db::quarter_thread_count()
+    }
+}
+
 struct AccountsIndexFlushThreadsArg;
 impl ThreadArg for AccountsIndexFlushThreadsArg {
     const NAME: &'static str = "accounts_index_flush_threads";
@@ -323,6 +382,17 @@ impl ThreadArg for ReplayTransactionsThreadsArg {
     }
 }
 
+struct SnapshotPackagingThreadsArg;
+impl ThreadArg for SnapshotPackagingThreadsArg {
+    const NAME: &'static str = "snapshot_packaging_threads";
+    const LONG_NAME: &'static str = "snapshot-packaging-threads";
+    const HELP: &'static str = "Number of threads to use for packaging snapshots";
+
+    fn default() -> usize {
+        SnapshotPackagerService::DEFAULT_NUM_THREADS.get()
+    }
+}
+
 struct TpuSigverifyThreadsArg;
 impl ThreadArg for TpuSigverifyThreadsArg {
     const NAME: &'static str = "tpu_sigverify_threads";
@@ -21,6 +21,7 @@ use {
     solana_core::{
         banking_trace::DirByteLimit,
         validator::{BlockProductionMethod, BlockVerificationMethod},
+        vote_tracker_archive::MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES,
     },
     solana_keypair::Keypair,
     solana_ledger::{blockstore_options::BlockstoreOptions, use_snapshot_archives_at_startup},
@@ -30,7 +31,13 @@ use {
     solana_send_transaction_service::send_transaction_service::Config as SendTransactionServiceConfig,
     solana_signer::Signer,
     solana_unified_scheduler_pool::DefaultSchedulerPool,
-    std::{collections::HashSet, net::SocketAddr, path::PathBuf, str::FromStr},
+    std::{
+        collections::HashSet,
+        net::SocketAddr,
+        num::{NonZeroU64, NonZeroUsize},
+        path::PathBuf,
+        str::FromStr,
+    },
 };
 
 const EXCLUDE_KEY: &str = "account-index-exclude-key";
@@ -284,6 +291,14 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .help(use_snapshot_archives_at_startup::cli::HELP)
             .long_help(use_snapshot_archives_at_startup::cli::LONG_HELP),
     )
+    .arg(
+        Arg::with_name("preserve_bank_snapshots_at_startup")
+            .long("preserve-bank-snapshots-at-startup")
+            .help(
+                "Keep the incomplete and old bank snapshots found at startup, e.g. to inspect \
+                 them after a crash, instead of purging them",
+            ),
+    )
     .arg(
         Arg::with_name("full_snapshot_archive_path")
             .long("full-snapshot-archive-path")
@@ -461,6 +476,13 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
                  priority, positive value decreases priority.",
             ),
     )
+    .arg(
+        Arg::with_name("no_snapshot_gossip_push")
+            .long("no-snapshot-gossip-push")
+            .takes_value(false)
+            .hidden(hidden_unless_forced())
+            .help("Do not advertise the hashes of the snapshots taken by this node over gossip"),
+    )
     .arg(
         Arg::with_name("minimal_snapshot_download_speed")
             .long("minimal-snapshot-download-speed")
@@ -498,6 +520,29 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .hidden(hidden_unless_forced())
             .help("Skip the check for PoH speed."),
     )
+    .arg(
+        Arg::with_name("poh_speed_check_samples")
+            .long("poh-speed-check-samples")
+            .value_name("NUMBER")
+            .takes_value(true)
+            .hidden(hidden_unless_forced())
+            .conflicts_with("no_poh_speed_test")
+            .validator(is_non_zero)
+            .help(
+                "Number of hashes to time in the check for PoH speed. Fewer hashes make the check \
+                 faster, more make it more accurate. [default: the hashes in one slot]",
+            ),
+    )
+    .arg(
+        Arg::with_name("require_hashes_per_tick")
+            .long("require-hashes-per-tick")
+            .hidden(hidden_unless_forced())
+            .conflicts_with("no_poh_speed_test")
+            .help(
+                "Fail the check for PoH speed, instead of skipping it, if the genesis config does \
+                 not set hashes per tick.",
+            ),
+    )
     .arg(
         Arg::with_name("no_os_network_limits_test")
             .hidden(hidden_unless_forced())
@@ -543,6 +588,15 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .takes_value(false)
             .help("Skip ledger verification at validator bootup."),
     )
+    .arg(
+        Arg::with_name("skip_startup_ledger_signature_verification")
+            .long("skip-startup-ledger-signature-verification")
+            .takes_value(false)
+            .help(
+                "Skip transaction signature verification of the ledger at validator bootup, while \
+                 still verifying the ledger PoH.",
+            ),
+    )
     .arg(
         clap::Arg::with_name("require_tower")
             .long("require-tower")
@@ -612,6 +666,44 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
                  signing. Turn off to risk double signing a block.",
             ),
     )
+    .arg(
+        Arg::with_name("min_leader_slot")
+            .long("min-leader-slot")
+            .value_name("SLOT")
+            .validator(is_slot)
+            .help(
+                "Do not produce any blocks before SLOT, regardless of whether the validator has \
+                 voted",
+            ),
+    )
+    .arg(
+        Arg::with_name("max_active_banks")
+            .long("max-active-banks")
+            .takes_value(true)
+            .value_name("NUMBER")
+            .validator(is_parsable::<NonZeroUsize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "When replay has more than NUMBER banks that are not yet frozen, mark the fork \
+                 tips with the least observed vote stake dead and drop them. Protects against \
+                 floods of shreds for competing forks. [default: unlimited]",
+            ),
+    )
+    .arg(
+        Arg::with_name("precompute_leader_schedule_slots")
+            .long("precompute-leader-schedule-slots")
+            .takes_value(true)
+            .value_name("SLOTS")
+            .validator(is_parsable::<u64>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Once the root is within SLOTS slots of an epoch boundary, activate the stakes of \
+                 the next epoch and compute the next leader schedule in the background from the \
+                 root bank. The activated stakes are only reused if no stake delegation changed, \
+                 and the leader schedule is only used if it matches the epoch stakes computed at \
+                 the boundary. [default: disabled]",
+            ),
+    )
     .arg(
         Arg::with_name("hard_forks")
             .long("hard-fork")
@@ -670,6 +762,45 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
                  validators. [default: all validators]",
             ),
     )
+    .arg(
+        Arg::with_name("repair_serve_blockstore_path")
+            .hidden(hidden_unless_forced())
+            .long("repair-serve-blockstore-path")
+            .takes_value(true)
+            .value_name("DIR")
+            .help(
+                "Serve repairs from a secondary, read-only blockstore that follows the primary \
+                 blockstore, keeping its own state in DIR. Offloads reads from the primary \
+                 blockstore. Slots the secondary has not caught up to are served from the primary \
+                 blockstore. [default: disabled]",
+            ),
+    )
+    .arg(
+        Arg::with_name("repair_serve_blockstore_catch_up_interval_ms")
+            .hidden(hidden_unless_forced())
+            .long("repair-serve-blockstore-catch-up-interval-ms")
+            .takes_value(true)
+            .value_name("MILLISECONDS")
+            .default_value(&default_args.repair_serve_blockstore_catch_up_interval_ms)
+            .validator(is_parsable::<NonZeroU64>)
+            .help(
+                "How often the secondary blockstore serving repairs catches up with the primary \
+                 blockstore, if --repair-serve-blockstore-path is set",
+            ),
+    )
+    .arg(
+        Arg::with_name("defer_blockstore_compactions_slots")
+            .hidden(hidden_unless_forced())
+            .long("defer-blockstore-compactions-slots")
+            .takes_value(true)
+            .value_name("SLOTS")
+            .validator(is_parsable::<u64>)
+            .help(
+                "Defer the blockstore compactions that can wait, and run the others with fewer \
+                 threads, from this many slots before the leader slots of this node until they \
+                 are over. [default: disabled]",
+            ),
+    )
     .arg(
         Arg::with_name("gossip_validators")
             .long("gossip-validator")
@@ -691,6 +822,121 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .hidden(hidden_unless_forced())
             .help("Controls the rate of the clients connections per IpAddr per minute."),
     )
+    .arg(
+        Arg::with_name("gossip_export_dir")
+            .long("gossip-export-dir")
+            .takes_value(true)
+            .value_name("DIR")
+            .help(
+                "Periodically export the contact infos held in gossip to compressed files in DIR, \
+                 for offline analysis of the network topology. [default: disabled]",
+            ),
+    )
+    .arg(
+        Arg::with_name("gossip_export_interval_secs")
+            .long("gossip-export-interval-secs")
+            .takes_value(true)
+            .value_name("SECONDS")
+            .default_value(&default_args.gossip_export_interval_secs)
+            .validator(is_parsable::<NonZeroU64>)
+            .help("Interval between gossip exports, if --gossip-export-dir is set"),
+    )
+    .arg(
+        Arg::with_name("gossip_export_max_files")
+            .long("gossip-export-max-files")
+            .takes_value(true)
+            .value_name("NUMBER")
+            .default_value(&default_args.gossip_export_max_files)
+            .validator(is_parsable::<NonZeroUsize>)
+            .help("Number of most recent gossip exports to keep, if --gossip-export-dir is set"),
+    )
+    .arg(
+        Arg::with_name("vote_tracker_archive_dir")
+            .long("vote-tracker-archive-dir")
+            .takes_value(true)
+            .value_name("DIR")
+            .help(
+                "Archive the vote aggregates of the rooted slots to binary files in DIR, for \
+                 long-term analysis of the voting behavior of the cluster. [default: disabled]",
+            ),
+    )
+    .arg(
+        Arg::with_name("vote_tracker_archive_max_file_bytes")
+            .long("vote-tracker-archive-max-file-bytes")
+            .takes_value(true)
+            .value_name("BYTES")
+            .default_value(&default_args.vote_tracker_archive_max_file_bytes)
+            .validator(|value| {
+                is_within_range(value, MIN_VOTE_TRACKER_ARCHIVE_MAX_FILE_BYTES as usize..)
+            })
+            .help(
+                "Size beyond which vote tracker archive files are rotated, if \
+                 --vote-tracker-archive-dir is set",
+            ),
+    )
+    .arg(
+        Arg::with_name("vote_capture_capacity")
+            .long("vote-capture-capacity")
+            .takes_value(true)
+            .value_name("NUMBER")
+            .validator(is_parsable::<NonZeroUsize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Keep this many of the most recently processed votes, and write them to the \
+                 ledger directory when an optimistically confirmed slot is not rooted. [default: \
+                 disabled]",
+            ),
+    )
+    .arg(
+        Arg::with_name("gossip_vote_forwarding_leader_window_slots")
+            .long("gossip-vote-forwarding-leader-window-slots")
+            .takes_value(true)
+            .value_name("SLOTS")
+            .validator(is_parsable::<u64>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Forward the votes received over gossip to the banking stage only from this many \
+                 slots before the leader slots of this node until they are over. The votes \
+                 received in between are held back until then, and still processed for \
+                 confirmation. [default: always forward]",
+            ),
+    )
+    .arg(
+        Arg::with_name("max_gossip_vote_age_ms")
+            .long("max-gossip-vote-age-ms")
+            .takes_value(true)
+            .value_name("MILLISECONDS")
+            .validator(is_parsable::<u64>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Drop the votes received over gossip which were pushed longer than this many \
+                 milliseconds ago, rather than processing them. [default: process votes of any \
+                 age]",
+            ),
+    )
+    .arg(
+        Arg::with_name("tpu_ingest_memory_budget_bytes")
+            .long("tpu-ingest-memory-budget-bytes")
+            .takes_value(true)
+            .value_name("BYTES")
+            .validator(is_parsable::<NonZeroUsize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Approximate memory the TPU, TPU forward and vote QUIC servers may use together \
+                 for the packets they ingest. Connections and streams of unstaked peers are shed \
+                 first once it fills up. [default: unlimited]",
+            ),
+    )
+    .arg(
+        Arg::with_name("tpu_refuse_connections_until_ready")
+            .long("tpu-refuse-connections-until-ready")
+            .takes_value(false)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Close connections to the TPU QUIC server with a not_ready reason until the \
+                 validator has finished starting its services, so that clients know to retry",
+            ),
+    )
     .arg(
         Arg::with_name("vote_use_quic")
             .long("vote-use-quic")
@@ -773,6 +1019,77 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .hidden(hidden_unless_forced())
             .help("Controls the max number of streams for a TPU service."),
     )
+    .arg(
+        Arg::with_name("tpu_channel_size")
+            .long("tpu-channel-size")
+            .takes_value(true)
+            .default_value(&default_args.tpu_channel_size)
+            .validator(is_parsable::<NonZeroUsize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Number of packets the channel between the TPU QUIC server and the sigverify \
+                 stage holds. Packets are dropped once it is full.",
+            ),
+    )
+    .arg(
+        Arg::with_name("tpu_fwd_channel_size")
+            .long("tpu-fwd-channel-size")
+            .takes_value(true)
+            .default_value(&default_args.tpu_fwd_channel_size)
+            .validator(is_parsable::<NonZeroUsize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Number of packets the channel between the TPU-forward QUIC server and the fetch \
+                 stage holds. Packets are dropped once it is full.",
+            ),
+    )
+    .arg(
+        Arg::with_name("tpu_vote_channel_size")
+            .long("tpu-vote-channel-size")
+            .takes_value(true)
+            .default_value(&default_args.tpu_vote_channel_size)
+            .validator(is_parsable::<NonZeroUsize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Number of packets the channel between the TPU vote QUIC server and the sigverify \
+                 stage holds. The oldest votes are evicted once it is full.",
+            ),
+    )
+    .arg(
+        Arg::with_name("strict_tpu_config")
+            .long("strict-tpu-config")
+            .takes_value(false)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Refuse to start if the limits of the TPU QUIC servers cannot all be met, e.g. \
+                 when a stream limit fills up the packet channels faster than they are drained, \
+                 rather than only warning about it",
+            ),
+    )
+    .arg(
+        Arg::with_name("tpu_unthrottled_top_stake_peers")
+            .long("tpu-unthrottled-top-stake-peers")
+            .takes_value(true)
+            .value_name("NUMBER")
+            .default_value(&default_args.tpu_unthrottled_top_stake_peers)
+            .validator(is_parsable::<usize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Never throttle the streams of the NUMBER staked peers with the most stake among \
+                 those connected to the TPU and TPU forwards QUIC servers",
+            ),
+    )
+    .arg(
+        Arg::with_name("tpu_report_staked_connection_demotions")
+            .long("tpu-report-staked-connection-demotions")
+            .takes_value(false)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Emit a datapoint with the stake of the peer when a TPU connection from a staked \
+                 peer is demoted to the unstaked connections as the staked connections are at \
+                 capacity, at most one per second",
+            ),
+    )
     .arg(
         Arg::with_name("num_quic_endpoints")
             .long("num-quic-endpoints")
@@ -839,6 +1156,17 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .takes_value(false)
             .help("Еnable Geyser interface even if no Geyser configs are specified."),
     )
+    .arg(
+        Arg::with_name("enable_completed_data_sets")
+            .long("enable-completed-data-sets")
+            .value_name("BOOLEAN")
+            .takes_value(true)
+            .possible_values(&["true", "false"])
+            .help(
+                "Whether to notify the RPC subscriptions and Geyser plugins of the transactions \
+                 of completed data sets [default: only with --full-rpc-api]",
+            ),
+    )
     .arg(
         Arg::with_name("snapshot_archive_format")
             .long("snapshot-archive-format")
@@ -948,6 +1276,16 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .help("Do not skip the initial cleaning of accounts when verifying snapshot bank")
             .hidden(hidden_unless_forced()),
     )
+    .arg(
+        Arg::with_name("accounts_db_allow_migration")
+            .long("accounts-db-allow-migration")
+            .help(
+                "If the local accounts state cannot be loaded with the current accounts-db \
+                 options, e.g. after changing --accounts, rebuild the accounts from the snapshot \
+                 archives instead of failing to start, even with \
+                 --use-snapshot-archives-at-startup never",
+            ),
+    )
     .arg(
         Arg::with_name("accounts_db_ancient_append_vecs")
             .long("accounts-db-ancient-append-vecs")
@@ -1138,6 +1476,19 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .takes_value(false)
             .help("Disables the banking trace"),
     )
+    .arg(
+        Arg::with_name("duplicate_confirmed_slots_channel_capacity")
+            .long("duplicate-confirmed-slots-channel-capacity")
+            .takes_value(true)
+            .value_name("NUMBER")
+            .default_value(&default_args.duplicate_confirmed_slots_channel_capacity)
+            .validator(is_parsable::<NonZeroUsize>)
+            .hidden(hidden_unless_forced())
+            .help(
+                "Maximum number of duplicate confirmed slot notifications queued for replay. \
+                 Notifications beyond this are coalesced until replay catches up.",
+            ),
+    )
     .arg(
         Arg::with_name("delay_leader_block_for_pending_fork")
             .hidden(hidden_unless_forced())
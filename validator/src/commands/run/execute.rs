@@ -33,22 +33,26 @@ use {
     solana_core::{
         banking_stage::transaction_scheduler::scheduler_controller::SchedulerConfig,
         banking_trace::DISABLED_BAKING_TRACE_DIR,
+        compaction_scheduler::CompactionSchedulerConfig,
         consensus::tower_storage,
         repair::repair_handler::RepairHandlerType,
         resource_limits,
         snapshot_packager_service::SnapshotPackagerService,
         system_monitor_service::SystemMonitorService,
-        tpu::MAX_VOTES_PER_SECOND,
+        tpu::{MAX_VOTES_PER_SECOND, TpuChannelSizes},
         validator::{
-            BlockProductionMethod, BlockVerificationMethod, SchedulerPacing, Validator,
-            ValidatorConfig, ValidatorLogConfig, ValidatorStartProgress, ValidatorTpuConfig,
+            BankHashMismatchPolicy, BlockProductionMethod, BlockVerificationMethod,
+            GenesisHashMismatchPolicy, SchedulerPacing, Validator, ValidatorConfig,
+            ValidatorLogConfig, ValidatorStartProgress, ValidatorTpuConfig,
             is_snapshot_config_valid,
         },
+        vote_tracker_archive::VoteTrackerArchiveConfig,
     },
     solana_genesis_utils::MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
     solana_gossip::{
         cluster_info::{DEFAULT_CONTACT_SAVE_INTERVAL_MILLIS, NodeConfig},
         contact_info::ContactInfo,
+        gossip_export::GossipExportConfig,
         node::Node,
     },
     solana_hash::Hash,
@@ -65,7 +69,10 @@ use {
     solana_signer::Signer,
     solana_streamer::{
         nonblocking::{simple_qos::SimpleQosConfig, swqos::SwQosConfig},
-        quic::{QuicStreamerConfig, SimpleQosQuicStreamerConfig, SwQosQuicStreamerConfig},
+        quic::{
+            DEFAULT_MAX_BYTES_PER_SECOND_PER_UNSTAKED_PEER, QuicStreamerConfig,
+            SimpleQosQuicStreamerConfig, SwQosQuicStreamerConfig,
+        },
     },
     solana_tpu_client::tpu_client::DEFAULT_TPU_CONNECTION_POOL_SIZE,
     solana_turbine::broadcast_stage::BroadcastStageType,
@@ -79,6 +86,7 @@ use {
         path::{Path, PathBuf},
         str::{self, FromStr},
         sync::{Arc, RwLock, atomic::AtomicBool},
+        time::{Duration, Instant},
     },
 };
 #[cfg(target_os = "linux")]
@@ -96,6 +104,8 @@ pub fn execute(
     operation: Operation,
     config: super::Config,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let process_start_time = Instant::now();
+
     // Debugging panics is easier with a backtrace
     if env::var_os("RUST_BACKTRACE").is_none() {
         // Safety: env update is made before any spawned threads might access the environment
@@ -107,12 +117,15 @@ pub fn execute(
     let cli::thread_args::NumThreadConfig {
         accounts_db_background_threads,
         accounts_db_foreground_threads,
+        accounts_db_startup_hash_threads,
+        accounts_db_steady_state_hash_threads,
         accounts_index_flush_threads,
         block_production_num_workers,
         ip_echo_server_threads,
         rayon_global_threads,
         replay_forks_threads,
         replay_transactions_threads,
+        snapshot_packaging_threads,
         tpu_sigverify_threads,
         tpu_transaction_forward_receive_threads,
         tpu_transaction_receive_threads,
@@ -693,6 +706,9 @@ pub fn execute(
         scan_filter_for_shrinking,
         num_background_threads: Some(accounts_db_background_threads),
         num_foreground_threads: Some(accounts_db_foreground_threads),
+        num_startup_hash_threads: Some(accounts_db_startup_hash_threads),
+        num_steady_state_hash_threads: Some(accounts_db_steady_state_hash_threads),
+        num_accounts_indexed: None,
     };
 
     let on_start_geyser_plugin_config_files = if matches.is_present("geyser_plugin_config") {
@@ -749,13 +765,16 @@ pub fn execute(
         expected_genesis_hash: matches
             .value_of("expected_genesis_hash")
             .map(|s| Hash::from_str(s).unwrap()),
+        genesis_hash_mismatch_policy: GenesisHashMismatchPolicy::default(),
         fixed_leader_schedule: None,
         expected_bank_hash: matches
             .value_of("expected_bank_hash")
             .map(|s| Hash::from_str(s).unwrap()),
+        bank_hash_mismatch_policy: BankHashMismatchPolicy::default(),
         expected_shred_version,
         new_hard_forks: hardforks_of(matches, "hard_forks"),
         rpc_config: run_args.json_rpc_config,
+        enable_completed_data_sets: value_t!(matches, "enable_completed_data_sets", bool).ok(),
         on_start_geyser_plugin_config_files,
         geyser_plugin_always_enabled: matches.is_present("geyser_plugin_always_enabled"),
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
@@ -774,11 +793,30 @@ pub fn execute(
         repair_validators,
         should_check_duplicate_instance: true,
         repair_whitelist,
+        repair_serve_blockstore_path: matches
+            .value_of("repair_serve_blockstore_path")
+            .map(PathBuf::from),
+        repair_serve_blockstore_catch_up_interval: Duration::from_millis(
+            value_t_or_exit!(
+                matches,
+                "repair_serve_blockstore_catch_up_interval_ms",
+                NonZeroU64
+            )
+            .get(),
+        ),
         repair_handler_type: RepairHandlerType::default(),
         gossip_validators,
         max_ledger_shreds,
+        compaction_scheduler_config: value_t!(matches, "defer_blockstore_compactions_slots", u64)
+            .ok()
+            .map(|lead_slots| CompactionSchedulerConfig {
+                lead_slots,
+                ..CompactionSchedulerConfig::default()
+            }),
         blockstore_options: run_args.blockstore_options,
-        run_verification: !matches.is_present("skip_startup_ledger_verification"),
+        run_poh_verification: !matches.is_present("skip_startup_ledger_verification"),
+        run_sig_verification: !(matches.is_present("skip_startup_ledger_verification")
+            || matches.is_present("skip_startup_ledger_signature_verification")),
         debug_keys,
         filter_keys: Arc::new(run_args.filter_keys),
         warp_slot: None,
@@ -787,6 +825,8 @@ pub fn execute(
         contact_save_interval: DEFAULT_CONTACT_SAVE_INTERVAL_MILLIS,
         send_transaction_service_config: run_args.send_transaction_service_config,
         no_poh_speed_test: matches.is_present("no_poh_speed_test"),
+        poh_speed_check_samples: value_t!(matches, "poh_speed_check_samples", u64).ok(),
+        require_hashes_per_tick: matches.is_present("require_hashes_per_tick"),
         no_os_memory_stats_reporting: matches.is_present("no_os_memory_stats_reporting"),
         no_os_network_stats_reporting: matches.is_present("no_os_network_stats_reporting"),
         no_os_cpu_stats_reporting: matches.is_present("no_os_cpu_stats_reporting"),
@@ -803,15 +843,26 @@ pub fn execute(
         accounts_db_config,
         accounts_db_skip_shrink: true,
         accounts_db_force_initial_clean: matches.is_present("no_skip_initial_accounts_db_clean"),
+        accounts_db_allow_migration: matches.is_present("accounts_db_allow_migration"),
         snapshot_config,
         no_wait_for_vote_to_start_leader: matches.is_present("no_wait_for_vote_to_start_leader"),
         wait_to_vote_slot: None,
+        min_leader_slot: value_t!(matches, "min_leader_slot", Slot).ok(),
+        max_active_banks: value_t!(matches, "max_active_banks", NonZeroUsize).ok(),
+        precompute_leader_schedule_slots: value_t!(
+            matches,
+            "precompute_leader_schedule_slots",
+            u64
+        )
+        .ok(),
         runtime_config: RuntimeConfig {
             log_messages_bytes_limit: value_of(matches, "log_messages_bytes_limit"),
             ..RuntimeConfig::default()
         },
         staked_nodes_overrides: staked_nodes_overrides.clone(),
         use_snapshot_archives_at_startup,
+        preserve_bank_snapshots_at_startup: matches
+            .is_present("preserve_bank_snapshots_at_startup"),
         ip_echo_server_threads,
         rayon_global_threads,
         replay_forks_threads,
@@ -846,6 +897,7 @@ pub fn execute(
                 SchedulerPacing
             ),
         },
+        reserved_leader_compute_units: 0,
         enable_block_production_forwarding: staked_nodes_overrides_path.is_some(),
         enable_scheduler_bindings: matches.is_present("enable_scheduler_bindings"),
         banking_trace_dir_byte_limit: parse_banking_trace_dir_byte_limit(matches),
@@ -861,6 +913,51 @@ pub fn execute(
             "snapshot_packager_niceness_adj",
             i8
         ),
+        snapshot_packaging_threads,
+        snapshot_gossip_push: !matches.is_present("no_snapshot_gossip_push"),
+        vote_capture_capacity: value_t!(matches, "vote_capture_capacity", NonZeroUsize).ok(),
+        gossip_vote_forwarding_leader_window_slots: value_t!(
+            matches,
+            "gossip_vote_forwarding_leader_window_slots",
+            u64
+        )
+        .ok(),
+        max_gossip_vote_age_ms: value_t!(matches, "max_gossip_vote_age_ms", u64).ok(),
+        tpu_ingest_memory_budget_bytes: value_t!(
+            matches,
+            "tpu_ingest_memory_budget_bytes",
+            NonZeroUsize
+        )
+        .ok(),
+        tpu_refuse_connections_until_ready: matches
+            .is_present("tpu_refuse_connections_until_ready"),
+        duplicate_confirmed_slots_channel_capacity: value_t_or_exit!(
+            matches,
+            "duplicate_confirmed_slots_channel_capacity",
+            NonZeroUsize
+        ),
+        gossip_export: matches
+            .value_of("gossip_export_dir")
+            .map(|dir| GossipExportConfig {
+                interval: Duration::from_secs(
+                    value_t_or_exit!(matches, "gossip_export_interval_secs", NonZeroU64).get(),
+                ),
+                dir: PathBuf::from(dir),
+                max_files: value_t_or_exit!(matches, "gossip_export_max_files", NonZeroUsize),
+            }),
+        vote_tracker_archive: matches.value_of("vote_tracker_archive_dir").map(|dir| {
+            VoteTrackerArchiveConfig {
+                dir: PathBuf::from(dir),
+                max_file_bytes: value_t_or_exit!(
+                    matches,
+                    "vote_tracker_archive_max_file_bytes",
+                    NonZeroU64
+                ),
+            }
+        }),
+        process_start_time: Some(process_start_time),
+        deterministic_rng_seed: None,
+        strict_tpu_config: matches.is_present("strict_tpu_config"),
     };
     validator_config
         .block_production_method
@@ -946,6 +1043,15 @@ pub fn execute(
     let tpu_max_connections_per_ipaddr_per_minute: u64 =
         value_t_or_exit!(matches, "tpu_max_connections_per_ipaddr_per_minute", u64);
     let max_streams_per_ms = value_t_or_exit!(matches, "tpu_max_streams_per_ms", u64);
+    let tpu_channel_sizes = TpuChannelSizes {
+        tpu: value_t_or_exit!(matches, "tpu_channel_size", NonZeroUsize),
+        tpu_forwards: value_t_or_exit!(matches, "tpu_fwd_channel_size", NonZeroUsize),
+        vote: value_t_or_exit!(matches, "tpu_vote_channel_size", NonZeroUsize),
+    };
+    let tpu_report_staked_connection_demotions =
+        matches.is_present("tpu_report_staked_connection_demotions");
+    let tpu_unthrottled_top_stake_peers =
+        value_t_or_exit!(matches, "tpu_unthrottled_top_stake_peers", usize);
 
     let cluster_entrypoints = entrypoint_addrs
         .iter()
@@ -997,6 +1103,11 @@ pub fn execute(
             .snapshot_config
             .incremental_snapshot_archives_dir,
     );
+    // Staging dirs of archives written to another filesystem are left with
+    // the bank snapshots
+    snapshot_utils::remove_tmp_snapshot_archives(
+        &validator_config.snapshot_config.bank_snapshots_dir,
+    );
 
     if !cluster_entrypoints.is_empty() {
         bootstrap::rpc_bootstrap(
@@ -1049,6 +1160,11 @@ pub fn execute(
             max_staked_connections: tpu_max_staked_connections.try_into().unwrap(),
             max_unstaked_connections: tpu_max_unstaked_connections.try_into().unwrap(),
             max_streams_per_ms,
+            report_staked_connection_demotions: tpu_report_staked_connection_demotions,
+            prune_rng_seed: None,
+            unthrottled_top_stake_peers: tpu_unthrottled_top_stake_peers,
+            max_bytes_per_second_per_peer: None,
+            max_bytes_per_second_per_unstaked_peer: DEFAULT_MAX_BYTES_PER_SECOND_PER_UNSTAKED_PEER,
         },
     };
 
@@ -1070,6 +1186,11 @@ pub fn execute(
             max_staked_connections: tpu_max_fwd_staked_connections.try_into().unwrap(),
             max_unstaked_connections: tpu_max_fwd_unstaked_connections.try_into().unwrap(),
             max_streams_per_ms,
+            report_staked_connection_demotions: tpu_report_staked_connection_demotions,
+            prune_rng_seed: None,
+            unthrottled_top_stake_peers: tpu_unthrottled_top_stake_peers,
+            max_bytes_per_second_per_peer: None,
+            max_bytes_per_second_per_unstaked_peer: DEFAULT_MAX_BYTES_PER_SECOND_PER_UNSTAKED_PEER,
         },
     };
 
@@ -1102,6 +1223,7 @@ pub fn execute(
             tpu_quic_server_config,
             tpu_fwd_quic_server_config,
             vote_quic_server_config,
+            channel_sizes: tpu_channel_sizes,
             sigverify_threads: tpu_sigverify_threads,
         },
         admin_service_post_init,
@@ -1351,6 +1473,7 @@ fn new_snapshot_config(
             solana_accounts_db::accounts_db::TOTAL_IO_URING_BUFFERS_SIZE_LIMIT,
         ),
         use_direct_io: !matches.is_present("no_accounts_db_snapshots_direct_io"),
+        unpack_progress: None,
     };
 
     if !is_snapshot_config_valid(&snapshot_config) {
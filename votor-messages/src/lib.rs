@@ -7,6 +7,7 @@ use {
     crossbeam_channel::{Receiver, Sender},
     solana_clock::Slot,
     solana_pubkey::Pubkey,
+    std::time::Instant,
 };
 
 pub mod certificate;
@@ -22,7 +23,8 @@ pub mod vote;
 extern crate solana_frozen_abi_macro;
 
 /// Send side of verified voter channel.
-/// Each message contains the Pubkey of the voter and the slots in last verified vote.
-pub type VerifiedVoterSlotsSender = Sender<(Pubkey, Vec<Slot>)>;
+/// Each message contains the Pubkey of the voter, the slots in last verified vote and the time
+/// the vote was verified.
+pub type VerifiedVoterSlotsSender = Sender<(Pubkey, Vec<Slot>, Instant)>;
 /// Receive side of verified voter channel.
-pub type VerifiedVoterSlotsReceiver = Receiver<(Pubkey, Vec<Slot>)>;
+pub type VerifiedVoterSlotsReceiver = Receiver<(Pubkey, Vec<Slot>, Instant)>;